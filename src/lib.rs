@@ -1,3 +1,3 @@
-/// Initialization of library part of the crate for testing purposes.
+//! Initialization of library part of the crate for testing purposes.
 
-pub mod resp;
\ No newline at end of file
+pub mod resp;
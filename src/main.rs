@@ -1,82 +1,80 @@
 use dotenv::dotenv;
 use log::{error, info};
+use std::net::SocketAddr;
 use std::sync::Arc;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::Mutex;
 
 pub mod resp;
-use resp::command_dispatcher::CommandDispatcher;
-use resp::protocol::{deserialize, serialize, RespType};
+use resp::blocking;
+use resp::client::{BlockedOn, ClientContext};
+use resp::command_dispatcher::{parse_command, CommandDispatcher};
+use resp::cluster::{bus, ClusterState};
+use resp::config::ServerConfig;
+use resp::connection::Connection;
+use resp::protocol::RespType;
+use resp::replication::{master, replica, MasterAddr, ReplicationState};
+use resp::sentinel::{monitor, SentinelState};
 use crate::resp::state::default_server_state::DefaultServerState;
+use crate::resp::state::expiration;
+use crate::resp::state::server_state::ServerState;
 
 async fn handle_connection(
-    mut stream: TcpStream,
+    stream: TcpStream,
+    addr: SocketAddr,
     dispatcher: Arc<CommandDispatcher>,
     state: Arc<Mutex<DefaultServerState>>,
 ) {
-    let mut buffer = vec![0; 1024];
+    let mut connection = Connection::new(stream);
+    let mut client = ClientContext::new(addr);
 
     loop {
-        match stream.read(&mut buffer).await {
-            Ok(0) => {
+        match connection.read_frame().await {
+            Ok(None) => {
                 // Connection closed
                 info!("Connection closed by client");
                 return;
             }
-            Ok(n) => {
-                let input = &buffer[..n];
-                match deserialize(input) {
-                    Ok((resp_type, _)) => {
-                        let command_name = match resp_type {
-                            RespType::Array(ref arr) if !arr.is_empty() => {
-                                if let RespType::BulkString(Some(ref cmd)) = arr[0] {
-                                    cmd.to_uppercase()
-                                } else {
-                                    error!("Invalid command format");
-                                    continue;
-                                }
-                            }
-                            _ => {
-                                error!("Invalid command format");
-                                continue;
-                            }
-                        };
+            Ok(Some((resp_type, _))) => {
+                let Some((command_name, args)) = parse_command(resp_type) else {
+                    error!("Invalid command format");
+                    continue;
+                };
 
-                        let args = match resp_type {
-                            RespType::Array(arr) => {
-                                arr[1..].iter().cloned().collect::<Vec<RespType>>()
-                            }
-                            _ => Vec::new(),
-                        };
-                        let mut guard = state.lock().await;
-                        match dispatcher.dispatch(&command_name, args, &mut *guard) {
-                            Ok(response) => {
-                                let response_bytes = serialize(&response);
-                                if let Err(e) = stream.write_all(&response_bytes).await {
-                                    error!("Failed to write response: {}", e);
-                                    return;
-                                }
-                            }
-                            Err(e) => {
-                                error!("Command execution failed: {}", e);
-                                let error_response = RespType::Error(e);
-                                let response_bytes = serialize(&error_response);
-                                if let Err(e) = stream.write_all(&response_bytes).await {
-                                    error!("Failed to write error response: {}", e);
-                                    return;
-                                }
+                let result = {
+                    let mut guard = state.lock().await;
+                    dispatcher.dispatch(&command_name, args, &mut guard, &mut client)
+                };
+                let mut response = result.unwrap_or_else(|e| {
+                    error!("Command execution failed: {}", e);
+                    RespType::Error(e)
+                });
+                if let Some(blocked) = client.blocked.take() {
+                    if matches!(blocked, BlockedOn::Keys(_)) {
+                        // Stop waiting when the client goes away so that it is not served anymore.
+                        tokio::select! {
+                            reply = blocking::wait_until_unblocked(blocked, &state) => response = reply,
+                            _ = connection.closed() => {
+                                info!("Connection closed by blocked client");
+                                return;
                             }
                         }
+                    } else {
+                        response = blocking::wait_until_unblocked(blocked, &state).await;
                     }
-                    Err(e) => {
-                        error!("Failed to parse request: {}", e);
-                        return;
-                    }
+                }
+                if let Err(e) = connection.write_frame(&response).await {
+                    error!("Failed to write response: {}", e);
+                    return;
+                }
+
+                if client.replication_stream.is_some() {
+                    master::serve_replica(connection, client, dispatcher, state).await;
+                    return;
                 }
             }
             Err(e) => {
-                error!("Failed to read from stream: {}", e);
+                error!("Failed to parse request: {}", e);
                 return;
             }
         }
@@ -89,21 +87,73 @@ async fn main() {
     env_logger::init();
     info!("Logs from your program will appear here!");
 
-    let dispatcher = Arc::new(CommandDispatcher::new());
-    let state = Arc::new(Mutex::new(DefaultServerState::default()));
+    let config = match ServerConfig::from_args(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(e) => {
+            error!("Invalid configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let (dispatcher, state) = match &config.sentinel {
+        Some(sentinel) => {
+            let addr = MasterAddr {
+                host: "127.0.0.1".to_string(),
+                port: config.port,
+            };
+            let state = Arc::new(Mutex::new(DefaultServerState::with_sentinel(SentinelState::new(sentinel, addr))));
+            tokio::spawn(monitor::run_sentinel(Arc::clone(&state)));
+            (Arc::new(CommandDispatcher::sentinel()), state)
+        }
+        None => {
+            let dispatcher = Arc::new(CommandDispatcher::new());
+            let mut replication = ReplicationState::new(config.repl_backlog_size, config.replicaof.clone());
+            replication.read_only = config.replica_read_only;
+            replication.serve_stale_data = config.replica_serve_stale_data;
+            let mut state = DefaultServerState::with_replication(replication);
+            if config.cluster_enabled {
+                let addr = MasterAddr {
+                    host: "127.0.0.1".to_string(),
+                    port: config.port,
+                };
+                match ClusterState::open(&config.cluster_config_file, addr) {
+                    Ok(mut cluster) => {
+                        cluster.node_timeout = config.cluster_node_timeout;
+                        state = state.with_cluster(cluster);
+                    }
+                    Err(e) => {
+                        error!("Failed to load cluster config: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            let cluster_enabled = state.cluster().is_some();
+            let state = Arc::new(Mutex::new(state));
+            if cluster_enabled {
+                tokio::spawn(bus::run_cluster_bus(Arc::clone(&state)));
+            }
+            tokio::spawn(expiration::run_active_expire(Arc::clone(&state)));
+
+            tokio::spawn(replica::run_replication_link(
+                config.port,
+                Arc::clone(&dispatcher),
+                Arc::clone(&state),
+            ));
+            (dispatcher, state)
+        }
+    };
 
-    // TODO: use dotenv to load configuration
-    info!("Starting Redis server on port 6379...");
+    info!("Starting Redis server on port {}...", config.port);
 
-    let listener = TcpListener::bind("127.0.0.1:6379").await.unwrap();
+    let listener = TcpListener::bind(("127.0.0.1", config.port)).await.unwrap();
 
     loop {
         match listener.accept().await {
-            Ok((stream, _addr)) => {
+            Ok((stream, addr)) => {
                 let d = Arc::clone(&dispatcher);
                 let s = Arc::clone(&state);
                 tokio::spawn(async move {
-                    handle_connection(stream, d, s).await;
+                    handle_connection(stream, addr, d, s).await;
                 });
             }
             Err(e) => {
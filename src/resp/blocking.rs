@@ -0,0 +1,241 @@
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use tokio::sync::{oneshot, Mutex};
+
+use crate::resp::client::BlockedOn;
+use crate::resp::cluster::migrate::migrate;
+use crate::resp::commands::propagate;
+use crate::resp::commands::list::{self, End};
+use crate::resp::commands::sorted_set;
+use crate::resp::commands::stream;
+use crate::resp::protocol::RespType;
+use crate::resp::replication::master::wait_for_acks;
+use crate::resp::state::default_server_state::DefaultServerState;
+use crate::resp::state::server_state::ServerState;
+use crate::resp::state::stream::StreamId;
+
+/// Waits, without holding the state lock, until the condition a blocking command
+/// registered is met and returns the reply for the client.
+pub async fn wait_until_unblocked(blocked: BlockedOn, state: &Mutex<DefaultServerState>) -> RespType {
+    match blocked {
+        BlockedOn::Replicas {
+            numreplicas,
+            offset,
+            deadline,
+        } => RespType::Integer(wait_for_acks(state, numreplicas, offset, deadline).await as i64),
+        BlockedOn::Migrate(request) => migrate(request, state).await,
+        BlockedOn::Keys(wait) => wait_for_keys(wait, state).await,
+    }
+}
+
+/// What a client blocked on keys does once one of them can serve it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum KeyOperation {
+    /// `BLPOP`/`BRPOP`: replies with the key and the popped element.
+    Pop(End),
+    /// `BLMPOP`: pops up to `count` elements and replies with the key and the elements.
+    MultiPop { end: End, count: usize },
+    /// `BLMOVE`: moves an element to `destination` and replies with it.
+    Move { destination: String, from: End, to: End },
+    /// `BZPOPMIN`/`BZPOPMAX`: replies with the key, the popped member and its score.
+    ZPop { max: bool },
+    /// `BZMPOP`: pops up to `count` members and replies with the key and the members with their scores.
+    ZMultiPop { max: bool, count: usize },
+    /// `XREAD`: replies with the entries added after `ids` to any of the streams.
+    XRead { ids: Vec<StreamId>, count: Option<usize> },
+    /// `XREADGROUP`: delivers the entries the group has not read yet to the consumer.
+    XReadGroup {
+        group: String,
+        consumer: String,
+        ids: Vec<Option<StreamId>>,
+        count: Option<usize>,
+        noack: bool,
+    },
+}
+
+/// A blocking command waiting for data on any of `keys`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyWait {
+    pub keys: Vec<String>,
+    pub operation: KeyOperation,
+    pub deadline: Option<Instant>,
+}
+
+impl KeyWait {
+    fn timeout_reply(&self) -> RespType {
+        match self.operation {
+            KeyOperation::Move { .. } => RespType::BulkString(None),
+            _ => RespType::NullArray,
+        }
+    }
+}
+
+/// Performs the operation on the first key able to serve it, propagating what was done
+/// as the equivalent non-blocking command, or reads from all the streams for `XREAD` and
+/// `XREADGROUP`. Returns `None` when no key has data.
+pub fn serve(state: &mut dyn ServerState, wait: &KeyWait) -> Result<Option<RespType>, String> {
+    let bulk = |value: &str| RespType::BulkString(Some(value.to_string()));
+    match &wait.operation {
+        KeyOperation::XRead { ids, count } => return stream::read(state, &wait.keys, ids, *count),
+        KeyOperation::XReadGroup {
+            group,
+            consumer,
+            ids,
+            count,
+            noack,
+        } => return stream::read_group(state, &wait.keys, ids, group, consumer, *count, *noack),
+        _ => {}
+    }
+    for key in &wait.keys {
+        match &wait.operation {
+            KeyOperation::Pop(end) => {
+                if let Some(element) = list::pop(state, key, *end, 1)?.and_then(|mut popped| popped.pop()) {
+                    propagate(state, &[pop_command(*end), key]);
+                    return Ok(Some(RespType::Array(vec![bulk(key), bulk(&element)])));
+                }
+            }
+            KeyOperation::MultiPop { end, count } => {
+                if let Some(popped) = list::pop(state, key, *end, *count)? {
+                    propagate(state, &[pop_command(*end), key, &popped.len().to_string()]);
+                    let elements = popped.iter().map(|element| bulk(element)).collect();
+                    return Ok(Some(RespType::Array(vec![bulk(key), RespType::Array(elements)])));
+                }
+            }
+            KeyOperation::Move { destination, from, to } => {
+                if let Some(element) = list::move_element(state, key, destination, *from, *to)? {
+                    propagate(state, &["LMOVE", key, destination, end_name(*from), end_name(*to)]);
+                    return Ok(Some(bulk(&element)));
+                }
+            }
+            KeyOperation::ZPop { max } => {
+                if let Some((member, score)) = sorted_set::pop(state, key, 1, *max)?.pop() {
+                    propagate(state, &[zpop_command(*max), key]);
+                    return Ok(Some(RespType::Array(vec![bulk(key), bulk(&member), sorted_set::score_reply(score)])));
+                }
+            }
+            KeyOperation::ZMultiPop { max, count } => {
+                let popped = sorted_set::pop(state, key, *count, *max)?;
+                if !popped.is_empty() {
+                    propagate(state, &[zpop_command(*max), key, &popped.len().to_string()]);
+                    let entries = popped
+                        .iter()
+                        .map(|(member, score)| RespType::Array(vec![bulk(member), sorted_set::score_reply(*score)]))
+                        .collect();
+                    return Ok(Some(RespType::Array(vec![bulk(key), RespType::Array(entries)])));
+                }
+            }
+            // Served for all keys at once above.
+            KeyOperation::XRead { .. } | KeyOperation::XReadGroup { .. } => {}
+        }
+    }
+    Ok(None)
+}
+
+fn pop_command(end: End) -> &'static str {
+    match end {
+        End::Left => "LPOP",
+        End::Right => "RPOP",
+    }
+}
+
+fn zpop_command(max: bool) -> &'static str {
+    if max {
+        "ZPOPMAX"
+    } else {
+        "ZPOPMIN"
+    }
+}
+
+fn end_name(end: End) -> &'static str {
+    match end {
+        End::Left => "LEFT",
+        End::Right => "RIGHT",
+    }
+}
+
+struct Waiter {
+    id: u64,
+    wait: KeyWait,
+    reply: oneshot::Sender<RespType>,
+}
+
+/// Clients blocked on keys, in the order they blocked.
+#[derive(Default)]
+pub struct BlockedClients {
+    next_id: u64,
+    waiting: VecDeque<Waiter>,
+}
+
+impl BlockedClients {
+    fn block(&mut self, wait: KeyWait) -> (u64, oneshot::Receiver<RespType>) {
+        let (reply, receiver) = oneshot::channel();
+        let id = self.next_id;
+        self.next_id += 1;
+        self.waiting.push_back(Waiter { id, wait, reply });
+        (id, receiver)
+    }
+
+    fn unblock(&mut self, id: u64) {
+        self.waiting.retain(|waiter| waiter.id != id);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.waiting.is_empty()
+    }
+}
+
+/// Serves the blocked clients that can be served after a write, the longest waiting first.
+/// Runs until nothing changes since serving a client (e.g. `BLMOVE`) may feed another one.
+pub fn serve_blocked_clients(state: &mut DefaultServerState) {
+    loop {
+        let mut waiting = std::mem::take(&mut state.blocked_clients().waiting);
+        // Clients that disconnected while blocked must not consume any data.
+        waiting.retain(|waiter| !waiter.reply.is_closed());
+
+        let mut served = false;
+        let mut remaining = VecDeque::with_capacity(waiting.len());
+        for waiter in waiting {
+            // A key now holding another type just keeps the client blocked.
+            match serve(state, &waiter.wait) {
+                Ok(Some(reply)) => {
+                    let _ = waiter.reply.send(reply);
+                    served = true;
+                }
+                _ => remaining.push_back(waiter),
+            }
+        }
+        state.blocked_clients().waiting = remaining;
+        if !served {
+            return;
+        }
+    }
+}
+
+async fn wait_for_keys(wait: KeyWait, state: &Mutex<DefaultServerState>) -> RespType {
+    let deadline = wait.deadline;
+    let timeout_reply = wait.timeout_reply();
+    let (id, mut receiver) = {
+        let mut guard = state.lock().await;
+        // Data may have arrived since the command ran.
+        match serve(&mut *guard, &wait) {
+            Ok(Some(reply)) => return reply,
+            Ok(None) => {}
+            Err(e) => return RespType::Error(e),
+        }
+        guard.blocked_clients().block(wait)
+    };
+
+    let reply = match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline.into(), &mut receiver).await.ok(),
+        None => Some((&mut receiver).await),
+    };
+    match reply {
+        Some(Ok(reply)) => reply,
+        _ => {
+            state.lock().await.blocked_clients().unblock(id);
+            // The client may have been served right when the timeout fired.
+            receiver.try_recv().unwrap_or(timeout_reply)
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Scalable Bloom filters, as RedisBloom builds them: a chain of Bloom filters where each
+//! new one holds `expansion` times more items than the last, with half its error rate, so
+//! the chain keeps the requested error rate as it grows.
+
+use crate::resp::hyperloglog::murmur_hash64a;
+use crate::resp::state::value::MAX_STRING_BYTES;
+
+/// Error rate of each filter added to a chain, relative to the previous one.
+const ERROR_TIGHTENING_RATIO: f64 = 0.5;
+const HASH_SEED: u64 = 0xc6a4a7935bd1e995;
+
+pub const DEFAULT_ERROR_RATE: f64 = 0.01;
+pub const DEFAULT_CAPACITY: u64 = 100;
+pub const DEFAULT_EXPANSION: u64 = 2;
+
+/// A single Bloom filter of the chain.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BloomFilter {
+    /// Number of items the filter is sized for.
+    pub capacity: u64,
+    pub error_rate: f64,
+    pub hashes: u64,
+    /// Bits per item.
+    pub bits_per_item: f64,
+    pub bits: Vec<u8>,
+    /// Number of items added.
+    pub size: u64,
+}
+
+impl BloomFilter {
+    /// An empty filter for `capacity` items, failing when it would be too large.
+    pub fn new(capacity: u64, error_rate: f64) -> Result<BloomFilter, String> {
+        let bits_per_item = -error_rate.ln() / std::f64::consts::LN_2.powi(2);
+        let bytes = (capacity as f64 * bits_per_item / 8.0).ceil().max(1.0);
+        if bytes > MAX_STRING_BYTES as f64 {
+            return Err("Insufficient memory to create filter".to_string());
+        }
+        Ok(BloomFilter {
+            capacity,
+            error_rate,
+            hashes: (std::f64::consts::LN_2 * bits_per_item).ceil() as u64,
+            bits_per_item,
+            bits: vec![0; bytes as usize],
+            size: 0,
+        })
+    }
+
+    /// Positions of the bits of `item`, from two hashes combined as `a + i * b`.
+    fn positions(&self, item: &[u8]) -> impl Iterator<Item = u64> {
+        let a = murmur_hash64a(item, HASH_SEED);
+        let b = murmur_hash64a(item, a);
+        let len = self.bits.len() as u64 * 8;
+        (0..self.hashes).map(move |index| a.wrapping_add(index.wrapping_mul(b)) % len)
+    }
+
+    fn contains(&self, item: &[u8]) -> bool {
+        self.positions(item).all(|bit| self.bits[(bit / 8) as usize] & (1 << (bit % 8)) != 0)
+    }
+
+    fn add(&mut self, item: &[u8]) {
+        for bit in self.positions(item).collect::<Vec<_>>() {
+            self.bits[(bit / 8) as usize] |= 1 << (bit % 8);
+        }
+        self.size += 1;
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScalableBloom {
+    pub filters: Vec<BloomFilter>,
+    /// How many times larger each new filter is than the last, 0 for non-scaling filters.
+    pub expansion: u64,
+}
+
+impl ScalableBloom {
+    pub fn new(error_rate: f64, capacity: u64, expansion: u64) -> Result<ScalableBloom, String> {
+        Ok(ScalableBloom {
+            filters: vec![BloomFilter::new(capacity, error_rate)?],
+            expansion,
+        })
+    }
+
+    /// Number of items added.
+    pub fn size(&self) -> u64 {
+        self.filters.iter().map(|filter| filter.size).sum()
+    }
+
+    pub fn contains(&self, item: &[u8]) -> bool {
+        self.filters.iter().any(|filter| filter.contains(item))
+    }
+
+    /// Adds `item` unless it may already be present, growing the chain when the last filter
+    /// is full. Returns whether it was added.
+    pub fn add(&mut self, item: &[u8]) -> Result<bool, String> {
+        if self.contains(item) {
+            return Ok(false);
+        }
+        let Some(last) = self.filters.last() else {
+            return Err("filter has no capacity".to_string());
+        };
+        if last.size >= last.capacity {
+            if self.expansion == 0 {
+                return Err("non scaling filter is full".to_string());
+            }
+            let capacity = last.capacity.saturating_mul(self.expansion);
+            let filter = BloomFilter::new(capacity, last.error_rate * ERROR_TIGHTENING_RATIO)?;
+            self.filters.push(filter);
+        }
+        if let Some(last) = self.filters.last_mut() {
+            last.add(item);
+        }
+        Ok(true)
+    }
+}
@@ -0,0 +1,63 @@
+use std::net::SocketAddr;
+use std::time::Instant;
+
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use crate::resp::blocking::KeyWait;
+use crate::resp::cluster::migrate::MigrateRequest;
+
+/// Per-connection state that lives next to the socket rather than in the shared server state.
+#[derive(Default)]
+pub struct ClientContext {
+    /// Address of the peer, if the connection came from a socket.
+    pub addr: Option<SocketAddr>,
+    /// Set for the connection a replica holds to its primary; such commands are applied
+    /// silently and are not propagated any further by the dispatcher.
+    pub is_master: bool,
+    /// Port announced with `REPLCONF listening-port`.
+    pub listening_port: Option<u16>,
+    /// Capabilities announced with `REPLCONF capa`.
+    pub capabilities: Vec<String>,
+    /// Id under which the connection is registered as a replica after `PSYNC`.
+    pub replica_id: Option<u64>,
+    /// Set once `PSYNC` succeeds: the connection is turned into a replication stream
+    /// and everything received here has to be written to the socket.
+    pub replication_stream: Option<UnboundedReceiver<Vec<u8>>>,
+    /// Set by blocking commands: the reply is produced by the connection task once the
+    /// condition is met, without holding the state lock while waiting.
+    pub blocked: Option<BlockedOn>,
+    /// Set by `ASKING`: the next command may access a slot that is being imported.
+    pub asking: bool,
+}
+
+/// Condition a blocked client is waiting for.
+#[derive(Debug, PartialEq)]
+pub enum BlockedOn {
+    /// `WAIT`: until `numreplicas` replicas acknowledged the replication `offset`.
+    Replicas {
+        numreplicas: usize,
+        offset: u64,
+        deadline: Option<Instant>,
+    },
+    /// `MIGRATE`: the transfer runs in the connection task since it talks to another node.
+    Migrate(MigrateRequest),
+    /// Blocking list commands: until one of the keys can serve the client.
+    Keys(KeyWait),
+}
+
+impl ClientContext {
+    pub fn new(addr: SocketAddr) -> Self {
+        ClientContext {
+            addr: Some(addr),
+            ..Default::default()
+        }
+    }
+
+    /// Context used for the link a replica keeps open to its primary.
+    pub fn master_link() -> Self {
+        ClientContext {
+            is_master: true,
+            ..Default::default()
+        }
+    }
+}
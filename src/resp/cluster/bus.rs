@@ -0,0 +1,100 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::{error, warn};
+use tokio::sync::Mutex;
+
+use crate::resp::cluster::gossip::Heartbeat;
+use crate::resp::connection::request;
+use crate::resp::protocol::RespType;
+use crate::resp::replication::MasterAddr;
+use crate::resp::state::default_server_state::DefaultServerState;
+use crate::resp::state::server_state::ServerState;
+
+const MIN_HEARTBEAT_PERIOD: Duration = Duration::from_millis(100);
+const MAX_HEARTBEAT_PERIOD: Duration = Duration::from_secs(1);
+
+/// Periodically sends a heartbeat to every known node and to the addresses given to
+/// `CLUSTER MEET`, then updates the failure state of the nodes that did not answer.
+pub async fn run_cluster_bus(state: Arc<Mutex<DefaultServerState>>) {
+    loop {
+        let Some((heartbeat, targets, period)) = ({
+            let mut guard = state.lock().await;
+            guard.cluster().map(|cluster| {
+                let timeout = cluster.node_timeout;
+                cluster.pending_meets.retain(|(_, since)| since.elapsed() < timeout);
+                let targets = cluster
+                    .nodes
+                    .values()
+                    .filter(|node| node.id != cluster.myself)
+                    .map(|node| node.addr.clone())
+                    .chain(cluster.pending_meets.iter().map(|(addr, _)| addr.clone()))
+                    .collect::<Vec<_>>();
+                let period = (timeout / 10).clamp(MIN_HEARTBEAT_PERIOD, MAX_HEARTBEAT_PERIOD);
+                (cluster.heartbeat(), targets, period)
+            })
+        }) else {
+            return;
+        };
+
+        let mut parts = vec!["CLUSTER".to_string(), "HEARTBEAT".to_string()];
+        parts.extend(heartbeat.to_args());
+        let parts = Arc::new(parts);
+        let tasks = targets
+            .into_iter()
+            .map(|addr| {
+                let parts = Arc::clone(&parts);
+                let state = Arc::clone(&state);
+                tokio::spawn(async move { exchange(&addr, &parts, period, &state).await })
+            })
+            .collect::<Vec<_>>();
+        for task in tasks {
+            let _ = task.await;
+        }
+
+        {
+            let mut guard = state.lock().await;
+            if let Some(cluster) = guard.cluster() {
+                cluster.detect_failures();
+            }
+        }
+        tokio::time::sleep(period).await;
+    }
+}
+
+/// Sends our heartbeat to one node and applies the one it answers with.
+async fn exchange(addr: &MasterAddr, parts: &[String], timeout: Duration, state: &Mutex<DefaultServerState>) {
+    let parts = parts.iter().map(String::as_str).collect::<Vec<_>>();
+    let reply = match request(addr, &parts, timeout.max(MIN_HEARTBEAT_PERIOD * 5)).await {
+        Ok(RespType::Array(reply)) => reply,
+        Ok(other) => {
+            warn!("Unexpected heartbeat reply from {}:{}: {:?}", addr.host, addr.port, other);
+            return;
+        }
+        Err(_) => return,
+    };
+
+    let args = reply
+        .iter()
+        .filter_map(|part| match part {
+            RespType::BulkString(Some(value)) => Some(value.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let heartbeat = match Heartbeat::parse(&args) {
+        Ok(heartbeat) => heartbeat,
+        Err(e) => {
+            warn!("Invalid heartbeat reply from {}:{}: {}", addr.host, addr.port, e);
+            return;
+        }
+    };
+
+    let mut guard = state.lock().await;
+    if let Some(cluster) = guard.cluster() {
+        if cluster.receive_heartbeat(heartbeat) {
+            if let Err(e) = cluster.save() {
+                error!("{}", e);
+            }
+        }
+    }
+}
@@ -0,0 +1,329 @@
+//! Heartbeats exchanged between cluster nodes.
+//!
+//! A heartbeat describes the sender (address, config epoch and claimed slots) and carries
+//! what it knows about a few other nodes, which is how nodes learn about each other and
+//! agree that a node failed.
+
+use std::time::Instant;
+
+use log::{info, warn};
+
+use crate::resp::cluster::{parse_slot_range, ClusterNode, ClusterState};
+use crate::resp::replication::MasterAddr;
+
+/// State of a node as seen by the sender of a heartbeat.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NodeFlag {
+    Ok,
+    PFail,
+    Fail,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GossipEntry {
+    pub id: String,
+    pub addr: MasterAddr,
+    pub flag: NodeFlag,
+}
+
+/// `CLUSTER HEARTBEAT <id> <host> <port> <config-epoch> <current-epoch> <master-id|-> <slots|->
+/// [<id> <host> <port> <ok|pfail|fail> ...]`, also sent back as the reply.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Heartbeat {
+    pub id: String,
+    pub addr: MasterAddr,
+    pub config_epoch: u64,
+    pub current_epoch: u64,
+    pub master_id: Option<String>,
+    pub slots: Vec<(u16, u16)>,
+    pub gossip: Vec<GossipEntry>,
+}
+
+impl Heartbeat {
+    /// Arguments following `CLUSTER HEARTBEAT`.
+    pub fn to_args(&self) -> Vec<String> {
+        let slots = self
+            .slots
+            .iter()
+            .map(|(start, end)| format!("{}-{}", start, end))
+            .collect::<Vec<_>>();
+        let mut args = vec![
+            self.id.clone(),
+            self.addr.host.clone(),
+            self.addr.port.to_string(),
+            self.config_epoch.to_string(),
+            self.current_epoch.to_string(),
+            self.master_id.clone().unwrap_or_else(|| "-".to_string()),
+            if slots.is_empty() { "-".to_string() } else { slots.join(",") },
+        ];
+        for entry in &self.gossip {
+            args.push(entry.id.clone());
+            args.push(entry.addr.host.clone());
+            args.push(entry.addr.port.to_string());
+            args.push(
+                match entry.flag {
+                    NodeFlag::Ok => "ok",
+                    NodeFlag::PFail => "pfail",
+                    NodeFlag::Fail => "fail",
+                }
+                .to_string(),
+            );
+        }
+        args
+    }
+
+    pub fn parse(args: &[&str]) -> Result<Self, String> {
+        if args.len() < 7 || !(args.len() - 7).is_multiple_of(4) {
+            return Err("Invalid heartbeat".to_string());
+        }
+        let number = |value: &str| value.parse::<u64>().map_err(|_| "Invalid heartbeat".to_string());
+        let port = |value: &str| value.parse::<u16>().map_err(|_| "Invalid heartbeat".to_string());
+
+        let slots = match args[6] {
+            "-" => Vec::new(),
+            ranges => ranges.split(',').map(parse_slot_range).collect::<Result<_, _>>()?,
+        };
+        let gossip = args[7..]
+            .chunks(4)
+            .map(|entry| {
+                Ok(GossipEntry {
+                    id: entry[0].to_string(),
+                    addr: MasterAddr {
+                        host: entry[1].to_string(),
+                        port: port(entry[2])?,
+                    },
+                    flag: match entry[3] {
+                        "ok" => NodeFlag::Ok,
+                        "pfail" => NodeFlag::PFail,
+                        "fail" => NodeFlag::Fail,
+                        _ => return Err("Invalid heartbeat".to_string()),
+                    },
+                })
+            })
+            .collect::<Result<_, String>>()?;
+
+        Ok(Heartbeat {
+            id: args[0].to_string(),
+            addr: MasterAddr {
+                host: args[1].to_string(),
+                port: port(args[2])?,
+            },
+            config_epoch: number(args[3])?,
+            current_epoch: number(args[4])?,
+            master_id: match args[5] {
+                "-" => None,
+                id => Some(id.to_string()),
+            },
+            slots,
+            gossip,
+        })
+    }
+}
+
+impl ClusterState {
+    /// Builds the heartbeat describing this node.
+    pub fn heartbeat(&self) -> Heartbeat {
+        let myself = self.myself();
+        let mut gossip = self
+            .nodes
+            .values()
+            .filter(|node| node.id != self.myself)
+            .map(|node| GossipEntry {
+                id: node.id.clone(),
+                addr: node.addr.clone(),
+                flag: match self.health(&node.id) {
+                    Some(health) if health.fail => NodeFlag::Fail,
+                    Some(health) if health.pfail => NodeFlag::PFail,
+                    _ => NodeFlag::Ok,
+                },
+            })
+            .collect::<Vec<_>>();
+        gossip.sort_by(|a, b| a.id.cmp(&b.id));
+
+        Heartbeat {
+            id: myself.id.clone(),
+            addr: myself.addr.clone(),
+            config_epoch: myself.config_epoch,
+            current_epoch: self.current_epoch,
+            master_id: myself.master_id.clone(),
+            slots: self.slots_of(&self.myself),
+            gossip,
+        }
+    }
+
+    /// Applies a heartbeat received from, or sent back by, another node.
+    /// Returns whether the topology changed and has to be saved.
+    pub fn receive_heartbeat(&mut self, heartbeat: Heartbeat) -> bool {
+        if heartbeat.id == self.myself {
+            return false;
+        }
+        let mut changed = false;
+        self.pending_meets.retain(|(addr, _)| *addr != heartbeat.addr);
+
+        let sender = ClusterNode {
+            id: heartbeat.id.clone(),
+            addr: heartbeat.addr.clone(),
+            master_id: heartbeat.master_id.clone(),
+            config_epoch: heartbeat.config_epoch,
+        };
+        match self.nodes.get(&sender.id) {
+            None => {
+                info!("Discovered cluster node {} at {}", sender.id, sender.endpoint());
+                changed = true;
+            }
+            Some(known) if *known != sender => changed = true,
+            Some(_) => {}
+        }
+        self.add_node(sender);
+        if heartbeat.current_epoch > self.current_epoch {
+            self.current_epoch = heartbeat.current_epoch;
+            changed = true;
+        }
+        if let Some(health) = self.health_mut(&heartbeat.id) {
+            health.last_pong = Instant::now();
+            health.pfail = false;
+            if health.fail {
+                info!("Cluster node {} is reachable again", heartbeat.id);
+                health.fail = false;
+            }
+        }
+
+        changed |= self.apply_slot_claims(&heartbeat);
+        changed |= self.handle_epoch_collision(&heartbeat);
+        self.apply_gossip(&heartbeat);
+        changed
+    }
+
+    /// Takes over the slots claimed by the sender when its configuration is newer than the
+    /// one of their current owner. Slots being imported are left alone until the migration ends.
+    fn apply_slot_claims(&mut self, heartbeat: &Heartbeat) -> bool {
+        let mut changed = false;
+        for (start, end) in &heartbeat.slots {
+            for slot in *start..=*end {
+                if self.importing.contains_key(&slot) {
+                    continue;
+                }
+                let owner = self.slot_owner(slot).map(|node| (node.id.clone(), node.config_epoch));
+                let newer = match &owner {
+                    Some((id, _)) if *id == heartbeat.id => continue,
+                    Some((_, epoch)) => *epoch < heartbeat.config_epoch,
+                    None => true,
+                };
+                if !newer {
+                    continue;
+                }
+                if owner.is_some_and(|(id, _)| id == self.myself) {
+                    info!("Slot {} is now served by {}", slot, heartbeat.id);
+                    self.migrating.remove(&slot);
+                }
+                self.slots[slot as usize] = Some(heartbeat.id.clone());
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Two primaries with the same config epoch could both win a slot; the one with the
+    /// smaller id moves to a new epoch.
+    fn handle_epoch_collision(&mut self, heartbeat: &Heartbeat) -> bool {
+        let myself = self.myself();
+        if heartbeat.master_id.is_some()
+            || myself.master_id.is_some()
+            || heartbeat.config_epoch != myself.config_epoch
+            || heartbeat.id <= self.myself
+        {
+            return false;
+        }
+        let epoch = self.bump_epoch();
+        info!("Config epoch collision with {}, moved to epoch {}", heartbeat.id, epoch);
+        true
+    }
+
+    /// Learns about new nodes and records the failure reports of the sender.
+    fn apply_gossip(&mut self, heartbeat: &Heartbeat) {
+        let reporter_votes = self.is_voter(&heartbeat.id);
+        for entry in &heartbeat.gossip {
+            if entry.id == self.myself {
+                continue;
+            }
+            if !self.nodes.contains_key(&entry.id) {
+                info!("Discovered cluster node {} at {}:{} through gossip", entry.id, entry.addr.host, entry.addr.port);
+                self.add_node(ClusterNode {
+                    id: entry.id.clone(),
+                    addr: entry.addr.clone(),
+                    master_id: None,
+                    config_epoch: 0,
+                });
+            }
+
+            let Some(health) = self.health_mut(&entry.id) else { continue };
+            match entry.flag {
+                NodeFlag::Ok => {
+                    health.fail_reports.remove(&heartbeat.id);
+                }
+                NodeFlag::PFail if reporter_votes => {
+                    health.fail_reports.insert(heartbeat.id.clone(), Instant::now());
+                }
+                NodeFlag::Fail if !health.fail && health.pfail => {
+                    warn!("Cluster node {} marked as failing by {}", entry.id, heartbeat.id);
+                    health.fail = true;
+                }
+                _ => {}
+            }
+        }
+        self.mark_failures();
+    }
+
+    /// Flags nodes we did not hear from within the node timeout, and marks them as failed
+    /// once a majority of the primaries agrees.
+    pub fn detect_failures(&mut self) {
+        let timeout = self.node_timeout;
+        for (id, health) in self.health.iter_mut() {
+            let pfail = health.last_pong.elapsed() > timeout;
+            if pfail && !health.pfail {
+                warn!("Cluster node {} is not reachable", id);
+            }
+            health.pfail = pfail;
+            // Reports expire so that a recovered node is not failed on stale information.
+            health.fail_reports.retain(|_, at| at.elapsed() < timeout * 2);
+        }
+        self.mark_failures();
+    }
+
+    fn mark_failures(&mut self) {
+        let voters = self.voters();
+        let needed = voters.len() / 2 + 1;
+        let myself_votes = voters.contains(&self.myself);
+
+        for (id, health) in self.health.iter_mut() {
+            if health.fail || !health.pfail {
+                continue;
+            }
+            let reports = health.fail_reports.keys().filter(|reporter| voters.contains(reporter)).count();
+            if reports + myself_votes as usize >= needed {
+                warn!("Cluster node {} marked as failing ({} reports)", id, reports);
+                health.fail = true;
+            }
+        }
+    }
+
+    /// Primaries whose opinion counts for failure detection: the ones serving slots,
+    /// or every primary while no slots are assigned.
+    fn voters(&self) -> Vec<String> {
+        let masters = self.masters();
+        let serving = masters
+            .iter()
+            .filter(|node| !self.slots_of(&node.id).is_empty())
+            .map(|node| node.id.clone())
+            .collect::<Vec<_>>();
+        if serving.is_empty() {
+            masters.iter().map(|node| node.id.clone()).collect()
+        } else {
+            serving
+        }
+    }
+
+    fn is_voter(&self, id: &str) -> bool {
+        self.voters().iter().any(|voter| voter == id)
+    }
+}
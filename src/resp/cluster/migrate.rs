@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+use tokio::sync::Mutex;
+
+use crate::resp::commands::{command_frame, current_time_ms, keys};
+use crate::resp::connection::Connection;
+use crate::resp::protocol::RespType;
+use crate::resp::replication::MasterAddr;
+use crate::resp::state::default_server_state::DefaultServerState;
+use crate::resp::state::server_state::ServerState;
+
+/// Keys to move with `MIGRATE`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MigrateRequest {
+    pub addr: MasterAddr,
+    pub keys: Vec<String>,
+    pub timeout: Duration,
+    /// Keep the keys on this node.
+    pub copy: bool,
+    /// Overwrite keys that already exist on the target.
+    pub replace: bool,
+}
+
+/// Copies the keys to the target with `RESTORE` and deletes them here once the target
+/// accepted all of them. The state stays locked for the whole transfer, so the keys
+/// cannot change or be read here half moved.
+pub async fn migrate(request: MigrateRequest, state: &Mutex<DefaultServerState>) -> RespType {
+    let mut guard = state.lock().await;
+
+    let now = current_time_ms();
+    let mut entries = Vec::new();
+    for key in &request.keys {
+        let Some(payload) = keys::dump(&mut *guard, key) else {
+            continue;
+        };
+        // A TTL of 0 means no expiration, so keys about to expire keep at least 1ms.
+        let ttl = guard
+            .expires_at(key)
+            .map(|expires_at| expires_at.saturating_sub(now).max(1))
+            .unwrap_or(0);
+        entries.push((key.clone(), ttl.to_string(), payload));
+    }
+    if entries.is_empty() {
+        return RespType::SimpleString("NOKEY".to_string());
+    }
+
+    let transfer = tokio::time::timeout(request.timeout, transfer(&request.addr, &entries, request.replace)).await;
+    match transfer {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => return RespType::Error(e),
+        Err(_) => return RespType::Error("IOERR error or timeout reading to target instance".to_string()),
+    }
+
+    if !request.copy {
+        for (key, _, _) in &entries {
+            if let Err(e) = guard.del(key) {
+                return RespType::Error(e);
+            }
+            if guard.replication().is_master() {
+                guard.replication().propagate(&command_frame(&["DEL", key]));
+            }
+        }
+    }
+    RespType::SimpleString("OK".to_string())
+}
+
+async fn transfer(addr: &MasterAddr, entries: &[(String, String, Vec<u8>)], replace: bool) -> Result<(), String> {
+    let io_error = |e: std::io::Error| format!("IOERR error or timeout writing to target instance: {}", e);
+    let mut connection = Connection::connect(&addr.host, addr.port).await.map_err(io_error)?;
+
+    for (key, ttl, payload) in entries {
+        // The target may only be importing the slot, so every key is sent with ASKING.
+        connection.request(&["ASKING"]).await.map_err(io_error)?;
+        let mut restore = vec![bulk("RESTORE"), bulk(key), bulk(ttl), RespType::bulk_bytes(payload.clone())];
+        if replace {
+            restore.push(bulk("REPLACE"));
+        }
+        match connection.request_frame(&RespType::Array(restore)).await.map_err(io_error)? {
+            RespType::Error(e) => return Err(format!("Target instance replied with error: {}", e)),
+            _ => continue,
+        }
+    }
+    Ok(())
+}
+
+fn bulk(value: &str) -> RespType {
+    RespType::BulkString(Some(value.to_string()))
+}
@@ -0,0 +1,437 @@
+//! Cluster mode: the key space is split into hash slots, each served by one primary.
+//!
+//! Every node knows which node owns each slot and redirects clients with `-MOVED` when a key
+//! does not belong here, or with `-ASK` while the slot is being migrated to another node.
+//! The topology is kept in a `nodes.conf` file using the same format as Redis.
+//!
+//! Nodes exchange heartbeats over the regular client port (see [`gossip`] and [`bus`]) to
+//! discover each other, agree on slot ownership and detect failures.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::resp::replication::{generate_replid, MasterAddr};
+
+pub mod bus;
+pub mod gossip;
+pub mod migrate;
+pub mod slot;
+
+pub use slot::{key_hash_slot, slot_ranges, CLUSTER_SLOTS};
+
+/// Offset between the client port and the cluster bus port reported in `CLUSTER NODES`.
+const BUS_PORT_OFFSET: u16 = 10000;
+
+pub const DEFAULT_NODE_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClusterNode {
+    pub id: String,
+    pub addr: MasterAddr,
+    /// Primary of this node when it is a replica.
+    pub master_id: Option<String>,
+    pub config_epoch: u64,
+}
+
+impl ClusterNode {
+    pub fn endpoint(&self) -> String {
+        format!("{}:{}", self.addr.host, self.addr.port)
+    }
+}
+
+/// What we know about the reachability of another node.
+#[derive(Clone, Debug)]
+pub struct NodeHealth {
+    pub last_pong: Instant,
+    /// Not reachable from here for longer than the node timeout.
+    pub pfail: bool,
+    /// Not reachable from a majority of the primaries.
+    pub fail: bool,
+    /// Primaries that reported the node as unreachable, and when.
+    pub fail_reports: HashMap<String, Instant>,
+}
+
+impl NodeHealth {
+    fn new() -> Self {
+        NodeHealth {
+            last_pong: Instant::now(),
+            pfail: false,
+            fail: false,
+            fail_reports: HashMap::new(),
+        }
+    }
+}
+
+pub struct ClusterState {
+    pub myself: String,
+    pub current_epoch: u64,
+    pub nodes: HashMap<String, ClusterNode>,
+    /// Owner of every slot.
+    slots: Vec<Option<String>>,
+    /// Slots we own that are being moved to another node.
+    pub migrating: HashMap<u16, String>,
+    /// Slots owned by another node that are being moved here.
+    pub importing: HashMap<u16, String>,
+    pub node_timeout: Duration,
+    health: HashMap<String, NodeHealth>,
+    /// Addresses given to `CLUSTER MEET` that did not answer yet.
+    pub pending_meets: Vec<(MasterAddr, Instant)>,
+    config_file: Option<PathBuf>,
+}
+
+impl ClusterState {
+    /// A node that only knows about itself and serves no slots.
+    pub fn new(addr: MasterAddr) -> Self {
+        let myself = ClusterNode {
+            id: generate_replid(),
+            addr,
+            master_id: None,
+            config_epoch: 0,
+        };
+        ClusterState {
+            myself: myself.id.clone(),
+            current_epoch: 0,
+            nodes: HashMap::from([(myself.id.clone(), myself)]),
+            slots: vec![None; CLUSTER_SLOTS],
+            migrating: HashMap::new(),
+            importing: HashMap::new(),
+            node_timeout: DEFAULT_NODE_TIMEOUT,
+            health: HashMap::new(),
+            pending_meets: Vec::new(),
+            config_file: None,
+        }
+    }
+
+    /// Loads the topology from `path`, or creates the file for a new node if it does not exist.
+    pub fn open(path: impl Into<PathBuf>, addr: MasterAddr) -> Result<Self, String> {
+        let path = path.into();
+        let mut state = match fs::read_to_string(&path) {
+            Ok(contents) => Self::parse(&contents, addr)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Self::new(addr),
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+        };
+        state.config_file = Some(path);
+        state.save()?;
+        Ok(state)
+    }
+
+    /// Parses a `nodes.conf` file. The address of the `myself` entry is replaced by `addr`.
+    pub fn parse(contents: &str, addr: MasterAddr) -> Result<Self, String> {
+        let mut myself = None;
+        let mut current_epoch = 0;
+        let mut nodes = HashMap::new();
+        let mut slots = vec![None; CLUSTER_SLOTS];
+
+        for line in contents.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            if fields[0] == "vars" {
+                for pair in fields[1..].chunks(2) {
+                    if let ["currentEpoch", epoch] = pair {
+                        current_epoch = epoch.parse().map_err(|_| "Invalid currentEpoch".to_string())?;
+                    }
+                }
+                continue;
+            }
+            if fields.len() < 8 {
+                return Err(format!("Invalid node line: {}", line));
+            }
+
+            let id = fields[0].to_string();
+            let flags = fields[2].split(',').collect::<Vec<_>>();
+            let node_addr = if flags.contains(&"myself") {
+                myself = Some(id.clone());
+                addr.clone()
+            } else {
+                parse_node_addr(fields[1])?
+            };
+            let master_id = match fields[3] {
+                "-" => None,
+                master => Some(master.to_string()),
+            };
+            let config_epoch = fields[6].parse().map_err(|_| format!("Invalid config epoch: {}", line))?;
+
+            for range in &fields[8..] {
+                // Migration states (`[slot->-id]`) are not persisted across restarts.
+                if range.starts_with('[') {
+                    continue;
+                }
+                let (start, end) = parse_slot_range(range)?;
+                for slot in start..=end {
+                    slots[slot as usize] = Some(id.clone());
+                }
+            }
+
+            nodes.insert(
+                id.clone(),
+                ClusterNode {
+                    id,
+                    addr: node_addr,
+                    master_id,
+                    config_epoch,
+                },
+            );
+        }
+
+        let myself = myself.ok_or("Cluster config has no myself entry")?;
+        let health = nodes
+            .keys()
+            .filter(|id| **id != myself)
+            .map(|id| (id.clone(), NodeHealth::new()))
+            .collect();
+        Ok(ClusterState {
+            myself,
+            current_epoch,
+            nodes,
+            slots,
+            migrating: HashMap::new(),
+            importing: HashMap::new(),
+            node_timeout: DEFAULT_NODE_TIMEOUT,
+            health,
+            pending_meets: Vec::new(),
+            config_file: None,
+        })
+    }
+
+    /// Writes the topology back to the config file, if the state was opened from one.
+    pub fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.config_file else {
+            return Ok(());
+        };
+        let contents = format!(
+            "{}vars currentEpoch {} lastVoteEpoch 0\n",
+            self.nodes_description(),
+            self.current_epoch
+        );
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, contents)
+            .and_then(|_| fs::rename(&tmp, path))
+            .map_err(|e| format!("Failed to save {}: {}", path.display(), e))
+    }
+
+    pub fn myself(&self) -> &ClusterNode {
+        &self.nodes[&self.myself]
+    }
+
+    pub fn node(&self, id: &str) -> Option<&ClusterNode> {
+        self.nodes.get(id)
+    }
+
+    /// Adds or updates another node.
+    pub fn add_node(&mut self, node: ClusterNode) {
+        self.health.entry(node.id.clone()).or_insert_with(NodeHealth::new);
+        self.nodes.insert(node.id.clone(), node);
+    }
+
+    pub fn health(&self, id: &str) -> Option<&NodeHealth> {
+        self.health.get(id)
+    }
+
+    fn health_mut(&mut self, id: &str) -> Option<&mut NodeHealth> {
+        self.health.get_mut(id)
+    }
+
+    /// Takes a new config epoch so that our slot claims win over older ones.
+    pub fn bump_epoch(&mut self) -> u64 {
+        self.current_epoch += 1;
+        let epoch = self.current_epoch;
+        if let Some(myself) = self.nodes.get_mut(&self.myself) {
+            myself.config_epoch = epoch;
+        }
+        epoch
+    }
+
+    pub fn slot_owner(&self, slot: u16) -> Option<&ClusterNode> {
+        self.slots[slot as usize].as_ref().and_then(|id| self.nodes.get(id))
+    }
+
+    /// Assigns a slot to a known node.
+    pub fn assign(&mut self, slot: u16, id: &str) -> Result<(), String> {
+        if !self.nodes.contains_key(id) {
+            return Err(format!("Unknown node {}", id));
+        }
+        self.slots[slot as usize] = Some(id.to_string());
+        Ok(())
+    }
+
+    pub fn unassign(&mut self, slot: u16) {
+        self.slots[slot as usize] = None;
+    }
+
+    pub fn slots_of(&self, id: &str) -> Vec<(u16, u16)> {
+        slot_ranges(
+            (0..CLUSTER_SLOTS as u16).filter(|slot| self.slots[*slot as usize].as_deref() == Some(id)),
+        )
+    }
+
+    pub fn slots_assigned(&self) -> usize {
+        self.slots.iter().filter(|owner| owner.is_some()).count()
+    }
+
+    /// Contiguous slot ranges with their owner, in slot order.
+    pub fn slot_map(&self) -> Vec<(u16, u16, &ClusterNode)> {
+        let mut ranges: Vec<(u16, u16, &ClusterNode)> = Vec::new();
+        for slot in 0..CLUSTER_SLOTS as u16 {
+            let Some(owner) = self.slot_owner(slot) else { continue };
+            match ranges.last_mut() {
+                Some((_, end, node)) if *end + 1 == slot && node.id == owner.id => *end = slot,
+                _ => ranges.push((slot, slot, owner)),
+            }
+        }
+        ranges
+    }
+
+    pub fn replicas_of(&self, id: &str) -> Vec<&ClusterNode> {
+        let mut replicas = self
+            .nodes
+            .values()
+            .filter(|node| node.master_id.as_deref() == Some(id))
+            .collect::<Vec<_>>();
+        replicas.sort_by(|a, b| a.id.cmp(&b.id));
+        replicas
+    }
+
+    /// Primaries sorted by id.
+    pub fn masters(&self) -> Vec<&ClusterNode> {
+        let mut masters = self.nodes.values().filter(|node| node.master_id.is_none()).collect::<Vec<_>>();
+        masters.sort_by(|a, b| a.id.cmp(&b.id));
+        masters
+    }
+
+    /// Checks whether a command on `keys` can run here and returns the redirection otherwise.
+    /// `missing` is the number of those keys that do not exist locally, `asking` is set when
+    /// the client sent `ASKING` right before.
+    pub fn route(&self, keys: &[&str], missing: usize, asking: bool) -> Result<(), String> {
+        let Some(first) = keys.first() else {
+            return Ok(());
+        };
+        let slot = key_hash_slot(first);
+        if keys.iter().any(|key| key_hash_slot(key) != slot) {
+            return Err("CROSSSLOT Keys in request don't hash to the same slot".to_string());
+        }
+
+        if asking && self.importing.contains_key(&slot) {
+            return Ok(());
+        }
+        let Some(owner) = self.slot_owner(slot) else {
+            return Err("CLUSTERDOWN Hash slot not served".to_string());
+        };
+        if owner.id != self.myself {
+            return Err(format!("MOVED {} {}", slot, owner.endpoint()));
+        }
+
+        // Keys of a migrating slot that are already gone have been moved to the target.
+        if let Some(target) = self.migrating.get(&slot).and_then(|id| self.nodes.get(id)) {
+            if missing == keys.len() {
+                return Err(format!("ASK {} {}", slot, target.endpoint()));
+            }
+            if missing > 0 {
+                return Err("TRYAGAIN Multiple keys request during rehashing of slot".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Renders `CLUSTER NODES`, one line per node.
+    pub fn nodes_description(&self) -> String {
+        let mut nodes = self.nodes.values().collect::<Vec<_>>();
+        nodes.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let mut lines = String::new();
+        for node in nodes {
+            let mut flags = Vec::new();
+            if node.id == self.myself {
+                flags.push("myself");
+            }
+            flags.push(if node.master_id.is_some() { "slave" } else { "master" });
+            match self.health(&node.id) {
+                Some(health) if health.fail => flags.push("fail"),
+                Some(health) if health.pfail => flags.push("fail?"),
+                _ => {}
+            }
+
+            let mut line = format!(
+                "{} {}@{} {} {} 0 0 {} connected",
+                node.id,
+                node.endpoint(),
+                node.addr.port.saturating_add(BUS_PORT_OFFSET),
+                flags.join(","),
+                node.master_id.as_deref().unwrap_or("-"),
+                node.config_epoch
+            );
+            for (start, end) in self.slots_of(&node.id) {
+                if start == end {
+                    line.push_str(&format!(" {}", start));
+                } else {
+                    line.push_str(&format!(" {}-{}", start, end));
+                }
+            }
+            if node.id == self.myself {
+                let mut migrations = self
+                    .migrating
+                    .iter()
+                    .map(|(slot, id)| format!(" [{}->-{}]", slot, id))
+                    .chain(self.importing.iter().map(|(slot, id)| format!(" [{}-<-{}]", slot, id)))
+                    .collect::<Vec<_>>();
+                migrations.sort();
+                line.push_str(&migrations.concat());
+            }
+            lines.push_str(&line);
+            lines.push('\n');
+        }
+        lines
+    }
+
+    /// Renders `CLUSTER INFO`.
+    pub fn info(&self) -> String {
+        let assigned = self.slots_assigned();
+        let size = self
+            .masters()
+            .iter()
+            .filter(|node| !self.slots_of(&node.id).is_empty())
+            .count();
+        let failed = self.slots.iter().flatten().any(|id| self.health(id).is_some_and(|health| health.fail));
+        let ok = assigned == CLUSTER_SLOTS && !failed;
+        [
+            format!("cluster_state:{}", if ok { "ok" } else { "fail" }),
+            format!("cluster_slots_assigned:{}", assigned),
+            format!("cluster_known_nodes:{}", self.nodes.len()),
+            format!("cluster_size:{}", size),
+            format!("cluster_current_epoch:{}", self.current_epoch),
+            format!("cluster_my_epoch:{}", self.myself().config_epoch),
+        ]
+        .join("\r\n")
+            + "\r\n"
+    }
+}
+
+/// Parses `host:port@cport`.
+fn parse_node_addr(value: &str) -> Result<MasterAddr, String> {
+    let endpoint = value.split('@').next().unwrap_or(value);
+    let (host, port) = endpoint
+        .rsplit_once(':')
+        .ok_or_else(|| format!("Invalid node address: {}", value))?;
+    Ok(MasterAddr {
+        host: host.to_string(),
+        port: port.parse().map_err(|_| format!("Invalid node address: {}", value))?,
+    })
+}
+
+/// Parses `slot` or `start-end`.
+pub fn parse_slot_range(value: &str) -> Result<(u16, u16), String> {
+    let parse = |slot: &str| {
+        slot.parse::<u16>()
+            .ok()
+            .filter(|slot| (*slot as usize) < CLUSTER_SLOTS)
+            .ok_or_else(|| format!("Invalid slot: {}", value))
+    };
+    let (start, end) = match value.split_once('-') {
+        Some((start, end)) => (parse(start)?, parse(end)?),
+        None => (parse(value)?, parse(value)?),
+    };
+    if start > end {
+        return Err(format!("Invalid slot range: {}", value));
+    }
+    Ok((start, end))
+}
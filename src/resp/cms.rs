@@ -0,0 +1,95 @@
+//! Count-Min Sketches: `depth` rows of `width` counters, each row indexed by its own hash of
+//! the item. Counts are the smallest of the item's counters, which only overestimates.
+
+use std::collections::HashMap;
+
+use crate::resp::hyperloglog::murmur_hash64a;
+use crate::resp::state::value::MAX_STRING_BYTES;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CountMinSketch {
+    pub width: u64,
+    pub depth: u64,
+    /// Total of all increments.
+    pub count: u64,
+    /// Counters, row by row.
+    pub counters: Vec<u32>,
+}
+
+impl CountMinSketch {
+    pub fn new(width: u64, depth: u64) -> Result<CountMinSketch, String> {
+        let len = width.checked_mul(depth).filter(|len| len.saturating_mul(4) <= MAX_STRING_BYTES);
+        let len = len.ok_or_else(|| "CMS: Insufficient memory to create sketch".to_string())?;
+        Ok(CountMinSketch {
+            width,
+            depth,
+            count: 0,
+            counters: vec![0; len as usize],
+        })
+    }
+
+    /// Dimensions for overestimating by at most `error` times the total count, with the given
+    /// probability of exceeding it.
+    pub fn dimensions(error: f64, probability: f64) -> (u64, u64) {
+        let width = (2.0 / error).ceil() as u64;
+        let depth = (probability.ln() / 0.5f64.ln()).ceil() as u64;
+        (width, depth.max(1))
+    }
+
+    /// Index of the counter of `item` in each row.
+    fn indexes(&self, item: &[u8]) -> Vec<usize> {
+        (0..self.depth).map(|row| (row * self.width + murmur_hash64a(item, row) % self.width) as usize).collect()
+    }
+
+    pub fn query(&self, item: &[u8]) -> u32 {
+        self.indexes(item).into_iter().map(|index| self.counters[index]).min().unwrap_or_default()
+    }
+
+    /// Adds each increment to the counters of its item, returning the new counts. Nothing
+    /// changes if a counter would overflow.
+    pub fn increment(&mut self, increments: &[(&[u8], u32)]) -> Result<Vec<u32>, String> {
+        let mut pending = HashMap::<usize, u64>::new();
+        for (item, increment) in increments {
+            for index in self.indexes(item) {
+                *pending.entry(index).or_default() += *increment as u64;
+            }
+        }
+        if pending.iter().any(|(index, increment)| self.counters[*index] as u64 + increment > u32::MAX as u64) {
+            return Err("CMS: INCRBY overflow".to_string());
+        }
+
+        let mut counts = Vec::new();
+        for (item, increment) in increments {
+            for index in self.indexes(item) {
+                self.counters[index] += increment;
+            }
+            self.count = self.count.saturating_add(*increment as u64);
+            counts.push(self.query(item));
+        }
+        Ok(counts)
+    }
+
+    /// Replaces the counters with the weighted sum of those of `sources`, which must all have
+    /// the dimensions of this sketch.
+    pub fn merge(&mut self, sources: &[(&CountMinSketch, i64)]) -> Result<(), String> {
+        if sources.iter().any(|(source, _)| (source.width, source.depth) != (self.width, self.depth)) {
+            return Err("CMS: width/depth is not equal".to_string());
+        }
+        let overflow = || "CMS: MERGE overflow".to_string();
+        // Weighted sum of a value of each source.
+        let sum = |value: &dyn Fn(&CountMinSketch) -> u64| {
+            sources.iter().try_fold(0i128, |sum, (source, weight)| {
+                sum.checked_add((value(source) as i128).checked_mul(*weight as i128)?)
+            })
+        };
+        let mut counters = Vec::with_capacity(self.counters.len());
+        for index in 0..self.counters.len() {
+            let counter = sum(&|source| source.counters[index] as u64);
+            counters.push(counter.and_then(|counter| u32::try_from(counter).ok()).ok_or_else(overflow)?);
+        }
+        let count = sum(&|source| source.count);
+        self.count = count.and_then(|count| u64::try_from(count).ok()).ok_or_else(overflow)?;
+        self.counters = counters;
+        Ok(())
+    }
+}
@@ -1,6 +1,25 @@
 use std::collections::HashMap;
 
-use crate::resp::commands::{Command, Ping, Echo, Set, Get};
+use crate::resp::blocking::serve_blocked_clients;
+use crate::resp::client::ClientContext;
+use crate::resp::commands::{
+    Append, Asking, BLMPop, BLMove, BLPop, BRPop, BZMPop, BZPopMax, BZPopMin, BfAdd, BfExists, BfMAdd, BfMExists,
+    BfReserve, BitCount, BitField, BitFieldRo, BitOp, BitPos, CfAdd, CfDel, CfExists, CfReserve, Cluster, CmsIncrBy,
+    CmsInitByDim, CmsInitByProb, CmsMerge, CmsQuery, Command, Decr, DecrBy, Del, Dump, Echo, GeoAdd, GeoDist, GeoHash,
+    GeoPos, GeoSearch, GeoSearchStore, Get, GetBit, GetDel, GetEx, GetRange, GetSet, HDel, HExists, HExpire, HExpireAt,
+    HExpireTime, HGet, HGetAll, HIncrBy, HIncrByFloat, HKeys, HLen, HMGet, HPExpire, HPExpireAt, HPExpireTime, HPTtl,
+    HPersist, HRandField, HScan, HSet, HSetNx, HStrLen, HTtl, HVals, Incr, IncrBy, IncrByFloat, Info, JsonArrAppend,
+    JsonArrInsert, JsonArrLen, JsonArrPop, JsonDel, JsonGet, JsonNumIncrBy, JsonObjKeys, JsonSet, JsonStrAppend,
+    JsonType, LIndex, LInsert, LLen, LMPop, LMove, LPop, LPos, LPush, LPushX, LRange, LRem, LSet, LTrim, MGet, MSet,
+    MSetNx, Migrate, PSetEx, PfAdd, PfCount, PfMerge, Ping, Psync, RPop, RPopLPush, RPush, RPushX, Replconf, Replicaof,
+    Restore, SAdd, SCard, SDiff, SDiffStore, SInter, SInterCard, SInterStore, SIsMember, SMIsMember, SMembers, SMove,
+    SPop, SRandMember, SRem, SScan, SUnion, SUnionStore, Sentinel, Set, SetBit, SetEx, SetNx, SetRange, StrLen, TopKAdd,
+    TopKIncrBy, TopKList, TopKQuery, TopKReserve, TsAdd, TsCreate, TsCreateRule, TsDeleteRule, TsGet, TsMAdd, TsMRange,
+    TsMRevRange, TsRange, TsRevRange, Type, Wait, XAck, XAdd, XAutoClaim, XClaim, XDel, XGroup, XInfo, XLen, XPending,
+    XRange, XRead, XReadGroup, XRevRange, XTrim, ZAdd, ZCard, ZCount, ZDiff, ZDiffStore, ZIncrBy, ZInter, ZInterCard,
+    ZInterStore, ZMPop, ZMScore, ZPopMax, ZPopMin, ZRange, ZRangeStore, ZRank, ZRem, ZRemRangeByLex, ZRemRangeByRank,
+    ZRemRangeByScore, ZRevRank, ZScan, ZScore, ZUnion, ZUnionStore,
+};
 use crate::resp::protocol::RespType;
 use crate::resp::state::default_server_state::DefaultServerState;
 use crate::resp::state::server_state::ServerState;
@@ -11,6 +30,12 @@ pub struct CommandDispatcher {
     pub commands: HashMap<String, Box<dyn Command + Send + Sync>>,
 }
 
+impl Default for CommandDispatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl CommandDispatcher {
     pub fn new() -> Self {
         let mut commands: HashMap<String, Box<dyn Command + Send + Sync>> = HashMap::new();
@@ -18,16 +43,279 @@ impl CommandDispatcher {
         commands.insert("PING".to_string(), Box::new(Ping));
         commands.insert("SET".to_string(), Box::new(Set));
         commands.insert("GET".to_string(), Box::new(Get));
+        commands.insert("INCR".to_string(), Box::new(Incr));
+        commands.insert("DECR".to_string(), Box::new(Decr));
+        commands.insert("INCRBY".to_string(), Box::new(IncrBy));
+        commands.insert("DECRBY".to_string(), Box::new(DecrBy));
+        commands.insert("INCRBYFLOAT".to_string(), Box::new(IncrByFloat));
+        commands.insert("APPEND".to_string(), Box::new(Append));
+        commands.insert("STRLEN".to_string(), Box::new(StrLen));
+        commands.insert("GETRANGE".to_string(), Box::new(GetRange));
+        commands.insert("SETRANGE".to_string(), Box::new(SetRange));
+        commands.insert("GETSET".to_string(), Box::new(GetSet));
+        commands.insert("GETDEL".to_string(), Box::new(GetDel));
+        commands.insert("GETEX".to_string(), Box::new(GetEx));
+        commands.insert("SETNX".to_string(), Box::new(SetNx));
+        commands.insert("SETEX".to_string(), Box::new(SetEx));
+        commands.insert("PSETEX".to_string(), Box::new(PSetEx));
+        commands.insert("MSET".to_string(), Box::new(MSet));
+        commands.insert("MSETNX".to_string(), Box::new(MSetNx));
+        commands.insert("MGET".to_string(), Box::new(MGet));
+        commands.insert("INFO".to_string(), Box::new(Info));
+        commands.insert("REPLCONF".to_string(), Box::new(Replconf));
+        commands.insert("PSYNC".to_string(), Box::new(Psync));
+        commands.insert("WAIT".to_string(), Box::new(Wait));
+        commands.insert("REPLICAOF".to_string(), Box::new(Replicaof));
+        commands.insert("SLAVEOF".to_string(), Box::new(Replicaof));
+        commands.insert("CLUSTER".to_string(), Box::new(Cluster));
+        commands.insert("ASKING".to_string(), Box::new(Asking));
+        commands.insert("MIGRATE".to_string(), Box::new(Migrate));
+        commands.insert("DEL".to_string(), Box::new(Del));
+        commands.insert("DUMP".to_string(), Box::new(Dump));
+        commands.insert("RESTORE".to_string(), Box::new(Restore));
+        commands.insert("TYPE".to_string(), Box::new(Type));
+        commands.insert("LPUSH".to_string(), Box::new(LPush));
+        commands.insert("RPUSH".to_string(), Box::new(RPush));
+        commands.insert("LPUSHX".to_string(), Box::new(LPushX));
+        commands.insert("RPUSHX".to_string(), Box::new(RPushX));
+        commands.insert("LPOP".to_string(), Box::new(LPop));
+        commands.insert("RPOP".to_string(), Box::new(RPop));
+        commands.insert("LLEN".to_string(), Box::new(LLen));
+        commands.insert("LRANGE".to_string(), Box::new(LRange));
+        commands.insert("LINDEX".to_string(), Box::new(LIndex));
+        commands.insert("LSET".to_string(), Box::new(LSet));
+        commands.insert("LINSERT".to_string(), Box::new(LInsert));
+        commands.insert("LREM".to_string(), Box::new(LRem));
+        commands.insert("LTRIM".to_string(), Box::new(LTrim));
+        commands.insert("LPOS".to_string(), Box::new(LPos));
+        commands.insert("LMOVE".to_string(), Box::new(LMove));
+        commands.insert("RPOPLPUSH".to_string(), Box::new(RPopLPush));
+        commands.insert("LMPOP".to_string(), Box::new(LMPop));
+        commands.insert("BLPOP".to_string(), Box::new(BLPop));
+        commands.insert("BRPOP".to_string(), Box::new(BRPop));
+        commands.insert("BLMOVE".to_string(), Box::new(BLMove));
+        commands.insert("BLMPOP".to_string(), Box::new(BLMPop));
+        commands.insert("HSET".to_string(), Box::new(HSet));
+        commands.insert("HSETNX".to_string(), Box::new(HSetNx));
+        commands.insert("HGET".to_string(), Box::new(HGet));
+        commands.insert("HMGET".to_string(), Box::new(HMGet));
+        commands.insert("HDEL".to_string(), Box::new(HDel));
+        commands.insert("HEXISTS".to_string(), Box::new(HExists));
+        commands.insert("HLEN".to_string(), Box::new(HLen));
+        commands.insert("HSTRLEN".to_string(), Box::new(HStrLen));
+        commands.insert("HKEYS".to_string(), Box::new(HKeys));
+        commands.insert("HVALS".to_string(), Box::new(HVals));
+        commands.insert("HGETALL".to_string(), Box::new(HGetAll));
+        commands.insert("HINCRBY".to_string(), Box::new(HIncrBy));
+        commands.insert("HINCRBYFLOAT".to_string(), Box::new(HIncrByFloat));
+        commands.insert("HRANDFIELD".to_string(), Box::new(HRandField));
+        commands.insert("HSCAN".to_string(), Box::new(HScan));
+        commands.insert("HEXPIRE".to_string(), Box::new(HExpire));
+        commands.insert("HPEXPIRE".to_string(), Box::new(HPExpire));
+        commands.insert("HEXPIREAT".to_string(), Box::new(HExpireAt));
+        commands.insert("HPEXPIREAT".to_string(), Box::new(HPExpireAt));
+        commands.insert("HTTL".to_string(), Box::new(HTtl));
+        commands.insert("HPTTL".to_string(), Box::new(HPTtl));
+        commands.insert("HEXPIRETIME".to_string(), Box::new(HExpireTime));
+        commands.insert("HPEXPIRETIME".to_string(), Box::new(HPExpireTime));
+        commands.insert("HPERSIST".to_string(), Box::new(HPersist));
+        commands.insert("SADD".to_string(), Box::new(SAdd));
+        commands.insert("SREM".to_string(), Box::new(SRem));
+        commands.insert("SISMEMBER".to_string(), Box::new(SIsMember));
+        commands.insert("SMISMEMBER".to_string(), Box::new(SMIsMember));
+        commands.insert("SMEMBERS".to_string(), Box::new(SMembers));
+        commands.insert("SCARD".to_string(), Box::new(SCard));
+        commands.insert("SPOP".to_string(), Box::new(SPop));
+        commands.insert("SRANDMEMBER".to_string(), Box::new(SRandMember));
+        commands.insert("SMOVE".to_string(), Box::new(SMove));
+        commands.insert("SINTER".to_string(), Box::new(SInter));
+        commands.insert("SINTERCARD".to_string(), Box::new(SInterCard));
+        commands.insert("SINTERSTORE".to_string(), Box::new(SInterStore));
+        commands.insert("SUNION".to_string(), Box::new(SUnion));
+        commands.insert("SUNIONSTORE".to_string(), Box::new(SUnionStore));
+        commands.insert("SDIFF".to_string(), Box::new(SDiff));
+        commands.insert("SDIFFSTORE".to_string(), Box::new(SDiffStore));
+        commands.insert("SSCAN".to_string(), Box::new(SScan));
+        commands.insert("ZADD".to_string(), Box::new(ZAdd));
+        commands.insert("ZINCRBY".to_string(), Box::new(ZIncrBy));
+        commands.insert("ZREM".to_string(), Box::new(ZRem));
+        commands.insert("ZSCORE".to_string(), Box::new(ZScore));
+        commands.insert("ZMSCORE".to_string(), Box::new(ZMScore));
+        commands.insert("ZCARD".to_string(), Box::new(ZCard));
+        commands.insert("ZCOUNT".to_string(), Box::new(ZCount));
+        commands.insert("ZRANK".to_string(), Box::new(ZRank));
+        commands.insert("ZREVRANK".to_string(), Box::new(ZRevRank));
+        commands.insert("ZRANGE".to_string(), Box::new(ZRange));
+        commands.insert("ZRANGESTORE".to_string(), Box::new(ZRangeStore));
+        commands.insert("ZREMRANGEBYRANK".to_string(), Box::new(ZRemRangeByRank));
+        commands.insert("ZREMRANGEBYSCORE".to_string(), Box::new(ZRemRangeByScore));
+        commands.insert("ZREMRANGEBYLEX".to_string(), Box::new(ZRemRangeByLex));
+        commands.insert("ZPOPMIN".to_string(), Box::new(ZPopMin));
+        commands.insert("ZPOPMAX".to_string(), Box::new(ZPopMax));
+        commands.insert("ZSCAN".to_string(), Box::new(ZScan));
+        commands.insert("ZUNION".to_string(), Box::new(ZUnion));
+        commands.insert("ZINTER".to_string(), Box::new(ZInter));
+        commands.insert("ZDIFF".to_string(), Box::new(ZDiff));
+        commands.insert("ZUNIONSTORE".to_string(), Box::new(ZUnionStore));
+        commands.insert("ZINTERSTORE".to_string(), Box::new(ZInterStore));
+        commands.insert("ZDIFFSTORE".to_string(), Box::new(ZDiffStore));
+        commands.insert("ZINTERCARD".to_string(), Box::new(ZInterCard));
+        commands.insert("ZMPOP".to_string(), Box::new(ZMPop));
+        commands.insert("BZPOPMIN".to_string(), Box::new(BZPopMin));
+        commands.insert("BZPOPMAX".to_string(), Box::new(BZPopMax));
+        commands.insert("BZMPOP".to_string(), Box::new(BZMPop));
+        commands.insert("XADD".to_string(), Box::new(XAdd));
+        commands.insert("XLEN".to_string(), Box::new(XLen));
+        commands.insert("XRANGE".to_string(), Box::new(XRange));
+        commands.insert("XREVRANGE".to_string(), Box::new(XRevRange));
+        commands.insert("XREAD".to_string(), Box::new(XRead));
+        commands.insert("XDEL".to_string(), Box::new(XDel));
+        commands.insert("XTRIM".to_string(), Box::new(XTrim));
+        commands.insert("XREADGROUP".to_string(), Box::new(XReadGroup));
+        commands.insert("XGROUP".to_string(), Box::new(XGroup));
+        commands.insert("XACK".to_string(), Box::new(XAck));
+        commands.insert("XPENDING".to_string(), Box::new(XPending));
+        commands.insert("XCLAIM".to_string(), Box::new(XClaim));
+        commands.insert("XAUTOCLAIM".to_string(), Box::new(XAutoClaim));
+        commands.insert("XINFO".to_string(), Box::new(XInfo));
+        commands.insert("PFADD".to_string(), Box::new(PfAdd));
+        commands.insert("PFCOUNT".to_string(), Box::new(PfCount));
+        commands.insert("PFMERGE".to_string(), Box::new(PfMerge));
+        commands.insert("SETBIT".to_string(), Box::new(SetBit));
+        commands.insert("GETBIT".to_string(), Box::new(GetBit));
+        commands.insert("BITCOUNT".to_string(), Box::new(BitCount));
+        commands.insert("BITPOS".to_string(), Box::new(BitPos));
+        commands.insert("BITOP".to_string(), Box::new(BitOp));
+        commands.insert("BITFIELD".to_string(), Box::new(BitField));
+        commands.insert("BITFIELD_RO".to_string(), Box::new(BitFieldRo));
+        commands.insert("GEOADD".to_string(), Box::new(GeoAdd));
+        commands.insert("GEOPOS".to_string(), Box::new(GeoPos));
+        commands.insert("GEODIST".to_string(), Box::new(GeoDist));
+        commands.insert("GEOHASH".to_string(), Box::new(GeoHash));
+        commands.insert("GEOSEARCH".to_string(), Box::new(GeoSearch));
+        commands.insert("GEOSEARCHSTORE".to_string(), Box::new(GeoSearchStore));
+        commands.insert("JSON.SET".to_string(), Box::new(JsonSet));
+        commands.insert("JSON.GET".to_string(), Box::new(JsonGet));
+        commands.insert("JSON.DEL".to_string(), Box::new(JsonDel));
+        commands.insert("JSON.TYPE".to_string(), Box::new(JsonType));
+        commands.insert("JSON.NUMINCRBY".to_string(), Box::new(JsonNumIncrBy));
+        commands.insert("JSON.STRAPPEND".to_string(), Box::new(JsonStrAppend));
+        commands.insert("JSON.ARRAPPEND".to_string(), Box::new(JsonArrAppend));
+        commands.insert("JSON.ARRINSERT".to_string(), Box::new(JsonArrInsert));
+        commands.insert("JSON.ARRPOP".to_string(), Box::new(JsonArrPop));
+        commands.insert("JSON.ARRLEN".to_string(), Box::new(JsonArrLen));
+        commands.insert("JSON.OBJKEYS".to_string(), Box::new(JsonObjKeys));
+        commands.insert("BF.RESERVE".to_string(), Box::new(BfReserve));
+        commands.insert("BF.ADD".to_string(), Box::new(BfAdd));
+        commands.insert("BF.MADD".to_string(), Box::new(BfMAdd));
+        commands.insert("BF.EXISTS".to_string(), Box::new(BfExists));
+        commands.insert("BF.MEXISTS".to_string(), Box::new(BfMExists));
+        commands.insert("CF.RESERVE".to_string(), Box::new(CfReserve));
+        commands.insert("CF.ADD".to_string(), Box::new(CfAdd));
+        commands.insert("CF.DEL".to_string(), Box::new(CfDel));
+        commands.insert("CF.EXISTS".to_string(), Box::new(CfExists));
+        commands.insert("CMS.INITBYDIM".to_string(), Box::new(CmsInitByDim));
+        commands.insert("CMS.INITBYPROB".to_string(), Box::new(CmsInitByProb));
+        commands.insert("CMS.INCRBY".to_string(), Box::new(CmsIncrBy));
+        commands.insert("CMS.QUERY".to_string(), Box::new(CmsQuery));
+        commands.insert("CMS.MERGE".to_string(), Box::new(CmsMerge));
+        commands.insert("TOPK.RESERVE".to_string(), Box::new(TopKReserve));
+        commands.insert("TOPK.ADD".to_string(), Box::new(TopKAdd));
+        commands.insert("TOPK.INCRBY".to_string(), Box::new(TopKIncrBy));
+        commands.insert("TOPK.QUERY".to_string(), Box::new(TopKQuery));
+        commands.insert("TOPK.LIST".to_string(), Box::new(TopKList));
+        commands.insert("TS.CREATE".to_string(), Box::new(TsCreate));
+        commands.insert("TS.ADD".to_string(), Box::new(TsAdd));
+        commands.insert("TS.MADD".to_string(), Box::new(TsMAdd));
+        commands.insert("TS.GET".to_string(), Box::new(TsGet));
+        commands.insert("TS.RANGE".to_string(), Box::new(TsRange));
+        commands.insert("TS.REVRANGE".to_string(), Box::new(TsRevRange));
+        commands.insert("TS.MRANGE".to_string(), Box::new(TsMRange));
+        commands.insert("TS.MREVRANGE".to_string(), Box::new(TsMRevRange));
+        commands.insert("TS.CREATERULE".to_string(), Box::new(TsCreateRule));
+        commands.insert("TS.DELETERULE".to_string(), Box::new(TsDeleteRule));
         // Add more commands as needed
 
         Self { commands }
     }
 
-    pub fn dispatch(&self, command_name: &str, args: Vec<RespType>, state: &mut DefaultServerState) -> Result<RespType, String> {
-        if let Some(command) = self.commands.get(command_name.to_uppercase().as_str()) {
-            command.execute(&args, state as &mut dyn ServerState)
+    /// Dispatcher of a sentinel, which only answers monitoring commands.
+    pub fn sentinel() -> Self {
+        let mut commands: HashMap<String, Box<dyn Command + Send + Sync>> = HashMap::new();
+        commands.insert("PING".to_string(), Box::new(Ping));
+        commands.insert("INFO".to_string(), Box::new(Info));
+        commands.insert("SENTINEL".to_string(), Box::new(Sentinel));
+
+        Self { commands }
+    }
+
+    pub fn dispatch(
+        &self,
+        command_name: &str,
+        args: Vec<RespType>,
+        state: &mut DefaultServerState,
+        client: &mut ClientContext,
+    ) -> Result<RespType, String> {
+        let name = command_name.to_uppercase();
+        if let Some(command) = self.commands.get(name.as_str()) {
+            if !client.is_master {
+                let replication = state.replication();
+                if command.is_write() && !replication.is_master() && replication.read_only {
+                    return Err("READONLY You can't write against a read only replica.".to_string());
+                }
+                if !command.allow_stale() && replication.is_stale() && !replication.serve_stale_data {
+                    return Err(
+                        "MASTERDOWN Link with MASTER is down and replica-serve-stale-data is set to 'no'."
+                            .to_string(),
+                    );
+                }
+            }
+
+            // `ASKING` only applies to the command that follows it.
+            let asking = std::mem::take(&mut client.asking);
+            if !client.is_master && state.cluster().is_some() {
+                let keys = command.keys(&args);
+                if !keys.is_empty() {
+                    let missing = keys.iter().filter(|key| !state.exists(key)).count();
+                    if let Some(cluster) = state.cluster() {
+                        cluster.route(&keys, missing, asking)?;
+                    }
+                }
+            }
+
+            let response = command.execute_with_client(&args, state as &mut dyn ServerState, client)?;
+
+            // Writes applied from our own primary are fed into the stream by the replica link.
+            if command.is_write()
+                && command.propagate_verbatim()
+                && !client.is_master
+                && state.replication().is_master()
+            {
+                let mut frame = vec![RespType::BulkString(Some(name))];
+                frame.extend(args);
+                state.replication().propagate(&RespType::Array(frame));
+            }
+            if command.is_write() && !state.blocked_clients().is_empty() {
+                serve_blocked_clients(state);
+            }
+
+            Ok(response)
         } else {
             Err(format!("Unknown command: {}", command_name))
         }
     }
-}
\ No newline at end of file
+}
+
+/// Splits a request into the upper-cased command name and its arguments.
+pub fn parse_command(frame: RespType) -> Option<(String, Vec<RespType>)> {
+    match frame {
+        RespType::Array(mut arr) if !arr.is_empty() => {
+            let args = arr.split_off(1);
+            match arr.pop() {
+                Some(RespType::BulkString(Some(cmd))) => Some((cmd.to_uppercase(), args)),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
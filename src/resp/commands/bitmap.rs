@@ -0,0 +1,556 @@
+//! Bitmap commands. Bitmaps are string values addressed bit by bit, bit 0 being the most
+//! significant bit of the first byte. Strings grow with zero bytes as bits are set.
+
+use crate::resp::commands::{arg_i64, arg_str, first_key, Command};
+use crate::resp::protocol::RespType;
+use crate::resp::state::server_state::ServerState;
+use crate::resp::state::value::{Value, WRONG_TYPE};
+
+/// Highest bit offset, keeping strings within Redis' 512MB limit.
+const MAX_BIT_OFFSET: u64 = (1 << 32) - 1;
+
+fn bytes<'a>(state: &'a mut dyn ServerState, key: &str) -> Result<Option<&'a [u8]>, String> {
+    match state.get(key) {
+        Some(Value::String(value)) => Ok(Some(value)),
+        Some(_) => Err(WRONG_TYPE.to_string()),
+        None => Ok(None),
+    }
+}
+
+fn bit_offset(args: &[RespType], index: usize, name: &str) -> Result<u64, String> {
+    arg_str(args, index, name)?
+        .parse::<u64>()
+        .ok()
+        .filter(|offset| *offset <= MAX_BIT_OFFSET)
+        .ok_or_else(|| "bit offset is not an integer or out of range".to_string())
+}
+
+fn bit(bytes: &[u8], offset: u64) -> u8 {
+    let byte = bytes.get((offset / 8) as usize).copied().unwrap_or(0);
+    (byte >> (7 - offset % 8)) & 1
+}
+
+/// Sets or clears a bit within `bytes`, returning its previous value.
+fn set_bit(bytes: &mut [u8], offset: u64, on: bool) -> bool {
+    let mask = 1 << (7 - offset % 8);
+    let byte = &mut bytes[(offset / 8) as usize];
+    let previous = *byte & mask != 0;
+    if on {
+        *byte |= mask;
+    } else {
+        *byte &= !mask;
+    }
+    previous
+}
+
+/// Parses the optional `BYTE` or `BIT` unit, returning whether offsets count bits.
+fn bit_unit(args: &[RespType], index: usize, name: &str) -> Result<bool, String> {
+    if index >= args.len() {
+        return Ok(false);
+    }
+    match arg_str(args, index, name)?.to_uppercase().as_str() {
+        "BYTE" => Ok(false),
+        "BIT" => Ok(true),
+        _ => Err("syntax error".to_string()),
+    }
+}
+
+/// Inclusive range of bits selected by `start` and `end`, which count from the end when
+/// negative, in bytes or bits. `None` when the range is empty.
+fn bit_range(start: i64, end: i64, len: usize, bits: bool) -> Option<(u64, u64)> {
+    let total = if bits { len as i64 * 8 } else { len as i64 };
+    let normalize = |index: i64| if index < 0 { (total + index).max(0) } else { index };
+    let (start, end) = (normalize(start), normalize(end).min(total - 1));
+    if start > end {
+        return None;
+    }
+    let (start, end) = (start as u64, end as u64);
+    Some(if bits { (start, end) } else { (start * 8, end * 8 + 7) })
+}
+
+/// `SETBIT key offset value`: sets or clears a bit, returning its previous value.
+pub struct SetBit;
+
+impl Command for SetBit {
+    fn name(&self) -> &str {
+        "SETBIT"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() != 3 {
+            return Err("wrong number of arguments for 'setbit' command".to_string());
+        }
+        let key = arg_str(args, 0, self.name())?;
+        let offset = bit_offset(args, 1, self.name())?;
+        let on = match arg_str(args, 2, self.name())? {
+            "0" => false,
+            "1" => true,
+            _ => return Err("bit is not an integer or out of range".to_string()),
+        };
+
+        if bytes(state, key)?.is_none() {
+            state.set(key.to_string(), Value::String(Vec::new()), None)?;
+        }
+        let Some(Value::String(value)) = state.get_mut(key) else {
+            return Err("Failed to create string".to_string());
+        };
+        let byte = (offset / 8) as usize;
+        if byte >= value.len() {
+            value.resize(byte + 1, 0);
+        }
+        Ok(RespType::Integer(set_bit(value, offset, on) as i64))
+    }
+}
+
+/// `GETBIT key offset`: returns the bit at `offset`, 0 past the end of the string.
+pub struct GetBit;
+
+impl Command for GetBit {
+    fn name(&self) -> &str {
+        "GETBIT"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() != 2 {
+            return Err("wrong number of arguments for 'getbit' command".to_string());
+        }
+        let key = arg_str(args, 0, self.name())?;
+        let offset = bit_offset(args, 1, self.name())?;
+        let value = bytes(state, key)?.unwrap_or_default();
+        Ok(RespType::Integer(bit(value, offset) as i64))
+    }
+}
+
+/// `BITCOUNT key [start end [BYTE | BIT]]`: counts the set bits, of the whole string or of
+/// a range of bytes or bits.
+pub struct BitCount;
+
+impl Command for BitCount {
+    fn name(&self) -> &str {
+        "BITCOUNT"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let (start, end, bits) = match args.len() {
+            1 => (0, -1, false),
+            3 | 4 => (arg_i64(args, 1, self.name())?, arg_i64(args, 2, self.name())?, bit_unit(args, 3, self.name())?),
+            _ => return Err("syntax error".to_string()),
+        };
+        let value = bytes(state, key)?.unwrap_or_default();
+        let Some((start, end)) = bit_range(start, end, value.len(), bits) else {
+            return Ok(RespType::Integer(0));
+        };
+
+        // Whole bytes are counted at once, the partial ones at the edges bit by bit.
+        let (first, last) = (start.div_ceil(8), (end + 1) / 8);
+        let count = if first < last {
+            let edges = (start..first * 8).chain(last * 8..=end).map(|offset| bit(value, offset) as u64);
+            let middle = value[first as usize..last as usize].iter().map(|byte| byte.count_ones() as u64);
+            edges.sum::<u64>() + middle.sum::<u64>()
+        } else {
+            (start..=end).map(|offset| bit(value, offset) as u64).sum()
+        };
+        Ok(RespType::Integer(count as i64))
+    }
+}
+
+/// `BITPOS key bit [start [end [BYTE | BIT]]]`: returns the position of the first bit set to
+/// `bit`, or -1. Without an explicit end, the string counts as padded with clear bits.
+pub struct BitPos;
+
+impl Command for BitPos {
+    fn name(&self) -> &str {
+        "BITPOS"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if !(2..=5).contains(&args.len()) {
+            return Err(if args.len() < 2 {
+                "wrong number of arguments for 'bitpos' command".to_string()
+            } else {
+                "syntax error".to_string()
+            });
+        }
+        let key = arg_str(args, 0, self.name())?;
+        let wanted = match arg_str(args, 1, self.name())? {
+            "0" => 0,
+            "1" => 1,
+            _ => return Err("The bit argument must be 1 or 0.".to_string()),
+        };
+        let start = if args.len() > 2 { arg_i64(args, 2, self.name())? } else { 0 };
+        let end_given = args.len() > 3;
+        let end = if end_given { arg_i64(args, 3, self.name())? } else { -1 };
+        let bits = bit_unit(args, 4, self.name())?;
+
+        let Some(value) = bytes(state, key)? else {
+            return Ok(RespType::Integer(if wanted == 1 { -1 } else { 0 }));
+        };
+        let Some((start, end)) = bit_range(start, end, value.len(), bits) else {
+            return Ok(RespType::Integer(-1));
+        };
+
+        let skipped = if wanted == 1 { 0 } else { 0xFF };
+        let mut offset = start;
+        while offset <= end {
+            // Bytes without the wanted bit are skipped whole.
+            if offset.is_multiple_of(8) && offset + 7 <= end && value[(offset / 8) as usize] == skipped {
+                offset += 8;
+                continue;
+            }
+            if bit(value, offset) == wanted {
+                return Ok(RespType::Integer(offset as i64));
+            }
+            offset += 1;
+        }
+        Ok(RespType::Integer(if wanted == 0 && !end_given { end as i64 + 1 } else { -1 }))
+    }
+}
+
+#[derive(Clone, Copy)]
+enum BitOperation {
+    And,
+    Or,
+    Xor,
+    Not,
+    /// Bits set in the first key and in none of the others.
+    Diff,
+}
+
+/// `BITOP <AND | OR | XOR | NOT | DIFF> destkey key [key ...]`: stores the bitwise operation
+/// over the strings, shorter ones padded with zero bytes, and returns the length of the result.
+/// The destination is deleted when the result is empty.
+pub struct BitOp;
+
+impl Command for BitOp {
+    fn name(&self) -> &str {
+        "BITOP"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        (1..args.len()).filter_map(|index| arg_str(args, index, self.name()).ok()).collect()
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() < 3 {
+            return Err("wrong number of arguments for 'bitop' command".to_string());
+        }
+        let operation = match arg_str(args, 0, self.name())?.to_uppercase().as_str() {
+            "AND" => BitOperation::And,
+            "OR" => BitOperation::Or,
+            "XOR" => BitOperation::Xor,
+            "NOT" => BitOperation::Not,
+            "DIFF" => BitOperation::Diff,
+            _ => return Err("syntax error".to_string()),
+        };
+        let destination = arg_str(args, 1, self.name())?;
+        let sources = (2..args.len())
+            .map(|index| Ok(bytes(state, arg_str(args, index, self.name())?)?.unwrap_or_default().to_vec()))
+            .collect::<Result<Vec<_>, String>>()?;
+        match operation {
+            BitOperation::Not if sources.len() != 1 => {
+                return Err("BITOP NOT must be called with a single source key.".to_string());
+            }
+            BitOperation::Diff if sources.len() < 2 => {
+                return Err("BITOP DIFF must be called with multiple source keys.".to_string());
+            }
+            _ => {}
+        }
+
+        let len = sources.iter().map(Vec::len).max().unwrap_or(0);
+        let byte = |source: &Vec<u8>, index: usize| source.get(index).copied().unwrap_or(0);
+        let result = (0..len)
+            .map(|index| {
+                let mut bytes = sources.iter().map(|source| byte(source, index));
+                let first = bytes.next().unwrap_or(0);
+                match operation {
+                    BitOperation::And => bytes.fold(first, |result, byte| result & byte),
+                    BitOperation::Or => bytes.fold(first, |result, byte| result | byte),
+                    BitOperation::Xor => bytes.fold(first, |result, byte| result ^ byte),
+                    BitOperation::Not => !first,
+                    BitOperation::Diff => first & !bytes.fold(0, |result, byte| result | byte),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if result.is_empty() {
+            state.del(destination)?;
+        } else {
+            state.set(destination.to_string(), Value::String(result), None)?;
+        }
+        Ok(RespType::Integer(len as i64))
+    }
+}
+
+/// Integer type of a `BITFIELD` field: `i1` to `i64` or `u1` to `u63`.
+#[derive(Clone, Copy)]
+struct FieldType {
+    signed: bool,
+    bits: u32,
+}
+
+impl FieldType {
+    fn parse(value: &str) -> Result<FieldType, String> {
+        let invalid = || "Invalid bitfield type. Use something like i16 u8. Note that u64 is not supported but i64 is.";
+        let signed = match value.as_bytes().first() {
+            Some(b'i' | b'I') => true,
+            Some(b'u' | b'U') => false,
+            _ => return Err(invalid().to_string()),
+        };
+        let bits = value[1..].parse::<u32>().map_err(|_| invalid().to_string())?;
+        if bits == 0 || bits > 64 || (!signed && bits == 64) {
+            return Err(invalid().to_string());
+        }
+        Ok(FieldType { signed, bits })
+    }
+
+    fn min(&self) -> i128 {
+        if self.signed {
+            -(1 << (self.bits - 1))
+        } else {
+            0
+        }
+    }
+
+    fn max(&self) -> i128 {
+        if self.signed {
+            (1 << (self.bits - 1)) - 1
+        } else {
+            (1 << self.bits) - 1
+        }
+    }
+
+    /// Brings a value into the range of the type according to the overflow policy, or
+    /// `None` when it overflows with `FAIL`.
+    fn fit(&self, value: i128, overflow: Overflow) -> Option<i128> {
+        if (self.min()..=self.max()).contains(&value) {
+            return Some(value);
+        }
+        match overflow {
+            Overflow::Wrap => {
+                let wrapped = value.rem_euclid(1 << self.bits);
+                Some(if wrapped > self.max() { wrapped - (1 << self.bits) } else { wrapped })
+            }
+            Overflow::Sat => Some(value.clamp(self.min(), self.max())),
+            Overflow::Fail => None,
+        }
+    }
+
+    fn read(&self, bytes: &[u8], offset: u64) -> i128 {
+        let raw = (offset..offset + self.bits as u64)
+            .fold(0u64, |value, offset| (value << 1) | bit(bytes, offset) as u64);
+        if self.signed && self.bits < 64 && raw >> (self.bits - 1) == 1 {
+            raw as i128 - (1 << self.bits)
+        } else if self.signed {
+            raw as i64 as i128
+        } else {
+            raw as i128
+        }
+    }
+
+    fn write(&self, bytes: &mut [u8], offset: u64, value: i128) {
+        let raw = value as u64;
+        for index in 0..self.bits as u64 {
+            set_bit(bytes, offset + index, (raw >> (self.bits as u64 - 1 - index)) & 1 == 1);
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Overflow {
+    Wrap,
+    Sat,
+    Fail,
+}
+
+enum FieldOperation {
+    Get,
+    Set(i64),
+    IncrBy(i64),
+}
+
+struct Field {
+    operation: FieldOperation,
+    field_type: FieldType,
+    offset: u64,
+    overflow: Overflow,
+}
+
+/// Parses a field offset, multiplied by the width of the type when prefixed with `#`.
+fn field_offset(value: &str, field_type: FieldType) -> Result<u64, String> {
+    let (number, positional) = match value.strip_prefix('#') {
+        Some(number) => (number, true),
+        None => (value, false),
+    };
+    number
+        .parse::<u64>()
+        .ok()
+        .and_then(|offset| if positional { offset.checked_mul(field_type.bits as u64) } else { Some(offset) })
+        .filter(|offset| *offset <= MAX_BIT_OFFSET)
+        .ok_or_else(|| "bit offset is not an integer or out of range".to_string())
+}
+
+fn parse_fields(args: &[RespType], name: &str, read_only: bool) -> Result<Vec<Field>, String> {
+    let mut fields = Vec::new();
+    let mut overflow = Overflow::Wrap;
+    let mut index = 1;
+    while index < args.len() {
+        let subcommand = arg_str(args, index, name)?.to_uppercase();
+        if subcommand == "OVERFLOW" {
+            let policy = arg_str(args, index + 1, name).map_err(|_| "syntax error".to_string())?;
+            overflow = match policy.to_uppercase().as_str() {
+                "WRAP" => Overflow::Wrap,
+                "SAT" => Overflow::Sat,
+                "FAIL" => Overflow::Fail,
+                _ => return Err("Invalid OVERFLOW type specified".to_string()),
+            };
+            index += 2;
+            continue;
+        }
+        let arity = match subcommand.as_str() {
+            "GET" => 3,
+            "SET" | "INCRBY" => 4,
+            _ => return Err("syntax error".to_string()),
+        };
+        if index + arity > args.len() {
+            return Err("syntax error".to_string());
+        }
+        if read_only && subcommand != "GET" {
+            return Err("BITFIELD_RO only supports the GET subcommand".to_string());
+        }
+        let field_type = FieldType::parse(arg_str(args, index + 1, name)?)?;
+        let offset = field_offset(arg_str(args, index + 2, name)?, field_type)?;
+        let operation = match subcommand.as_str() {
+            "GET" => FieldOperation::Get,
+            "SET" => FieldOperation::Set(arg_i64(args, index + 3, name)?),
+            _ => FieldOperation::IncrBy(arg_i64(args, index + 3, name)?),
+        };
+        fields.push(Field {
+            operation,
+            field_type,
+            offset,
+            overflow,
+        });
+        index += arity;
+    }
+    Ok(fields)
+}
+
+fn bitfield(args: &[RespType], state: &mut dyn ServerState, name: &str, read_only: bool) -> Result<RespType, String> {
+    let key = arg_str(args, 0, name)?;
+    let fields = parse_fields(args, name, read_only)?;
+    let existing = bytes(state, key)?.is_some();
+
+    // As in Redis, the string grows to fit every written field before any is written.
+    let written_len = fields
+        .iter()
+        .filter(|field| !matches!(field.operation, FieldOperation::Get))
+        .map(|field| (field.offset + field.field_type.bits as u64).div_ceil(8) as usize)
+        .max();
+    let Some(written_len) = written_len else {
+        let value = bytes(state, key)?.unwrap_or_default();
+        let values = fields.iter().map(|field| RespType::Integer(field.field_type.read(value, field.offset) as i64));
+        return Ok(RespType::Array(values.collect()));
+    };
+    if !existing {
+        state.set(key.to_string(), Value::String(Vec::new()), None)?;
+    }
+    let Some(Value::String(value)) = state.get_mut(key) else {
+        return Err("Failed to create string".to_string());
+    };
+    if value.len() < written_len {
+        value.resize(written_len, 0);
+    }
+
+    let mut replies = Vec::new();
+    for field in &fields {
+        let (field_type, offset) = (field.field_type, field.offset);
+        let current = field_type.read(value, offset);
+        let (new_value, reply) = match field.operation {
+            FieldOperation::Get => {
+                replies.push(RespType::Integer(current as i64));
+                continue;
+            }
+            FieldOperation::Set(new_value) => {
+                let new_value = field_type.fit(new_value as i128, field.overflow);
+                (new_value, new_value.map(|_| current))
+            }
+            FieldOperation::IncrBy(increment) => {
+                let new_value = field_type.fit(current + increment as i128, field.overflow);
+                (new_value, new_value)
+            }
+        };
+        if let Some(new_value) = new_value {
+            field_type.write(value, offset, new_value);
+        }
+        replies.push(reply.map_or(RespType::BulkString(None), |reply| RespType::Integer(reply as i64)));
+    }
+    Ok(RespType::Array(replies))
+}
+
+/// `BITFIELD key [GET type offset | SET type offset value | INCRBY type offset increment |
+/// OVERFLOW WRAP | SAT | FAIL] ...`: reads and writes integers packed in a string. Types are
+/// `i1` to `i64` and `u1` to `u63`, and `#n` offsets count in fields of the type. Overflow
+/// policies apply to the `SET` and `INCRBY` that follow them; with `FAIL` overflowing
+/// writes are skipped and reply nil.
+pub struct BitField;
+
+impl Command for BitField {
+    fn name(&self) -> &str {
+        "BITFIELD"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        bitfield(args, state, self.name(), false)
+    }
+}
+
+/// `BITFIELD_RO key [GET type offset ...]`: the read-only variant of `BITFIELD`, which
+/// replicas serve.
+pub struct BitFieldRo;
+
+impl Command for BitFieldRo {
+    fn name(&self) -> &str {
+        "BITFIELD_RO"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        bitfield(args, state, self.name(), true)
+    }
+}
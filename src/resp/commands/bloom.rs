@@ -0,0 +1,202 @@
+//! Scalable Bloom filter commands. Adding to a missing key creates a filter with RedisBloom's
+//! defaults: a 1% error rate for 100 items, doubling in size when full.
+
+use crate::resp::bloom::{ScalableBloom, DEFAULT_CAPACITY, DEFAULT_ERROR_RATE, DEFAULT_EXPANSION};
+use crate::resp::commands::{arg_bytes, arg_str, first_key, Command};
+use crate::resp::protocol::RespType;
+use crate::resp::state::server_state::ServerState;
+use crate::resp::state::value::{Value, WRONG_TYPE};
+
+fn filter<'a>(state: &'a mut dyn ServerState, key: &str) -> Result<Option<&'a mut ScalableBloom>, String> {
+    match state.get_mut(key) {
+        Some(Value::Bloom(bloom)) => Ok(Some(bloom)),
+        Some(_) => Err(WRONG_TYPE.to_string()),
+        None => Ok(None),
+    }
+}
+
+/// The filter at `key`, created with the defaults when missing.
+fn filter_or_default<'a>(state: &'a mut dyn ServerState, key: &str) -> Result<&'a mut ScalableBloom, String> {
+    if filter(state, key)?.is_none() {
+        let bloom = ScalableBloom::new(DEFAULT_ERROR_RATE, DEFAULT_CAPACITY, DEFAULT_EXPANSION)?;
+        state.set(key.to_string(), Value::Bloom(bloom), None)?;
+    }
+    filter(state, key)?.ok_or_else(|| WRONG_TYPE.to_string())
+}
+
+/// Adds the items from `args[1..]`, with whether each one was added or why it could not be.
+fn add_all(args: &[RespType], state: &mut dyn ServerState, name: &str) -> Result<Vec<Result<bool, String>>, String> {
+    let key = arg_str(args, 0, name)?;
+    let items = (1..args.len()).map(|index| arg_bytes(args, index, name)).collect::<Result<Vec<_>, _>>()?;
+    let bloom = filter_or_default(state, key)?;
+    Ok(items.into_iter().map(|item| bloom.add(item)).collect())
+}
+
+/// Replies 1 for each item of `args[1..]` that may have been added.
+fn exists_all(args: &[RespType], state: &mut dyn ServerState, name: &str) -> Result<Vec<RespType>, String> {
+    let key = arg_str(args, 0, name)?;
+    let items = (1..args.len()).map(|index| arg_bytes(args, index, name)).collect::<Result<Vec<_>, _>>()?;
+    let bloom = filter(state, key)?;
+    let exists = |item: &[u8]| bloom.as_ref().is_some_and(|bloom| bloom.contains(item));
+    Ok(items.into_iter().map(|item| RespType::Integer(exists(item) as i64)).collect())
+}
+
+/// `BF.RESERVE key error_rate capacity [EXPANSION expansion] [NONSCALING]`: creates an empty
+/// filter for `capacity` items at the given false positive rate.
+pub struct BfReserve;
+
+impl Command for BfReserve {
+    fn name(&self) -> &str {
+        "BF.RESERVE"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() < 3 {
+            return Err("wrong number of arguments for 'bf.reserve' command".to_string());
+        }
+        let key = arg_str(args, 0, self.name())?;
+        let error_rate = arg_str(args, 1, self.name())?.parse::<f64>().map_err(|_| "bad error rate".to_string())?;
+        if !(error_rate > 0.0 && error_rate < 1.0) {
+            return Err("(0 < error rate range < 1)".to_string());
+        }
+        let capacity = arg_str(args, 2, self.name())?
+            .parse::<u64>()
+            .ok()
+            .filter(|capacity| *capacity > 0)
+            .ok_or_else(|| "(capacity should be larger than 0)".to_string())?;
+
+        let mut expansion = None;
+        let mut nonscaling = false;
+        let mut index = 3;
+        while index < args.len() {
+            let option = arg_str(args, index, self.name())?;
+            if option.eq_ignore_ascii_case("NONSCALING") {
+                nonscaling = true;
+                index += 1;
+            } else if option.eq_ignore_ascii_case("EXPANSION") {
+                let value = arg_str(args, index + 1, self.name())?;
+                let value = value.parse::<u64>().map_err(|_| "bad expansion".to_string())?;
+                if value < 1 {
+                    return Err("expansion should be greater or equal to 1".to_string());
+                }
+                expansion = Some(value);
+                index += 2;
+            } else {
+                return Err("syntax error".to_string());
+            }
+        }
+        if nonscaling && expansion.is_some() {
+            return Err("Nonscaling filters cannot expand".to_string());
+        }
+
+        if state.get(key).is_some() {
+            return Err("item exists".to_string());
+        }
+        let expansion = if nonscaling { 0 } else { expansion.unwrap_or(DEFAULT_EXPANSION) };
+        let bloom = ScalableBloom::new(error_rate, capacity, expansion)?;
+        state.set(key.to_string(), Value::Bloom(bloom), None)?;
+        Ok(RespType::SimpleString("OK".to_string()))
+    }
+}
+
+/// `BF.ADD key item`: returns 1 if the item was added, 0 if it may already be present.
+pub struct BfAdd;
+
+impl Command for BfAdd {
+    fn name(&self) -> &str {
+        "BF.ADD"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() != 2 {
+            return Err("wrong number of arguments for 'bf.add' command".to_string());
+        }
+        let added = add_all(args, state, self.name())?.remove(0)?;
+        Ok(RespType::Integer(added as i64))
+    }
+}
+
+/// `BF.MADD key item [item ...]`: `BF.ADD` for each item, with an error in place of the items
+/// that did not fit a full non-scaling filter.
+pub struct BfMAdd;
+
+impl Command for BfMAdd {
+    fn name(&self) -> &str {
+        "BF.MADD"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() < 2 {
+            return Err("wrong number of arguments for 'bf.madd' command".to_string());
+        }
+        let replies = add_all(args, state, self.name())?.into_iter().map(|added| match added {
+            Ok(added) => RespType::Integer(added as i64),
+            Err(error) => RespType::Error(error),
+        });
+        Ok(RespType::Array(replies.collect()))
+    }
+}
+
+/// `BF.EXISTS key item`: returns 1 if the item may have been added, 0 if it certainly was not.
+pub struct BfExists;
+
+impl Command for BfExists {
+    fn name(&self) -> &str {
+        "BF.EXISTS"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() != 2 {
+            return Err("wrong number of arguments for 'bf.exists' command".to_string());
+        }
+        Ok(exists_all(args, state, self.name())?.remove(0))
+    }
+}
+
+/// `BF.MEXISTS key item [item ...]`: `BF.EXISTS` for each item.
+pub struct BfMExists;
+
+impl Command for BfMExists {
+    fn name(&self) -> &str {
+        "BF.MEXISTS"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() < 2 {
+            return Err("wrong number of arguments for 'bf.mexists' command".to_string());
+        }
+        Ok(RespType::Array(exists_all(args, state, self.name())?))
+    }
+}
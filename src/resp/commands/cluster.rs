@@ -0,0 +1,368 @@
+use std::time::{Duration, Instant};
+
+use log::info;
+
+use crate::resp::client::{BlockedOn, ClientContext};
+use crate::resp::cluster::gossip::Heartbeat;
+use crate::resp::cluster::migrate::MigrateRequest;
+use crate::resp::cluster::{key_hash_slot, parse_slot_range, ClusterNode, ClusterState, CLUSTER_SLOTS};
+use crate::resp::commands::{arg_i64, arg_str, Command};
+use crate::resp::protocol::RespType;
+use crate::resp::replication::MasterAddr;
+use crate::resp::state::server_state::ServerState;
+
+/// `CLUSTER <subcommand>`, only available when running in cluster mode.
+pub struct Cluster;
+
+impl Command for Cluster {
+    fn name(&self) -> &str {
+        "CLUSTER"
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let subcommand = arg_str(args, 0, self.name())?.to_lowercase();
+
+        // Subcommands that need to look at the dataset.
+        match subcommand.as_str() {
+            "countkeysinslot" => {
+                let slot = slot_arg(args, 1)?;
+                cluster(state)?;
+                let count = state.keys().iter().filter(|key| key_hash_slot(key) == slot).count();
+                return Ok(RespType::Integer(count as i64));
+            }
+            "getkeysinslot" => {
+                let slot = slot_arg(args, 1)?;
+                let count = arg_i64(args, 2, self.name())?;
+                if count < 0 {
+                    return Err("Invalid number of keys".to_string());
+                }
+                cluster(state)?;
+                let mut keys = state
+                    .keys()
+                    .into_iter()
+                    .filter(|key| key_hash_slot(key) == slot)
+                    .collect::<Vec<_>>();
+                keys.sort();
+                keys.truncate(count as usize);
+                return Ok(RespType::Array(
+                    keys.into_iter().map(|key| RespType::BulkString(Some(key))).collect(),
+                ));
+            }
+            "setslot" => {
+                let slot = slot_arg(args, 1)?;
+                let keys_in_slot = state.keys().iter().filter(|key| key_hash_slot(key) == slot).count();
+                return set_slot(cluster(state)?, slot, &args[2..], keys_in_slot);
+            }
+            _ => {}
+        }
+
+        let cluster = cluster(state)?;
+        match subcommand.as_str() {
+            "info" => Ok(RespType::BulkString(Some(cluster.info()))),
+            "myid" => Ok(RespType::BulkString(Some(cluster.myself.clone()))),
+            "nodes" => Ok(RespType::BulkString(Some(cluster.nodes_description()))),
+            "keyslot" => {
+                let key = arg_str(args, 1, self.name())?;
+                Ok(RespType::Integer(key_hash_slot(key) as i64))
+            }
+            "slots" => Ok(RespType::Array(
+                cluster
+                    .slot_map()
+                    .into_iter()
+                    .map(|(start, end, owner)| {
+                        let mut entry = vec![
+                            RespType::Integer(start as i64),
+                            RespType::Integer(end as i64),
+                            node_endpoint(owner),
+                        ];
+                        entry.extend(cluster.replicas_of(&owner.id).into_iter().map(node_endpoint));
+                        RespType::Array(entry)
+                    })
+                    .collect(),
+            )),
+            "shards" => Ok(RespType::Array(
+                cluster
+                    .masters()
+                    .into_iter()
+                    .map(|master| {
+                        let slots = cluster
+                            .slots_of(&master.id)
+                            .into_iter()
+                            .flat_map(|(start, end)| [RespType::Integer(start as i64), RespType::Integer(end as i64)])
+                            .collect();
+                        let nodes = std::iter::once(master)
+                            .chain(cluster.replicas_of(&master.id))
+                            .map(shard_node)
+                            .collect();
+                        RespType::Array(vec![
+                            bulk("slots"),
+                            RespType::Array(slots),
+                            bulk("nodes"),
+                            RespType::Array(nodes),
+                        ])
+                    })
+                    .collect(),
+            )),
+            "meet" => {
+                let addr = MasterAddr {
+                    host: arg_str(args, 1, self.name())?.to_string(),
+                    port: u16::try_from(arg_i64(args, 2, self.name())?).map_err(|_| "Invalid node port".to_string())?,
+                };
+                let known = cluster.nodes.values().any(|node| node.addr == addr)
+                    || cluster.pending_meets.iter().any(|(pending, _)| *pending == addr);
+                if !known {
+                    cluster.pending_meets.push((addr, Instant::now()));
+                }
+                Ok(RespType::SimpleString("OK".to_string()))
+            }
+            "heartbeat" => {
+                let parts = (1..args.len())
+                    .map(|index| arg_str(args, index, self.name()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if cluster.receive_heartbeat(Heartbeat::parse(&parts)?) {
+                    cluster.save()?;
+                }
+                Ok(RespType::Array(
+                    cluster
+                        .heartbeat()
+                        .to_args()
+                        .into_iter()
+                        .map(|arg| RespType::BulkString(Some(arg)))
+                        .collect(),
+                ))
+            }
+            "addslots" | "delslots" | "addslotsrange" | "delslotsrange" => {
+                let slots = slot_list(&args[1..], subcommand.ends_with("range"))?;
+                let myself = cluster.myself.clone();
+                for slot in &slots {
+                    let owner = cluster.slot_owner(*slot).map(|node| node.id.clone());
+                    match (subcommand.starts_with("add"), owner) {
+                        (true, Some(_)) => return Err(format!("Slot {} is already busy", slot)),
+                        (false, None) => return Err(format!("Slot {} is already unassigned", slot)),
+                        _ => {}
+                    }
+                }
+                for slot in slots {
+                    if subcommand.starts_with("add") {
+                        cluster.assign(slot, &myself)?;
+                    } else {
+                        cluster.unassign(slot);
+                    }
+                }
+                cluster.save()?;
+                Ok(RespType::SimpleString("OK".to_string()))
+            }
+            _ => Err(format!("Unknown cluster subcommand '{}'", subcommand)),
+        }
+    }
+}
+
+/// `ASKING` lets the next command access a slot that is being imported.
+pub struct Asking;
+
+impl Command for Asking {
+    fn name(&self) -> &str {
+        "ASKING"
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        self.execute_with_client(args, state, &mut ClientContext::default())
+    }
+
+    fn execute_with_client(
+        &self,
+        _args: &[RespType],
+        state: &mut dyn ServerState,
+        client: &mut ClientContext,
+    ) -> Result<RespType, String> {
+        cluster(state)?;
+        client.asking = true;
+        Ok(RespType::SimpleString("OK".to_string()))
+    }
+}
+
+/// `MIGRATE host port key|"" destination-db timeout [COPY] [REPLACE] [KEYS key [key ...]]`
+pub struct Migrate;
+
+impl Command for Migrate {
+    fn name(&self) -> &str {
+        "MIGRATE"
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        self.execute_with_client(args, state, &mut ClientContext::default())
+    }
+
+    fn execute_with_client(
+        &self,
+        args: &[RespType],
+        _state: &mut dyn ServerState,
+        client: &mut ClientContext,
+    ) -> Result<RespType, String> {
+        let addr = MasterAddr {
+            host: arg_str(args, 0, self.name())?.to_string(),
+            port: u16::try_from(arg_i64(args, 1, self.name())?).map_err(|_| "Invalid port".to_string())?,
+        };
+        let key = arg_str(args, 2, self.name())?;
+        if arg_i64(args, 3, self.name())? != 0 {
+            return Err("MIGRATE only supports database 0".to_string());
+        }
+        let timeout = match arg_i64(args, 4, self.name())? {
+            timeout if timeout <= 0 => Duration::from_secs(1),
+            timeout => Duration::from_millis(timeout as u64),
+        };
+
+        let mut copy = false;
+        let mut replace = false;
+        let mut keys = Vec::new();
+        let mut index = 5;
+        while index < args.len() {
+            match arg_str(args, index, self.name())?.to_uppercase().as_str() {
+                "COPY" => copy = true,
+                "REPLACE" => replace = true,
+                "KEYS" => {
+                    if !key.is_empty() {
+                        return Err("When using MIGRATE KEYS option, the key argument must be set to the empty string".to_string());
+                    }
+                    for key_index in index + 1..args.len() {
+                        keys.push(arg_str(args, key_index, self.name())?.to_string());
+                    }
+                    break;
+                }
+                _ => return Err("syntax error".to_string()),
+            }
+            index += 1;
+        }
+        if keys.is_empty() {
+            if key.is_empty() {
+                return Err("MIGRATE requires a key or the KEYS option".to_string());
+            }
+            keys.push(key.to_string());
+        }
+
+        client.blocked = Some(BlockedOn::Migrate(MigrateRequest {
+            addr,
+            keys,
+            timeout,
+            copy,
+            replace,
+        }));
+        Ok(RespType::SimpleString("OK".to_string()))
+    }
+}
+
+/// `CLUSTER SETSLOT <slot> MIGRATING|IMPORTING|NODE <id>` and `CLUSTER SETSLOT <slot> STABLE`.
+fn set_slot(cluster: &mut ClusterState, slot: u16, args: &[RespType], keys_in_slot: usize) -> Result<RespType, String> {
+    let action = arg_str(args, 0, "CLUSTER")?.to_lowercase();
+    if action == "stable" {
+        cluster.migrating.remove(&slot);
+        cluster.importing.remove(&slot);
+        return Ok(RespType::SimpleString("OK".to_string()));
+    }
+
+    let id = arg_str(args, 1, "CLUSTER")?.to_string();
+    if cluster.node(&id).is_none() {
+        return Err(format!("I don't know about node {}", id));
+    }
+    let owned = cluster.slot_owner(slot).is_some_and(|owner| owner.id == cluster.myself);
+
+    match action.as_str() {
+        "migrating" => {
+            if !owned {
+                return Err(format!("I'm not the owner of hash slot {}", slot));
+            }
+            cluster.migrating.insert(slot, id);
+        }
+        "importing" => {
+            if owned {
+                return Err(format!("I'm already the owner of hash slot {}", slot));
+            }
+            cluster.importing.insert(slot, id);
+        }
+        "node" => {
+            if id == cluster.myself {
+                // Closing an import: claim the slot with a new epoch so the rest of the cluster follows.
+                if cluster.importing.remove(&slot).is_some() {
+                    let epoch = cluster.bump_epoch();
+                    info!("Imported slot {}, moved to config epoch {}", slot, epoch);
+                }
+            } else {
+                if owned && keys_in_slot > 0 {
+                    return Err(format!(
+                        "Can't assign hashslot {} to a different node while I still hold keys for this hash slot.",
+                        slot
+                    ));
+                }
+                cluster.migrating.remove(&slot);
+            }
+            cluster.assign(slot, &id)?;
+            cluster.save()?;
+        }
+        _ => return Err("Invalid CLUSTER SETSLOT action or number of arguments".to_string()),
+    }
+    Ok(RespType::SimpleString("OK".to_string()))
+}
+
+fn cluster(state: &mut dyn ServerState) -> Result<&mut ClusterState, String> {
+    state
+        .cluster()
+        .ok_or_else(|| "This instance has cluster support disabled".to_string())
+}
+
+fn bulk(value: &str) -> RespType {
+    RespType::BulkString(Some(value.to_string()))
+}
+
+fn node_endpoint(node: &ClusterNode) -> RespType {
+    RespType::Array(vec![
+        bulk(&node.addr.host),
+        RespType::Integer(node.addr.port as i64),
+        bulk(&node.id),
+    ])
+}
+
+fn shard_node(node: &ClusterNode) -> RespType {
+    RespType::Array(vec![
+        bulk("id"),
+        bulk(&node.id),
+        bulk("port"),
+        RespType::Integer(node.addr.port as i64),
+        bulk("ip"),
+        bulk(&node.addr.host),
+        bulk("endpoint"),
+        bulk(&node.addr.host),
+        bulk("role"),
+        bulk(if node.master_id.is_some() { "replica" } else { "master" }),
+        bulk("replication-offset"),
+        RespType::Integer(0),
+        bulk("health"),
+        bulk("online"),
+    ])
+}
+
+pub(crate) fn slot_arg(args: &[RespType], index: usize) -> Result<u16, String> {
+    u16::try_from(arg_i64(args, index, "CLUSTER")?)
+        .ok()
+        .filter(|slot| (*slot as usize) < CLUSTER_SLOTS)
+        .ok_or_else(|| "Invalid or out of range slot".to_string())
+}
+
+/// Reads the slots of `ADDSLOTS`/`DELSLOTS`, or the start/end pairs of their `RANGE` variants.
+fn slot_list(args: &[RespType], ranges: bool) -> Result<Vec<u16>, String> {
+    if args.is_empty() || (ranges && !args.len().is_multiple_of(2)) {
+        return Err("wrong number of arguments for 'cluster' command".to_string());
+    }
+    let mut slots = Vec::new();
+    if ranges {
+        for pair in (0..args.len()).step_by(2) {
+            let range = format!("{}-{}", slot_arg(args, pair)?, slot_arg(args, pair + 1)?);
+            let (start, end) = parse_slot_range(&range)?;
+            slots.extend(start..=end);
+        }
+    } else {
+        for index in 0..args.len() {
+            slots.push(slot_arg(args, index)?);
+        }
+    }
+    Ok(slots)
+}
@@ -0,0 +1,197 @@
+//! Count-Min Sketch commands. Sketches are created explicitly with their dimensions.
+
+use crate::resp::cms::CountMinSketch;
+use crate::resp::commands::{arg_bytes, arg_i64, arg_str, first_key, numkeys_keys, Command};
+use crate::resp::protocol::RespType;
+use crate::resp::state::server_state::ServerState;
+use crate::resp::state::value::{Value, WRONG_TYPE};
+
+const NO_KEY: &str = "CMS: key does not exist";
+
+fn sketch<'a>(state: &'a mut dyn ServerState, key: &str) -> Result<&'a mut CountMinSketch, String> {
+    match state.get_mut(key) {
+        Some(Value::CountMinSketch(sketch)) => Ok(sketch),
+        Some(_) => Err(WRONG_TYPE.to_string()),
+        None => Err(NO_KEY.to_string()),
+    }
+}
+
+/// Stores a new sketch at `key`, which must not exist yet.
+fn create(state: &mut dyn ServerState, key: &str, width: u64, depth: u64) -> Result<RespType, String> {
+    if state.get(key).is_some() {
+        return Err("CMS: key already exists".to_string());
+    }
+    let sketch = CountMinSketch::new(width, depth)?;
+    state.set(key.to_string(), Value::CountMinSketch(sketch), None)?;
+    Ok(RespType::SimpleString("OK".to_string()))
+}
+
+/// `CMS.INITBYDIM key width depth`: creates a sketch of `depth` rows of `width` counters.
+pub struct CmsInitByDim;
+
+impl Command for CmsInitByDim {
+    fn name(&self) -> &str {
+        "CMS.INITBYDIM"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() != 3 {
+            return Err("wrong number of arguments for 'cms.initbydim' command".to_string());
+        }
+        let key = arg_str(args, 0, self.name())?;
+        let dimension = |index: usize, error: &str| {
+            let value = arg_str(args, index, self.name())?.parse::<u64>().ok();
+            value.filter(|value| *value > 0).ok_or_else(|| error.to_string())
+        };
+        let width = dimension(1, "CMS: invalid width")?;
+        let depth = dimension(2, "CMS: invalid depth")?;
+        create(state, key, width, depth)
+    }
+}
+
+/// `CMS.INITBYPROB key error probability`: creates a sketch overestimating counts by at most
+/// `error` times the total count, except with the given probability.
+pub struct CmsInitByProb;
+
+impl Command for CmsInitByProb {
+    fn name(&self) -> &str {
+        "CMS.INITBYPROB"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() != 3 {
+            return Err("wrong number of arguments for 'cms.initbyprob' command".to_string());
+        }
+        let key = arg_str(args, 0, self.name())?;
+        let rate = |index: usize, error: &str| {
+            let value = arg_str(args, index, self.name())?.parse::<f64>().ok();
+            value.filter(|value| *value > 0.0 && *value < 1.0).ok_or_else(|| error.to_string())
+        };
+        let error = rate(1, "CMS: invalid overestimation value")?;
+        let probability = rate(2, "CMS: invalid prob value")?;
+        let (width, depth) = CountMinSketch::dimensions(error, probability);
+        create(state, key, width, depth)
+    }
+}
+
+/// `CMS.INCRBY key item increment [item increment ...]`: counts more occurrences of the items,
+/// replying with their new counts. Nothing is counted if a counter would overflow.
+pub struct CmsIncrBy;
+
+impl Command for CmsIncrBy {
+    fn name(&self) -> &str {
+        "CMS.INCRBY"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() < 3 || args.len().is_multiple_of(2) {
+            return Err("wrong number of arguments for 'cms.incrby' command".to_string());
+        }
+        let key = arg_str(args, 0, self.name())?;
+        let mut increments = Vec::new();
+        for index in (1..args.len()).step_by(2) {
+            let increment = arg_str(args, index + 1, self.name())?.parse::<u32>();
+            let increment = increment.map_err(|_| "CMS: Cannot parse number".to_string())?;
+            increments.push((arg_bytes(args, index, self.name())?, increment));
+        }
+
+        let counts = sketch(state, key)?.increment(&increments)?;
+        Ok(RespType::Array(counts.into_iter().map(|count| RespType::Integer(count as i64)).collect()))
+    }
+}
+
+/// `CMS.QUERY key item [item ...]`: the estimated counts of the items.
+pub struct CmsQuery;
+
+impl Command for CmsQuery {
+    fn name(&self) -> &str {
+        "CMS.QUERY"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() < 2 {
+            return Err("wrong number of arguments for 'cms.query' command".to_string());
+        }
+        let key = arg_str(args, 0, self.name())?;
+        let items = (1..args.len()).map(|index| arg_bytes(args, index, self.name())).collect::<Result<Vec<_>, _>>()?;
+        let sketch = sketch(state, key)?;
+        Ok(RespType::Array(items.into_iter().map(|item| RespType::Integer(sketch.query(item) as i64)).collect()))
+    }
+}
+
+/// `CMS.MERGE destination numkeys source [source ...] [WEIGHTS weight [weight ...]]`: sets the
+/// counters of the destination, which must exist, to the weighted sum of the sources'.
+pub struct CmsMerge;
+
+impl Command for CmsMerge {
+    fn name(&self) -> &str {
+        "CMS.MERGE"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        let mut keys = first_key(args);
+        keys.extend(numkeys_keys(args, 1, self.name()));
+        keys
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() < 3 {
+            return Err("wrong number of arguments for 'cms.merge' command".to_string());
+        }
+        let destination = arg_str(args, 0, self.name())?;
+        let numkeys = arg_i64(args, 1, self.name())
+            .ok()
+            .filter(|numkeys| *numkeys > 0 && (*numkeys as usize) < args.len() - 1)
+            .ok_or_else(|| "CMS: invalid numkeys".to_string())? as usize;
+        let weights_at = numkeys + 2;
+        let weights = match args.get(weights_at).map(|_| arg_str(args, weights_at, self.name())).transpose()? {
+            None => vec![1; numkeys],
+            Some(option) if option.eq_ignore_ascii_case("WEIGHTS") && args.len() == weights_at + 1 + numkeys => {
+                (weights_at + 1..args.len())
+                    .map(|index| arg_i64(args, index, self.name()).map_err(|_| "CMS: invalid weight value".to_string()))
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+            Some(_) => return Err("CMS: wrong number of keys/weights".to_string()),
+        };
+
+        let mut sources = Vec::new();
+        for index in 2..weights_at {
+            sources.push(sketch(state, arg_str(args, index, self.name())?)?.clone());
+        }
+        let sources = sources.iter().zip(weights).collect::<Vec<_>>();
+        sketch(state, destination)?.merge(&sources)?;
+        Ok(RespType::SimpleString("OK".to_string()))
+    }
+}
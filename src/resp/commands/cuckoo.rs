@@ -0,0 +1,165 @@
+//! Cuckoo filter commands. Unlike Bloom filters, items can be deleted. `CF.ADD` on a missing
+//! key creates a filter with RedisBloom's defaults: 1024 items in buckets of 2.
+
+use crate::resp::commands::{arg_bytes, arg_str, first_key, Command};
+use crate::resp::cuckoo::{
+    CuckooFilter, DEFAULT_BUCKET_SIZE, DEFAULT_CAPACITY, DEFAULT_EXPANSION, DEFAULT_MAX_ITERATIONS,
+};
+use crate::resp::protocol::RespType;
+use crate::resp::state::server_state::ServerState;
+use crate::resp::state::value::{Value, WRONG_TYPE};
+
+fn filter<'a>(state: &'a mut dyn ServerState, key: &str) -> Result<Option<&'a mut CuckooFilter>, String> {
+    match state.get_mut(key) {
+        Some(Value::Cuckoo(cuckoo)) => Ok(Some(cuckoo)),
+        Some(_) => Err(WRONG_TYPE.to_string()),
+        None => Ok(None),
+    }
+}
+
+/// Parses the integer option at `index`, failing with `error` outside `range`.
+fn option_arg(
+    args: &[RespType],
+    index: usize,
+    name: &str,
+    range: std::ops::RangeInclusive<u64>,
+    error: &str,
+) -> Result<u64, String> {
+    let value = arg_str(args, index, name)?.parse::<u64>().ok();
+    value.filter(|value| range.contains(value)).ok_or_else(|| error.to_string())
+}
+
+/// `CF.RESERVE key capacity [BUCKETSIZE size] [MAXITERATIONS iterations] [EXPANSION expansion]`:
+/// creates an empty filter for about `capacity` items. Full filters get one `expansion` times
+/// larger chained after them, unless it is 0.
+pub struct CfReserve;
+
+impl Command for CfReserve {
+    fn name(&self) -> &str {
+        "CF.RESERVE"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() < 2 || !args.len().is_multiple_of(2) {
+            return Err("wrong number of arguments for 'cf.reserve' command".to_string());
+        }
+        let key = arg_str(args, 0, self.name())?;
+        let capacity = option_arg(args, 1, self.name(), 1..=u64::MAX, "Bad capacity")?;
+        let mut bucket_size = DEFAULT_BUCKET_SIZE;
+        let mut max_iterations = DEFAULT_MAX_ITERATIONS;
+        let mut expansion = DEFAULT_EXPANSION;
+        for index in (2..args.len()).step_by(2) {
+            let option = arg_str(args, index, self.name())?.to_uppercase();
+            match option.as_str() {
+                "BUCKETSIZE" => bucket_size = option_arg(args, index + 1, self.name(), 1..=255, "Bad bucket size")?,
+                "MAXITERATIONS" => {
+                    max_iterations = option_arg(args, index + 1, self.name(), 1..=65535, "Bad maxIterations")?
+                }
+                "EXPANSION" => expansion = option_arg(args, index + 1, self.name(), 0..=32768, "Bad expansion")?,
+                _ => return Err("syntax error".to_string()),
+            }
+        }
+        if capacity < bucket_size * 2 {
+            return Err("Capacity must be at least (BucketSize * 2)".to_string());
+        }
+
+        if state.get(key).is_some() {
+            return Err("item exists".to_string());
+        }
+        let cuckoo = CuckooFilter::new(capacity, bucket_size, max_iterations, expansion)?;
+        state.set(key.to_string(), Value::Cuckoo(cuckoo), None)?;
+        Ok(RespType::SimpleString("OK".to_string()))
+    }
+}
+
+/// `CF.ADD key item`: adds the item, even if it is already present, and returns 1.
+pub struct CfAdd;
+
+impl Command for CfAdd {
+    fn name(&self) -> &str {
+        "CF.ADD"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() != 2 {
+            return Err("wrong number of arguments for 'cf.add' command".to_string());
+        }
+        let key = arg_str(args, 0, self.name())?;
+        let item = arg_bytes(args, 1, self.name())?;
+        if filter(state, key)?.is_none() {
+            let cuckoo =
+                CuckooFilter::new(DEFAULT_CAPACITY, DEFAULT_BUCKET_SIZE, DEFAULT_MAX_ITERATIONS, DEFAULT_EXPANSION)?;
+            state.set(key.to_string(), Value::Cuckoo(cuckoo), None)?;
+        }
+        let cuckoo = filter(state, key)?.ok_or_else(|| WRONG_TYPE.to_string())?;
+        cuckoo.add(item)?;
+        Ok(RespType::Integer(1))
+    }
+}
+
+/// `CF.DEL key item`: removes one occurrence of the item, returning 1 if it may have been
+/// present.
+pub struct CfDel;
+
+impl Command for CfDel {
+    fn name(&self) -> &str {
+        "CF.DEL"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() != 2 {
+            return Err("wrong number of arguments for 'cf.del' command".to_string());
+        }
+        let key = arg_str(args, 0, self.name())?;
+        let item = arg_bytes(args, 1, self.name())?;
+        let cuckoo = filter(state, key)?.ok_or_else(|| "Not found".to_string())?;
+        Ok(RespType::Integer(cuckoo.delete(item) as i64))
+    }
+}
+
+/// `CF.EXISTS key item`: returns 1 if the item may have been added, 0 if it certainly was not.
+pub struct CfExists;
+
+impl Command for CfExists {
+    fn name(&self) -> &str {
+        "CF.EXISTS"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() != 2 {
+            return Err("wrong number of arguments for 'cf.exists' command".to_string());
+        }
+        let key = arg_str(args, 0, self.name())?;
+        let item = arg_bytes(args, 1, self.name())?;
+        let exists = filter(state, key)?.is_some_and(|cuckoo| cuckoo.contains(item));
+        Ok(RespType::Integer(exists as i64))
+    }
+}
@@ -0,0 +1,476 @@
+//! Geospatial commands. Positions are members of a sorted set scored by their 52-bit
+//! geohash, so the sorted set commands work on them as well.
+
+use crate::resp::commands::sorted_set::{zset, zset_or_create};
+use crate::resp::commands::{arg_i64, arg_str, first_key, Command};
+use crate::resp::geohash;
+use crate::resp::protocol::RespType;
+use crate::resp::state::server_state::ServerState;
+use crate::resp::state::sorted_set::SortedSet;
+use crate::resp::state::value::Value;
+
+fn bulk(value: &str) -> RespType {
+    RespType::BulkString(Some(value.to_string()))
+}
+
+fn float_arg(args: &[RespType], index: usize, name: &str, error: &str) -> Result<f64, String> {
+    arg_str(args, index, name)?
+        .parse::<f64>()
+        .ok()
+        .filter(|value| !value.is_nan())
+        .ok_or_else(|| error.to_string())
+}
+
+/// Parses a `longitude latitude` pair within the range geohashes cover.
+fn position_arg(args: &[RespType], index: usize, name: &str) -> Result<(f64, f64), String> {
+    let longitude = float_arg(args, index, name, "value is not a valid float")?;
+    let latitude = float_arg(args, index + 1, name, "value is not a valid float")?;
+    if !geohash::is_valid(longitude, latitude) {
+        return Err(format!("invalid longitude,latitude pair {:.6},{:.6}", longitude, latitude));
+    }
+    Ok((longitude, latitude))
+}
+
+/// Meters per unit of `m`, `km`, `ft` or `mi`.
+fn unit_arg(args: &[RespType], index: usize, name: &str) -> Result<f64, String> {
+    match arg_str(args, index, name)?.to_lowercase().as_str() {
+        "m" => Ok(1.0),
+        "km" => Ok(1000.0),
+        "ft" => Ok(0.3048),
+        "mi" => Ok(1609.34),
+        _ => Err("unsupported unit provided. please use M, KM, FT, MI".to_string()),
+    }
+}
+
+fn position(zset: &SortedSet, member: &str) -> Option<(f64, f64)> {
+    zset.score(member).map(|score| geohash::decode(score as u64))
+}
+
+fn distance_reply(meters: f64, unit: f64) -> RespType {
+    bulk(&format!("{:.4}", meters / unit))
+}
+
+fn position_reply((longitude, latitude): (f64, f64)) -> RespType {
+    RespType::Array(vec![bulk(&longitude.to_string()), bulk(&latitude.to_string())])
+}
+
+/// `GEOADD key [NX | XX] [CH] longitude latitude member [longitude latitude member ...]`:
+/// returns the number of members added, or also updated with `CH`.
+pub struct GeoAdd;
+
+impl Command for GeoAdd {
+    fn name(&self) -> &str {
+        "GEOADD"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let (mut nx, mut xx, mut ch) = (false, false, false);
+        let mut index = 1;
+        while index < args.len() {
+            match arg_str(args, index, self.name())?.to_uppercase().as_str() {
+                "NX" => nx = true,
+                "XX" => xx = true,
+                "CH" => ch = true,
+                _ => break,
+            }
+            index += 1;
+        }
+        let remaining = args.len() - index;
+        if remaining == 0 || !remaining.is_multiple_of(3) || (nx && xx) {
+            return Err("syntax error".to_string());
+        }
+        let members = (index..args.len())
+            .step_by(3)
+            .map(|index| {
+                let (longitude, latitude) = position_arg(args, index, self.name())?;
+                Ok((geohash::encode(longitude, latitude) as f64, arg_str(args, index + 2, self.name())?))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        if xx && zset(state, key)?.is_none() {
+            return Ok(RespType::Integer(0));
+        }
+        let zset = zset_or_create(state, key)?;
+        let (mut added, mut changed) = (0, 0);
+        for (score, member) in members {
+            match zset.score(member) {
+                None if !xx => {
+                    zset.insert(member.to_string(), score);
+                    added += 1;
+                }
+                Some(current) if !nx && current != score => {
+                    zset.insert(member.to_string(), score);
+                    changed += 1;
+                }
+                _ => {}
+            }
+        }
+        if zset.is_empty() {
+            state.del(key)?;
+        }
+        Ok(RespType::Integer(if ch { added + changed } else { added }))
+    }
+}
+
+/// `GEOPOS key [member ...]`: the position of each member, nil for missing ones.
+pub struct GeoPos;
+
+impl Command for GeoPos {
+    fn name(&self) -> &str {
+        "GEOPOS"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let zset = zset(state, key)?;
+        let positions = (1..args.len())
+            .map(|index| {
+                let member = arg_str(args, index, self.name())?;
+                Ok(match zset.as_ref().and_then(|zset| position(zset, member)) {
+                    Some(position) => position_reply(position),
+                    None => RespType::NullArray,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(RespType::Array(positions))
+    }
+}
+
+/// `GEODIST key member1 member2 [M | KM | FT | MI]`: the distance between two members, nil
+/// if either is missing.
+pub struct GeoDist;
+
+impl Command for GeoDist {
+    fn name(&self) -> &str {
+        "GEODIST"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        match args.len() {
+            3 | 4 => {}
+            len if len < 3 => return Err("wrong number of arguments for 'geodist' command".to_string()),
+            _ => return Err("syntax error".to_string()),
+        }
+        let key = arg_str(args, 0, self.name())?;
+        let (first, second) = (arg_str(args, 1, self.name())?, arg_str(args, 2, self.name())?);
+        let unit = if args.len() == 4 { unit_arg(args, 3, self.name())? } else { 1.0 };
+        let Some(zset) = zset(state, key)? else {
+            return Ok(RespType::BulkString(None));
+        };
+        match (position(zset, first), position(zset, second)) {
+            (Some(first), Some(second)) => Ok(distance_reply(geohash::distance(first, second), unit)),
+            _ => Ok(RespType::BulkString(None)),
+        }
+    }
+}
+
+/// `GEOHASH key [member ...]`: the standard geohash string of each member, nil for missing ones.
+pub struct GeoHash;
+
+impl Command for GeoHash {
+    fn name(&self) -> &str {
+        "GEOHASH"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let zset = zset(state, key)?;
+        let hashes = (1..args.len())
+            .map(|index| {
+                let member = arg_str(args, index, self.name())?;
+                Ok(match zset.as_ref().and_then(|zset| position(zset, member)) {
+                    Some((longitude, latitude)) => bulk(&geohash::to_string(longitude, latitude)),
+                    None => RespType::BulkString(None),
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(RespType::Array(hashes))
+    }
+}
+
+enum Origin {
+    Member(String),
+    Position(f64, f64),
+}
+
+/// Area searched around the origin, in meters.
+enum Shape {
+    Radius(f64),
+    Box { width: f64, height: f64 },
+}
+
+#[derive(Default)]
+struct SearchOptions {
+    origin: Option<Origin>,
+    shape: Option<Shape>,
+    /// Meters per unit of the shape, in which distances are also reported.
+    unit: f64,
+    /// `Some(true)` for `DESC`.
+    descending: Option<bool>,
+    count: Option<usize>,
+    any: bool,
+    with_coord: bool,
+    with_dist: bool,
+    with_hash: bool,
+    store_dist: bool,
+}
+
+fn search_options(args: &[RespType], mut index: usize, name: &str, store: bool) -> Result<SearchOptions, String> {
+    let mut options = SearchOptions::default();
+    let (mut origins, mut shapes) = (0, 0);
+    while index < args.len() {
+        let option = arg_str(args, index, name)?.to_uppercase();
+        let arity = match option.as_str() {
+            "FROMMEMBER" => {
+                options.origin = Some(Origin::Member(arg_str(args, index + 1, name)?.to_string()));
+                origins += 1;
+                2
+            }
+            "FROMLONLAT" => {
+                let (longitude, latitude) = position_arg(args, index + 1, name)?;
+                options.origin = Some(Origin::Position(longitude, latitude));
+                origins += 1;
+                3
+            }
+            "BYRADIUS" => {
+                let radius = float_arg(args, index + 1, name, "need numeric radius")?;
+                if radius < 0.0 {
+                    return Err("radius cannot be negative".to_string());
+                }
+                options.unit = unit_arg(args, index + 2, name)?;
+                options.shape = Some(Shape::Radius(radius * options.unit));
+                shapes += 1;
+                3
+            }
+            "BYBOX" => {
+                let width = float_arg(args, index + 1, name, "value is not a valid float")?;
+                let height = float_arg(args, index + 2, name, "value is not a valid float")?;
+                if width < 0.0 || height < 0.0 {
+                    return Err("height or width cannot be negative".to_string());
+                }
+                options.unit = unit_arg(args, index + 3, name)?;
+                options.shape = Some(Shape::Box {
+                    width: width * options.unit,
+                    height: height * options.unit,
+                });
+                shapes += 1;
+                4
+            }
+            "ASC" => {
+                options.descending = Some(false);
+                1
+            }
+            "DESC" => {
+                options.descending = Some(true);
+                1
+            }
+            "COUNT" => {
+                let count = arg_i64(args, index + 1, name)?;
+                if count <= 0 {
+                    return Err("COUNT must be > 0".to_string());
+                }
+                options.count = Some(count as usize);
+                if arg_str(args, index + 2, name).is_ok_and(|any| any.eq_ignore_ascii_case("ANY")) {
+                    options.any = true;
+                    3
+                } else {
+                    2
+                }
+            }
+            "ANY" => return Err("the ANY argument requires COUNT argument".to_string()),
+            "WITHCOORD" => {
+                options.with_coord = true;
+                1
+            }
+            "WITHDIST" => {
+                options.with_dist = true;
+                1
+            }
+            "WITHHASH" => {
+                options.with_hash = true;
+                1
+            }
+            "STOREDIST" if store => {
+                options.store_dist = true;
+                1
+            }
+            _ => return Err("syntax error".to_string()),
+        };
+        index += arity;
+    }
+
+    if origins != 1 {
+        return Err(format!("exactly one of FROMMEMBER or FROMLONLAT can be specified for {}", name));
+    }
+    if shapes != 1 {
+        return Err(format!("exactly one of BYRADIUS and BYBOX can be specified for {}", name));
+    }
+    if store && (options.with_coord || options.with_dist || options.with_hash) {
+        return Err(format!("{} is not compatible with WITHDIST, WITHHASH and WITHCOORD options", name));
+    }
+    Ok(options)
+}
+
+struct Found {
+    member: String,
+    hash: u64,
+    position: (f64, f64),
+    /// Distance from the origin in meters.
+    distance: f64,
+}
+
+/// Members of the sorted set at `key` within the shape, sorted as requested. `COUNT` without
+/// `ANY` sorts by increasing distance, as Redis does.
+fn search(state: &mut dyn ServerState, key: &str, options: &SearchOptions) -> Result<Vec<Found>, String> {
+    let Some(zset) = zset(state, key)? else {
+        return Ok(Vec::new());
+    };
+    let origin = match &options.origin {
+        Some(Origin::Member(member)) => {
+            position(zset, member).ok_or_else(|| "could not decode requested zset member".to_string())?
+        }
+        Some(Origin::Position(longitude, latitude)) => (*longitude, *latitude),
+        None => return Ok(Vec::new()),
+    };
+
+    let mut found = Vec::new();
+    for (member, score) in zset.iter() {
+        if options.any && options.count.is_some_and(|count| found.len() >= count) {
+            break;
+        }
+        let hash = score as u64;
+        let position = geohash::decode(hash);
+        let distance = geohash::distance(origin, position);
+        let within = match options.shape {
+            Some(Shape::Radius(radius)) => distance <= radius,
+            Some(Shape::Box { width, height }) => {
+                geohash::latitude_distance(position.1, origin.1) <= height / 2.0
+                    && geohash::distance(position, (origin.0, position.1)) <= width / 2.0
+            }
+            None => false,
+        };
+        if within {
+            found.push(Found {
+                member: member.clone(),
+                hash,
+                position,
+                distance,
+            });
+        }
+    }
+
+    let descending = match options.descending {
+        None if options.count.is_some() && !options.any => Some(false),
+        descending => descending,
+    };
+    if let Some(descending) = descending {
+        found.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        if descending {
+            found.reverse();
+        }
+    }
+    if let Some(count) = options.count {
+        found.truncate(count);
+    }
+    Ok(found)
+}
+
+/// `GEOSEARCH key <FROMMEMBER member | FROMLONLAT longitude latitude> <BYRADIUS radius unit |
+/// BYBOX width height unit> [ASC | DESC] [COUNT count [ANY]] [WITHCOORD] [WITHDIST] [WITHHASH]`:
+/// returns the matching members, each as `[member, distance, hash, [longitude, latitude]]`
+/// when any of the `WITH` options is given.
+pub struct GeoSearch;
+
+impl Command for GeoSearch {
+    fn name(&self) -> &str {
+        "GEOSEARCH"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let options = search_options(args, 1, self.name(), false)?;
+        let found = search(state, key, &options)?;
+        let detailed = options.with_coord || options.with_dist || options.with_hash;
+        let replies = found.into_iter().map(|found| {
+            if !detailed {
+                return RespType::BulkString(Some(found.member));
+            }
+            let mut reply = vec![RespType::BulkString(Some(found.member))];
+            if options.with_dist {
+                reply.push(distance_reply(found.distance, options.unit));
+            }
+            if options.with_hash {
+                reply.push(RespType::Integer(found.hash as i64));
+            }
+            if options.with_coord {
+                reply.push(position_reply(found.position));
+            }
+            RespType::Array(reply)
+        });
+        Ok(RespType::Array(replies.collect()))
+    }
+}
+
+/// `GEOSEARCHSTORE destination source ... [STOREDIST]`: stores the members `GEOSEARCH` would
+/// return, scored by their geohash or, with `STOREDIST`, by their distance in the unit of the
+/// search. Returns their number, deleting `destination` when there are none.
+pub struct GeoSearchStore;
+
+impl Command for GeoSearchStore {
+    fn name(&self) -> &str {
+        "GEOSEARCHSTORE"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        (0..args.len().min(2)).filter_map(|index| arg_str(args, index, self.name()).ok()).collect()
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let destination = arg_str(args, 0, self.name())?;
+        let source = arg_str(args, 1, self.name())?;
+        let options = search_options(args, 2, self.name(), true)?;
+        let found = search(state, source, &options)?;
+        let zset = found
+            .into_iter()
+            .map(|found| {
+                let score = if options.store_dist { found.distance / options.unit } else { found.hash as f64 };
+                (found.member, score)
+            })
+            .collect::<SortedSet>();
+        let len = zset.len();
+        state.del(destination)?;
+        if len > 0 {
+            state.set(destination.to_string(), Value::SortedSet(zset), None)?;
+        }
+        Ok(RespType::Integer(len as i64))
+    }
+}
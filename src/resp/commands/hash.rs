@@ -0,0 +1,817 @@
+//! Hash commands. Hashes are deleted as soon as their last field is removed, including
+//! when it expires.
+
+use std::collections::HashMap;
+
+use crate::resp::commands::scan::{scan_args, scan_page};
+use crate::resp::commands::{arg_i64, arg_str, current_time_ms, first_key, propagate, Command};
+use crate::resp::protocol::RespType;
+use crate::resp::random::{random_below, sample};
+use crate::resp::state::server_state::ServerState;
+use crate::resp::state::value::{Value, WRONG_TYPE};
+
+fn hash<'a>(state: &'a mut dyn ServerState, key: &str) -> Result<Option<&'a mut HashMap<String, String>>, String> {
+    match state.get_mut(key) {
+        Some(Value::Hash(hash)) => Ok(Some(hash)),
+        Some(_) => Err(WRONG_TYPE.to_string()),
+        None => Ok(None),
+    }
+}
+
+/// Returns the hash stored at `key`, creating an empty one when the key does not exist.
+fn hash_or_create<'a>(state: &'a mut dyn ServerState, key: &str) -> Result<&'a mut HashMap<String, String>, String> {
+    if hash(state, key)?.is_none() {
+        state.set(key.to_string(), Value::Hash(HashMap::new()), None)?;
+    }
+    hash(state, key)?.ok_or_else(|| "Failed to create hash".to_string())
+}
+
+fn delete_if_empty(state: &mut dyn ServerState, key: &str) -> Result<(), String> {
+    if matches!(state.get(key), Some(Value::Hash(hash)) if hash.is_empty()) {
+        state.del(key)?;
+    }
+    Ok(())
+}
+
+fn bulk(value: &str) -> RespType {
+    RespType::BulkString(Some(value.to_string()))
+}
+
+fn wrong_arity(name: &str) -> String {
+    format!("wrong number of arguments for '{}' command", name.to_lowercase())
+}
+
+/// `HSET key field value [field value ...]`: returns the number of fields added.
+pub struct HSet;
+
+impl Command for HSet {
+    fn name(&self) -> &str {
+        "HSET"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() < 3 || args.len().is_multiple_of(2) {
+            return Err(wrong_arity(self.name()));
+        }
+        let key = arg_str(args, 0, self.name())?;
+        let pairs = (1..args.len())
+            .step_by(2)
+            .map(|index| Ok((arg_str(args, index, self.name())?, arg_str(args, index + 1, self.name())?)))
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let hash = hash_or_create(state, key)?;
+        let added = pairs
+            .iter()
+            .filter(|(field, value)| hash.insert(field.to_string(), value.to_string()).is_none())
+            .count();
+        // Setting a field clears its expiration time.
+        for (field, _) in pairs {
+            state.set_field_expires_at(key, field, None);
+        }
+        Ok(RespType::Integer(added as i64))
+    }
+}
+
+/// `HSETNX key field value`: only sets a field that does not exist yet.
+pub struct HSetNx;
+
+impl Command for HSetNx {
+    fn name(&self) -> &str {
+        "HSETNX"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let field = arg_str(args, 1, self.name())?;
+        let value = arg_str(args, 2, self.name())?;
+
+        let hash = hash_or_create(state, key)?;
+        if hash.contains_key(field) {
+            return Ok(RespType::Integer(0));
+        }
+        hash.insert(field.to_string(), value.to_string());
+        Ok(RespType::Integer(1))
+    }
+}
+
+/// `HGET key field`
+pub struct HGet;
+
+impl Command for HGet {
+    fn name(&self) -> &str {
+        "HGET"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let field = arg_str(args, 1, self.name())?;
+        Ok(RespType::BulkString(hash(state, key)?.and_then(|hash| hash.get(field).cloned())))
+    }
+}
+
+/// `HMGET key field [field ...]`
+pub struct HMGet;
+
+impl Command for HMGet {
+    fn name(&self) -> &str {
+        "HMGET"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() < 2 {
+            return Err(wrong_arity(self.name()));
+        }
+        let key = arg_str(args, 0, self.name())?;
+        let fields = (1..args.len())
+            .map(|index| arg_str(args, index, self.name()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let hash = hash(state, key)?;
+        Ok(RespType::Array(
+            fields
+                .into_iter()
+                .map(|field| RespType::BulkString(hash.as_ref().and_then(|hash| hash.get(field).cloned())))
+                .collect(),
+        ))
+    }
+}
+
+/// `HDEL key field [field ...]`: returns the number of fields removed.
+pub struct HDel;
+
+impl Command for HDel {
+    fn name(&self) -> &str {
+        "HDEL"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() < 2 {
+            return Err(wrong_arity(self.name()));
+        }
+        let key = arg_str(args, 0, self.name())?;
+        let fields = (1..args.len())
+            .map(|index| arg_str(args, index, self.name()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let Some(hash) = hash(state, key)? else {
+            return Ok(RespType::Integer(0));
+        };
+        let removed = fields.iter().filter(|field| hash.remove(**field).is_some()).count();
+        for field in fields {
+            state.set_field_expires_at(key, field, None);
+        }
+        delete_if_empty(state, key)?;
+        Ok(RespType::Integer(removed as i64))
+    }
+}
+
+/// `HEXISTS key field`
+pub struct HExists;
+
+impl Command for HExists {
+    fn name(&self) -> &str {
+        "HEXISTS"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let field = arg_str(args, 1, self.name())?;
+        let exists = hash(state, key)?.is_some_and(|hash| hash.contains_key(field));
+        Ok(RespType::Integer(exists as i64))
+    }
+}
+
+/// `HLEN key`
+pub struct HLen;
+
+impl Command for HLen {
+    fn name(&self) -> &str {
+        "HLEN"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        Ok(RespType::Integer(hash(state, key)?.map_or(0, |hash| hash.len() as i64)))
+    }
+}
+
+/// `HSTRLEN key field`
+pub struct HStrLen;
+
+impl Command for HStrLen {
+    fn name(&self) -> &str {
+        "HSTRLEN"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let field = arg_str(args, 1, self.name())?;
+        let len = hash(state, key)?.and_then(|hash| hash.get(field)).map_or(0, |value| value.len());
+        Ok(RespType::Integer(len as i64))
+    }
+}
+
+/// `HKEYS key`
+pub struct HKeys;
+
+impl Command for HKeys {
+    fn name(&self) -> &str {
+        "HKEYS"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let fields = hash(state, key)?.map(|hash| hash.keys().map(|field| bulk(field)).collect());
+        Ok(RespType::Array(fields.unwrap_or_default()))
+    }
+}
+
+/// `HVALS key`
+pub struct HVals;
+
+impl Command for HVals {
+    fn name(&self) -> &str {
+        "HVALS"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let values = hash(state, key)?.map(|hash| hash.values().map(|value| bulk(value)).collect());
+        Ok(RespType::Array(values.unwrap_or_default()))
+    }
+}
+
+/// `HGETALL key`: fields followed by their values.
+pub struct HGetAll;
+
+impl Command for HGetAll {
+    fn name(&self) -> &str {
+        "HGETALL"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let pairs = hash(state, key)?.map(|hash| {
+            hash.iter()
+                .flat_map(|(field, value)| [bulk(field), bulk(value)])
+                .collect()
+        });
+        Ok(RespType::Array(pairs.unwrap_or_default()))
+    }
+}
+
+/// `HINCRBY key field increment`
+pub struct HIncrBy;
+
+impl Command for HIncrBy {
+    fn name(&self) -> &str {
+        "HINCRBY"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let field = arg_str(args, 1, self.name())?;
+        let increment = arg_i64(args, 2, self.name())?;
+
+        let hash = hash_or_create(state, key)?;
+        let current = match hash.get(field) {
+            Some(value) => value
+                .parse::<i64>()
+                .map_err(|_| "hash value is not an integer".to_string())?,
+            None => 0,
+        };
+        let value = current
+            .checked_add(increment)
+            .ok_or_else(|| "increment or decrement would overflow".to_string())?;
+        hash.insert(field.to_string(), value.to_string());
+        Ok(RespType::Integer(value))
+    }
+}
+
+/// `HINCRBYFLOAT key field increment`
+pub struct HIncrByFloat;
+
+impl Command for HIncrByFloat {
+    fn name(&self) -> &str {
+        "HINCRBYFLOAT"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let field = arg_str(args, 1, self.name())?;
+        let increment = arg_str(args, 2, self.name())?
+            .parse::<f64>()
+            .ok()
+            .filter(|increment| increment.is_finite())
+            .ok_or_else(|| "value is not a valid float".to_string())?;
+
+        let hash = hash_or_create(state, key)?;
+        let current = match hash.get(field) {
+            Some(value) => value
+                .parse::<f64>()
+                .ok()
+                .filter(|value| value.is_finite())
+                .ok_or_else(|| "hash value is not a float".to_string())?,
+            None => 0.0,
+        };
+        let value = current + increment;
+        if !value.is_finite() {
+            return Err("increment would produce NaN or Infinity".to_string());
+        }
+        hash.insert(field.to_string(), value.to_string());
+        Ok(bulk(&value.to_string()))
+    }
+}
+
+/// `HRANDFIELD key [count [WITHVALUES]]`: a negative count allows the same field more than once.
+pub struct HRandField;
+
+impl Command for HRandField {
+    fn name(&self) -> &str {
+        "HRANDFIELD"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let count = match args.get(1) {
+            Some(_) => Some(arg_i64(args, 1, self.name())?),
+            None => None,
+        };
+        let with_values = match args.get(2) {
+            Some(_) if arg_str(args, 2, self.name())?.eq_ignore_ascii_case("WITHVALUES") && args.len() == 3 => true,
+            Some(_) => return Err("syntax error".to_string()),
+            None => false,
+        };
+
+        let Some(hash) = hash(state, key)? else {
+            return Ok(match count {
+                Some(_) => RespType::Array(Vec::new()),
+                None => RespType::BulkString(None),
+            });
+        };
+        let fields = hash.iter().collect::<Vec<_>>();
+        let Some(count) = count else {
+            let (field, _) = fields[random_below(fields.len() as u64) as usize];
+            return Ok(bulk(field));
+        };
+
+        let picked = sample(fields, count)?;
+        Ok(RespType::Array(
+            picked
+                .into_iter()
+                .flat_map(|(field, value)| {
+                    let mut reply = vec![bulk(field)];
+                    if with_values {
+                        reply.push(bulk(value));
+                    }
+                    reply
+                })
+                .collect(),
+        ))
+    }
+}
+
+/// `HSCAN key cursor [MATCH pattern] [COUNT count] [NOVALUES]`
+pub struct HScan;
+
+impl Command for HScan {
+    fn name(&self) -> &str {
+        "HSCAN"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let scan = scan_args(args, 1, self.name(), true)?;
+
+        let (cursor, page) = match hash(state, key)? {
+            Some(hash) => scan_page(hash.iter(), &scan),
+            None => (0, Vec::new()),
+        };
+        let items = page
+            .into_iter()
+            .flat_map(|(field, value)| {
+                let mut reply = vec![bulk(field)];
+                if !scan.novalues {
+                    reply.push(bulk(value));
+                }
+                reply
+            })
+            .collect();
+        Ok(RespType::Array(vec![bulk(&cursor.to_string()), RespType::Array(items)]))
+    }
+}
+
+/// Returns the fields listed after `FIELDS numfields` at `offset`.
+fn fields_arg<'a>(args: &'a [RespType], offset: usize, name: &str) -> Result<Vec<&'a str>, String> {
+    if !arg_str(args, offset, name)?.eq_ignore_ascii_case("FIELDS") {
+        return Err("Mandatory argument FIELDS is missing or not at the right position".to_string());
+    }
+    let count = arg_i64(args, offset + 1, name)?;
+    if count <= 0 {
+        return Err("Parameter `numFields` should be greater than 0".to_string());
+    }
+    if args.len() - offset - 2 != count as usize {
+        return Err("The `numfields` parameter must match the number of arguments".to_string());
+    }
+    (offset + 2..args.len()).map(|index| arg_str(args, index, name)).collect()
+}
+
+/// Condition of `HEXPIRE` and friends. A field without expiration time counts as never expiring.
+#[derive(Clone, Copy, PartialEq)]
+enum Condition {
+    Always,
+    Nx,
+    Xx,
+    Gt,
+    Lt,
+}
+
+impl Condition {
+    fn allows(self, current: Option<u64>, expires_at: u64) -> bool {
+        match (self, current) {
+            (Condition::Always, _) => true,
+            (Condition::Nx, current) => current.is_none(),
+            (Condition::Xx, current) => current.is_some(),
+            (Condition::Gt, current) => current.is_some_and(|current| expires_at > current),
+            (Condition::Lt, current) => current.is_none_or(|current| expires_at < current),
+        }
+    }
+}
+
+/// Shared implementation of `HEXPIRE`, `HPEXPIRE`, `HEXPIREAT` and `HPEXPIREAT`: `key time
+/// [NX | XX | GT | LT] FIELDS numfields field ...`. Replies for each field with -2 when it does
+/// not exist, 0 when the condition is not met, 1 when the time was set and 2 when the field
+/// was deleted because the time is in the past. Replicas receive the absolute time.
+fn execute_field_expire(
+    args: &[RespType],
+    state: &mut dyn ServerState,
+    name: &str,
+    unit_ms: u64,
+    absolute: bool,
+) -> Result<RespType, String> {
+    let key = arg_str(args, 0, name)?;
+    let time = arg_i64(args, 1, name)?;
+    let invalid_time = || format!("invalid expire time in '{}' command", name.to_lowercase());
+    let now = current_time_ms();
+    let expires_at = u64::try_from(time)
+        .ok()
+        .and_then(|time| time.checked_mul(unit_ms))
+        .and_then(|time| if absolute { Some(time) } else { time.checked_add(now) })
+        .filter(|expires_at| *expires_at <= i64::MAX as u64)
+        .ok_or_else(invalid_time)?;
+
+    let (condition, fields_offset) = match arg_str(args, 2, name)?.to_uppercase().as_str() {
+        "NX" => (Condition::Nx, 3),
+        "XX" => (Condition::Xx, 3),
+        "GT" => (Condition::Gt, 3),
+        "LT" => (Condition::Lt, 3),
+        _ => (Condition::Always, 2),
+    };
+    let fields = fields_arg(args, fields_offset, name)?;
+
+    let mut replies = Vec::with_capacity(fields.len());
+    for field in &fields {
+        if !hash(state, key)?.is_some_and(|hash| hash.contains_key(*field)) {
+            replies.push(RespType::Integer(-2));
+            continue;
+        }
+        if !condition.allows(state.field_expires_at(key, field), expires_at) {
+            replies.push(RespType::Integer(0));
+            continue;
+        }
+        if expires_at <= now {
+            if let Some(hash) = hash(state, key)? {
+                hash.remove(*field);
+            }
+            state.set_field_expires_at(key, field, None);
+            replies.push(RespType::Integer(2));
+        } else {
+            state.set_field_expires_at(key, field, Some(expires_at));
+            replies.push(RespType::Integer(1));
+        }
+    }
+    delete_if_empty(state, key)?;
+
+    if replies.iter().any(|reply| matches!(reply, RespType::Integer(1 | 2))) {
+        let expires_at = expires_at.to_string();
+        let count = fields.len().to_string();
+        let mut parts = vec!["HPEXPIREAT", key, &expires_at];
+        if fields_offset == 3 {
+            parts.push(arg_str(args, 2, name)?);
+        }
+        parts.extend(["FIELDS", &count]);
+        parts.extend(fields.iter().copied());
+        propagate(state, &parts);
+    }
+    Ok(RespType::Array(replies))
+}
+
+/// `HEXPIRE key seconds [NX | XX | GT | LT] FIELDS numfields field ...`
+pub struct HExpire;
+
+impl Command for HExpire {
+    fn name(&self) -> &str {
+        "HEXPIRE"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn propagate_verbatim(&self) -> bool {
+        false
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_field_expire(args, state, self.name(), 1000, false)
+    }
+}
+
+/// `HPEXPIRE key milliseconds [NX | XX | GT | LT] FIELDS numfields field ...`
+pub struct HPExpire;
+
+impl Command for HPExpire {
+    fn name(&self) -> &str {
+        "HPEXPIRE"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn propagate_verbatim(&self) -> bool {
+        false
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_field_expire(args, state, self.name(), 1, false)
+    }
+}
+
+/// `HEXPIREAT key unix-time-seconds [NX | XX | GT | LT] FIELDS numfields field ...`
+pub struct HExpireAt;
+
+impl Command for HExpireAt {
+    fn name(&self) -> &str {
+        "HEXPIREAT"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn propagate_verbatim(&self) -> bool {
+        false
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_field_expire(args, state, self.name(), 1000, true)
+    }
+}
+
+/// `HPEXPIREAT key unix-time-milliseconds [NX | XX | GT | LT] FIELDS numfields field ...`
+pub struct HPExpireAt;
+
+impl Command for HPExpireAt {
+    fn name(&self) -> &str {
+        "HPEXPIREAT"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn propagate_verbatim(&self) -> bool {
+        false
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_field_expire(args, state, self.name(), 1, true)
+    }
+}
+
+/// Shared implementation of the commands reading field expiration times: `key FIELDS
+/// numfields field ...`. Replies -2 for missing fields and -1 for fields that do not expire.
+fn execute_field_ttl(
+    args: &[RespType],
+    state: &mut dyn ServerState,
+    name: &str,
+    reply: fn(expires_at: u64, now: u64) -> u64,
+) -> Result<RespType, String> {
+    let key = arg_str(args, 0, name)?;
+    let fields = fields_arg(args, 1, name)?;
+    let now = current_time_ms();
+
+    let mut replies = Vec::with_capacity(fields.len());
+    for field in fields {
+        if !hash(state, key)?.is_some_and(|hash| hash.contains_key(field)) {
+            replies.push(RespType::Integer(-2));
+            continue;
+        }
+        let ttl = state
+            .field_expires_at(key, field)
+            .map_or(-1, |expires_at| reply(expires_at, now) as i64);
+        replies.push(RespType::Integer(ttl));
+    }
+    Ok(RespType::Array(replies))
+}
+
+/// `HTTL key FIELDS numfields field ...`: remaining seconds.
+pub struct HTtl;
+
+impl Command for HTtl {
+    fn name(&self) -> &str {
+        "HTTL"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_field_ttl(args, state, self.name(), |expires_at, now| expires_at.saturating_sub(now).div_ceil(1000))
+    }
+}
+
+/// `HPTTL key FIELDS numfields field ...`: remaining milliseconds.
+pub struct HPTtl;
+
+impl Command for HPTtl {
+    fn name(&self) -> &str {
+        "HPTTL"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_field_ttl(args, state, self.name(), |expires_at, now| expires_at.saturating_sub(now))
+    }
+}
+
+/// `HEXPIRETIME key FIELDS numfields field ...`: Unix time in seconds.
+pub struct HExpireTime;
+
+impl Command for HExpireTime {
+    fn name(&self) -> &str {
+        "HEXPIRETIME"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_field_ttl(args, state, self.name(), |expires_at, _| expires_at / 1000)
+    }
+}
+
+/// `HPEXPIRETIME key FIELDS numfields field ...`: Unix time in milliseconds.
+pub struct HPExpireTime;
+
+impl Command for HPExpireTime {
+    fn name(&self) -> &str {
+        "HPEXPIRETIME"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_field_ttl(args, state, self.name(), |expires_at, _| expires_at)
+    }
+}
+
+/// `HPERSIST key FIELDS numfields field ...`: replies 1 for each field whose expiration time
+/// was removed, -1 when it had none and -2 when it does not exist.
+pub struct HPersist;
+
+impl Command for HPersist {
+    fn name(&self) -> &str {
+        "HPERSIST"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let fields = fields_arg(args, 1, self.name())?;
+
+        let mut replies = Vec::with_capacity(fields.len());
+        for field in fields {
+            if !hash(state, key)?.is_some_and(|hash| hash.contains_key(field)) {
+                replies.push(RespType::Integer(-2));
+            } else if state.field_expires_at(key, field).is_some() {
+                state.set_field_expires_at(key, field, None);
+                replies.push(RespType::Integer(1));
+            } else {
+                replies.push(RespType::Integer(-1));
+            }
+        }
+        Ok(RespType::Array(replies))
+    }
+}
@@ -0,0 +1,128 @@
+//! HyperLogLog commands. HyperLogLogs are string values, so `GET` and `SET` copy them.
+
+use crate::resp::commands::{arg_bytes, arg_str, first_key, Command};
+use crate::resp::hyperloglog::HyperLogLog;
+use crate::resp::protocol::RespType;
+use crate::resp::state::server_state::ServerState;
+use crate::resp::state::value::{Value, WRONG_TYPE};
+
+fn load(state: &mut dyn ServerState, key: &str) -> Result<Option<HyperLogLog>, String> {
+    match state.get(key) {
+        Some(Value::String(value)) => Ok(Some(HyperLogLog::decode(value)?)),
+        Some(_) => Err(WRONG_TYPE.to_string()),
+        None => Ok(None),
+    }
+}
+
+/// Stores a HyperLogLog, keeping the expiration time of an existing key.
+fn store(state: &mut dyn ServerState, key: &str, hll: &HyperLogLog) -> Result<(), String> {
+    let value = hll.encode();
+    match state.get_mut(key) {
+        Some(Value::String(existing)) => *existing = value,
+        _ => state.set(key.to_string(), Value::String(value), None)?,
+    }
+    Ok(())
+}
+
+fn all_keys<'a>(args: &'a [RespType], name: &str) -> Vec<&'a str> {
+    (0..args.len()).filter_map(|index| arg_str(args, index, name).ok()).collect()
+}
+
+/// `PFADD key [element ...]`: returns 1 if the key was created or its estimate may have changed.
+pub struct PfAdd;
+
+impl Command for PfAdd {
+    fn name(&self) -> &str {
+        "PFADD"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let (mut hll, created) = match load(state, key)? {
+            Some(hll) => (hll, false),
+            None => (HyperLogLog::default(), true),
+        };
+        let mut changed = false;
+        for index in 1..args.len() {
+            changed |= hll.add(arg_bytes(args, index, self.name())?);
+        }
+        if created || changed {
+            store(state, key, &hll)?;
+        }
+        Ok(RespType::Integer((created || changed) as i64))
+    }
+}
+
+/// `PFCOUNT key [key ...]`: estimates the number of distinct elements added to the keys.
+///
+/// For a single key the estimate is cached in the value, as Redis does, until it changes.
+pub struct PfCount;
+
+impl Command for PfCount {
+    fn name(&self) -> &str {
+        "PFCOUNT"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        all_keys(args, self.name())
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        if args.len() == 1 {
+            let Some(mut hll) = load(state, key)? else {
+                return Ok(RespType::Integer(0));
+            };
+            let (count, computed) = hll.count();
+            if computed {
+                store(state, key, &hll)?;
+            }
+            return Ok(RespType::Integer(count as i64));
+        }
+
+        let mut union = HyperLogLog::default();
+        for key in all_keys(args, self.name()) {
+            if let Some(hll) = load(state, key)? {
+                union.merge(&hll);
+            }
+        }
+        Ok(RespType::Integer(union.count().0 as i64))
+    }
+}
+
+/// `PFMERGE destkey [sourcekey ...]`: stores the union of the HyperLogLogs, `destkey` included.
+pub struct PfMerge;
+
+impl Command for PfMerge {
+    fn name(&self) -> &str {
+        "PFMERGE"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        all_keys(args, self.name())
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let destination = arg_str(args, 0, self.name())?;
+        let mut union = load(state, destination)?.unwrap_or_default();
+        for index in 1..args.len() {
+            if let Some(hll) = load(state, arg_str(args, index, self.name())?)? {
+                union.merge(&hll);
+            }
+        }
+        store(state, destination, &union)?;
+        Ok(RespType::SimpleString("OK".to_string()))
+    }
+}
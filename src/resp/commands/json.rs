@@ -0,0 +1,545 @@
+//! JSON document commands. Documents are parsed once and modified in place.
+//!
+//! Commands given a JSONPath (starting with `$`) reply for every match, with nil for matches
+//! of the wrong type. With a legacy path they reply for a single match and fail when there is
+//! none or it has the wrong type.
+
+use crate::resp::commands::{arg_i64, arg_str, first_key, Command};
+use crate::resp::json::{self, Format, Json};
+use crate::resp::json_path::Path;
+use crate::resp::protocol::RespType;
+use crate::resp::state::server_state::ServerState;
+use crate::resp::state::value::{Value, WRONG_TYPE};
+
+const NO_KEY: &str = "could not perform this operation on a key that doesn't exist";
+
+fn document<'a>(state: &'a mut dyn ServerState, key: &str) -> Result<Option<&'a mut Json>, String> {
+    match state.get_mut(key) {
+        Some(Value::Json(document)) => Ok(Some(document)),
+        Some(_) => Err(WRONG_TYPE.to_string()),
+        None => Ok(None),
+    }
+}
+
+/// The document at `key`, which the modifying commands require to exist.
+fn existing_document<'a>(state: &'a mut dyn ServerState, key: &str) -> Result<&'a mut Json, String> {
+    document(state, key)?.ok_or_else(|| NO_KEY.to_string())
+}
+
+/// Path argument at `index`, the root when absent.
+fn path_arg(args: &[RespType], index: usize, name: &str) -> Result<Path, String> {
+    if index >= args.len() {
+        return Ok(Path::root());
+    }
+    Path::parse(arg_str(args, index, name)?)
+}
+
+fn json_arg(args: &[RespType], index: usize, name: &str) -> Result<Json, String> {
+    json::parse(arg_str(args, index, name)?)
+}
+
+fn missing_path(path: &Path) -> String {
+    format!("Path '{}' does not exist", path.text)
+}
+
+fn bulk(value: &str) -> RespType {
+    RespType::BulkString(Some(value.to_string()))
+}
+
+/// Replies for the values matched by `path`, from `reply` which returns `None` for values of
+/// the wrong type. A legacy path replies for its last match and fails if any has the wrong
+/// type, naming the `expected` one.
+fn reply_each(
+    document: &mut Json,
+    path: &Path,
+    expected: &str,
+    mut reply: impl FnMut(&mut Json) -> Result<Option<RespType>, String>,
+) -> Result<RespType, String> {
+    let mut replies = Vec::new();
+    for location in path.locate(document) {
+        let Some(value) = document.get_mut(&location) else {
+            continue;
+        };
+        let found = value.type_name();
+        match reply(value)? {
+            Some(value) => replies.push(value),
+            None if path.legacy => {
+                return Err(format!("WRONGTYPE wrong type of path value - expected {} but found {}", expected, found));
+            }
+            None => replies.push(RespType::BulkString(None)),
+        }
+    }
+    if path.legacy {
+        return replies.pop().ok_or_else(|| missing_path(path));
+    }
+    Ok(RespType::Array(replies))
+}
+
+/// `JSON.SET key path value [NX | XX]`: sets the values matched by `path`, or adds the last
+/// member of the path to the objects matched by the rest of it. New documents must be set at
+/// the root. Replies nil when nothing was set.
+pub struct JsonSet;
+
+impl Command for JsonSet {
+    fn name(&self) -> &str {
+        "JSON.SET"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if !(3..=4).contains(&args.len()) {
+            return Err("wrong number of arguments for 'json.set' command".to_string());
+        }
+        let key = arg_str(args, 0, self.name())?;
+        let path = Path::parse(arg_str(args, 1, self.name())?)?;
+        let value = json_arg(args, 2, self.name())?;
+        let (nx, xx) = match args.get(3).map(|_| arg_str(args, 3, self.name())).transpose()? {
+            None => (false, false),
+            Some(condition) if condition.eq_ignore_ascii_case("NX") => (true, false),
+            Some(condition) if condition.eq_ignore_ascii_case("XX") => (false, true),
+            Some(_) => return Err("syntax error".to_string()),
+        };
+
+        let Some(document) = document(state, key)? else {
+            if !path.is_root() {
+                return Err("new objects must be created at the root".to_string());
+            }
+            if xx {
+                return Ok(RespType::BulkString(None));
+            }
+            state.set(key.to_string(), Value::Json(value), None)?;
+            return Ok(RespType::SimpleString("OK".to_string()));
+        };
+
+        let locations = path.locate(document);
+        if !locations.is_empty() {
+            if nx {
+                return Ok(RespType::BulkString(None));
+            }
+            for location in locations {
+                if let Some(existing) = document.get_mut(&location) {
+                    *existing = value.clone();
+                }
+            }
+            return Ok(RespType::SimpleString("OK".to_string()));
+        }
+        let insertion_points = path.insertion_points(document);
+        if xx || insertion_points.is_empty() {
+            return Ok(RespType::BulkString(None));
+        }
+        for (location, key) in insertion_points {
+            if let Some(object) = document.get_mut(&location) {
+                object.set_member(&key, value.clone());
+            }
+        }
+        Ok(RespType::SimpleString("OK".to_string()))
+    }
+}
+
+/// `JSON.GET key [INDENT indent] [NEWLINE newline] [SPACE space] [path ...]`: serializes the
+/// values matched by the paths, the whole document by default. A JSONPath replies with an
+/// array of its matches and several paths with an object keyed by path.
+pub struct JsonGet;
+
+impl Command for JsonGet {
+    fn name(&self) -> &str {
+        "JSON.GET"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let mut format = Format::default();
+        let mut paths = Vec::new();
+        let mut index = 1;
+        while index < args.len() {
+            let arg = arg_str(args, index, self.name())?;
+            let option = match arg.to_uppercase().as_str() {
+                "INDENT" => &mut format.indent,
+                "NEWLINE" => &mut format.newline,
+                "SPACE" => &mut format.space,
+                _ => {
+                    paths.push(Path::parse(arg)?);
+                    index += 1;
+                    continue;
+                }
+            };
+            *option = arg_str(args, index + 1, self.name()).map_err(|_| "syntax error".to_string())?.to_string();
+            index += 2;
+        }
+        if paths.is_empty() {
+            paths.push(Path::root());
+        }
+
+        let Some(document) = document(state, key)? else {
+            return Ok(RespType::BulkString(None));
+        };
+        let select = |path: &Path| -> Result<Json, String> {
+            let mut matches = path.locate(document).into_iter().filter_map(|location| document.get(&location).cloned());
+            if path.legacy {
+                return matches.next().ok_or_else(|| missing_path(path));
+            }
+            Ok(Json::Array(matches.collect()))
+        };
+        let result = match paths.as_slice() {
+            [path] => select(path)?,
+            paths => {
+                // Legacy paths only keep that syntax when all of them use it.
+                let legacy = paths.iter().all(|path| path.legacy);
+                let mut object = Json::Object(Vec::new());
+                for path in paths {
+                    let mut path = path.clone();
+                    path.legacy = legacy;
+                    object.set_member(&path.text, select(&path)?);
+                }
+                object
+            }
+        };
+        Ok(bulk(&result.format(&format)))
+    }
+}
+
+/// `JSON.DEL key [path]`: deletes the matched values, and the key with the root. Returns the
+/// number of values deleted.
+pub struct JsonDel;
+
+impl Command for JsonDel {
+    fn name(&self) -> &str {
+        "JSON.DEL"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let path = path_arg(args, 1, self.name())?;
+        let Some(document) = document(state, key)? else {
+            return Ok(RespType::Integer(0));
+        };
+        if path.is_root() {
+            state.del(key)?;
+            return Ok(RespType::Integer(1));
+        }
+
+        // Later array elements go first so the indexes of earlier ones stay valid, and values
+        // inside deleted ones are skipped.
+        let mut locations = path.locate(document);
+        locations.sort();
+        locations.dedup();
+        let mut deleted: Vec<Vec<_>> = Vec::new();
+        for location in locations.into_iter().rev() {
+            if deleted.iter().any(|parent| location.starts_with(parent)) {
+                continue;
+            }
+            if document.remove(&location).is_some() {
+                deleted.retain(|child| !child.starts_with(&location));
+                deleted.push(location);
+            }
+        }
+        Ok(RespType::Integer(deleted.len() as i64))
+    }
+}
+
+/// `JSON.TYPE key [path]`: the type of the matched values.
+pub struct JsonType;
+
+impl Command for JsonType {
+    fn name(&self) -> &str {
+        "JSON.TYPE"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let path = path_arg(args, 1, self.name())?;
+        let Some(document) = document(state, key)? else {
+            return Ok(RespType::BulkString(None));
+        };
+        let types = path.locate(document).into_iter().filter_map(|location| document.get(&location));
+        let mut types = types.map(|value| bulk(value.type_name())).collect::<Vec<_>>();
+        if path.legacy {
+            return Ok(types.pop().unwrap_or(RespType::BulkString(None)));
+        }
+        Ok(RespType::Array(types))
+    }
+}
+
+/// `JSON.NUMINCRBY key path value`: adds to the matched numbers, replying with the new values
+/// serialized, as an array for a JSONPath. Integers stay integers when both are.
+pub struct JsonNumIncrBy;
+
+impl Command for JsonNumIncrBy {
+    fn name(&self) -> &str {
+        "JSON.NUMINCRBY"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() != 3 {
+            return Err("wrong number of arguments for 'json.numincrby' command".to_string());
+        }
+        let key = arg_str(args, 0, self.name())?;
+        let path = Path::parse(arg_str(args, 1, self.name())?)?;
+        let increment = json_arg(args, 2, self.name())?;
+        if increment.as_f64().is_none() {
+            return Err("expected a number as increment".to_string());
+        }
+        let document = existing_document(state, key)?;
+
+        let mut results = Vec::new();
+        let reply = reply_each(document, &path, "number", |value| {
+            let result = match (&*value, &increment) {
+                (Json::Integer(value), Json::Integer(increment)) if value.checked_add(*increment).is_some() => {
+                    Json::Integer(value + increment)
+                }
+                _ => match (value.as_f64(), increment.as_f64()) {
+                    (Some(value), Some(increment)) if (value + increment).is_finite() => Json::Float(value + increment),
+                    (Some(_), Some(_)) => return Err("result is too large".to_string()),
+                    _ => {
+                        results.push(Json::Null);
+                        return Ok(None);
+                    }
+                },
+            };
+            *value = result.clone();
+            let reply = bulk(&result.serialize());
+            results.push(result);
+            Ok(Some(reply))
+        })?;
+        if path.legacy {
+            return Ok(reply);
+        }
+        Ok(bulk(&Json::Array(results).serialize()))
+    }
+}
+
+/// `JSON.STRAPPEND key [path] value`: appends a JSON string to the matched strings, replying
+/// with their new lengths.
+pub struct JsonStrAppend;
+
+impl Command for JsonStrAppend {
+    fn name(&self) -> &str {
+        "JSON.STRAPPEND"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let (path, value) = match args.len() {
+            2 => (Path::root(), json_arg(args, 1, self.name())?),
+            3 => (Path::parse(arg_str(args, 1, self.name())?)?, json_arg(args, 2, self.name())?),
+            _ => return Err("wrong number of arguments for 'json.strappend' command".to_string()),
+        };
+        let Json::String(suffix) = value else {
+            return Err("expected a JSON string to append".to_string());
+        };
+        let document = existing_document(state, arg_str(args, 0, self.name())?)?;
+        reply_each(document, &path, "string", |value| {
+            let Json::String(value) = value else {
+                return Ok(None);
+            };
+            value.push_str(&suffix);
+            Ok(Some(RespType::Integer(value.len() as i64)))
+        })
+    }
+}
+
+/// `JSON.ARRAPPEND key path value [value ...]`: appends to the matched arrays, replying with
+/// their new lengths.
+pub struct JsonArrAppend;
+
+impl Command for JsonArrAppend {
+    fn name(&self) -> &str {
+        "JSON.ARRAPPEND"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() < 3 {
+            return Err("wrong number of arguments for 'json.arrappend' command".to_string());
+        }
+        let path = Path::parse(arg_str(args, 1, self.name())?)?;
+        let values = (2..args.len()).map(|index| json_arg(args, index, self.name())).collect::<Result<Vec<_>, _>>()?;
+        let document = existing_document(state, arg_str(args, 0, self.name())?)?;
+        reply_each(document, &path, "array", |value| {
+            let Json::Array(items) = value else {
+                return Ok(None);
+            };
+            items.extend(values.iter().cloned());
+            Ok(Some(RespType::Integer(items.len() as i64)))
+        })
+    }
+}
+
+/// `JSON.ARRINSERT key path index value [value ...]`: inserts before `index` of the matched
+/// arrays, which counts from the end when negative, replying with their new lengths.
+pub struct JsonArrInsert;
+
+impl Command for JsonArrInsert {
+    fn name(&self) -> &str {
+        "JSON.ARRINSERT"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() < 4 {
+            return Err("wrong number of arguments for 'json.arrinsert' command".to_string());
+        }
+        let path = Path::parse(arg_str(args, 1, self.name())?)?;
+        let index = arg_i64(args, 2, self.name())?;
+        let values = (3..args.len()).map(|index| json_arg(args, index, self.name())).collect::<Result<Vec<_>, _>>()?;
+        let document = existing_document(state, arg_str(args, 0, self.name())?)?;
+
+        let position = |len: usize| {
+            let position = if index < 0 { len as i64 + index } else { index };
+            (0..=len as i64).contains(&position).then_some(position as usize)
+        };
+        // The index is checked against every array before any is modified.
+        let out_of_bounds = path.locate(document).iter().any(|location| {
+            matches!(document.get(location), Some(Json::Array(items)) if position(items.len()).is_none())
+        });
+        if out_of_bounds {
+            return Err("index out of bounds".to_string());
+        }
+        reply_each(document, &path, "array", |value| {
+            let Json::Array(items) = value else {
+                return Ok(None);
+            };
+            let position = position(items.len()).unwrap_or(items.len());
+            items.splice(position..position, values.iter().cloned());
+            Ok(Some(RespType::Integer(items.len() as i64)))
+        })
+    }
+}
+
+/// `JSON.ARRPOP key [path [index]]`: removes and returns the element at `index` of the
+/// matched arrays, the last by default. Out of range indexes pop the first or last element.
+pub struct JsonArrPop;
+
+impl Command for JsonArrPop {
+    fn name(&self) -> &str {
+        "JSON.ARRPOP"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() > 3 {
+            return Err("wrong number of arguments for 'json.arrpop' command".to_string());
+        }
+        let path = path_arg(args, 1, self.name())?;
+        let index = if args.len() == 3 { arg_i64(args, 2, self.name())? } else { -1 };
+        let document = existing_document(state, arg_str(args, 0, self.name())?)?;
+        reply_each(document, &path, "array", |value| {
+            let Json::Array(items) = value else {
+                return Ok(None);
+            };
+            if items.is_empty() {
+                return Ok(Some(RespType::BulkString(None)));
+            }
+            let len = items.len() as i64;
+            let position = (if index < 0 { len + index } else { index }).clamp(0, len - 1);
+            Ok(Some(bulk(&items.remove(position as usize).serialize())))
+        })
+    }
+}
+
+/// `JSON.ARRLEN key [path]`: the length of the matched arrays.
+pub struct JsonArrLen;
+
+impl Command for JsonArrLen {
+    fn name(&self) -> &str {
+        "JSON.ARRLEN"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let path = path_arg(args, 1, self.name())?;
+        let Some(document) = document(state, arg_str(args, 0, self.name())?)? else {
+            return Ok(RespType::BulkString(None));
+        };
+        reply_each(document, &path, "array", |value| match value {
+            Json::Array(items) => Ok(Some(RespType::Integer(items.len() as i64))),
+            _ => Ok(None),
+        })
+    }
+}
+
+/// `JSON.OBJKEYS key [path]`: the member names of the matched objects.
+pub struct JsonObjKeys;
+
+impl Command for JsonObjKeys {
+    fn name(&self) -> &str {
+        "JSON.OBJKEYS"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let path = path_arg(args, 1, self.name())?;
+        let Some(document) = document(state, arg_str(args, 0, self.name())?)? else {
+            return Ok(RespType::BulkString(None));
+        };
+        reply_each(document, &path, "object", |value| match value {
+            Json::Object(members) => Ok(Some(RespType::Array(members.iter().map(|(key, _)| bulk(key)).collect()))),
+            _ => Ok(None),
+        })
+    }
+}
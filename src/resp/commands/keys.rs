@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+
+use crate::resp::commands::{arg_bytes, arg_i64, arg_str, current_time_ms, first_key, Command};
+use crate::resp::protocol::RespType;
+use crate::resp::rdb;
+use crate::resp::state::server_state::ServerState;
+use crate::resp::state::value::Value;
+
+/// `DEL key [key ...]`
+pub struct Del;
+
+impl Command for Del {
+    fn name(&self) -> &str {
+        "DEL"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        (0..args.len()).filter_map(|index| arg_str(args, index, "DEL").ok()).collect()
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.is_empty() {
+            return Err("DEL requires at least one key".to_string());
+        }
+        let mut deleted = 0;
+        for index in 0..args.len() {
+            let key = arg_str(args, index, self.name())?;
+            if state.get(key).is_some() {
+                state.del(key)?;
+                deleted += 1;
+            }
+        }
+        Ok(RespType::Integer(deleted))
+    }
+}
+
+/// `DUMP key`: serializes a value so that it can be recreated with `RESTORE`.
+pub struct Dump;
+
+impl Command for Dump {
+    fn name(&self) -> &str {
+        "DUMP"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        Ok(match dump(state, key) {
+            Some(payload) => RespType::bulk_bytes(payload),
+            None => RespType::BulkString(None),
+        })
+    }
+}
+
+/// `RESTORE key ttl payload [REPLACE] [ABSTTL]`
+pub struct Restore;
+
+impl Command for Restore {
+    fn name(&self) -> &str {
+        "RESTORE"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let ttl = arg_i64(args, 1, self.name())?;
+        let payload = arg_bytes(args, 2, self.name())?;
+
+        let mut replace = false;
+        let mut absttl = false;
+        for index in 3..args.len() {
+            match arg_str(args, index, self.name())?.to_uppercase().as_str() {
+                "REPLACE" => replace = true,
+                "ABSTTL" => absttl = true,
+                _ => return Err("syntax error".to_string()),
+            }
+        }
+        if ttl < 0 {
+            return Err("Invalid TTL value, must be >= 0".to_string());
+        }
+
+        if !replace && state.get(key).is_some() {
+            return Err("BUSYKEY Target key name already exists.".to_string());
+        }
+        let (value, field_expires) = rdb::restore_value(payload)?;
+
+        let ttl = match (ttl, absttl) {
+            (0, _) => None,
+            (expires_at, true) => {
+                let now = current_time_ms() as i64;
+                // Already expired keys are not created at all.
+                if expires_at <= now {
+                    state.del(key)?;
+                    return Ok(RespType::SimpleString("OK".to_string()));
+                }
+                Some(expires_at - now)
+            }
+            (ttl, false) => Some(ttl),
+        };
+        state.set(key.to_string(), value, ttl)?;
+        for (field, expires_at) in field_expires {
+            state.set_field_expires_at(key, &field, Some(expires_at));
+        }
+        Ok(RespType::SimpleString("OK".to_string()))
+    }
+}
+
+/// `TYPE key`
+pub struct Type;
+
+impl Command for Type {
+    fn name(&self) -> &str {
+        "TYPE"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        Ok(RespType::SimpleString(state.type_of(key).to_string()))
+    }
+}
+
+/// `DUMP` payload of the value at `key`, with the expiration times of its fields for hashes.
+pub(crate) fn dump(state: &mut dyn ServerState, key: &str) -> Option<Vec<u8>> {
+    let fields = match state.get(key)? {
+        Value::Hash(fields) => fields.keys().cloned().collect(),
+        _ => Vec::new(),
+    };
+    let field_expires = fields
+        .into_iter()
+        .filter_map(|field| Some((field.clone(), state.field_expires_at(key, &field)?)))
+        .collect::<HashMap<_, _>>();
+    state.get(key).map(|value| rdb::dump_value(value, &field_expires))
+}
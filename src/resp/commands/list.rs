@@ -0,0 +1,903 @@
+//! List commands. Lists are stored as `VecDeque`s and deleted as soon as they become empty.
+//! The blocking variants wait in `blocking` until a write makes one of their keys non-empty.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::resp::blocking::{serve, KeyOperation, KeyWait};
+use crate::resp::client::{BlockedOn, ClientContext};
+use crate::resp::commands::{arg_i64, arg_str, first_key, numkeys_keys, Command};
+use crate::resp::protocol::RespType;
+use crate::resp::state::server_state::ServerState;
+use crate::resp::state::value::{Value, WRONG_TYPE};
+
+/// End of a list, as given by the `LEFT`/`RIGHT` arguments.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum End {
+    Left,
+    Right,
+}
+
+pub(crate) fn end_arg(args: &[RespType], index: usize, name: &str) -> Result<End, String> {
+    match arg_str(args, index, name)?.to_uppercase().as_str() {
+        "LEFT" => Ok(End::Left),
+        "RIGHT" => Ok(End::Right),
+        _ => Err("syntax error".to_string()),
+    }
+}
+
+fn list<'a>(state: &'a mut dyn ServerState, key: &str) -> Result<Option<&'a mut VecDeque<String>>, String> {
+    match state.get_mut(key) {
+        Some(Value::List(list)) => Ok(Some(list)),
+        Some(_) => Err(WRONG_TYPE.to_string()),
+        None => Ok(None),
+    }
+}
+
+fn delete_if_empty(state: &mut dyn ServerState, key: &str) -> Result<(), String> {
+    if matches!(state.get(key), Some(Value::List(list)) if list.is_empty()) {
+        state.del(key)?;
+    }
+    Ok(())
+}
+
+fn bulk(value: String) -> RespType {
+    RespType::BulkString(Some(value))
+}
+
+fn array(values: impl IntoIterator<Item = String>) -> RespType {
+    RespType::Array(values.into_iter().map(bulk).collect())
+}
+
+/// Resolves an inclusive range where negative indexes count from the end of the list.
+fn range(start: i64, stop: i64, len: usize) -> Option<(usize, usize)> {
+    let len = len as i64;
+    let start = if start < 0 { (len + start).max(0) } else { start };
+    let stop = if stop < 0 { len + stop } else { stop.min(len - 1) };
+    if start > stop || start >= len {
+        None
+    } else {
+        Some((start as usize, stop as usize))
+    }
+}
+
+/// Resolves a single index where negative indexes count from the end of the list.
+fn index(index: i64, len: usize) -> Option<usize> {
+    let index = if index < 0 { len as i64 + index } else { index };
+    (0..len as i64).contains(&index).then_some(index as usize)
+}
+
+/// Pushes the values one after the other, creating the list unless `existing_only` is set.
+/// Returns the new length of the list.
+pub(crate) fn push(
+    state: &mut dyn ServerState,
+    key: &str,
+    values: &[&str],
+    end: End,
+    existing_only: bool,
+) -> Result<i64, String> {
+    if list(state, key)?.is_none() {
+        if existing_only {
+            return Ok(0);
+        }
+        state.set(key.to_string(), Value::List(VecDeque::new()), None)?;
+    }
+    let list = list(state, key)?.ok_or_else(|| "Failed to create list".to_string())?;
+    for value in values {
+        match end {
+            End::Left => list.push_front(value.to_string()),
+            End::Right => list.push_back(value.to_string()),
+        }
+    }
+    Ok(list.len() as i64)
+}
+
+/// Pops up to `count` elements, or `None` when the list does not exist.
+pub(crate) fn pop(state: &mut dyn ServerState, key: &str, end: End, count: usize) -> Result<Option<Vec<String>>, String> {
+    let Some(list) = list(state, key)? else {
+        return Ok(None);
+    };
+    let count = count.min(list.len());
+    let popped = match end {
+        End::Left => list.drain(..count).collect(),
+        End::Right => list.drain(list.len() - count..).rev().collect(),
+    };
+    delete_if_empty(state, key)?;
+    Ok(Some(popped))
+}
+
+/// Atomically pops an element from `source` and pushes it to `destination`.
+pub(crate) fn move_element(
+    state: &mut dyn ServerState,
+    source: &str,
+    destination: &str,
+    from: End,
+    to: End,
+) -> Result<Option<String>, String> {
+    if list(state, source)?.is_none() {
+        return Ok(None);
+    }
+    // The destination type is checked before anything is popped.
+    list(state, destination)?;
+    let Some(element) = pop(state, source, from, 1)?.and_then(|mut popped| popped.pop()) else {
+        return Ok(None);
+    };
+    push(state, destination, &[&element], to, false)?;
+    Ok(Some(element))
+}
+
+fn values(args: &[RespType], name: &str) -> Result<Vec<String>, String> {
+    if args.len() < 2 {
+        return Err(format!("wrong number of arguments for '{}' command", name.to_lowercase()));
+    }
+    (1..args.len()).map(|index| arg_str(args, index, name).map(str::to_string)).collect()
+}
+
+fn execute_push(args: &[RespType], state: &mut dyn ServerState, name: &str, end: End, existing_only: bool) -> Result<RespType, String> {
+    let key = arg_str(args, 0, name)?;
+    let values = values(args, name)?;
+    let values = values.iter().map(String::as_str).collect::<Vec<_>>();
+    Ok(RespType::Integer(push(state, key, &values, end, existing_only)?))
+}
+
+fn execute_pop(args: &[RespType], state: &mut dyn ServerState, name: &str, end: End) -> Result<RespType, String> {
+    let key = arg_str(args, 0, name)?;
+    let count = match args.get(1) {
+        Some(_) => Some(arg_i64(args, 1, name)?),
+        None => None,
+    };
+    if args.len() > 2 {
+        return Err(format!("wrong number of arguments for '{}' command", name.to_lowercase()));
+    }
+
+    match count {
+        Some(count) if count < 0 => Err("value is out of range, must be positive".to_string()),
+        Some(count) => Ok(match pop(state, key, end, count as usize)? {
+            Some(popped) => array(popped),
+            None => RespType::NullArray,
+        }),
+        None => Ok(match pop(state, key, end, 1)? {
+            Some(mut popped) => RespType::BulkString(popped.pop()),
+            None => RespType::BulkString(None),
+        }),
+    }
+}
+
+/// `LPUSH key element [element ...]`
+pub struct LPush;
+
+impl Command for LPush {
+    fn name(&self) -> &str {
+        "LPUSH"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_push(args, state, self.name(), End::Left, false)
+    }
+}
+
+/// `RPUSH key element [element ...]`
+pub struct RPush;
+
+impl Command for RPush {
+    fn name(&self) -> &str {
+        "RPUSH"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_push(args, state, self.name(), End::Right, false)
+    }
+}
+
+/// `LPUSHX key element [element ...]`: only pushes to an existing list.
+pub struct LPushX;
+
+impl Command for LPushX {
+    fn name(&self) -> &str {
+        "LPUSHX"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_push(args, state, self.name(), End::Left, true)
+    }
+}
+
+/// `RPUSHX key element [element ...]`: only pushes to an existing list.
+pub struct RPushX;
+
+impl Command for RPushX {
+    fn name(&self) -> &str {
+        "RPUSHX"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_push(args, state, self.name(), End::Right, true)
+    }
+}
+
+/// `LPOP key [count]`
+pub struct LPop;
+
+impl Command for LPop {
+    fn name(&self) -> &str {
+        "LPOP"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_pop(args, state, self.name(), End::Left)
+    }
+}
+
+/// `RPOP key [count]`
+pub struct RPop;
+
+impl Command for RPop {
+    fn name(&self) -> &str {
+        "RPOP"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_pop(args, state, self.name(), End::Right)
+    }
+}
+
+/// `LLEN key`
+pub struct LLen;
+
+impl Command for LLen {
+    fn name(&self) -> &str {
+        "LLEN"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        Ok(RespType::Integer(list(state, key)?.map_or(0, |list| list.len() as i64)))
+    }
+}
+
+/// `LRANGE key start stop`
+pub struct LRange;
+
+impl Command for LRange {
+    fn name(&self) -> &str {
+        "LRANGE"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let start = arg_i64(args, 1, self.name())?;
+        let stop = arg_i64(args, 2, self.name())?;
+
+        let Some(list) = list(state, key)? else {
+            return Ok(RespType::Array(Vec::new()));
+        };
+        Ok(match range(start, stop, list.len()) {
+            Some((start, stop)) => array(list.range(start..=stop).cloned()),
+            None => RespType::Array(Vec::new()),
+        })
+    }
+}
+
+/// `LINDEX key index`
+pub struct LIndex;
+
+impl Command for LIndex {
+    fn name(&self) -> &str {
+        "LINDEX"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let position = arg_i64(args, 1, self.name())?;
+
+        let element = list(state, key)?.and_then(|list| index(position, list.len()).map(|index| list[index].clone()));
+        Ok(RespType::BulkString(element))
+    }
+}
+
+/// `LSET key index element`
+pub struct LSet;
+
+impl Command for LSet {
+    fn name(&self) -> &str {
+        "LSET"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let position = arg_i64(args, 1, self.name())?;
+        let element = arg_str(args, 2, self.name())?;
+
+        let list = list(state, key)?.ok_or_else(|| "no such key".to_string())?;
+        let index = index(position, list.len()).ok_or_else(|| "index out of range".to_string())?;
+        list[index] = element.to_string();
+        Ok(RespType::SimpleString("OK".to_string()))
+    }
+}
+
+/// `LINSERT key BEFORE|AFTER pivot element`
+pub struct LInsert;
+
+impl Command for LInsert {
+    fn name(&self) -> &str {
+        "LINSERT"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let after = match arg_str(args, 1, self.name())?.to_uppercase().as_str() {
+            "BEFORE" => false,
+            "AFTER" => true,
+            _ => return Err("syntax error".to_string()),
+        };
+        let pivot = arg_str(args, 2, self.name())?;
+        let element = arg_str(args, 3, self.name())?;
+
+        let Some(list) = list(state, key)? else {
+            return Ok(RespType::Integer(0));
+        };
+        let Some(position) = list.iter().position(|item| item == pivot) else {
+            return Ok(RespType::Integer(-1));
+        };
+        list.insert(position + after as usize, element.to_string());
+        Ok(RespType::Integer(list.len() as i64))
+    }
+}
+
+/// `LREM key count element`: removes `count` occurrences from the head, from the tail
+/// when negative, or all of them when zero.
+pub struct LRem;
+
+impl Command for LRem {
+    fn name(&self) -> &str {
+        "LREM"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let count = arg_i64(args, 1, self.name())?;
+        let element = arg_str(args, 2, self.name())?;
+
+        let Some(list) = list(state, key)? else {
+            return Ok(RespType::Integer(0));
+        };
+        let limit = if count == 0 { usize::MAX } else { count.unsigned_abs() as usize };
+        let mut positions = list
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| *item == element)
+            .map(|(position, _)| position)
+            .collect::<Vec<_>>();
+        if count < 0 {
+            positions.reverse();
+        }
+        positions.truncate(limit);
+        positions.sort_unstable_by(|a, b| b.cmp(a));
+        for position in &positions {
+            list.remove(*position);
+        }
+
+        delete_if_empty(state, key)?;
+        Ok(RespType::Integer(positions.len() as i64))
+    }
+}
+
+/// `LTRIM key start stop`
+pub struct LTrim;
+
+impl Command for LTrim {
+    fn name(&self) -> &str {
+        "LTRIM"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let start = arg_i64(args, 1, self.name())?;
+        let stop = arg_i64(args, 2, self.name())?;
+
+        if let Some(list) = list(state, key)? {
+            match range(start, stop, list.len()) {
+                Some((start, stop)) => {
+                    list.truncate(stop + 1);
+                    list.drain(..start);
+                }
+                None => list.clear(),
+            }
+            delete_if_empty(state, key)?;
+        }
+        Ok(RespType::SimpleString("OK".to_string()))
+    }
+}
+
+/// `LPOS key element [RANK rank] [COUNT num-matches] [MAXLEN len]`
+pub struct LPos;
+
+impl Command for LPos {
+    fn name(&self) -> &str {
+        "LPOS"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let element = arg_str(args, 1, self.name())?;
+
+        let mut rank = 1;
+        let mut count = None;
+        let mut max_len = 0;
+        let mut index = 2;
+        while index < args.len() {
+            let value = arg_i64(args, index + 1, self.name())?;
+            match arg_str(args, index, self.name())?.to_uppercase().as_str() {
+                "RANK" if value == 0 || value == i64::MIN => {
+                    return Err("RANK can't be zero: use 1 to start from the first match, 2 from the second ... or use negative to start from the end of the list".to_string());
+                }
+                "RANK" => rank = value,
+                "COUNT" if value < 0 => return Err("COUNT can't be negative".to_string()),
+                "COUNT" => count = Some(value as usize),
+                "MAXLEN" if value < 0 => return Err("MAXLEN can't be negative".to_string()),
+                "MAXLEN" => max_len = value as usize,
+                _ => return Err("syntax error".to_string()),
+            }
+            index += 2;
+        }
+
+        let positions = match list(state, key)? {
+            Some(list) => {
+                let scanned = if max_len == 0 { list.len() } else { max_len.min(list.len()) };
+                let matches: Box<dyn Iterator<Item = usize>> = if rank > 0 {
+                    Box::new((0..scanned).filter(|position| list[*position] == element))
+                } else {
+                    Box::new((list.len() - scanned..list.len()).rev().filter(|position| list[*position] == element))
+                };
+                let wanted = match count {
+                    Some(0) => usize::MAX,
+                    Some(count) => count,
+                    None => 1,
+                };
+                matches
+                    .skip(rank.unsigned_abs() as usize - 1)
+                    .take(wanted)
+                    .collect::<Vec<_>>()
+            }
+            None => Vec::new(),
+        };
+
+        Ok(match count {
+            Some(_) => RespType::Array(positions.into_iter().map(|position| RespType::Integer(position as i64)).collect()),
+            None => match positions.first() {
+                Some(position) => RespType::Integer(*position as i64),
+                None => RespType::BulkString(None),
+            },
+        })
+    }
+}
+
+/// `LMOVE source destination LEFT|RIGHT LEFT|RIGHT`
+pub struct LMove;
+
+impl Command for LMove {
+    fn name(&self) -> &str {
+        "LMOVE"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        (0..2.min(args.len())).filter_map(|index| arg_str(args, index, "LMOVE").ok()).collect()
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let source = arg_str(args, 0, self.name())?;
+        let destination = arg_str(args, 1, self.name())?;
+        let from = end_arg(args, 2, self.name())?;
+        let to = end_arg(args, 3, self.name())?;
+
+        Ok(RespType::BulkString(move_element(state, source, destination, from, to)?))
+    }
+}
+
+/// `RPOPLPUSH source destination`, the same as `LMOVE source destination RIGHT LEFT`.
+pub struct RPopLPush;
+
+impl Command for RPopLPush {
+    fn name(&self) -> &str {
+        "RPOPLPUSH"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        (0..2.min(args.len())).filter_map(|index| arg_str(args, index, "RPOPLPUSH").ok()).collect()
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let source = arg_str(args, 0, self.name())?;
+        let destination = arg_str(args, 1, self.name())?;
+
+        Ok(RespType::BulkString(move_element(state, source, destination, End::Right, End::Left)?))
+    }
+}
+
+/// Parses the timeout of a blocking command, in seconds with 0 meaning forever.
+pub(crate) fn deadline_arg(args: &[RespType], index: usize, name: &str) -> Result<Option<Instant>, String> {
+    let timeout = arg_str(args, index, name)?
+        .parse::<f64>()
+        .ok()
+        .and_then(|timeout| Duration::try_from_secs_f64(timeout.max(0.0)).ok().map(|duration| (timeout, duration)))
+        .ok_or_else(|| "timeout is not a float or out of range".to_string())?;
+    match timeout {
+        (timeout, _) if timeout < 0.0 => Err("timeout is negative".to_string()),
+        (_, duration) if duration.is_zero() => Ok(None),
+        (_, duration) => Ok(Some(Instant::now() + duration)),
+    }
+}
+
+/// Serves the client right away when one of the keys has data, and blocks it otherwise.
+pub(crate) fn serve_or_block(
+    wait: KeyWait,
+    state: &mut dyn ServerState,
+    client: &mut ClientContext,
+) -> Result<RespType, String> {
+    match serve(state, &wait)? {
+        Some(reply) => Ok(reply),
+        None => {
+            let reply = match wait.operation {
+                KeyOperation::Move { .. } => RespType::BulkString(None),
+                _ => RespType::NullArray,
+            };
+            client.blocked = Some(BlockedOn::Keys(wait));
+            Ok(reply)
+        }
+    }
+}
+
+/// Keys of `BLPOP`, `BRPOP` and the like: every argument but the trailing timeout.
+pub(crate) fn keys_before_timeout<'a>(args: &'a [RespType], name: &str) -> Vec<&'a str> {
+    (0..args.len().saturating_sub(1)).filter_map(|index| arg_str(args, index, name).ok()).collect()
+}
+
+fn execute_blocking_pop(
+    args: &[RespType],
+    state: &mut dyn ServerState,
+    client: &mut ClientContext,
+    name: &str,
+    end: End,
+) -> Result<RespType, String> {
+    if args.len() < 2 {
+        return Err(format!("wrong number of arguments for '{}' command", name.to_lowercase()));
+    }
+    let keys = keys_before_timeout(args, name).into_iter().map(str::to_string).collect();
+    let deadline = deadline_arg(args, args.len() - 1, name)?;
+    serve_or_block(
+        KeyWait {
+            keys,
+            operation: KeyOperation::Pop(end),
+            deadline,
+        },
+        state,
+        client,
+    )
+}
+
+/// Arguments of `LMPOP`/`BLMPOP` starting at `numkeys`: `numkeys key [key ...] LEFT|RIGHT [COUNT count]`.
+fn mpop_args(args: &[RespType], offset: usize, name: &str) -> Result<(Vec<String>, KeyOperation), String> {
+    let numkeys = arg_i64(args, offset, name)?;
+    if numkeys <= 0 {
+        return Err("numkeys should be greater than 0".to_string());
+    }
+    let numkeys = numkeys as usize;
+    if args.len() < offset + numkeys + 2 {
+        return Err(format!("wrong number of arguments for '{}' command", name.to_lowercase()));
+    }
+    let keys = (offset + 1..offset + 1 + numkeys)
+        .map(|index| arg_str(args, index, name).map(str::to_string))
+        .collect::<Result<Vec<_>, _>>()?;
+    let end = end_arg(args, offset + 1 + numkeys, name)?;
+
+    let count = match &args[offset + 2 + numkeys..] {
+        [] => 1,
+        [_, _] if arg_str(args, offset + 2 + numkeys, name)?.eq_ignore_ascii_case("COUNT") => {
+            match arg_i64(args, offset + 3 + numkeys, name)? {
+                count if count > 0 => count as usize,
+                _ => return Err("count should be greater than 0".to_string()),
+            }
+        }
+        _ => return Err("syntax error".to_string()),
+    };
+    Ok((keys, KeyOperation::MultiPop { end, count }))
+}
+
+/// `BLPOP key [key ...] timeout`
+pub struct BLPop;
+
+impl Command for BLPop {
+    fn name(&self) -> &str {
+        "BLPOP"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn propagate_verbatim(&self) -> bool {
+        false
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        keys_before_timeout(args, self.name())
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        self.execute_with_client(args, state, &mut ClientContext::default())
+    }
+
+    fn execute_with_client(
+        &self,
+        args: &[RespType],
+        state: &mut dyn ServerState,
+        client: &mut ClientContext,
+    ) -> Result<RespType, String> {
+        execute_blocking_pop(args, state, client, self.name(), End::Left)
+    }
+}
+
+/// `BRPOP key [key ...] timeout`
+pub struct BRPop;
+
+impl Command for BRPop {
+    fn name(&self) -> &str {
+        "BRPOP"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn propagate_verbatim(&self) -> bool {
+        false
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        keys_before_timeout(args, self.name())
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        self.execute_with_client(args, state, &mut ClientContext::default())
+    }
+
+    fn execute_with_client(
+        &self,
+        args: &[RespType],
+        state: &mut dyn ServerState,
+        client: &mut ClientContext,
+    ) -> Result<RespType, String> {
+        execute_blocking_pop(args, state, client, self.name(), End::Right)
+    }
+}
+
+/// `BLMOVE source destination LEFT|RIGHT LEFT|RIGHT timeout`
+pub struct BLMove;
+
+impl Command for BLMove {
+    fn name(&self) -> &str {
+        "BLMOVE"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn propagate_verbatim(&self) -> bool {
+        false
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        (0..2.min(args.len())).filter_map(|index| arg_str(args, index, "BLMOVE").ok()).collect()
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        self.execute_with_client(args, state, &mut ClientContext::default())
+    }
+
+    fn execute_with_client(
+        &self,
+        args: &[RespType],
+        state: &mut dyn ServerState,
+        client: &mut ClientContext,
+    ) -> Result<RespType, String> {
+        let source = arg_str(args, 0, self.name())?;
+        let destination = arg_str(args, 1, self.name())?;
+        let from = end_arg(args, 2, self.name())?;
+        let to = end_arg(args, 3, self.name())?;
+        let deadline = deadline_arg(args, 4, self.name())?;
+
+        serve_or_block(
+            KeyWait {
+                keys: vec![source.to_string()],
+                operation: KeyOperation::Move {
+                    destination: destination.to_string(),
+                    from,
+                    to,
+                },
+                deadline,
+            },
+            state,
+            client,
+        )
+    }
+}
+
+/// `LMPOP numkeys key [key ...] LEFT|RIGHT [COUNT count]`: pops from the first non-empty list.
+pub struct LMPop;
+
+impl Command for LMPop {
+    fn name(&self) -> &str {
+        "LMPOP"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn propagate_verbatim(&self) -> bool {
+        false
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        numkeys_keys(args, 0, self.name())
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let (keys, operation) = mpop_args(args, 0, self.name())?;
+        let wait = KeyWait {
+            keys,
+            operation,
+            deadline: None,
+        };
+        Ok(serve(state, &wait)?.unwrap_or(RespType::NullArray))
+    }
+}
+
+/// `BLMPOP timeout numkeys key [key ...] LEFT|RIGHT [COUNT count]`
+pub struct BLMPop;
+
+impl Command for BLMPop {
+    fn name(&self) -> &str {
+        "BLMPOP"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn propagate_verbatim(&self) -> bool {
+        false
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        numkeys_keys(args, 1, self.name())
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        self.execute_with_client(args, state, &mut ClientContext::default())
+    }
+
+    fn execute_with_client(
+        &self,
+        args: &[RespType],
+        state: &mut dyn ServerState,
+        client: &mut ClientContext,
+    ) -> Result<RespType, String> {
+        let deadline = deadline_arg(args, 0, self.name())?;
+        let (keys, operation) = mpop_args(args, 1, self.name())?;
+        serve_or_block(
+            KeyWait {
+                keys,
+                operation,
+                deadline,
+            },
+            state,
+            client,
+        )
+    }
+}
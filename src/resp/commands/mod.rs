@@ -0,0 +1,327 @@
+//! This module defines the RESP commands and their serialization/deserialization logic.
+//! Utilizes Strategy pattern for command handling.
+
+use crate::resp::client::ClientContext;
+use crate::resp::protocol::RespType;
+use crate::resp::state::server_state::ServerState;
+use crate::resp::state::value::{Value, WRONG_TYPE};
+
+pub mod bitmap;
+pub mod bloom;
+pub mod cluster;
+pub mod cms;
+pub mod cuckoo;
+pub mod geo;
+pub mod hash;
+pub mod hyperloglog;
+pub mod json;
+pub mod keys;
+pub mod list;
+pub mod replication;
+pub mod scan;
+pub mod sentinel;
+pub mod set;
+pub mod sorted_set;
+pub mod stream;
+pub mod string;
+pub mod timeseries;
+pub mod topk;
+
+pub use bitmap::{BitCount, BitField, BitFieldRo, BitOp, BitPos, GetBit, SetBit};
+pub use bloom::{BfAdd, BfExists, BfMAdd, BfMExists, BfReserve};
+pub use cluster::{Asking, Cluster, Migrate};
+pub use cms::{CmsIncrBy, CmsInitByDim, CmsInitByProb, CmsMerge, CmsQuery};
+pub use cuckoo::{CfAdd, CfDel, CfExists, CfReserve};
+pub use geo::{GeoAdd, GeoDist, GeoHash, GeoPos, GeoSearch, GeoSearchStore};
+pub use hash::{
+    HDel, HExists, HExpire, HExpireAt, HExpireTime, HGet, HGetAll, HIncrBy, HIncrByFloat, HKeys, HLen, HMGet, HPExpire,
+    HPExpireAt, HPExpireTime, HPTtl, HPersist, HRandField, HScan, HSet, HSetNx, HStrLen, HTtl, HVals,
+};
+pub use hyperloglog::{PfAdd, PfCount, PfMerge};
+pub use json::{
+    JsonArrAppend, JsonArrInsert, JsonArrLen, JsonArrPop, JsonDel, JsonGet, JsonNumIncrBy, JsonObjKeys, JsonSet,
+    JsonStrAppend, JsonType,
+};
+pub use keys::{Del, Dump, Restore, Type};
+pub use list::{
+    BLMPop, BLMove, BLPop, BRPop, LIndex, LInsert, LLen, LMPop, LMove, LPop, LPos, LPush, LPushX, LRange, LRem, LSet, LTrim,
+    RPop, RPopLPush, RPush, RPushX,
+};
+pub use replication::{Info, Psync, Replconf, Replicaof, Wait};
+pub use sentinel::Sentinel;
+pub use set::{
+    SAdd, SCard, SDiff, SDiffStore, SInter, SInterCard, SInterStore, SIsMember, SMIsMember, SMembers, SMove, SPop,
+    SRandMember, SRem, SScan, SUnion, SUnionStore,
+};
+pub use sorted_set::{
+    BZMPop, BZPopMax, BZPopMin, ZAdd, ZCard, ZCount, ZDiff, ZDiffStore, ZIncrBy, ZInter, ZInterCard, ZInterStore,
+    ZMPop, ZMScore, ZPopMax, ZPopMin, ZRange, ZRangeStore, ZRank, ZRem, ZRemRangeByLex, ZRemRangeByRank,
+    ZRemRangeByScore, ZRevRank, ZScan, ZScore, ZUnion, ZUnionStore,
+};
+pub use stream::{
+    XAck, XAdd, XAutoClaim, XClaim, XDel, XGroup, XInfo, XLen, XPending, XRange, XRead, XReadGroup, XRevRange, XTrim,
+};
+pub use string::{
+    Append, Decr, DecrBy, GetDel, GetEx, GetRange, GetSet, Incr, IncrBy, IncrByFloat, MGet, MSet, MSetNx, PSetEx, SetEx,
+    SetNx, SetRange, StrLen,
+};
+pub use timeseries::{
+    TsAdd, TsCreate, TsCreateRule, TsDeleteRule, TsGet, TsMAdd, TsMRange, TsMRevRange, TsRange, TsRevRange,
+};
+pub use topk::{TopKAdd, TopKIncrBy, TopKList, TopKQuery, TopKReserve};
+
+pub trait Command: Send + Sync + 'static {
+    fn name(&self) -> &str;
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String>;
+
+    /// Executes the command on behalf of a specific client connection.
+    /// Commands that need per-connection state (e.g. replication handshake) override this.
+    fn execute_with_client(
+        &self,
+        args: &[RespType],
+        state: &mut dyn ServerState,
+        _client: &mut ClientContext,
+    ) -> Result<RespType, String> {
+        self.execute(args, state)
+    }
+
+    /// Whether the command modifies the dataset and has to be propagated to replicas.
+    fn is_write(&self) -> bool {
+        false
+    }
+
+    /// Whether a write is propagated to replicas as received. Commands whose effect depends
+    /// on when they run, like blocking pops, propagate the commands they performed instead.
+    fn propagate_verbatim(&self) -> bool {
+        true
+    }
+
+    /// Whether the command may run on a replica whose link to the primary is down
+    /// while `replica-serve-stale-data` is disabled.
+    fn allow_stale(&self) -> bool {
+        false
+    }
+
+    /// Keys accessed by the command, used to route it in cluster mode.
+    fn keys<'a>(&self, _args: &'a [RespType]) -> Vec<&'a str> {
+        Vec::new()
+    }
+}
+
+/// Extracts a string argument, accepting both bulk and simple strings.
+pub(crate) fn arg_str<'a>(args: &'a [RespType], index: usize, name: &str) -> Result<&'a str, String> {
+    match args.get(index) {
+        Some(RespType::BulkString(Some(value))) | Some(RespType::SimpleString(value)) => Ok(value),
+        Some(_) => Err(format!("{} arguments must be strings", name)),
+        None => Err(format!("wrong number of arguments for '{}' command", name.to_lowercase())),
+    }
+}
+
+/// Extracts a string argument as bytes, accepting bulk strings that are not UTF-8.
+pub(crate) fn arg_bytes<'a>(args: &'a [RespType], index: usize, name: &str) -> Result<&'a [u8], String> {
+    match args.get(index) {
+        Some(RespType::BulkBytes(value)) => Ok(value),
+        _ => arg_str(args, index, name).map(str::as_bytes),
+    }
+}
+
+/// Extracts an integer argument, accepting both integers and numeric strings.
+pub(crate) fn arg_i64(args: &[RespType], index: usize, name: &str) -> Result<i64, String> {
+    match args.get(index) {
+        Some(RespType::Integer(value)) => Ok(*value),
+        Some(_) => arg_str(args, index, name)?
+            .parse::<i64>()
+            .map_err(|_| "value is not an integer or out of range".to_string()),
+        None => Err(format!("wrong number of arguments for '{}' command", name.to_lowercase())),
+    }
+}
+
+/// Key spec of commands whose only key is the first argument.
+pub(crate) fn first_key(args: &[RespType]) -> Vec<&str> {
+    match args.first() {
+        Some(RespType::BulkString(Some(key))) | Some(RespType::SimpleString(key)) => vec![key.as_str()],
+        _ => Vec::new(),
+    }
+}
+
+/// Key spec of commands taking `numkeys key [key ...]` at `offset`.
+pub(crate) fn numkeys_keys<'a>(args: &'a [RespType], offset: usize, name: &str) -> Vec<&'a str> {
+    let numkeys = arg_i64(args, offset, name).unwrap_or(0).max(0) as usize;
+    (offset + 1..(offset + 1 + numkeys).min(args.len()))
+        .filter_map(|index| arg_str(args, index, name).ok())
+        .collect()
+}
+
+/// Unix time in milliseconds.
+pub(crate) fn current_time_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Builds a RESP array of bulk strings, the way commands are sent over the wire.
+pub fn command_frame(parts: &[&str]) -> RespType {
+    RespType::Array(
+        parts
+            .iter()
+            .map(|part| RespType::BulkString(Some(part.to_string())))
+            .collect(),
+    )
+}
+
+/// Sends a command to the replicas when this server is a primary, for commands that
+/// replicate something else than what the client sent.
+pub(crate) fn propagate(state: &mut dyn ServerState, parts: &[&str]) {
+    if state.replication().is_master() {
+        state.replication().propagate(&command_frame(parts));
+    }
+}
+
+pub struct Ping;
+
+impl Command for Ping {
+    fn name(&self) -> &str {
+        "PING"
+    }
+
+    fn allow_stale(&self) -> bool {
+        true
+    }
+
+    fn execute(
+        &self,
+        _args: &[RespType],
+        _state: &mut dyn ServerState,
+    ) -> Result<RespType, String> {
+        Ok(RespType::SimpleString("PONG".to_string()))
+    }
+}
+
+pub struct Echo;
+
+impl Command for Echo {
+    fn name(&self) -> &str {
+        "ECHO"
+    }
+
+    fn execute(&self, args: &[RespType], _state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.is_empty() {
+            Err("ECHO requires at least one argument".to_string())
+        } else {
+            match &args[0] {
+                RespType::BulkString(Some(value)) => Ok(RespType::BulkString(Some(value.clone()))),
+                RespType::SimpleString(value) => Ok(RespType::SimpleString(value.clone())),
+                _ => Err("ECHO argument must be a string".to_string()),
+            }
+        }
+    }
+}
+
+/// `SET key value [NX | XX] [GET] [EX seconds | PX milliseconds | EXAT timestamp | PXAT timestamp | KEEPTTL]`
+pub struct Set;
+impl Command for Set {
+    fn name(&self) -> &str {
+        "SET"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() < 2 {
+            return Err("SET requires at least two arguments".to_string());
+        }
+        let key = match &args[0] {
+            RespType::BulkString(Some(key)) => key,
+            _ => return Err("SET key and value must be strings".to_string()),
+        };
+        let value = match &args[1] {
+            RespType::BulkString(Some(value)) | RespType::SimpleString(value) => value.clone().into_bytes(),
+            RespType::BulkBytes(value) => value.clone(),
+            RespType::Integer(value) => value.to_string().into_bytes(),
+            _ => return Err("SET key and value must be strings".to_string()),
+        };
+
+        // `Some(true)` for NX, `Some(false)` for XX.
+        let mut only_missing = None;
+        let mut get = false;
+        let mut keep_ttl = false;
+        let mut expires_at = None;
+        let mut index = 2;
+        while index < args.len() {
+            let option = arg_str(args, index, self.name())?.to_uppercase();
+            match option.as_str() {
+                "NX" | "XX" if only_missing.is_none() => only_missing = Some(option == "NX"),
+                "GET" => get = true,
+                "KEEPTTL" if expires_at.is_none() => keep_ttl = true,
+                _ if expires_at.is_none() && !keep_ttl => match string::expire_option(args, index, self.name())? {
+                    Some(time) => {
+                        expires_at = Some(time);
+                        index += 1;
+                    }
+                    None => return Err("syntax error".to_string()),
+                },
+                _ => return Err("syntax error".to_string()),
+            }
+            index += 1;
+        }
+
+        let previous = match state.get(key) {
+            Some(Value::String(previous)) if get => Some(previous.clone()),
+            Some(_) if get => return Err(WRONG_TYPE.to_string()),
+            _ => None,
+        };
+        let reply = match get {
+            true => previous.map_or(RespType::BulkString(None), RespType::bulk_bytes),
+            false => RespType::SimpleString("OK".to_string()),
+        };
+        if only_missing.is_some_and(|only_missing| only_missing == state.exists(key)) {
+            return Ok(if get { reply } else { RespType::BulkString(None) });
+        }
+
+        if keep_ttl {
+            expires_at = state.expires_at(key);
+        }
+        let now = current_time_ms();
+        match expires_at {
+            // Like Redis, a time in the past deletes the key rather than setting it.
+            Some(expires_at) if expires_at <= now => state.del(key)?,
+            expires_at => state
+                .set(key.clone(), Value::String(value), expires_at.map(|expires_at| (expires_at - now) as i64))
+                .map_err(|_| "Failed to set value".to_string())?,
+        }
+        Ok(reply)
+    }
+}
+
+pub struct Get;
+impl Command for Get {
+    fn name(&self) -> &str {
+        "GET"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.is_empty() {
+            Err("GET requires at least one argument".to_string())
+        } else {
+            match &args[0] {
+                RespType::BulkString(Some(key)) => match state.get(key) {
+                    Some(Value::String(value)) => Ok(RespType::bulk_bytes(value.clone())),
+                    Some(_) => Err(WRONG_TYPE.to_string()),
+                    None => Ok(RespType::BulkString(None)),
+                },
+                _ => Err("GET key must be a string".to_string()),
+            }
+        }
+    }
+}
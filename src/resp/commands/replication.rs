@@ -0,0 +1,260 @@
+use std::time::{Duration, Instant};
+
+use crate::resp::client::{BlockedOn, ClientContext};
+use crate::resp::commands::{arg_i64, arg_str, command_frame, Command};
+use crate::resp::protocol::RespType;
+use crate::resp::replication::MasterAddr;
+use crate::resp::state::server_state::ServerState;
+
+pub struct Info;
+
+impl Command for Info {
+    fn name(&self) -> &str {
+        "INFO"
+    }
+
+    fn allow_stale(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let section = match args.first() {
+            Some(_) => arg_str(args, 0, self.name())?.to_lowercase(),
+            None => "default".to_string(),
+        };
+
+        if let Some(sentinel) = state.sentinel() {
+            return Ok(RespType::BulkString(Some(sentinel.info())));
+        }
+
+        let replication = state.replication();
+        let mut sections = Vec::new();
+        if matches!(section.as_str(), "replication" | "default" | "all" | "everything") {
+            sections.push(replication.info());
+        }
+        if matches!(section.as_str(), "stats" | "default" | "all" | "everything") {
+            sections.push(format!(
+                "# Stats\r\nsync_full:{}\r\nsync_partial_ok:{}\r\nsync_partial_err:{}\r\n",
+                replication.stats.full, replication.stats.partial_ok, replication.stats.partial_err
+            ));
+        }
+        if matches!(section.as_str(), "cluster" | "default" | "all" | "everything") {
+            let enabled = state.cluster().is_some() as u8;
+            sections.push(format!("# Cluster\r\ncluster_enabled:{}\r\n", enabled));
+        }
+
+        Ok(RespType::BulkString(Some(sections.join("\r\n"))))
+    }
+}
+
+/// `REPLCONF` is used by replicas to configure the replication link and report their offset.
+pub struct Replconf;
+
+impl Command for Replconf {
+    fn name(&self) -> &str {
+        "REPLCONF"
+    }
+
+    fn allow_stale(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        self.execute_with_client(args, state, &mut ClientContext::default())
+    }
+
+    fn execute_with_client(
+        &self,
+        args: &[RespType],
+        state: &mut dyn ServerState,
+        client: &mut ClientContext,
+    ) -> Result<RespType, String> {
+        if args.is_empty() || !args.len().is_multiple_of(2) {
+            return Err("REPLCONF requires option/value pairs".to_string());
+        }
+
+        for pair in (0..args.len()).step_by(2) {
+            let option = arg_str(args, pair, self.name())?.to_lowercase();
+            match option.as_str() {
+                "listening-port" => {
+                    let port = arg_i64(args, pair + 1, self.name())?;
+                    let port = u16::try_from(port).map_err(|_| "Invalid listening port".to_string())?;
+                    client.listening_port = Some(port);
+                }
+                "capa" => {
+                    let capability = arg_str(args, pair + 1, self.name())?.to_lowercase();
+                    client.capabilities.push(capability);
+                }
+                "ack" => {
+                    let offset = arg_i64(args, pair + 1, self.name())?;
+                    if let (Some(id), Ok(offset)) = (client.replica_id, u64::try_from(offset)) {
+                        state.replication().record_ack(id, offset);
+                    }
+                }
+                "getack" => {
+                    let offset = state.replication().offset().to_string();
+                    return Ok(command_frame(&["REPLCONF", "ACK", &offset]));
+                }
+                _ => return Err(format!("Unrecognized REPLCONF option: {}", option)),
+            }
+        }
+
+        Ok(RespType::SimpleString("OK".to_string()))
+    }
+}
+
+/// `PSYNC replid offset` turns the connection into a replication stream, continuing
+/// from the backlog when possible and falling back to a full RDB transfer otherwise.
+pub struct Psync;
+
+impl Command for Psync {
+    fn name(&self) -> &str {
+        "PSYNC"
+    }
+
+    fn execute(&self, _args: &[RespType], _state: &mut dyn ServerState) -> Result<RespType, String> {
+        Err("PSYNC can only be used by a replica connection".to_string())
+    }
+
+    fn execute_with_client(
+        &self,
+        args: &[RespType],
+        state: &mut dyn ServerState,
+        client: &mut ClientContext,
+    ) -> Result<RespType, String> {
+        if args.len() < 2 {
+            return Err("PSYNC requires replication id and offset".to_string());
+        }
+        if client.is_master || client.replica_id.is_some() {
+            return Err("Connection is already part of a replication link".to_string());
+        }
+
+        let replid = arg_str(args, 0, self.name())?;
+        let offset = arg_i64(args, 1, self.name())?;
+        let ip = client
+            .addr
+            .map(|addr| addr.ip().to_string())
+            .unwrap_or_default();
+        let port = client.listening_port.unwrap_or_default();
+
+        let continued = state.replication().partial_resync(replid, offset);
+        let (response, synced_offset, initial) = match continued {
+            Some(missing) => {
+                state.replication().stats.partial_ok += 1;
+                let response = if client.capabilities.iter().any(|capa| capa == "psync2") {
+                    format!("CONTINUE {}", state.replication().replid())
+                } else {
+                    "CONTINUE".to_string()
+                };
+                (response, offset as u64 - 1, missing)
+            }
+            None => {
+                let replication = state.replication();
+                if replid != "?" {
+                    replication.stats.partial_err += 1;
+                }
+                replication.stats.full += 1;
+                let synced_offset = replication.offset();
+                let response = format!("FULLRESYNC {} {}", replication.replid(), synced_offset);
+
+                let rdb = state.dump_rdb();
+                let mut payload = format!("${}\r\n", rdb.len()).into_bytes();
+                payload.extend(rdb);
+                (response, synced_offset, payload)
+            }
+        };
+
+        let (id, receiver) = state.replication().register_replica(ip, port, synced_offset, initial);
+        client.replica_id = Some(id);
+        client.replication_stream = Some(receiver);
+
+        Ok(RespType::SimpleString(response))
+    }
+}
+
+/// `WAIT numreplicas timeout` blocks the client until enough replicas acknowledged
+/// every write issued so far, or the timeout (in milliseconds, 0 meaning forever) expires.
+pub struct Wait;
+
+impl Command for Wait {
+    fn name(&self) -> &str {
+        "WAIT"
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        self.execute_with_client(args, state, &mut ClientContext::default())
+    }
+
+    fn execute_with_client(
+        &self,
+        args: &[RespType],
+        state: &mut dyn ServerState,
+        client: &mut ClientContext,
+    ) -> Result<RespType, String> {
+        if args.len() != 2 {
+            return Err("WAIT requires numreplicas and timeout".to_string());
+        }
+        let numreplicas = usize::try_from(arg_i64(args, 0, self.name())?)
+            .map_err(|_| "numreplicas must be a non-negative integer".to_string())?;
+        let timeout = u64::try_from(arg_i64(args, 1, self.name())?)
+            .map_err(|_| "timeout is negative".to_string())?;
+
+        let replication = state.replication();
+        if !replication.is_master() {
+            return Err("WAIT cannot be used with replica instances".to_string());
+        }
+
+        let offset = replication.offset();
+        let acked = replication.count_acked(offset);
+        if acked < numreplicas {
+            replication.request_acks();
+            client.blocked = Some(BlockedOn::Replicas {
+                numreplicas,
+                offset,
+                deadline: (timeout > 0).then(|| Instant::now() + Duration::from_millis(timeout)),
+            });
+        }
+
+        Ok(RespType::Integer(acked as i64))
+    }
+}
+
+/// `REPLICAOF host port` starts following another primary, `REPLICAOF NO ONE`
+/// promotes this server to a primary keeping its dataset.
+pub struct Replicaof;
+
+impl Command for Replicaof {
+    fn name(&self) -> &str {
+        "REPLICAOF"
+    }
+
+    fn allow_stale(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() != 2 {
+            return Err("REPLICAOF requires host and port, or NO ONE".to_string());
+        }
+        let host = arg_str(args, 0, self.name())?;
+        let port = arg_str(args, 1, self.name())?;
+
+        let replication = state.replication();
+        if host.eq_ignore_ascii_case("no") && port.eq_ignore_ascii_case("one") {
+            replication.promote();
+            return Ok(RespType::SimpleString("OK".to_string()));
+        }
+
+        let addr = MasterAddr {
+            host: host.to_string(),
+            port: port.parse().map_err(|_| "Invalid master port".to_string())?,
+        };
+        if replication.master().is_some_and(|master| master.addr == addr) {
+            return Ok(RespType::SimpleString(
+                "OK Already connected to specified master".to_string(),
+            ));
+        }
+        replication.follow(addr);
+        Ok(RespType::SimpleString("OK".to_string()))
+    }
+}
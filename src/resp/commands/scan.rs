@@ -0,0 +1,139 @@
+//! Shared parts of the `*SCAN` commands: cursors and glob-style patterns.
+
+use crate::resp::commands::{arg_i64, arg_str};
+use crate::resp::protocol::RespType;
+
+/// Options of `HSCAN`/`SSCAN`/`ZSCAN` after the key.
+pub(crate) struct ScanArgs {
+    pub cursor: u64,
+    pub pattern: Option<String>,
+    pub count: usize,
+    pub novalues: bool,
+}
+
+/// Parses `cursor [MATCH pattern] [COUNT count] [NOVALUES]` starting at `offset`.
+pub(crate) fn scan_args(args: &[RespType], offset: usize, name: &str, allow_novalues: bool) -> Result<ScanArgs, String> {
+    let cursor = arg_str(args, offset, name)?
+        .parse::<u64>()
+        .map_err(|_| "invalid cursor".to_string())?;
+
+    let mut scan = ScanArgs {
+        cursor,
+        pattern: None,
+        count: 10,
+        novalues: false,
+    };
+    let mut index = offset + 1;
+    while index < args.len() {
+        match arg_str(args, index, name)?.to_uppercase().as_str() {
+            "MATCH" => {
+                scan.pattern = Some(arg_str(args, index + 1, name)?.to_string());
+                index += 2;
+            }
+            "COUNT" => {
+                scan.count = match arg_i64(args, index + 1, name)? {
+                    count if count >= 1 => count as usize,
+                    _ => return Err("syntax error".to_string()),
+                };
+                index += 2;
+            }
+            "NOVALUES" if allow_novalues => {
+                scan.novalues = true;
+                index += 1;
+            }
+            _ => return Err("syntax error".to_string()),
+        }
+    }
+    Ok(scan)
+}
+
+/// Position of an element in the scan order. Elements are visited by increasing hash of
+/// their name, so a cursor stays meaningful while the collection changes: elements present
+/// during the whole scan are returned exactly once.
+fn position(element: &str) -> u64 {
+    let hash = element
+        .bytes()
+        .fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3));
+    // 0 is the cursor that ends the scan.
+    hash.max(1)
+}
+
+/// Returns the cursor of the next call (0 once done) and the elements of this page that
+/// match the pattern.
+pub(crate) fn scan_page<K: AsRef<str>, T>(
+    elements: impl Iterator<Item = (K, T)>,
+    scan: &ScanArgs,
+) -> (u64, Vec<(K, T)>) {
+    let mut candidates = elements
+        .map(|(element, value)| (position(element.as_ref()), element, value))
+        .filter(|(position, _, _)| *position >= scan.cursor)
+        .collect::<Vec<_>>();
+    candidates.sort_by_key(|(position, _, _)| *position);
+
+    // Elements sharing a position are returned together so that none is skipped.
+    let mut end = scan.count.min(candidates.len());
+    while end > 0 && end < candidates.len() && candidates[end].0 == candidates[end - 1].0 {
+        end += 1;
+    }
+    let next = candidates.get(end).map_or(0, |(position, _, _)| *position);
+    candidates.truncate(end);
+
+    let page = candidates
+        .into_iter()
+        .filter(|(_, element, _)| scan.pattern.as_ref().is_none_or(|pattern| glob_match(pattern, element.as_ref())))
+        .map(|(_, element, value)| (element, value))
+        .collect();
+    (next, page)
+}
+
+/// Glob-style matching as used by `KEYS` and `MATCH`: `*`, `?`, `[abc]`, `[^a-z]` and `\` escapes.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+    matches_at(&pattern, &text)
+}
+
+fn matches_at(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') => (0..=text.len()).any(|skip| matches_at(&pattern[1..], &text[skip..])),
+        Some('?') => !text.is_empty() && matches_at(&pattern[1..], &text[1..]),
+        Some('[') => {
+            let Some(&c) = text.first() else { return false };
+            match class_matches(&pattern[1..], c) {
+                Some((matched, len)) => matched && matches_at(&pattern[1 + len..], &text[1..]),
+                // An unterminated class is taken literally.
+                None => c == '[' && matches_at(&pattern[1..], &text[1..]),
+            }
+        }
+        Some('\\') if pattern.len() > 1 => text.first() == Some(&pattern[1]) && matches_at(&pattern[2..], &text[1..]),
+        Some(p) => text.first() == Some(p) && matches_at(&pattern[1..], &text[1..]),
+    }
+}
+
+/// Matches `c` against a character class following `[`. Returns whether it matched and
+/// how many pattern characters the class used, including the closing `]`.
+fn class_matches(class: &[char], c: char) -> Option<(bool, usize)> {
+    let negated = class.first() == Some(&'^');
+    let mut index = negated as usize;
+    let mut matched = false;
+    while index < class.len() {
+        match class[index] {
+            ']' => return Some((matched != negated, index + 1)),
+            '\\' if index + 1 < class.len() => {
+                matched |= class[index + 1] == c;
+                index += 2;
+            }
+            start if index + 2 < class.len() && class[index + 1] == '-' && class[index + 2] != ']' => {
+                let end = class[index + 2];
+                matched |= (start.min(end)..=start.max(end)).contains(&c);
+                index += 3;
+            }
+            other => {
+                matched |= other == c;
+                index += 1;
+            }
+        }
+    }
+    None
+}
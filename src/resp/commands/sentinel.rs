@@ -0,0 +1,147 @@
+use crate::resp::commands::{arg_i64, arg_str, Command};
+use crate::resp::protocol::RespType;
+use crate::resp::replication::MasterAddr;
+use crate::resp::sentinel::{MonitoredMaster, SentinelState};
+use crate::resp::state::server_state::ServerState;
+
+/// `SENTINEL <subcommand>`, only available when running in sentinel mode.
+pub struct Sentinel;
+
+impl Command for Sentinel {
+    fn name(&self) -> &str {
+        "SENTINEL"
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let Some(sentinel) = state.sentinel() else {
+            return Err("This instance is not running in sentinel mode".to_string());
+        };
+        let subcommand = arg_str(args, 0, self.name())?.to_lowercase();
+
+        match subcommand.as_str() {
+            "myid" => Ok(RespType::BulkString(Some(sentinel.myid.clone()))),
+            "get-master-addr-by-name" => {
+                let name = arg_str(args, 1, self.name())?;
+                Ok(match sentinel.master(name) {
+                    Some(master) => RespType::Array(vec![
+                        RespType::BulkString(Some(master.addr.host.clone())),
+                        RespType::BulkString(Some(master.addr.port.to_string())),
+                    ]),
+                    None => RespType::BulkString(None),
+                })
+            }
+            "masters" => Ok(RespType::Array(
+                sentinel
+                    .masters
+                    .iter()
+                    .map(|master| pairs(sentinel.describe(master)))
+                    .collect(),
+            )),
+            "master" => {
+                let master = find_master(sentinel, arg_str(args, 1, self.name())?)?;
+                Ok(pairs(sentinel.describe(master)))
+            }
+            "replicas" | "slaves" => {
+                let master = find_master(sentinel, arg_str(args, 1, self.name())?)?;
+                Ok(RespType::Array(
+                    master
+                        .replicas
+                        .iter()
+                        .map(|replica| {
+                            pairs(vec![
+                                ("name".to_string(), format!("{}:{}", replica.addr.host, replica.addr.port)),
+                                ("ip".to_string(), replica.addr.host.clone()),
+                                ("port".to_string(), replica.addr.port.to_string()),
+                                ("flags".to_string(), "slave".to_string()),
+                            ])
+                        })
+                        .collect(),
+                ))
+            }
+            "sentinels" => {
+                find_master(sentinel, arg_str(args, 1, self.name())?)?;
+                Ok(RespType::Array(
+                    sentinel
+                        .peers
+                        .iter()
+                        .map(|peer| {
+                            pairs(vec![
+                                ("ip".to_string(), peer.addr.host.clone()),
+                                ("port".to_string(), peer.addr.port.to_string()),
+                                ("runid".to_string(), peer.runid.clone().unwrap_or_default()),
+                            ])
+                        })
+                        .collect(),
+                ))
+            }
+            "is-master-down-by-addr" => {
+                let addr = MasterAddr {
+                    host: arg_str(args, 1, self.name())?.to_string(),
+                    port: port_arg(args, 2)?,
+                };
+                let epoch = epoch_arg(args, 3)?;
+                let runid = arg_str(args, 4, self.name())?;
+
+                let down = sentinel
+                    .masters
+                    .iter()
+                    .any(|master| master.addr == addr && master.subjectively_down);
+                // `*` only asks for our opinion, a run id asks for our vote.
+                let (leader, leader_epoch) = match runid {
+                    "*" => ("*".to_string(), 0),
+                    _ if down => sentinel.vote(&addr, epoch, runid).unwrap_or(("*".to_string(), 0)),
+                    _ => ("*".to_string(), 0),
+                };
+
+                Ok(RespType::Array(vec![
+                    RespType::Integer(down as i64),
+                    RespType::BulkString(Some(leader)),
+                    RespType::Integer(leader_epoch as i64),
+                ]))
+            }
+            "hello" => {
+                // SENTINEL HELLO <name> <host> <port> <config-epoch> <runid> <host> <port> <current-epoch>
+                let name = arg_str(args, 1, self.name())?.to_string();
+                let addr = MasterAddr {
+                    host: arg_str(args, 2, self.name())?.to_string(),
+                    port: port_arg(args, 3)?,
+                };
+                let config_epoch = epoch_arg(args, 4)?;
+                let runid = arg_str(args, 5, self.name())?.to_string();
+                let peer = MasterAddr {
+                    host: arg_str(args, 6, self.name())?.to_string(),
+                    port: port_arg(args, 7)?,
+                };
+                let epoch = epoch_arg(args, 8)?;
+                sentinel.hello(&name, addr, config_epoch, &runid, &peer, epoch);
+                Ok(RespType::SimpleString("OK".to_string()))
+            }
+            _ => Err(format!("Unknown sentinel subcommand '{}'", subcommand)),
+        }
+    }
+}
+
+fn find_master<'a>(sentinel: &'a SentinelState, name: &str) -> Result<&'a MonitoredMaster, String> {
+    sentinel
+        .masters
+        .iter()
+        .find(|master| master.name == name)
+        .ok_or_else(|| "No such master with that name".to_string())
+}
+
+fn pairs(fields: Vec<(String, String)>) -> RespType {
+    RespType::Array(
+        fields
+            .into_iter()
+            .flat_map(|(field, value)| [RespType::BulkString(Some(field)), RespType::BulkString(Some(value))])
+            .collect(),
+    )
+}
+
+fn port_arg(args: &[RespType], index: usize) -> Result<u16, String> {
+    u16::try_from(arg_i64(args, index, "SENTINEL")?).map_err(|_| "Invalid port".to_string())
+}
+
+fn epoch_arg(args: &[RespType], index: usize) -> Result<u64, String> {
+    u64::try_from(arg_i64(args, index, "SENTINEL")?).map_err(|_| "Invalid epoch".to_string())
+}
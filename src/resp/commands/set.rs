@@ -0,0 +1,578 @@
+//! Set commands. Sets are deleted as soon as their last member is removed.
+
+use std::collections::HashSet;
+
+use crate::resp::commands::scan::{scan_args, scan_page};
+use crate::resp::commands::{arg_i64, arg_str, first_key, numkeys_keys, propagate, Command};
+use crate::resp::protocol::RespType;
+use crate::resp::random::sample;
+use crate::resp::state::member_set::MemberSet;
+use crate::resp::state::server_state::ServerState;
+use crate::resp::state::value::{Value, WRONG_TYPE};
+
+fn set<'a>(state: &'a mut dyn ServerState, key: &str) -> Result<Option<&'a mut MemberSet>, String> {
+    match state.get_mut(key) {
+        Some(Value::Set(set)) => Ok(Some(set)),
+        Some(_) => Err(WRONG_TYPE.to_string()),
+        None => Ok(None),
+    }
+}
+
+/// Returns the set stored at `key`, creating an empty one when the key does not exist.
+fn set_or_create<'a>(state: &'a mut dyn ServerState, key: &str) -> Result<&'a mut MemberSet, String> {
+    if set(state, key)?.is_none() {
+        state.set(key.to_string(), Value::Set(MemberSet::default()), None)?;
+    }
+    set(state, key)?.ok_or_else(|| "Failed to create set".to_string())
+}
+
+fn delete_if_empty(state: &mut dyn ServerState, key: &str) -> Result<(), String> {
+    if matches!(state.get(key), Some(Value::Set(set)) if set.is_empty()) {
+        state.del(key)?;
+    }
+    Ok(())
+}
+
+fn bulk(value: &str) -> RespType {
+    RespType::BulkString(Some(value.to_string()))
+}
+
+fn array(members: impl IntoIterator<Item = String>) -> RespType {
+    RespType::Array(members.into_iter().map(|member| RespType::BulkString(Some(member))).collect())
+}
+
+/// String arguments from `offset` on, at least one.
+fn rest_args<'a>(args: &'a [RespType], offset: usize, name: &str) -> Result<Vec<&'a str>, String> {
+    if args.len() <= offset {
+        return Err(format!("wrong number of arguments for '{}' command", name.to_lowercase()));
+    }
+    (offset..args.len()).map(|index| arg_str(args, index, name)).collect()
+}
+
+#[derive(Clone, Copy)]
+enum Operation {
+    Inter,
+    Union,
+    Diff,
+}
+
+/// Applies the operation to the sets at `keys`, missing keys counting as empty sets.
+fn combine(state: &mut dyn ServerState, keys: &[&str], operation: Operation) -> Result<Vec<String>, String> {
+    // Every key is type checked, even when the result is known early.
+    let sets = keys
+        .iter()
+        .map(|key| Ok(set(state, key)?.cloned()))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    Ok(match operation {
+        Operation::Inter => {
+            if sets.iter().any(Option::is_none) {
+                return Ok(Vec::new());
+            }
+            let mut sets = sets.into_iter().flatten().collect::<Vec<_>>();
+            sets.sort_by_key(MemberSet::len);
+            let Some((smallest, others)) = sets.split_first() else {
+                return Ok(Vec::new());
+            };
+            smallest
+                .iter()
+                .filter(|member| others.iter().all(|other| other.contains(member)))
+                .map(|member| member.into_owned())
+                .collect()
+        }
+        Operation::Union => sets
+            .iter()
+            .flatten()
+            .flat_map(|set| set.iter().map(|member| member.into_owned()))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect(),
+        Operation::Diff => {
+            let Some((Some(first), others)) = sets.split_first() else {
+                return Ok(Vec::new());
+            };
+            first
+                .iter()
+                .filter(|member| others.iter().flatten().all(|other| !other.contains(member)))
+                .map(|member| member.into_owned())
+                .collect()
+        }
+    })
+}
+
+/// Replaces `destination` with the members, deleting it when there are none.
+fn store(state: &mut dyn ServerState, destination: &str, members: Vec<String>) -> Result<RespType, String> {
+    let len = members.len();
+    state.del(destination)?;
+    if len > 0 {
+        state.set(destination.to_string(), Value::Set(members.into_iter().collect()), None)?;
+    }
+    Ok(RespType::Integer(len as i64))
+}
+
+fn execute_combine(
+    args: &[RespType],
+    state: &mut dyn ServerState,
+    name: &str,
+    operation: Operation,
+) -> Result<RespType, String> {
+    let keys = rest_args(args, 0, name)?;
+    Ok(array(combine(state, &keys, operation)?))
+}
+
+fn execute_combine_store(
+    args: &[RespType],
+    state: &mut dyn ServerState,
+    name: &str,
+    operation: Operation,
+) -> Result<RespType, String> {
+    let destination = arg_str(args, 0, name)?;
+    let keys = rest_args(args, 1, name)?;
+    let members = combine(state, &keys, operation)?;
+    store(state, destination, members)
+}
+
+/// `SADD key member [member ...]`: returns the number of members added.
+pub struct SAdd;
+
+impl Command for SAdd {
+    fn name(&self) -> &str {
+        "SADD"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let members = rest_args(args, 1, self.name())?;
+
+        let set = set_or_create(state, key)?;
+        let added = members.into_iter().filter(|member| set.insert(member.to_string())).count();
+        Ok(RespType::Integer(added as i64))
+    }
+}
+
+/// `SREM key member [member ...]`: returns the number of members removed.
+pub struct SRem;
+
+impl Command for SRem {
+    fn name(&self) -> &str {
+        "SREM"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let members = rest_args(args, 1, self.name())?;
+
+        let Some(set) = set(state, key)? else {
+            return Ok(RespType::Integer(0));
+        };
+        let removed = members.into_iter().filter(|member| set.remove(member)).count();
+        delete_if_empty(state, key)?;
+        Ok(RespType::Integer(removed as i64))
+    }
+}
+
+/// `SISMEMBER key member`
+pub struct SIsMember;
+
+impl Command for SIsMember {
+    fn name(&self) -> &str {
+        "SISMEMBER"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let member = arg_str(args, 1, self.name())?;
+        let is_member = set(state, key)?.is_some_and(|set| set.contains(member));
+        Ok(RespType::Integer(is_member as i64))
+    }
+}
+
+/// `SMISMEMBER key member [member ...]`
+pub struct SMIsMember;
+
+impl Command for SMIsMember {
+    fn name(&self) -> &str {
+        "SMISMEMBER"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let members = rest_args(args, 1, self.name())?;
+
+        let set = set(state, key)?;
+        Ok(RespType::Array(
+            members
+                .into_iter()
+                .map(|member| RespType::Integer(set.as_ref().is_some_and(|set| set.contains(member)) as i64))
+                .collect(),
+        ))
+    }
+}
+
+/// `SMEMBERS key`
+pub struct SMembers;
+
+impl Command for SMembers {
+    fn name(&self) -> &str {
+        "SMEMBERS"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let members = set(state, key)?.map(|set| set.iter().map(|member| member.into_owned()).collect::<Vec<_>>());
+        Ok(array(members.unwrap_or_default()))
+    }
+}
+
+/// `SCARD key`
+pub struct SCard;
+
+impl Command for SCard {
+    fn name(&self) -> &str {
+        "SCARD"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        Ok(RespType::Integer(set(state, key)?.map_or(0, |set| set.len() as i64)))
+    }
+}
+
+/// `SPOP key [count]`: removes random members. Replicas receive the `SREM` of the popped
+/// members so that they remove the same ones.
+pub struct SPop;
+
+impl Command for SPop {
+    fn name(&self) -> &str {
+        "SPOP"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn propagate_verbatim(&self) -> bool {
+        false
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let count = match args.get(1) {
+            Some(_) => match arg_i64(args, 1, self.name())? {
+                count if count >= 0 => Some(count as usize),
+                _ => return Err("value is out of range, must be positive".to_string()),
+            },
+            None => None,
+        };
+        if args.len() > 2 {
+            return Err("syntax error".to_string());
+        }
+
+        let popped = match set(state, key)? {
+            Some(set) => (0..count.unwrap_or(1)).map_while(|_| set.pop_random()).collect::<Vec<_>>(),
+            None => Vec::new(),
+        };
+        delete_if_empty(state, key)?;
+        if !popped.is_empty() {
+            let mut parts = vec!["SREM", key];
+            parts.extend(popped.iter().map(String::as_str));
+            propagate(state, &parts);
+        }
+
+        Ok(match count {
+            Some(_) => array(popped),
+            None => RespType::BulkString(popped.into_iter().next()),
+        })
+    }
+}
+
+/// `SRANDMEMBER key [count]`: a negative count allows the same member more than once.
+pub struct SRandMember;
+
+impl Command for SRandMember {
+    fn name(&self) -> &str {
+        "SRANDMEMBER"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let count = match args.get(1) {
+            Some(_) => Some(arg_i64(args, 1, self.name())?),
+            None => None,
+        };
+        if args.len() > 2 {
+            return Err("syntax error".to_string());
+        }
+
+        let set = set(state, key)?;
+        Ok(match count {
+            Some(count) => {
+                let members = set.map(|set| set.iter().map(|member| member.into_owned()).collect());
+                array(sample(members.unwrap_or_default(), count)?)
+            }
+            None => RespType::BulkString(set.and_then(|set| set.random_member())),
+        })
+    }
+}
+
+/// `SMOVE source destination member`
+pub struct SMove;
+
+impl Command for SMove {
+    fn name(&self) -> &str {
+        "SMOVE"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        (0..2.min(args.len())).filter_map(|index| arg_str(args, index, "SMOVE").ok()).collect()
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let source = arg_str(args, 0, self.name())?;
+        let destination = arg_str(args, 1, self.name())?;
+        let member = arg_str(args, 2, self.name())?;
+
+        // The destination is checked first so that nothing is removed when it cannot be added.
+        set(state, destination)?;
+        let Some(source_set) = set(state, source)? else {
+            return Ok(RespType::Integer(0));
+        };
+        if source == destination {
+            return Ok(RespType::Integer(source_set.contains(member) as i64));
+        }
+        if !source_set.remove(member) {
+            return Ok(RespType::Integer(0));
+        }
+        delete_if_empty(state, source)?;
+        set_or_create(state, destination)?.insert(member.to_string());
+        Ok(RespType::Integer(1))
+    }
+}
+
+/// `SINTER key [key ...]`
+pub struct SInter;
+
+impl Command for SInter {
+    fn name(&self) -> &str {
+        "SINTER"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        (0..args.len()).filter_map(|index| arg_str(args, index, "SINTER").ok()).collect()
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_combine(args, state, self.name(), Operation::Inter)
+    }
+}
+
+/// `SINTERCARD numkeys key [key ...] [LIMIT limit]`: size of the intersection, counting
+/// at most `limit` members (0 for no limit).
+pub struct SInterCard;
+
+impl Command for SInterCard {
+    fn name(&self) -> &str {
+        "SINTERCARD"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        numkeys_keys(args, 0, "SINTERCARD")
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let numkeys = arg_i64(args, 0, self.name())?;
+        if numkeys <= 0 {
+            return Err("numkeys should be greater than 0".to_string());
+        }
+        let numkeys = numkeys as usize;
+        if numkeys >= args.len() {
+            return Err("Number of keys can't be greater than number of args".to_string());
+        }
+        let keys = (1..=numkeys)
+            .map(|index| arg_str(args, index, self.name()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let limit = match args.len() - numkeys - 1 {
+            0 => 0,
+            2 if arg_str(args, numkeys + 1, self.name())?.eq_ignore_ascii_case("LIMIT") => {
+                match arg_i64(args, numkeys + 2, self.name())? {
+                    limit if limit >= 0 => limit as usize,
+                    _ => return Err("LIMIT can't be negative".to_string()),
+                }
+            }
+            _ => return Err("syntax error".to_string()),
+        };
+
+        let len = combine(state, &keys, Operation::Inter)?.len();
+        let len = if limit > 0 { len.min(limit) } else { len };
+        Ok(RespType::Integer(len as i64))
+    }
+}
+
+/// `SINTERSTORE destination key [key ...]`
+pub struct SInterStore;
+
+impl Command for SInterStore {
+    fn name(&self) -> &str {
+        "SINTERSTORE"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        (0..args.len()).filter_map(|index| arg_str(args, index, "SINTERSTORE").ok()).collect()
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_combine_store(args, state, self.name(), Operation::Inter)
+    }
+}
+
+/// `SUNION key [key ...]`
+pub struct SUnion;
+
+impl Command for SUnion {
+    fn name(&self) -> &str {
+        "SUNION"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        (0..args.len()).filter_map(|index| arg_str(args, index, "SUNION").ok()).collect()
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_combine(args, state, self.name(), Operation::Union)
+    }
+}
+
+/// `SUNIONSTORE destination key [key ...]`
+pub struct SUnionStore;
+
+impl Command for SUnionStore {
+    fn name(&self) -> &str {
+        "SUNIONSTORE"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        (0..args.len()).filter_map(|index| arg_str(args, index, "SUNIONSTORE").ok()).collect()
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_combine_store(args, state, self.name(), Operation::Union)
+    }
+}
+
+/// `SDIFF key [key ...]`: members of the first set missing from all the others.
+pub struct SDiff;
+
+impl Command for SDiff {
+    fn name(&self) -> &str {
+        "SDIFF"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        (0..args.len()).filter_map(|index| arg_str(args, index, "SDIFF").ok()).collect()
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_combine(args, state, self.name(), Operation::Diff)
+    }
+}
+
+/// `SDIFFSTORE destination key [key ...]`
+pub struct SDiffStore;
+
+impl Command for SDiffStore {
+    fn name(&self) -> &str {
+        "SDIFFSTORE"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        (0..args.len()).filter_map(|index| arg_str(args, index, "SDIFFSTORE").ok()).collect()
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_combine_store(args, state, self.name(), Operation::Diff)
+    }
+}
+
+/// `SSCAN key cursor [MATCH pattern] [COUNT count]`
+pub struct SScan;
+
+impl Command for SScan {
+    fn name(&self) -> &str {
+        "SSCAN"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let scan = scan_args(args, 1, self.name(), false)?;
+
+        let (cursor, members) = match set(state, key)? {
+            Some(set) => {
+                let (cursor, page) = scan_page(set.iter().map(|member| (member, ())), &scan);
+                (cursor, page.into_iter().map(|(member, _)| member.into_owned()).collect())
+            }
+            None => (0, Vec::new()),
+        };
+        Ok(RespType::Array(vec![bulk(&cursor.to_string()), array(members)]))
+    }
+}
@@ -0,0 +1,1393 @@
+//! Sorted set commands. Sorted sets are deleted as soon as their last member is removed.
+//! The blocking pops wait in `blocking` like the list ones.
+
+use std::collections::HashMap;
+
+use crate::resp::blocking::{serve, KeyOperation, KeyWait};
+use crate::resp::client::ClientContext;
+use crate::resp::commands::list::{deadline_arg, keys_before_timeout, serve_or_block};
+use crate::resp::commands::scan::{scan_args, scan_page};
+use crate::resp::commands::{arg_i64, arg_str, first_key, numkeys_keys, Command};
+use crate::resp::protocol::RespType;
+use crate::resp::state::server_state::ServerState;
+use crate::resp::state::sorted_set::{format_score, SortedSet};
+use crate::resp::state::value::{Value, WRONG_TYPE};
+
+pub(crate) fn zset<'a>(state: &'a mut dyn ServerState, key: &str) -> Result<Option<&'a mut SortedSet>, String> {
+    match state.get_mut(key) {
+        Some(Value::SortedSet(zset)) => Ok(Some(zset)),
+        Some(_) => Err(WRONG_TYPE.to_string()),
+        None => Ok(None),
+    }
+}
+
+/// Returns the sorted set stored at `key`, creating an empty one when the key does not exist.
+pub(crate) fn zset_or_create<'a>(state: &'a mut dyn ServerState, key: &str) -> Result<&'a mut SortedSet, String> {
+    if zset(state, key)?.is_none() {
+        state.set(key.to_string(), Value::SortedSet(SortedSet::default()), None)?;
+    }
+    zset(state, key)?.ok_or_else(|| "Failed to create sorted set".to_string())
+}
+
+fn delete_if_empty(state: &mut dyn ServerState, key: &str) -> Result<(), String> {
+    if matches!(state.get(key), Some(Value::SortedSet(zset)) if zset.is_empty()) {
+        state.del(key)?;
+    }
+    Ok(())
+}
+
+fn bulk(value: &str) -> RespType {
+    RespType::BulkString(Some(value.to_string()))
+}
+
+pub(crate) fn score_reply(score: f64) -> RespType {
+    bulk(&format_score(score))
+}
+
+/// Members, each followed by its score when `with_scores` is set.
+fn entries_reply(entries: Vec<(String, f64)>, with_scores: bool) -> RespType {
+    RespType::Array(
+        entries
+            .into_iter()
+            .flat_map(|(member, score)| {
+                let mut reply = vec![RespType::BulkString(Some(member))];
+                if with_scores {
+                    reply.push(score_reply(score));
+                }
+                reply
+            })
+            .collect(),
+    )
+}
+
+/// Parses a score, accepting `inf`, `+inf` and `-inf` but not NaN.
+pub(crate) fn parse_score(value: &str) -> Option<f64> {
+    value.parse::<f64>().ok().filter(|score| !score.is_nan())
+}
+
+fn score_arg(args: &[RespType], index: usize, name: &str) -> Result<f64, String> {
+    parse_score(arg_str(args, index, name)?).ok_or_else(|| "value is not a valid float".to_string())
+}
+
+/// Where a range of `ZRANGE` and friends starts or ends.
+enum Bound {
+    Score { score: f64, exclusive: bool },
+    /// `-` and `+`.
+    LexMin,
+    LexMax,
+    Lex { member: String, exclusive: bool },
+}
+
+fn score_bound(value: &str) -> Result<Bound, String> {
+    let (value, exclusive) = match value.strip_prefix('(') {
+        Some(value) => (value, true),
+        None => (value, false),
+    };
+    let score = parse_score(value).ok_or_else(|| "min or max is not a float".to_string())?;
+    Ok(Bound::Score { score, exclusive })
+}
+
+fn lex_bound(value: &str) -> Result<Bound, String> {
+    match value {
+        "-" => Ok(Bound::LexMin),
+        "+" => Ok(Bound::LexMax),
+        _ => match (value.strip_prefix('['), value.strip_prefix('(')) {
+            (Some(member), _) => Ok(Bound::Lex {
+                member: member.to_string(),
+                exclusive: false,
+            }),
+            (_, Some(member)) => Ok(Bound::Lex {
+                member: member.to_string(),
+                exclusive: true,
+            }),
+            _ => Err("min or max not valid string range item".to_string()),
+        },
+    }
+}
+
+impl Bound {
+    /// Number of entries, in ascending order, before the range starting at this bound.
+    fn entries_below(&self, zset: &SortedSet) -> usize {
+        match self {
+            Bound::Score { score, exclusive } => zset.count_before(|other, _| {
+                if *exclusive {
+                    other <= *score
+                } else {
+                    other < *score
+                }
+            }),
+            Bound::LexMin => 0,
+            Bound::LexMax => zset.len(),
+            Bound::Lex { member, exclusive } => zset.count_before(|_, other| {
+                if *exclusive {
+                    other <= member.as_str()
+                } else {
+                    other < member.as_str()
+                }
+            }),
+        }
+    }
+
+    /// Number of entries, in ascending order, up to the end of a range ending at this bound.
+    fn entries_up_to(&self, zset: &SortedSet) -> usize {
+        match self {
+            Bound::Score { score, exclusive } => zset.count_before(|other, _| {
+                if *exclusive {
+                    other < *score
+                } else {
+                    other <= *score
+                }
+            }),
+            Bound::LexMin => 0,
+            Bound::LexMax => zset.len(),
+            Bound::Lex { member, exclusive } => zset.count_before(|_, other| {
+                if *exclusive {
+                    other < member.as_str()
+                } else {
+                    other <= member.as_str()
+                }
+            }),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum RangeBy {
+    Rank,
+    Score,
+    Lex,
+}
+
+/// A range as given to `ZRANGE`. With `rev`, `start` is the highest end of a score or
+/// lexicographical range.
+struct RangeQuery<'a> {
+    by: RangeBy,
+    rev: bool,
+    start: &'a str,
+    stop: &'a str,
+    limit: Option<(i64, i64)>,
+    with_scores: bool,
+}
+
+/// Parses `start stop [BYSCORE | BYLEX] [REV] [LIMIT offset count] [WITHSCORES]` at `offset`.
+fn range_query<'a>(
+    args: &'a [RespType],
+    offset: usize,
+    name: &str,
+    allow_with_scores: bool,
+) -> Result<RangeQuery<'a>, String> {
+    let mut query = RangeQuery {
+        by: RangeBy::Rank,
+        rev: false,
+        start: arg_str(args, offset, name)?,
+        stop: arg_str(args, offset + 1, name)?,
+        limit: None,
+        with_scores: false,
+    };
+    let mut index = offset + 2;
+    while index < args.len() {
+        match arg_str(args, index, name)?.to_uppercase().as_str() {
+            "BYSCORE" => query.by = RangeBy::Score,
+            "BYLEX" => query.by = RangeBy::Lex,
+            "REV" => query.rev = true,
+            "WITHSCORES" if allow_with_scores => query.with_scores = true,
+            "LIMIT" => {
+                query.limit = Some((arg_i64(args, index + 1, name)?, arg_i64(args, index + 2, name)?));
+                index += 2;
+            }
+            _ => return Err("syntax error".to_string()),
+        }
+        index += 1;
+    }
+    if query.limit.is_some() && query.by == RangeBy::Rank {
+        return Err("syntax error, LIMIT is only supported in combination with either BYSCORE or BYLEX".to_string());
+    }
+    if query.with_scores && query.by == RangeBy::Lex {
+        return Err("syntax error, WITHSCORES not supported in combination with BYLEX".to_string());
+    }
+    Ok(query)
+}
+
+/// Positions `start..end` of the entries selected by the query, counted in the direction
+/// of the query.
+fn range_positions(zset: &SortedSet, query: &RangeQuery) -> Result<(usize, usize), String> {
+    let len = zset.len();
+    let (start, end) = match query.by {
+        RangeBy::Rank => {
+            let parse = |value: &str| {
+                value
+                    .parse::<i64>()
+                    .map_err(|_| "value is not an integer or out of range".to_string())
+            };
+            let normalize = |index: i64| if index < 0 { index + len as i64 } else { index };
+            let start = normalize(parse(query.start)?).max(0);
+            let stop = normalize(parse(query.stop)?);
+            if start > stop || start >= len as i64 {
+                return Ok((0, 0));
+            }
+            (start as usize, (stop as usize + 1).min(len))
+        }
+        RangeBy::Score | RangeBy::Lex => {
+            let (min, max) = if query.rev {
+                (query.stop, query.start)
+            } else {
+                (query.start, query.stop)
+            };
+            let parse = if query.by == RangeBy::Score { score_bound } else { lex_bound };
+            let (min, max) = (parse(min)?, parse(max)?);
+            let (below, up_to) = (min.entries_below(zset), max.entries_up_to(zset));
+            if below >= up_to {
+                return Ok((0, 0));
+            }
+            if query.rev {
+                (len - up_to, len - below)
+            } else {
+                (below, up_to)
+            }
+        }
+    };
+    Ok(match query.limit {
+        Some((offset, _)) if offset < 0 => (0, 0),
+        Some((offset, count)) => {
+            let start = start.saturating_add(offset as usize).min(end);
+            let end = if count < 0 { end } else { end.min(start.saturating_add(count as usize)) };
+            (start, end)
+        }
+        None => (start, end),
+    })
+}
+
+fn select(zset: &SortedSet, query: &RangeQuery) -> Result<Vec<(String, f64)>, String> {
+    let (start, end) = range_positions(zset, query)?;
+    Ok(zset.range(start, end, query.rev))
+}
+
+/// Result of adding one member with `ZADD` or `ZINCRBY`.
+enum Added {
+    New(f64),
+    Changed(f64),
+    Unchanged(f64),
+    /// Not added or updated because of `NX`, `XX`, `GT` or `LT`.
+    Skipped,
+}
+
+#[derive(Default)]
+struct AddFlags {
+    nx: bool,
+    xx: bool,
+    gt: bool,
+    lt: bool,
+    incr: bool,
+}
+
+fn add(zset: &mut SortedSet, member: &str, score: f64, flags: &AddFlags) -> Result<Added, String> {
+    let Some(current) = zset.score(member) else {
+        if flags.xx {
+            return Ok(Added::Skipped);
+        }
+        zset.insert(member.to_string(), score);
+        return Ok(Added::New(score));
+    };
+    if flags.nx {
+        return Ok(Added::Skipped);
+    }
+    let score = if flags.incr { current + score } else { score };
+    if score.is_nan() {
+        return Err("resulting score is not a number (NaN)".to_string());
+    }
+    if (flags.gt && score <= current) || (flags.lt && score >= current) {
+        return Ok(Added::Skipped);
+    }
+    if score == current {
+        return Ok(Added::Unchanged(score));
+    }
+    zset.insert(member.to_string(), score);
+    Ok(Added::Changed(score))
+}
+
+/// `ZADD key [NX | XX] [GT | LT] [CH] [INCR] score member [score member ...]`
+pub struct ZAdd;
+
+impl Command for ZAdd {
+    fn name(&self) -> &str {
+        "ZADD"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let mut flags = AddFlags::default();
+        let mut ch = false;
+        let mut index = 1;
+        while index < args.len() {
+            match arg_str(args, index, self.name())?.to_uppercase().as_str() {
+                "NX" => flags.nx = true,
+                "XX" => flags.xx = true,
+                "GT" => flags.gt = true,
+                "LT" => flags.lt = true,
+                "CH" => ch = true,
+                "INCR" => flags.incr = true,
+                _ => break,
+            }
+            index += 1;
+        }
+
+        let remaining = args.len() - index;
+        if remaining == 0 || !remaining.is_multiple_of(2) {
+            return Err("syntax error".to_string());
+        }
+        if flags.nx && flags.xx {
+            return Err("XX and NX options at the same time are not compatible".to_string());
+        }
+        if (flags.gt && flags.lt) || (flags.nx && (flags.gt || flags.lt)) {
+            return Err("GT, LT, and/or NX options at the same time are not compatible".to_string());
+        }
+        if flags.incr && remaining != 2 {
+            return Err("INCR option supports a single increment-element pair".to_string());
+        }
+        let pairs = (index..args.len())
+            .step_by(2)
+            .map(|index| Ok((score_arg(args, index, self.name())?, arg_str(args, index + 1, self.name())?)))
+            .collect::<Result<Vec<_>, String>>()?;
+
+        if flags.xx && zset(state, key)?.is_none() {
+            return Ok(if flags.incr { RespType::BulkString(None) } else { RespType::Integer(0) });
+        }
+        let zset = zset_or_create(state, key)?;
+        let mut added = 0;
+        let mut changed = 0;
+        let mut last = Added::Skipped;
+        for (score, member) in pairs {
+            last = add(zset, member, score, &flags)?;
+            match last {
+                Added::New(_) => added += 1,
+                Added::Changed(_) => changed += 1,
+                Added::Unchanged(_) | Added::Skipped => {}
+            }
+        }
+        delete_if_empty(state, key)?;
+
+        if flags.incr {
+            return Ok(match last {
+                Added::New(score) | Added::Changed(score) | Added::Unchanged(score) => score_reply(score),
+                Added::Skipped => RespType::BulkString(None),
+            });
+        }
+        Ok(RespType::Integer(if ch { added + changed } else { added }))
+    }
+}
+
+/// `ZINCRBY key increment member`
+pub struct ZIncrBy;
+
+impl Command for ZIncrBy {
+    fn name(&self) -> &str {
+        "ZINCRBY"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let increment = score_arg(args, 1, self.name())?;
+        let member = arg_str(args, 2, self.name())?;
+
+        let flags = AddFlags {
+            incr: true,
+            ..AddFlags::default()
+        };
+        match add(zset_or_create(state, key)?, member, increment, &flags)? {
+            Added::New(score) | Added::Changed(score) | Added::Unchanged(score) => Ok(score_reply(score)),
+            Added::Skipped => Ok(RespType::BulkString(None)),
+        }
+    }
+}
+
+/// `ZREM key member [member ...]`
+pub struct ZRem;
+
+impl Command for ZRem {
+    fn name(&self) -> &str {
+        "ZREM"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        arg_str(args, 1, self.name())?;
+        let members = (1..args.len())
+            .map(|index| arg_str(args, index, self.name()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let Some(zset) = zset(state, key)? else {
+            return Ok(RespType::Integer(0));
+        };
+        let removed = members.into_iter().filter(|member| zset.remove(member)).count();
+        delete_if_empty(state, key)?;
+        Ok(RespType::Integer(removed as i64))
+    }
+}
+
+/// `ZSCORE key member`
+pub struct ZScore;
+
+impl Command for ZScore {
+    fn name(&self) -> &str {
+        "ZSCORE"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let member = arg_str(args, 1, self.name())?;
+        Ok(match zset(state, key)?.and_then(|zset| zset.score(member)) {
+            Some(score) => score_reply(score),
+            None => RespType::BulkString(None),
+        })
+    }
+}
+
+/// `ZMSCORE key member [member ...]`
+pub struct ZMScore;
+
+impl Command for ZMScore {
+    fn name(&self) -> &str {
+        "ZMSCORE"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        arg_str(args, 1, self.name())?;
+        let members = (1..args.len())
+            .map(|index| arg_str(args, index, self.name()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let zset = zset(state, key)?;
+        Ok(RespType::Array(
+            members
+                .into_iter()
+                .map(|member| match zset.as_ref().and_then(|zset| zset.score(member)) {
+                    Some(score) => score_reply(score),
+                    None => RespType::BulkString(None),
+                })
+                .collect(),
+        ))
+    }
+}
+
+/// `ZCARD key`
+pub struct ZCard;
+
+impl Command for ZCard {
+    fn name(&self) -> &str {
+        "ZCARD"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        Ok(RespType::Integer(zset(state, key)?.map_or(0, |zset| zset.len() as i64)))
+    }
+}
+
+/// `ZCOUNT key min max`
+pub struct ZCount;
+
+impl Command for ZCount {
+    fn name(&self) -> &str {
+        "ZCOUNT"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let min = score_bound(arg_str(args, 1, self.name())?)?;
+        let max = score_bound(arg_str(args, 2, self.name())?)?;
+
+        let count = zset(state, key)?.map_or(0, |zset| max.entries_up_to(zset).saturating_sub(min.entries_below(zset)));
+        Ok(RespType::Integer(count as i64))
+    }
+}
+
+fn execute_rank(args: &[RespType], state: &mut dyn ServerState, name: &str, rev: bool) -> Result<RespType, String> {
+    let key = arg_str(args, 0, name)?;
+    let member = arg_str(args, 1, name)?;
+    let with_score = match args.get(2) {
+        Some(_) if args.len() == 3 && arg_str(args, 2, name)?.eq_ignore_ascii_case("WITHSCORE") => true,
+        Some(_) => return Err("syntax error".to_string()),
+        None => false,
+    };
+
+    let found = zset(state, key)?.and_then(|zset| Some((zset.rank(member)?, zset.score(member)?, zset.len())));
+    let Some((rank, score, len)) = found else {
+        return Ok(if with_score { RespType::NullArray } else { RespType::BulkString(None) });
+    };
+    let rank = RespType::Integer(if rev { len - 1 - rank } else { rank } as i64);
+    Ok(if with_score { RespType::Array(vec![rank, score_reply(score)]) } else { rank })
+}
+
+/// `ZRANK key member [WITHSCORE]`: rank in ascending order of scores.
+pub struct ZRank;
+
+impl Command for ZRank {
+    fn name(&self) -> &str {
+        "ZRANK"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_rank(args, state, self.name(), false)
+    }
+}
+
+/// `ZREVRANK key member [WITHSCORE]`: rank in descending order of scores.
+pub struct ZRevRank;
+
+impl Command for ZRevRank {
+    fn name(&self) -> &str {
+        "ZREVRANK"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_rank(args, state, self.name(), true)
+    }
+}
+
+/// `ZRANGE key start stop [BYSCORE | BYLEX] [REV] [LIMIT offset count] [WITHSCORES]`
+pub struct ZRange;
+
+impl Command for ZRange {
+    fn name(&self) -> &str {
+        "ZRANGE"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let query = range_query(args, 1, self.name(), true)?;
+        let entries = match zset(state, key)? {
+            Some(zset) => select(zset, &query)?,
+            None => Vec::new(),
+        };
+        Ok(entries_reply(entries, query.with_scores))
+    }
+}
+
+/// `ZRANGESTORE destination source min max [BYSCORE | BYLEX] [REV] [LIMIT offset count]`
+pub struct ZRangeStore;
+
+impl Command for ZRangeStore {
+    fn name(&self) -> &str {
+        "ZRANGESTORE"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        (0..2.min(args.len())).filter_map(|index| arg_str(args, index, "ZRANGESTORE").ok()).collect()
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let destination = arg_str(args, 0, self.name())?;
+        let source = arg_str(args, 1, self.name())?;
+        let query = range_query(args, 2, self.name(), false)?;
+
+        let entries = match zset(state, source)? {
+            Some(zset) => select(zset, &query)?,
+            None => Vec::new(),
+        };
+        let len = entries.len();
+        state.del(destination)?;
+        if len > 0 {
+            state.set(destination.to_string(), Value::SortedSet(entries.into_iter().collect()), None)?;
+        }
+        Ok(RespType::Integer(len as i64))
+    }
+}
+
+/// Removes the entries selected by the query and returns how many there were.
+fn remove_range(state: &mut dyn ServerState, key: &str, query: &RangeQuery) -> Result<RespType, String> {
+    let Some(zset) = zset(state, key)? else {
+        return Ok(RespType::Integer(0));
+    };
+    let entries = select(zset, query)?;
+    for (member, _) in &entries {
+        zset.remove(member);
+    }
+    delete_if_empty(state, key)?;
+    Ok(RespType::Integer(entries.len() as i64))
+}
+
+fn execute_remove_range(
+    args: &[RespType],
+    state: &mut dyn ServerState,
+    name: &str,
+    by: RangeBy,
+) -> Result<RespType, String> {
+    let key = arg_str(args, 0, name)?;
+    let query = RangeQuery {
+        by,
+        rev: false,
+        start: arg_str(args, 1, name)?,
+        stop: arg_str(args, 2, name)?,
+        limit: None,
+        with_scores: false,
+    };
+    if args.len() > 3 {
+        return Err(format!("wrong number of arguments for '{}' command", name.to_lowercase()));
+    }
+    remove_range(state, key, &query)
+}
+
+/// `ZREMRANGEBYRANK key start stop`
+pub struct ZRemRangeByRank;
+
+impl Command for ZRemRangeByRank {
+    fn name(&self) -> &str {
+        "ZREMRANGEBYRANK"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_remove_range(args, state, self.name(), RangeBy::Rank)
+    }
+}
+
+/// `ZREMRANGEBYSCORE key min max`
+pub struct ZRemRangeByScore;
+
+impl Command for ZRemRangeByScore {
+    fn name(&self) -> &str {
+        "ZREMRANGEBYSCORE"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_remove_range(args, state, self.name(), RangeBy::Score)
+    }
+}
+
+/// `ZREMRANGEBYLEX key min max`
+pub struct ZRemRangeByLex;
+
+impl Command for ZRemRangeByLex {
+    fn name(&self) -> &str {
+        "ZREMRANGEBYLEX"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_remove_range(args, state, self.name(), RangeBy::Lex)
+    }
+}
+
+/// Pops up to `count` entries with the lowest scores, or the highest with `max`.
+pub(crate) fn pop(
+    state: &mut dyn ServerState,
+    key: &str,
+    count: usize,
+    max: bool,
+) -> Result<Vec<(String, f64)>, String> {
+    let Some(zset) = zset(state, key)? else {
+        return Ok(Vec::new());
+    };
+    let entries = zset.range(0, count, max);
+    for (member, _) in &entries {
+        zset.remove(member);
+    }
+    delete_if_empty(state, key)?;
+    Ok(entries)
+}
+
+fn execute_pop(args: &[RespType], state: &mut dyn ServerState, name: &str, max: bool) -> Result<RespType, String> {
+    let key = arg_str(args, 0, name)?;
+    let count = match args.get(1) {
+        Some(_) => match arg_i64(args, 1, name)? {
+            count if count >= 0 => count as usize,
+            _ => return Err("value is out of range, must be positive".to_string()),
+        },
+        None => 1,
+    };
+    if args.len() > 2 {
+        return Err("syntax error".to_string());
+    }
+    Ok(entries_reply(pop(state, key, count, max)?, true))
+}
+
+/// `ZPOPMIN key [count]`
+pub struct ZPopMin;
+
+impl Command for ZPopMin {
+    fn name(&self) -> &str {
+        "ZPOPMIN"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_pop(args, state, self.name(), false)
+    }
+}
+
+/// `ZPOPMAX key [count]`
+pub struct ZPopMax;
+
+impl Command for ZPopMax {
+    fn name(&self) -> &str {
+        "ZPOPMAX"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_pop(args, state, self.name(), true)
+    }
+}
+
+/// `ZSCAN key cursor [MATCH pattern] [COUNT count]`
+pub struct ZScan;
+
+impl Command for ZScan {
+    fn name(&self) -> &str {
+        "ZSCAN"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let scan = scan_args(args, 1, self.name(), false)?;
+
+        let (cursor, entries) = match zset(state, key)? {
+            Some(zset) => {
+                let (cursor, page) = scan_page(zset.iter(), &scan);
+                (cursor, page.into_iter().map(|(member, score)| (member.clone(), score)).collect())
+            }
+            None => (0, Vec::new()),
+        };
+        Ok(RespType::Array(vec![bulk(&cursor.to_string()), entries_reply(entries, true)]))
+    }
+}
+
+/// Arguments of `ZMPOP`/`BZMPOP` starting at `numkeys`: `numkeys key [key ...] MIN|MAX [COUNT count]`.
+fn zmpop_args(args: &[RespType], offset: usize, name: &str) -> Result<(Vec<String>, KeyOperation), String> {
+    let numkeys = arg_i64(args, offset, name)?;
+    if numkeys <= 0 {
+        return Err("numkeys should be greater than 0".to_string());
+    }
+    let numkeys = numkeys as usize;
+    if args.len() < offset + numkeys + 2 {
+        return Err(format!("wrong number of arguments for '{}' command", name.to_lowercase()));
+    }
+    let keys = (offset + 1..offset + 1 + numkeys)
+        .map(|index| arg_str(args, index, name).map(str::to_string))
+        .collect::<Result<Vec<_>, _>>()?;
+    let max = match arg_str(args, offset + 1 + numkeys, name)?.to_uppercase().as_str() {
+        "MIN" => false,
+        "MAX" => true,
+        _ => return Err("syntax error".to_string()),
+    };
+
+    let count = match &args[offset + 2 + numkeys..] {
+        [] => 1,
+        [_, _] if arg_str(args, offset + 2 + numkeys, name)?.eq_ignore_ascii_case("COUNT") => {
+            match arg_i64(args, offset + 3 + numkeys, name)? {
+                count if count > 0 => count as usize,
+                _ => return Err("count should be greater than 0".to_string()),
+            }
+        }
+        _ => return Err("syntax error".to_string()),
+    };
+    Ok((keys, KeyOperation::ZMultiPop { max, count }))
+}
+
+fn execute_blocking_pop(
+    args: &[RespType],
+    state: &mut dyn ServerState,
+    client: &mut ClientContext,
+    name: &str,
+    max: bool,
+) -> Result<RespType, String> {
+    if args.len() < 2 {
+        return Err(format!("wrong number of arguments for '{}' command", name.to_lowercase()));
+    }
+    let keys = keys_before_timeout(args, name).into_iter().map(str::to_string).collect();
+    let deadline = deadline_arg(args, args.len() - 1, name)?;
+    serve_or_block(
+        KeyWait {
+            keys,
+            operation: KeyOperation::ZPop { max },
+            deadline,
+        },
+        state,
+        client,
+    )
+}
+
+/// `BZPOPMIN key [key ...] timeout`
+pub struct BZPopMin;
+
+impl Command for BZPopMin {
+    fn name(&self) -> &str {
+        "BZPOPMIN"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn propagate_verbatim(&self) -> bool {
+        false
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        keys_before_timeout(args, self.name())
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        self.execute_with_client(args, state, &mut ClientContext::default())
+    }
+
+    fn execute_with_client(
+        &self,
+        args: &[RespType],
+        state: &mut dyn ServerState,
+        client: &mut ClientContext,
+    ) -> Result<RespType, String> {
+        execute_blocking_pop(args, state, client, self.name(), false)
+    }
+}
+
+/// `BZPOPMAX key [key ...] timeout`
+pub struct BZPopMax;
+
+impl Command for BZPopMax {
+    fn name(&self) -> &str {
+        "BZPOPMAX"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn propagate_verbatim(&self) -> bool {
+        false
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        keys_before_timeout(args, self.name())
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        self.execute_with_client(args, state, &mut ClientContext::default())
+    }
+
+    fn execute_with_client(
+        &self,
+        args: &[RespType],
+        state: &mut dyn ServerState,
+        client: &mut ClientContext,
+    ) -> Result<RespType, String> {
+        execute_blocking_pop(args, state, client, self.name(), true)
+    }
+}
+
+/// `ZMPOP numkeys key [key ...] MIN|MAX [COUNT count]`: pops from the first non-empty sorted set.
+pub struct ZMPop;
+
+impl Command for ZMPop {
+    fn name(&self) -> &str {
+        "ZMPOP"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn propagate_verbatim(&self) -> bool {
+        false
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        numkeys_keys(args, 0, self.name())
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let (keys, operation) = zmpop_args(args, 0, self.name())?;
+        let wait = KeyWait {
+            keys,
+            operation,
+            deadline: None,
+        };
+        Ok(serve(state, &wait)?.unwrap_or(RespType::NullArray))
+    }
+}
+
+/// `BZMPOP timeout numkeys key [key ...] MIN|MAX [COUNT count]`
+pub struct BZMPop;
+
+impl Command for BZMPop {
+    fn name(&self) -> &str {
+        "BZMPOP"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn propagate_verbatim(&self) -> bool {
+        false
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        numkeys_keys(args, 1, self.name())
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        self.execute_with_client(args, state, &mut ClientContext::default())
+    }
+
+    fn execute_with_client(
+        &self,
+        args: &[RespType],
+        state: &mut dyn ServerState,
+        client: &mut ClientContext,
+    ) -> Result<RespType, String> {
+        let deadline = deadline_arg(args, 0, self.name())?;
+        let (keys, operation) = zmpop_args(args, 1, self.name())?;
+        serve_or_block(
+            KeyWait {
+                keys,
+                operation,
+                deadline,
+            },
+            state,
+            client,
+        )
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Operation {
+    Inter,
+    Union,
+    Diff,
+}
+
+#[derive(Clone, Copy)]
+enum Aggregate {
+    Sum,
+    Min,
+    Max,
+}
+
+impl Aggregate {
+    fn apply(self, total: f64, score: f64) -> f64 {
+        match self {
+            // inf + -inf counts as 0, like in Redis.
+            Aggregate::Sum => Some(total + score).filter(|sum| !sum.is_nan()).unwrap_or(0.0),
+            Aggregate::Min => total.min(score),
+            Aggregate::Max => total.max(score),
+        }
+    }
+}
+
+/// Arguments of the algebra commands from `numkeys` on:
+/// `numkeys key [key ...] [WEIGHTS weight [weight ...]] [AGGREGATE SUM|MIN|MAX] [WITHSCORES]`.
+struct CombineArgs<'a> {
+    keys: Vec<&'a str>,
+    weights: Vec<f64>,
+    aggregate: Aggregate,
+    with_scores: bool,
+}
+
+fn combine_args<'a>(
+    args: &'a [RespType],
+    offset: usize,
+    name: &str,
+    operation: Operation,
+    allow_with_scores: bool,
+) -> Result<CombineArgs<'a>, String> {
+    let numkeys = arg_i64(args, offset, name)?;
+    if numkeys < 1 {
+        return Err(format!("at least 1 input key is needed for '{}' command", name.to_lowercase()));
+    }
+    let numkeys = numkeys as usize;
+    if numkeys > args.len() - offset - 1 {
+        return Err("syntax error".to_string());
+    }
+    let mut combine = CombineArgs {
+        keys: (offset + 1..offset + 1 + numkeys)
+            .map(|index| arg_str(args, index, name))
+            .collect::<Result<Vec<_>, _>>()?,
+        weights: vec![1.0; numkeys],
+        aggregate: Aggregate::Sum,
+        with_scores: false,
+    };
+
+    let mut index = offset + 1 + numkeys;
+    while index < args.len() {
+        match arg_str(args, index, name)?.to_uppercase().as_str() {
+            "WEIGHTS" if operation != Operation::Diff && index + numkeys < args.len() => {
+                for weight in combine.weights.iter_mut() {
+                    index += 1;
+                    *weight = parse_score(arg_str(args, index, name)?)
+                        .ok_or_else(|| "weight value is not a float".to_string())?;
+                }
+            }
+            "AGGREGATE" if operation != Operation::Diff && index + 1 < args.len() => {
+                index += 1;
+                combine.aggregate = match arg_str(args, index, name)?.to_uppercase().as_str() {
+                    "SUM" => Aggregate::Sum,
+                    "MIN" => Aggregate::Min,
+                    "MAX" => Aggregate::Max,
+                    _ => return Err("syntax error".to_string()),
+                };
+            }
+            "WITHSCORES" if allow_with_scores => combine.with_scores = true,
+            _ => return Err("syntax error".to_string()),
+        }
+        index += 1;
+    }
+    Ok(combine)
+}
+
+/// Members and scores of a sorted set, or of a set with every score being 1.
+fn scores(state: &mut dyn ServerState, key: &str) -> Result<Option<HashMap<String, f64>>, String> {
+    match state.get(key) {
+        Some(Value::SortedSet(zset)) => Ok(Some(zset.iter().map(|(member, score)| (member.clone(), score)).collect())),
+        Some(Value::Set(set)) => Ok(Some(set.iter().map(|member| (member.into_owned(), 1.0)).collect())),
+        Some(_) => Err(WRONG_TYPE.to_string()),
+        None => Ok(None),
+    }
+}
+
+/// Applies the operation to the sets at `keys`, missing keys counting as empty sets.
+fn combine(state: &mut dyn ServerState, combine: &CombineArgs, operation: Operation) -> Result<SortedSet, String> {
+    // Every key is type checked, even when the result is known early.
+    let sets = combine
+        .keys
+        .iter()
+        .map(|key| scores(state, key))
+        .collect::<Result<Vec<_>, String>>()?;
+    // inf * 0 counts as 0, like in Redis.
+    let weighted = |score: f64, weight: f64| Some(score * weight).filter(|score| !score.is_nan()).unwrap_or(0.0);
+
+    Ok(match operation {
+        Operation::Inter => {
+            let Some((Some(first), others)) = sets.split_first() else {
+                return Ok(SortedSet::default());
+            };
+            first
+                .iter()
+                .filter_map(|(member, score)| {
+                    let mut total = weighted(*score, combine.weights[0]);
+                    for (other, weight) in others.iter().zip(&combine.weights[1..]) {
+                        let score = other.as_ref()?.get(member)?;
+                        total = combine.aggregate.apply(total, weighted(*score, *weight));
+                    }
+                    Some((member.clone(), total))
+                })
+                .collect()
+        }
+        Operation::Union => {
+            let mut totals = HashMap::new();
+            for (set, weight) in sets.iter().zip(&combine.weights) {
+                for (member, score) in set.iter().flatten() {
+                    let score = weighted(*score, *weight);
+                    totals
+                        .entry(member.clone())
+                        .and_modify(|total| *total = combine.aggregate.apply(*total, score))
+                        .or_insert(score);
+                }
+            }
+            totals.into_iter().collect()
+        }
+        Operation::Diff => {
+            let Some((Some(first), others)) = sets.split_first() else {
+                return Ok(SortedSet::default());
+            };
+            first
+                .iter()
+                .filter(|(member, _)| others.iter().flatten().all(|other| !other.contains_key(*member)))
+                .map(|(member, score)| (member.clone(), *score))
+                .collect()
+        }
+    })
+}
+
+fn execute_combine(
+    args: &[RespType],
+    state: &mut dyn ServerState,
+    name: &str,
+    operation: Operation,
+) -> Result<RespType, String> {
+    let combine_args = combine_args(args, 0, name, operation, true)?;
+    let zset = combine(state, &combine_args, operation)?;
+    Ok(entries_reply(zset.range(0, zset.len(), false), combine_args.with_scores))
+}
+
+/// Replaces `destination` with the result of the operation, deleting it when it is empty.
+fn execute_combine_store(
+    args: &[RespType],
+    state: &mut dyn ServerState,
+    name: &str,
+    operation: Operation,
+) -> Result<RespType, String> {
+    let destination = arg_str(args, 0, name)?;
+    let zset = combine(state, &combine_args(args, 1, name, operation, false)?, operation)?;
+    let len = zset.len();
+    state.del(destination)?;
+    if len > 0 {
+        state.set(destination.to_string(), Value::SortedSet(zset), None)?;
+    }
+    Ok(RespType::Integer(len as i64))
+}
+
+/// Keys of the storing algebra commands: the destination, then the sources.
+fn store_keys<'a>(args: &'a [RespType], name: &str) -> Vec<&'a str> {
+    let mut keys = first_key(args);
+    keys.extend(numkeys_keys(args, 1, name));
+    keys
+}
+
+/// `ZUNION numkeys key [key ...] [WEIGHTS weight [weight ...]] [AGGREGATE SUM|MIN|MAX] [WITHSCORES]`
+pub struct ZUnion;
+
+impl Command for ZUnion {
+    fn name(&self) -> &str {
+        "ZUNION"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        numkeys_keys(args, 0, self.name())
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_combine(args, state, self.name(), Operation::Union)
+    }
+}
+
+/// `ZINTER numkeys key [key ...] [WEIGHTS weight [weight ...]] [AGGREGATE SUM|MIN|MAX] [WITHSCORES]`
+pub struct ZInter;
+
+impl Command for ZInter {
+    fn name(&self) -> &str {
+        "ZINTER"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        numkeys_keys(args, 0, self.name())
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_combine(args, state, self.name(), Operation::Inter)
+    }
+}
+
+/// `ZDIFF numkeys key [key ...] [WITHSCORES]`
+pub struct ZDiff;
+
+impl Command for ZDiff {
+    fn name(&self) -> &str {
+        "ZDIFF"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        numkeys_keys(args, 0, self.name())
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_combine(args, state, self.name(), Operation::Diff)
+    }
+}
+
+/// `ZUNIONSTORE destination numkeys key [key ...] [WEIGHTS weight [weight ...]] [AGGREGATE SUM|MIN|MAX]`
+pub struct ZUnionStore;
+
+impl Command for ZUnionStore {
+    fn name(&self) -> &str {
+        "ZUNIONSTORE"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        store_keys(args, self.name())
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_combine_store(args, state, self.name(), Operation::Union)
+    }
+}
+
+/// `ZINTERSTORE destination numkeys key [key ...] [WEIGHTS weight [weight ...]] [AGGREGATE SUM|MIN|MAX]`
+pub struct ZInterStore;
+
+impl Command for ZInterStore {
+    fn name(&self) -> &str {
+        "ZINTERSTORE"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        store_keys(args, self.name())
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_combine_store(args, state, self.name(), Operation::Inter)
+    }
+}
+
+/// `ZDIFFSTORE destination numkeys key [key ...]`
+pub struct ZDiffStore;
+
+impl Command for ZDiffStore {
+    fn name(&self) -> &str {
+        "ZDIFFSTORE"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        store_keys(args, self.name())
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_combine_store(args, state, self.name(), Operation::Diff)
+    }
+}
+
+/// `ZINTERCARD numkeys key [key ...] [LIMIT limit]`: size of the intersection, counting
+/// at most `limit` members (0 for no limit).
+pub struct ZInterCard;
+
+impl Command for ZInterCard {
+    fn name(&self) -> &str {
+        "ZINTERCARD"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        numkeys_keys(args, 0, self.name())
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let numkeys = arg_i64(args, 0, self.name())?;
+        if numkeys <= 0 {
+            return Err("numkeys should be greater than 0".to_string());
+        }
+        let numkeys = numkeys as usize;
+        if numkeys >= args.len() {
+            return Err("Number of keys can't be greater than number of args".to_string());
+        }
+        let limit = match args.len() - numkeys - 1 {
+            0 => 0,
+            2 if arg_str(args, numkeys + 1, self.name())?.eq_ignore_ascii_case("LIMIT") => {
+                match arg_i64(args, numkeys + 2, self.name())? {
+                    limit if limit >= 0 => limit as usize,
+                    _ => return Err("LIMIT can't be negative".to_string()),
+                }
+            }
+            _ => return Err("syntax error".to_string()),
+        };
+
+        let combine_args = CombineArgs {
+            keys: (1..=numkeys)
+                .map(|index| arg_str(args, index, self.name()))
+                .collect::<Result<Vec<_>, _>>()?,
+            weights: vec![1.0; numkeys],
+            aggregate: Aggregate::Sum,
+            with_scores: false,
+        };
+        let len = combine(state, &combine_args, Operation::Inter)?.len();
+        let len = if limit > 0 { len.min(limit) } else { len };
+        Ok(RespType::Integer(len as i64))
+    }
+}
@@ -0,0 +1,1289 @@
+//! Stream commands. Unlike other types, streams are kept when their last entry is removed.
+//! `XREAD BLOCK` and `XREADGROUP BLOCK` wait in `blocking` until an entry is added to one of
+//! their streams.
+//!
+//! Consumer groups depend on the time entries are delivered, so reads and claims are
+//! replicated as the `XCLAIM ... TIME ms RETRYCOUNT count FORCE JUSTID` giving each entry
+//! its final state, followed by the `XGROUP SETID` moving the group forward.
+
+use std::time::{Duration, Instant};
+
+use crate::resp::blocking::{serve, KeyOperation, KeyWait};
+use crate::resp::client::ClientContext;
+use crate::resp::commands::list::serve_or_block;
+use crate::resp::commands::{arg_i64, arg_str, current_time_ms, first_key, propagate, Command};
+use crate::resp::protocol::RespType;
+use crate::resp::state::server_state::ServerState;
+use crate::resp::state::stream::{ConsumerGroup, Fields, Stream, StreamId, Trim};
+use crate::resp::state::value::{Value, WRONG_TYPE};
+
+const INVALID_ID: &str = "Invalid stream ID specified as stream command argument";
+
+fn stream<'a>(state: &'a mut dyn ServerState, key: &str) -> Result<Option<&'a mut Stream>, String> {
+    match state.get_mut(key) {
+        Some(Value::Stream(stream)) => Ok(Some(stream)),
+        Some(_) => Err(WRONG_TYPE.to_string()),
+        None => Ok(None),
+    }
+}
+
+fn bulk(value: &str) -> RespType {
+    RespType::BulkString(Some(value.to_string()))
+}
+
+/// `[id, [field, value, ...]]`
+fn entry_reply(id: &StreamId, fields: &Fields) -> RespType {
+    RespType::Array(vec![
+        bulk(&id.to_string()),
+        RespType::Array(fields.iter().flat_map(|(field, value)| [bulk(field), bulk(value)]).collect()),
+    ])
+}
+
+/// Parses `<ms>-<seq>`, or `<ms>` which stands for `<ms>-<seq>`.
+fn parse_id(value: &str, seq: u64) -> Result<StreamId, String> {
+    let invalid = |_| INVALID_ID.to_string();
+    match value.split_once('-') {
+        Some((ms, sequence)) => Ok(StreamId::new(ms.parse().map_err(invalid)?, sequence.parse().map_err(invalid)?)),
+        None => Ok(StreamId::new(value.parse().map_err(invalid)?, seq)),
+    }
+}
+
+/// Start of an `XRANGE` interval: `-`, `+`, an ID, or `(` and one of those to exclude it.
+fn range_start(value: &str) -> Result<StreamId, String> {
+    match value {
+        "-" => Ok(StreamId::MIN),
+        "+" => Ok(StreamId::MAX),
+        _ => match value.strip_prefix('(') {
+            Some(value) => range_start(value)?
+                .next()
+                .ok_or_else(|| "invalid start ID for the interval".to_string()),
+            None => parse_id(value, 0),
+        },
+    }
+}
+
+/// End of an `XRANGE` interval, where an ID without sequence number includes all of them.
+fn range_end(value: &str) -> Result<StreamId, String> {
+    match value {
+        "-" => Ok(StreamId::MIN),
+        "+" => Ok(StreamId::MAX),
+        _ => match value.strip_prefix('(') {
+            Some(value) => range_end(value)?
+                .previous()
+                .ok_or_else(|| "invalid end ID for the interval".to_string()),
+            None => parse_id(value, u64::MAX),
+        },
+    }
+}
+
+/// Parses `MAXLEN|MINID [=|~] threshold [LIMIT count]` at `index`. Returns the strategy,
+/// the limit on removed entries and the index following the arguments. Streams are always
+/// trimmed exactly, `~` only allowing a `LIMIT`.
+fn trim_args(args: &[RespType], index: usize, name: &str) -> Result<(Trim, Option<u64>, usize), String> {
+    let strategy = arg_str(args, index, name)?.to_uppercase();
+    let mut index = index + 1;
+    let approximate = match arg_str(args, index, name)? {
+        "~" => true,
+        "=" => false,
+        _ => {
+            index -= 1;
+            false
+        }
+    };
+    index += 1;
+    let trim = match strategy.as_str() {
+        "MAXLEN" => match arg_i64(args, index, name)? {
+            max_len if max_len >= 0 => Trim::MaxLen(max_len as u64),
+            _ => return Err("The MAXLEN argument must be >= 0.".to_string()),
+        },
+        "MINID" => Trim::MinId(parse_id(arg_str(args, index, name)?, 0)?),
+        _ => return Err("syntax error".to_string()),
+    };
+    index += 1;
+
+    let mut limit = None;
+    if arg_str(args, index, name).is_ok_and(|arg| arg.eq_ignore_ascii_case("LIMIT")) {
+        let count = arg_i64(args, index + 1, name)?;
+        if count < 0 {
+            return Err("The LIMIT argument must be >= 0.".to_string());
+        }
+        if !approximate {
+            return Err("syntax error, LIMIT cannot be used without the special ~ option".to_string());
+        }
+        // 0 means no limit.
+        limit = Some(count as u64).filter(|count| *count > 0);
+        index += 2;
+    }
+    Ok((trim, limit, index))
+}
+
+/// ID given to `XADD`.
+enum NewId {
+    /// `*`
+    Auto,
+    /// `<ms>-*`
+    AutoSequence(u64),
+    Explicit(StreamId),
+}
+
+impl NewId {
+    fn parse(value: &str) -> Result<NewId, String> {
+        if value == "*" {
+            return Ok(NewId::Auto);
+        }
+        if let Some(ms) = value.strip_suffix("-*") {
+            return ms.parse().map(NewId::AutoSequence).map_err(|_| INVALID_ID.to_string());
+        }
+        match parse_id(value, 0)? {
+            StreamId::MIN => Err("The ID specified in XADD must be greater than 0-0".to_string()),
+            id => Ok(NewId::Explicit(id)),
+        }
+    }
+
+    /// The ID of the entry to add after `last_id`.
+    fn resolve(&self, last_id: StreamId) -> Result<StreamId, String> {
+        let too_small = || "The ID specified in XADD is equal or smaller than the target stream top item".to_string();
+        match *self {
+            NewId::Auto => match current_time_ms() {
+                now if now > last_id.ms => Ok(StreamId::new(now, 0)),
+                _ => last_id.next().ok_or_else(|| {
+                    "The stream has exhausted the last possible ID, unable to add more items".to_string()
+                }),
+            },
+            NewId::AutoSequence(ms) if ms > last_id.ms => Ok(StreamId::new(ms, 0)),
+            NewId::AutoSequence(ms) if ms == last_id.ms => {
+                last_id.seq.checked_add(1).map(|seq| StreamId::new(ms, seq)).ok_or_else(too_small)
+            }
+            NewId::AutoSequence(_) => Err(too_small()),
+            NewId::Explicit(id) if id > last_id => Ok(id),
+            NewId::Explicit(_) => Err(too_small()),
+        }
+    }
+}
+
+/// `XADD key [NOMKSTREAM] [MAXLEN|MINID [=|~] threshold [LIMIT count]] *|id field value [field value ...]`
+///
+/// Replicated with the ID that was given to the entry.
+pub struct XAdd;
+
+impl Command for XAdd {
+    fn name(&self) -> &str {
+        "XADD"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn propagate_verbatim(&self) -> bool {
+        false
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let mut no_mkstream = false;
+        let mut trim = None;
+        let mut index = 1;
+        loop {
+            match arg_str(args, index, self.name())?.to_uppercase().as_str() {
+                "NOMKSTREAM" => {
+                    no_mkstream = true;
+                    index += 1;
+                }
+                "MAXLEN" | "MINID" => {
+                    let (strategy, limit, next) = trim_args(args, index, self.name())?;
+                    trim = Some((strategy, limit));
+                    index = next;
+                }
+                _ => break,
+            }
+        }
+
+        let id_index = index;
+        let new_id = NewId::parse(arg_str(args, id_index, self.name())?)?;
+        let values = args.len() - id_index - 1;
+        if values == 0 || !values.is_multiple_of(2) {
+            return Err("wrong number of arguments for 'xadd' command".to_string());
+        }
+        let fields = (id_index + 1..args.len())
+            .step_by(2)
+            .map(|index| {
+                let field = arg_str(args, index, self.name())?.to_string();
+                Ok((field, arg_str(args, index + 1, self.name())?.to_string()))
+            })
+            .collect::<Result<Fields, String>>()?;
+
+        let id = match stream(state, key)? {
+            Some(stream) => new_id.resolve(stream.last_id)?,
+            None if no_mkstream => return Ok(RespType::BulkString(None)),
+            None => {
+                let id = new_id.resolve(StreamId::MIN)?;
+                state.set(key.to_string(), Value::Stream(Stream::default()), None)?;
+                id
+            }
+        };
+        let stream = stream(state, key)?.ok_or_else(|| "Failed to create stream".to_string())?;
+        stream.add(id, fields);
+        if let Some((strategy, limit)) = trim {
+            stream.trim(strategy, limit);
+        }
+
+        let id = id.to_string();
+        let mut parts = vec![self.name()];
+        parts.extend((0..args.len()).filter_map(|index| arg_str(args, index, self.name()).ok()));
+        parts[id_index + 1] = &id;
+        propagate(state, &parts);
+        Ok(bulk(&id))
+    }
+}
+
+/// `XLEN key`
+pub struct XLen;
+
+impl Command for XLen {
+    fn name(&self) -> &str {
+        "XLEN"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        Ok(RespType::Integer(stream(state, key)?.map_or(0, |stream| stream.len() as i64)))
+    }
+}
+
+/// `XDEL key id [id ...]`: returns the number of entries removed.
+pub struct XDel;
+
+impl Command for XDel {
+    fn name(&self) -> &str {
+        "XDEL"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        arg_str(args, 1, self.name())?;
+        let ids = (1..args.len())
+            .map(|index| parse_id(arg_str(args, index, self.name())?, 0))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let Some(stream) = stream(state, key)? else {
+            return Ok(RespType::Integer(0));
+        };
+        Ok(RespType::Integer(ids.into_iter().filter(|id| stream.remove(*id)).count() as i64))
+    }
+}
+
+/// `XTRIM key MAXLEN|MINID [=|~] threshold [LIMIT count]`: returns the number of entries removed.
+pub struct XTrim;
+
+impl Command for XTrim {
+    fn name(&self) -> &str {
+        "XTRIM"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let (trim, limit, end) = trim_args(args, 1, self.name())?;
+        if end != args.len() {
+            return Err("syntax error".to_string());
+        }
+
+        let removed = stream(state, key)?.map_or(0, |stream| stream.trim(trim, limit));
+        Ok(RespType::Integer(removed as i64))
+    }
+}
+
+fn execute_range(args: &[RespType], state: &mut dyn ServerState, name: &str, rev: bool) -> Result<RespType, String> {
+    let key = arg_str(args, 0, name)?;
+    let (start, end) = if rev {
+        (range_start(arg_str(args, 2, name)?)?, range_end(arg_str(args, 1, name)?)?)
+    } else {
+        (range_start(arg_str(args, 1, name)?)?, range_end(arg_str(args, 2, name)?)?)
+    };
+    let count = match args.len() {
+        3 => usize::MAX,
+        5 if arg_str(args, 3, name)?.eq_ignore_ascii_case("COUNT") => arg_i64(args, 4, name)?.max(0) as usize,
+        _ => return Err("syntax error".to_string()),
+    };
+
+    let Some(stream) = stream(state, key)? else {
+        return Ok(RespType::Array(vec![]));
+    };
+    let entries = stream.range(start, end);
+    let entries: Box<dyn Iterator<Item = _>> = if rev { Box::new(entries.rev()) } else { entries };
+    Ok(RespType::Array(entries.take(count).map(|(id, fields)| entry_reply(id, fields)).collect()))
+}
+
+/// `XRANGE key start end [COUNT count]`
+pub struct XRange;
+
+impl Command for XRange {
+    fn name(&self) -> &str {
+        "XRANGE"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_range(args, state, self.name(), false)
+    }
+}
+
+/// `XREVRANGE key end start [COUNT count]`
+pub struct XRevRange;
+
+impl Command for XRevRange {
+    fn name(&self) -> &str {
+        "XREVRANGE"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        execute_range(args, state, self.name(), true)
+    }
+}
+
+/// Entries added after `ids` to the streams at `keys`, as an `XREAD` reply, or `None` when
+/// there are none.
+pub(crate) fn read(
+    state: &mut dyn ServerState,
+    keys: &[String],
+    ids: &[StreamId],
+    count: Option<usize>,
+) -> Result<Option<RespType>, String> {
+    let mut reply = Vec::new();
+    for (key, id) in keys.iter().zip(ids) {
+        let (Some(stream), Some(start)) = (stream(state, key)?, id.next()) else {
+            continue;
+        };
+        let entries = stream
+            .range(start, StreamId::MAX)
+            .take(count.unwrap_or(usize::MAX))
+            .map(|(id, fields)| entry_reply(id, fields))
+            .collect::<Vec<_>>();
+        if !entries.is_empty() {
+            reply.push(RespType::Array(vec![bulk(key), RespType::Array(entries)]));
+        }
+    }
+    if reply.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(RespType::Array(reply)))
+}
+
+/// Index of the `STREAMS` argument of `XREAD` and `XREADGROUP`.
+fn streams_index(args: &[RespType], name: &str) -> Option<usize> {
+    (0..args.len()).find(|index| arg_str(args, *index, name).is_ok_and(|arg| arg.eq_ignore_ascii_case("STREAMS")))
+}
+
+/// Keys of `XREAD` and `XREADGROUP`, the first half of the arguments after `STREAMS`.
+fn read_keys<'a>(args: &'a [RespType], name: &str) -> Vec<&'a str> {
+    let Some(streams) = streams_index(args, name) else {
+        return Vec::new();
+    };
+    let numkeys = (args.len() - streams - 1) / 2;
+    (streams + 1..streams + 1 + numkeys).filter_map(|index| arg_str(args, index, name).ok()).collect()
+}
+
+/// Options of `XREAD` and `XREADGROUP` preceding `STREAMS`.
+#[derive(Default)]
+struct ReadOptions {
+    count: Option<usize>,
+    /// Timeout in milliseconds when blocking, `None` meaning forever.
+    block: Option<Option<u64>>,
+    noack: bool,
+    /// Index of the `STREAMS` argument.
+    streams: usize,
+}
+
+impl ReadOptions {
+    fn deadline(&self) -> Option<Instant> {
+        self.block.flatten().map(|timeout| Instant::now() + Duration::from_millis(timeout))
+    }
+}
+
+/// Parses the options from `index` up to `STREAMS`. `NOACK` is only accepted for groups.
+fn read_options(args: &[RespType], mut index: usize, name: &str, group: bool) -> Result<ReadOptions, String> {
+    let mut options = ReadOptions::default();
+    loop {
+        match arg_str(args, index, name)?.to_uppercase().as_str() {
+            "COUNT" => {
+                // 0 or less means no limit.
+                options.count = Some(arg_i64(args, index + 1, name)?)
+                    .filter(|count| *count > 0)
+                    .map(|count| count as usize);
+                index += 2;
+            }
+            "BLOCK" => {
+                let timeout = arg_str(args, index + 1, name)?
+                    .parse::<i64>()
+                    .map_err(|_| "timeout is not an integer or out of range".to_string())?;
+                if timeout < 0 {
+                    return Err("timeout is negative".to_string());
+                }
+                options.block = Some(Some(timeout as u64).filter(|timeout| *timeout > 0));
+                index += 2;
+            }
+            "NOACK" if group => {
+                options.noack = true;
+                index += 1;
+            }
+            "STREAMS" => {
+                options.streams = index;
+                return Ok(options);
+            }
+            _ => return Err("syntax error".to_string()),
+        }
+    }
+}
+
+/// Pairs the keys following `STREAMS` with their IDs.
+fn stream_args<'a>(args: &'a [RespType], streams: usize, name: &str) -> Result<Vec<(&'a str, &'a str)>, String> {
+    let count = args.len() - streams - 1;
+    if count == 0 || !count.is_multiple_of(2) {
+        let id = if name == "XREADGROUP" { ">" } else { "$" };
+        return Err(format!(
+            "Unbalanced '{}' list of streams: for each stream key an ID or '{}' must be specified.",
+            name.to_lowercase(),
+            id
+        ));
+    }
+    (1..=count / 2)
+        .map(|offset| Ok((arg_str(args, streams + offset, name)?, arg_str(args, streams + offset + count / 2, name)?)))
+        .collect()
+}
+
+/// `XREAD [COUNT count] [BLOCK milliseconds] STREAMS key [key ...] id [id ...]`
+///
+/// `$` as ID stands for the last entry of the stream when the command runs, so that a
+/// blocking read only returns entries added afterwards.
+pub struct XRead;
+
+impl Command for XRead {
+    fn name(&self) -> &str {
+        "XREAD"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        read_keys(args, self.name())
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        self.execute_with_client(args, state, &mut ClientContext::default())
+    }
+
+    fn execute_with_client(
+        &self,
+        args: &[RespType],
+        state: &mut dyn ServerState,
+        client: &mut ClientContext,
+    ) -> Result<RespType, String> {
+        let options = read_options(args, 0, self.name(), false)?;
+        let mut keys = Vec::new();
+        let mut ids = Vec::new();
+        for (key, id) in stream_args(args, options.streams, self.name())? {
+            let id = match id {
+                "$" => stream(state, key)?.map_or(StreamId::MIN, |stream| stream.last_id),
+                id => parse_id(id, 0)?,
+            };
+            keys.push(key.to_string());
+            ids.push(id);
+        }
+
+        let wait = KeyWait {
+            keys,
+            operation: KeyOperation::XRead {
+                ids,
+                count: options.count,
+            },
+            deadline: options.deadline(),
+        };
+        match options.block {
+            Some(_) => serve_or_block(wait, state, client),
+            None => Ok(serve(state, &wait)?.unwrap_or(RespType::NullArray)),
+        }
+    }
+}
+
+fn no_group(key: &str, group: &str) -> String {
+    format!("NOGROUP No such key '{}' or consumer group '{}'", key, group)
+}
+
+/// The stream at `key` if it has the consumer group.
+fn group_stream<'a>(state: &'a mut dyn ServerState, key: &str, group: &str) -> Result<&'a mut Stream, String> {
+    stream(state, key)?.filter(|stream| stream.groups.contains_key(group)).ok_or_else(|| no_group(key, group))
+}
+
+/// `XCLAIM` replicating the delivery of a pending entry.
+fn claim_command(key: &str, group: &str, id: StreamId, consumer: &str, delivery_time: u64, count: u64) -> Vec<String> {
+    let id = id.to_string();
+    ["XCLAIM", key, group, consumer, "0", &id, "TIME", &delivery_time.to_string(), "RETRYCOUNT"]
+        .iter()
+        .map(|part| part.to_string())
+        .chain([count.to_string(), "FORCE".to_string(), "JUSTID".to_string()])
+        .collect()
+}
+
+/// `XGROUP SETID` replicating the position of a group.
+fn set_id_command(key: &str, group_name: &str, group: &ConsumerGroup) -> Vec<String> {
+    let entries_read = group.entries_read.map_or("-1".to_string(), |entries_read| entries_read.to_string());
+    ["XGROUP", "SETID", key, group_name, &group.last_id.to_string(), "ENTRIESREAD", &entries_read]
+        .iter()
+        .map(|part| part.to_string())
+        .collect()
+}
+
+fn propagate_all(state: &mut dyn ServerState, commands: Vec<Vec<String>>) {
+    for command in commands {
+        propagate(state, &command.iter().map(String::as_str).collect::<Vec<_>>());
+    }
+}
+
+/// Entries of the streams at `keys` delivered to `consumer` of `group`, as an `XREADGROUP`
+/// reply. An ID of `None` (`>`) delivers entries no consumer of the group got yet, and
+/// `None` is returned when there are none; other IDs read the consumer's pending entries.
+pub(crate) fn read_group(
+    state: &mut dyn ServerState,
+    keys: &[String],
+    ids: &[Option<StreamId>],
+    group: &str,
+    consumer: &str,
+    count: Option<usize>,
+    noack: bool,
+) -> Result<Option<RespType>, String> {
+    let now = current_time_ms();
+    let count = count.unwrap_or(usize::MAX);
+    let mut reply = Vec::new();
+    for (key, id) in keys.iter().zip(ids) {
+        let stream = group_stream(state, key, group)
+            .map_err(|_| format!("{} in XREADGROUP with GROUP option", no_group(key, group)))?;
+        let mut commands = Vec::new();
+        let group_state = stream.groups.get_mut(group).ok_or_else(|| no_group(key, group))?;
+        if group_state.touch_consumer(consumer, now) {
+            commands.push(["XGROUP", "CREATECONSUMER", key, group, consumer].map(str::to_string).to_vec());
+        }
+
+        let entries = match id {
+            None => {
+                let delivered = stream.deliver_new(group, consumer, count, noack, now);
+                if !delivered.is_empty() {
+                    let group_state = stream.groups.get(group).ok_or_else(|| no_group(key, group))?;
+                    if !noack {
+                        let claims = delivered.iter().map(|(id, _)| claim_command(key, group, *id, consumer, now, 1));
+                        commands.extend(claims);
+                    }
+                    commands.push(set_id_command(key, group, group_state));
+                }
+                delivered.iter().map(|(id, fields)| entry_reply(id, fields)).collect::<Vec<_>>()
+            }
+            Some(start) => {
+                let pending = match (group_state.consumers.get(consumer), start.next()) {
+                    (Some(consumer), Some(start)) => consumer.pending.range(start..).take(count).copied().collect(),
+                    _ => Vec::new(),
+                };
+                let mut entries = Vec::new();
+                for id in pending {
+                    let Some(fields) = stream.get(id).cloned() else {
+                        // Deleted from the stream while pending.
+                        entries.push(RespType::Array(vec![bulk(&id.to_string()), RespType::NullArray]));
+                        continue;
+                    };
+                    let group_state = stream.groups.get_mut(group).ok_or_else(|| no_group(key, group))?;
+                    if let Some(delivery_count) = group_state.pending.get(&id).map(|entry| entry.delivery_count + 1) {
+                        group_state.deliver(id, consumer, now, delivery_count);
+                        commands.push(claim_command(key, group, id, consumer, now, delivery_count));
+                    }
+                    entries.push(entry_reply(&id, &fields));
+                }
+                entries
+            }
+        };
+
+        propagate_all(state, commands);
+        // History reads always reply for every stream.
+        if id.is_some() || !entries.is_empty() {
+            reply.push(RespType::Array(vec![bulk(key), RespType::Array(entries)]));
+        }
+    }
+    if reply.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(RespType::Array(reply)))
+}
+
+/// `XREADGROUP GROUP group consumer [COUNT count] [BLOCK milliseconds] [NOACK] STREAMS key [key ...] id [id ...]`
+///
+/// `>` as ID delivers entries no consumer of the group got yet, adding them to the pending
+/// entries of the consumer unless `NOACK` is given. Other IDs read back the consumer's
+/// pending entries and never block.
+pub struct XReadGroup;
+
+impl Command for XReadGroup {
+    fn name(&self) -> &str {
+        "XREADGROUP"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn propagate_verbatim(&self) -> bool {
+        false
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        read_keys(args, self.name())
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        self.execute_with_client(args, state, &mut ClientContext::default())
+    }
+
+    fn execute_with_client(
+        &self,
+        args: &[RespType],
+        state: &mut dyn ServerState,
+        client: &mut ClientContext,
+    ) -> Result<RespType, String> {
+        if !arg_str(args, 0, self.name())?.eq_ignore_ascii_case("GROUP") {
+            return Err("syntax error".to_string());
+        }
+        let group = arg_str(args, 1, self.name())?;
+        let consumer = arg_str(args, 2, self.name())?;
+        let options = read_options(args, 3, self.name(), true)?;
+        let mut keys = Vec::new();
+        let mut ids = Vec::new();
+        for (key, id) in stream_args(args, options.streams, self.name())? {
+            let id = match id {
+                ">" => None,
+                "$" => {
+                    return Err("The $ ID is meaningless in the context of XREADGROUP: you want to read the history \
+                                of this consumer by specifying a proper ID, or use the > ID to get new messages. The \
+                                $ ID would just return an empty result set."
+                        .to_string())
+                }
+                id => Some(parse_id(id, 0)?),
+            };
+            group_stream(state, key, group)
+                .map_err(|_| format!("{} in XREADGROUP with GROUP option", no_group(key, group)))?;
+            keys.push(key.to_string());
+            ids.push(id);
+        }
+
+        let wait = KeyWait {
+            keys,
+            operation: KeyOperation::XReadGroup {
+                group: group.to_string(),
+                consumer: consumer.to_string(),
+                ids,
+                count: options.count,
+                noack: options.noack,
+            },
+            deadline: options.deadline(),
+        };
+        match options.block {
+            Some(_) => serve_or_block(wait, state, client),
+            None => Ok(serve(state, &wait)?.unwrap_or(RespType::NullArray)),
+        }
+    }
+}
+
+/// Parses the ID a group starts reading after, `$` standing for the last entry.
+fn group_id(value: &str, stream: &Stream) -> Result<StreamId, String> {
+    match value {
+        "$" => Ok(stream.last_id),
+        value => parse_id(value, 0),
+    }
+}
+
+/// Parses the optional `ENTRIESREAD entries_read` at `index`, -1 meaning unknown.
+fn entries_read_arg(args: &[RespType], index: usize, name: &str) -> Result<Option<u64>, String> {
+    if !arg_str(args, index, name)?.eq_ignore_ascii_case("ENTRIESREAD") {
+        return Err("syntax error".to_string());
+    }
+    match arg_i64(args, index + 1, name)? {
+        -1 => Ok(None),
+        entries_read if entries_read >= 0 => Ok(Some(entries_read as u64)),
+        _ => Err("value for ENTRIESREAD must be positive or -1".to_string()),
+    }
+}
+
+/// `XGROUP CREATE key group id|$ [MKSTREAM] [ENTRIESREAD entries_read]`,
+/// `XGROUP SETID key group id|$ [ENTRIESREAD entries_read]`, `XGROUP DESTROY key group`,
+/// `XGROUP CREATECONSUMER key group consumer` and `XGROUP DELCONSUMER key group consumer`.
+pub struct XGroup;
+
+impl Command for XGroup {
+    fn name(&self) -> &str {
+        "XGROUP"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        args.get(1..).map_or_else(Vec::new, first_key)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let subcommand = arg_str(args, 0, self.name())?.to_lowercase();
+        let key = arg_str(args, 1, self.name())?;
+        let group = arg_str(args, 2, self.name())?;
+        if subcommand == "create" && stream(state, key)?.is_none() {
+            let mkstream = (4..args.len())
+                .any(|index| arg_str(args, index, self.name()).is_ok_and(|arg| arg.eq_ignore_ascii_case("MKSTREAM")));
+            if mkstream {
+                state.set(key.to_string(), Value::Stream(Stream::default()), None)?;
+            }
+        }
+        let Some(stream) = stream(state, key)? else {
+            return Err("The XGROUP subcommand requires the key to exist. Note that for CREATE you may want to use \
+                        the MKSTREAM option to create an empty stream automatically."
+                .to_string());
+        };
+        let no_group = || format!("NOGROUP No such consumer group '{}' for key name '{}'", group, key);
+
+        match subcommand.as_str() {
+            "create" => {
+                let id = group_id(arg_str(args, 3, self.name())?, stream)?;
+                let mut entries_read = None;
+                let mut index = 4;
+                while index < args.len() {
+                    if arg_str(args, index, self.name())?.eq_ignore_ascii_case("MKSTREAM") {
+                        index += 1;
+                    } else {
+                        entries_read = entries_read_arg(args, index, self.name())?;
+                        index += 2;
+                    }
+                }
+                if stream.groups.contains_key(group) {
+                    return Err("BUSYGROUP Consumer Group name already exists".to_string());
+                }
+                stream.groups.insert(group.to_string(), ConsumerGroup::new(id, entries_read));
+                Ok(RespType::SimpleString("OK".to_string()))
+            }
+            "setid" => {
+                let id = group_id(arg_str(args, 3, self.name())?, stream)?;
+                let entries_read = match args.len() {
+                    4 => None,
+                    6 => entries_read_arg(args, 4, self.name())?,
+                    _ => return Err("syntax error".to_string()),
+                };
+                let group = stream.groups.get_mut(group).ok_or_else(no_group)?;
+                group.last_id = id;
+                group.entries_read = entries_read;
+                Ok(RespType::SimpleString("OK".to_string()))
+            }
+            "destroy" => Ok(RespType::Integer(stream.groups.remove(group).is_some() as i64)),
+            "createconsumer" => {
+                let consumer = arg_str(args, 3, self.name())?;
+                let group = stream.groups.get_mut(group).ok_or_else(no_group)?;
+                if group.consumers.contains_key(consumer) {
+                    return Ok(RespType::Integer(0));
+                }
+                group.touch_consumer(consumer, current_time_ms());
+                Ok(RespType::Integer(1))
+            }
+            "delconsumer" => {
+                let consumer = arg_str(args, 3, self.name())?;
+                let group = stream.groups.get_mut(group).ok_or_else(no_group)?;
+                Ok(RespType::Integer(group.remove_consumer(consumer).unwrap_or(0) as i64))
+            }
+            _ => Err(format!("Unknown xgroup subcommand '{}'", subcommand)),
+        }
+    }
+}
+
+/// `XACK key group id [id ...]`: returns the number of entries acknowledged.
+pub struct XAck;
+
+impl Command for XAck {
+    fn name(&self) -> &str {
+        "XACK"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let group = arg_str(args, 1, self.name())?;
+        arg_str(args, 2, self.name())?;
+        let ids = (2..args.len())
+            .map(|index| parse_id(arg_str(args, index, self.name())?, 0))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let Some(group) = stream(state, key)?.and_then(|stream| stream.groups.get_mut(group)) else {
+            return Ok(RespType::Integer(0));
+        };
+        Ok(RespType::Integer(ids.into_iter().filter(|id| group.acknowledge(*id)).count() as i64))
+    }
+}
+
+/// `XPENDING key group [[IDLE min-idle-time] start end count [consumer]]`
+///
+/// Without a range, summarizes the pending entries of the group: their number, smallest
+/// and greatest IDs, and the number for each consumer.
+pub struct XPending;
+
+impl Command for XPending {
+    fn name(&self) -> &str {
+        "XPENDING"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let group_name = arg_str(args, 1, self.name())?;
+        let group = group_stream(state, key, group_name)?
+            .groups
+            .get(group_name)
+            .ok_or_else(|| no_group(key, group_name))?;
+
+        if args.len() == 2 {
+            let (Some(first), Some(last)) = (group.pending.keys().next(), group.pending.keys().next_back()) else {
+                return Ok(RespType::Array(vec![
+                    RespType::Integer(0),
+                    RespType::BulkString(None),
+                    RespType::BulkString(None),
+                    RespType::NullArray,
+                ]));
+            };
+            let consumers = group
+                .consumers
+                .iter()
+                .filter(|(_, consumer)| !consumer.pending.is_empty())
+                .map(|(name, consumer)| RespType::Array(vec![bulk(name), bulk(&consumer.pending.len().to_string())]))
+                .collect();
+            return Ok(RespType::Array(vec![
+                RespType::Integer(group.pending.len() as i64),
+                bulk(&first.to_string()),
+                bulk(&last.to_string()),
+                RespType::Array(consumers),
+            ]));
+        }
+
+        let mut index = 2;
+        let mut min_idle = 0;
+        if arg_str(args, index, self.name())?.eq_ignore_ascii_case("IDLE") {
+            min_idle = arg_i64(args, index + 1, self.name())?.max(0) as u64;
+            index += 2;
+        }
+        let start = range_start(arg_str(args, index, self.name())?)?;
+        let end = range_end(arg_str(args, index + 1, self.name())?)?;
+        let count = arg_i64(args, index + 2, self.name())?.max(0) as usize;
+        let consumer = match args.len() - index {
+            3 => None,
+            4 => Some(arg_str(args, index + 3, self.name())?),
+            _ => return Err("syntax error".to_string()),
+        };
+        if start > end {
+            return Ok(RespType::Array(vec![]));
+        }
+
+        let now = current_time_ms();
+        let entries = group
+            .pending
+            .range(start..=end)
+            .filter(|(_, entry)| consumer.is_none_or(|consumer| entry.consumer == consumer))
+            .filter(|(_, entry)| now.saturating_sub(entry.delivery_time) >= min_idle)
+            .take(count)
+            .map(|(id, entry)| {
+                RespType::Array(vec![
+                    bulk(&id.to_string()),
+                    bulk(&entry.consumer),
+                    RespType::Integer(now.saturating_sub(entry.delivery_time) as i64),
+                    RespType::Integer(entry.delivery_count as i64),
+                ])
+            })
+            .collect();
+        Ok(RespType::Array(entries))
+    }
+}
+
+/// Outcome of claiming a pending entry.
+enum Claim {
+    Claimed(StreamId, Fields),
+    /// The entry was deleted from the stream, so it was removed from the pending entries.
+    Deleted(StreamId),
+    Skipped,
+}
+
+/// How `XCLAIM` and `XAUTOCLAIM` update the entries they claim.
+struct ClaimOptions {
+    now: u64,
+    min_idle: u64,
+    /// Delivery time given to the claimed entries.
+    delivery_time: u64,
+    /// Delivery count given to the claimed entries, which is otherwise incremented.
+    retry_count: Option<u64>,
+    force: bool,
+    just_id: bool,
+}
+
+/// Gives a pending entry to `consumer` if it has been idle for long enough, or creates
+/// it with `FORCE`. Propagates the claim as an `XCLAIM` (or an `XACK` if the entry was
+/// deleted) in `commands`.
+fn claim(
+    stream: &mut Stream,
+    key: &str,
+    group_name: &str,
+    consumer: &str,
+    id: StreamId,
+    options: &ClaimOptions,
+    commands: &mut Vec<Vec<String>>,
+) -> Claim {
+    let fields = stream.get(id).cloned();
+    let Some(group) = stream.groups.get_mut(group_name) else {
+        return Claim::Skipped;
+    };
+    let delivery_count = match group.pending.get(&id) {
+        None if options.force && fields.is_some() => 1,
+        None => return Claim::Skipped,
+        Some(_) if fields.is_none() => {
+            group.acknowledge(id);
+            commands.push(["XACK", key, group_name, &id.to_string()].map(str::to_string).to_vec());
+            return Claim::Deleted(id);
+        }
+        Some(entry) if options.now.saturating_sub(entry.delivery_time) < options.min_idle => {
+            return Claim::Skipped;
+        }
+        Some(entry) => entry.delivery_count,
+    };
+    let delivery_count = match options.retry_count {
+        Some(retry_count) => retry_count,
+        None if options.just_id => delivery_count,
+        None => delivery_count + 1,
+    };
+    group.touch_consumer(consumer, options.delivery_time);
+    group.deliver(id, consumer, options.delivery_time, delivery_count);
+    commands.push(claim_command(key, group_name, id, consumer, options.delivery_time, delivery_count));
+    Claim::Claimed(id, fields.unwrap_or_default())
+}
+
+fn claimed_reply(id: StreamId, fields: &Fields, just_id: bool) -> RespType {
+    if just_id {
+        bulk(&id.to_string())
+    } else {
+        entry_reply(&id, fields)
+    }
+}
+
+/// `XCLAIM key group consumer min-idle-time id [id ...] [IDLE ms] [TIME unix-time-milliseconds]
+/// [RETRYCOUNT count] [FORCE] [JUSTID] [LASTID lastid]`
+///
+/// Gives the pending entries idle for at least `min-idle-time` milliseconds to `consumer`.
+pub struct XClaim;
+
+impl Command for XClaim {
+    fn name(&self) -> &str {
+        "XCLAIM"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn propagate_verbatim(&self) -> bool {
+        false
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let group_name = arg_str(args, 1, self.name())?;
+        let consumer = arg_str(args, 2, self.name())?;
+        let min_idle = arg_str(args, 3, self.name())?
+            .parse::<i64>()
+            .map_err(|_| "Invalid min-idle-time argument for XCLAIM".to_string())?
+            .max(0) as u64;
+        arg_str(args, 4, self.name())?;
+        let mut ids = Vec::new();
+        let mut index = 4;
+        while let Some(id) = arg_str(args, index, self.name()).ok().and_then(|arg| parse_id(arg, 0).ok()) {
+            ids.push(id);
+            index += 1;
+        }
+
+        let now = current_time_ms();
+        let mut options = ClaimOptions {
+            now,
+            min_idle,
+            delivery_time: now,
+            retry_count: None,
+            force: false,
+            just_id: false,
+        };
+        let mut last_id = None;
+        while index < args.len() {
+            let option = arg_str(args, index, self.name())?;
+            match option.to_uppercase().as_str() {
+                "IDLE" => {
+                    options.delivery_time = now.saturating_sub(arg_i64(args, index + 1, self.name())?.max(0) as u64);
+                    index += 1;
+                }
+                "TIME" => {
+                    // Times in the future are the current time.
+                    options.delivery_time = (arg_i64(args, index + 1, self.name())?.max(0) as u64).min(now);
+                    index += 1;
+                }
+                "RETRYCOUNT" => {
+                    options.retry_count = Some(arg_i64(args, index + 1, self.name())?.max(0) as u64);
+                    index += 1;
+                }
+                "FORCE" => options.force = true,
+                "JUSTID" => options.just_id = true,
+                "LASTID" => {
+                    last_id = Some(parse_id(arg_str(args, index + 1, self.name())?, 0)?);
+                    index += 1;
+                }
+                _ => return Err(format!("Unrecognized XCLAIM option '{}'", option)),
+            }
+            index += 1;
+        }
+        let stream = group_stream(state, key, group_name)?;
+        let mut commands = Vec::new();
+        let mut reply = Vec::new();
+        for id in ids {
+            if let Claim::Claimed(id, fields) = claim(stream, key, group_name, consumer, id, &options, &mut commands) {
+                reply.push(claimed_reply(id, &fields, options.just_id));
+            }
+        }
+        if let (Some(last_id), Some(group)) = (last_id, stream.groups.get_mut(group_name)) {
+            if last_id > group.last_id {
+                group.last_id = last_id;
+                commands.push(set_id_command(key, group_name, group));
+            }
+        }
+        propagate_all(state, commands);
+        Ok(RespType::Array(reply))
+    }
+}
+
+/// `XAUTOCLAIM key group consumer min-idle-time start [COUNT count] [JUSTID]`
+///
+/// Claims up to `count` entries idle for long enough, scanning the pending entries from
+/// `start`. Replies with the ID to continue from (0-0 once done), the claimed entries and
+/// the IDs of the entries that were deleted from the stream.
+pub struct XAutoClaim;
+
+impl Command for XAutoClaim {
+    fn name(&self) -> &str {
+        "XAUTOCLAIM"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn propagate_verbatim(&self) -> bool {
+        false
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        /// Pending entries looked at for each entry to claim.
+        const ATTEMPTS_FACTOR: usize = 10;
+
+        let key = arg_str(args, 0, self.name())?;
+        let group_name = arg_str(args, 1, self.name())?;
+        let consumer = arg_str(args, 2, self.name())?;
+        let min_idle = arg_str(args, 3, self.name())?
+            .parse::<i64>()
+            .map_err(|_| "Invalid min-idle-time argument for XAUTOCLAIM".to_string())?
+            .max(0) as u64;
+        let start = range_start(arg_str(args, 4, self.name())?)?;
+        let mut count = 100;
+        let mut just_id = false;
+        let mut index = 5;
+        while index < args.len() {
+            match arg_str(args, index, self.name())?.to_uppercase().as_str() {
+                "COUNT" => {
+                    count = match arg_i64(args, index + 1, self.name())? {
+                        count if count >= 1 && count <= i64::MAX / ATTEMPTS_FACTOR as i64 => count as usize,
+                        _ => return Err("COUNT must be > 0".to_string()),
+                    };
+                    index += 2;
+                }
+                "JUSTID" => {
+                    just_id = true;
+                    index += 1;
+                }
+                _ => return Err("syntax error".to_string()),
+            }
+        }
+
+        let stream = group_stream(state, key, group_name)?;
+        let attempts = count.saturating_mul(ATTEMPTS_FACTOR);
+        let candidates = stream.groups.get(group_name).map_or_else(Vec::new, |group| {
+            group.pending.range(start..).map(|(id, _)| *id).take(attempts.saturating_add(1)).collect::<Vec<_>>()
+        });
+        let now = current_time_ms();
+        let options = ClaimOptions {
+            now,
+            min_idle,
+            delivery_time: now,
+            retry_count: None,
+            force: false,
+            just_id,
+        };
+
+        let mut commands = Vec::new();
+        let mut claimed = Vec::new();
+        let mut deleted = Vec::new();
+        let mut visited = 0;
+        for id in &candidates {
+            if visited == attempts || claimed.len() == count {
+                break;
+            }
+            visited += 1;
+            match claim(stream, key, group_name, consumer, *id, &options, &mut commands) {
+                Claim::Claimed(id, fields) => claimed.push(claimed_reply(id, &fields, just_id)),
+                Claim::Deleted(id) => deleted.push(bulk(&id.to_string())),
+                Claim::Skipped => {}
+            }
+        }
+        let cursor = candidates.get(visited).copied().unwrap_or(StreamId::MIN);
+        propagate_all(state, commands);
+        Ok(RespType::Array(vec![
+            bulk(&cursor.to_string()),
+            RespType::Array(claimed),
+            RespType::Array(deleted),
+        ]))
+    }
+}
+
+/// `XINFO STREAM key`, `XINFO GROUPS key` and `XINFO CONSUMERS key group`, replying with
+/// flat lists of names and values.
+pub struct XInfo;
+
+impl Command for XInfo {
+    fn name(&self) -> &str {
+        "XINFO"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        args.get(1..).map_or_else(Vec::new, first_key)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let subcommand = arg_str(args, 0, self.name())?.to_lowercase();
+        let key = arg_str(args, 1, self.name())?;
+        let arity = if subcommand == "consumers" { 3 } else { 2 };
+        if args.len() != arity {
+            return Err("syntax error".to_string());
+        }
+        let Some(stream) = stream(state, key)? else {
+            return Err("no such key".to_string());
+        };
+        let id = |id: StreamId| bulk(&id.to_string());
+        let optional = |value: Option<u64>| {
+            value.map_or(RespType::BulkString(None), |value| RespType::Integer(value as i64))
+        };
+        let fields = |pairs: Vec<(&str, RespType)>| {
+            RespType::Array(pairs.into_iter().flat_map(|(name, value)| [bulk(name), value]).collect())
+        };
+        match subcommand.as_str() {
+            "stream" => {
+                let first = stream.iter().next();
+                let last = stream.iter().last();
+                let entry = |entry: Option<(&StreamId, &Fields)>| {
+                    entry.map_or(RespType::BulkString(None), |(id, fields)| entry_reply(id, fields))
+                };
+                Ok(fields(vec![
+                    ("length", RespType::Integer(stream.len() as i64)),
+                    ("last-generated-id", id(stream.last_id)),
+                    ("max-deleted-entry-id", id(stream.max_deleted_id)),
+                    ("entries-added", RespType::Integer(stream.entries_added as i64)),
+                    ("recorded-first-entry-id", id(stream.first_id().unwrap_or_default())),
+                    ("groups", RespType::Integer(stream.groups.len() as i64)),
+                    ("first-entry", entry(first)),
+                    ("last-entry", entry(last)),
+                ]))
+            }
+            "groups" => Ok(RespType::Array(
+                stream
+                    .groups
+                    .iter()
+                    .map(|(name, group)| {
+                        fields(vec![
+                            ("name", bulk(name)),
+                            ("consumers", RespType::Integer(group.consumers.len() as i64)),
+                            ("pending", RespType::Integer(group.pending.len() as i64)),
+                            ("last-delivered-id", id(group.last_id)),
+                            ("entries-read", optional(group.entries_read)),
+                            ("lag", optional(stream.lag(group))),
+                        ])
+                    })
+                    .collect(),
+            )),
+            "consumers" => {
+                let group_name = arg_str(args, 2, self.name())?;
+                let group = stream.groups.get(group_name).ok_or_else(|| no_group(key, group_name))?;
+                let now = current_time_ms();
+                Ok(RespType::Array(
+                    group
+                        .consumers
+                        .iter()
+                        .map(|(name, consumer)| {
+                            let inactive = consumer
+                                .active_time
+                                .map_or(-1, |active_time| now.saturating_sub(active_time) as i64);
+                            fields(vec![
+                                ("name", bulk(name)),
+                                ("pending", RespType::Integer(consumer.pending.len() as i64)),
+                                ("idle", RespType::Integer(now.saturating_sub(consumer.seen_time) as i64)),
+                                ("inactive", RespType::Integer(inactive)),
+                            ])
+                        })
+                        .collect(),
+                ))
+            }
+            _ => Err(format!("Unknown xinfo subcommand '{}'", subcommand)),
+        }
+    }
+}
@@ -0,0 +1,549 @@
+//! String commands besides `SET` and `GET`. Counters are strings holding the decimal form of a
+//! number, and modifying them keeps the TTL of the key.
+
+use crate::resp::commands::{arg_bytes, arg_i64, arg_str, current_time_ms, first_key, propagate, Command};
+use crate::resp::protocol::RespType;
+use crate::resp::state::server_state::ServerState;
+use crate::resp::state::value::{Value, WRONG_TYPE};
+
+fn string<'a>(state: &'a mut dyn ServerState, key: &str) -> Result<Option<&'a [u8]>, String> {
+    match state.get(key) {
+        Some(Value::String(value)) => Ok(Some(value)),
+        Some(_) => Err(WRONG_TYPE.to_string()),
+        None => Ok(None),
+    }
+}
+
+fn bulk_or_nil(value: Option<&[u8]>) -> RespType {
+    value.map_or(RespType::BulkString(None), |value| RespType::bulk_bytes(value.to_vec()))
+}
+
+fn ok() -> RespType {
+    RespType::SimpleString("OK".to_string())
+}
+
+fn arity(args: &[RespType], expected: usize, name: &str) -> Result<(), String> {
+    if args.len() != expected {
+        return Err(format!("wrong number of arguments for '{}' command", name.to_lowercase()));
+    }
+    Ok(())
+}
+
+/// Keys of commands taking `key value [key value ...]`.
+fn pair_keys<'a>(args: &'a [RespType], name: &str) -> Vec<&'a str> {
+    (0..args.len()).step_by(2).filter_map(|index| arg_str(args, index, name).ok()).collect()
+}
+
+/// A positive expire time in milliseconds, given in seconds when `unit` is 1000.
+fn expire_time(args: &[RespType], index: usize, unit: u64, name: &str) -> Result<u64, String> {
+    let time = arg_i64(args, index, name)?;
+    let time = u64::try_from(time).ok().filter(|time| *time > 0).and_then(|time| time.checked_mul(unit));
+    time.ok_or_else(|| format!("invalid expire time in '{}' command", name.to_lowercase()))
+}
+
+/// Absolute expiration time in milliseconds set by the `EX`, `PX`, `EXAT` or `PXAT` option at
+/// `index`, shared by `SET` and `GETEX`. `None` when the argument is another option.
+pub(crate) fn expire_option(args: &[RespType], index: usize, name: &str) -> Result<Option<u64>, String> {
+    let (unit, relative) = match arg_str(args, index, name)?.to_uppercase().as_str() {
+        "EX" => (1000, true),
+        "PX" => (1, true),
+        "EXAT" => (1000, false),
+        "PXAT" => (1, false),
+        _ => return Ok(None),
+    };
+    if args.len() <= index + 1 {
+        return Err("syntax error".to_string());
+    }
+    let time = expire_time(args, index + 1, unit, name)?;
+    match relative {
+        true => current_time_ms()
+            .checked_add(time)
+            .map(Some)
+            .ok_or_else(|| format!("invalid expire time in '{}' command", name.to_lowercase())),
+        false => Ok(Some(time)),
+    }
+}
+
+/// `INCR key`
+pub struct Incr;
+
+impl Command for Incr {
+    fn name(&self) -> &str {
+        "INCR"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        arity(args, 1, self.name())?;
+        Ok(RespType::Integer(state.incr(arg_str(args, 0, self.name())?)?))
+    }
+}
+
+/// `DECR key`
+pub struct Decr;
+
+impl Command for Decr {
+    fn name(&self) -> &str {
+        "DECR"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        arity(args, 1, self.name())?;
+        Ok(RespType::Integer(state.decr(arg_str(args, 0, self.name())?)?))
+    }
+}
+
+/// `INCRBY key increment`
+pub struct IncrBy;
+
+impl Command for IncrBy {
+    fn name(&self) -> &str {
+        "INCRBY"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        arity(args, 2, self.name())?;
+        let key = arg_str(args, 0, self.name())?;
+        Ok(RespType::Integer(state.incr_by(key, arg_i64(args, 1, self.name())?)?))
+    }
+}
+
+/// `DECRBY key decrement`
+pub struct DecrBy;
+
+impl Command for DecrBy {
+    fn name(&self) -> &str {
+        "DECRBY"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        arity(args, 2, self.name())?;
+        let key = arg_str(args, 0, self.name())?;
+        let delta = arg_i64(args, 1, self.name())?.checked_neg();
+        let delta = delta.ok_or_else(|| "decrement would overflow".to_string())?;
+        Ok(RespType::Integer(state.incr_by(key, delta)?))
+    }
+}
+
+/// `INCRBYFLOAT key increment`: replies with the new value as a string.
+pub struct IncrByFloat;
+
+impl Command for IncrByFloat {
+    fn name(&self) -> &str {
+        "INCRBYFLOAT"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    /// Replicas get a `SET` of the result, so they store the same value rather than
+    /// rounding it their own way.
+    fn propagate_verbatim(&self) -> bool {
+        false
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        arity(args, 2, self.name())?;
+        let key = arg_str(args, 0, self.name())?;
+        let float = |value: &str| value.parse::<f64>().ok().filter(|value| value.is_finite());
+        let not_float = || "value is not a valid float".to_string();
+        let increment = float(arg_str(args, 1, self.name())?).ok_or_else(not_float)?;
+        let current = match string(state, key)? {
+            Some(value) => std::str::from_utf8(value).ok().and_then(float).ok_or_else(not_float)?,
+            None => 0.0,
+        };
+        let value = current + increment;
+        if !value.is_finite() {
+            return Err("increment would produce NaN or Infinity".to_string());
+        }
+
+        let value = value.to_string();
+        match state.get_mut(key) {
+            Some(Value::String(existing)) => *existing = value.clone().into_bytes(),
+            _ => state.set(key.to_string(), Value::String(value.clone().into_bytes()), None)?,
+        }
+        propagate(state, &["SET", key, &value, "KEEPTTL"]);
+        Ok(RespType::BulkString(Some(value)))
+    }
+}
+
+/// `APPEND key value`: replies with the new length of the string.
+pub struct Append;
+
+impl Command for Append {
+    fn name(&self) -> &str {
+        "APPEND"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        arity(args, 2, self.name())?;
+        state.append(arg_str(args, 0, self.name())?, arg_bytes(args, 1, self.name())?)
+    }
+}
+
+/// `STRLEN key`: 0 for a missing key.
+pub struct StrLen;
+
+impl Command for StrLen {
+    fn name(&self) -> &str {
+        "STRLEN"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        arity(args, 1, self.name())?;
+        let len = string(state, arg_str(args, 0, self.name())?)?.map_or(0, <[u8]>::len);
+        Ok(RespType::Integer(len as i64))
+    }
+}
+
+/// `GETRANGE key start end`: the bytes from `start` to `end` inclusive, negative indexes
+/// counting from the end of the string.
+pub struct GetRange;
+
+impl Command for GetRange {
+    fn name(&self) -> &str {
+        "GETRANGE"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        arity(args, 3, self.name())?;
+        let key = arg_str(args, 0, self.name())?;
+        state.get_range(key, arg_i64(args, 1, self.name())?, arg_i64(args, 2, self.name())?)
+    }
+}
+
+/// `SETRANGE key offset value`: overwrites the string from `offset`, padding it with zero
+/// bytes, and replies with its new length.
+pub struct SetRange;
+
+impl Command for SetRange {
+    fn name(&self) -> &str {
+        "SETRANGE"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        arity(args, 3, self.name())?;
+        let key = arg_str(args, 0, self.name())?;
+        state.set_range(key, arg_i64(args, 1, self.name())?, arg_bytes(args, 2, self.name())?)
+    }
+}
+
+/// `GETSET key value`: sets the string and replies with the previous one.
+pub struct GetSet;
+
+impl Command for GetSet {
+    fn name(&self) -> &str {
+        "GETSET"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        arity(args, 2, self.name())?;
+        state.get_set(arg_str(args, 0, self.name())?, arg_bytes(args, 1, self.name())?)
+    }
+}
+
+/// `GETDEL key`: deletes the string and replies with it.
+pub struct GetDel;
+
+impl Command for GetDel {
+    fn name(&self) -> &str {
+        "GETDEL"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        arity(args, 1, self.name())?;
+        let key = arg_str(args, 0, self.name())?;
+        let Some(value) = string(state, key)?.map(<[u8]>::to_vec) else {
+            return Ok(RespType::BulkString(None));
+        };
+        state.del(key)?;
+        Ok(RespType::bulk_bytes(value))
+    }
+}
+
+/// `GETEX key [EX seconds | PX milliseconds | EXAT unix-time-seconds |
+/// PXAT unix-time-milliseconds | PERSIST]`: replies with the string, changing its TTL.
+pub struct GetEx;
+
+impl Command for GetEx {
+    fn name(&self) -> &str {
+        "GETEX"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        // Absolute expiration time in milliseconds, or `None` to persist the key.
+        let expires_at = match args.len() {
+            1 => return Ok(bulk_or_nil(string(state, key)?)),
+            2 if arg_str(args, 1, self.name())?.eq_ignore_ascii_case("PERSIST") => None,
+            3 => Some(expire_option(args, 1, self.name())?.ok_or_else(|| "syntax error".to_string())?),
+            _ => return Err("syntax error".to_string()),
+        };
+
+        let Some(value) = string(state, key)?.map(<[u8]>::to_vec) else {
+            return Ok(RespType::BulkString(None));
+        };
+        match expires_at {
+            None => state.persist(key)?,
+            // A time in the past deletes the key, as Redis does.
+            Some(expires_at) if expires_at <= current_time_ms() => state.del(key)?,
+            Some(expires_at) => {
+                let ttl = (expires_at - current_time_ms()) as i64;
+                state.set(key.to_string(), Value::String(value.clone()), Some(ttl))?;
+            }
+        }
+        Ok(RespType::bulk_bytes(value))
+    }
+}
+
+/// `SETNX key value`: sets the string only if the key does not exist, replying 1 if it did not.
+pub struct SetNx;
+
+impl Command for SetNx {
+    fn name(&self) -> &str {
+        "SETNX"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        arity(args, 2, self.name())?;
+        let key = arg_str(args, 0, self.name())?;
+        if state.exists(key) {
+            return Ok(RespType::Integer(0));
+        }
+        state.set(key.to_string(), Value::String(arg_bytes(args, 1, self.name())?.to_vec()), None)?;
+        Ok(RespType::Integer(1))
+    }
+}
+
+/// `SETEX key seconds value`
+pub struct SetEx;
+
+impl Command for SetEx {
+    fn name(&self) -> &str {
+        "SETEX"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        arity(args, 3, self.name())?;
+        let key = arg_str(args, 0, self.name())?;
+        let ttl = expire_time(args, 1, 1000, self.name())?;
+        state.set(key.to_string(), Value::String(arg_bytes(args, 2, self.name())?.to_vec()), Some(ttl as i64))?;
+        Ok(ok())
+    }
+}
+
+/// `PSETEX key milliseconds value`
+pub struct PSetEx;
+
+impl Command for PSetEx {
+    fn name(&self) -> &str {
+        "PSETEX"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        arity(args, 3, self.name())?;
+        let key = arg_str(args, 0, self.name())?;
+        let ttl = expire_time(args, 1, 1, self.name())?;
+        state.set(key.to_string(), Value::String(arg_bytes(args, 2, self.name())?.to_vec()), Some(ttl as i64))?;
+        Ok(ok())
+    }
+}
+
+/// Parses the `key value` pairs of `MSET` and `MSETNX`.
+fn pairs<'a>(args: &'a [RespType], name: &str) -> Result<Vec<(&'a str, &'a [u8])>, String> {
+    if args.is_empty() || !args.len().is_multiple_of(2) {
+        return Err(format!("wrong number of arguments for '{}' command", name.to_lowercase()));
+    }
+    (0..args.len())
+        .step_by(2)
+        .map(|index| Ok((arg_str(args, index, name)?, arg_bytes(args, index + 1, name)?)))
+        .collect()
+}
+
+/// `MSET key value [key value ...]`
+pub struct MSet;
+
+impl Command for MSet {
+    fn name(&self) -> &str {
+        "MSET"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        pair_keys(args, self.name())
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        for (key, value) in pairs(args, self.name())? {
+            state.set(key.to_string(), Value::String(value.to_vec()), None)?;
+        }
+        Ok(ok())
+    }
+}
+
+/// `MSETNX key value [key value ...]`: sets all the strings if none of the keys exists,
+/// replying 1 if they were set.
+pub struct MSetNx;
+
+impl Command for MSetNx {
+    fn name(&self) -> &str {
+        "MSETNX"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        pair_keys(args, self.name())
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let pairs = pairs(args, self.name())?;
+        if pairs.iter().any(|(key, _)| state.exists(key)) {
+            return Ok(RespType::Integer(0));
+        }
+        for (key, value) in pairs {
+            state.set(key.to_string(), Value::String(value.to_vec()), None)?;
+        }
+        Ok(RespType::Integer(1))
+    }
+}
+
+/// `MGET key [key ...]`: nil for keys missing or not holding a string.
+pub struct MGet;
+
+impl Command for MGet {
+    fn name(&self) -> &str {
+        "MGET"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        (0..args.len()).filter_map(|index| arg_str(args, index, self.name()).ok()).collect()
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.is_empty() {
+            return Err("wrong number of arguments for 'mget' command".to_string());
+        }
+        let mut values = Vec::new();
+        for index in 0..args.len() {
+            values.push(match state.get(arg_str(args, index, self.name())?) {
+                Some(Value::String(value)) => RespType::bulk_bytes(value.clone()),
+                _ => RespType::BulkString(None),
+            });
+        }
+        Ok(RespType::Array(values))
+    }
+}
@@ -0,0 +1,682 @@
+//! Time series commands. Adding to a missing key creates the series, and the rules of a series
+//! write each bucket to their destination once a sample in a later bucket arrives.
+
+use crate::resp::commands::{arg_str, current_time_ms, first_key, propagate, Command};
+use crate::resp::protocol::RespType;
+use crate::resp::state::server_state::ServerState;
+use crate::resp::state::value::{Value, WRONG_TYPE};
+use crate::resp::timeseries::{aggregate, bucket_start, Aggregation, DuplicatePolicy, Rule, TimeSeries};
+
+const NO_KEY: &str = "TSDB: the key does not exist";
+
+fn series<'a>(state: &'a mut dyn ServerState, key: &str) -> Result<Option<&'a mut TimeSeries>, String> {
+    match state.get_mut(key) {
+        Some(Value::TimeSeries(series)) => Ok(Some(series)),
+        Some(_) => Err(WRONG_TYPE.to_string()),
+        None => Ok(None),
+    }
+}
+
+fn existing<'a>(state: &'a mut dyn ServerState, key: &str) -> Result<&'a mut TimeSeries, String> {
+    series(state, key)?.ok_or_else(|| NO_KEY.to_string())
+}
+
+fn bulk(value: &str) -> RespType {
+    RespType::BulkString(Some(value.to_string()))
+}
+
+fn sample_reply((timestamp, value): (u64, f64)) -> RespType {
+    RespType::Array(vec![RespType::Integer(timestamp as i64), bulk(&value.to_string())])
+}
+
+/// A timestamp in milliseconds, `*` being the current time.
+fn parse_timestamp(timestamp: &str) -> Result<u64, String> {
+    if timestamp == "*" {
+        return Ok(current_time_ms());
+    }
+    timestamp.parse::<u64>().map_err(|_| "TSDB: invalid timestamp".to_string())
+}
+
+fn parse_value(value: &str) -> Result<f64, String> {
+    let value = value.parse::<f64>().ok().filter(|value| !value.is_nan());
+    value.ok_or_else(|| "TSDB: invalid value".to_string())
+}
+
+fn parse_policy(args: &[RespType], index: usize, name: &str) -> Result<DuplicatePolicy, String> {
+    DuplicatePolicy::parse(arg_str(args, index, name)?).ok_or_else(|| "TSDB: Unknown DUPLICATE_POLICY".to_string())
+}
+
+/// Parses `aggregator bucketDuration` at `index`.
+fn parse_aggregation(args: &[RespType], index: usize, name: &str) -> Result<(Aggregation, u64), String> {
+    let aggregation = Aggregation::parse(arg_str(args, index, name)?);
+    let aggregation = aggregation.ok_or_else(|| "TSDB: Unknown aggregation type".to_string())?;
+    let duration = arg_str(args, index + 1, name)?.parse::<u64>().ok().filter(|duration| *duration > 0);
+    let duration = duration.ok_or_else(|| "TSDB: bucketDuration must be greater than zero".to_string())?;
+    Ok((aggregation, duration))
+}
+
+/// Options of a series created by `TS.CREATE` or `TS.ADD`, and the duplicate policy of the
+/// sample added by `TS.ADD`.
+struct CreateOptions {
+    retention: u64,
+    duplicate_policy: DuplicatePolicy,
+    on_duplicate: Option<DuplicatePolicy>,
+    labels: Vec<(String, String)>,
+}
+
+impl CreateOptions {
+    fn parse(args: &[RespType], mut index: usize, name: &str) -> Result<CreateOptions, String> {
+        let mut options = CreateOptions {
+            retention: 0,
+            duplicate_policy: DuplicatePolicy::Block,
+            on_duplicate: None,
+            labels: Vec::new(),
+        };
+        while index < args.len() {
+            let value = |error: &str| {
+                let value = arg_str(args, index + 1, name)?.parse::<u64>();
+                value.map_err(|_| error.to_string())
+            };
+            match arg_str(args, index, name)?.to_uppercase().as_str() {
+                "RETENTION" => options.retention = value("TSDB: Couldn't parse RETENTION")?,
+                "ENCODING" => {
+                    let encoding = arg_str(args, index + 1, name)?;
+                    if !["COMPRESSED", "UNCOMPRESSED"].iter().any(|known| known.eq_ignore_ascii_case(encoding)) {
+                        return Err("TSDB: unknown ENCODING parameter".to_string());
+                    }
+                }
+                // Samples are not stored in chunks, the size is only validated.
+                "CHUNK_SIZE" => {
+                    let size = value("TSDB: Couldn't parse CHUNK_SIZE")?;
+                    if !(48..=1_048_576).contains(&size) || !size.is_multiple_of(8) {
+                        return Err("TSDB: CHUNK_SIZE value must be a multiple of 8 in the range [48 .. 1048576]"
+                            .to_string());
+                    }
+                }
+                "DUPLICATE_POLICY" => options.duplicate_policy = parse_policy(args, index + 1, name)?,
+                "ON_DUPLICATE" if name == "TS.ADD" => options.on_duplicate = Some(parse_policy(args, index + 1, name)?),
+                "LABELS" => {
+                    let labels = &args[index + 1..];
+                    if labels.is_empty() || !labels.len().is_multiple_of(2) {
+                        return Err(format!("wrong number of arguments for '{}' command", name.to_lowercase()));
+                    }
+                    for label in (index + 1..args.len()).step_by(2) {
+                        let value = arg_str(args, label + 1, name)?.to_string();
+                        options.labels.push((arg_str(args, label, name)?.to_string(), value));
+                    }
+                    break;
+                }
+                _ => return Err("syntax error".to_string()),
+            }
+            index += 2;
+        }
+        Ok(options)
+    }
+
+    fn create(&self) -> TimeSeries {
+        TimeSeries::new(self.retention, self.duplicate_policy, self.labels.clone())
+    }
+}
+
+/// Adds a sample to the series at `key` and writes the buckets the sample closes, or changes
+/// once closed, to the destinations of the series' rules, before applying the retention.
+fn add_sample(
+    state: &mut dyn ServerState,
+    key: &str,
+    timestamp: u64,
+    value: f64,
+    policy: Option<DuplicatePolicy>,
+) -> Result<(), String> {
+    let source = existing(state, key)?;
+    source.add(timestamp, value, policy)?;
+
+    let mut buckets = Vec::new();
+    for rule in &mut source.rules {
+        let start = bucket_start(timestamp, rule.bucket_duration, rule.align);
+        let bucket = match rule.bucket {
+            Some(open) if start > open => open,
+            Some(open) if start < open => start,
+            Some(_) => continue,
+            None => {
+                rule.bucket = Some(start);
+                continue;
+            }
+        };
+        rule.bucket = rule.bucket.max(Some(start));
+        buckets.push((rule.destination.clone(), rule.aggregation, rule.bucket_duration, rule.align, bucket));
+    }
+    let mut samples = Vec::new();
+    for (destination, aggregation, duration, align, bucket) in buckets {
+        let end = bucket.saturating_add(duration - 1);
+        let buckets = aggregate(source.range(bucket, end), aggregation, duration, align);
+        if let Some(sample) = buckets.into_iter().find(|(start, _)| *start == bucket) {
+            samples.push((destination, sample));
+        }
+    }
+    source.trim();
+
+    for (destination, (timestamp, value)) in samples {
+        // Destinations deleted or replaced since the rule was created are left alone, and a
+        // bucket past their retention is not an error of the sample added to the source.
+        if let Ok(Some(_)) = series(state, &destination) {
+            let _ = add_sample(state, &destination, timestamp, value, Some(DuplicatePolicy::Last));
+        }
+    }
+    Ok(())
+}
+
+/// Label matcher of `TS.MRANGE`: `label=value`, `label!=value`, or either with a
+/// `(value,value,...)` list. An empty value stands for a missing label.
+struct Matcher {
+    label: String,
+    negate: bool,
+    values: Vec<String>,
+}
+
+impl Matcher {
+    fn parse(filter: &str) -> Option<Matcher> {
+        let (label, negate, values) = match filter.split_once("!=") {
+            Some((label, values)) => (label, true, values),
+            None => filter.split_once('=').map(|(label, values)| (label, false, values))?,
+        };
+        if label.is_empty() {
+            return None;
+        }
+        let values = match values.strip_prefix('(').and_then(|list| list.strip_suffix(')')) {
+            Some(list) => list.split(',').map(str::to_string).collect(),
+            None => vec![values.to_string()],
+        };
+        Some(Matcher {
+            label: label.to_string(),
+            negate,
+            values,
+        })
+    }
+
+    /// Whether the matcher requires the label to have some value.
+    fn is_positive(&self) -> bool {
+        !self.negate && self.values.iter().all(|value| !value.is_empty())
+    }
+
+    fn matches(&self, series: &TimeSeries) -> bool {
+        let value = series.label(&self.label).unwrap_or_default();
+        self.values.iter().any(|expected| expected == value) != self.negate
+    }
+}
+
+/// Labels replied by `TS.MRANGE` for each series.
+enum LabelReply {
+    None,
+    All,
+    Selected(Vec<String>),
+}
+
+/// Arguments of the range commands following the key, or the timestamps for `TS.MRANGE`.
+struct RangeQuery {
+    from: u64,
+    to: u64,
+    timestamps: Option<Vec<u64>>,
+    values: Option<(f64, f64)>,
+    count: Option<usize>,
+    align: Option<u64>,
+    aggregation: Option<(Aggregation, u64)>,
+    labels: LabelReply,
+    filters: Vec<Matcher>,
+}
+
+impl RangeQuery {
+    /// Parses `from to [options]` at `index`. Label options and filters are accepted by the
+    /// commands over several series only, which require a filter.
+    fn parse(args: &[RespType], mut index: usize, name: &str, multi: bool) -> Result<RangeQuery, String> {
+        let timestamp = |index: usize, error: &str| match arg_str(args, index, name)? {
+            "-" => Ok(0),
+            "+" => Ok(u64::MAX),
+            timestamp => timestamp.parse::<u64>().map_err(|_| error.to_string()),
+        };
+        let mut query = RangeQuery {
+            from: timestamp(index, "TSDB: wrong fromTimestamp")?,
+            to: timestamp(index + 1, "TSDB: wrong toTimestamp")?,
+            timestamps: None,
+            values: None,
+            count: None,
+            align: None,
+            aggregation: None,
+            labels: LabelReply::None,
+            filters: Vec::new(),
+        };
+        index += 2;
+        while index < args.len() {
+            let option = arg_str(args, index, name)?.to_uppercase();
+            index += 1;
+            match option.as_str() {
+                "FILTER_BY_TS" => {
+                    let mut timestamps = Vec::new();
+                    while let Some(timestamp) = args.get(index).and_then(|_| arg_str(args, index, name).ok()) {
+                        let Ok(timestamp) = timestamp.parse::<u64>() else {
+                            break;
+                        };
+                        timestamps.push(timestamp);
+                        index += 1;
+                    }
+                    if timestamps.is_empty() {
+                        return Err("TSDB: FILTER_BY_TS one or more arguments are missing".to_string());
+                    }
+                    query.timestamps = Some(timestamps);
+                }
+                "FILTER_BY_VALUE" => {
+                    let value = |index: usize, error: &str| {
+                        arg_str(args, index, name)?.parse::<f64>().map_err(|_| error.to_string())
+                    };
+                    let min = value(index, "TSDB: Couldn't parse MIN")?;
+                    query.values = Some((min, value(index + 1, "TSDB: Couldn't parse MAX")?));
+                    index += 2;
+                }
+                "COUNT" => {
+                    let count = arg_str(args, index, name)?.parse::<usize>();
+                    query.count = Some(count.map_err(|_| "TSDB: Couldn't parse COUNT".to_string())?);
+                    index += 1;
+                }
+                "ALIGN" => {
+                    query.align = Some(match arg_str(args, index, name)?.to_lowercase().as_str() {
+                        "start" | "-" => query.from,
+                        "end" | "+" => query.to,
+                        align => align.parse::<u64>().map_err(|_| "TSDB: unknown ALIGN parameter".to_string())?,
+                    });
+                    index += 1;
+                }
+                "AGGREGATION" => {
+                    query.aggregation = Some(parse_aggregation(args, index, name)?);
+                    index += 2;
+                }
+                "WITHLABELS" if multi => query.labels = LabelReply::All,
+                "SELECTED_LABELS" if multi => {
+                    let mut labels = Vec::new();
+                    while index < args.len() && !arg_str(args, index, name)?.eq_ignore_ascii_case("FILTER") {
+                        labels.push(arg_str(args, index, name)?.to_string());
+                        index += 1;
+                    }
+                    query.labels = LabelReply::Selected(labels);
+                }
+                "FILTER" if multi => {
+                    for index in index..args.len() {
+                        let matcher = Matcher::parse(arg_str(args, index, name)?);
+                        query.filters.push(matcher.ok_or_else(|| "TSDB: failed parsing labels".to_string())?);
+                    }
+                    break;
+                }
+                _ => return Err("syntax error".to_string()),
+            }
+        }
+        if query.align.is_some() && query.aggregation.is_none() {
+            return Err("TSDB: ALIGN parameter can only be used with AGGREGATION".to_string());
+        }
+        if multi && !query.filters.iter().any(Matcher::is_positive) {
+            return Err("TSDB: please provide at least one matcher of the form label=value".to_string());
+        }
+        Ok(query)
+    }
+
+    /// The samples of the series in range, aggregated, latest first if `reverse`.
+    fn samples(&self, series: &TimeSeries, reverse: bool) -> Vec<(u64, f64)> {
+        let samples = series.range(self.from, self.to).filter(|(timestamp, value)| {
+            self.timestamps.as_ref().is_none_or(|timestamps| timestamps.contains(timestamp))
+                && self.values.is_none_or(|(min, max)| (min..=max).contains(value))
+        });
+        let mut samples = match self.aggregation {
+            Some((aggregation, duration)) => aggregate(samples, aggregation, duration, self.align.unwrap_or_default()),
+            None => samples.collect(),
+        };
+        if reverse {
+            samples.reverse();
+        }
+        if let Some(count) = self.count {
+            samples.truncate(count);
+        }
+        samples
+    }
+}
+
+fn range(args: &[RespType], state: &mut dyn ServerState, name: &str, reverse: bool) -> Result<RespType, String> {
+    let key = arg_str(args, 0, name)?;
+    let query = RangeQuery::parse(args, 1, name, false)?;
+    let samples = query.samples(existing(state, key)?, reverse);
+    Ok(RespType::Array(samples.into_iter().map(sample_reply).collect()))
+}
+
+/// Replies `[key, labels, samples]` for each series matching the filters, sorted by key.
+fn multi_range(args: &[RespType], state: &mut dyn ServerState, name: &str, reverse: bool) -> Result<RespType, String> {
+    let query = RangeQuery::parse(args, 0, name, true)?;
+    let mut keys = state.keys();
+    keys.sort();
+    let mut reply = Vec::new();
+    for key in keys {
+        let Some(Value::TimeSeries(series)) = state.get(&key) else {
+            continue;
+        };
+        if !query.filters.iter().all(|matcher| matcher.matches(series)) {
+            continue;
+        }
+        let label = |name: &str, value: Option<&str>| {
+            RespType::Array(vec![bulk(name), RespType::BulkString(value.map(str::to_string))])
+        };
+        let labels = match &query.labels {
+            LabelReply::None => Vec::new(),
+            LabelReply::All => series.labels.iter().map(|(name, value)| label(name, Some(value))).collect(),
+            LabelReply::Selected(names) => names.iter().map(|name| label(name, series.label(name))).collect(),
+        };
+        let samples = query.samples(series, reverse).into_iter().map(sample_reply).collect();
+        reply.push(RespType::Array(vec![bulk(&key), RespType::Array(labels), RespType::Array(samples)]));
+    }
+    Ok(RespType::Array(reply))
+}
+
+/// `TS.CREATE key [RETENTION ms] [ENCODING enc] [CHUNK_SIZE size] [DUPLICATE_POLICY policy]
+/// [LABELS label value ...]`
+pub struct TsCreate;
+
+impl Command for TsCreate {
+    fn name(&self) -> &str {
+        "TS.CREATE"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let options = CreateOptions::parse(args, 1, self.name())?;
+        if state.get(key).is_some() {
+            return Err("TSDB: key already exists".to_string());
+        }
+        state.set(key.to_string(), Value::TimeSeries(options.create()), None)?;
+        Ok(RespType::SimpleString("OK".to_string()))
+    }
+}
+
+/// `TS.ADD key timestamp|* value [ON_DUPLICATE policy] [options of TS.CREATE]`: adds a sample,
+/// creating the series with the options when missing.
+///
+/// Replicated with the timestamp given to the sample.
+pub struct TsAdd;
+
+impl Command for TsAdd {
+    fn name(&self) -> &str {
+        "TS.ADD"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn propagate_verbatim(&self) -> bool {
+        false
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() < 3 {
+            return Err("wrong number of arguments for 'ts.add' command".to_string());
+        }
+        let key = arg_str(args, 0, self.name())?;
+        let timestamp = parse_timestamp(arg_str(args, 1, self.name())?)?;
+        let value = parse_value(arg_str(args, 2, self.name())?)?;
+        let options = CreateOptions::parse(args, 3, self.name())?;
+
+        if series(state, key)?.is_none() {
+            state.set(key.to_string(), Value::TimeSeries(options.create()), None)?;
+        }
+        add_sample(state, key, timestamp, value, options.on_duplicate)?;
+
+        let resolved = timestamp.to_string();
+        let mut parts = vec![self.name()];
+        parts.extend((0..args.len()).filter_map(|index| arg_str(args, index, self.name()).ok()));
+        parts[2] = &resolved;
+        propagate(state, &parts);
+        Ok(RespType::Integer(timestamp as i64))
+    }
+}
+
+/// `TS.MADD key timestamp|* value [key timestamp|* value ...]`: adds samples to existing series,
+/// replying with the timestamp of each sample or why it could not be added.
+///
+/// Replicated with the timestamps given to the samples.
+pub struct TsMAdd;
+
+impl Command for TsMAdd {
+    fn name(&self) -> &str {
+        "TS.MADD"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn propagate_verbatim(&self) -> bool {
+        false
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        (0..args.len()).step_by(3).filter_map(|index| arg_str(args, index, self.name()).ok()).collect()
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.is_empty() || !args.len().is_multiple_of(3) {
+            return Err("wrong number of arguments for 'ts.madd' command".to_string());
+        }
+        let mut samples = Vec::new();
+        for index in (0..args.len()).step_by(3) {
+            let key = arg_str(args, index, self.name())?;
+            let timestamp = parse_timestamp(arg_str(args, index + 1, self.name())?)?;
+            samples.push((key, timestamp, parse_value(arg_str(args, index + 2, self.name())?)?));
+        }
+
+        let mut reply = Vec::new();
+        let mut parts = vec![self.name().to_string()];
+        for (key, timestamp, value) in samples {
+            reply.push(match add_sample(state, key, timestamp, value, None) {
+                Ok(()) => RespType::Integer(timestamp as i64),
+                Err(error) => RespType::Error(error),
+            });
+            parts.extend([key.to_string(), timestamp.to_string(), value.to_string()]);
+        }
+        propagate(state, &parts.iter().map(String::as_str).collect::<Vec<_>>());
+        Ok(RespType::Array(reply))
+    }
+}
+
+/// `TS.GET key`: the latest sample, or an empty array.
+pub struct TsGet;
+
+impl Command for TsGet {
+    fn name(&self) -> &str {
+        "TS.GET"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() != 1 {
+            return Err("wrong number of arguments for 'ts.get' command".to_string());
+        }
+        let key = arg_str(args, 0, self.name())?;
+        match existing(state, key)?.last() {
+            Some(sample) => Ok(sample_reply(sample)),
+            None => Ok(RespType::Array(Vec::new())),
+        }
+    }
+}
+
+/// `TS.RANGE key from to [FILTER_BY_TS ts ...] [FILTER_BY_VALUE min max] [COUNT count]
+/// [ALIGN align] [AGGREGATION aggregator bucketDuration]`: the samples from `from` (or `-`) to
+/// `to` (or `+`), or one per bucket with their aggregate.
+pub struct TsRange;
+
+impl Command for TsRange {
+    fn name(&self) -> &str {
+        "TS.RANGE"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        range(args, state, self.name(), false)
+    }
+}
+
+/// `TS.REVRANGE key from to [options of TS.RANGE]`: `TS.RANGE`, latest first.
+pub struct TsRevRange;
+
+impl Command for TsRevRange {
+    fn name(&self) -> &str {
+        "TS.REVRANGE"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        range(args, state, self.name(), true)
+    }
+}
+
+/// `TS.MRANGE from to [options of TS.RANGE] [WITHLABELS | SELECTED_LABELS label ...]
+/// FILTER matcher ...`: `TS.RANGE` over every series whose labels match all the matchers.
+pub struct TsMRange;
+
+impl Command for TsMRange {
+    fn name(&self) -> &str {
+        "TS.MRANGE"
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        multi_range(args, state, self.name(), false)
+    }
+}
+
+/// `TS.MREVRANGE from to [options of TS.MRANGE] FILTER matcher ...`: `TS.MRANGE`, latest first.
+pub struct TsMRevRange;
+
+impl Command for TsMRevRange {
+    fn name(&self) -> &str {
+        "TS.MREVRANGE"
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        multi_range(args, state, self.name(), true)
+    }
+}
+
+/// Whether the series at `source` still downsamples into `destination`.
+fn has_rule(state: &mut dyn ServerState, source: &str, destination: &str) -> bool {
+    matches!(series(state, source), Ok(Some(series)) if series.rules.iter().any(|rule| rule.destination == destination))
+}
+
+/// `TS.CREATERULE source destination AGGREGATION aggregator bucketDuration [alignTimestamp]`:
+/// downsamples the samples added to the source from now on into the destination.
+pub struct TsCreateRule;
+
+impl Command for TsCreateRule {
+    fn name(&self) -> &str {
+        "TS.CREATERULE"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        (0..args.len().min(2)).filter_map(|index| arg_str(args, index, self.name()).ok()).collect()
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() != 5 && args.len() != 6 {
+            return Err("wrong number of arguments for 'ts.createrule' command".to_string());
+        }
+        let source = arg_str(args, 0, self.name())?;
+        let destination = arg_str(args, 1, self.name())?;
+        if !arg_str(args, 2, self.name())?.eq_ignore_ascii_case("AGGREGATION") {
+            return Err("syntax error".to_string());
+        }
+        let (aggregation, bucket_duration) = parse_aggregation(args, 3, self.name())?;
+        let align = match args.get(5) {
+            Some(_) => arg_str(args, 5, self.name())?
+                .parse::<u64>()
+                .map_err(|_| "TSDB: Couldn't parse alignTimestamp".to_string())?,
+            None => 0,
+        };
+        if source == destination {
+            return Err("TSDB: the source key and destination key should be different".to_string());
+        }
+
+        // Compactions are not chained: a destination cannot be downsampled any further.
+        if let Some(current) = existing(state, source)?.source.clone() {
+            if has_rule(state, &current, source) {
+                return Err("TSDB: the source key already has a source rule".to_string());
+            }
+        }
+        let target = existing(state, destination)?;
+        if !target.rules.is_empty() {
+            return Err("TSDB: the destination key already has a dst rule".to_string());
+        }
+        if let Some(current) = target.source.clone() {
+            if has_rule(state, &current, destination) {
+                return Err("TSDB: the destination key already has a src rule".to_string());
+            }
+        }
+
+        existing(state, destination)?.source = Some(source.to_string());
+        existing(state, source)?.rules.push(Rule {
+            destination: destination.to_string(),
+            aggregation,
+            bucket_duration,
+            align,
+            bucket: None,
+        });
+        Ok(RespType::SimpleString("OK".to_string()))
+    }
+}
+
+/// `TS.DELETERULE source destination`: stops downsampling the source into the destination.
+pub struct TsDeleteRule;
+
+impl Command for TsDeleteRule {
+    fn name(&self) -> &str {
+        "TS.DELETERULE"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        (0..args.len().min(2)).filter_map(|index| arg_str(args, index, self.name()).ok()).collect()
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() != 2 {
+            return Err("wrong number of arguments for 'ts.deleterule' command".to_string());
+        }
+        let source = arg_str(args, 0, self.name())?;
+        let destination = arg_str(args, 1, self.name())?;
+        let rules = &mut existing(state, source)?.rules;
+        let position = rules.iter().position(|rule| rule.destination == destination);
+        rules.remove(position.ok_or_else(|| "TSDB: compaction rule does not exist".to_string())?);
+        if let Ok(Some(target)) = series(state, destination) {
+            target.source = None;
+        }
+        Ok(RespType::SimpleString("OK".to_string()))
+    }
+}
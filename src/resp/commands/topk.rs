@@ -0,0 +1,191 @@
+//! Top-K commands. Lists are created explicitly with `TOPK.RESERVE`.
+
+use crate::resp::commands::{arg_bytes, arg_str, first_key, Command};
+use crate::resp::protocol::RespType;
+use crate::resp::state::server_state::ServerState;
+use crate::resp::state::value::{Value, WRONG_TYPE};
+use crate::resp::topk::{TopK, DEFAULT_DECAY, DEFAULT_DEPTH, DEFAULT_WIDTH};
+
+/// Largest increment of `TOPK.INCRBY`, which decays colliding buckets once per unit.
+const MAX_INCREMENT: u32 = 100_000;
+
+fn topk<'a>(state: &'a mut dyn ServerState, key: &str) -> Result<&'a mut TopK, String> {
+    match state.get_mut(key) {
+        Some(Value::TopK(topk)) => Ok(topk),
+        Some(_) => Err(WRONG_TYPE.to_string()),
+        None => Err("TopK: key does not exist".to_string()),
+    }
+}
+
+/// Counts the occurrences, replying with the item each one pushed out of the list, or nil.
+fn add_all(state: &mut dyn ServerState, key: &str, increments: &[(&[u8], u32)]) -> Result<RespType, String> {
+    let topk = topk(state, key)?;
+    let expelled = increments.iter().map(|(item, increment)| match topk.add(item, *increment) {
+        Some(item) => RespType::bulk_bytes(item),
+        None => RespType::BulkString(None),
+    });
+    Ok(RespType::Array(expelled.collect()))
+}
+
+/// `TOPK.RESERVE key k [width depth decay]`: creates a list of the `k` most frequent items,
+/// counted in `depth` rows of `width` buckets.
+pub struct TopKReserve;
+
+impl Command for TopKReserve {
+    fn name(&self) -> &str {
+        "TOPK.RESERVE"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() != 2 && args.len() != 5 {
+            return Err("wrong number of arguments for 'topk.reserve' command".to_string());
+        }
+        let key = arg_str(args, 0, self.name())?;
+        let positive = |index: usize, error: &str| {
+            let value = arg_str(args, index, self.name())?.parse::<u64>().ok();
+            value.filter(|value| *value > 0).ok_or_else(|| error.to_string())
+        };
+        let k = positive(1, "TopK: invalid k")?;
+        let (width, depth, decay) = if args.len() == 5 {
+            let decay = arg_str(args, 4, self.name())?.parse::<f64>().ok();
+            let decay = decay.filter(|decay| *decay > 0.0 && *decay <= 1.0);
+            let decay = decay.ok_or_else(|| "TopK: invalid decay value. must be '<= 1' & '> 0'".to_string())?;
+            (positive(2, "TopK: invalid width")?, positive(3, "TopK: invalid depth")?, decay)
+        } else {
+            (DEFAULT_WIDTH, DEFAULT_DEPTH, DEFAULT_DECAY)
+        };
+
+        if state.get(key).is_some() {
+            return Err("TopK: key already exists".to_string());
+        }
+        let topk = TopK::new(k, width, depth, decay)?;
+        state.set(key.to_string(), Value::TopK(topk), None)?;
+        Ok(RespType::SimpleString("OK".to_string()))
+    }
+}
+
+/// `TOPK.ADD key item [item ...]`: counts one occurrence of each item.
+pub struct TopKAdd;
+
+impl Command for TopKAdd {
+    fn name(&self) -> &str {
+        "TOPK.ADD"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() < 2 {
+            return Err("wrong number of arguments for 'topk.add' command".to_string());
+        }
+        let key = arg_str(args, 0, self.name())?;
+        let increments = (1..args.len())
+            .map(|index| Ok((arg_bytes(args, index, self.name())?, 1)))
+            .collect::<Result<Vec<_>, String>>()?;
+        add_all(state, key, &increments)
+    }
+}
+
+/// `TOPK.INCRBY key item increment [item increment ...]`: counts `increment` occurrences of
+/// each item.
+pub struct TopKIncrBy;
+
+impl Command for TopKIncrBy {
+    fn name(&self) -> &str {
+        "TOPK.INCRBY"
+    }
+
+    fn is_write(&self) -> bool {
+        true
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() < 3 || args.len().is_multiple_of(2) {
+            return Err("wrong number of arguments for 'topk.incrby' command".to_string());
+        }
+        let key = arg_str(args, 0, self.name())?;
+        let mut increments = Vec::new();
+        for index in (1..args.len()).step_by(2) {
+            let increment = arg_str(args, index + 1, self.name())?.parse::<u32>().ok();
+            let increment = increment.filter(|increment| (1..=MAX_INCREMENT).contains(increment)).ok_or_else(|| {
+                "TopK: increment must be an integer greater or equal to 1 and less than or equal to 100,000".to_string()
+            })?;
+            increments.push((arg_bytes(args, index, self.name())?, increment));
+        }
+        add_all(state, key, &increments)
+    }
+}
+
+/// `TOPK.QUERY key item [item ...]`: 1 for each item in the list, 0 otherwise.
+pub struct TopKQuery;
+
+impl Command for TopKQuery {
+    fn name(&self) -> &str {
+        "TOPK.QUERY"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        if args.len() < 2 {
+            return Err("wrong number of arguments for 'topk.query' command".to_string());
+        }
+        let key = arg_str(args, 0, self.name())?;
+        let items = (1..args.len()).map(|index| arg_bytes(args, index, self.name())).collect::<Result<Vec<_>, _>>()?;
+        let topk = topk(state, key)?;
+        Ok(RespType::Array(items.into_iter().map(|item| RespType::Integer(topk.contains(item) as i64)).collect()))
+    }
+}
+
+/// `TOPK.LIST key [WITHCOUNT]`: the items of the list, most frequent first, each followed by
+/// its estimated count with `WITHCOUNT`.
+pub struct TopKList;
+
+impl Command for TopKList {
+    fn name(&self) -> &str {
+        "TOPK.LIST"
+    }
+
+    fn keys<'a>(&self, args: &'a [RespType]) -> Vec<&'a str> {
+        first_key(args)
+    }
+
+    fn execute(&self, args: &[RespType], state: &mut dyn ServerState) -> Result<RespType, String> {
+        let key = arg_str(args, 0, self.name())?;
+        let with_count = match args.len() {
+            1 => false,
+            2 if arg_str(args, 1, self.name())?.eq_ignore_ascii_case("WITHCOUNT") => true,
+            2 => return Err("syntax error".to_string()),
+            _ => return Err("wrong number of arguments for 'topk.list' command".to_string()),
+        };
+        let topk = topk(state, key)?;
+        let mut reply = Vec::new();
+        for (item, count) in topk.list() {
+            reply.push(RespType::bulk_bytes(item.to_vec()));
+            if with_count {
+                reply.push(RespType::Integer(count as i64));
+            }
+        }
+        Ok(RespType::Array(reply))
+    }
+}
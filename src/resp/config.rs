@@ -0,0 +1,177 @@
+use std::time::Duration;
+
+use crate::resp::cluster::DEFAULT_NODE_TIMEOUT;
+use crate::resp::replication::{MasterAddr, DEFAULT_BACKLOG_SIZE};
+use crate::resp::sentinel::{MonitorConfig, SentinelConfig, DEFAULT_SENTINEL_PORT};
+
+pub const DEFAULT_PORT: u16 = 6379;
+pub const DEFAULT_CLUSTER_CONFIG_FILE: &str = "nodes.conf";
+
+/// Server configuration, read from `--option value` command line arguments
+/// named after the corresponding `redis.conf` directives.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ServerConfig {
+    pub port: u16,
+    pub replicaof: Option<MasterAddr>,
+    pub repl_backlog_size: usize,
+    pub replica_read_only: bool,
+    pub replica_serve_stale_data: bool,
+    pub cluster_enabled: bool,
+    pub cluster_config_file: String,
+    pub cluster_node_timeout: Duration,
+    /// Set when started with `--sentinel`.
+    pub sentinel: Option<SentinelConfig>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            port: DEFAULT_PORT,
+            replicaof: None,
+            repl_backlog_size: DEFAULT_BACKLOG_SIZE,
+            replica_read_only: true,
+            replica_serve_stale_data: true,
+            cluster_enabled: false,
+            cluster_config_file: DEFAULT_CLUSTER_CONFIG_FILE.to_string(),
+            cluster_node_timeout: DEFAULT_NODE_TIMEOUT,
+            sentinel: None,
+        }
+    }
+}
+
+impl ServerConfig {
+    pub fn from_args<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut config = ServerConfig::default();
+        let mut args = args.into_iter();
+        let mut sentinel = SentinelConfig::default();
+        let mut sentinel_mode = false;
+        let mut port = None;
+
+        while let Some(option) = args.next() {
+            if option == "--sentinel" {
+                sentinel_mode = true;
+                continue;
+            }
+            let value = args
+                .next()
+                .ok_or_else(|| format!("Missing value for {}", option))?;
+
+            match option.as_str() {
+                "--port" => {
+                    port = Some(value.parse().map_err(|_| "Invalid port".to_string())?);
+                }
+                "--replicaof" => {
+                    // Accepts both `--replicaof "host port"` and `--replicaof host port`.
+                    let mut parts = value.split_whitespace().map(str::to_string).collect::<Vec<_>>();
+                    if parts.len() == 1 {
+                        parts.push(args.next().ok_or("Missing port for --replicaof")?);
+                    }
+                    config.replicaof = match parts.as_slice() {
+                        [no, one] if no.eq_ignore_ascii_case("no") && one.eq_ignore_ascii_case("one") => None,
+                        [host, port] => Some(MasterAddr {
+                            host: host.clone(),
+                            port: port.parse().map_err(|_| "Invalid primary port".to_string())?,
+                        }),
+                        _ => return Err("Expected --replicaof <host> <port>".to_string()),
+                    };
+                }
+                "--repl-backlog-size" => {
+                    config.repl_backlog_size = parse_memory(&value)?;
+                }
+                "--replica-read-only" => {
+                    config.replica_read_only = parse_bool(&value)?;
+                }
+                "--replica-serve-stale-data" => {
+                    config.replica_serve_stale_data = parse_bool(&value)?;
+                }
+                "--cluster-enabled" => {
+                    config.cluster_enabled = parse_bool(&value)?;
+                }
+                "--cluster-config-file" => {
+                    config.cluster_config_file = value;
+                }
+                "--cluster-node-timeout" => {
+                    config.cluster_node_timeout = parse_millis(&value)?;
+                }
+                "--sentinel-monitor" => {
+                    let parts = value.split_whitespace().collect::<Vec<_>>();
+                    let [name, host, port, quorum] = parts.as_slice() else {
+                        return Err("Expected --sentinel-monitor \"<name> <host> <port> <quorum>\"".to_string());
+                    };
+                    sentinel.monitors.push(MonitorConfig {
+                        name: name.to_string(),
+                        addr: MasterAddr {
+                            host: host.to_string(),
+                            port: port.parse().map_err(|_| "Invalid primary port".to_string())?,
+                        },
+                        quorum: quorum
+                            .parse()
+                            .ok()
+                            .filter(|quorum| *quorum > 0)
+                            .ok_or("Invalid quorum")?,
+                    });
+                }
+                "--sentinel-peer" => {
+                    let parts = value.split_whitespace().collect::<Vec<_>>();
+                    let [host, port] = parts.as_slice() else {
+                        return Err("Expected --sentinel-peer \"<host> <port>\"".to_string());
+                    };
+                    sentinel.peers.push(MasterAddr {
+                        host: host.to_string(),
+                        port: port.parse().map_err(|_| "Invalid sentinel port".to_string())?,
+                    });
+                }
+                "--sentinel-down-after-milliseconds" => {
+                    sentinel.down_after = parse_millis(&value)?;
+                }
+                "--sentinel-failover-timeout" => {
+                    sentinel.failover_timeout = parse_millis(&value)?;
+                }
+                _ => return Err(format!("Unknown option: {}", option)),
+            }
+        }
+
+        if sentinel_mode {
+            config.port = port.unwrap_or(DEFAULT_SENTINEL_PORT);
+            config.sentinel = Some(sentinel);
+        } else {
+            config.port = port.unwrap_or(DEFAULT_PORT);
+        }
+
+        Ok(config)
+    }
+}
+
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value.to_lowercase().as_str() {
+        "yes" => Ok(true),
+        "no" => Ok(false),
+        _ => Err(format!("Expected yes or no, got {}", value)),
+    }
+}
+
+fn parse_millis(value: &str) -> Result<Duration, String> {
+    value
+        .parse()
+        .map(Duration::from_millis)
+        .map_err(|_| format!("Invalid number of milliseconds: {}", value))
+}
+
+/// Parses sizes like `1048576`, `512kb` or `1mb`.
+fn parse_memory(value: &str) -> Result<usize, String> {
+    let lower = value.to_lowercase();
+    let (digits, multiplier) = if let Some(digits) = lower.strip_suffix("kb") {
+        (digits, 1024)
+    } else if let Some(digits) = lower.strip_suffix("mb") {
+        (digits, 1024 * 1024)
+    } else if let Some(digits) = lower.strip_suffix("gb") {
+        (digits, 1024 * 1024 * 1024)
+    } else {
+        (lower.as_str(), 1)
+    };
+
+    digits
+        .parse::<usize>()
+        .map(|size| size * multiplier)
+        .map_err(|_| format!("Invalid size: {}", value))
+}
@@ -0,0 +1,130 @@
+use std::io::{Error, ErrorKind};
+use std::time::Duration;
+
+use bytes::{Buf, BytesMut};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use crate::resp::commands::command_frame;
+use crate::resp::protocol::{deserialize, serialize, RespType};
+use crate::resp::replication::MasterAddr;
+
+/// Buffered RESP connection.
+/// Frames may arrive split across several reads or several frames may arrive in a single one,
+/// so incoming bytes are accumulated until a complete frame can be parsed.
+pub struct Connection {
+    stream: TcpStream,
+    buffer: BytesMut,
+}
+
+impl Connection {
+    pub fn new(stream: TcpStream) -> Self {
+        Connection {
+            stream,
+            buffer: BytesMut::with_capacity(4096),
+        }
+    }
+
+    pub async fn connect(host: &str, port: u16) -> Result<Self, Error> {
+        Ok(Connection::new(TcpStream::connect((host, port)).await?))
+    }
+
+    /// Reads the next frame together with its raw bytes.
+    /// Returns `None` when the peer closed the connection between two frames.
+    pub async fn read_frame(&mut self) -> Result<Option<(RespType, Vec<u8>)>, Error> {
+        loop {
+            if !self.buffer.is_empty() {
+                match deserialize(&self.buffer) {
+                    Ok((frame, n)) => {
+                        let raw = self.buffer.split_to(n).to_vec();
+                        return Ok(Some((frame, raw)));
+                    }
+                    Err(e) if e.kind() == ErrorKind::UnexpectedEof => {}
+                    Err(e) => return Err(e),
+                }
+            }
+
+            if self.stream.read_buf(&mut self.buffer).await? == 0 {
+                return if self.buffer.is_empty() {
+                    Ok(None)
+                } else {
+                    Err(Error::new(
+                        ErrorKind::ConnectionReset,
+                        "Connection closed in the middle of a frame",
+                    ))
+                };
+            }
+        }
+    }
+
+    /// Resolves once the peer closed the connection. Data received in the meantime is
+    /// kept for the next `read_frame`.
+    pub async fn closed(&mut self) {
+        loop {
+            match self.stream.read_buf(&mut self.buffer).await {
+                Ok(0) | Err(_) => return,
+                Ok(_) => {}
+            }
+        }
+    }
+
+    /// Reads an RDB payload, sent as a bulk string without the trailing CRLF.
+    pub async fn read_rdb(&mut self) -> Result<Vec<u8>, Error> {
+        loop {
+            if let Some(pos) = self.buffer.windows(2).position(|w| w == b"\r\n") {
+                if self.buffer[0] != b'$' {
+                    return Err(Error::new(ErrorKind::InvalidData, "Expected RDB payload"));
+                }
+                let len = std::str::from_utf8(&self.buffer[1..pos])
+                    .ok()
+                    .and_then(|len| len.parse::<usize>().ok())
+                    .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Invalid RDB length"))?;
+
+                if self.buffer.len() >= pos + 2 + len {
+                    self.buffer.advance(pos + 2);
+                    return Ok(self.buffer.split_to(len).to_vec());
+                }
+            }
+
+            if self.stream.read_buf(&mut self.buffer).await? == 0 {
+                return Err(Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "Connection closed while receiving RDB",
+                ));
+            }
+        }
+    }
+
+    pub async fn write_frame(&mut self, frame: &RespType) -> Result<(), Error> {
+        self.write_bytes(&serialize(frame)).await
+    }
+
+    pub async fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.stream.write_all(bytes).await
+    }
+
+    /// Sends a command and waits for its reply.
+    pub async fn request(&mut self, parts: &[&str]) -> Result<RespType, Error> {
+        self.request_frame(&command_frame(parts)).await
+    }
+
+    /// Sends a command whose arguments need not be UTF-8 and waits for its reply.
+    pub async fn request_frame(&mut self, frame: &RespType) -> Result<RespType, Error> {
+        self.write_frame(frame).await?;
+        match self.read_frame().await? {
+            Some((reply, _)) => Ok(reply),
+            None => Err(Error::new(ErrorKind::UnexpectedEof, "Connection closed")),
+        }
+    }
+}
+
+/// Sends a command to another instance over a fresh connection and returns the reply.
+pub async fn request(addr: &MasterAddr, parts: &[&str], timeout: Duration) -> Result<RespType, Error> {
+    let request = async {
+        let mut connection = Connection::connect(&addr.host, addr.port).await?;
+        connection.request(parts).await
+    };
+    tokio::time::timeout(timeout, request)
+        .await
+        .map_err(|_| Error::new(ErrorKind::TimedOut, "Request timed out"))?
+}
@@ -0,0 +1,162 @@
+//! Cuckoo filters, as RedisBloom builds them: buckets of 8-bit fingerprints where each item
+//! has two candidate buckets, the second derived from the first and the fingerprint so
+//! items can be moved between them. A full filter gets a larger one chained after it.
+
+use crate::resp::hyperloglog::murmur_hash64a;
+use crate::resp::state::value::MAX_STRING_BYTES;
+
+pub const DEFAULT_CAPACITY: u64 = 1024;
+pub const DEFAULT_BUCKET_SIZE: u64 = 2;
+pub const DEFAULT_MAX_ITERATIONS: u64 = 20;
+pub const DEFAULT_EXPANSION: u64 = 1;
+/// Fingerprint of an empty slot.
+const EMPTY: u8 = 0;
+
+/// Where an item may be stored.
+struct Lookup {
+    fingerprint: u8,
+    first: u64,
+    second: u64,
+}
+
+impl Lookup {
+    fn new(item: &[u8]) -> Lookup {
+        let hash = murmur_hash64a(item, 0);
+        let fingerprint = (hash % 255 + 1) as u8;
+        Lookup {
+            fingerprint,
+            first: hash,
+            second: alternate(fingerprint, hash),
+        }
+    }
+}
+
+/// The other bucket of a fingerprint stored at `index`. Bucket counts are powers of two, so
+/// this also gives back `index` from the other bucket.
+fn alternate(fingerprint: u8, index: u64) -> u64 {
+    index ^ (fingerprint as u64).wrapping_mul(0x5bd1e995)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CuckooFilter {
+    /// Buckets of the first filter; each following one has `expansion` times more.
+    pub buckets: u64,
+    pub bucket_size: u64,
+    pub max_iterations: u64,
+    /// 0 when the filter cannot grow.
+    pub expansion: u64,
+    pub items: u64,
+    pub deletes: u64,
+    /// `bucket_size` slots per bucket for each filter.
+    pub filters: Vec<Vec<u8>>,
+}
+
+impl CuckooFilter {
+    pub fn new(capacity: u64, bucket_size: u64, max_iterations: u64, expansion: u64) -> Result<CuckooFilter, String> {
+        let buckets = (capacity / bucket_size).max(1).next_power_of_two();
+        let mut filter = CuckooFilter {
+            buckets,
+            bucket_size,
+            max_iterations,
+            expansion: if expansion == 0 { 0 } else { expansion.next_power_of_two() },
+            items: 0,
+            deletes: 0,
+            filters: Vec::new(),
+        };
+        filter.grow()?;
+        Ok(filter)
+    }
+
+    fn grow(&mut self) -> Result<(), String> {
+        let growth = self.expansion.max(1).checked_pow(self.filters.len() as u32);
+        let slots = growth.and_then(|growth| self.buckets.checked_mul(self.bucket_size)?.checked_mul(growth));
+        match slots {
+            Some(slots) if slots <= MAX_STRING_BYTES => self.filters.push(vec![EMPTY; slots as usize]),
+            _ => return Err("Insufficient memory to create filter".to_string()),
+        }
+        Ok(())
+    }
+
+    /// Slots of the bucket of `hash` in the filter at `index`.
+    fn bucket(&self, index: usize, hash: u64) -> std::ops::Range<usize> {
+        let buckets = self.filters[index].len() as u64 / self.bucket_size;
+        let start = (hash % buckets * self.bucket_size) as usize;
+        start..start + self.bucket_size as usize
+    }
+
+    /// Filter index and slot of the fingerprint of `lookup`, or of an empty slot for it,
+    /// searching the newest filters first.
+    fn find(&self, lookup: &Lookup, fingerprint: u8) -> Option<(usize, usize)> {
+        (0..self.filters.len()).rev().find_map(|index| {
+            let mut slots = self.bucket(index, lookup.first).chain(self.bucket(index, lookup.second));
+            slots.find(|slot| self.filters[index][*slot] == fingerprint).map(|slot| (index, slot))
+        })
+    }
+
+    pub fn contains(&self, item: &[u8]) -> bool {
+        let lookup = Lookup::new(item);
+        self.find(&lookup, lookup.fingerprint).is_some()
+    }
+
+    /// Adds `item`, moving fingerprints of the newest filter to their other bucket to make
+    /// room and chaining a new filter when that fails.
+    pub fn add(&mut self, item: &[u8]) -> Result<(), String> {
+        let lookup = Lookup::new(item);
+        loop {
+            if let Some((index, slot)) = self.find(&lookup, EMPTY) {
+                self.filters[index][slot] = lookup.fingerprint;
+                self.items += 1;
+                return Ok(());
+            }
+            if self.kick_out(&lookup) {
+                self.items += 1;
+                return Ok(());
+            }
+            if self.expansion == 0 {
+                return Err("Filter is full".to_string());
+            }
+            self.grow()?;
+        }
+    }
+
+    /// Inserts the fingerprint of `lookup` in the newest filter by evicting others to their
+    /// alternate bucket, at most `max_iterations` times. Evictions are undone on failure.
+    fn kick_out(&mut self, lookup: &Lookup) -> bool {
+        let Some(filter) = self.filters.last_mut() else {
+            return false;
+        };
+        let bucket_size = self.bucket_size as usize;
+        let buckets = (filter.len() / bucket_size) as u64;
+        let mut fingerprint = lookup.fingerprint;
+        let mut victim = 0;
+        let mut bucket = lookup.first % buckets;
+        for _ in 0..self.max_iterations {
+            std::mem::swap(&mut filter[bucket as usize * bucket_size + victim], &mut fingerprint);
+            bucket = alternate(fingerprint, bucket) % buckets;
+            let start = bucket as usize * bucket_size;
+            if let Some(slot) = filter[start..start + bucket_size].iter_mut().find(|slot| **slot == EMPTY) {
+                *slot = fingerprint;
+                return true;
+            }
+            victim = (victim + 1) % bucket_size;
+        }
+        for _ in 0..self.max_iterations {
+            victim = (victim + bucket_size - 1) % bucket_size;
+            bucket = alternate(fingerprint, bucket) % buckets;
+            std::mem::swap(&mut filter[bucket as usize * bucket_size + victim], &mut fingerprint);
+        }
+        false
+    }
+
+    /// Removes one occurrence of `item`, returning whether it may have been present.
+    pub fn delete(&mut self, item: &[u8]) -> bool {
+        let lookup = Lookup::new(item);
+        let Some((index, slot)) = self.find(&lookup, lookup.fingerprint) else {
+            return false;
+        };
+        self.filters[index][slot] = EMPTY;
+        self.items = self.items.saturating_sub(1);
+        self.deletes += 1;
+        true
+    }
+}
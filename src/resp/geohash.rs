@@ -0,0 +1,98 @@
+//! Geohashes as Redis computes them: longitude and latitude quantized to 26 bits each and
+//! interleaved into a 52-bit integer, which is exact as a sorted set score.
+
+/// Bits per coordinate.
+const STEP: u32 = 26;
+pub const LONGITUDE_MIN: f64 = -180.0;
+pub const LONGITUDE_MAX: f64 = 180.0;
+/// Latitudes of the Web Mercator projection, beyond which squares become too distorted.
+pub const LATITUDE_MIN: f64 = -85.05112878;
+pub const LATITUDE_MAX: f64 = 85.05112878;
+/// Earth radius used by Redis' distance computations.
+const EARTH_RADIUS_METERS: f64 = 6372797.560856;
+const ALPHABET: &[u8] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+pub fn is_valid(longitude: f64, latitude: f64) -> bool {
+    (LONGITUDE_MIN..=LONGITUDE_MAX).contains(&longitude) && (LATITUDE_MIN..=LATITUDE_MAX).contains(&latitude)
+}
+
+/// Spreads the 32 bits of `value` to the even bits of the result.
+fn spread(value: u32) -> u64 {
+    let mut value = value as u64;
+    value = (value | (value << 16)) & 0x0000FFFF0000FFFF;
+    value = (value | (value << 8)) & 0x00FF00FF00FF00FF;
+    value = (value | (value << 4)) & 0x0F0F0F0F0F0F0F0F;
+    value = (value | (value << 2)) & 0x3333333333333333;
+    (value | (value << 1)) & 0x5555555555555555
+}
+
+/// Gathers the even bits of `value`.
+fn squash(value: u64) -> u32 {
+    let mut value = value & 0x5555555555555555;
+    value = (value | (value >> 1)) & 0x3333333333333333;
+    value = (value | (value >> 2)) & 0x0F0F0F0F0F0F0F0F;
+    value = (value | (value >> 4)) & 0x00FF00FF00FF00FF;
+    value = (value | (value >> 8)) & 0x0000FFFF0000FFFF;
+    ((value | (value >> 16)) & 0x00000000FFFFFFFF) as u32
+}
+
+fn quantize(value: f64, min: f64, max: f64) -> u32 {
+    (((value - min) / (max - min)) * (1u64 << STEP) as f64) as u32
+}
+
+fn encode_within(longitude: f64, latitude: f64, latitude_min: f64, latitude_max: f64) -> u64 {
+    let latitude = quantize(latitude, latitude_min, latitude_max);
+    let longitude = quantize(longitude, LONGITUDE_MIN, LONGITUDE_MAX);
+    spread(latitude) | (spread(longitude) << 1)
+}
+
+/// 52-bit geohash of a valid position.
+pub fn encode(longitude: f64, latitude: f64) -> u64 {
+    encode_within(longitude, latitude, LATITUDE_MIN, LATITUDE_MAX)
+}
+
+/// Center of the area of a geohash, as `(longitude, latitude)`.
+pub fn decode(hash: u64) -> (f64, f64) {
+    let cells = (1u64 << STEP) as f64;
+    let center = |cell: u32, min: f64, max: f64| {
+        let low = min + (cell as f64 / cells) * (max - min);
+        let high = min + ((cell as f64 + 1.0) / cells) * (max - min);
+        (low + high) / 2.0
+    };
+    let longitude = center(squash(hash >> 1), LONGITUDE_MIN, LONGITUDE_MAX);
+    let latitude = center(squash(hash), LATITUDE_MIN, LATITUDE_MAX);
+    (
+        longitude.clamp(LONGITUDE_MIN, LONGITUDE_MAX),
+        latitude.clamp(LATITUDE_MIN, LATITUDE_MAX),
+    )
+}
+
+/// Standard 11 character geohash of a position, over the full -90 to 90 latitude range.
+/// Only 52 bits are known, so the last character is always `0`.
+pub fn to_string(longitude: f64, latitude: f64) -> String {
+    let hash = encode_within(longitude, latitude, -90.0, 90.0);
+    (0..11)
+        .map(|index| {
+            let digit = if index == 10 { 0 } else { (hash >> (52 - (index + 1) * 5)) & 0x1F };
+            ALPHABET[digit as usize] as char
+        })
+        .collect()
+}
+
+/// Great-circle distance in meters, with the haversine formula.
+pub fn distance(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (longitude1, latitude1) = (from.0.to_radians(), from.1.to_radians());
+    let (longitude2, latitude2) = (to.0.to_radians(), to.1.to_radians());
+    let v = ((longitude2 - longitude1) / 2.0).sin();
+    if v == 0.0 {
+        return latitude_distance(from.1, to.1);
+    }
+    let u = ((latitude2 - latitude1) / 2.0).sin();
+    let a = u * u + latitude1.cos() * latitude2.cos() * v * v;
+    2.0 * EARTH_RADIUS_METERS * a.sqrt().asin()
+}
+
+/// Distance in meters along a meridian.
+pub fn latitude_distance(latitude1: f64, latitude2: f64) -> f64 {
+    EARTH_RADIUS_METERS * (latitude2.to_radians() - latitude1.to_radians()).abs()
+}
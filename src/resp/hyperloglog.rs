@@ -0,0 +1,283 @@
+//! HyperLogLog cardinality estimation, in the representation Redis stores in strings: a
+//! 16-byte header (`HYLL`, the encoding and a cached cardinality) followed by 16384 6-bit
+//! registers, either packed (dense) or run-length encoded (sparse) while mostly empty.
+
+const MAGIC: &[u8] = b"HYLL";
+const HEADER_LEN: usize = 16;
+const ENCODING_DENSE: u8 = 0;
+const ENCODING_SPARSE: u8 = 1;
+/// Bit of the last cached cardinality byte marking the cache as stale.
+const CACHE_INVALID: u8 = 1 << 7;
+
+/// Bits of the hash selecting the register.
+const P: u32 = 14;
+pub const REGISTERS: usize = 1 << P;
+/// Bits of the hash whose run of zeros is counted.
+const Q: u32 = 64 - P;
+const REGISTER_BITS: usize = 6;
+const DENSE_LEN: usize = HEADER_LEN + (REGISTERS * REGISTER_BITS).div_ceil(8);
+
+/// Greatest value a sparse `VAL` opcode holds.
+const SPARSE_MAX_VALUE: u8 = 32;
+/// Size above which sparse HyperLogLogs are converted to dense, Redis' `hll-sparse-max-bytes`.
+const SPARSE_MAX_BYTES: usize = 3000;
+
+pub const INVALID: &str = "WRONGTYPE Key is not a valid HyperLogLog string value.";
+pub const CORRUPTED: &str = "INVALIDOBJ Corrupted HLL object detected";
+
+/// Registers of a HyperLogLog, unpacked.
+#[derive(Clone)]
+pub struct HyperLogLog {
+    registers: Vec<u8>,
+    sparse: bool,
+    /// Cardinality cached in the header, if still valid.
+    cached: Option<u64>,
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        HyperLogLog {
+            registers: vec![0; REGISTERS],
+            sparse: true,
+            cached: Some(0),
+        }
+    }
+}
+
+impl HyperLogLog {
+    /// Parses the string representation, failing with `INVALID` for other strings.
+    pub fn decode(bytes: &[u8]) -> Result<HyperLogLog, String> {
+        if bytes.len() < HEADER_LEN || &bytes[..4] != MAGIC {
+            return Err(INVALID.to_string());
+        }
+        let cached = (bytes[15] & CACHE_INVALID == 0)
+            .then(|| u64::from_le_bytes(bytes[8..16].try_into().unwrap_or_default()));
+        let registers = match bytes[4] {
+            ENCODING_DENSE if bytes.len() == DENSE_LEN => {
+                (0..REGISTERS).map(|index| dense_register(&bytes[HEADER_LEN..], index)).collect()
+            }
+            ENCODING_SPARSE => sparse_registers(&bytes[HEADER_LEN..]).ok_or_else(|| CORRUPTED.to_string())?,
+            ENCODING_DENSE => return Err(CORRUPTED.to_string()),
+            _ => return Err(INVALID.to_string()),
+        };
+        Ok(HyperLogLog {
+            registers,
+            sparse: bytes[4] == ENCODING_SPARSE,
+            cached,
+        })
+    }
+
+    /// Encodes the registers, sparsely if the HyperLogLog still is and they fit.
+    pub fn encode(&self) -> Vec<u8> {
+        let sparse = self.sparse.then(|| encode_sparse(&self.registers)).flatten();
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(if sparse.is_some() { ENCODING_SPARSE } else { ENCODING_DENSE });
+        bytes.extend_from_slice(&[0; 3]);
+        match self.cached {
+            Some(cardinality) => bytes.extend_from_slice(&cardinality.to_le_bytes()),
+            None => bytes.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, CACHE_INVALID]),
+        }
+        match sparse {
+            Some(sparse) => bytes.extend(sparse),
+            None => bytes.extend(encode_dense(&self.registers)),
+        }
+        bytes
+    }
+
+    /// Adds an element. Returns whether a register changed.
+    pub fn add(&mut self, element: &[u8]) -> bool {
+        let hash = murmur_hash64a(element, 0xadc83b19);
+        let index = (hash & (REGISTERS as u64 - 1)) as usize;
+        // The extra bit bounds the run of zeros to Q.
+        let count = ((hash >> P) | (1 << Q)).trailing_zeros() as u8 + 1;
+        if count <= self.registers[index] {
+            return false;
+        }
+        self.registers[index] = count;
+        self.cached = None;
+        true
+    }
+
+    /// Takes the greatest of each register. The result is dense if `other` is.
+    pub fn merge(&mut self, other: &HyperLogLog) {
+        for (register, other) in self.registers.iter_mut().zip(&other.registers) {
+            if *other > *register {
+                *register = *other;
+                self.cached = None;
+            }
+        }
+        self.sparse &= other.sparse;
+    }
+
+    /// Estimated cardinality, from the cache when valid. Returns whether it was computed.
+    pub fn count(&mut self) -> (u64, bool) {
+        if let Some(cardinality) = self.cached {
+            return (cardinality, false);
+        }
+        let cardinality = estimate(&self.registers);
+        self.cached = Some(cardinality);
+        (cardinality, true)
+    }
+}
+
+fn dense_register(registers: &[u8], index: usize) -> u8 {
+    let bit = index * REGISTER_BITS;
+    let (byte, shift) = (bit / 8, bit % 8);
+    let low = registers[byte] as u16 >> shift;
+    let high = registers.get(byte + 1).map_or(0, |next| (*next as u16) << (8 - shift));
+    ((low | high) & 63) as u8
+}
+
+fn encode_dense(registers: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![0; DENSE_LEN - HEADER_LEN];
+    for (index, register) in registers.iter().enumerate() {
+        let bit = index * REGISTER_BITS;
+        let (byte, shift) = (bit / 8, bit % 8);
+        let value = (*register as u16) << shift;
+        bytes[byte] |= value as u8;
+        if let Some(next) = bytes.get_mut(byte + 1) {
+            *next |= (value >> 8) as u8;
+        }
+    }
+    bytes
+}
+
+/// Decodes the sparse opcodes: `00xxxxxx` for up to 64 empty registers, `01xxxxxx
+/// xxxxxxxx` for up to 16384, and `1vvvvvxx` for up to 4 registers set to `vvvvv + 1`.
+fn sparse_registers(opcodes: &[u8]) -> Option<Vec<u8>> {
+    let mut registers = Vec::with_capacity(REGISTERS);
+    let mut position = 0;
+    while position < opcodes.len() {
+        let opcode = opcodes[position];
+        if opcode & 0x80 != 0 {
+            let value = ((opcode >> 2) & 0x1F) + 1;
+            registers.extend(std::iter::repeat_n(value, (opcode & 0x3) as usize + 1));
+            position += 1;
+        } else if opcode & 0x40 != 0 {
+            let len = ((((opcode & 0x3F) as usize) << 8) | *opcodes.get(position + 1)? as usize) + 1;
+            registers.extend(std::iter::repeat_n(0, len));
+            position += 2;
+        } else {
+            registers.extend(std::iter::repeat_n(0, (opcode & 0x3F) as usize + 1));
+            position += 1;
+        }
+        if registers.len() > REGISTERS {
+            return None;
+        }
+    }
+    (registers.len() == REGISTERS).then_some(registers)
+}
+
+/// Run-length encodes the registers, or `None` when a value is too large for the sparse
+/// representation or it would grow past `SPARSE_MAX_BYTES`.
+fn encode_sparse(registers: &[u8]) -> Option<Vec<u8>> {
+    let mut opcodes = Vec::new();
+    let mut index = 0;
+    while index < registers.len() {
+        let value = registers[index];
+        if value > SPARSE_MAX_VALUE {
+            return None;
+        }
+        let run = registers[index..].iter().take_while(|register| **register == value).count();
+        let mut left = run;
+        while left > 0 {
+            let len = match value {
+                0 if left > 64 => {
+                    let len = left.min(REGISTERS);
+                    opcodes.extend_from_slice(&[0x40 | ((len - 1) >> 8) as u8, (len - 1) as u8]);
+                    len
+                }
+                0 => {
+                    opcodes.push((left - 1) as u8);
+                    left
+                }
+                _ => {
+                    let len = left.min(4);
+                    opcodes.push(0x80 | ((value - 1) << 2) | (len - 1) as u8);
+                    len
+                }
+            };
+            left -= len;
+        }
+        index += run;
+    }
+    (opcodes.len() <= SPARSE_MAX_BYTES).then_some(opcodes)
+}
+
+/// Ertl's improved estimator, as used by Redis, from the histogram of the register values.
+fn estimate(registers: &[u8]) -> u64 {
+    let mut histogram = [0u32; Q as usize + 2];
+    for register in registers {
+        histogram[*register as usize] += 1;
+    }
+    let m = REGISTERS as f64;
+    let mut z = m * tau((m - histogram[Q as usize + 1] as f64) / m);
+    for count in histogram[1..=Q as usize].iter().rev() {
+        z += *count as f64;
+        z *= 0.5;
+    }
+    z += m * sigma(histogram[0] as f64 / m);
+    let alpha_inf = 0.5 / std::f64::consts::LN_2;
+    (alpha_inf * m * m / z).round() as u64
+}
+
+fn sigma(mut x: f64) -> f64 {
+    if x == 1.0 {
+        return f64::INFINITY;
+    }
+    let mut y = 1.0;
+    let mut z = x;
+    loop {
+        x *= x;
+        let previous = z;
+        z += x * y;
+        y += y;
+        if z == previous {
+            return z;
+        }
+    }
+}
+
+fn tau(mut x: f64) -> f64 {
+    if x == 0.0 || x == 1.0 {
+        return 0.0;
+    }
+    let mut y = 1.0;
+    let mut z = 1.0 - x;
+    loop {
+        x = x.sqrt();
+        let previous = z;
+        y *= 0.5;
+        z -= (1.0 - x).powi(2) * y;
+        if z == previous {
+            return z / 3.0;
+        }
+    }
+}
+
+/// MurmurHash64A, the hash Redis uses for HyperLogLogs and RedisBloom for its filters.
+pub(crate) fn murmur_hash64a(data: &[u8], seed: u64) -> u64 {
+    const M: u64 = 0xc6a4a7935bd1e995;
+    const R: u32 = 47;
+    let mut h = seed ^ (data.len() as u64).wrapping_mul(M);
+    let mut chunks = data.chunks_exact(8);
+    for chunk in &mut chunks {
+        let mut k = u64::from_le_bytes(chunk.try_into().unwrap_or_default());
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+        h ^= k;
+        h = h.wrapping_mul(M);
+    }
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        for (index, byte) in tail.iter().enumerate() {
+            h ^= (*byte as u64) << (8 * index);
+        }
+        h = h.wrapping_mul(M);
+    }
+    h ^= h >> R;
+    h = h.wrapping_mul(M);
+    h ^= h >> R;
+    h
+}
@@ -0,0 +1,426 @@
+//! JSON documents stored as values: parsing, serialization and in place access by location.
+//! Object members keep their insertion order, as RedisJSON does.
+
+use std::fmt::Write;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+/// One step from a value to a child: a member of an object or an element of an array.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Step {
+    Index(usize),
+    Key(String),
+}
+
+/// Whitespace inserted by `JSON.GET` with `INDENT`, `NEWLINE` and `SPACE`.
+#[derive(Clone, Debug, Default)]
+pub struct Format {
+    pub indent: String,
+    pub newline: String,
+    pub space: String,
+}
+
+impl Json {
+    /// Type name as reported by `JSON.TYPE`.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Json::Null => "null",
+            Json::Bool(_) => "boolean",
+            Json::Integer(_) => "integer",
+            Json::Float(_) => "number",
+            Json::String(_) => "string",
+            Json::Array(_) => "array",
+            Json::Object(_) => "object",
+        }
+    }
+
+    pub fn member(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members.iter().find(|(name, _)| name == key).map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// Child reached by `step`, if any.
+    pub fn child(&self, step: &Step) -> Option<&Json> {
+        match (self, step) {
+            (Json::Array(items), Step::Index(index)) => items.get(*index),
+            (_, Step::Key(key)) => self.member(key),
+            _ => None,
+        }
+    }
+
+    fn child_mut(&mut self, step: &Step) -> Option<&mut Json> {
+        match (self, step) {
+            (Json::Array(items), Step::Index(index)) => items.get_mut(*index),
+            (Json::Object(members), Step::Key(key)) => {
+                members.iter_mut().find(|(name, _)| name == key).map(|(_, value)| value)
+            }
+            _ => None,
+        }
+    }
+
+    /// Children with the steps reaching them, in document order.
+    pub fn children(&self) -> Vec<(Step, &Json)> {
+        match self {
+            Json::Array(items) => items.iter().enumerate().map(|(index, item)| (Step::Index(index), item)).collect(),
+            Json::Object(members) => members.iter().map(|(key, value)| (Step::Key(key.clone()), value)).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn get(&self, location: &[Step]) -> Option<&Json> {
+        location.iter().try_fold(self, |value, step| value.child(step))
+    }
+
+    pub fn get_mut(&mut self, location: &[Step]) -> Option<&mut Json> {
+        location.iter().try_fold(self, |value, step| value.child_mut(step))
+    }
+
+    /// Sets a member of an object, keeping its position when it already exists.
+    pub fn set_member(&mut self, key: &str, value: Json) -> bool {
+        let Json::Object(members) = self else {
+            return false;
+        };
+        match members.iter_mut().find(|(name, _)| name == key) {
+            Some((_, existing)) => *existing = value,
+            None => members.push((key.to_string(), value)),
+        }
+        true
+    }
+
+    /// Removes the value at `location`, which must not be the root.
+    pub fn remove(&mut self, location: &[Step]) -> Option<Json> {
+        let (last, parent) = location.split_last()?;
+        match (self.get_mut(parent)?, last) {
+            (Json::Array(items), Step::Index(index)) if *index < items.len() => Some(items.remove(*index)),
+            (Json::Object(members), Step::Key(key)) => {
+                let position = members.iter().position(|(name, _)| name == key)?;
+                Some(members.remove(position).1)
+            }
+            _ => None,
+        }
+    }
+
+    /// Numeric value, for comparisons and increments.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Integer(value) => Some(*value as f64),
+            Json::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Compact serialization.
+    pub fn serialize(&self) -> String {
+        self.format(&Format::default())
+    }
+
+    pub fn format(&self, format: &Format) -> String {
+        let mut out = String::new();
+        self.write(&mut out, format, 0);
+        out
+    }
+
+    fn write(&self, out: &mut String, format: &Format, depth: usize) {
+        let separate = |out: &mut String, depth: usize| {
+            out.push_str(&format.newline);
+            out.push_str(&format.indent.repeat(depth));
+        };
+        match self {
+            Json::Null => out.push_str("null"),
+            Json::Bool(value) => out.push_str(if *value { "true" } else { "false" }),
+            Json::Integer(value) => out.push_str(&value.to_string()),
+            // Debug keeps the fraction of integral floats, like `3.0`.
+            Json::Float(value) => out.push_str(&format!("{:?}", value)),
+            Json::String(value) => write_string(out, value),
+            Json::Array(items) if items.is_empty() => out.push_str("[]"),
+            Json::Object(members) if members.is_empty() => out.push_str("{}"),
+            Json::Array(items) => {
+                out.push('[');
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        out.push(',');
+                    }
+                    separate(out, depth + 1);
+                    item.write(out, format, depth + 1);
+                }
+                separate(out, depth);
+                out.push(']');
+            }
+            Json::Object(members) => {
+                out.push('{');
+                for (index, (key, value)) in members.iter().enumerate() {
+                    if index > 0 {
+                        out.push(',');
+                    }
+                    separate(out, depth + 1);
+                    write_string(out, key);
+                    out.push(':');
+                    out.push_str(&format.space);
+                    value.write(out, format, depth + 1);
+                }
+                separate(out, depth);
+                out.push('}');
+            }
+        }
+    }
+}
+
+fn write_string(out: &mut String, value: &str) {
+    out.push('"');
+    for char in value.chars() {
+        match char {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            char if (char as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", char as u32);
+            }
+            char => out.push(char),
+        }
+    }
+    out.push('"');
+}
+
+/// Parses a whole document.
+pub fn parse(input: &str) -> Result<Json, String> {
+    let mut parser = Parser {
+        input: input.as_bytes(),
+        position: 0,
+    };
+    let value = parser.value(0)?;
+    parser.whitespace();
+    if parser.position < parser.input.len() {
+        return Err(parser.error("trailing characters"));
+    }
+    Ok(value)
+}
+
+/// Nesting deeper than this is rejected rather than overflowing the stack.
+pub(crate) const MAX_DEPTH: usize = 128;
+
+/// Parses the leading JSON value of `input`, returning it and the number of bytes read.
+pub(crate) fn parse_prefix(input: &str) -> Result<(Json, usize), String> {
+    let mut parser = Parser {
+        input: input.as_bytes(),
+        position: 0,
+    };
+    let value = parser.value(0)?;
+    Ok((value, parser.position))
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> String {
+        let before = &self.input[..self.position.min(self.input.len())];
+        let line = before.iter().filter(|byte| **byte == b'\n').count() + 1;
+        let column = before.iter().rev().take_while(|byte| **byte != b'\n').count() + 1;
+        format!("{} at line {} column {}", message, line, column)
+    }
+
+    fn whitespace(&mut self) {
+        while self.peek().is_some_and(|byte| matches!(byte, b' ' | b'\t' | b'\n' | b'\r')) {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.position).copied()
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        self.whitespace();
+        if self.peek() != Some(byte) {
+            return Err(self.error(&format!("expected `{}`", byte as char)));
+        }
+        self.position += 1;
+        Ok(())
+    }
+
+    fn literal(&mut self, literal: &str, value: Json) -> Result<Json, String> {
+        if !self.input[self.position..].starts_with(literal.as_bytes()) {
+            return Err(self.error("expected value"));
+        }
+        self.position += literal.len();
+        Ok(value)
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Json, String> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("recursion limit exceeded"));
+        }
+        self.whitespace();
+        match self.peek() {
+            None => Err(self.error("EOF while parsing a value")),
+            Some(b'n') => self.literal("null", Json::Null),
+            Some(b't') => self.literal("true", Json::Bool(true)),
+            Some(b'f') => self.literal("false", Json::Bool(false)),
+            Some(b'"') => Ok(Json::String(self.string()?)),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(b'[') => {
+                self.position += 1;
+                let mut items = Vec::new();
+                self.whitespace();
+                if self.peek() == Some(b']') {
+                    self.position += 1;
+                    return Ok(Json::Array(items));
+                }
+                loop {
+                    items.push(self.value(depth + 1)?);
+                    self.whitespace();
+                    match self.peek() {
+                        Some(b',') => self.position += 1,
+                        Some(b']') => {
+                            self.position += 1;
+                            return Ok(Json::Array(items));
+                        }
+                        _ => return Err(self.error("expected `,` or `]`")),
+                    }
+                }
+            }
+            Some(b'{') => {
+                self.position += 1;
+                let mut object = Json::Object(Vec::new());
+                self.whitespace();
+                if self.peek() == Some(b'}') {
+                    self.position += 1;
+                    return Ok(object);
+                }
+                loop {
+                    self.whitespace();
+                    if self.peek() != Some(b'"') {
+                        return Err(self.error("key must be a string"));
+                    }
+                    let key = self.string()?;
+                    self.expect(b':')?;
+                    object.set_member(&key, self.value(depth + 1)?);
+                    self.whitespace();
+                    match self.peek() {
+                        Some(b',') => self.position += 1,
+                        Some(b'}') => {
+                            self.position += 1;
+                            return Ok(object);
+                        }
+                        _ => return Err(self.error("expected `,` or `}`")),
+                    }
+                }
+            }
+            Some(_) => Err(self.error("expected value")),
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.position;
+        let digits = |parser: &mut Parser| {
+            let from = parser.position;
+            while parser.peek().is_some_and(|byte| byte.is_ascii_digit()) {
+                parser.position += 1;
+            }
+            parser.position > from
+        };
+        if self.peek() == Some(b'-') {
+            self.position += 1;
+        }
+        if !digits(self) {
+            return Err(self.error("invalid number"));
+        }
+        let mut float = false;
+        if self.peek() == Some(b'.') {
+            self.position += 1;
+            float = true;
+            if !digits(self) {
+                return Err(self.error("invalid number"));
+            }
+        }
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            self.position += 1;
+            float = true;
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.position += 1;
+            }
+            if !digits(self) {
+                return Err(self.error("invalid number"));
+            }
+        }
+        let text = std::str::from_utf8(&self.input[start..self.position]).unwrap_or_default();
+        if !float {
+            if let Ok(value) = text.parse::<i64>() {
+                return Ok(Json::Integer(value));
+            }
+        }
+        match text.parse::<f64>() {
+            Ok(value) if value.is_finite() => Ok(Json::Float(value)),
+            _ => Err(self.error("number out of range")),
+        }
+    }
+
+    fn hex(&mut self) -> Result<u32, String> {
+        let digits = self.input.get(self.position..self.position + 4).ok_or_else(|| self.error("invalid escape"))?;
+        let value = std::str::from_utf8(digits).ok().and_then(|digits| u32::from_str_radix(digits, 16).ok());
+        self.position += 4;
+        value.ok_or_else(|| self.error("invalid escape"))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.position += 1;
+        let mut bytes = Vec::new();
+        loop {
+            let Some(byte) = self.peek() else {
+                return Err(self.error("EOF while parsing a string"));
+            };
+            self.position += 1;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let Some(escape) = self.peek() else {
+                        return Err(self.error("EOF while parsing a string"));
+                    };
+                    self.position += 1;
+                    let char = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let mut code = self.hex()?;
+                            // A high surrogate must be followed by the escaped low surrogate.
+                            if (0xD800..0xDC00).contains(&code) && self.input[self.position..].starts_with(b"\\u") {
+                                self.position += 2;
+                                let low = self.hex()?;
+                                code = 0x10000 + ((code - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF);
+                            }
+                            char::from_u32(code).ok_or_else(|| self.error("invalid unicode code point"))?
+                        }
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    bytes.extend_from_slice(char.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                byte if byte < 0x20 => return Err(self.error("control character while parsing a string")),
+                byte => bytes.push(byte),
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| self.error("invalid UTF-8"))
+    }
+}
@@ -0,0 +1,446 @@
+//! JSONPath expressions selecting values of a JSON document, as RedisJSON accepts them.
+//!
+//! Paths starting with `$` return every match. Other paths use the legacy syntax (`.`,
+//! `.a.b`, `a[0]`), where commands only reply for a single value and fail when none matches.
+//! Supported selectors are `.name`, `['name', ...]`, `.*` and `[*]`, `[index, ...]` with
+//! negative indexes, `[start:end:step]` slices, `..` recursive descent and `[?(...)]` filters
+//! comparing `@` relative paths with literals through `==`, `!=`, `<`, `<=`, `>`, `>=`,
+//! `&&` and `||`.
+
+use crate::resp::json::{parse_prefix, Json, Step, MAX_DEPTH};
+
+#[derive(Clone, Debug)]
+pub struct Path {
+    /// The path as given, for error messages and `JSON.GET` replies.
+    pub text: String,
+    pub legacy: bool,
+    segments: Vec<Segment>,
+}
+
+#[derive(Clone, Debug)]
+enum Segment {
+    Child(Selector),
+    /// The selector applied to the value and all its descendants.
+    Descendant(Selector),
+}
+
+#[derive(Clone, Debug)]
+enum Selector {
+    Keys(Vec<String>),
+    Wildcard,
+    Indexes(Vec<i64>),
+    Slice {
+        start: Option<i64>,
+        end: Option<i64>,
+        step: i64,
+    },
+    Filter(Box<Filter>),
+}
+
+#[derive(Clone, Debug)]
+enum Filter {
+    Or(Vec<Filter>),
+    And(Vec<Filter>),
+    Exists(Operand),
+    Compare(Operand, Comparison, Operand),
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+#[derive(Clone, Debug)]
+enum Operand {
+    /// A path relative to the value being filtered.
+    Relative(Vec<Segment>),
+    Literal(Json),
+}
+
+impl Path {
+    pub fn parse(text: &str) -> Result<Path, String> {
+        let invalid = || format!("Invalid JSONPath '{}'", text);
+        let (legacy, body) = match text.strip_prefix('$') {
+            Some(body) => (false, body.to_string()),
+            None if text == "." => (true, String::new()),
+            None if text.starts_with('.') || text.starts_with('[') => (true, text.to_string()),
+            None => (true, format!(".{}", text)),
+        };
+        let mut parser = Parser {
+            input: body.as_bytes(),
+            position: 0,
+        };
+        let segments = parser.segments(0).ok_or_else(invalid)?;
+        if parser.position != body.len() {
+            return Err(invalid());
+        }
+        Ok(Path {
+            text: text.to_string(),
+            legacy,
+            segments,
+        })
+    }
+
+    /// The root path in the legacy syntax, which commands use when given none.
+    pub fn root() -> Path {
+        Path {
+            text: ".".to_string(),
+            legacy: true,
+            segments: Vec::new(),
+        }
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Locations of the values matched in `root`, in document order.
+    pub fn locate(&self, root: &Json) -> Vec<Vec<Step>> {
+        locate(&self.segments, root, Vec::new())
+    }
+
+    /// Where a new member could be added when the path matches nothing: the locations of
+    /// the objects matching all but the last segment, when that is a single name.
+    pub fn insertion_points(&self, root: &Json) -> Vec<(Vec<Step>, String)> {
+        let Some((Segment::Child(Selector::Keys(keys)), parent)) = self.segments.split_last() else {
+            return Vec::new();
+        };
+        let [key] = keys.as_slice() else {
+            return Vec::new();
+        };
+        locate(parent, root, Vec::new())
+            .into_iter()
+            .filter(|location| matches!(root.get(location), Some(Json::Object(_))))
+            .map(|location| (location, key.clone()))
+            .collect()
+    }
+}
+
+fn locate(segments: &[Segment], root: &Json, from: Vec<Step>) -> Vec<Vec<Step>> {
+    let mut current = vec![from];
+    for segment in segments {
+        let mut next = Vec::new();
+        for location in current {
+            let Some(value) = root.get(&location) else {
+                continue;
+            };
+            match segment {
+                Segment::Child(selector) => select(selector, value, &location, &mut next),
+                Segment::Descendant(selector) => {
+                    for (location, value) in descendants(value, location) {
+                        select(selector, value, &location, &mut next);
+                    }
+                }
+            }
+        }
+        current = next;
+    }
+    current
+}
+
+/// The value and all the values it contains, depth first.
+fn descendants(value: &Json, location: Vec<Step>) -> Vec<(Vec<Step>, &Json)> {
+    let mut found = vec![(location.clone(), value)];
+    for (step, child) in value.children() {
+        let mut child_location = location.clone();
+        child_location.push(step);
+        found.extend(descendants(child, child_location));
+    }
+    found
+}
+
+fn select(selector: &Selector, value: &Json, location: &[Step], out: &mut Vec<Vec<Step>>) {
+    let mut push = |step: Step| {
+        let mut location = location.to_vec();
+        location.push(step);
+        out.push(location);
+    };
+    match (selector, value) {
+        (Selector::Keys(keys), Json::Object(_)) => {
+            keys.iter().filter(|key| value.member(key).is_some()).for_each(|key| push(Step::Key(key.clone())));
+        }
+        (Selector::Wildcard, _) => value.children().into_iter().for_each(|(step, _)| push(step)),
+        (Selector::Indexes(indexes), Json::Array(items)) => {
+            let len = items.len() as i64;
+            for index in indexes {
+                let index = if *index < 0 { len + index } else { *index };
+                if (0..len).contains(&index) {
+                    push(Step::Index(index as usize));
+                }
+            }
+        }
+        (Selector::Slice { start, end, step }, Json::Array(items)) => {
+            let len = items.len() as i64;
+            let bound = |index: Option<i64>, default: i64| {
+                let index = index.unwrap_or(default);
+                (if index < 0 { len + index } else { index }).clamp(0, len)
+            };
+            let (start, end) = (bound(*start, 0), bound(*end, len));
+            (start..end).step_by(*step as usize).for_each(|index| push(Step::Index(index as usize)));
+        }
+        (Selector::Filter(filter), _) => {
+            for (step, child) in value.children() {
+                if matches(filter, child) {
+                    push(step);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn matches(filter: &Filter, value: &Json) -> bool {
+    let operand = |operand: &Operand| match operand {
+        Operand::Relative(segments) => {
+            locate(segments, value, Vec::new()).first().and_then(|location| value.get(location)).cloned()
+        }
+        Operand::Literal(literal) => Some(literal.clone()),
+    };
+    match filter {
+        Filter::Or(filters) => filters.iter().any(|filter| matches(filter, value)),
+        Filter::And(filters) => filters.iter().all(|filter| matches(filter, value)),
+        Filter::Exists(exists) => operand(exists).is_some(),
+        Filter::Compare(left, comparison, right) => {
+            let (Some(left), Some(right)) = (operand(left), operand(right)) else {
+                return false;
+            };
+            compare(&left, *comparison, &right)
+        }
+    }
+}
+
+fn compare(left: &Json, comparison: Comparison, right: &Json) -> bool {
+    let ordering = match (left, right) {
+        (Json::String(left), Json::String(right)) => Some(left.cmp(right)),
+        _ => match (left.as_f64(), right.as_f64()) {
+            (Some(left), Some(right)) => left.partial_cmp(&right),
+            _ => None,
+        },
+    };
+    let equal = ordering.map_or(left == right, |ordering| ordering.is_eq());
+    match comparison {
+        Comparison::Equal => equal,
+        Comparison::NotEqual => !equal,
+        Comparison::Less => ordering.is_some_and(|ordering| ordering.is_lt()),
+        Comparison::LessOrEqual => ordering.is_some_and(|ordering| ordering.is_le()),
+        Comparison::Greater => ordering.is_some_and(|ordering| ordering.is_gt()),
+        Comparison::GreaterOrEqual => ordering.is_some_and(|ordering| ordering.is_ge()),
+    }
+}
+
+/// Recursive descent parser of the part of a path after `$`. Returns `None` when invalid.
+struct Parser<'a> {
+    input: &'a [u8],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.position).copied()
+    }
+
+    fn whitespace(&mut self) {
+        while self.peek() == Some(b' ') {
+            self.position += 1;
+        }
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.whitespace();
+        if self.input[self.position..].starts_with(token.as_bytes()) {
+            self.position += token.len();
+            return true;
+        }
+        false
+    }
+
+    /// Segments of a path, nested `depth` filters deep.
+    fn segments(&mut self, depth: usize) -> Option<Vec<Segment>> {
+        let mut segments = Vec::new();
+        loop {
+            match self.peek() {
+                Some(b'.') if self.input.get(self.position + 1) == Some(&b'.') => {
+                    self.position += 2;
+                    let selector = if self.peek() == Some(b'[') { self.bracket(depth)? } else { self.name()? };
+                    segments.push(Segment::Descendant(selector));
+                }
+                Some(b'.') => {
+                    self.position += 1;
+                    segments.push(Segment::Child(self.name()?));
+                }
+                Some(b'[') => segments.push(Segment::Child(self.bracket(depth)?)),
+                _ => return Some(segments),
+            }
+        }
+    }
+
+    /// A name after a dot, or `*`.
+    fn name(&mut self) -> Option<Selector> {
+        if self.peek() == Some(b'*') {
+            self.position += 1;
+            return Some(Selector::Wildcard);
+        }
+        let start = self.position;
+        let is_name = |byte: u8| byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'-' || byte >= 0x80;
+        while self.peek().is_some_and(is_name) {
+            self.position += 1;
+        }
+        if self.position == start {
+            return None;
+        }
+        let name = std::str::from_utf8(&self.input[start..self.position]).ok()?;
+        Some(Selector::Keys(vec![name.to_string()]))
+    }
+
+    fn bracket(&mut self, depth: usize) -> Option<Selector> {
+        self.position += 1;
+        self.whitespace();
+        let selector = match self.peek()? {
+            b'*' => {
+                self.position += 1;
+                Selector::Wildcard
+            }
+            b'?' => {
+                self.position += 1;
+                if !self.eat("(") {
+                    return None;
+                }
+                let filter = self.or(depth + 1)?;
+                if !self.eat(")") {
+                    return None;
+                }
+                Selector::Filter(Box::new(filter))
+            }
+            b'\'' | b'"' => {
+                let mut keys = vec![self.quoted()?];
+                while self.eat(",") {
+                    self.whitespace();
+                    keys.push(self.quoted()?);
+                }
+                Selector::Keys(keys)
+            }
+            _ => self.indexes()?,
+        };
+        self.eat("]").then_some(selector)
+    }
+
+    fn quoted(&mut self) -> Option<String> {
+        let quote = self.peek()?;
+        if quote != b'\'' && quote != b'"' {
+            return None;
+        }
+        self.position += 1;
+        let mut bytes = Vec::new();
+        loop {
+            match self.peek()? {
+                byte if byte == quote => {
+                    self.position += 1;
+                    return String::from_utf8(bytes).ok();
+                }
+                b'\\' => {
+                    bytes.push(*self.input.get(self.position + 1)?);
+                    self.position += 2;
+                }
+                byte => {
+                    bytes.push(byte);
+                    self.position += 1;
+                }
+            }
+        }
+    }
+
+    fn integer(&mut self) -> Option<i64> {
+        self.whitespace();
+        let start = self.position;
+        if self.peek() == Some(b'-') {
+            self.position += 1;
+        }
+        while self.peek().is_some_and(|byte| byte.is_ascii_digit()) {
+            self.position += 1;
+        }
+        std::str::from_utf8(&self.input[start..self.position]).ok()?.parse().ok()
+    }
+
+    /// `[index, ...]` or `[start:end:step]` with optional bounds.
+    fn indexes(&mut self) -> Option<Selector> {
+        let first = self.integer();
+        if !self.eat(":") {
+            let mut indexes = vec![first?];
+            while self.eat(",") {
+                indexes.push(self.integer()?);
+            }
+            return Some(Selector::Indexes(indexes));
+        }
+        let end = self.integer();
+        let step = if self.eat(":") { self.integer().unwrap_or(1) } else { 1 };
+        (step > 0).then_some(Selector::Slice {
+            start: first,
+            end,
+            step,
+        })
+    }
+
+    /// Filters nested deeper than `MAX_DEPTH` are rejected rather than overflowing the stack.
+    fn or(&mut self, depth: usize) -> Option<Filter> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        let mut filters = vec![self.and(depth)?];
+        while self.eat("||") {
+            filters.push(self.and(depth)?);
+        }
+        Some(if filters.len() == 1 { filters.remove(0) } else { Filter::Or(filters) })
+    }
+
+    fn and(&mut self, depth: usize) -> Option<Filter> {
+        let mut filters = vec![self.comparison(depth)?];
+        while self.eat("&&") {
+            filters.push(self.comparison(depth)?);
+        }
+        Some(if filters.len() == 1 { filters.remove(0) } else { Filter::And(filters) })
+    }
+
+    fn comparison(&mut self, depth: usize) -> Option<Filter> {
+        if self.eat("(") {
+            let filter = self.or(depth + 1)?;
+            return self.eat(")").then_some(filter);
+        }
+        let left = self.operand(depth)?;
+        let comparisons = [
+            ("==", Comparison::Equal),
+            ("!=", Comparison::NotEqual),
+            ("<=", Comparison::LessOrEqual),
+            (">=", Comparison::GreaterOrEqual),
+            ("<", Comparison::Less),
+            (">", Comparison::Greater),
+        ];
+        for (token, comparison) in comparisons {
+            if self.eat(token) {
+                return Some(Filter::Compare(left, comparison, self.operand(depth)?));
+            }
+        }
+        Some(Filter::Exists(left))
+    }
+
+    fn operand(&mut self, depth: usize) -> Option<Operand> {
+        self.whitespace();
+        match self.peek()? {
+            b'@' => {
+                self.position += 1;
+                Some(Operand::Relative(self.segments(depth)?))
+            }
+            b'\'' => Some(Operand::Literal(Json::String(self.quoted()?))),
+            _ => {
+                let rest = std::str::from_utf8(&self.input[self.position..]).ok()?;
+                let (literal, len) = parse_prefix(rest).ok()?;
+                self.position += len;
+                Some(Operand::Literal(literal))
+            }
+        }
+    }
+}
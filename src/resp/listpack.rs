@@ -0,0 +1,166 @@
+//! Listpacks, the compact encoding Redis uses for streams in RDB files: a header with the
+//! total size and element count, the elements, each followed by its own length so the list
+//! can be walked backwards, and an end byte.
+
+const END: u8 = 0xFF;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Element {
+    Integer(i64),
+    String(Vec<u8>),
+}
+
+impl Element {
+    pub fn string(value: &str) -> Self {
+        Element::String(value.as_bytes().to_vec())
+    }
+
+    /// Integer value; Redis may store integers as strings and the other way around.
+    pub fn as_integer(&self) -> Result<i64, String> {
+        match self {
+            Element::Integer(value) => Ok(*value),
+            Element::String(bytes) => std::str::from_utf8(bytes)
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .ok_or_else(|| "Expected an integer in listpack".to_string()),
+        }
+    }
+
+    pub fn into_string(self) -> Result<String, String> {
+        match self {
+            Element::Integer(value) => Ok(value.to_string()),
+            Element::String(bytes) => {
+                String::from_utf8(bytes).map_err(|_| "Invalid UTF-8 string in listpack".to_string())
+            }
+        }
+    }
+}
+
+pub fn encode(elements: &[Element]) -> Vec<u8> {
+    let mut out = vec![0; 6];
+    for element in elements {
+        let start = out.len();
+        match element {
+            Element::Integer(value) => write_integer(&mut out, *value),
+            Element::String(bytes) => write_string(&mut out, bytes),
+        }
+        let len = out.len() - start;
+        write_backlen(&mut out, len);
+    }
+    out.push(END);
+
+    let total = out.len() as u32;
+    let count = elements.len().min(u16::MAX as usize) as u16;
+    out[..4].copy_from_slice(&total.to_le_bytes());
+    out[4..6].copy_from_slice(&count.to_le_bytes());
+    out
+}
+
+fn write_integer(out: &mut Vec<u8>, value: i64) {
+    match value {
+        0..=127 => out.push(value as u8),
+        -4096..=4095 => {
+            let value = value as u16 & 0x1FFF;
+            out.extend_from_slice(&[0xC0 | (value >> 8) as u8, value as u8]);
+        }
+        _ if i16::try_from(value).is_ok() => {
+            out.push(0xF1);
+            out.extend_from_slice(&(value as i16).to_le_bytes());
+        }
+        _ if (-(1 << 23)..1 << 23).contains(&value) => {
+            out.push(0xF2);
+            out.extend_from_slice(&(value as i32).to_le_bytes()[..3]);
+        }
+        _ if i32::try_from(value).is_ok() => {
+            out.push(0xF3);
+            out.extend_from_slice(&(value as i32).to_le_bytes());
+        }
+        _ => {
+            out.push(0xF4);
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+}
+
+fn write_string(out: &mut Vec<u8>, bytes: &[u8]) {
+    let len = bytes.len();
+    if len < 1 << 6 {
+        out.push(0x80 | len as u8);
+    } else if len < 1 << 12 {
+        out.extend_from_slice(&[0xE0 | (len >> 8) as u8, len as u8]);
+    } else {
+        out.push(0xF0);
+        out.extend_from_slice(&(len as u32).to_le_bytes());
+    }
+    out.extend_from_slice(bytes);
+}
+
+/// Writes the size of an element in 7-bit groups, most significant first, every byte but
+/// the first one having its high bit set.
+fn write_backlen(out: &mut Vec<u8>, len: usize) {
+    let groups = backlen_size(len);
+    for group in (0..groups).rev() {
+        let bits = ((len >> (7 * group)) & 127) as u8;
+        out.push(if group + 1 == groups { bits } else { bits | 128 });
+    }
+}
+
+fn backlen_size(len: usize) -> usize {
+    match len {
+        0..=127 => 1,
+        128..16383 => 2,
+        16383..2097151 => 3,
+        2097151..268435455 => 4,
+        _ => 5,
+    }
+}
+
+pub fn decode(bytes: &[u8]) -> Result<Vec<Element>, String> {
+    let invalid = || "Invalid listpack".to_string();
+    if bytes.len() < 7 || u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize != bytes.len() {
+        return Err(invalid());
+    }
+    let take = |position: usize, len: usize| bytes.get(position..position + len).ok_or_else(invalid);
+    let signed = |raw: u64, bits: u32| {
+        let raw = raw as i64;
+        if raw >= 1 << (bits - 1) {
+            raw - (1 << bits)
+        } else {
+            raw
+        }
+    };
+    let little_endian = |data: &[u8]| data.iter().rev().fold(0u64, |value, byte| (value << 8) | *byte as u64);
+
+    let mut elements = Vec::new();
+    let mut position = 6;
+    loop {
+        let first = *bytes.get(position).ok_or_else(invalid)?;
+        let (element, size) = match first {
+            END => return Ok(elements),
+            0x00..=0x7F => (Element::Integer(first as i64), 1),
+            0x80..=0xBF => {
+                let len = (first & 0x3F) as usize;
+                (Element::String(take(position + 1, len)?.to_vec()), 1 + len)
+            }
+            0xC0..=0xDF => {
+                let raw = (((first & 0x1F) as u64) << 8) | take(position + 1, 1)?[0] as u64;
+                (Element::Integer(signed(raw, 13)), 2)
+            }
+            0xE0..=0xEF => {
+                let len = (((first & 0x0F) as usize) << 8) | take(position + 1, 1)?[0] as usize;
+                (Element::String(take(position + 2, len)?.to_vec()), 2 + len)
+            }
+            0xF0 => {
+                let len = little_endian(take(position + 1, 4)?) as usize;
+                (Element::String(take(position + 5, len)?.to_vec()), 5 + len)
+            }
+            0xF1 => (Element::Integer(signed(little_endian(take(position + 1, 2)?), 16)), 3),
+            0xF2 => (Element::Integer(signed(little_endian(take(position + 1, 3)?), 24)), 4),
+            0xF3 => (Element::Integer(signed(little_endian(take(position + 1, 4)?), 32)), 5),
+            0xF4 => (Element::Integer(little_endian(take(position + 1, 8)?) as i64), 9),
+            _ => return Err(invalid()),
+        };
+        elements.push(element);
+        position += size + backlen_size(size);
+    }
+}
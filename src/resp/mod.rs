@@ -1,7 +1,26 @@
-/// Init for resp module
+//! Init for resp module
 
 pub mod protocol;
 pub mod commands;
 pub mod command_dispatcher;
+pub mod blocking;
+pub mod bloom;
+pub mod client;
+pub mod cluster;
+pub mod cms;
+pub mod config;
+pub mod connection;
+pub mod cuckoo;
+pub mod geohash;
+pub mod hyperloglog;
+pub mod json;
+pub mod json_path;
+pub mod listpack;
+pub mod random;
+pub mod rdb;
+pub mod replication;
+pub mod sentinel;
+pub mod timeseries;
+pub mod topk;
 
-pub mod state;
\ No newline at end of file
+pub mod state;
@@ -8,7 +8,20 @@ pub enum RespType {
     Error(String),
     Integer(i64),
     BulkString(Option<String>), // None for incomplete bulk strings
+    /// Bulk string that is not valid UTF-8, such as a bitmap.
+    BulkBytes(Vec<u8>),
     Array(Vec<RespType>),
+    NullArray,
+}
+
+impl RespType {
+    /// Bulk string of arbitrary bytes, kept as a `BulkString` when they are valid UTF-8.
+    pub fn bulk_bytes(bytes: Vec<u8>) -> RespType {
+        match String::from_utf8(bytes) {
+            Ok(value) => RespType::BulkString(Some(value)),
+            Err(e) => RespType::BulkBytes(e.into_bytes()),
+        }
+    }
 }
 
 impl Clone for RespType {
@@ -18,7 +31,9 @@ impl Clone for RespType {
             RespType::Error(e) => RespType::Error(e.clone()),
             RespType::Integer(i) => RespType::Integer(*i),
             RespType::BulkString(s) => RespType::BulkString(s.clone()),
+            RespType::BulkBytes(bytes) => RespType::BulkBytes(bytes.clone()),
             RespType::Array(arr) => RespType::Array(arr.clone()),
+            RespType::NullArray => RespType::NullArray,
         }
     }
 }
@@ -31,7 +46,9 @@ impl PartialEq<RespType> for RespType {
             (RespType::Integer(i1), RespType::Integer(i2)) => i1 == i2,
             (RespType::BulkString(Some(s1)), RespType::BulkString(Some(s2))) => s1 == s2,
             (RespType::BulkString(None), RespType::BulkString(None)) => true,
+            (RespType::BulkBytes(b1), RespType::BulkBytes(b2)) => b1 == b2,
             (RespType::Array(a1), RespType::Array(a2)) => a1 == a2,
+            (RespType::NullArray, RespType::NullArray) => true,
             _ => false,
         }
     }
@@ -45,7 +62,9 @@ impl Debug for RespType {
             RespType::Integer(i) => write!(f, "Integer({})", i),
             RespType::BulkString(Some(s)) => write!(f, "BulkString({})", s),
             RespType::BulkString(None) => write!(f, "BulkString(None)"),
+            RespType::BulkBytes(bytes) => write!(f, "BulkBytes({:?})", bytes),
             RespType::Array(arr) => write!(f, "Array({:?})", arr),
+            RespType::NullArray => write!(f, "NullArray"),
         }
     }
 }
@@ -62,6 +81,12 @@ pub fn serialize(resp: &RespType) -> Vec<u8> {
                 b"$-1\r\n".to_vec() // Incomplete bulk string
             }
         }
+        RespType::BulkBytes(bytes) => {
+            let mut res = format!("${}\r\n", bytes.len()).into_bytes();
+            res.extend_from_slice(bytes);
+            res.extend_from_slice(b"\r\n");
+            res
+        }
         RespType::Array(arr) => {
             let mut res = format!("*{}\r\n", arr.len()).into_bytes();
 
@@ -71,6 +96,7 @@ pub fn serialize(resp: &RespType) -> Vec<u8> {
 
             res
         }
+        RespType::NullArray => b"*-1\r\n".to_vec(),
     }
 }
 
@@ -133,11 +159,7 @@ pub fn deserialize(input: &[u8]) -> Result<(RespType, usize), Error> {
                 }
 
                 let data = &input[1 + header..1 + header + len as usize];
-                let s = std::str::from_utf8(data)
-                    .map_err(|e| Error::new(ErrorKind::InvalidData, e))?
-                    .to_string();
-
-                Ok((RespType::BulkString(Some(s)), total))
+                Ok((RespType::bulk_bytes(data.to_vec()), total))
             }
         }
         b'*' => {
@@ -148,7 +170,7 @@ pub fn deserialize(input: &[u8]) -> Result<(RespType, usize), Error> {
                 .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
 
             if count < 0 {
-                Ok((RespType::Array(Vec::new()), 1 + header))
+                Ok((RespType::NullArray, 1 + header))
             } else {
                 let mut items = Vec::with_capacity(count as usize);
                 let mut offset = 1 + header;
@@ -0,0 +1,50 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Returns a random number without pulling in an external crate.
+/// Every `RandomState` is seeded with fresh random keys, so hashing the clock with it
+/// is good enough for ids, jitter and sampling, but not for cryptography.
+pub fn random_u64() -> u64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or_default();
+
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(nanos);
+    hasher.write_u32(std::process::id());
+    hasher.finish()
+}
+
+/// Returns a random number in `0..limit`.
+pub fn random_below(limit: u64) -> u64 {
+    random_u64() % limit.max(1)
+}
+
+/// Most items a negative count may pick, since the whole reply is built in memory.
+const MAX_REPEATED_PICKS: u64 = 1 << 24;
+
+/// Picks `count` items at random: distinct ones when `count` is positive, possibly the
+/// same one several times when it is negative, as `HRANDFIELD` and `SRANDMEMBER` do.
+pub fn sample<T: Clone>(mut items: Vec<T>, count: i64) -> Result<Vec<T>, String> {
+    if count < 0 && count.unsigned_abs() > MAX_REPEATED_PICKS {
+        return Err("value is out of range".to_string());
+    }
+    if items.is_empty() {
+        return Ok(items);
+    }
+    if count < 0 {
+        return Ok((0..count.unsigned_abs())
+            .map(|_| items[random_below(items.len() as u64) as usize].clone())
+            .collect());
+    }
+    // Partial Fisher-Yates shuffle.
+    let count = (count as usize).min(items.len());
+    for index in 0..count {
+        let other = index + random_below((items.len() - index) as u64) as usize;
+        items.swap(index, other);
+    }
+    items.truncate(count);
+    Ok(items)
+}
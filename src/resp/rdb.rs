@@ -0,0 +1,926 @@
+//! Minimal RDB snapshot encoding used for full resynchronization of replicas.
+//! Values use the plain (non ziplist/listpack) encoding of each type, except for streams
+//! which only have a listpack encoding.
+
+use std::collections::HashMap;
+
+use crate::resp::bloom::{BloomFilter, ScalableBloom};
+use crate::resp::cms::CountMinSketch;
+use crate::resp::cuckoo::CuckooFilter;
+use crate::resp::json;
+use crate::resp::listpack::{self, Element};
+use crate::resp::state::stream::{ConsumerGroup, Stream, StreamId};
+use crate::resp::state::value::Value;
+use crate::resp::timeseries::{Aggregation, DuplicatePolicy, Rule, TimeSeries};
+use crate::resp::topk::{Bucket, TopK};
+
+/// RDB version 12 (Redis 7.4), the first with hashes whose fields expire.
+const MAGIC: &[u8] = b"REDIS0012";
+const REDIS_VERSION: &[u8] = b"7.4.0";
+
+const OPCODE_AUX: u8 = 0xFA;
+const OPCODE_RESIZEDB: u8 = 0xFB;
+const OPCODE_EXPIRETIME_MS: u8 = 0xFC;
+const OPCODE_EXPIRETIME: u8 = 0xFD;
+const OPCODE_SELECTDB: u8 = 0xFE;
+const OPCODE_EOF: u8 = 0xFF;
+
+const TYPE_STRING: u8 = 0;
+const TYPE_LIST: u8 = 1;
+const TYPE_SET: u8 = 2;
+const TYPE_HASH: u8 = 4;
+/// Sorted set with binary scores.
+const TYPE_ZSET_2: u8 = 5;
+/// Value of a module type: the module ID, then the fields it saved, each tagged with its type.
+const TYPE_MODULE_2: u8 = 7;
+/// Stream with consumer groups tracking entries read and consumers active times (Redis 7.2).
+const TYPE_STREAM_LISTPACKS_3: u8 = 21;
+/// Hash with per-field expiration times (Redis 7.4).
+const TYPE_HASH_METADATA: u8 = 24;
+
+const MODULE_OPCODE_EOF: u64 = 0;
+const MODULE_OPCODE_UINT: u64 = 2;
+const MODULE_OPCODE_DOUBLE: u64 = 4;
+const MODULE_OPCODE_STRING: u64 = 5;
+/// Characters of module type names, whose index is packed 6 bits each in module IDs.
+const MODULE_NAME_CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+/// RedisJSON's type, whose encoding version 3 saves documents as serialized JSON.
+const JSON_MODULE: &str = "ReJSON-RL";
+const JSON_ENCODING_VERSION: u64 = 3;
+/// RedisBloom's Bloom and Cuckoo filter types, saved field by field with encoding version 4.
+const BLOOM_MODULE: &str = "MBbloom--";
+const CUCKOO_MODULE: &str = "MBbloomCF";
+const FILTER_ENCODING_VERSION: u64 = 4;
+/// RedisBloom's Count-Min Sketch and Top-K types. Sketches are saved with their counters
+/// packed as little-endian 32-bit integers.
+const CMS_MODULE: &str = "CMSk-TYPE";
+const TOPK_MODULE: &str = "TopK-TYPE";
+const SKETCH_ENCODING_VERSION: u64 = 0;
+/// RedisTimeSeries' type. Series are saved sample by sample rather than in compressed chunks.
+const TIMESERIES_MODULE: &str = "TSDB-TYPE";
+const TIMESERIES_ENCODING_VERSION: u64 = 0;
+/// Options of RedisBloom's Bloom filters: sizes are not rounded to powers of two, bit
+/// positions are 64-bit and the chain does not grow.
+const BLOOM_OPT_NOROUND: u64 = 1;
+const BLOOM_OPT_FORCE64: u64 = 4;
+const BLOOM_OPT_NO_SCALING: u64 = 8;
+
+/// Entries per listpack when writing streams, Redis' `stream-node-max-entries` default.
+const STREAM_NODE_ENTRIES: usize = 100;
+/// Flags of a stream entry in a listpack.
+const STREAM_ITEM_DELETED: i64 = 1;
+const STREAM_ITEM_SAME_FIELDS: i64 = 2;
+
+/// RDB version written after `DUMP` payloads.
+const DUMP_VERSION: u16 = 12;
+
+/// A key stored in a snapshot together with its absolute expiration time in milliseconds.
+#[derive(Debug, PartialEq)]
+pub struct RdbEntry {
+    pub key: String,
+    pub value: Value,
+    pub expires_at: Option<u64>,
+    /// Absolute expiration times of the fields of a hash, in milliseconds.
+    pub field_expires: HashMap<String, u64>,
+}
+
+pub fn encode(entries: &[RdbEntry]) -> Vec<u8> {
+    let mut out = MAGIC.to_vec();
+
+    out.push(OPCODE_AUX);
+    write_string(&mut out, b"redis-ver");
+    write_string(&mut out, REDIS_VERSION);
+
+    out.push(OPCODE_SELECTDB);
+    write_length(&mut out, 0);
+    out.push(OPCODE_RESIZEDB);
+    write_length(&mut out, entries.len() as u64);
+    write_length(
+        &mut out,
+        entries.iter().filter(|entry| entry.expires_at.is_some()).count() as u64,
+    );
+
+    for entry in entries {
+        if let Some(expires_at) = entry.expires_at {
+            out.push(OPCODE_EXPIRETIME_MS);
+            out.extend_from_slice(&expires_at.to_le_bytes());
+        }
+        match &entry.value {
+            Value::Hash(fields) if !entry.field_expires.is_empty() => {
+                out.push(TYPE_HASH_METADATA);
+                write_string(&mut out, entry.key.as_bytes());
+                write_hash_metadata(&mut out, fields, &entry.field_expires);
+            }
+            value => {
+                out.push(value_type(value));
+                write_string(&mut out, entry.key.as_bytes());
+                write_value(&mut out, value);
+            }
+        }
+    }
+
+    out.push(OPCODE_EOF);
+    // A zero checksum tells the loader that checksumming is disabled.
+    out.extend_from_slice(&[0; 8]);
+    out
+}
+
+pub fn decode(input: &[u8]) -> Result<Vec<RdbEntry>, String> {
+    if input.len() < 9 || &input[..5] != b"REDIS" {
+        return Err("Invalid RDB header".to_string());
+    }
+
+    let mut reader = Reader {
+        input,
+        position: 9,
+    };
+    let mut entries = Vec::new();
+    let mut expires_at = None;
+
+    loop {
+        match reader.byte()? {
+            OPCODE_EOF => return Ok(entries),
+            OPCODE_AUX => {
+                reader.string()?;
+                reader.string()?;
+            }
+            OPCODE_SELECTDB => {
+                reader.length()?;
+            }
+            OPCODE_RESIZEDB => {
+                reader.length()?;
+                reader.length()?;
+            }
+            OPCODE_EXPIRETIME_MS => {
+                expires_at = Some(u64::from_le_bytes(reader.array()?));
+            }
+            OPCODE_EXPIRETIME => {
+                expires_at = Some(u32::from_le_bytes(reader.array()?) as u64 * 1000);
+            }
+            value_type @ (TYPE_STRING
+            | TYPE_LIST
+            | TYPE_SET
+            | TYPE_HASH
+            | TYPE_ZSET_2
+            | TYPE_MODULE_2
+            | TYPE_STREAM_LISTPACKS_3) => {
+                let key = reader.utf8()?;
+                let value = reader.value(value_type)?;
+                entries.push(RdbEntry {
+                    key,
+                    value,
+                    expires_at: expires_at.take(),
+                    field_expires: HashMap::new(),
+                });
+            }
+            TYPE_HASH_METADATA => {
+                let key = reader.utf8()?;
+                let (value, field_expires) = reader.hash_metadata()?;
+                entries.push(RdbEntry {
+                    key,
+                    value,
+                    expires_at: expires_at.take(),
+                    field_expires,
+                });
+            }
+            other => return Err(format!("Unsupported RDB opcode or type: {:#04x}", other)),
+        }
+    }
+}
+
+/// Serializes a single value the way `DUMP` does: its RDB encoding, with the expiration
+/// times of its fields for hashes, followed by the RDB version and a checksum, left at zero.
+pub fn dump_value(value: &Value, field_expires: &HashMap<String, u64>) -> Vec<u8> {
+    let mut out = Vec::new();
+    match value {
+        Value::Hash(fields) if !field_expires.is_empty() => {
+            out.push(TYPE_HASH_METADATA);
+            write_hash_metadata(&mut out, fields, field_expires);
+        }
+        value => {
+            out.push(value_type(value));
+            write_value(&mut out, value);
+        }
+    }
+    out.extend_from_slice(&DUMP_VERSION.to_le_bytes());
+    out.extend_from_slice(&[0; 8]);
+    out
+}
+
+/// Reads back a payload produced by `dump_value`.
+pub fn restore_value(payload: &[u8]) -> Result<(Value, HashMap<String, u64>), String> {
+    let invalid = || "DUMP payload version or checksum are wrong".to_string();
+    if payload.len() < 11 {
+        return Err(invalid());
+    }
+    let (body, footer) = payload.split_at(payload.len() - 10);
+    if u16::from_le_bytes([footer[0], footer[1]]) > DUMP_VERSION {
+        return Err(invalid());
+    }
+
+    let mut reader = Reader {
+        input: body,
+        position: 0,
+    };
+    let restored = match reader.byte()? {
+        value_type @ (TYPE_STRING
+        | TYPE_LIST
+        | TYPE_SET
+        | TYPE_HASH
+        | TYPE_ZSET_2
+        | TYPE_MODULE_2
+        | TYPE_STREAM_LISTPACKS_3) => (reader.value(value_type)?, HashMap::new()),
+        TYPE_HASH_METADATA => reader.hash_metadata()?,
+        _ => return Err("Bad data format".to_string()),
+    };
+    if reader.position != body.len() {
+        return Err(invalid());
+    }
+    Ok(restored)
+}
+
+fn value_type(value: &Value) -> u8 {
+    match value {
+        Value::String(_) => TYPE_STRING,
+        Value::List(_) => TYPE_LIST,
+        Value::Set(_) => TYPE_SET,
+        Value::Hash(_) => TYPE_HASH,
+        Value::SortedSet(_) => TYPE_ZSET_2,
+        Value::Stream(_) => TYPE_STREAM_LISTPACKS_3,
+        Value::Json(_)
+        | Value::Bloom(_)
+        | Value::Cuckoo(_)
+        | Value::CountMinSketch(_)
+        | Value::TopK(_)
+        | Value::TimeSeries(_) => TYPE_MODULE_2,
+    }
+}
+
+fn write_value(out: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::String(value) => write_string(out, value),
+        Value::List(items) => {
+            write_length(out, items.len() as u64);
+            items.iter().for_each(|item| write_string(out, item.as_bytes()));
+        }
+        Value::Set(members) => {
+            write_length(out, members.len() as u64);
+            members.iter().for_each(|member| write_string(out, member.as_bytes()));
+        }
+        Value::SortedSet(entries) => {
+            write_length(out, entries.len() as u64);
+            for (member, score) in entries.iter() {
+                write_string(out, member.as_bytes());
+                out.extend_from_slice(&score.to_le_bytes());
+            }
+        }
+        Value::Hash(fields) => {
+            write_length(out, fields.len() as u64);
+            for (field, value) in fields {
+                write_string(out, field.as_bytes());
+                write_string(out, value.as_bytes());
+            }
+        }
+        Value::Stream(stream) => write_stream(out, stream),
+        Value::Json(document) => {
+            write_length(out, module_id(JSON_MODULE, JSON_ENCODING_VERSION));
+            write_module_string(out, document.serialize().as_bytes());
+            write_length(out, MODULE_OPCODE_EOF);
+        }
+        Value::Bloom(bloom) => {
+            write_length(out, module_id(BLOOM_MODULE, FILTER_ENCODING_VERSION));
+            write_bloom(out, bloom);
+            write_length(out, MODULE_OPCODE_EOF);
+        }
+        Value::Cuckoo(cuckoo) => {
+            write_length(out, module_id(CUCKOO_MODULE, FILTER_ENCODING_VERSION));
+            write_cuckoo(out, cuckoo);
+            write_length(out, MODULE_OPCODE_EOF);
+        }
+        Value::CountMinSketch(sketch) => {
+            write_length(out, module_id(CMS_MODULE, SKETCH_ENCODING_VERSION));
+            write_module_unsigned(out, sketch.width);
+            write_module_unsigned(out, sketch.depth);
+            write_module_unsigned(out, sketch.count);
+            let counters = sketch.counters.iter().flat_map(|counter| counter.to_le_bytes()).collect::<Vec<_>>();
+            write_module_string(out, &counters);
+            write_length(out, MODULE_OPCODE_EOF);
+        }
+        Value::TopK(topk) => {
+            write_length(out, module_id(TOPK_MODULE, SKETCH_ENCODING_VERSION));
+            write_topk(out, topk);
+            write_length(out, MODULE_OPCODE_EOF);
+        }
+        Value::TimeSeries(series) => {
+            write_length(out, module_id(TIMESERIES_MODULE, TIMESERIES_ENCODING_VERSION));
+            write_timeseries(out, series);
+            write_length(out, MODULE_OPCODE_EOF);
+        }
+    }
+}
+
+fn write_module_unsigned(out: &mut Vec<u8>, value: u64) {
+    write_length(out, MODULE_OPCODE_UINT);
+    write_length(out, value);
+}
+
+fn write_module_double(out: &mut Vec<u8>, value: f64) {
+    write_length(out, MODULE_OPCODE_DOUBLE);
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn write_module_string(out: &mut Vec<u8>, value: &[u8]) {
+    write_length(out, MODULE_OPCODE_STRING);
+    write_string(out, value);
+}
+
+/// Writes the chain's size, filter count, options and growth, then each filter.
+fn write_bloom(out: &mut Vec<u8>, bloom: &ScalableBloom) {
+    let scaling = if bloom.expansion == 0 { BLOOM_OPT_NO_SCALING } else { 0 };
+    write_module_unsigned(out, bloom.size());
+    write_module_unsigned(out, bloom.filters.len() as u64);
+    write_module_unsigned(out, BLOOM_OPT_NOROUND | BLOOM_OPT_FORCE64 | scaling);
+    write_module_unsigned(out, bloom.expansion);
+    for filter in &bloom.filters {
+        write_module_unsigned(out, filter.capacity);
+        write_module_double(out, filter.error_rate);
+        write_module_unsigned(out, filter.hashes);
+        write_module_double(out, filter.bits_per_item);
+        write_module_unsigned(out, filter.bits.len() as u64 * 8);
+        // Power of two sizes are not used.
+        write_module_unsigned(out, 0);
+        write_module_string(out, &filter.bits);
+        write_module_unsigned(out, filter.size);
+    }
+}
+
+fn write_cuckoo(out: &mut Vec<u8>, cuckoo: &CuckooFilter) {
+    write_module_unsigned(out, cuckoo.filters.len() as u64);
+    write_module_unsigned(out, cuckoo.buckets);
+    write_module_unsigned(out, cuckoo.items);
+    write_module_unsigned(out, cuckoo.deletes);
+    write_module_unsigned(out, cuckoo.bucket_size);
+    write_module_unsigned(out, cuckoo.max_iterations);
+    write_module_unsigned(out, cuckoo.expansion);
+    for filter in &cuckoo.filters {
+        write_module_unsigned(out, filter.len() as u64 / cuckoo.bucket_size);
+        write_module_string(out, filter);
+    }
+}
+
+fn invalid_module() -> String {
+    "Invalid module value in RDB".to_string()
+}
+
+/// Writes the parameters, the buckets as fingerprint and count pairs, the top items with
+/// their counts and the state of the generator deciding decays.
+fn write_topk(out: &mut Vec<u8>, topk: &TopK) {
+    write_module_unsigned(out, topk.k);
+    write_module_unsigned(out, topk.width);
+    write_module_unsigned(out, topk.depth);
+    write_module_double(out, topk.decay);
+    let buckets = topk.buckets.iter().flat_map(|bucket| {
+        let [a, b, c, d] = bucket.fingerprint.to_le_bytes();
+        let [e, f, g, h] = bucket.count.to_le_bytes();
+        [a, b, c, d, e, f, g, h]
+    });
+    write_module_string(out, &buckets.collect::<Vec<_>>());
+    write_module_unsigned(out, topk.top.len() as u64);
+    for (item, count) in &topk.top {
+        write_module_string(out, item);
+        write_module_unsigned(out, *count as u64);
+    }
+    write_module_unsigned(out, topk.random_state);
+}
+
+/// Writes the options and labels, the source and rules, where a bucket is saved as its start
+/// plus one and 0 for none, then each sample.
+fn write_timeseries(out: &mut Vec<u8>, series: &TimeSeries) {
+    write_module_unsigned(out, series.retention);
+    let policy = DuplicatePolicy::ALL.iter().position(|policy| *policy == series.duplicate_policy);
+    write_module_unsigned(out, policy.unwrap_or_default() as u64);
+    write_module_unsigned(out, series.labels.len() as u64);
+    for (label, value) in &series.labels {
+        write_module_string(out, label.as_bytes());
+        write_module_string(out, value.as_bytes());
+    }
+    write_module_unsigned(out, series.source.is_some() as u64);
+    if let Some(source) = &series.source {
+        write_module_string(out, source.as_bytes());
+    }
+    write_module_unsigned(out, series.rules.len() as u64);
+    for rule in &series.rules {
+        write_module_string(out, rule.destination.as_bytes());
+        let aggregation = Aggregation::ALL.iter().position(|aggregation| *aggregation == rule.aggregation);
+        write_module_unsigned(out, aggregation.unwrap_or_default() as u64);
+        write_module_unsigned(out, rule.bucket_duration);
+        write_module_unsigned(out, rule.align);
+        write_module_unsigned(out, rule.bucket.map_or(0, |bucket| bucket.saturating_add(1)));
+    }
+    write_module_unsigned(out, series.samples.len() as u64);
+    for (timestamp, value) in &series.samples {
+        write_module_unsigned(out, *timestamp);
+        write_module_double(out, *value);
+    }
+}
+
+/// Module IDs pack the 9 characters of the type name above a 10-bit encoding version.
+fn module_id(name: &str, version: u64) -> u64 {
+    let name = name.bytes().fold(0, |id, char| {
+        let index = MODULE_NAME_CHARSET.iter().position(|known| *known == char).unwrap_or_default();
+        (id << 6) | index as u64
+    });
+    (name << 10) | version
+}
+
+/// Writes a stream as listpacks keyed by the ID of their first entry, followed by its
+/// metadata. Each listpack starts with a master entry holding the fields of its first
+/// entry, which the following entries with the same fields omit.
+fn write_stream(out: &mut Vec<u8>, stream: &Stream) {
+    let entries = stream.iter().collect::<Vec<_>>();
+    write_length(out, entries.len().div_ceil(STREAM_NODE_ENTRIES) as u64);
+    for node in entries.chunks(STREAM_NODE_ENTRIES) {
+        let (master_id, master_fields) = node[0];
+        let mut elements = vec![
+            Element::Integer(node.len() as i64),
+            Element::Integer(0),
+            Element::Integer(master_fields.len() as i64),
+        ];
+        elements.extend(master_fields.iter().map(|(field, _)| Element::string(field)));
+        elements.push(Element::Integer(0));
+
+        for (id, fields) in node {
+            let same_fields = fields.len() == master_fields.len()
+                && fields.iter().zip(master_fields.iter()).all(|((field, _), (master, _))| field == master);
+            elements.push(Element::Integer(if same_fields { STREAM_ITEM_SAME_FIELDS } else { 0 }));
+            elements.push(Element::Integer(id.ms.wrapping_sub(master_id.ms) as i64));
+            elements.push(Element::Integer(id.seq.wrapping_sub(master_id.seq) as i64));
+            if same_fields {
+                elements.extend(fields.iter().map(|(_, value)| Element::string(value)));
+            } else {
+                elements.push(Element::Integer(fields.len() as i64));
+                for (field, value) in fields.iter() {
+                    elements.extend([Element::string(field), Element::string(value)]);
+                }
+            }
+            // Number of elements of the entry, to walk the listpack backwards.
+            let count = 3 + fields.len() + if same_fields { 0 } else { fields.len() + 1 };
+            elements.push(Element::Integer(count as i64));
+        }
+        write_string(out, &master_id.to_bytes());
+        write_string(out, &listpack::encode(&elements));
+    }
+
+    write_length(out, stream.len() as u64);
+    let first_id = stream.first_id().unwrap_or_default();
+    for id in [stream.last_id, first_id, stream.max_deleted_id] {
+        write_length(out, id.ms);
+        write_length(out, id.seq);
+    }
+    write_length(out, stream.entries_added);
+
+    // Consumer groups, with times as little-endian milliseconds and IDs as raw bytes.
+    write_length(out, stream.groups.len() as u64);
+    for (name, group) in &stream.groups {
+        write_string(out, name.as_bytes());
+        write_length(out, group.last_id.ms);
+        write_length(out, group.last_id.seq);
+        // An unknown count is saved as -1.
+        write_length(out, group.entries_read.unwrap_or(u64::MAX));
+        write_length(out, group.pending.len() as u64);
+        for (id, entry) in &group.pending {
+            out.extend_from_slice(&id.to_bytes());
+            out.extend_from_slice(&entry.delivery_time.to_le_bytes());
+            write_length(out, entry.delivery_count);
+        }
+        write_length(out, group.consumers.len() as u64);
+        for (name, consumer) in &group.consumers {
+            write_string(out, name.as_bytes());
+            out.extend_from_slice(&consumer.seen_time.to_le_bytes());
+            out.extend_from_slice(&consumer.active_time.unwrap_or(u64::MAX).to_le_bytes());
+            write_length(out, consumer.pending.len() as u64);
+            for id in &consumer.pending {
+                out.extend_from_slice(&id.to_bytes());
+            }
+        }
+    }
+}
+
+/// Writes a hash whose fields may expire: the earliest expiration time, then each field
+/// preceded by its expiration time relative to it (0 when the field does not expire).
+fn write_hash_metadata(out: &mut Vec<u8>, fields: &HashMap<String, String>, field_expires: &HashMap<String, u64>) {
+    let min_expire = field_expires.values().copied().min().unwrap_or_default();
+    out.extend_from_slice(&min_expire.to_le_bytes());
+    write_length(out, fields.len() as u64);
+    for (field, value) in fields {
+        write_length(out, field_expires.get(field).map_or(0, |expires_at| expires_at - min_expire + 1));
+        write_string(out, field.as_bytes());
+        write_string(out, value.as_bytes());
+    }
+}
+
+fn write_length(out: &mut Vec<u8>, len: u64) {
+    if len < 1 << 6 {
+        out.push(len as u8);
+    } else if len < 1 << 14 {
+        out.push(0x40 | (len >> 8) as u8);
+        out.push(len as u8);
+    } else if len <= u32::MAX as u64 {
+        out.push(0x80);
+        out.extend_from_slice(&(len as u32).to_be_bytes());
+    } else {
+        out.push(0x81);
+        out.extend_from_slice(&len.to_be_bytes());
+    }
+}
+
+fn write_string(out: &mut Vec<u8>, value: &[u8]) {
+    write_length(out, value.len() as u64);
+    out.extend_from_slice(value);
+}
+
+/// Either a plain length or one of the special integer encodings of a string.
+enum Length {
+    Plain(u64),
+    Integer(i64),
+}
+
+struct Reader<'a> {
+    input: &'a [u8],
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn bytes(&mut self, len: usize) -> Result<&[u8], String> {
+        if self.position + len > self.input.len() {
+            return Err("Unexpected end of RDB".to_string());
+        }
+        let bytes = &self.input[self.position..self.position + len];
+        self.position += len;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.bytes(N)?);
+        Ok(array)
+    }
+
+    fn encoded_length(&mut self) -> Result<Length, String> {
+        let first = self.byte()?;
+        match first >> 6 {
+            0 => Ok(Length::Plain((first & 0x3F) as u64)),
+            1 => Ok(Length::Plain(
+                (((first & 0x3F) as u64) << 8) | self.byte()? as u64,
+            )),
+            2 => match first {
+                0x80 => Ok(Length::Plain(u32::from_be_bytes(self.array()?) as u64)),
+                0x81 => Ok(Length::Plain(u64::from_be_bytes(self.array()?))),
+                _ => Err(format!("Invalid RDB length encoding: {:#04x}", first)),
+            },
+            _ => match first & 0x3F {
+                0 => Ok(Length::Integer(self.byte()? as i8 as i64)),
+                1 => Ok(Length::Integer(i16::from_le_bytes(self.array()?) as i64)),
+                2 => Ok(Length::Integer(i32::from_le_bytes(self.array()?) as i64)),
+                _ => Err("Compressed RDB strings are not supported".to_string()),
+            },
+        }
+    }
+
+    fn length(&mut self) -> Result<u64, String> {
+        match self.encoded_length()? {
+            Length::Plain(len) => Ok(len),
+            Length::Integer(_) => Err("Expected a length in RDB".to_string()),
+        }
+    }
+
+    fn string(&mut self) -> Result<Vec<u8>, String> {
+        match self.encoded_length()? {
+            Length::Plain(len) => Ok(self.bytes(len as usize)?.to_vec()),
+            Length::Integer(value) => Ok(value.to_string().into_bytes()),
+        }
+    }
+
+    fn utf8(&mut self) -> Result<String, String> {
+        String::from_utf8(self.string()?).map_err(|_| "Invalid UTF-8 string in RDB".to_string())
+    }
+
+    fn value(&mut self, value_type: u8) -> Result<Value, String> {
+        match value_type {
+            TYPE_STRING => return Ok(Value::String(self.string()?)),
+            TYPE_STREAM_LISTPACKS_3 => return Ok(Value::Stream(self.stream()?)),
+            TYPE_MODULE_2 => return self.module(),
+            _ => {}
+        }
+        let len = self.length()?;
+        Ok(match value_type {
+            TYPE_LIST => Value::List((0..len).map(|_| self.utf8()).collect::<Result<_, _>>()?),
+            TYPE_SET => Value::Set((0..len).map(|_| self.utf8()).collect::<Result<_, _>>()?),
+            TYPE_ZSET_2 => Value::SortedSet(
+                (0..len)
+                    .map(|_| Ok((self.utf8()?, f64::from_le_bytes(self.array()?))))
+                    .collect::<Result<_, String>>()?,
+            ),
+            _ => Value::Hash(
+                (0..len)
+                    .map(|_| Ok((self.utf8()?, self.utf8()?)))
+                    .collect::<Result<_, String>>()?,
+            ),
+        })
+    }
+
+    /// Reads a module value: a RedisJSON document saved as a string, a RedisBloom filter or
+    /// sketch, or a RedisTimeSeries series.
+    fn module(&mut self) -> Result<Value, String> {
+        let id = self.length()?;
+        let version = id & 0x3FF;
+        let is = |name: &str| id >> 10 == module_id(name, 0) >> 10;
+        let value = if is(JSON_MODULE) && (2..=JSON_ENCODING_VERSION).contains(&version) {
+            let document = self.module_string()?;
+            Value::Json(json::parse(std::str::from_utf8(&document).map_err(|_| invalid_module())?)?)
+        } else if is(BLOOM_MODULE) && version == FILTER_ENCODING_VERSION {
+            Value::Bloom(self.bloom()?)
+        } else if is(CUCKOO_MODULE) && version == FILTER_ENCODING_VERSION {
+            Value::Cuckoo(self.cuckoo()?)
+        } else if is(CMS_MODULE) && version == SKETCH_ENCODING_VERSION {
+            Value::CountMinSketch(self.count_min_sketch()?)
+        } else if is(TOPK_MODULE) && version == SKETCH_ENCODING_VERSION {
+            Value::TopK(self.topk()?)
+        } else if is(TIMESERIES_MODULE) && version == TIMESERIES_ENCODING_VERSION {
+            Value::TimeSeries(self.timeseries()?)
+        } else {
+            return Err(format!("Unsupported RDB module type {:#x}", id));
+        };
+        if self.length()? != MODULE_OPCODE_EOF {
+            return Err(invalid_module());
+        }
+        Ok(value)
+    }
+
+    fn module_opcode(&mut self, opcode: u64) -> Result<(), String> {
+        if self.length()? != opcode {
+            return Err(invalid_module());
+        }
+        Ok(())
+    }
+
+    fn module_unsigned(&mut self) -> Result<u64, String> {
+        self.module_opcode(MODULE_OPCODE_UINT)?;
+        self.length()
+    }
+
+    fn module_double(&mut self) -> Result<f64, String> {
+        self.module_opcode(MODULE_OPCODE_DOUBLE)?;
+        Ok(f64::from_le_bytes(self.array()?))
+    }
+
+    fn module_string(&mut self) -> Result<Vec<u8>, String> {
+        self.module_opcode(MODULE_OPCODE_STRING)?;
+        self.string()
+    }
+
+    fn bloom(&mut self) -> Result<ScalableBloom, String> {
+        self.module_unsigned()?;
+        let filters = self.module_unsigned()?;
+        let options = self.module_unsigned()?;
+        let growth = self.module_unsigned()?;
+        let mut bloom = ScalableBloom {
+            filters: Vec::new(),
+            expansion: if options & BLOOM_OPT_NO_SCALING != 0 { 0 } else { growth },
+        };
+        for _ in 0..filters {
+            let capacity = self.module_unsigned()?;
+            let error_rate = self.module_double()?;
+            let hashes = self.module_unsigned()?;
+            let bits_per_item = self.module_double()?;
+            let len = self.module_unsigned()?;
+            self.module_unsigned()?;
+            let bits = self.module_string()?;
+            if bits.is_empty() || bits.len() as u64 * 8 != len {
+                return Err(invalid_module());
+            }
+            bloom.filters.push(BloomFilter {
+                capacity,
+                error_rate,
+                hashes,
+                bits_per_item,
+                bits,
+                size: self.module_unsigned()?,
+            });
+        }
+        if bloom.filters.is_empty() {
+            return Err(invalid_module());
+        }
+        Ok(bloom)
+    }
+
+    fn count_min_sketch(&mut self) -> Result<CountMinSketch, String> {
+        let width = self.module_unsigned()?;
+        let depth = self.module_unsigned()?;
+        let count = self.module_unsigned()?;
+        let counters = self.module_string()?;
+        if width == 0 || width.checked_mul(depth).and_then(|len| len.checked_mul(4)) != Some(counters.len() as u64) {
+            return Err(invalid_module());
+        }
+        let counters = counters.chunks_exact(4).map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap_or_default()));
+        Ok(CountMinSketch {
+            width,
+            depth,
+            count,
+            counters: counters.collect(),
+        })
+    }
+
+    fn topk(&mut self) -> Result<TopK, String> {
+        let k = self.module_unsigned()?;
+        let width = self.module_unsigned()?;
+        let depth = self.module_unsigned()?;
+        let decay = self.module_double()?;
+        let buckets = self.module_string()?;
+        if width == 0 || width.checked_mul(depth).and_then(|len| len.checked_mul(8)) != Some(buckets.len() as u64) {
+            return Err(invalid_module());
+        }
+        let buckets = buckets.chunks_exact(8).map(|chunk| Bucket {
+            fingerprint: u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]),
+            count: u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]),
+        });
+        let mut topk = TopK {
+            k,
+            width,
+            depth,
+            decay,
+            buckets: buckets.collect(),
+            top: Vec::new(),
+            random_state: 0,
+        };
+        for _ in 0..self.module_unsigned()?.min(k) {
+            let item = self.module_string()?;
+            let count = u32::try_from(self.module_unsigned()?).map_err(|_| invalid_module())?;
+            topk.top.push((item, count));
+        }
+        topk.random_state = self.module_unsigned()?;
+        Ok(topk)
+    }
+
+    fn module_utf8(&mut self) -> Result<String, String> {
+        String::from_utf8(self.module_string()?).map_err(|_| invalid_module())
+    }
+
+    fn timeseries(&mut self) -> Result<TimeSeries, String> {
+        let retention = self.module_unsigned()?;
+        let policy = DuplicatePolicy::ALL.get(self.module_unsigned()? as usize).ok_or_else(invalid_module)?;
+        let mut labels = Vec::new();
+        for _ in 0..self.module_unsigned()? {
+            labels.push((self.module_utf8()?, self.module_utf8()?));
+        }
+        let mut series = TimeSeries::new(retention, *policy, labels);
+        if self.module_unsigned()? != 0 {
+            series.source = Some(self.module_utf8()?);
+        }
+        for _ in 0..self.module_unsigned()? {
+            let destination = self.module_utf8()?;
+            let aggregation = Aggregation::ALL.get(self.module_unsigned()? as usize).ok_or_else(invalid_module)?;
+            let bucket_duration = self.module_unsigned()?;
+            if bucket_duration == 0 {
+                return Err(invalid_module());
+            }
+            series.rules.push(Rule {
+                destination,
+                aggregation: *aggregation,
+                bucket_duration,
+                align: self.module_unsigned()?,
+                bucket: self.module_unsigned()?.checked_sub(1),
+            });
+        }
+        for _ in 0..self.module_unsigned()? {
+            let timestamp = self.module_unsigned()?;
+            series.samples.insert(timestamp, self.module_double()?);
+        }
+        Ok(series)
+    }
+
+    fn cuckoo(&mut self) -> Result<CuckooFilter, String> {
+        let filters = self.module_unsigned()?;
+        let mut cuckoo = CuckooFilter {
+            buckets: self.module_unsigned()?,
+            items: self.module_unsigned()?,
+            deletes: self.module_unsigned()?,
+            bucket_size: self.module_unsigned()?,
+            max_iterations: self.module_unsigned()?,
+            expansion: self.module_unsigned()?,
+            filters: Vec::new(),
+        };
+        for _ in 0..filters {
+            let buckets = self.module_unsigned()?;
+            let filter = self.module_string()?;
+            if !buckets.is_power_of_two() || filter.len() as u64 != buckets.saturating_mul(cuckoo.bucket_size) {
+                return Err(invalid_module());
+            }
+            cuckoo.filters.push(filter);
+        }
+        if cuckoo.filters.is_empty() || cuckoo.bucket_size == 0 {
+            return Err(invalid_module());
+        }
+        Ok(cuckoo)
+    }
+
+    fn hash_metadata(&mut self) -> Result<(Value, HashMap<String, u64>), String> {
+        let min_expire = u64::from_le_bytes(self.array()?);
+        let len = self.length()?;
+        let mut fields = HashMap::new();
+        let mut field_expires = HashMap::new();
+        for _ in 0..len {
+            let ttl = self.length()?;
+            let field = self.utf8()?;
+            if ttl != 0 {
+                field_expires.insert(field.clone(), min_expire + ttl - 1);
+            }
+            fields.insert(field, self.utf8()?);
+        }
+        Ok((Value::Hash(fields), field_expires))
+    }
+
+    fn stream_id(&mut self) -> Result<StreamId, String> {
+        Ok(StreamId::new(self.length()?, self.length()?))
+    }
+
+    fn stream(&mut self) -> Result<Stream, String> {
+        let mut stream = Stream::default();
+        for _ in 0..self.length()? {
+            let key = self.string()?;
+            let master_id = StreamId::from_bytes(key.try_into().map_err(|_| "Invalid stream node key".to_string())?);
+            let mut elements = listpack::decode(&self.string()?)?.into_iter();
+            let mut next = || elements.next().ok_or_else(|| "Truncated stream listpack".to_string());
+
+            let count = next()?.as_integer()? + next()?.as_integer()?;
+            let master_fields = (0..next()?.as_integer()?)
+                .map(|_| next()?.into_string())
+                .collect::<Result<Vec<_>, _>>()?;
+            next()?;
+            for _ in 0..count {
+                let flags = next()?.as_integer()?;
+                let id = StreamId::new(
+                    master_id.ms.wrapping_add(next()?.as_integer()? as u64),
+                    master_id.seq.wrapping_add(next()?.as_integer()? as u64),
+                );
+                let fields = if flags & STREAM_ITEM_SAME_FIELDS != 0 {
+                    master_fields
+                        .iter()
+                        .map(|field| Ok((field.clone(), next()?.into_string()?)))
+                        .collect::<Result<Vec<_>, String>>()?
+                } else {
+                    (0..next()?.as_integer()?)
+                        .map(|_| Ok((next()?.into_string()?, next()?.into_string()?)))
+                        .collect::<Result<Vec<_>, String>>()?
+                };
+                next()?;
+                if flags & STREAM_ITEM_DELETED == 0 {
+                    stream.add(id, fields);
+                }
+            }
+        }
+
+        self.length()?;
+        stream.last_id = self.stream_id()?;
+        self.stream_id()?;
+        stream.max_deleted_id = self.stream_id()?;
+        stream.entries_added = self.length()?;
+
+        for _ in 0..self.length()? {
+            let name = self.utf8()?;
+            let last_id = self.stream_id()?;
+            let entries_read = Some(self.length()?).filter(|entries_read| *entries_read != u64::MAX);
+            let mut group = ConsumerGroup::new(last_id, entries_read);
+            // Owners are only known once the consumers are read.
+            let mut pending = HashMap::new();
+            for _ in 0..self.length()? {
+                let id = StreamId::from_bytes(self.array()?);
+                pending.insert(id, (u64::from_le_bytes(self.array()?), self.length()?));
+            }
+            for _ in 0..self.length()? {
+                let consumer = self.utf8()?;
+                let seen_time = u64::from_le_bytes(self.array()?);
+                let active_time = Some(u64::from_le_bytes(self.array()?)).filter(|time| *time != u64::MAX);
+                for _ in 0..self.length()? {
+                    let id = StreamId::from_bytes(self.array()?);
+                    let (delivery_time, delivery_count) = pending
+                        .remove(&id)
+                        .ok_or_else(|| "Stream consumer has an entry missing from its group".to_string())?;
+                    group.deliver(id, &consumer, delivery_time, delivery_count);
+                }
+                let consumer = group.consumers.entry(consumer).or_default();
+                consumer.seen_time = seen_time;
+                consumer.active_time = active_time;
+            }
+            if !pending.is_empty() {
+                return Err("Stream group has entries without consumer".to_string());
+            }
+            stream.groups.insert(name, group);
+        }
+        Ok(stream)
+    }
+}
@@ -0,0 +1,87 @@
+/// Circular buffer with the most recent part of the replication stream.
+/// Replicas that reconnect with an offset still covered by it can resume with a partial resync.
+pub struct ReplicationBacklog {
+    buffer: Vec<u8>,
+    /// Position in `buffer` where the next byte is written.
+    idx: usize,
+    /// Number of valid bytes stored in the buffer.
+    histlen: usize,
+    /// Replication offset of the last byte fed into the backlog (`master_repl_offset`).
+    end_offset: u64,
+}
+
+impl ReplicationBacklog {
+    pub fn new(size: usize) -> Self {
+        ReplicationBacklog {
+            buffer: vec![0; size.max(1)],
+            idx: 0,
+            histlen: 0,
+            end_offset: 0,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn histlen(&self) -> usize {
+        self.histlen
+    }
+
+    /// Offset of the last byte of the replication stream.
+    pub fn end_offset(&self) -> u64 {
+        self.end_offset
+    }
+
+    /// Offset of the oldest byte still available, 1-based like in Redis.
+    pub fn first_byte_offset(&self) -> u64 {
+        self.end_offset - self.histlen as u64 + 1
+    }
+
+    /// Drops the history and continues the stream from the given offset.
+    pub fn reset(&mut self, end_offset: u64) {
+        self.idx = 0;
+        self.histlen = 0;
+        self.end_offset = end_offset;
+    }
+
+    pub fn feed(&mut self, data: &[u8]) {
+        self.end_offset += data.len() as u64;
+
+        // Only the tail of an oversized write can be kept anyway.
+        let size = self.buffer.len();
+        let data = if data.len() > size {
+            &data[data.len() - size..]
+        } else {
+            data
+        };
+
+        let first = (size - self.idx).min(data.len());
+        self.buffer[self.idx..self.idx + first].copy_from_slice(&data[..first]);
+        self.buffer[..data.len() - first].copy_from_slice(&data[first..]);
+
+        self.idx = (self.idx + data.len()) % size;
+        self.histlen = (self.histlen + data.len()).min(size);
+    }
+
+    /// Returns the bytes a replica is missing when it asks to continue from `offset`
+    /// (the offset of the first byte it has not received), or `None` if they are gone.
+    pub fn read_from(&self, offset: u64) -> Option<Vec<u8>> {
+        if offset < self.first_byte_offset() || offset > self.end_offset + 1 {
+            return None;
+        }
+
+        let len = (self.end_offset + 1 - offset) as usize;
+        let size = self.buffer.len();
+        let start = (self.idx + size - len) % size;
+
+        let mut result = Vec::with_capacity(len);
+        if start + len <= size {
+            result.extend_from_slice(&self.buffer[start..start + len]);
+        } else {
+            result.extend_from_slice(&self.buffer[start..]);
+            result.extend_from_slice(&self.buffer[..len - (size - start)]);
+        }
+        Some(result)
+    }
+}
@@ -0,0 +1,91 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use log::{error, info};
+use tokio::sync::Mutex;
+
+use crate::resp::client::ClientContext;
+use crate::resp::command_dispatcher::{parse_command, CommandDispatcher};
+use crate::resp::connection::Connection;
+use crate::resp::state::default_server_state::DefaultServerState;
+use crate::resp::state::server_state::ServerState;
+
+/// Serves a connection that completed `PSYNC`: everything fed into the replication stream
+/// is written to the replica, while the replica only reports its offset with `REPLCONF ACK`.
+pub async fn serve_replica(
+    mut connection: Connection,
+    mut client: ClientContext,
+    dispatcher: Arc<CommandDispatcher>,
+    state: Arc<Mutex<DefaultServerState>>,
+) {
+    let (Some(id), Some(mut stream)) = (client.replica_id, client.replication_stream.take()) else {
+        return;
+    };
+
+    loop {
+        tokio::select! {
+            data = stream.recv() => {
+                let Some(data) = data else { break };
+                if let Err(e) = connection.write_bytes(&data).await {
+                    error!("Failed to write to replica {}: {}", id, e);
+                    break;
+                }
+            }
+            frame = connection.read_frame() => {
+                match frame {
+                    Ok(Some((frame, _))) => {
+                        if let Some((command_name, args)) = parse_command(frame) {
+                            let mut guard = state.lock().await;
+                            // Replicas don't expect replies on the replication link.
+                            if let Err(e) = dispatcher.dispatch(&command_name, args, &mut guard, &mut client) {
+                                error!("Replica {} command failed: {}", id, e);
+                            }
+                        }
+                    }
+                    Ok(None) => break,
+                    Err(e) => {
+                        error!("Failed to read from replica {}: {}", id, e);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    info!("Replica {} disconnected", id);
+    state.lock().await.replication().unregister_replica(id);
+}
+
+/// Suspends a `WAIT` until `numreplicas` replicas acknowledged `offset` or the deadline
+/// passes, returning the number of replicas that did.
+pub async fn wait_for_acks(
+    state: &Mutex<DefaultServerState>,
+    numreplicas: usize,
+    offset: u64,
+    deadline: Option<Instant>,
+) -> usize {
+    loop {
+        let notify = state.lock().await.replication().ack_notify();
+        let notified = notify.notified();
+        tokio::pin!(notified);
+        // Register before checking so an ACK arriving in between is not missed.
+        notified.as_mut().enable();
+
+        let acked = state.lock().await.replication().count_acked(offset);
+        if acked >= numreplicas {
+            return acked;
+        }
+
+        match deadline {
+            Some(deadline) => {
+                tokio::select! {
+                    _ = &mut notified => {}
+                    _ = tokio::time::sleep_until(deadline.into()) => {
+                        return state.lock().await.replication().count_acked(offset);
+                    }
+                }
+            }
+            None => notified.await,
+        }
+    }
+}
@@ -0,0 +1,362 @@
+//! Primary/replica replication: replication ids and offsets, the backlog used for
+//! partial resynchronization and the bookkeeping of connected replicas.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use log::info;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::sync::{watch, Notify};
+
+use crate::resp::protocol::{serialize, RespType};
+use crate::resp::random::random_u64;
+
+pub mod backlog;
+pub mod master;
+pub mod replica;
+
+use backlog::ReplicationBacklog;
+
+pub const DEFAULT_BACKLOG_SIZE: usize = 1024 * 1024;
+
+/// Address of the primary a replica follows.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MasterAddr {
+    pub host: String,
+    pub port: u16,
+}
+
+/// State of the link a replica keeps to its primary.
+pub struct MasterLink {
+    pub addr: MasterAddr,
+    pub up: bool,
+    pub last_io: Instant,
+}
+
+/// A replica connected to this server.
+pub struct ReplicaHandle {
+    pub id: u64,
+    pub ip: String,
+    pub listening_port: u16,
+    /// Last offset acknowledged with `REPLCONF ACK`.
+    pub ack_offset: u64,
+    pub last_ack: Instant,
+    sender: UnboundedSender<Vec<u8>>,
+}
+
+/// Counters reported in the `stats` section of `INFO`.
+#[derive(Default)]
+pub struct SyncStats {
+    pub full: u64,
+    pub partial_ok: u64,
+    pub partial_err: u64,
+}
+
+pub struct ReplicationState {
+    replid: String,
+    /// Replication id of the primary we followed before being promoted, still accepted
+    /// for partial resyncs up to `second_replid_offset`.
+    replid2: String,
+    second_replid_offset: Option<u64>,
+    backlog: ReplicationBacklog,
+    master: Option<MasterLink>,
+    /// Primary the replication link should follow, watched by the link supervisor.
+    master_target: watch::Sender<Option<MasterAddr>>,
+    /// Whether our replid/offset describe a history worth continuing with `PSYNC`.
+    has_history: bool,
+    replicas: Vec<ReplicaHandle>,
+    next_replica_id: u64,
+    /// Woken whenever a replica acknowledges an offset, used by `WAIT`.
+    ack_notify: Arc<Notify>,
+    pub stats: SyncStats,
+    /// `replica-read-only`: reject writes from clients other than the primary.
+    pub read_only: bool,
+    /// `replica-serve-stale-data`: keep answering queries while the link is down.
+    pub serve_stale_data: bool,
+}
+
+impl Default for ReplicationState {
+    fn default() -> Self {
+        ReplicationState::new(DEFAULT_BACKLOG_SIZE, None)
+    }
+}
+
+impl ReplicationState {
+    pub fn new(backlog_size: usize, master: Option<MasterAddr>) -> Self {
+        let (master_target, _) = watch::channel(master.clone());
+        ReplicationState {
+            replid: generate_replid(),
+            replid2: "0".repeat(40),
+            second_replid_offset: None,
+            backlog: ReplicationBacklog::new(backlog_size),
+            master: master.map(|addr| MasterLink {
+                addr,
+                up: false,
+                last_io: Instant::now(),
+            }),
+            master_target,
+            has_history: false,
+            replicas: Vec::new(),
+            next_replica_id: 0,
+            ack_notify: Arc::new(Notify::new()),
+            stats: SyncStats::default(),
+            read_only: true,
+            serve_stale_data: true,
+        }
+    }
+
+    pub fn is_master(&self) -> bool {
+        self.master.is_none()
+    }
+
+    /// Whether this is a replica that lost the link to its primary (or never synced).
+    pub fn is_stale(&self) -> bool {
+        self.master.as_ref().is_some_and(|master| !master.up)
+    }
+
+    pub fn master(&self) -> Option<&MasterLink> {
+        self.master.as_ref()
+    }
+
+    pub fn replid(&self) -> &str {
+        &self.replid
+    }
+
+    pub fn offset(&self) -> u64 {
+        self.backlog.end_offset()
+    }
+
+    pub fn backlog(&self) -> &ReplicationBacklog {
+        &self.backlog
+    }
+
+    pub fn has_history(&self) -> bool {
+        self.has_history
+    }
+
+    /// Subscribes to changes of the primary this server should replicate from.
+    pub fn watch_master(&self) -> watch::Receiver<Option<MasterAddr>> {
+        self.master_target.subscribe()
+    }
+
+    /// Turns a replica into a primary (`REPLICAOF NO ONE`), keeping the dataset.
+    /// Replicas of the old primary can still continue with its replication id.
+    pub fn promote(&mut self) {
+        if self.master.take().is_none() {
+            return;
+        }
+        self.replid2 = std::mem::replace(&mut self.replid, generate_replid());
+        self.second_replid_offset = Some(self.offset() + 1);
+        self.has_history = true;
+        self.master_target.send_replace(None);
+        info!("Promoted to primary with replication id {}", self.replid);
+    }
+
+    /// Starts following a new primary (`REPLICAOF host port`).
+    /// Our own replicas are disconnected and have to resync against the new history.
+    pub fn follow(&mut self, addr: MasterAddr) {
+        if self.master.is_none() {
+            // The dataset we served as a primary is our history to continue from.
+            self.has_history = true;
+        }
+        self.replicas.clear();
+        self.master = Some(MasterLink {
+            addr: addr.clone(),
+            up: false,
+            last_io: Instant::now(),
+        });
+        self.master_target.send_replace(Some(addr));
+    }
+
+    pub fn replicas(&self) -> &[ReplicaHandle] {
+        &self.replicas
+    }
+
+    /// Appends raw bytes to the replication stream and forwards them to every replica.
+    pub fn feed(&mut self, data: &[u8]) {
+        self.backlog.feed(data);
+        // A failed send means the replica connection is gone; it unregisters itself.
+        for replica in &self.replicas {
+            let _ = replica.sender.send(data.to_vec());
+        }
+    }
+
+    /// Propagates a command that modified the dataset.
+    pub fn propagate(&mut self, command: &RespType) {
+        self.feed(&serialize(command));
+    }
+
+    /// Registers a replica that is in sync up to `offset` and whose stream starts with
+    /// `initial` (an RDB payload or the missing part of the backlog).
+    pub fn register_replica(
+        &mut self,
+        ip: String,
+        listening_port: u16,
+        offset: u64,
+        initial: Vec<u8>,
+    ) -> (u64, UnboundedReceiver<Vec<u8>>) {
+        let (sender, receiver) = unbounded_channel();
+        if !initial.is_empty() {
+            let _ = sender.send(initial);
+        }
+
+        let id = self.next_replica_id;
+        self.next_replica_id += 1;
+        info!("Replica {}:{} registered with id {}", ip, listening_port, id);
+
+        self.replicas.push(ReplicaHandle {
+            id,
+            ip,
+            listening_port,
+            ack_offset: offset,
+            last_ack: Instant::now(),
+            sender,
+        });
+        (id, receiver)
+    }
+
+    pub fn unregister_replica(&mut self, id: u64) {
+        self.replicas.retain(|replica| replica.id != id);
+    }
+
+    pub fn record_ack(&mut self, id: u64, offset: u64) {
+        if let Some(replica) = self.replicas.iter_mut().find(|replica| replica.id == id) {
+            replica.ack_offset = replica.ack_offset.max(offset);
+            replica.last_ack = Instant::now();
+        }
+        self.ack_notify.notify_waiters();
+    }
+
+    pub fn ack_notify(&self) -> Arc<Notify> {
+        Arc::clone(&self.ack_notify)
+    }
+
+    /// Number of replicas that acknowledged at least `offset`.
+    pub fn count_acked(&self, offset: u64) -> usize {
+        self.replicas
+            .iter()
+            .filter(|replica| replica.ack_offset >= offset)
+            .count()
+    }
+
+    /// Asks every replica to report its offset right away.
+    pub fn request_acks(&mut self) {
+        self.propagate(&RespType::Array(vec![
+            RespType::BulkString(Some("REPLCONF".to_string())),
+            RespType::BulkString(Some("GETACK".to_string())),
+            RespType::BulkString(Some("*".to_string())),
+        ]));
+    }
+
+    /// Returns the part of the stream a replica is missing if it can continue
+    /// from `offset` of the history identified by `replid`.
+    pub fn partial_resync(&self, replid: &str, offset: i64) -> Option<Vec<u8>> {
+        if offset < 0 {
+            return None;
+        }
+        let offset = offset as u64;
+        let known = replid == self.replid
+            || (replid == self.replid2
+                && self.second_replid_offset.is_some_and(|limit| offset <= limit));
+        if !known {
+            return None;
+        }
+        self.backlog.read_from(offset)
+    }
+
+    /// Adopts the history of the primary after a full resynchronization.
+    pub fn reset(&mut self, replid: String, offset: u64) {
+        self.replid = replid;
+        self.replid2 = "0".repeat(40);
+        self.second_replid_offset = None;
+        self.backlog.reset(offset);
+        self.has_history = true;
+    }
+
+    /// Adopts a new replication id announced by the primary with `+CONTINUE <replid>`,
+    /// which happens when it was promoted since we last synced.
+    pub fn set_replid(&mut self, replid: String) {
+        if replid != self.replid {
+            self.replid2 = std::mem::replace(&mut self.replid, replid);
+            self.second_replid_offset = Some(self.offset() + 1);
+        }
+    }
+
+    pub fn set_link_up(&mut self, up: bool) {
+        if let Some(master) = self.master.as_mut() {
+            master.up = up;
+            master.last_io = Instant::now();
+        }
+    }
+
+    pub fn touch_link(&mut self) {
+        if let Some(master) = self.master.as_mut() {
+            master.last_io = Instant::now();
+        }
+    }
+
+    /// Renders the `replication` section of `INFO`.
+    pub fn info(&self) -> String {
+        let mut lines = vec!["# Replication".to_string()];
+        match &self.master {
+            None => lines.push("role:master".to_string()),
+            Some(master) => {
+                lines.push("role:slave".to_string());
+                lines.push(format!("master_host:{}", master.addr.host));
+                lines.push(format!("master_port:{}", master.addr.port));
+                lines.push(format!(
+                    "master_link_status:{}",
+                    if master.up { "up" } else { "down" }
+                ));
+                lines.push(format!(
+                    "master_last_io_seconds_ago:{}",
+                    master.last_io.elapsed().as_secs()
+                ));
+                lines.push(format!("slave_repl_offset:{}", self.offset()));
+                lines.push(format!("slave_read_only:{}", self.read_only as u8));
+                lines.push(format!(
+                    "replica_serve_stale_data:{}",
+                    self.serve_stale_data as u8
+                ));
+            }
+        }
+
+        lines.push(format!("connected_slaves:{}", self.replicas.len()));
+        for (index, replica) in self.replicas.iter().enumerate() {
+            lines.push(format!(
+                "slave{}:ip={},port={},state=online,offset={},lag={}",
+                index,
+                replica.ip,
+                replica.listening_port,
+                replica.ack_offset,
+                replica.last_ack.elapsed().as_secs()
+            ));
+        }
+
+        lines.push(format!("master_replid:{}", self.replid));
+        lines.push(format!("master_replid2:{}", self.replid2));
+        lines.push(format!("master_repl_offset:{}", self.offset()));
+        lines.push(format!(
+            "second_repl_offset:{}",
+            self.second_replid_offset
+                .map_or("-1".to_string(), |offset| offset.to_string())
+        ));
+        lines.push("repl_backlog_active:1".to_string());
+        lines.push(format!("repl_backlog_size:{}", self.backlog.size()));
+        lines.push(format!(
+            "repl_backlog_first_byte_offset:{}",
+            self.backlog.first_byte_offset()
+        ));
+        lines.push(format!("repl_backlog_histlen:{}", self.backlog.histlen()));
+        lines.join("\r\n") + "\r\n"
+    }
+}
+
+/// Generates a random 40 characters long hexadecimal replication id.
+pub fn generate_replid() -> String {
+    let mut id = (0..3)
+        .map(|_| format!("{:016x}", random_u64()))
+        .collect::<String>();
+    id.truncate(40);
+    id
+}
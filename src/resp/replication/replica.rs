@@ -0,0 +1,181 @@
+use std::io::{Error, ErrorKind};
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::{error, info};
+use tokio::sync::Mutex;
+
+use crate::resp::client::ClientContext;
+use crate::resp::command_dispatcher::{parse_command, CommandDispatcher};
+use crate::resp::commands::command_frame;
+use crate::resp::connection::Connection;
+use crate::resp::protocol::RespType;
+use crate::resp::replication::MasterAddr;
+use crate::resp::state::default_server_state::DefaultServerState;
+use crate::resp::state::server_state::ServerState;
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const ACK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Follows the primary configured in the replication state, restarting the link
+/// whenever `REPLICAOF` points it somewhere else.
+pub async fn run_replication_link(
+    listening_port: u16,
+    dispatcher: Arc<CommandDispatcher>,
+    state: Arc<Mutex<DefaultServerState>>,
+) {
+    let mut target = state.lock().await.replication().watch_master();
+    loop {
+        let master = target.borrow_and_update().clone();
+        match master {
+            Some(master) => {
+                info!("Replicating from {}:{}", master.host, master.port);
+                tokio::select! {
+                    _ = run_master_link(master, listening_port, &dispatcher, &state) => {}
+                    changed = target.changed() => {
+                        if changed.is_err() {
+                            return;
+                        }
+                    }
+                }
+            }
+            None => {
+                if target.changed().await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Keeps the link to the primary alive, reconnecting after failures.
+/// Reconnections ask for a partial resync from the last processed offset.
+async fn run_master_link(
+    master: MasterAddr,
+    listening_port: u16,
+    dispatcher: &CommandDispatcher,
+    state: &Mutex<DefaultServerState>,
+) {
+    loop {
+        if let Err(e) = sync_with_master(&master, listening_port, dispatcher, state).await {
+            error!("Replication link to {}:{} failed: {}", master.host, master.port, e);
+        }
+        state.lock().await.replication().set_link_up(false);
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+async fn sync_with_master(
+    master: &MasterAddr,
+    listening_port: u16,
+    dispatcher: &CommandDispatcher,
+    state: &Mutex<DefaultServerState>,
+) -> Result<(), Error> {
+    let mut connection = Connection::connect(&master.host, master.port).await?;
+    info!("Connected to primary {}:{}", master.host, master.port);
+
+    request(&mut connection, &["PING"]).await?;
+    request(&mut connection, &["REPLCONF", "listening-port", &listening_port.to_string()]).await?;
+    request(&mut connection, &["REPLCONF", "capa", "psync2"]).await?;
+
+    let (replid, offset) = {
+        let mut guard = state.lock().await;
+        let replication = guard.replication();
+        if replication.has_history() {
+            (replication.replid().to_string(), (replication.offset() + 1).to_string())
+        } else {
+            ("?".to_string(), "-1".to_string())
+        }
+    };
+
+    let reply = request(&mut connection, &["PSYNC", &replid, &offset]).await?;
+    let parts = reply.split_whitespace().collect::<Vec<_>>();
+    match parts.as_slice() {
+        ["FULLRESYNC", replid, offset] => {
+            let offset = offset
+                .parse::<u64>()
+                .map_err(|_| Error::new(ErrorKind::InvalidData, "Invalid FULLRESYNC offset"))?;
+            let rdb = connection.read_rdb().await?;
+
+            let mut guard = state.lock().await;
+            guard
+                .load_rdb(&rdb)
+                .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+            guard.replication().reset(replid.to_string(), offset);
+            info!("Full resync with primary completed at offset {}", offset);
+        }
+        ["CONTINUE", rest @ ..] => {
+            if let Some(replid) = rest.first() {
+                state.lock().await.replication().set_replid(replid.to_string());
+            }
+            info!("Partial resync with primary accepted");
+        }
+        _ => {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Unexpected PSYNC reply: {}", reply),
+            ))
+        }
+    }
+
+    state.lock().await.replication().set_link_up(true);
+
+    let mut client = ClientContext::master_link();
+    let mut ack_interval = tokio::time::interval(ACK_INTERVAL);
+    loop {
+        tokio::select! {
+            frame = connection.read_frame() => {
+                let Some((frame, raw)) = frame? else { return Ok(()) };
+                let reply = {
+                    let mut guard = state.lock().await;
+                    let reply = match parse_command(frame) {
+                        Some((command_name, args)) => {
+                            let is_getack = command_name == "REPLCONF"
+                                && matches!(args.first(), Some(RespType::BulkString(Some(arg))) if arg.eq_ignore_ascii_case("GETACK"));
+                            match dispatcher.dispatch(&command_name, args, &mut guard, &mut client) {
+                                Ok(response) if is_getack => Some(response),
+                                Ok(_) => None,
+                                Err(e) => {
+                                    error!("Failed to apply command from primary: {}", e);
+                                    None
+                                }
+                            }
+                        }
+                        None => None,
+                    };
+
+                    // The offset counts every byte of the stream, including pings and GETACKs.
+                    let replication = guard.replication();
+                    replication.feed(&raw);
+                    replication.touch_link();
+                    reply
+                };
+
+                if let Some(reply) = reply {
+                    connection.write_frame(&reply).await?;
+                }
+            }
+            _ = ack_interval.tick() => {
+                let offset = state.lock().await.replication().offset().to_string();
+                connection.write_frame(&command_frame(&["REPLCONF", "ACK", &offset])).await?;
+            }
+        }
+    }
+}
+
+/// Sends a handshake command and returns the status line of the reply.
+async fn request(connection: &mut Connection, parts: &[&str]) -> Result<String, Error> {
+    connection.write_frame(&command_frame(parts)).await?;
+    match connection.read_frame().await? {
+        Some((RespType::SimpleString(reply), _)) => Ok(reply),
+        Some((RespType::Error(e), _)) => Err(Error::other(e)),
+        Some((other, _)) => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Unexpected reply from primary: {:?}", other),
+        )),
+        None => Err(Error::new(
+            ErrorKind::UnexpectedEof,
+            "Primary closed the connection during handshake",
+        )),
+    }
+}
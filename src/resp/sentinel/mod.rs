@@ -0,0 +1,251 @@
+//! Sentinel mode: monitors a primary and its replicas, agrees with the other sentinels
+//! that the primary is down and promotes one of the replicas.
+//!
+//! Sentinels are configured with the addresses of their peers and exchange their view of
+//! each monitored primary with `SENTINEL HELLO`, the configuration with the highest epoch wins.
+
+use std::time::{Duration, Instant};
+
+use crate::resp::replication::{generate_replid, MasterAddr};
+
+pub mod monitor;
+
+pub const DEFAULT_SENTINEL_PORT: u16 = 26379;
+
+/// A primary to monitor, as given by `--sentinel-monitor "<name> <host> <port> <quorum>"`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MonitorConfig {
+    pub name: String,
+    pub addr: MasterAddr,
+    pub quorum: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SentinelConfig {
+    pub monitors: Vec<MonitorConfig>,
+    pub peers: Vec<MasterAddr>,
+    pub down_after: Duration,
+    pub failover_timeout: Duration,
+}
+
+impl Default for SentinelConfig {
+    fn default() -> Self {
+        SentinelConfig {
+            monitors: Vec::new(),
+            peers: Vec::new(),
+            down_after: Duration::from_secs(30),
+            failover_timeout: Duration::from_secs(180),
+        }
+    }
+}
+
+/// A replica of a monitored primary, discovered through `INFO replication`.
+pub struct ReplicaInstance {
+    pub addr: MasterAddr,
+    pub last_ok_ping: Option<Instant>,
+}
+
+pub struct MonitoredMaster {
+    pub name: String,
+    pub addr: MasterAddr,
+    pub quorum: usize,
+    pub down_after: Duration,
+    pub failover_timeout: Duration,
+    /// Epoch of the failover that produced the current configuration.
+    pub config_epoch: u64,
+    pub last_ok_ping: Instant,
+    pub subjectively_down: bool,
+    pub objectively_down: bool,
+    pub replicas: Vec<ReplicaInstance>,
+    /// Sentinel we voted for as failover leader, and in which epoch.
+    pub leader: Option<String>,
+    pub leader_epoch: u64,
+    /// Last time a failover was attempted or a vote given to another sentinel.
+    pub failover_started: Option<Instant>,
+}
+
+impl MonitoredMaster {
+    fn new(config: &MonitorConfig, sentinel: &SentinelConfig) -> Self {
+        MonitoredMaster {
+            name: config.name.clone(),
+            addr: config.addr.clone(),
+            quorum: config.quorum,
+            down_after: sentinel.down_after,
+            failover_timeout: sentinel.failover_timeout,
+            config_epoch: 0,
+            last_ok_ping: Instant::now(),
+            subjectively_down: false,
+            objectively_down: false,
+            replicas: Vec::new(),
+            leader: None,
+            leader_epoch: 0,
+            failover_started: None,
+        }
+    }
+
+    /// Whether this sentinel may start a new failover attempt.
+    pub fn can_start_failover(&self) -> bool {
+        self.failover_started
+            .is_none_or(|started| started.elapsed() >= self.failover_timeout * 2)
+    }
+
+    pub fn add_replica(&mut self, addr: MasterAddr) {
+        if addr != self.addr && !self.replicas.iter().any(|replica| replica.addr == addr) {
+            self.replicas.push(ReplicaInstance {
+                addr,
+                last_ok_ping: None,
+            });
+        }
+    }
+
+    /// Switches to a new primary; the old one is kept as a replica so that it
+    /// gets reconfigured once it comes back.
+    pub fn switch_to(&mut self, addr: MasterAddr, config_epoch: u64) {
+        let old = std::mem::replace(&mut self.addr, addr.clone());
+        self.replicas.retain(|replica| replica.addr != addr);
+        self.add_replica(old);
+        self.config_epoch = config_epoch;
+        self.last_ok_ping = Instant::now();
+        self.subjectively_down = false;
+        self.objectively_down = false;
+    }
+
+    fn flags(&self) -> String {
+        let mut flags = vec!["master"];
+        if self.subjectively_down {
+            flags.push("s_down");
+        }
+        if self.objectively_down {
+            flags.push("o_down");
+        }
+        flags.join(",")
+    }
+}
+
+/// Another sentinel monitoring the same primaries.
+pub struct SentinelPeer {
+    pub addr: MasterAddr,
+    pub runid: Option<String>,
+    pub last_hello: Option<Instant>,
+}
+
+pub struct SentinelState {
+    pub myid: String,
+    pub addr: MasterAddr,
+    pub current_epoch: u64,
+    pub masters: Vec<MonitoredMaster>,
+    pub peers: Vec<SentinelPeer>,
+}
+
+impl SentinelState {
+    pub fn new(config: &SentinelConfig, addr: MasterAddr) -> Self {
+        SentinelState {
+            myid: generate_replid(),
+            addr,
+            current_epoch: 0,
+            masters: config
+                .monitors
+                .iter()
+                .map(|monitor| MonitoredMaster::new(monitor, config))
+                .collect(),
+            peers: config
+                .peers
+                .iter()
+                .map(|addr| SentinelPeer {
+                    addr: addr.clone(),
+                    runid: None,
+                    last_hello: None,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn master(&mut self, name: &str) -> Option<&mut MonitoredMaster> {
+        self.masters.iter_mut().find(|master| master.name == name)
+    }
+
+    /// Votes for `runid` as the leader of the failover in `epoch`, unless a vote was
+    /// already given in that epoch. Returns the leader we voted for and its epoch.
+    pub fn vote(&mut self, addr: &MasterAddr, epoch: u64, runid: &str) -> Option<(String, u64)> {
+        if epoch > self.current_epoch {
+            self.current_epoch = epoch;
+        }
+        let myid = self.myid.clone();
+        let master = self.masters.iter_mut().find(|master| &master.addr == addr)?;
+
+        if master.leader_epoch < epoch && self.current_epoch <= epoch {
+            master.leader = Some(runid.to_string());
+            master.leader_epoch = epoch;
+            // Give the elected sentinel time to finish before trying ourselves.
+            if runid != myid {
+                master.failover_started = Some(Instant::now());
+            }
+        }
+        master
+            .leader
+            .clone()
+            .map(|leader| (leader, master.leader_epoch))
+    }
+
+    /// Applies a configuration announced by a peer if it is newer than ours.
+    pub fn hello(&mut self, name: &str, addr: MasterAddr, config_epoch: u64, runid: &str, peer: &MasterAddr, epoch: u64) {
+        self.current_epoch = self.current_epoch.max(epoch);
+        if let Some(known) = self.peers.iter_mut().find(|known| &known.addr == peer) {
+            known.runid = Some(runid.to_string());
+            known.last_hello = Some(Instant::now());
+        }
+
+        if let Some(master) = self.master(name) {
+            if config_epoch > master.config_epoch {
+                if master.addr == addr {
+                    master.config_epoch = config_epoch;
+                } else {
+                    master.switch_to(addr, config_epoch);
+                }
+            }
+        }
+    }
+
+    /// Renders the `sentinel` section of `INFO`.
+    pub fn info(&self) -> String {
+        let mut lines = vec![
+            "# Sentinel".to_string(),
+            format!("sentinel_masters:{}", self.masters.len()),
+        ];
+        for (index, master) in self.masters.iter().enumerate() {
+            lines.push(format!(
+                "master{}:name={},status={},address={}:{},slaves={},sentinels={}",
+                index,
+                master.name,
+                if master.objectively_down { "odown" } else if master.subjectively_down { "sdown" } else { "ok" },
+                master.addr.host,
+                master.addr.port,
+                master.replicas.len(),
+                self.peers.len() + 1
+            ));
+        }
+        lines.join("\r\n") + "\r\n"
+    }
+
+    /// Field/value pairs describing a monitored primary, as in `SENTINEL MASTER`.
+    pub fn describe(&self, master: &MonitoredMaster) -> Vec<(String, String)> {
+        vec![
+            ("name".to_string(), master.name.clone()),
+            ("ip".to_string(), master.addr.host.clone()),
+            ("port".to_string(), master.addr.port.to_string()),
+            ("flags".to_string(), master.flags()),
+            ("quorum".to_string(), master.quorum.to_string()),
+            ("num-slaves".to_string(), master.replicas.len().to_string()),
+            ("num-other-sentinels".to_string(), self.peers.len().to_string()),
+            ("config-epoch".to_string(), master.config_epoch.to_string()),
+            (
+                "down-after-milliseconds".to_string(),
+                master.down_after.as_millis().to_string(),
+            ),
+            (
+                "failover-timeout".to_string(),
+                master.failover_timeout.as_millis().to_string(),
+            ),
+        ]
+    }
+}
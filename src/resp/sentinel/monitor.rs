@@ -0,0 +1,406 @@
+use std::io::Error;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use log::{info, warn};
+use tokio::sync::Mutex;
+
+use crate::resp::connection::request;
+use crate::resp::protocol::RespType;
+use crate::resp::random::random_below;
+use crate::resp::replication::MasterAddr;
+use crate::resp::sentinel::SentinelState;
+use crate::resp::state::default_server_state::DefaultServerState;
+use crate::resp::state::server_state::ServerState;
+
+const PING_PERIOD: Duration = Duration::from_millis(250);
+const INFO_PERIOD: Duration = Duration::from_secs(1);
+const HELLO_PERIOD: Duration = Duration::from_secs(1);
+const QUERY_TIMEOUT: Duration = Duration::from_millis(500);
+/// Upper bound of the random delay before asking for votes, to avoid split elections.
+const MAX_ELECTION_DELAY_MS: u64 = 500;
+
+/// Starts monitoring every configured primary and exchanging hellos with the other sentinels.
+pub async fn run_sentinel(state: Arc<Mutex<DefaultServerState>>) {
+    let names = match state.lock().await.sentinel() {
+        Some(sentinel) => sentinel
+            .masters
+            .iter()
+            .map(|master| master.name.clone())
+            .collect::<Vec<_>>(),
+        None => return,
+    };
+
+    for name in names {
+        tokio::spawn(monitor_master(name, Arc::clone(&state)));
+    }
+    send_hellos(state).await;
+}
+
+async fn query(addr: &MasterAddr, parts: &[&str]) -> Result<RespType, Error> {
+    request(addr, parts, QUERY_TIMEOUT).await
+}
+
+/// Parsed `INFO replication` of a monitored instance.
+#[derive(Debug, Default, PartialEq)]
+pub struct InstanceInfo {
+    pub is_master: bool,
+    pub master: Option<MasterAddr>,
+    pub offset: u64,
+    pub replicas: Vec<MasterAddr>,
+}
+
+pub fn parse_info(info: &str) -> InstanceInfo {
+    let mut parsed = InstanceInfo::default();
+    let mut master_host = None;
+    let mut master_port = None;
+
+    for line in info.lines() {
+        let Some((field, value)) = line.split_once(':') else { continue };
+        match field {
+            "role" => parsed.is_master = value == "master",
+            "master_host" => master_host = Some(value.to_string()),
+            "master_port" => master_port = value.parse().ok(),
+            "slave_repl_offset" | "master_repl_offset" => {
+                parsed.offset = parsed.offset.max(value.parse().unwrap_or_default());
+            }
+            _ if field.starts_with("slave") && value.starts_with("ip=") => {
+                let mut ip = None;
+                let mut port = None;
+                for pair in value.split(',') {
+                    match pair.split_once('=') {
+                        Some(("ip", value)) => ip = Some(value.to_string()),
+                        Some(("port", value)) => port = value.parse().ok(),
+                        _ => {}
+                    }
+                }
+                if let (Some(host), Some(port)) = (ip, port) {
+                    parsed.replicas.push(MasterAddr { host, port });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let (Some(host), Some(port)) = (master_host, master_port) {
+        parsed.master = Some(MasterAddr { host, port });
+    }
+    parsed
+}
+
+async fn instance_info(addr: &MasterAddr) -> Option<InstanceInfo> {
+    match query(addr, &["INFO", "replication"]).await {
+        Ok(RespType::BulkString(Some(info))) => Some(parse_info(&info)),
+        _ => None,
+    }
+}
+
+async fn ping(addr: &MasterAddr) -> bool {
+    matches!(query(addr, &["PING"]).await, Ok(RespType::SimpleString(_)))
+}
+
+async fn monitor_master(name: String, state: Arc<Mutex<DefaultServerState>>) {
+    let mut interval = tokio::time::interval(PING_PERIOD);
+    let mut last_info: Option<Instant> = None;
+
+    loop {
+        interval.tick().await;
+        let Some(addr) = with_sentinel(&state, |sentinel| {
+            sentinel.master(&name).map(|master| master.addr.clone())
+        })
+        .await
+        .flatten() else {
+            return;
+        };
+
+        if ping(&addr).await {
+            with_sentinel(&state, |sentinel| {
+                if let Some(master) = sentinel.master(&name) {
+                    if master.addr == addr {
+                        master.last_ok_ping = Instant::now();
+                    }
+                }
+            })
+            .await;
+        }
+
+        if last_info.is_none_or(|last| last.elapsed() >= INFO_PERIOD) {
+            last_info = Some(Instant::now());
+            refresh_topology(&name, &addr, &state).await;
+        }
+
+        check_down(&name, &addr, &state).await;
+    }
+}
+
+/// Discovers replicas of the primary and turns known instances that claim to be primaries,
+/// such as an old primary coming back after a failover, into its replicas.
+async fn refresh_topology(name: &str, addr: &MasterAddr, state: &Mutex<DefaultServerState>) {
+    let Some(info) = instance_info(addr).await else {
+        return;
+    };
+    if !info.is_master {
+        return;
+    }
+    with_sentinel(state, |sentinel| {
+        if let Some(master) = sentinel.master(name) {
+            for replica in info.replicas {
+                master.add_replica(replica);
+            }
+        }
+    })
+    .await;
+
+    let replicas = with_sentinel(state, |sentinel| {
+        sentinel
+            .master(name)
+            .map(|master| master.replicas.iter().map(|replica| replica.addr.clone()).collect::<Vec<_>>())
+    })
+    .await
+    .flatten()
+    .unwrap_or_default();
+
+    for replica in replicas {
+        let Some(info) = instance_info(&replica).await else { continue };
+        with_sentinel(state, |sentinel| {
+            if let Some(known) = sentinel
+                .master(name)
+                .and_then(|master| master.replicas.iter_mut().find(|known| known.addr == replica))
+            {
+                known.last_ok_ping = Some(Instant::now());
+            }
+        })
+        .await;
+
+        // Only touched while our primary answers, so that a failover announced by
+        // another sentinel is never undone before its hello reaches us.
+        if info.is_master {
+            info!("Reconfiguring {}:{} as replica of {}:{}", replica.host, replica.port, addr.host, addr.port);
+            let port = addr.port.to_string();
+            if let Err(e) = query(&replica, &["REPLICAOF", &addr.host, &port]).await {
+                warn!("Failed to reconfigure {}:{}: {}", replica.host, replica.port, e);
+            }
+        }
+    }
+}
+
+/// Updates the subjective and objective down state and starts a failover when needed.
+async fn check_down(name: &str, addr: &MasterAddr, state: &Mutex<DefaultServerState>) {
+    let Some((sdown, quorum, peers, epoch, can_failover)) = with_sentinel(state, |sentinel| {
+        let peers = sentinel.peers.iter().map(|peer| peer.addr.clone()).collect::<Vec<_>>();
+        let epoch = sentinel.current_epoch;
+        sentinel.master(name).map(|master| {
+            master.subjectively_down = master.last_ok_ping.elapsed() > master.down_after;
+            if !master.subjectively_down {
+                master.objectively_down = false;
+            }
+            (master.subjectively_down, master.quorum, peers, epoch, master.can_start_failover())
+        })
+    })
+    .await
+    .flatten() else {
+        return;
+    };
+
+    if !sdown {
+        return;
+    }
+
+    let port = addr.port.to_string();
+    let epoch = epoch.to_string();
+    let mut down_votes = 1;
+    for peer in &peers {
+        let reply = query(peer, &["SENTINEL", "is-master-down-by-addr", &addr.host, &port, &epoch, "*"]).await;
+        if let Ok(RespType::Array(reply)) = reply {
+            if reply.first() == Some(&RespType::Integer(1)) {
+                down_votes += 1;
+            }
+        }
+    }
+
+    let odown = down_votes >= quorum;
+    with_sentinel(state, |sentinel| {
+        if let Some(master) = sentinel.master(name) {
+            if !master.objectively_down && odown {
+                warn!("Primary {} is objectively down ({} votes)", name, down_votes);
+            }
+            master.objectively_down = odown;
+        }
+    })
+    .await;
+
+    if odown && can_failover {
+        start_failover(name, addr, quorum, &peers, state).await;
+    }
+}
+
+/// Asks the other sentinels to elect us as leader and performs the failover if they do.
+async fn start_failover(
+    name: &str,
+    addr: &MasterAddr,
+    quorum: usize,
+    peers: &[MasterAddr],
+    state: &Mutex<DefaultServerState>,
+) {
+    tokio::time::sleep(Duration::from_millis(random_below(MAX_ELECTION_DELAY_MS))).await;
+
+    // A vote for another sentinel may have arrived while we were waiting.
+    let Some((epoch, myid)) = with_sentinel(state, |sentinel| {
+        let can_failover = sentinel.master(name).is_some_and(|master| master.can_start_failover());
+        if !can_failover {
+            return None;
+        }
+        sentinel.current_epoch += 1;
+        let epoch = sentinel.current_epoch;
+        let myid = sentinel.myid.clone();
+        sentinel.vote(addr, epoch, &myid);
+        if let Some(master) = sentinel.master(name) {
+            master.failover_started = Some(Instant::now());
+        }
+        Some((epoch, myid))
+    })
+    .await
+    .flatten() else {
+        return;
+    };
+
+    info!("Starting leader election for {} in epoch {}", name, epoch);
+    let port = addr.port.to_string();
+    let epoch_arg = epoch.to_string();
+    let mut votes = 1;
+    for peer in peers {
+        let reply = query(peer, &["SENTINEL", "is-master-down-by-addr", &addr.host, &port, &epoch_arg, &myid]).await;
+        if let Ok(RespType::Array(reply)) = reply {
+            if reply.get(1) == Some(&RespType::BulkString(Some(myid.clone())))
+                && reply.get(2) == Some(&RespType::Integer(epoch as i64))
+            {
+                votes += 1;
+            }
+        }
+    }
+
+    let sentinels = peers.len() + 1;
+    let needed = quorum.max(sentinels / 2 + 1);
+    if votes < needed {
+        info!("Not elected as leader for {} ({} of {} votes)", name, votes, needed);
+        return;
+    }
+
+    info!("Elected leader for {} with {} votes, failing over", name, votes);
+    failover(name, addr, epoch, state).await;
+}
+
+/// Promotes the most up to date replica and points the others to it.
+async fn failover(name: &str, addr: &MasterAddr, epoch: u64, state: &Mutex<DefaultServerState>) {
+    let replicas = with_sentinel(state, |sentinel| {
+        sentinel
+            .master(name)
+            .map(|master| master.replicas.iter().map(|replica| replica.addr.clone()).collect::<Vec<_>>())
+    })
+    .await
+    .flatten()
+    .unwrap_or_default();
+
+    let mut best: Option<(u64, MasterAddr)> = None;
+    for replica in &replicas {
+        let Some(info) = instance_info(replica).await else { continue };
+        if info.is_master {
+            continue;
+        }
+        let better = match &best {
+            None => true,
+            Some((offset, current)) => {
+                info.offset > *offset
+                    || (info.offset == *offset && (replica.host.as_str(), replica.port) < (current.host.as_str(), current.port))
+            }
+        };
+        if better {
+            best = Some((info.offset, replica.clone()));
+        }
+    }
+
+    let Some((_, promoted)) = best else {
+        warn!("No replica of {} can be promoted", name);
+        return;
+    };
+
+    info!("Promoting {}:{} to primary of {}", promoted.host, promoted.port, name);
+    if let Err(e) = query(&promoted, &["REPLICAOF", "NO", "ONE"]).await {
+        warn!("Failed to promote {}:{}: {}", promoted.host, promoted.port, e);
+        return;
+    }
+    if !instance_info(&promoted).await.is_some_and(|info| info.is_master) {
+        warn!("{}:{} did not turn into a primary", promoted.host, promoted.port);
+        return;
+    }
+
+    let port = promoted.port.to_string();
+    for replica in replicas.iter().filter(|replica| **replica != promoted && *replica != addr) {
+        if let Err(e) = query(replica, &["REPLICAOF", &promoted.host, &port]).await {
+            warn!("Failed to reconfigure {}:{}: {}", replica.host, replica.port, e);
+        }
+    }
+
+    with_sentinel(state, |sentinel| {
+        if let Some(master) = sentinel.master(name) {
+            master.switch_to(promoted.clone(), epoch);
+        }
+    })
+    .await;
+    info!("Failover of {} to {}:{} completed", name, promoted.host, promoted.port);
+
+    broadcast_hello(state).await;
+}
+
+async fn send_hellos(state: Arc<Mutex<DefaultServerState>>) {
+    let mut interval = tokio::time::interval(HELLO_PERIOD);
+    loop {
+        interval.tick().await;
+        broadcast_hello(&state).await;
+    }
+}
+
+/// Announces our configuration of every primary to the other sentinels.
+async fn broadcast_hello(state: &Mutex<DefaultServerState>) {
+    let Some((hellos, peers)) = with_sentinel(state, |sentinel| {
+        let hellos = sentinel
+            .masters
+            .iter()
+            .map(|master| {
+                vec![
+                    "SENTINEL".to_string(),
+                    "HELLO".to_string(),
+                    master.name.clone(),
+                    master.addr.host.clone(),
+                    master.addr.port.to_string(),
+                    master.config_epoch.to_string(),
+                    sentinel.myid.clone(),
+                    sentinel.addr.host.clone(),
+                    sentinel.addr.port.to_string(),
+                    sentinel.current_epoch.to_string(),
+                ]
+            })
+            .collect::<Vec<_>>();
+        let peers = sentinel.peers.iter().map(|peer| peer.addr.clone()).collect::<Vec<_>>();
+        (hellos, peers)
+    })
+    .await
+    else {
+        return;
+    };
+
+    for peer in &peers {
+        for hello in &hellos {
+            let parts = hello.iter().map(String::as_str).collect::<Vec<_>>();
+            let _ = query(peer, &parts).await;
+        }
+    }
+}
+
+async fn with_sentinel<T>(
+    state: &Mutex<DefaultServerState>,
+    f: impl FnOnce(&mut SentinelState) -> T,
+) -> Option<T> {
+    let mut guard = state.lock().await;
+    guard.sentinel().map(f)
+}
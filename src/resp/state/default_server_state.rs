@@ -1,52 +1,188 @@
+use crate::resp::blocking::BlockedClients;
+use crate::resp::cluster::ClusterState;
+use crate::resp::commands::current_time_ms;
 use crate::resp::protocol::RespType;
+use crate::resp::rdb::{self, RdbEntry};
+use crate::resp::replication::ReplicationState;
+use crate::resp::sentinel::SentinelState;
+use crate::resp::state::sampled_map::SampledMap;
 use crate::resp::state::server_state::ServerState;
-use log::{info};
+use crate::resp::state::value::{Value, MAX_STRING_BYTES, WRONG_TYPE};
+use log::{debug, info};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
 
-// TODO: handle active expiration
+/// Keys with a TTL checked at a time by active expiration, like Redis'
+/// `ACTIVE_EXPIRE_CYCLE_KEYS_PER_LOOP`.
+const EXPIRE_SAMPLE_SIZE: usize = 20;
+/// Longest an active expiration cycle may run, a quarter of the cycle period like Redis.
+const EXPIRE_CYCLE_BUDGET: Duration = Duration::from_millis(25);
+
+#[derive(Default)]
 pub struct DefaultServerState {
     // This is a placeholder for the actual server state implementation.
     // In a real application, this would manage the data store.
-    data: std::collections::HashMap<String, RespType>,
+    data: std::collections::HashMap<String, Value>,
+
+    expires: SampledMap<u64>, // Placeholder for expiration times
+
+    /// Expiration times of hash fields, by key and then by field.
+    field_expires: SampledMap<HashMap<String, u64>>,
+
+    replication: ReplicationState,
 
-    expires: std::collections::HashMap<String, u64>, // Placeholder for expiration times
+    sentinel: Option<SentinelState>,
+
+    cluster: Option<ClusterState>,
+
+    blocked_clients: BlockedClients,
 }
 
-impl Default for DefaultServerState {
-    fn default() -> Self {
+impl DefaultServerState {
+    pub fn with_replication(replication: ReplicationState) -> Self {
         DefaultServerState {
-            data: std::collections::HashMap::new(),
-            expires: std::collections::HashMap::new(),
+            replication,
+            ..Default::default()
         }
     }
-}
 
-impl ServerState for DefaultServerState {
-    fn get(&mut self, key: &str) -> Option<RespType> {
-        info!("Getting key: {}", key);
-        if let Some(expiration) = self.expires.get(key) {
-            let current_time = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .map_err(|_| "Failed to get current time".to_string())
-                .ok()?
-                .as_millis() as u64;
+    pub fn with_sentinel(sentinel: SentinelState) -> Self {
+        DefaultServerState {
+            sentinel: Some(sentinel),
+            ..Default::default()
+        }
+    }
 
-            info!(
-                "Checking expiration for key: {}, Expiration: {}, Current Time: {}",
-                key, expiration, current_time
-            );
+    /// Enables cluster mode.
+    pub fn with_cluster(self, cluster: ClusterState) -> Self {
+        DefaultServerState {
+            cluster: Some(cluster),
+            ..self
+        }
+    }
+}
 
-            if *expiration <= current_time {
-                self.data.remove(key);
-                self.expires.remove(key);
-                return None;
+impl DefaultServerState {
+    /// Clients blocked on keys until a write makes data available.
+    pub fn blocked_clients(&mut self) -> &mut BlockedClients {
+        &mut self.blocked_clients
+    }
+
+    /// Removes expired keys and hash fields, including the ones no client accesses anymore.
+    /// Like Redis' `activeExpireCycle`, this checks random samples of the keys with a TTL and
+    /// samples again only while more than a tenth of them had expired, so a cycle stays short
+    /// however many keys have a TTL.
+    pub fn active_expire(&mut self) {
+        let started = Instant::now();
+        loop {
+            let current_time = current_time_ms();
+            let is_expired = |expiration: &u64| *expiration <= current_time;
+            let sampled = self
+                .expires
+                .sample(EXPIRE_SAMPLE_SIZE)
+                .map(|(key, expiration)| (key.clone(), is_expired(expiration)))
+                .chain(
+                    self.field_expires
+                        .sample(EXPIRE_SAMPLE_SIZE)
+                        .map(|(key, fields)| (key.clone(), fields.values().any(is_expired))),
+                )
+                .collect::<Vec<_>>();
+            let expired = sampled.iter().filter(|(_, expired)| *expired).collect::<Vec<_>>();
+            for (key, _) in &expired {
+                self.expire_if_needed(key);
+            }
+            if expired.len() * 10 <= sampled.len() || started.elapsed() >= EXPIRE_CYCLE_BUDGET {
+                return;
             }
         }
+    }
+
+    /// Removes the key if its expiration time has passed, and the expired fields of a hash.
+    /// Returns whether the key was removed.
+    fn expire_if_needed(&mut self, key: &str) -> bool {
+        if self.expire_fields(key) {
+            return true;
+        }
+        let Some(expiration) = self.expires.get(key) else {
+            return false;
+        };
+        let current_time = current_time_ms();
 
-        self.data.get(key).cloned()
+        debug!(
+            "Checking expiration for key: {}, Expiration: {}, Current Time: {}",
+            key, expiration, current_time
+        );
+
+        if *expiration <= current_time {
+            self.data.remove(key);
+            self.expires.remove(key);
+            self.field_expires.remove(key);
+            return true;
+        }
+        false
+    }
+
+    /// Removes the expired fields of the hash at `key`, and the hash itself once empty.
+    /// Returns whether the key was removed.
+    fn expire_fields(&mut self, key: &str) -> bool {
+        let Some(fields) = self.field_expires.get_mut(key) else {
+            return false;
+        };
+        let current_time = current_time_ms();
+        let expired = fields
+            .iter()
+            .filter(|(_, expiration)| **expiration <= current_time)
+            .map(|(field, _)| field.clone())
+            .collect::<Vec<_>>();
+        if expired.is_empty() {
+            return false;
+        }
+        fields.retain(|_, expiration| *expiration > current_time);
+        if fields.is_empty() {
+            self.field_expires.remove(key);
+        }
+
+        let Some(Value::Hash(hash)) = self.data.get_mut(key) else {
+            return false;
+        };
+        debug!("Expiring {} field(s) of hash: {}", expired.len(), key);
+        for field in &expired {
+            hash.remove(field);
+        }
+        if hash.is_empty() {
+            self.data.remove(key);
+            self.expires.remove(key);
+            self.field_expires.remove(key);
+            return true;
+        }
+        false
     }
 
-    fn set(&mut self, key: String, value: RespType, ttl: Option<i64>) -> Result<(), String> {
+    fn string_mut(&mut self, key: &str) -> Result<Option<&mut Vec<u8>>, String> {
+        self.expire_if_needed(key);
+        match self.data.get_mut(key) {
+            Some(Value::String(value)) => Ok(Some(value)),
+            Some(_) => Err(WRONG_TYPE.to_string()),
+            None => Ok(None),
+        }
+    }
+}
+
+impl ServerState for DefaultServerState {
+    fn get(&mut self, key: &str) -> Option<&Value> {
+        info!("Getting key: {}", key);
+        self.expire_if_needed(key);
+        self.data.get(key)
+    }
+
+    fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        self.expire_if_needed(key);
+        self.data.get_mut(key)
+    }
+
+    fn set(&mut self, key: String, value: Value, ttl: Option<i64>) -> Result<(), String> {
         self.data.insert(key.clone(), value);
+        self.field_expires.remove(&key);
         info!("Setting key: {}, value: {:?}", key, self.data.get(&key));
         if let Some(milliseconds) = ttl {
             if milliseconds < 0 {
@@ -65,21 +201,28 @@ impl ServerState for DefaultServerState {
 
             self.expires
                 .insert(key, current_time + milliseconds as u64);
+        } else {
+            self.expires.remove(&key);
         }
         Ok(())
     }
 
     fn del(&mut self, key: &str) -> Result<(), String> {
         self.data.remove(key);
+        self.expires.remove(key);
+        self.field_expires.remove(key);
         Ok(())
     }
 
     fn exists(&mut self, key: &str) -> bool {
+        self.expire_if_needed(key);
         self.data.contains_key(key)
     }
 
     fn flush(&mut self) -> Result<(), String> {
         self.data.clear();
+        self.expires.clear();
+        self.field_expires.clear();
         Ok(())
     }
 
@@ -87,7 +230,7 @@ impl ServerState for DefaultServerState {
         self.data.keys().cloned().collect()
     }
 
-    fn get_all(&mut self) -> Vec<(String, RespType)> {
+    fn get_all(&mut self) -> Vec<(String, Value)> {
         self.data
             .iter()
             .map(|(k, v)| (k.clone(), v.clone()))
@@ -95,25 +238,32 @@ impl ServerState for DefaultServerState {
     }
 
     fn incr(&mut self, key: &str) -> Result<i64, String> {
-        if let Some(RespType::Integer(value)) = self.data.get(key) {
-            let new_value = value + 1;
-            self.data
-                .insert(key.to_string(), RespType::Integer(new_value));
-            Ok(new_value)
-        } else {
-            Err("Key does not exist or is not an integer".to_string())
-        }
+        self.incr_by(key, 1)
     }
 
     fn decr(&mut self, key: &str) -> Result<i64, String> {
-        if let Some(RespType::Integer(value)) = self.data.get(key) {
-            let new_value = value - 1;
-            self.data
-                .insert(key.to_string(), RespType::Integer(new_value));
-            Ok(new_value)
-        } else {
-            Err("Key does not exist or is not an integer".to_string())
+        self.incr_by(key, -1)
+    }
+
+    fn incr_by(&mut self, key: &str, delta: i64) -> Result<i64, String> {
+        // Like Redis, only the canonical form of an integer is accepted, without `+` or zero padding.
+        let value = match self.string_mut(key)? {
+            Some(value) => std::str::from_utf8(value)
+                .ok()
+                .and_then(|value| value.parse::<i64>().ok().filter(|parsed| parsed.to_string() == value))
+                .ok_or_else(|| "value is not an integer or out of range".to_string())?,
+            None => 0,
+        };
+        let new_value = value
+            .checked_add(delta)
+            .ok_or_else(|| "increment or decrement would overflow".to_string())?;
+        match self.string_mut(key)? {
+            Some(value) => *value = new_value.to_string().into_bytes(),
+            None => {
+                self.data.insert(key.to_string(), Value::String(new_value.to_string().into_bytes()));
+            }
         }
+        Ok(new_value)
     }
 
     fn expire(&mut self, key: &str, _seconds: u64) -> Result<(), String> {
@@ -149,6 +299,31 @@ impl ServerState for DefaultServerState {
         }
     }
 
+    fn expires_at(&mut self, key: &str) -> Option<u64> {
+        self.expires.get(key).copied()
+    }
+
+    fn field_expires_at(&mut self, key: &str, field: &str) -> Option<u64> {
+        self.expire_if_needed(key);
+        self.field_expires.get(key).and_then(|fields| fields.get(field)).copied()
+    }
+
+    fn set_field_expires_at(&mut self, key: &str, field: &str, expires_at: Option<u64>) {
+        match expires_at {
+            Some(expires_at) => {
+                self.field_expires.get_or_default(key).insert(field.to_string(), expires_at);
+            }
+            None => {
+                if let Some(fields) = self.field_expires.get_mut(key) {
+                    fields.remove(field);
+                    if fields.is_empty() {
+                        self.field_expires.remove(key);
+                    }
+                }
+            }
+        }
+    }
+
     fn persist(&mut self, key: &str) -> Result<(), String> {
         if self.data.contains_key(key) {
             self.expires.remove(key);
@@ -158,23 +333,17 @@ impl ServerState for DefaultServerState {
         }
     }
 
-    fn type_of(&mut self, key: &str) -> Result<RespType, String> {
-        if let Some(value) = self.data.get(key) {
-            match value {
-                RespType::SimpleString(_) => Ok(RespType::SimpleString("string".to_string())),
-                RespType::Integer(_) => Ok(RespType::SimpleString("integer".to_string())),
-                RespType::BulkString(_) => Ok(RespType::SimpleString("bulk_string".to_string())),
-                RespType::Array(_) => Ok(RespType::SimpleString("array".to_string())),
-                RespType::Error(_) => Ok(RespType::SimpleString("error".to_string())),
-            }
-        } else {
-            Err("Key does not exist".to_string())
-        }
+    fn type_of(&mut self, key: &str) -> &'static str {
+        self.get(key).map(Value::type_name).unwrap_or("none")
     }
 
     fn rename(&mut self, old_key: &str, new_key: &str) -> Result<(), String> {
         if let Some(value) = self.data.remove(old_key) {
             self.data.insert(new_key.to_string(), value);
+            match self.field_expires.remove(old_key) {
+                Some(fields) => self.field_expires.insert(new_key.to_string(), fields),
+                None => self.field_expires.remove(new_key),
+            };
             Ok(())
         } else {
             Err("Key does not exist".to_string())
@@ -188,89 +357,134 @@ impl ServerState for DefaultServerState {
             }
             let value = self.data.remove(old_key).unwrap();
             self.data.insert(new_key.to_string(), value);
+            if let Some(fields) = self.field_expires.remove(old_key) {
+                self.field_expires.insert(new_key.to_string(), fields);
+            }
             Ok(())
         } else {
             Err("Old key does not exist".to_string())
         }
     }
 
-    fn append(&mut self, key: &str, value: &str) -> Result<RespType, String> {
-        match self.data.get_mut(key) {
-            Some(RespType::BulkString(existing_value)) => {
-                let new_value = match existing_value {
-                    Some(s) => {
-                        s.push_str(value);
-                        s.clone()
-                    }
-                    None => value.to_string(),
-                };
-                *existing_value = Some(new_value.clone());
-                Ok(RespType::Integer(new_value.len() as i64))
+    fn append(&mut self, key: &str, value: &[u8]) -> Result<RespType, String> {
+        match self.string_mut(key)? {
+            Some(existing_value) => {
+                existing_value.extend_from_slice(value);
+                Ok(RespType::Integer(existing_value.len() as i64))
             }
             None => {
-                self.data.insert(
-                    key.to_string(),
-                    RespType::BulkString(Some(value.to_string())),
-                );
+                self.data.insert(key.to_string(), Value::String(value.to_vec()));
                 Ok(RespType::Integer(value.len() as i64))
             }
-            _ => Err("Key exists but is not a bulk string".to_string()),
         }
     }
 
     fn get_range(&mut self, key: &str, start: i64, end: i64) -> Result<RespType, String> {
-        if let Some(RespType::BulkString(Some(value))) = self.data.get(key) {
-            let start = start.max(0) as usize;
-            let end = end.max(0) as usize;
-            let range_value = &value[start..end.min(value.len())];
-            Ok(RespType::BulkString(Some(range_value.to_string())))
-        } else {
-            Err("Key does not exist or is not a bulk string".to_string())
+        let Some(value) = self.string_mut(key)? else {
+            return Ok(RespType::bulk_bytes(Vec::new()));
+        };
+        if start < 0 && end < 0 && start > end {
+            return Ok(RespType::bulk_bytes(Vec::new()));
+        }
+        let len = value.len() as i64;
+        let start = if start < 0 { (len + start).max(0) } else { start };
+        let end = if end < 0 { (len + end).max(0) } else { end.min(len - 1) };
+        if start > end || len == 0 {
+            return Ok(RespType::bulk_bytes(Vec::new()));
         }
+        Ok(RespType::bulk_bytes(value[start as usize..=end as usize].to_vec()))
     }
 
-    fn set_range(&mut self, key: &str, offset: i64, value: &str) -> Result<RespType, String> {
+    fn set_range(&mut self, key: &str, offset: i64, value: &[u8]) -> Result<RespType, String> {
         if offset < 0 {
-            return Err("Offset is out of range".to_string());
+            return Err("offset is out of range".to_string());
         }
         let offset = offset as usize;
-        let new_value = match self.data.get_mut(key) {
-            Some(RespType::BulkString(existing_value)) => {
-                let mut s = existing_value.clone().unwrap_or_default();
-                if offset > s.len() {
-                    s.push_str(&" ".repeat(offset - s.len()));
-                }
+        let existing = self.string_mut(key)?;
+        // Empty values neither create nor pad the string.
+        if value.is_empty() {
+            return Ok(RespType::Integer(existing.map_or(0, |existing| existing.len()) as i64));
+        }
+        if offset.saturating_add(value.len()) as u64 > MAX_STRING_BYTES {
+            return Err("string exceeds maximum allowed size (proto-max-bulk-len)".to_string());
+        }
+        let len = match existing {
+            Some(s) => {
                 if offset + value.len() > s.len() {
-                    s.push_str(&" ".repeat(offset + value.len() - s.len()));
+                    s.resize(offset + value.len(), 0);
                 }
-                s.replace_range(offset..offset + value.len(), value);
-                *existing_value = Some(s.clone());
-                s
+                s[offset..offset + value.len()].copy_from_slice(value);
+                s.len()
             }
             None => {
-                let mut s = String::new();
-                if offset > 0 {
-                    s.push_str(&" ".repeat(offset));
-                }
-                s.push_str(value);
-                self.data
-                    .insert(key.to_string(), RespType::BulkString(Some(s.clone())));
-                s
+                let mut s = vec![0; offset];
+                s.extend_from_slice(value);
+                let len = s.len();
+                self.data.insert(key.to_string(), Value::String(s));
+                len
             }
-            _ => return Err("Key exists but is not a bulk string".to_string()),
         };
-        Ok(RespType::Integer(new_value.len() as i64))
+        Ok(RespType::Integer(len as i64))
     }
 
-    fn get_set(&mut self, key: &str, value: &str) -> Result<RespType, String> {
-        if let Some(existing_value) = self.data.remove(key) {
-            self.data.insert(
-                key.to_string(),
-                RespType::BulkString(Some(value.to_string())),
-            );
-            Ok(existing_value)
-        } else {
-            Err("Key does not exist".to_string())
+    fn get_set(&mut self, key: &str, value: &[u8]) -> Result<RespType, String> {
+        let old = match self.string_mut(key)? {
+            Some(existing_value) => Some(std::mem::replace(existing_value, value.to_vec())),
+            None => {
+                self.data.insert(key.to_string(), Value::String(value.to_vec()));
+                None
+            }
+        };
+        self.expires.remove(key);
+        Ok(old.map_or(RespType::BulkString(None), RespType::bulk_bytes))
+    }
+
+    fn replication(&mut self) -> &mut ReplicationState {
+        &mut self.replication
+    }
+
+    fn sentinel(&mut self) -> Option<&mut SentinelState> {
+        self.sentinel.as_mut()
+    }
+
+    fn cluster(&mut self) -> Option<&mut ClusterState> {
+        self.cluster.as_mut()
+    }
+
+    fn dump_rdb(&mut self) -> Vec<u8> {
+        let entries = self
+            .data
+            .iter()
+            .map(|(key, value)| RdbEntry {
+                key: key.clone(),
+                value: value.clone(),
+                expires_at: self.expires.get(key).copied(),
+                field_expires: self.field_expires.get(key).cloned().unwrap_or_default(),
+            })
+            .collect::<Vec<_>>();
+
+        rdb::encode(&entries)
+    }
+
+    fn load_rdb(&mut self, rdb: &[u8]) -> Result<(), String> {
+        let entries = rdb::decode(rdb)?;
+        let current_time = current_time_ms();
+
+        self.data.clear();
+        self.expires.clear();
+        self.field_expires.clear();
+        for entry in entries {
+            if let Some(expires_at) = entry.expires_at {
+                if expires_at <= current_time {
+                    continue;
+                }
+                self.expires.insert(entry.key.clone(), expires_at);
+            }
+            if !entry.field_expires.is_empty() {
+                self.field_expires.insert(entry.key.clone(), entry.field_expires);
+            }
+            self.data.insert(entry.key, entry.value);
         }
+        Ok(())
     }
 }
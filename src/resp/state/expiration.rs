@@ -0,0 +1,20 @@
+//! Active expiration: keys and hash fields are also expired lazily on access, this
+//! reclaims the ones no client reads anymore.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::Mutex;
+
+use crate::resp::state::default_server_state::DefaultServerState;
+
+/// How often expired keys and hash fields are reclaimed, like Redis' default `hz 10`.
+const EXPIRE_CYCLE_PERIOD: Duration = Duration::from_millis(100);
+
+pub async fn run_active_expire(state: Arc<Mutex<DefaultServerState>>) {
+    let mut interval = tokio::time::interval(EXPIRE_CYCLE_PERIOD);
+    loop {
+        interval.tick().await;
+        state.lock().await.active_expire();
+    }
+}
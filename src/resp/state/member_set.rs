@@ -0,0 +1,140 @@
+//! Members of a set value. Like Redis, small sets holding only integers are kept as a
+//! sorted vector of integers (an intset) and converted to a hash table once they hold
+//! anything else or grow past `MAX_INTSET_ENTRIES`.
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+use crate::resp::random::random_below;
+
+/// Same default as Redis' `set-max-intset-entries`.
+pub const MAX_INTSET_ENTRIES: usize = 512;
+
+#[derive(Clone, Debug)]
+enum Encoding {
+    IntSet(Vec<i64>),
+    HashTable(HashSet<String>),
+}
+
+#[derive(Clone, Debug)]
+pub struct MemberSet {
+    encoding: Encoding,
+}
+
+impl Default for MemberSet {
+    fn default() -> Self {
+        MemberSet {
+            encoding: Encoding::IntSet(Vec::new()),
+        }
+    }
+}
+
+/// The integer a member represents, only if it is written the way integers are printed
+/// back (`"01"` or `"+1"` must stay strings).
+fn as_integer(member: &str) -> Option<i64> {
+    member
+        .parse::<i64>()
+        .ok()
+        .filter(|integer| integer.to_string() == member)
+}
+
+impl MemberSet {
+    /// Whether the set still uses the compact integer encoding.
+    pub fn is_intset(&self) -> bool {
+        matches!(self.encoding, Encoding::IntSet(_))
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.encoding {
+            Encoding::IntSet(integers) => integers.len(),
+            Encoding::HashTable(members) => members.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains(&self, member: &str) -> bool {
+        match &self.encoding {
+            Encoding::IntSet(integers) => {
+                as_integer(member).is_some_and(|integer| integers.binary_search(&integer).is_ok())
+            }
+            Encoding::HashTable(members) => members.contains(member),
+        }
+    }
+
+    /// Adds a member. Returns whether it was not present yet.
+    pub fn insert(&mut self, member: String) -> bool {
+        if let Encoding::IntSet(integers) = &mut self.encoding {
+            if let Some(integer) = as_integer(&member) {
+                match integers.binary_search(&integer) {
+                    Ok(_) => return false,
+                    Err(position) if integers.len() < MAX_INTSET_ENTRIES => {
+                        integers.insert(position, integer);
+                        return true;
+                    }
+                    Err(_) => {}
+                }
+            }
+            let members = integers.iter().map(|integer| integer.to_string()).collect();
+            self.encoding = Encoding::HashTable(members);
+        }
+        match &mut self.encoding {
+            Encoding::HashTable(members) => members.insert(member),
+            Encoding::IntSet(_) => unreachable!("converted above"),
+        }
+    }
+
+    /// Removes a member. Returns whether it was present.
+    pub fn remove(&mut self, member: &str) -> bool {
+        match &mut self.encoding {
+            Encoding::IntSet(integers) => match as_integer(member).map(|integer| integers.binary_search(&integer)) {
+                Some(Ok(position)) => {
+                    integers.remove(position);
+                    true
+                }
+                _ => false,
+            },
+            Encoding::HashTable(members) => members.remove(member),
+        }
+    }
+
+    pub fn iter(&self) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_> {
+        match &self.encoding {
+            Encoding::IntSet(integers) => Box::new(integers.iter().map(|integer| Cow::Owned(integer.to_string()))),
+            Encoding::HashTable(members) => Box::new(members.iter().map(|member| Cow::Borrowed(member.as_str()))),
+        }
+    }
+
+    pub fn random_member(&self) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+        let index = random_below(self.len() as u64) as usize;
+        self.iter().nth(index).map(Cow::into_owned)
+    }
+
+    /// Removes and returns a random member.
+    pub fn pop_random(&mut self) -> Option<String> {
+        let member = self.random_member()?;
+        self.remove(&member);
+        Some(member)
+    }
+}
+
+impl PartialEq for MemberSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|member| other.contains(&member))
+    }
+}
+
+impl FromIterator<String> for MemberSet {
+    fn from_iter<I: IntoIterator<Item = String>>(members: I) -> Self {
+        let mut set = MemberSet::default();
+        for member in members {
+            set.insert(member);
+        }
+        set
+    }
+}
@@ -1,3 +1,9 @@
 /// Init for state module
 pub mod server_state;
-pub mod default_server_state;
\ No newline at end of file
+pub mod default_server_state;
+pub mod expiration;
+pub mod member_set;
+pub mod sampled_map;
+pub mod sorted_set;
+pub mod stream;
+pub mod value;
@@ -0,0 +1,84 @@
+//! Map by key that can also pick entries at random, so that active expiration checks a
+//! sample of the keys with a TTL instead of scanning all of them.
+
+use std::collections::HashMap;
+
+use crate::resp::random::random_below;
+
+/// Entries are kept in a vector, with the position of each key in a hash table.
+#[derive(Clone, Debug)]
+pub struct SampledMap<V> {
+    positions: HashMap<String, usize>,
+    entries: Vec<(String, V)>,
+}
+
+impl<V> Default for SampledMap<V> {
+    fn default() -> Self {
+        SampledMap {
+            positions: HashMap::new(),
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl<V> SampledMap<V> {
+    pub fn get(&self, key: &str) -> Option<&V> {
+        self.positions.get(key).map(|position| &self.entries[*position].1)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut V> {
+        self.positions.get(key).map(|position| &mut self.entries[*position].1)
+    }
+
+    /// The value of `key`, inserting the default value first when it is missing.
+    pub fn get_or_default(&mut self, key: &str) -> &mut V
+    where
+        V: Default,
+    {
+        let position = match self.positions.get(key) {
+            Some(position) => *position,
+            None => {
+                self.insert(key.to_string(), V::default());
+                self.entries.len() - 1
+            }
+        };
+        &mut self.entries[position].1
+    }
+
+    /// Sets the value of `key`, returning the previous one.
+    pub fn insert(&mut self, key: String, value: V) -> Option<V> {
+        match self.positions.get(&key) {
+            Some(position) => Some(std::mem::replace(&mut self.entries[*position].1, value)),
+            None => {
+                self.positions.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    /// Removes `key`, moving the last entry into its place.
+    pub fn remove(&mut self, key: &str) -> Option<V> {
+        let position = self.positions.remove(key)?;
+        let (_, value) = self.entries.swap_remove(position);
+        if let Some((moved, _)) = self.entries.get(position) {
+            self.positions.insert(moved.clone(), position);
+        }
+        Some(value)
+    }
+
+    pub fn clear(&mut self) {
+        self.positions.clear();
+        self.entries.clear();
+    }
+
+    /// Up to `count` distinct entries, following each other from a random one.
+    pub fn sample(&self, count: usize) -> impl Iterator<Item = (&String, &V)> {
+        let len = self.entries.len();
+        let start = random_below(len as u64) as usize;
+        (0..count.min(len)).map(move |offset| {
+            let (key, value) = &self.entries[(start + offset) % len];
+            (key, value)
+        })
+    }
+}
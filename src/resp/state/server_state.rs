@@ -1,9 +1,17 @@
+use crate::resp::cluster::ClusterState;
 use crate::resp::protocol::RespType;
+use crate::resp::replication::ReplicationState;
+use crate::resp::sentinel::SentinelState;
+use crate::resp::state::value::Value;
 
 /// Simple interface for redis server state.
 pub trait ServerState {
-    fn get(&mut self, key: &str) -> Option<RespType>;
-    fn set(&mut self, key: String, value: RespType, ttl: Option<i64>) -> Result<(), String>;
+    fn get(&mut self, key: &str) -> Option<&Value>;
+
+    /// Value of a key for in-place modification.
+    fn get_mut(&mut self, key: &str) -> Option<&mut Value>;
+
+    fn set(&mut self, key: String, value: Value, ttl: Option<i64>) -> Result<(), String>;
 
     fn del(&mut self, key: &str) -> Result<(), String>;
 
@@ -13,29 +21,62 @@ pub trait ServerState {
 
     fn keys(&mut self) -> Vec<String>;
 
-    fn get_all(&mut self) -> Vec<(String, RespType)>;
+    fn get_all(&mut self) -> Vec<(String, Value)>;
 
     fn incr(&mut self, key: &str) -> Result<i64, String>;
 
     fn decr(&mut self, key: &str) -> Result<i64, String>;
 
+    /// Adds `delta` to the integer stored as a string at `key`, 0 when missing, keeping its TTL.
+    fn incr_by(&mut self, key: &str, delta: i64) -> Result<i64, String>;
+
     fn expire(&mut self, key: &str, seconds: u64) -> Result<(), String>;
 
     fn ttl(&mut self, key: &str) -> Result<Option<u64>, String>;
 
+    /// Absolute expiration time of a key in milliseconds since the Unix epoch.
+    fn expires_at(&mut self, key: &str) -> Option<u64>;
+
+    /// Absolute expiration time of a hash field in milliseconds since the Unix epoch.
+    fn field_expires_at(&mut self, key: &str, field: &str) -> Option<u64>;
+
+    /// Sets the expiration time of a hash field, or removes it with `None`.
+    fn set_field_expires_at(&mut self, key: &str, field: &str, expires_at: Option<u64>);
+
     fn persist(&mut self, key: &str) -> Result<(), String>;
 
-    fn type_of(&mut self, key: &str) -> Result<RespType, String>;
+    /// Type name of the value stored at a key, "none" when it does not exist.
+    fn type_of(&mut self, key: &str) -> &'static str;
 
     fn rename(&mut self, old_key: &str, new_key: &str) -> Result<(), String>;
 
     fn rename_if_exists(&mut self, old_key: &str, new_key: &str) -> Result<(), String>;
 
-    fn append(&mut self, key: &str, value: &str) -> Result<RespType, String>;
+    /// Appends to the string at `key`, created when missing, replying with its new length.
+    fn append(&mut self, key: &str, value: &[u8]) -> Result<RespType, String>;
 
+    /// Bytes of the string at `key` from `start` to `end` inclusive, negative indexes counting
+    /// from the end.
     fn get_range(&mut self, key: &str, start: i64, end: i64) -> Result<RespType, String>;
 
-    fn set_range(&mut self, key: &str, offset: i64, value: &str) -> Result<RespType, String>;
+    /// Overwrites the string at `key` from `offset`, padding it with zero bytes, and replies
+    /// with its new length.
+    fn set_range(&mut self, key: &str, offset: i64, value: &[u8]) -> Result<RespType, String>;
+
+    /// Replaces the string at `key`, dropping its TTL, and replies with the previous one or nil.
+    fn get_set(&mut self, key: &str, value: &[u8]) -> Result<RespType, String>;
+
+    fn replication(&mut self) -> &mut ReplicationState;
+
+    /// State of the sentinel, present only when the server runs in sentinel mode.
+    fn sentinel(&mut self) -> Option<&mut SentinelState>;
+
+    /// Slot map of the cluster, present only when cluster mode is enabled.
+    fn cluster(&mut self) -> Option<&mut ClusterState>;
+
+    /// Serializes the dataset into an RDB snapshot.
+    fn dump_rdb(&mut self) -> Vec<u8>;
 
-    fn get_set(&mut self, key: &str, value: &str) -> Result<RespType, String>;
-}
\ No newline at end of file
+    /// Replaces the dataset with the contents of an RDB snapshot.
+    fn load_rdb(&mut self, rdb: &[u8]) -> Result<(), String>;
+}
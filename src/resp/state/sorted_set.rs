@@ -0,0 +1,364 @@
+//! Sorted set value: a hash from members to scores plus a skiplist ordered by
+//! `(score, member)`. Like in Redis, every skiplist link records how many nodes it
+//! skips, which makes rank lookups O(log n).
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::resp::random::random_u64;
+
+const MAX_LEVEL: usize = 32;
+
+/// Orders entries by score, then by member for equal scores.
+fn compare(score: f64, member: &str, other_score: f64, other_member: &str) -> Ordering {
+    score
+        .partial_cmp(&other_score)
+        .unwrap_or(Ordering::Equal)
+        .then_with(|| member.cmp(other_member))
+}
+
+/// Formats a score like Redis' `%.17g`: 17 significant digits without trailing zeros, in
+/// exponent notation below 1e-4 and from 1e17.
+pub fn format_score(score: f64) -> String {
+    const PRECISION: i32 = 17;
+    if !score.is_finite() {
+        return score.to_string();
+    }
+    let scientific = format!("{:.*e}", PRECISION as usize - 1, score);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let exponent = exponent.parse::<i32>().unwrap_or_default();
+    let trim = |digits: &str| match digits.contains('.') {
+        true => digits.trim_end_matches('0').trim_end_matches('.').to_string(),
+        false => digits.to_string(),
+    };
+    if !(-4..PRECISION).contains(&exponent) {
+        let sign = if exponent < 0 { '-' } else { '+' };
+        format!("{}e{}{:02}", trim(mantissa), sign, exponent.abs())
+    } else {
+        trim(&format!("{:.*}", (PRECISION - 1 - exponent) as usize, score))
+    }
+}
+
+/// A node gets one more level with probability 1/4, as in Redis.
+fn random_level() -> usize {
+    let levels = 1 + random_u64().trailing_zeros() as usize / 2;
+    levels.min(MAX_LEVEL)
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Link {
+    next: Option<usize>,
+    /// Number of nodes this link moves forward, counting the node it points to.
+    span: usize,
+}
+
+#[derive(Clone, Debug)]
+struct Node {
+    member: String,
+    score: f64,
+    links: Vec<Link>,
+    backward: Option<usize>,
+}
+
+/// Skiplist whose nodes live in a vector and point at each other by index. `None` as a
+/// position stands for the head.
+#[derive(Clone, Debug)]
+struct SkipList {
+    head: Vec<Link>,
+    nodes: Vec<Option<Node>>,
+    free: Vec<usize>,
+    level: usize,
+    tail: Option<usize>,
+    len: usize,
+}
+
+impl Default for SkipList {
+    fn default() -> Self {
+        SkipList {
+            head: vec![Link::default(); MAX_LEVEL],
+            nodes: Vec::new(),
+            free: Vec::new(),
+            level: 1,
+            tail: None,
+            len: 0,
+        }
+    }
+}
+
+impl SkipList {
+    fn node(&self, index: usize) -> &Node {
+        self.nodes[index].as_ref().expect("skiplist link to a freed node")
+    }
+
+    fn node_mut(&mut self, index: usize) -> &mut Node {
+        self.nodes[index].as_mut().expect("skiplist link to a freed node")
+    }
+
+    fn link(&self, position: Option<usize>, level: usize) -> Link {
+        match position {
+            Some(index) => self.node(index).links[level],
+            None => self.head[level],
+        }
+    }
+
+    fn link_mut(&mut self, position: Option<usize>, level: usize) -> &mut Link {
+        match position {
+            Some(index) => &mut self.node_mut(index).links[level],
+            None => &mut self.head[level],
+        }
+    }
+
+    /// For every level, the last position before `(score, member)` and its rank.
+    fn predecessors(&self, score: f64, member: &str) -> ([Option<usize>; MAX_LEVEL], [usize; MAX_LEVEL]) {
+        let mut update = [None; MAX_LEVEL];
+        let mut rank = [0; MAX_LEVEL];
+        let mut position = None;
+        for level in (0..self.level).rev() {
+            rank[level] = if level + 1 == self.level { 0 } else { rank[level + 1] };
+            loop {
+                let link = self.link(position, level);
+                match link.next {
+                    Some(next) if compare(self.node(next).score, &self.node(next).member, score, member).is_lt() => {
+                        rank[level] += link.span;
+                        position = Some(next);
+                    }
+                    _ => break,
+                }
+            }
+            update[level] = position;
+        }
+        (update, rank)
+    }
+
+    /// Inserts an entry that is not in the list yet.
+    fn insert(&mut self, score: f64, member: String) {
+        let (mut update, mut rank) = self.predecessors(score, &member);
+        let level = random_level();
+        if level > self.level {
+            for extra in self.level..level {
+                rank[extra] = 0;
+                update[extra] = None;
+                self.head[extra].span = self.len;
+            }
+            self.level = level;
+        }
+
+        let index = self.free.pop().unwrap_or(self.nodes.len());
+        let mut links = vec![Link::default(); level];
+        for (i, link) in links.iter_mut().enumerate() {
+            let previous = self.link(update[i], i);
+            *link = Link {
+                next: previous.next,
+                span: previous.span - (rank[0] - rank[i]),
+            };
+            *self.link_mut(update[i], i) = Link {
+                next: Some(index),
+                span: rank[0] - rank[i] + 1,
+            };
+        }
+        for (i, position) in update.iter().enumerate().take(self.level).skip(level) {
+            self.link_mut(*position, i).span += 1;
+        }
+
+        let next = links[0].next;
+        let node = Node {
+            member,
+            score,
+            links,
+            backward: update[0],
+        };
+        if index == self.nodes.len() {
+            self.nodes.push(Some(node));
+        } else {
+            self.nodes[index] = Some(node);
+        }
+        match next {
+            Some(next) => self.node_mut(next).backward = Some(index),
+            None => self.tail = Some(index),
+        }
+        self.len += 1;
+    }
+
+    /// Removes an entry. Returns whether it was found.
+    fn remove(&mut self, score: f64, member: &str) -> bool {
+        let (update, _) = self.predecessors(score, member);
+        let Some(index) = self.link(update[0], 0).next else {
+            return false;
+        };
+        if compare(self.node(index).score, &self.node(index).member, score, member).is_ne() {
+            return false;
+        }
+
+        let node = self.nodes[index].take().expect("skiplist link to a freed node");
+        for (i, position) in update.iter().enumerate().take(self.level) {
+            let link = self.link_mut(*position, i);
+            if link.next == Some(index) {
+                link.span += node.links[i].span;
+                link.span -= 1;
+                link.next = node.links[i].next;
+            } else {
+                link.span -= 1;
+            }
+        }
+        match node.links[0].next {
+            Some(next) => self.node_mut(next).backward = node.backward,
+            None => self.tail = node.backward,
+        }
+        while self.level > 1 && self.head[self.level - 1].next.is_none() {
+            self.level -= 1;
+        }
+        self.free.push(index);
+        self.len -= 1;
+        true
+    }
+
+    /// Number of leading entries for which `before` holds. `before` must hold for a
+    /// prefix of the list and not after it.
+    fn count_before(&self, before: impl Fn(f64, &str) -> bool) -> usize {
+        let mut position = None;
+        let mut rank = 0;
+        for level in (0..self.level).rev() {
+            loop {
+                let link = self.link(position, level);
+                match link.next {
+                    Some(next) if before(self.node(next).score, &self.node(next).member) => {
+                        rank += link.span;
+                        position = Some(next);
+                    }
+                    _ => break,
+                }
+            }
+        }
+        rank
+    }
+
+    /// Node at a 0-based rank.
+    fn at_rank(&self, rank: usize) -> Option<usize> {
+        let target = rank + 1;
+        let mut position = None;
+        let mut traversed = 0;
+        for level in (0..self.level).rev() {
+            loop {
+                let link = self.link(position, level);
+                match link.next {
+                    Some(next) if traversed + link.span <= target => {
+                        traversed += link.span;
+                        position = Some(next);
+                    }
+                    _ => break,
+                }
+            }
+            if traversed == target {
+                return position;
+            }
+        }
+        None
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SortedSet {
+    scores: HashMap<String, f64>,
+    list: SkipList,
+}
+
+impl SortedSet {
+    pub fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+
+    pub fn score(&self, member: &str) -> Option<f64> {
+        self.scores.get(member).copied()
+    }
+
+    /// Adds a member or updates its score. Returns whether the member is new.
+    pub fn insert(&mut self, member: String, score: f64) -> bool {
+        match self.scores.get(&member).copied() {
+            Some(current) if current == score => false,
+            Some(current) => {
+                self.list.remove(current, &member);
+                self.list.insert(score, member.clone());
+                self.scores.insert(member, score);
+                false
+            }
+            None => {
+                self.list.insert(score, member.clone());
+                self.scores.insert(member, score);
+                true
+            }
+        }
+    }
+
+    /// Removes a member. Returns whether it was present.
+    pub fn remove(&mut self, member: &str) -> bool {
+        match self.scores.remove(member) {
+            Some(score) => self.list.remove(score, member),
+            None => false,
+        }
+    }
+
+    /// 0-based rank of a member, in ascending order.
+    pub fn rank(&self, member: &str) -> Option<usize> {
+        let score = self.score(member)?;
+        Some(self.list.count_before(|other_score, other_member| {
+            compare(other_score, other_member, score, member).is_lt()
+        }))
+    }
+
+    /// Number of leading entries, in ascending order, for which `before(score, member)` holds.
+    /// It must hold for a prefix of the entries only, e.g. `score < min`.
+    pub fn count_before(&self, before: impl Fn(f64, &str) -> bool) -> usize {
+        self.list.count_before(before)
+    }
+
+    /// Entries whose 0-based position is in `start..end`, counting from the highest score
+    /// when `rev` is set.
+    pub fn range(&self, start: usize, end: usize, rev: bool) -> Vec<(String, f64)> {
+        let end = end.min(self.len());
+        if start >= end {
+            return Vec::new();
+        }
+        let first = if rev { self.len() - 1 - start } else { start };
+        let mut position = self.list.at_rank(first);
+        let mut entries = Vec::with_capacity(end - start);
+        while let Some(index) = position {
+            if entries.len() == end - start {
+                break;
+            }
+            let node = self.list.node(index);
+            entries.push((node.member.clone(), node.score));
+            position = if rev { node.backward } else { node.links[0].next };
+        }
+        entries
+    }
+
+    /// Entries in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, f64)> + '_ {
+        let mut position = self.list.head[0].next;
+        std::iter::from_fn(move || {
+            let node = self.list.node(position?);
+            position = node.links[0].next;
+            Some((&node.member, node.score))
+        })
+    }
+}
+
+impl PartialEq for SortedSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.scores == other.scores
+    }
+}
+
+impl FromIterator<(String, f64)> for SortedSet {
+    fn from_iter<I: IntoIterator<Item = (String, f64)>>(entries: I) -> Self {
+        let mut set = SortedSet::default();
+        for (member, score) in entries {
+            set.insert(member, score);
+        }
+        set
+    }
+}
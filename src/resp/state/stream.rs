@@ -0,0 +1,309 @@
+//! Stream value: entries of field-value pairs ordered by their `<ms>-<seq>` ID, and the
+//! consumer groups reading them.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StreamId {
+    pub ms: u64,
+    pub seq: u64,
+}
+
+impl StreamId {
+    pub const MIN: StreamId = StreamId { ms: 0, seq: 0 };
+    pub const MAX: StreamId = StreamId {
+        ms: u64::MAX,
+        seq: u64::MAX,
+    };
+
+    pub fn new(ms: u64, seq: u64) -> Self {
+        StreamId { ms, seq }
+    }
+
+    /// The smallest ID greater than this one.
+    pub fn next(self) -> Option<StreamId> {
+        match self.seq.checked_add(1) {
+            Some(seq) => Some(StreamId::new(self.ms, seq)),
+            None => Some(StreamId::new(self.ms.checked_add(1)?, 0)),
+        }
+    }
+
+    /// The greatest ID smaller than this one.
+    pub fn previous(self) -> Option<StreamId> {
+        match self.seq.checked_sub(1) {
+            Some(seq) => Some(StreamId::new(self.ms, seq)),
+            None => Some(StreamId::new(self.ms.checked_sub(1)?, u64::MAX)),
+        }
+    }
+
+    /// Big-endian encoding, which sorts like the IDs do.
+    pub fn to_bytes(self) -> [u8; 16] {
+        let mut bytes = [0; 16];
+        bytes[..8].copy_from_slice(&self.ms.to_be_bytes());
+        bytes[8..].copy_from_slice(&self.seq.to_be_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: [u8; 16]) -> Self {
+        let (ms, seq) = bytes.split_at(8);
+        StreamId::new(
+            u64::from_be_bytes(ms.try_into().unwrap_or_default()),
+            u64::from_be_bytes(seq.try_into().unwrap_or_default()),
+        )
+    }
+}
+
+impl fmt::Display for StreamId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.ms, self.seq)
+    }
+}
+
+pub type Fields = Vec<(String, String)>;
+
+pub type Entries<'a> = Box<dyn DoubleEndedIterator<Item = (&'a StreamId, &'a Fields)> + 'a>;
+
+/// How `XADD` and `XTRIM` shorten a stream.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Trim {
+    /// Keep at most this many entries.
+    MaxLen(u64),
+    /// Remove the entries with a smaller ID.
+    MinId(StreamId),
+}
+
+/// An entry delivered to a consumer of a group and not acknowledged yet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PendingEntry {
+    pub consumer: String,
+    /// When the entry was last delivered, in milliseconds.
+    pub delivery_time: u64,
+    pub delivery_count: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Consumer {
+    /// When the consumer last tried to read or claim entries, in milliseconds.
+    pub seen_time: u64,
+    /// When the consumer last got entries, if it ever did.
+    pub active_time: Option<u64>,
+    /// IDs of the entries delivered to the consumer and not acknowledged yet.
+    pub pending: BTreeSet<StreamId>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ConsumerGroup {
+    /// ID of the last entry delivered to the group.
+    pub last_id: StreamId,
+    /// Number of entries the group read up to `last_id`, when it is known.
+    pub entries_read: Option<u64>,
+    /// The pending entries of all the consumers.
+    pub pending: BTreeMap<StreamId, PendingEntry>,
+    pub consumers: BTreeMap<String, Consumer>,
+}
+
+impl ConsumerGroup {
+    pub fn new(last_id: StreamId, entries_read: Option<u64>) -> Self {
+        ConsumerGroup {
+            last_id,
+            entries_read,
+            ..Default::default()
+        }
+    }
+
+    /// Marks a consumer as seen, creating it if needed. Returns whether it was created.
+    pub fn touch_consumer(&mut self, name: &str, now: u64) -> bool {
+        let created = !self.consumers.contains_key(name);
+        self.consumers.entry(name.to_string()).or_default().seen_time = now;
+        created
+    }
+
+    /// Records the delivery of an entry to `consumer`, which becomes its owner.
+    pub fn deliver(&mut self, id: StreamId, consumer: &str, delivery_time: u64, delivery_count: u64) {
+        self.acknowledge(id);
+        self.pending.insert(
+            id,
+            PendingEntry {
+                consumer: consumer.to_string(),
+                delivery_time,
+                delivery_count,
+            },
+        );
+        let consumer = self.consumers.entry(consumer.to_string()).or_default();
+        consumer.pending.insert(id);
+        consumer.active_time = Some(delivery_time);
+    }
+
+    /// Removes an entry from the pending entries. Returns whether it was pending.
+    pub fn acknowledge(&mut self, id: StreamId) -> bool {
+        let Some(entry) = self.pending.remove(&id) else {
+            return false;
+        };
+        if let Some(consumer) = self.consumers.get_mut(&entry.consumer) {
+            consumer.pending.remove(&id);
+        }
+        true
+    }
+
+    /// Removes a consumer and its pending entries. Returns how many entries were pending.
+    pub fn remove_consumer(&mut self, name: &str) -> Option<usize> {
+        let consumer = self.consumers.remove(name)?;
+        for id in &consumer.pending {
+            self.pending.remove(id);
+        }
+        Some(consumer.pending.len())
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stream {
+    entries: BTreeMap<StreamId, Fields>,
+    /// ID of the last entry ever added, which new IDs must be greater than.
+    pub last_id: StreamId,
+    /// Greatest ID removed with `XDEL`.
+    pub max_deleted_id: StreamId,
+    /// Number of entries ever added.
+    pub entries_added: u64,
+    pub groups: BTreeMap<String, ConsumerGroup>,
+}
+
+impl Stream {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn first_id(&self) -> Option<StreamId> {
+        self.entries.keys().next().copied()
+    }
+
+    pub fn get(&self, id: StreamId) -> Option<&Fields> {
+        self.entries.get(&id)
+    }
+
+    /// Appends an entry. The caller makes sure `id` is greater than `last_id`.
+    pub fn add(&mut self, id: StreamId, fields: Fields) {
+        self.entries.insert(id, fields);
+        self.last_id = id;
+        self.entries_added += 1;
+    }
+
+    /// Removes an entry. Returns whether it was present.
+    pub fn remove(&mut self, id: StreamId) -> bool {
+        if self.entries.remove(&id).is_none() {
+            return false;
+        }
+        self.max_deleted_id = self.max_deleted_id.max(id);
+        true
+    }
+
+    /// Entries with an ID from `start` to `end` included, in ascending order.
+    pub fn range(&self, start: StreamId, end: StreamId) -> Entries<'_> {
+        if start > end {
+            return Box::new(std::iter::empty());
+        }
+        Box::new(self.entries.range(start..=end))
+    }
+
+    /// Removes the oldest entries as the strategy requires, at most `limit` of them.
+    /// Returns how many were removed.
+    pub fn trim(&mut self, trim: Trim, limit: Option<u64>) -> u64 {
+        let mut removed = 0;
+        while limit.is_none_or(|limit| removed < limit) {
+            let Some(first) = self.first_id() else {
+                break;
+            };
+            let excess = match trim {
+                Trim::MaxLen(max_len) => self.len() as u64 > max_len,
+                Trim::MinId(min_id) => first < min_id,
+            };
+            if !excess {
+                break;
+            }
+            self.entries.remove(&first);
+            removed += 1;
+        }
+        removed
+    }
+
+    /// Entries in ascending order of ID.
+    pub fn iter(&self) -> impl Iterator<Item = (&StreamId, &Fields)> + '_ {
+        self.entries.iter()
+    }
+
+    /// Whether entries from `start` on were removed with `XDEL`.
+    fn has_tombstones(&self, start: StreamId) -> bool {
+        !self.is_empty() && self.max_deleted_id != StreamId::MIN && self.max_deleted_id >= start
+    }
+
+    /// Number of entries added up to `id` included, when it can be told from the entries
+    /// left, the way Redis estimates it.
+    pub fn entries_added_until(&self, id: StreamId) -> Option<u64> {
+        if self.entries_added == 0 {
+            return Some(0);
+        }
+        if id == self.last_id || (self.is_empty() && id < self.last_id) {
+            return Some(self.entries_added);
+        }
+        let first_id = self.first_id()?;
+        if id > self.last_id || (self.max_deleted_id != StreamId::MIN && self.max_deleted_id >= first_id) {
+            return None;
+        }
+        match id.cmp(&first_id) {
+            std::cmp::Ordering::Less => Some(self.entries_added - self.len() as u64),
+            std::cmp::Ordering::Equal => Some(self.entries_added - self.len() as u64 + 1),
+            std::cmp::Ordering::Greater => None,
+        }
+    }
+
+    /// Number of entries a group has yet to read, when it can be told.
+    pub fn lag(&self, group: &ConsumerGroup) -> Option<u64> {
+        if self.entries_added == 0 {
+            return Some(0);
+        }
+        let entries_read = match group.entries_read {
+            Some(entries_read) if !self.has_tombstones(group.last_id) => entries_read,
+            _ => self.entries_added_until(group.last_id)?,
+        };
+        Some(self.entries_added.saturating_sub(entries_read))
+    }
+
+    /// Delivers up to `count` entries the group has not read yet to `consumer`, and tracks
+    /// them as pending unless `noack` is set.
+    pub fn deliver_new(
+        &mut self,
+        group: &str,
+        consumer: &str,
+        count: usize,
+        noack: bool,
+        now: u64,
+    ) -> Vec<(StreamId, Fields)> {
+        let Some(start) = self.groups.get(group).and_then(|group| group.last_id.next()) else {
+            return Vec::new();
+        };
+        let entries = self
+            .range(start, StreamId::MAX)
+            .take(count)
+            .map(|(id, fields)| (*id, fields.clone()))
+            .collect::<Vec<_>>();
+        for (id, _) in &entries {
+            let entries_read = match self.groups.get(group).and_then(|group| group.entries_read) {
+                Some(entries_read) if !self.has_tombstones(*id) => Some(entries_read + 1),
+                _ => self.entries_added_until(*id),
+            };
+            let Some(group) = self.groups.get_mut(group) else {
+                break;
+            };
+            group.entries_read = entries_read;
+            group.last_id = *id;
+            if !noack {
+                group.deliver(*id, consumer, now, 1);
+            }
+        }
+        entries
+    }
+}
@@ -0,0 +1,114 @@
+//! Values stored in the keyspace. Commands work on these and only turn them into
+//! `RespType` when building their reply.
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::resp::bloom::ScalableBloom;
+use crate::resp::cms::CountMinSketch;
+use crate::resp::cuckoo::CuckooFilter;
+use crate::resp::json::Json;
+use crate::resp::protocol::RespType;
+use crate::resp::state::member_set::MemberSet;
+use crate::resp::state::sorted_set::{format_score, SortedSet};
+use crate::resp::state::stream::Stream;
+use crate::resp::timeseries::TimeSeries;
+use crate::resp::topk::TopK;
+
+/// Largest string, or filter or sketch allocation, Redis' default `proto-max-bulk-len`.
+pub(crate) const MAX_STRING_BYTES: u64 = 512 * 1024 * 1024;
+
+pub const WRONG_TYPE: &str = "WRONGTYPE Operation against a key holding the wrong kind of value";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// Raw bytes, which need not be UTF-8.
+    String(Vec<u8>),
+    List(VecDeque<String>),
+    Hash(HashMap<String, String>),
+    Set(MemberSet),
+    SortedSet(SortedSet),
+    Stream(Stream),
+    Json(Json),
+    Bloom(ScalableBloom),
+    Cuckoo(CuckooFilter),
+    CountMinSketch(CountMinSketch),
+    TopK(TopK),
+    TimeSeries(TimeSeries),
+}
+
+impl Value {
+    /// Name of the type as reported by `TYPE`.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "string",
+            Value::List(_) => "list",
+            Value::Hash(_) => "hash",
+            Value::Set(_) => "set",
+            Value::SortedSet(_) => "zset",
+            Value::Stream(_) => "stream",
+            Value::Json(_) => "ReJSON-RL",
+            Value::Bloom(_) => "MBbloom--",
+            Value::Cuckoo(_) => "MBbloomCF",
+            Value::CountMinSketch(_) => "CMSk-TYPE",
+            Value::TopK(_) => "TopK-TYPE",
+            Value::TimeSeries(_) => "TSDB-TYPE",
+        }
+    }
+
+    /// Reply representation of the whole value: a bulk string for strings, an array of
+    /// elements for lists and sets, of fields followed by their values for hashes and of
+    /// members followed by their scores for sorted sets and of `[id, [field, value, ...]]`
+    /// entries for streams and of `[timestamp, value]` samples for time series. JSON documents
+    /// are serialized, and filters and sketches, whose items cannot be listed, are nil.
+    pub fn to_resp(&self) -> RespType {
+        let bulk = |value: &String| RespType::BulkString(Some(value.clone()));
+        match self {
+            Value::String(value) => RespType::bulk_bytes(value.clone()),
+            Value::List(items) => RespType::Array(items.iter().map(bulk).collect()),
+            Value::Set(members) => RespType::Array(
+                members
+                    .iter()
+                    .map(|member| RespType::BulkString(Some(member.into_owned())))
+                    .collect(),
+            ),
+            Value::SortedSet(entries) => RespType::Array(
+                entries
+                    .iter()
+                    .flat_map(|(member, score)| [bulk(member), bulk(&format_score(score))])
+                    .collect(),
+            ),
+            Value::Hash(fields) => RespType::Array(
+                fields
+                    .iter()
+                    .flat_map(|(field, value)| [bulk(field), bulk(value)])
+                    .collect(),
+            ),
+            Value::Stream(stream) => RespType::Array(
+                stream
+                    .iter()
+                    .map(|(id, fields)| {
+                        RespType::Array(vec![
+                            bulk(&id.to_string()),
+                            RespType::Array(
+                                fields.iter().flat_map(|(field, value)| [bulk(field), bulk(value)]).collect(),
+                            ),
+                        ])
+                    })
+                    .collect(),
+            ),
+            Value::Json(document) => bulk(&document.serialize()),
+            Value::TimeSeries(series) => RespType::Array(
+                series
+                    .samples
+                    .iter()
+                    .map(|(timestamp, value)| {
+                        RespType::Array(vec![RespType::Integer(*timestamp as i64), bulk(&value.to_string())])
+                    })
+                    .collect(),
+            ),
+            Value::Bloom(_) | Value::Cuckoo(_) | Value::CountMinSketch(_) | Value::TopK(_) => {
+                RespType::BulkString(None)
+            }
+        }
+    }
+}
@@ -0,0 +1,209 @@
+//! Time series: samples sorted by timestamp, with labels to select series by and rules
+//! downsampling new samples into other series.
+
+use std::collections::BTreeMap;
+
+/// How adding a sample at an existing timestamp is resolved.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DuplicatePolicy {
+    Block,
+    First,
+    Last,
+    Min,
+    Max,
+    Sum,
+}
+
+impl DuplicatePolicy {
+    pub const ALL: [DuplicatePolicy; 6] = [
+        DuplicatePolicy::Block,
+        DuplicatePolicy::First,
+        DuplicatePolicy::Last,
+        DuplicatePolicy::Min,
+        DuplicatePolicy::Max,
+        DuplicatePolicy::Sum,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DuplicatePolicy::Block => "block",
+            DuplicatePolicy::First => "first",
+            DuplicatePolicy::Last => "last",
+            DuplicatePolicy::Min => "min",
+            DuplicatePolicy::Max => "max",
+            DuplicatePolicy::Sum => "sum",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<DuplicatePolicy> {
+        Self::ALL.into_iter().find(|policy| policy.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// How the samples of a bucket are reduced to one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Aggregation {
+    Avg,
+    Sum,
+    Min,
+    Max,
+    Count,
+    First,
+    Last,
+}
+
+impl Aggregation {
+    pub const ALL: [Aggregation; 7] = [
+        Aggregation::Avg,
+        Aggregation::Sum,
+        Aggregation::Min,
+        Aggregation::Max,
+        Aggregation::Count,
+        Aggregation::First,
+        Aggregation::Last,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Aggregation::Avg => "avg",
+            Aggregation::Sum => "sum",
+            Aggregation::Min => "min",
+            Aggregation::Max => "max",
+            Aggregation::Count => "count",
+            Aggregation::First => "first",
+            Aggregation::Last => "last",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Aggregation> {
+        Self::ALL.into_iter().find(|aggregation| aggregation.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Reduces the values of a bucket, which has at least one.
+    fn apply(&self, values: &[f64]) -> f64 {
+        let sum = || values.iter().sum::<f64>();
+        match self {
+            Aggregation::Avg => sum() / values.len() as f64,
+            Aggregation::Sum => sum(),
+            Aggregation::Min => values.iter().copied().fold(f64::INFINITY, f64::min),
+            Aggregation::Max => values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            Aggregation::Count => values.len() as f64,
+            Aggregation::First => values[0],
+            Aggregation::Last => values[values.len() - 1],
+        }
+    }
+}
+
+/// Start of the bucket holding `timestamp`, buckets starting at multiples of `duration` after
+/// `align`.
+pub fn bucket_start(timestamp: u64, duration: u64, align: u64) -> u64 {
+    let offset = (timestamp as i128 - align as i128).rem_euclid(duration as i128);
+    (timestamp as i128 - offset).max(0) as u64
+}
+
+/// Reduces samples sorted by timestamp to one per bucket, at the start of the bucket.
+pub fn aggregate(
+    samples: impl Iterator<Item = (u64, f64)>,
+    aggregation: Aggregation,
+    duration: u64,
+    align: u64,
+) -> Vec<(u64, f64)> {
+    let mut buckets = Vec::new();
+    let mut current: Option<(u64, Vec<f64>)> = None;
+    for (timestamp, value) in samples {
+        let start = bucket_start(timestamp, duration, align);
+        match current.as_mut() {
+            Some((bucket, values)) if *bucket == start => values.push(value),
+            _ => {
+                if let Some((bucket, values)) = current.replace((start, vec![value])) {
+                    buckets.push((bucket, aggregation.apply(&values)));
+                }
+            }
+        }
+    }
+    if let Some((bucket, values)) = current {
+        buckets.push((bucket, aggregation.apply(&values)));
+    }
+    buckets
+}
+
+/// Downsampling of a series into `destination`, one sample per bucket of `bucket_duration`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rule {
+    pub destination: String,
+    pub aggregation: Aggregation,
+    pub bucket_duration: u64,
+    pub align: u64,
+    /// Start of the latest bucket, written to the destination once a later one starts.
+    pub bucket: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimeSeries {
+    /// Age in milliseconds, relative to the latest sample, past which samples are dropped, or 0
+    /// to keep them all.
+    pub retention: u64,
+    pub duplicate_policy: DuplicatePolicy,
+    pub labels: Vec<(String, String)>,
+    pub samples: BTreeMap<u64, f64>,
+    pub rules: Vec<Rule>,
+    /// Series whose rule downsamples into this one.
+    pub source: Option<String>,
+}
+
+impl TimeSeries {
+    pub fn new(retention: u64, duplicate_policy: DuplicatePolicy, labels: Vec<(String, String)>) -> TimeSeries {
+        TimeSeries {
+            retention,
+            duplicate_policy,
+            labels,
+            samples: BTreeMap::new(),
+            rules: Vec::new(),
+            source: None,
+        }
+    }
+
+    pub fn last(&self) -> Option<(u64, f64)> {
+        self.samples.last_key_value().map(|(timestamp, value)| (*timestamp, *value))
+    }
+
+    pub fn label(&self, name: &str) -> Option<&str> {
+        self.labels.iter().find(|(label, _)| label == name).map(|(_, value)| value.as_str())
+    }
+
+    /// Adds a sample, resolving a duplicate timestamp with `policy` or the series' own policy.
+    pub fn add(&mut self, timestamp: u64, value: f64, policy: Option<DuplicatePolicy>) -> Result<(), String> {
+        let latest = self.last().map(|(latest, _)| latest).unwrap_or_default();
+        if self.retention > 0 && timestamp < latest.saturating_sub(self.retention) {
+            return Err("TSDB: Timestamp is older than retention".to_string());
+        }
+        let value = match (self.samples.get(&timestamp), policy.unwrap_or(self.duplicate_policy)) {
+            (None, _) | (Some(_), DuplicatePolicy::Last) => value,
+            (Some(_), DuplicatePolicy::Block) => {
+                return Err(
+                    "TSDB: Error at upsert, update is not supported when DUPLICATE_POLICY is set to BLOCK mode"
+                        .to_string(),
+                )
+            }
+            (Some(old), DuplicatePolicy::First) => *old,
+            (Some(old), DuplicatePolicy::Min) => old.min(value),
+            (Some(old), DuplicatePolicy::Max) => old.max(value),
+            (Some(old), DuplicatePolicy::Sum) => old + value,
+        };
+        self.samples.insert(timestamp, value);
+        Ok(())
+    }
+
+    /// Drops the samples past the retention.
+    pub fn trim(&mut self) {
+        if let Some((latest, _)) = self.last().filter(|_| self.retention > 0) {
+            self.samples = self.samples.split_off(&latest.saturating_sub(self.retention));
+        }
+    }
+
+    /// Samples from `from` to `to`, inclusive.
+    pub fn range(&self, from: u64, to: u64) -> impl Iterator<Item = (u64, f64)> + '_ {
+        let samples = self.samples.range(from..).take_while(move |(timestamp, _)| **timestamp <= to);
+        samples.map(|(timestamp, value)| (*timestamp, *value))
+    }
+}
@@ -0,0 +1,123 @@
+//! Top-K lists tracked with HeavyKeeper, as RedisBloom does: `depth` rows of `width` buckets
+//! each hold a fingerprint and a count. An item shares a bucket with its own fingerprint and
+//! decays other ones with a probability falling exponentially with their count, so heavy
+//! hitters keep their buckets. The `k` items with the largest counts are kept in a list.
+//!
+//! Decays draw from a generator stored with the sketch rather than from the clock, so replicas
+//! replaying the same commands reach the same state.
+
+use crate::resp::hyperloglog::murmur_hash64a;
+use crate::resp::state::value::MAX_STRING_BYTES;
+
+pub const DEFAULT_WIDTH: u64 = 8;
+pub const DEFAULT_DEPTH: u64 = 7;
+pub const DEFAULT_DECAY: f64 = 0.9;
+const FINGERPRINT_SEED: u64 = 1919;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Bucket {
+    pub fingerprint: u32,
+    pub count: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TopK {
+    pub k: u64,
+    pub width: u64,
+    pub depth: u64,
+    pub decay: f64,
+    /// Buckets, row by row.
+    pub buckets: Vec<Bucket>,
+    /// The top items with their counts, at most `k` of them.
+    pub top: Vec<(Vec<u8>, u32)>,
+    /// State of the generator deciding decays.
+    pub random_state: u64,
+}
+
+impl TopK {
+    pub fn new(k: u64, width: u64, depth: u64, decay: f64) -> Result<TopK, String> {
+        let len = width.checked_mul(depth).filter(|len| len.saturating_mul(8) <= MAX_STRING_BYTES);
+        let len = len.filter(|_| k <= MAX_STRING_BYTES / 8);
+        let len = len.ok_or_else(|| "TopK: Insufficient memory to create sketch".to_string())?;
+        Ok(TopK {
+            k,
+            width,
+            depth,
+            decay,
+            buckets: vec![Bucket::default(); len as usize],
+            top: Vec::new(),
+            random_state: 0,
+        })
+    }
+
+    /// A number in `0..1` from SplitMix64.
+    fn next_random(&mut self) -> f64 {
+        self.random_state = self.random_state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.random_state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        (z ^ (z >> 31)) as f64 / u64::MAX as f64
+    }
+
+    /// Counts `increment` more occurrences of `item`, returning the item it pushed out of the
+    /// top list, if any.
+    pub fn add(&mut self, item: &[u8], increment: u32) -> Option<Vec<u8>> {
+        let fingerprint = murmur_hash64a(item, FINGERPRINT_SEED) as u32;
+        let mut count = 0;
+        for row in 0..self.depth {
+            let index = (row * self.width + murmur_hash64a(item, row) % self.width) as usize;
+            let mut bucket = self.buckets[index];
+            if bucket.count == 0 {
+                bucket = Bucket { fingerprint, count: increment };
+                count = count.max(increment);
+            } else if bucket.fingerprint == fingerprint {
+                bucket.count = bucket.count.saturating_add(increment);
+                count = count.max(bucket.count);
+            } else {
+                for remaining in (1..=increment).rev() {
+                    if self.next_random() < self.decay.powf(bucket.count as f64) {
+                        bucket.count -= 1;
+                        if bucket.count == 0 {
+                            bucket = Bucket { fingerprint, count: remaining };
+                            count = count.max(remaining);
+                            break;
+                        }
+                    }
+                }
+            }
+            self.buckets[index] = bucket;
+        }
+        self.update_top(item, count)
+    }
+
+    fn update_top(&mut self, item: &[u8], count: u32) -> Option<Vec<u8>> {
+        let full = self.top.len() as u64 >= self.k;
+        let min = self.top.iter().map(|(_, count)| *count).min().filter(|_| full).unwrap_or_default();
+        if count < min || count == 0 {
+            return None;
+        }
+        if let Some(entry) = self.top.iter_mut().find(|(top, _)| top == item) {
+            entry.1 = count;
+            return None;
+        }
+        let expelled = if full {
+            let position = self.top.iter().position(|(_, top)| *top == min)?;
+            Some(self.top.remove(position).0)
+        } else {
+            None
+        };
+        self.top.push((item.to_vec(), count));
+        expelled
+    }
+
+    pub fn contains(&self, item: &[u8]) -> bool {
+        self.top.iter().any(|(top, _)| top == item)
+    }
+
+    /// The top items, most frequent first.
+    pub fn list(&self) -> Vec<(&[u8], u32)> {
+        let mut list = self.top.iter().map(|(item, count)| (item.as_slice(), *count)).collect::<Vec<_>>();
+        list.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        list
+    }
+}
@@ -0,0 +1,301 @@
+/// Integration tests for bitmap commands
+#[cfg(test)]
+mod test_bitmap {
+    use codecrafters_redis::resp::client::ClientContext;
+    use codecrafters_redis::resp::command_dispatcher::CommandDispatcher;
+    use codecrafters_redis::resp::protocol::RespType;
+    use codecrafters_redis::resp::state::default_server_state::DefaultServerState;
+    use codecrafters_redis::resp::state::server_state::ServerState;
+    use codecrafters_redis::resp::state::value::Value;
+
+    fn bulk(s: &str) -> RespType {
+        RespType::BulkString(Some(s.to_string()))
+    }
+
+    fn run(state: &mut DefaultServerState, args: &[&str]) -> Result<RespType, String> {
+        let mut client = ClientContext::default();
+        CommandDispatcher::new().dispatch(args[0], args[1..].iter().map(|arg| bulk(arg)).collect(), state, &mut client)
+    }
+
+    fn int(state: &mut DefaultServerState, args: &[&str]) -> i64 {
+        match run(state, args).unwrap() {
+            RespType::Integer(value) => value,
+            other => panic!("expected an integer, got {:?}", other),
+        }
+    }
+
+    /// Copies `key` to `copy` through `DUMP` and `RESTORE`.
+    fn dump_and_restore(state: &mut DefaultServerState, key: &str, copy: &str) {
+        let payload = run(state, &["DUMP", key]).unwrap();
+        let args = vec![bulk(copy), bulk("0"), payload];
+        CommandDispatcher::new().dispatch("RESTORE", args, state, &mut ClientContext::default()).unwrap();
+    }
+
+    fn set(state: &mut DefaultServerState, key: &str, value: &[u8]) {
+        state.set(key.to_string(), Value::String(value.to_vec()), None).unwrap();
+    }
+
+    #[test]
+    fn test_set_and_get_bits() {
+        let mut state = DefaultServerState::default();
+
+        assert_eq!(int(&mut state, &["SETBIT", "bits", "7", "1"]), 0);
+        assert_eq!(int(&mut state, &["SETBIT", "bits", "7", "1"]), 1);
+        assert_eq!(run(&mut state, &["GET", "bits"]).unwrap(), bulk("\u{1}"));
+        assert_eq!(int(&mut state, &["GETBIT", "bits", "7"]), 1);
+        assert_eq!(int(&mut state, &["GETBIT", "bits", "6"]), 0);
+        assert_eq!(int(&mut state, &["GETBIT", "bits", "100"]), 0);
+        assert_eq!(int(&mut state, &["GETBIT", "missing", "0"]), 0);
+
+        // The string grows with zero bytes and holds arbitrary bytes.
+        assert_eq!(int(&mut state, &["SETBIT", "bits", "24", "1"]), 0);
+        assert_eq!(run(&mut state, &["GET", "bits"]).unwrap(), RespType::BulkBytes(vec![1, 0, 0, 0x80]));
+        assert_eq!(int(&mut state, &["SETBIT", "bits", "7", "0"]), 1);
+        assert_eq!(run(&mut state, &["GET", "bits"]).unwrap(), RespType::BulkBytes(vec![0, 0, 0, 0x80]));
+
+        run(&mut state, &["SET", "text", "a"]).unwrap();
+        assert_eq!(int(&mut state, &["SETBIT", "text", "6", "1"]), 0);
+        assert_eq!(run(&mut state, &["GET", "text"]).unwrap(), bulk("c"));
+
+        assert_eq!(
+            run(&mut state, &["SETBIT", "bits", "4294967296", "1"]).unwrap_err(),
+            "bit offset is not an integer or out of range"
+        );
+        assert_eq!(
+            run(&mut state, &["GETBIT", "bits", "-1"]).unwrap_err(),
+            "bit offset is not an integer or out of range"
+        );
+        assert_eq!(
+            run(&mut state, &["SETBIT", "bits", "0", "2"]).unwrap_err(),
+            "bit is not an integer or out of range"
+        );
+        run(&mut state, &["RPUSH", "list", "a"]).unwrap();
+        assert!(run(&mut state, &["SETBIT", "list", "0", "1"]).unwrap_err().starts_with("WRONGTYPE"));
+        assert!(run(&mut state, &["GETBIT", "list", "0"]).unwrap_err().starts_with("WRONGTYPE"));
+    }
+
+    #[test]
+    fn test_bitcount() {
+        let mut state = DefaultServerState::default();
+        run(&mut state, &["SET", "key", "foobar"]).unwrap();
+
+        assert_eq!(int(&mut state, &["BITCOUNT", "key"]), 26);
+        assert_eq!(int(&mut state, &["BITCOUNT", "key", "0", "0"]), 4);
+        assert_eq!(int(&mut state, &["BITCOUNT", "key", "1", "1"]), 6);
+        assert_eq!(int(&mut state, &["BITCOUNT", "key", "1", "1", "BYTE"]), 6);
+        assert_eq!(int(&mut state, &["BITCOUNT", "key", "-2", "-1"]), 7);
+        assert_eq!(int(&mut state, &["BITCOUNT", "key", "5", "30", "BIT"]), 17);
+        assert_eq!(int(&mut state, &["BITCOUNT", "key", "0", "-1", "bit"]), 26);
+        assert_eq!(int(&mut state, &["BITCOUNT", "key", "3", "5", "BIT"]), 1);
+        assert_eq!(int(&mut state, &["BITCOUNT", "key", "4", "2"]), 0);
+        assert_eq!(int(&mut state, &["BITCOUNT", "key", "-100", "100"]), 26);
+        assert_eq!(int(&mut state, &["BITCOUNT", "missing"]), 0);
+
+        assert_eq!(run(&mut state, &["BITCOUNT", "key", "0"]).unwrap_err(), "syntax error");
+        assert_eq!(run(&mut state, &["BITCOUNT", "key", "0", "1", "WORD"]).unwrap_err(), "syntax error");
+        assert_eq!(
+            run(&mut state, &["BITCOUNT", "key", "a", "1"]).unwrap_err(),
+            "value is not an integer or out of range"
+        );
+    }
+
+    #[test]
+    fn test_bitpos() {
+        let mut state = DefaultServerState::default();
+        set(&mut state, "key", &[0xff, 0xf0, 0x00]);
+
+        assert_eq!(int(&mut state, &["BITPOS", "key", "0"]), 12);
+        assert_eq!(int(&mut state, &["BITPOS", "key", "1"]), 0);
+        assert_eq!(int(&mut state, &["BITPOS", "key", "1", "2"]), -1);
+        assert_eq!(int(&mut state, &["BITPOS", "key", "1", "1"]), 8);
+        assert_eq!(int(&mut state, &["BITPOS", "key", "0", "2", "-1"]), 16);
+        assert_eq!(int(&mut state, &["BITPOS", "key", "1", "7", "15", "BIT"]), 7);
+        assert_eq!(int(&mut state, &["BITPOS", "key", "0", "7", "11", "BIT"]), -1);
+        assert_eq!(int(&mut state, &["BITPOS", "key", "0", "7", "12", "BIT"]), 12);
+
+        // Clear bits are found past the end unless the range is explicit.
+        set(&mut state, "ones", &[0xff, 0xff]);
+        assert_eq!(int(&mut state, &["BITPOS", "ones", "0"]), 16);
+        assert_eq!(int(&mut state, &["BITPOS", "ones", "0", "1"]), 16);
+        assert_eq!(int(&mut state, &["BITPOS", "ones", "0", "0", "-1"]), -1);
+        assert_eq!(int(&mut state, &["BITPOS", "ones", "0", "3", "-1", "BIT"]), -1);
+
+        assert_eq!(int(&mut state, &["BITPOS", "missing", "0"]), 0);
+        assert_eq!(int(&mut state, &["BITPOS", "missing", "1"]), -1);
+        assert_eq!(int(&mut state, &["BITPOS", "key", "1", "5", "4"]), -1);
+        assert_eq!(run(&mut state, &["BITPOS", "key", "2"]).unwrap_err(), "The bit argument must be 1 or 0.");
+        assert_eq!(run(&mut state, &["BITPOS", "key", "1", "0", "1", "WORD"]).unwrap_err(), "syntax error");
+    }
+
+    #[test]
+    fn test_bitop() {
+        let mut state = DefaultServerState::default();
+        set(&mut state, "a", &[0b1100_1100, 0xff]);
+        set(&mut state, "b", &[0b1010_1010]);
+        set(&mut state, "c", &[0b0000_1111]);
+
+        assert_eq!(int(&mut state, &["BITOP", "AND", "dest", "a", "b"]), 2);
+        assert_eq!(run(&mut state, &["GET", "dest"]).unwrap(), RespType::BulkBytes(vec![0b1000_1000, 0]));
+        assert_eq!(int(&mut state, &["BITOP", "or", "dest", "a", "b"]), 2);
+        assert_eq!(run(&mut state, &["GET", "dest"]).unwrap(), RespType::BulkBytes(vec![0b1110_1110, 0xff]));
+        assert_eq!(int(&mut state, &["BITOP", "XOR", "dest", "a", "b", "c"]), 2);
+        assert_eq!(run(&mut state, &["GET", "dest"]).unwrap(), RespType::BulkBytes(vec![0b0110_1001, 0xff]));
+        assert_eq!(int(&mut state, &["BITOP", "NOT", "dest", "b"]), 1);
+        assert_eq!(run(&mut state, &["GET", "dest"]).unwrap(), bulk("U"));
+        assert_eq!(int(&mut state, &["BITOP", "DIFF", "dest", "a", "b", "c"]), 2);
+        assert_eq!(run(&mut state, &["GET", "dest"]).unwrap(), RespType::BulkBytes(vec![0b0100_0000, 0xff]));
+
+        // Missing keys count as empty strings and an empty result deletes the destination.
+        assert_eq!(int(&mut state, &["BITOP", "AND", "dest", "a", "missing"]), 2);
+        assert_eq!(run(&mut state, &["GET", "dest"]).unwrap(), bulk("\u{0}\u{0}"));
+        assert_eq!(int(&mut state, &["BITOP", "OR", "dest", "missing", "other"]), 0);
+        assert_eq!(run(&mut state, &["TYPE", "dest"]).unwrap(), RespType::SimpleString("none".to_string()));
+
+        assert_eq!(
+            run(&mut state, &["BITOP", "NOT", "dest", "a", "b"]).unwrap_err(),
+            "BITOP NOT must be called with a single source key."
+        );
+        assert_eq!(
+            run(&mut state, &["BITOP", "DIFF", "dest", "a"]).unwrap_err(),
+            "BITOP DIFF must be called with multiple source keys."
+        );
+        assert_eq!(run(&mut state, &["BITOP", "NAND", "dest", "a"]).unwrap_err(), "syntax error");
+        run(&mut state, &["RPUSH", "list", "a"]).unwrap();
+        assert!(run(&mut state, &["BITOP", "OR", "dest", "a", "list"]).unwrap_err().starts_with("WRONGTYPE"));
+    }
+
+    #[test]
+    fn test_binary_strings_survive_dump() {
+        let mut state = DefaultServerState::default();
+        run(&mut state, &["SETBIT", "bits", "0", "1"]).unwrap();
+        run(&mut state, &["SETBIT", "bits", "15", "1"]).unwrap();
+        dump_and_restore(&mut state, "bits", "copy");
+        assert_eq!(run(&mut state, &["GET", "copy"]).unwrap(), RespType::BulkBytes(vec![0x80, 0x01]));
+    }
+
+    fn ints(values: &[Option<i64>]) -> RespType {
+        let values = values.iter().map(|value| value.map_or(RespType::BulkString(None), RespType::Integer));
+        RespType::Array(values.collect())
+    }
+
+    #[test]
+    fn test_bitfield() {
+        let mut state = DefaultServerState::default();
+
+        assert_eq!(run(&mut state, &["BITFIELD", "key", "GET", "u8", "0"]).unwrap(), ints(&[Some(0)]));
+        assert_eq!(run(&mut state, &["TYPE", "key"]).unwrap(), RespType::SimpleString("none".to_string()));
+        assert_eq!(
+            run(&mut state, &["BITFIELD", "key", "INCRBY", "i5", "100", "1", "GET", "u4", "0"]).unwrap(),
+            ints(&[Some(1), Some(0)])
+        );
+        let mut expected = vec![0; 14];
+        expected[13] = 0x80;
+        assert_eq!(run(&mut state, &["GET", "key"]).unwrap(), RespType::BulkBytes(expected));
+
+        // `#` offsets count in fields, and values wrap by default.
+        assert_eq!(
+            run(&mut state, &["BITFIELD", "packed", "SET", "i8", "#0", "100", "SET", "i8", "#1", "200"]).unwrap(),
+            ints(&[Some(0), Some(0)])
+        );
+        assert_eq!(
+            run(&mut state, &["BITFIELD", "packed", "GET", "i8", "#1", "GET", "u8", "8", "GET", "u16", "0"]).unwrap(),
+            ints(&[Some(-56), Some(200), Some(100 * 256 + 200)])
+        );
+        assert_eq!(run(&mut state, &["GET", "packed"]).unwrap(), RespType::BulkBytes(vec![100, 200]));
+        assert_eq!(
+            run(&mut state, &["BITFIELD", "packed", "SET", "u8", "0", "65", "SET", "u8", "8", "66"]).unwrap(),
+            ints(&[Some(100), Some(200)])
+        );
+        assert_eq!(run(&mut state, &["GET", "packed"]).unwrap(), bulk("AB"));
+
+        // Full 64 bit signed fields, across byte boundaries.
+        assert_eq!(
+            run(&mut state, &["BITFIELD", "wide", "SET", "i64", "3", &i64::MIN.to_string(), "GET", "i64", "3"])
+                .unwrap(),
+            ints(&[Some(0), Some(i64::MIN)])
+        );
+        assert_eq!(
+            run(&mut state, &["BITFIELD", "wide", "INCRBY", "i64", "3", "-1", "GET", "u63", "4"]).unwrap(),
+            ints(&[Some(i64::MAX), Some(i64::MAX)])
+        );
+    }
+
+    #[test]
+    fn test_bitfield_overflow() {
+        let mut state = DefaultServerState::default();
+        let incr = |state: &mut DefaultServerState, overflow: &str, field: &str, by: &str| {
+            run(state, &["BITFIELD", "counters", "OVERFLOW", overflow, "INCRBY", field, "0", by]).unwrap()
+        };
+
+        assert_eq!(incr(&mut state, "WRAP", "u2", "3"), ints(&[Some(3)]));
+        assert_eq!(incr(&mut state, "WRAP", "u2", "1"), ints(&[Some(0)]));
+        assert_eq!(incr(&mut state, "SAT", "u2", "10"), ints(&[Some(3)]));
+        assert_eq!(incr(&mut state, "FAIL", "u2", "1"), ints(&[None]));
+        assert_eq!(incr(&mut state, "fail", "u2", "-3"), ints(&[Some(0)]));
+        assert_eq!(incr(&mut state, "SAT", "u2", "-1"), ints(&[Some(0)]));
+
+        assert_eq!(incr(&mut state, "WRAP", "i4", "7"), ints(&[Some(7)]));
+        assert_eq!(incr(&mut state, "WRAP", "i4", "1"), ints(&[Some(-8)]));
+        assert_eq!(incr(&mut state, "SAT", "i4", "-100"), ints(&[Some(-8)]));
+        assert_eq!(incr(&mut state, "SAT", "i4", "100"), ints(&[Some(7)]));
+        assert_eq!(incr(&mut state, "FAIL", "i4", "1"), ints(&[None]));
+        assert_eq!(incr(&mut state, "SAT", "i64", "9223372036854775807"), ints(&[Some(i64::MAX)]));
+
+        // The policy applies to the writes after it, including SET.
+        assert_eq!(
+            run(&mut state, &["BITFIELD", "set", "SET", "u4", "0", "20", "OVERFLOW", "SAT", "SET", "u4", "4", "20"])
+                .unwrap(),
+            ints(&[Some(0), Some(0)])
+        );
+        assert_eq!(
+            run(&mut state, &["BITFIELD", "set", "GET", "u4", "0", "GET", "u4", "4"]).unwrap(),
+            ints(&[Some(4), Some(15)])
+        );
+        assert_eq!(
+            run(&mut state, &["BITFIELD", "set", "OVERFLOW", "FAIL", "SET", "i4", "0", "8"]).unwrap(),
+            ints(&[None])
+        );
+        assert_eq!(run(&mut state, &["BITFIELD", "set", "GET", "u4", "0"]).unwrap(), ints(&[Some(4)]));
+    }
+
+    #[test]
+    fn test_bitfield_errors_and_read_only() {
+        let mut state = DefaultServerState::default();
+        run(&mut state, &["BITFIELD", "key", "SET", "u8", "#1", "7"]).unwrap();
+
+        assert_eq!(
+            run(&mut state, &["BITFIELD_RO", "key", "GET", "u8", "8", "GET", "u4", "#3"]).unwrap(),
+            ints(&[Some(7), Some(7)])
+        );
+        assert_eq!(
+            run(&mut state, &["BITFIELD_RO", "key", "INCRBY", "u8", "8", "1"]).unwrap_err(),
+            "BITFIELD_RO only supports the GET subcommand"
+        );
+        let invalid_type =
+            "Invalid bitfield type. Use something like i16 u8. Note that u64 is not supported but i64 is.";
+        for field_type in ["u64", "i65", "i0", "x8", "u"] {
+            assert_eq!(run(&mut state, &["BITFIELD", "key", "GET", field_type, "0"]).unwrap_err(), invalid_type);
+        }
+        assert_eq!(
+            run(&mut state, &["BITFIELD", "key", "GET", "u8", "-1"]).unwrap_err(),
+            "bit offset is not an integer or out of range"
+        );
+        assert_eq!(
+            run(&mut state, &["BITFIELD", "key", "GET", "u8", "#536870912"]).unwrap_err(),
+            "bit offset is not an integer or out of range"
+        );
+        assert_eq!(
+            run(&mut state, &["BITFIELD", "key", "OVERFLOW", "NONE"]).unwrap_err(),
+            "Invalid OVERFLOW type specified"
+        );
+        assert_eq!(run(&mut state, &["BITFIELD", "key", "SET", "u8", "0"]).unwrap_err(), "syntax error");
+        assert_eq!(run(&mut state, &["BITFIELD", "key", "DEL", "u8", "0"]).unwrap_err(), "syntax error");
+        assert_eq!(run(&mut state, &["BITFIELD", "key"]).unwrap(), RespType::Array(Vec::new()));
+
+        // Parsing errors leave the value untouched.
+        assert!(run(&mut state, &["BITFIELD", "key", "SET", "u8", "0", "1", "GET", "u64", "0"]).is_err());
+        assert_eq!(run(&mut state, &["BITFIELD_RO", "key", "GET", "u16", "0"]).unwrap(), ints(&[Some(7)]));
+        run(&mut state, &["RPUSH", "list", "a"]).unwrap();
+        assert!(run(&mut state, &["BITFIELD_RO", "list", "GET", "u8", "0"]).unwrap_err().starts_with("WRONGTYPE"));
+    }
+}
@@ -0,0 +1,325 @@
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::process::{Child, Command};
+use std::thread::sleep;
+use std::time::Duration;
+
+/// Server process that is killed when the test finishes.
+struct Server(Child);
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+fn start_server(args: &[&str]) -> Server {
+    let child = Command::new(env!("CARGO_BIN_EXE_codecrafters-redis"))
+        .args(args)
+        .spawn()
+        .expect("failed to start server");
+    Server(child)
+}
+
+fn wait_for_server_ready(addr: &str) {
+    let start = std::time::Instant::now();
+    while start.elapsed() < Duration::from_secs(30) {
+        if TcpStream::connect(addr).is_ok() {
+            return;
+        }
+        sleep(Duration::from_millis(100));
+    }
+    panic!("Server did not start in time");
+}
+
+fn encode(parts: &[&str]) -> Vec<u8> {
+    let mut out = format!("*{}\r\n", parts.len()).into_bytes();
+    for part in parts {
+        out.extend(format!("${}\r\n{}\r\n", part.len(), part).into_bytes());
+    }
+    out
+}
+
+fn send_and_receive(stream: &mut TcpStream, parts: &[&str]) -> String {
+    stream.write_all(&encode(parts)).unwrap();
+    let mut buf = [0; 4096];
+    let n = stream.read(&mut buf).unwrap();
+    String::from_utf8_lossy(&buf[..n]).to_string()
+}
+
+/// Writes a `nodes.conf` for the node listening on `port` of a static cluster.
+fn write_nodes_conf(name: &str, port: u16, nodes: &[(&str, u16, &str)]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("{}-{}-{}.conf", name, std::process::id(), port));
+    let mut contents = String::new();
+    for (id, node_port, slots) in nodes {
+        let flags = if *node_port == port { "myself,master" } else { "master" };
+        contents.push_str(&format!(
+            "{} 127.0.0.1:{}@{} {} - 0 0 1 connected {}\n",
+            id,
+            node_port,
+            node_port + 10000,
+            flags,
+            slots
+        ));
+    }
+    contents.push_str("vars currentEpoch 1 lastVoteEpoch 0\n");
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn test_static_cluster_redirects() {
+    let nodes = [
+        ("a".repeat(40), 6440, "0-5460"),
+        ("b".repeat(40), 6441, "5461-10922"),
+        ("c".repeat(40), 6442, "10923-16383"),
+    ];
+    let nodes = nodes
+        .iter()
+        .map(|(id, port, slots)| (id.as_str(), *port, *slots))
+        .collect::<Vec<_>>();
+
+    let mut servers = Vec::new();
+    let mut configs = Vec::new();
+    for (_, port, _) in &nodes {
+        let config = write_nodes_conf("static", *port, &nodes);
+        let port = port.to_string();
+        servers.push(start_server(&[
+            "--port",
+            &port,
+            "--cluster-enabled",
+            "yes",
+            "--cluster-config-file",
+            config.to_str().unwrap(),
+        ]));
+        configs.push(config);
+    }
+    for (_, port, _) in &nodes {
+        wait_for_server_ready(&format!("127.0.0.1:{}", port));
+    }
+
+    // "foo" hashes to slot 12182, owned by the third node.
+    let mut first = TcpStream::connect("127.0.0.1:6440").unwrap();
+    assert_eq!(
+        send_and_receive(&mut first, &["SET", "foo", "bar"]),
+        "-MOVED 12182 127.0.0.1:6442\r\n"
+    );
+    let mut owner = TcpStream::connect("127.0.0.1:6442").unwrap();
+    assert_eq!(send_and_receive(&mut owner, &["SET", "foo", "bar"]), "+OK\r\n");
+    assert_eq!(send_and_receive(&mut owner, &["GET", "foo"]), "$3\r\nbar\r\n");
+
+    let slots = send_and_receive(&mut first, &["CLUSTER", "SLOTS"]);
+    assert!(slots.starts_with("*3\r\n*3\r\n:0\r\n:5460\r\n*3\r\n$9\r\n127.0.0.1\r\n:6440\r\n"));
+    let info = send_and_receive(&mut first, &["CLUSTER", "INFO"]);
+    assert!(info.contains("cluster_state:ok\r\n"));
+    assert!(info.contains("cluster_known_nodes:3\r\n"));
+
+    drop(servers);
+    for config in configs {
+        let _ = std::fs::remove_file(config);
+    }
+}
+
+/// Sends a command to the cluster through `port`, following redirections.
+fn cluster_request(port: u16, parts: &[&str]) -> String {
+    let mut port = port;
+    let mut asking = false;
+    for _ in 0..50 {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        if asking {
+            assert_eq!(send_and_receive(&mut stream, &["ASKING"]), "+OK\r\n");
+        }
+        let reply = send_and_receive(&mut stream, parts);
+
+        let redirect = reply
+            .strip_prefix("-MOVED ")
+            .map(|target| (target, false))
+            .or_else(|| reply.strip_prefix("-ASK ").map(|target| (target, true)));
+        if let Some((target, ask)) = redirect {
+            let endpoint = target.trim_end().split(' ').nth(1).unwrap();
+            port = endpoint.rsplit_once(':').unwrap().1.parse().unwrap();
+            asking = ask;
+            continue;
+        }
+        if reply.starts_with("-TRYAGAIN") {
+            sleep(Duration::from_millis(20));
+            continue;
+        }
+        return reply;
+    }
+    panic!("Too many redirections for {:?}", parts);
+}
+
+fn bulk_strings(reply: &str) -> Vec<String> {
+    reply
+        .split("\r\n")
+        .skip(1)
+        .filter(|line| !line.starts_with('$') && !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn node_id(port: u16) -> String {
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    bulk_strings(&format!("*1\r\n{}", send_and_receive(&mut stream, &["CLUSTER", "MYID"])))[0].clone()
+}
+
+fn run_on(port: u16, parts: &[&str]) -> String {
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    send_and_receive(&mut stream, parts)
+}
+
+#[test]
+fn test_reshard_without_losing_keys() {
+    let ports = [6450u16, 6451, 6452];
+    let mut servers = Vec::new();
+    let mut configs = Vec::new();
+    for port in ports {
+        let config = std::env::temp_dir().join(format!("reshard-{}-{}.conf", std::process::id(), port));
+        let _ = std::fs::remove_file(&config);
+        let port_arg = port.to_string();
+        servers.push(start_server(&[
+            "--port",
+            &port_arg,
+            "--cluster-enabled",
+            "yes",
+            "--cluster-config-file",
+            config.to_str().unwrap(),
+            "--cluster-node-timeout",
+            "2000",
+        ]));
+        configs.push(config);
+    }
+    for port in ports {
+        wait_for_server_ready(&format!("127.0.0.1:{}", port));
+    }
+
+    assert_eq!(run_on(6450, &["CLUSTER", "MEET", "127.0.0.1", "6451"]), "+OK\r\n");
+    assert_eq!(run_on(6450, &["CLUSTER", "MEET", "127.0.0.1", "6452"]), "+OK\r\n");
+    run_on(6450, &["CLUSTER", "ADDSLOTSRANGE", "0", "5460"]);
+    run_on(6451, &["CLUSTER", "ADDSLOTSRANGE", "5461", "10922"]);
+    run_on(6452, &["CLUSTER", "ADDSLOTSRANGE", "10923", "16383"]);
+
+    let mut converged = false;
+    for _ in 0..100 {
+        converged = ports.iter().all(|port| {
+            let info = run_on(*port, &["CLUSTER", "INFO"]);
+            info.contains("cluster_state:ok") && info.contains("cluster_known_nodes:3")
+        });
+        if converged {
+            break;
+        }
+        sleep(Duration::from_millis(100));
+    }
+    assert!(converged, "cluster did not converge");
+
+    let keys = (0..300).map(|i| format!("key:{}", i)).collect::<Vec<_>>();
+    for key in &keys {
+        assert_eq!(cluster_request(6450, &["SET", key, "initial"]), "+OK\r\n");
+    }
+    // Keys with a TTL, in the same slots as some of the keys above.
+    let ttl_keys = (0..300).step_by(10).map(|i| format!("{{key:{}}}:ttl", i)).collect::<Vec<_>>();
+    let ttl_started = std::time::Instant::now();
+    for key in &ttl_keys {
+        assert_eq!(cluster_request(6450, &["SET", key, "short", "PX", "6000"]), "+OK\r\n");
+    }
+    let ttl_set_at = std::time::Instant::now();
+
+    // Keep overwriting keys while their slots move, remembering the last acknowledged value.
+    let stop = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let writer = {
+        let stop = std::sync::Arc::clone(&stop);
+        let keys = keys.clone();
+        std::thread::spawn(move || {
+            let mut last = std::collections::HashMap::new();
+            let mut n = 0;
+            while !stop.load(std::sync::atomic::Ordering::Relaxed) {
+                let key = &keys[n % keys.len()];
+                let value = format!("v{}", n);
+                assert_eq!(cluster_request(6452, &["SET", key, &value]), "+OK\r\n");
+                last.insert(key.clone(), value);
+                n += 1;
+            }
+            last
+        })
+    };
+
+    // Move every slot of the first node that holds keys to the second one.
+    let source = node_id(6450);
+    let target = node_id(6451);
+    let mut slots = keys
+        .iter()
+        .map(|key| codecrafters_redis::resp::cluster::key_hash_slot(key))
+        .filter(|slot| *slot <= 5460)
+        .collect::<Vec<_>>();
+    slots.sort();
+    slots.dedup();
+    assert!(!slots.is_empty());
+
+    for slot in &slots {
+        let slot = slot.to_string();
+        assert_eq!(run_on(6451, &["CLUSTER", "SETSLOT", &slot, "IMPORTING", &source]), "+OK\r\n");
+        assert_eq!(run_on(6450, &["CLUSTER", "SETSLOT", &slot, "MIGRATING", &target]), "+OK\r\n");
+        loop {
+            let batch = bulk_strings(&run_on(6450, &["CLUSTER", "GETKEYSINSLOT", &slot, "10"]));
+            if batch.is_empty() {
+                break;
+            }
+            let mut migrate = vec!["MIGRATE", "127.0.0.1", "6451", "", "0", "5000", "KEYS"];
+            migrate.extend(batch.iter().map(String::as_str));
+            let reply = run_on(6450, &migrate);
+            assert!(reply == "+OK\r\n" || reply == "+NOKEY\r\n", "MIGRATE failed: {}", reply);
+        }
+        assert_eq!(run_on(6451, &["CLUSTER", "SETSLOT", &slot, "NODE", &target]), "+OK\r\n");
+        assert_eq!(run_on(6450, &["CLUSTER", "SETSLOT", &slot, "NODE", &target]), "+OK\r\n");
+    }
+
+    stop.store(true, std::sync::atomic::Ordering::Relaxed);
+    let last = writer.join().unwrap();
+
+    for key in &keys {
+        let expected = last.get(key).map(String::as_str).unwrap_or("initial");
+        assert_eq!(
+            cluster_request(6450, &["GET", key]),
+            format!("${}\r\n{}\r\n", expected.len(), expected),
+            "lost update of {}",
+            key
+        );
+    }
+    for slot in &slots {
+        assert_eq!(run_on(6450, &["CLUSTER", "COUNTKEYSINSLOT", &slot.to_string()]), ":0\r\n");
+    }
+
+    // TTLs moved with the keys.
+    assert!(ttl_started.elapsed() < Duration::from_secs(6));
+    for key in &ttl_keys {
+        assert_eq!(cluster_request(6450, &["GET", key]), "$5\r\nshort\r\n");
+    }
+    sleep(Duration::from_secs(6).saturating_sub(ttl_set_at.elapsed()) + Duration::from_millis(200));
+    for key in &ttl_keys {
+        assert_eq!(cluster_request(6450, &["GET", key]), "$-1\r\n");
+    }
+
+    // The third node learned about the new owner through gossip.
+    let moved = format!("-MOVED {} 127.0.0.1:6451\r\n", slots[0]);
+    let mut learned = false;
+    for _ in 0..50 {
+        let key = keys
+            .iter()
+            .find(|key| codecrafters_redis::resp::cluster::key_hash_slot(key) == slots[0])
+            .unwrap();
+        if run_on(6452, &["GET", key]) == moved {
+            learned = true;
+            break;
+        }
+        sleep(Duration::from_millis(100));
+    }
+    assert!(learned);
+
+    drop(servers);
+    for config in configs {
+        let _ = std::fs::remove_file(config);
+    }
+}
@@ -0,0 +1,437 @@
+/// Integration tests for cluster mode
+#[cfg(test)]
+mod test_slots {
+    use codecrafters_redis::resp::cluster::slot::{crc16, key_hash_slot, slot_ranges};
+
+    #[test]
+    fn test_crc16() {
+        assert_eq!(crc16(b"123456789"), 0x31c3);
+        assert_eq!(key_hash_slot("foo"), 12182);
+        assert_eq!(key_hash_slot("bar"), 5061);
+    }
+
+    #[test]
+    fn test_hash_tags() {
+        assert_eq!(key_hash_slot("{user1000}.following"), key_hash_slot("{user1000}.followers"));
+        assert_eq!(key_hash_slot("{user1000}.following"), key_hash_slot("user1000"));
+        // Empty tags hash the whole key, only the first tag counts.
+        assert_eq!(key_hash_slot("foo{}{bar}"), crc16(b"foo{}{bar}") % 16384);
+        assert_eq!(key_hash_slot("foo{{bar}}zap"), key_hash_slot("{bar"));
+        assert_eq!(key_hash_slot("foo{bar}{zap}"), key_hash_slot("bar"));
+    }
+
+    #[test]
+    fn test_slot_ranges() {
+        assert_eq!(slot_ranges([0, 1, 2, 5, 7, 8]), vec![(0, 2), (5, 5), (7, 8)]);
+        assert!(slot_ranges([]).is_empty());
+    }
+}
+
+#[cfg(test)]
+mod test_cluster_state {
+    use codecrafters_redis::resp::cluster::{key_hash_slot, ClusterState};
+    use codecrafters_redis::resp::replication::MasterAddr;
+
+    const NODES_CONF: &str = "\
+aaaa 127.0.0.1:7000@17000 myself,master - 0 0 1 connected 0-8191
+bbbb 127.0.0.1:7001@17001 master - 0 0 2 connected 8192-16383
+cccc 127.0.0.1:7002@17002 slave bbbb 0 0 2 connected
+vars currentEpoch 2 lastVoteEpoch 0
+";
+
+    fn addr(port: u16) -> MasterAddr {
+        MasterAddr {
+            host: "127.0.0.1".to_string(),
+            port,
+        }
+    }
+
+    fn cluster() -> ClusterState {
+        ClusterState::parse(NODES_CONF, addr(7000)).unwrap()
+    }
+
+    #[test]
+    fn test_parse_nodes_conf() {
+        let cluster = cluster();
+
+        assert_eq!(cluster.myself, "aaaa");
+        assert_eq!(cluster.current_epoch, 2);
+        assert_eq!(cluster.slots_assigned(), 16384);
+        assert_eq!(cluster.slots_of("bbbb"), vec![(8192, 16383)]);
+        assert_eq!(cluster.replicas_of("bbbb")[0].id, "cccc");
+        assert_eq!(cluster.slot_owner(100).unwrap().addr, addr(7000));
+        assert!(cluster.info().starts_with("cluster_state:ok\r\n"));
+    }
+
+    #[test]
+    fn test_nodes_description_round_trip() {
+        let cluster = cluster();
+        let description = cluster.nodes_description();
+
+        assert!(description.contains("aaaa 127.0.0.1:7000@17000 myself,master - 0 0 1 connected 0-8191\n"));
+        let reparsed = ClusterState::parse(&description, addr(7000)).unwrap();
+        assert_eq!(reparsed.nodes, cluster.nodes);
+        assert_eq!(reparsed.slot_map().len(), 2);
+    }
+
+    #[test]
+    fn test_missing_myself() {
+        assert!(ClusterState::parse("bbbb 127.0.0.1:7001@17001 master - 0 0 2 connected", addr(7000)).is_err());
+    }
+
+    #[test]
+    fn test_route() {
+        let mut cluster = cluster();
+        let local = "bar";
+        let remote = "foo";
+        assert!(key_hash_slot(local) < 8192 && key_hash_slot(remote) >= 8192);
+
+        assert_eq!(cluster.route(&[local], 0, false), Ok(()));
+        assert_eq!(
+            cluster.route(&[remote], 0, false),
+            Err(format!("MOVED {} 127.0.0.1:7001", key_hash_slot(remote)))
+        );
+        assert_eq!(
+            cluster.route(&[local, remote], 0, false),
+            Err("CROSSSLOT Keys in request don't hash to the same slot".to_string())
+        );
+
+        cluster.unassign(key_hash_slot(local));
+        assert_eq!(
+            cluster.route(&[local], 0, false),
+            Err("CLUSTERDOWN Hash slot not served".to_string())
+        );
+    }
+
+    #[test]
+    fn test_route_during_migration() {
+        let mut cluster = cluster();
+        let slot = key_hash_slot("{bar}a");
+        cluster.migrating.insert(slot, "bbbb".to_string());
+
+        // Keys still present are served here, missing ones were already moved.
+        assert_eq!(cluster.route(&["{bar}a"], 0, false), Ok(()));
+        assert_eq!(
+            cluster.route(&["{bar}a"], 1, false),
+            Err(format!("ASK {} 127.0.0.1:7001", slot))
+        );
+        assert!(cluster.route(&["{bar}a", "{bar}b"], 1, false).unwrap_err().starts_with("TRYAGAIN"));
+
+        let slot = key_hash_slot("foo");
+        cluster.importing.insert(slot, "bbbb".to_string());
+        assert!(cluster.route(&["foo"], 1, false).unwrap_err().starts_with("MOVED"));
+        assert_eq!(cluster.route(&["foo"], 1, true), Ok(()));
+    }
+
+    #[test]
+    fn test_open_creates_config_file() {
+        let path = std::env::temp_dir().join(format!("nodes-{}-open.conf", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut cluster = ClusterState::open(&path, addr(7000)).unwrap();
+        let myself = cluster.myself.clone();
+        cluster.assign(42, &myself).unwrap();
+        cluster.save().unwrap();
+
+        let reopened = ClusterState::open(&path, addr(7000)).unwrap();
+        assert_eq!(reopened.myself, myself);
+        assert_eq!(reopened.slots_of(&myself), vec![(42, 42)]);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod test_cluster_command {
+    use codecrafters_redis::resp::client::ClientContext;
+    use codecrafters_redis::resp::cluster::{key_hash_slot, ClusterState};
+    use codecrafters_redis::resp::command_dispatcher::CommandDispatcher;
+    use codecrafters_redis::resp::protocol::RespType;
+    use codecrafters_redis::resp::replication::MasterAddr;
+    use codecrafters_redis::resp::state::default_server_state::DefaultServerState;
+    use codecrafters_redis::resp::state::server_state::ServerState;
+
+    fn bulk(s: &str) -> RespType {
+        RespType::BulkString(Some(s.to_string()))
+    }
+
+    fn cluster_state() -> DefaultServerState {
+        let addr = MasterAddr {
+            host: "127.0.0.1".to_string(),
+            port: 7000,
+        };
+        DefaultServerState::default().with_cluster(ClusterState::new(addr))
+    }
+
+    fn run(
+        state: &mut DefaultServerState,
+        client: &mut ClientContext,
+        args: &[&str],
+    ) -> Result<RespType, String> {
+        CommandDispatcher::new().dispatch(args[0], args[1..].iter().map(|arg| bulk(arg)).collect(), state, client)
+    }
+
+    #[test]
+    fn test_disabled() {
+        let mut state = DefaultServerState::default();
+        let result = run(&mut state, &mut ClientContext::default(), &["CLUSTER", "KEYSLOT", "foo"]);
+        assert_eq!(result.unwrap_err(), "This instance has cluster support disabled");
+    }
+
+    #[test]
+    fn test_addslots_and_slots() {
+        let mut state = cluster_state();
+        let client = &mut ClientContext::default();
+        let myid = state.cluster().unwrap().myself.clone();
+
+        assert_eq!(
+            run(&mut state, client, &["SET", "foo", "bar"]).unwrap_err(),
+            "CLUSTERDOWN Hash slot not served"
+        );
+        run(&mut state, client, &["CLUSTER", "ADDSLOTSRANGE", "0", "16383"]).unwrap();
+        assert!(run(&mut state, client, &["CLUSTER", "ADDSLOTS", "5"]).is_err());
+        run(&mut state, client, &["SET", "foo", "bar"]).unwrap();
+
+        assert_eq!(
+            run(&mut state, client, &["CLUSTER", "SLOTS"]).unwrap(),
+            RespType::Array(vec![RespType::Array(vec![
+                RespType::Integer(0),
+                RespType::Integer(16383),
+                RespType::Array(vec![bulk("127.0.0.1"), RespType::Integer(7000), bulk(&myid)]),
+            ])])
+        );
+        assert_eq!(
+            run(&mut state, client, &["CLUSTER", "MYID"]).unwrap(),
+            bulk(&myid)
+        );
+
+        run(&mut state, client, &["CLUSTER", "DELSLOTS", "100", "101"]).unwrap();
+        let RespType::BulkString(Some(nodes)) = run(&mut state, client, &["CLUSTER", "NODES"]).unwrap() else {
+            panic!("CLUSTER NODES must return a bulk string");
+        };
+        assert!(nodes.ends_with("connected 0-99 102-16383\n"));
+    }
+
+    #[test]
+    fn test_keys_in_slot() {
+        let mut state = cluster_state();
+        let client = &mut ClientContext::default();
+        run(&mut state, client, &["CLUSTER", "ADDSLOTSRANGE", "0", "16383"]).unwrap();
+        for key in ["{user}a", "{user}b", "{user}c", "other"] {
+            run(&mut state, client, &["SET", key, "1"]).unwrap();
+        }
+        let slot = key_hash_slot("user").to_string();
+
+        assert_eq!(
+            run(&mut state, client, &["CLUSTER", "KEYSLOT", "{user}a"]).unwrap(),
+            RespType::Integer(key_hash_slot("user") as i64)
+        );
+        assert_eq!(
+            run(&mut state, client, &["CLUSTER", "COUNTKEYSINSLOT", &slot]).unwrap(),
+            RespType::Integer(3)
+        );
+        assert_eq!(
+            run(&mut state, client, &["CLUSTER", "GETKEYSINSLOT", &slot, "2"]).unwrap(),
+            RespType::Array(vec![bulk("{user}a"), bulk("{user}b")])
+        );
+        assert!(run(&mut state, client, &["CLUSTER", "COUNTKEYSINSLOT", "16384"]).is_err());
+    }
+
+    #[test]
+    fn test_asking_applies_to_next_command_only() {
+        let mut state = cluster_state();
+        let slot = key_hash_slot("foo");
+        let cluster = state.cluster().unwrap();
+        let other = ClusterState::new(MasterAddr {
+            host: "127.0.0.1".to_string(),
+            port: 7001,
+        });
+        let other_id = other.myself.clone();
+        cluster.nodes.insert(other_id.clone(), other.myself().clone());
+        cluster.assign(slot, &other_id).unwrap();
+        cluster.importing.insert(slot, other_id);
+
+        let client = &mut ClientContext::default();
+        assert!(run(&mut state, client, &["GET", "foo"]).unwrap_err().starts_with("MOVED"));
+        run(&mut state, client, &["ASKING"]).unwrap();
+        assert_eq!(run(&mut state, client, &["GET", "foo"]).unwrap(), RespType::BulkString(None));
+        assert!(run(&mut state, client, &["GET", "foo"]).unwrap_err().starts_with("MOVED"));
+    }
+}
+
+#[cfg(test)]
+mod test_gossip {
+    use std::time::Duration;
+
+    use codecrafters_redis::resp::client::{BlockedOn, ClientContext};
+    use codecrafters_redis::resp::cluster::gossip::{GossipEntry, Heartbeat, NodeFlag};
+    use codecrafters_redis::resp::cluster::{key_hash_slot, ClusterState};
+    use codecrafters_redis::resp::command_dispatcher::CommandDispatcher;
+    use codecrafters_redis::resp::protocol::RespType;
+    use codecrafters_redis::resp::replication::MasterAddr;
+    use codecrafters_redis::resp::state::default_server_state::DefaultServerState;
+    use codecrafters_redis::resp::state::server_state::ServerState;
+    use codecrafters_redis::resp::state::value::Value;
+
+    fn addr(port: u16) -> MasterAddr {
+        MasterAddr {
+            host: "127.0.0.1".to_string(),
+            port,
+        }
+    }
+
+    fn heartbeat(id: &str, port: u16, config_epoch: u64, slots: Vec<(u16, u16)>) -> Heartbeat {
+        Heartbeat {
+            id: id.to_string(),
+            addr: addr(port),
+            config_epoch,
+            current_epoch: config_epoch,
+            master_id: None,
+            slots,
+            gossip: Vec::new(),
+        }
+    }
+
+    fn run(state: &mut DefaultServerState, args: &[&str]) -> Result<RespType, String> {
+        let args = args[1..].iter().map(|arg| RespType::BulkString(Some(arg.to_string()))).collect();
+        CommandDispatcher::new().dispatch("CLUSTER", args, state, &mut ClientContext::default())
+    }
+
+    #[test]
+    fn test_heartbeat_round_trip() {
+        let mut heartbeat = heartbeat("bbbb", 7001, 3, vec![(0, 10), (20, 20)]);
+        heartbeat.master_id = Some("aaaa".to_string());
+        heartbeat.gossip.push(GossipEntry {
+            id: "cccc".to_string(),
+            addr: addr(7002),
+            flag: NodeFlag::PFail,
+        });
+
+        let args = heartbeat.to_args();
+        let parsed = Heartbeat::parse(&args.iter().map(String::as_str).collect::<Vec<_>>()).unwrap();
+
+        assert_eq!(parsed, heartbeat);
+        assert!(Heartbeat::parse(&["bbbb", "127.0.0.1"]).is_err());
+    }
+
+    #[test]
+    fn test_discovery_and_slot_claims() {
+        let mut cluster = ClusterState::new(addr(7000));
+        let myself = cluster.myself.clone();
+        cluster.assign(0, &myself).unwrap();
+
+        let mut sender = heartbeat("bbbb", 7001, 0, vec![(0, 1)]);
+        sender.gossip.push(GossipEntry {
+            id: "cccc".to_string(),
+            addr: addr(7002),
+            flag: NodeFlag::Ok,
+        });
+        assert!(cluster.receive_heartbeat(sender));
+
+        assert_eq!(cluster.nodes.len(), 3);
+        // Unassigned slots are taken, ours are kept while the sender's epoch is not newer.
+        assert_eq!(cluster.slot_owner(1).unwrap().id, "bbbb");
+        assert_eq!(cluster.slot_owner(0).unwrap().id, myself);
+
+        assert!(cluster.receive_heartbeat(heartbeat("bbbb", 7001, 5, vec![(0, 1)])));
+        assert_eq!(cluster.slot_owner(0).unwrap().id, "bbbb");
+        assert_eq!(cluster.current_epoch, 5);
+    }
+
+    #[test]
+    fn test_importing_slots_are_not_overridden() {
+        let mut cluster = ClusterState::new(addr(7000));
+        cluster.receive_heartbeat(heartbeat("bbbb", 7001, 1, vec![(0, 0)]));
+        cluster.importing.insert(0, "bbbb".to_string());
+        cluster.receive_heartbeat(heartbeat("cccc", 7002, 9, vec![(0, 0)]));
+
+        assert_eq!(cluster.slot_owner(0).unwrap().id, "bbbb");
+    }
+
+    #[test]
+    fn test_failure_needs_majority() {
+        let mut cluster = ClusterState::new(addr(7000));
+        let myself = cluster.myself.clone();
+        cluster.assign(0, &myself).unwrap();
+        cluster.node_timeout = Duration::from_millis(10);
+        cluster.receive_heartbeat(heartbeat("bbbb", 7001, 1, vec![(1, 1)]));
+        cluster.receive_heartbeat(heartbeat("cccc", 7002, 2, vec![(2, 2)]));
+
+        std::thread::sleep(Duration::from_millis(20));
+        let mut from_b = heartbeat("bbbb", 7001, 1, vec![(1, 1)]);
+        cluster.detect_failures();
+        assert!(cluster.health("cccc").unwrap().pfail);
+        assert!(!cluster.health("cccc").unwrap().fail);
+        assert!(cluster.nodes_description().contains("master,fail? -"));
+
+        // A second primary agreeing makes a majority of the three.
+        from_b.gossip.push(GossipEntry {
+            id: "cccc".to_string(),
+            addr: addr(7002),
+            flag: NodeFlag::PFail,
+        });
+        cluster.receive_heartbeat(from_b);
+        assert!(cluster.health("cccc").unwrap().fail);
+        assert!(cluster.info().starts_with("cluster_state:fail"));
+
+        cluster.receive_heartbeat(heartbeat("cccc", 7002, 2, vec![(2, 2)]));
+        assert!(!cluster.health("cccc").unwrap().fail);
+    }
+
+    #[test]
+    fn test_setslot() {
+        let mut state = DefaultServerState::default().with_cluster(ClusterState::new(addr(7000)));
+        let cluster = state.cluster().unwrap();
+        let myself = cluster.myself.clone();
+        cluster.receive_heartbeat(heartbeat("bbbb", 7001, 0, vec![(100, 100)]));
+        let slot = key_hash_slot("foo");
+        cluster.assign(slot, &myself).unwrap();
+        let slot = slot.to_string();
+
+        assert!(run(&mut state, &["CLUSTER", "SETSLOT", "100", "MIGRATING", "bbbb"]).is_err());
+        assert!(run(&mut state, &["CLUSTER", "SETSLOT", &slot, "IMPORTING", "bbbb"]).is_err());
+        assert!(run(&mut state, &["CLUSTER", "SETSLOT", &slot, "MIGRATING", "nobody"]).is_err());
+        run(&mut state, &["CLUSTER", "SETSLOT", &slot, "MIGRATING", "bbbb"]).unwrap();
+        let RespType::BulkString(Some(nodes)) = run(&mut state, &["CLUSTER", "NODES"]).unwrap() else {
+            panic!("CLUSTER NODES must return a bulk string");
+        };
+        assert!(nodes.contains(&format!("[{}->-bbbb]", slot)));
+
+        state.set("foo".to_string(), Value::String(b"bar".to_vec()), None).unwrap();
+        assert!(run(&mut state, &["CLUSTER", "SETSLOT", &slot, "NODE", "bbbb"])
+            .unwrap_err()
+            .starts_with("Can't assign hashslot"));
+        state.del("foo").unwrap();
+        run(&mut state, &["CLUSTER", "SETSLOT", &slot, "NODE", "bbbb"]).unwrap();
+        let cluster = state.cluster().unwrap();
+        assert_eq!(cluster.slot_owner(slot.parse().unwrap()).unwrap().id, "bbbb");
+        assert!(cluster.migrating.is_empty());
+
+        // Finishing an import takes a new epoch.
+        run(&mut state, &["CLUSTER", "SETSLOT", "100", "IMPORTING", "bbbb"]).unwrap();
+        run(&mut state, &["CLUSTER", "SETSLOT", "100", "NODE", &myself]).unwrap();
+        let cluster = state.cluster().unwrap();
+        assert_eq!(cluster.slot_owner(100).unwrap().id, myself);
+        assert!(cluster.myself().config_epoch > 0);
+    }
+
+    #[test]
+    fn test_migrate_arguments() {
+        let mut state = DefaultServerState::default();
+        let dispatcher = CommandDispatcher::new();
+        let mut client = ClientContext::default();
+        let args = ["127.0.0.1", "7001", "", "0", "5000", "REPLACE", "KEYS", "a", "b"]
+            .iter()
+            .map(|arg| RespType::BulkString(Some(arg.to_string())))
+            .collect();
+
+        dispatcher.dispatch("MIGRATE", args, &mut state, &mut client).unwrap();
+
+        let Some(BlockedOn::Migrate(request)) = client.blocked else {
+            panic!("MIGRATE must hand the transfer to the connection");
+        };
+        assert_eq!(request.addr, addr(7001));
+        assert_eq!(request.keys, vec!["a".to_string(), "b".to_string()]);
+        assert!(request.replace && !request.copy);
+        assert_eq!(request.timeout, Duration::from_millis(5000));
+    }
+}
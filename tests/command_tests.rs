@@ -107,6 +107,7 @@ mod test_set {
     use codecrafters_redis::resp::commands::{Command, Set};
     use codecrafters_redis::resp::protocol::RespType;
     use codecrafters_redis::resp::state::default_server_state::DefaultServerState;
+    use codecrafters_redis::resp::state::server_state::ServerState;
 
     #[test]
     fn test_set() {
@@ -163,12 +164,20 @@ mod test_set {
         let args = vec![
             RespType::BulkString(Option::from("key".to_string())),
             RespType::BulkString(Option::from("value".to_string())),
+            RespType::BulkString(Option::from("EX".to_string())),
             RespType::Integer(60), // TTL in seconds
         ];
 
         let result = cmd.execute(&args, &mut state).unwrap();
 
         assert_eq!(result, RespType::SimpleString("OK".to_string()));
+        assert!(state.ttl("key").unwrap().is_some_and(|ttl| ttl > 59_000));
+
+        // A bare TTL is not an option.
+        let mut args = args;
+        args.remove(2);
+        args[2] = RespType::BulkString(Option::from("60".to_string()));
+        assert_eq!(cmd.execute(&args, &mut state).unwrap_err(), "syntax error");
     }
 }
 
@@ -253,3 +262,103 @@ mod test_get {
         assert_eq!(result, RespType::BulkString(None));
     }
 }
+
+#[cfg(test)]
+mod test_dump_restore {
+    use codecrafters_redis::resp::commands::{Command, Del, Dump, Restore, Set};
+    use codecrafters_redis::resp::protocol::RespType;
+    use codecrafters_redis::resp::state::default_server_state::DefaultServerState;
+    use codecrafters_redis::resp::state::server_state::ServerState;
+    use codecrafters_redis::resp::state::value::Value;
+
+    fn bulk(s: &str) -> RespType {
+        RespType::BulkString(Some(s.to_string()))
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut state = DefaultServerState::default();
+        Set.execute(&[bulk("key"), bulk("value")], &mut state).unwrap();
+
+        let payload = Dump.execute(&[bulk("key")], &mut state).unwrap();
+        assert!(matches!(payload, RespType::BulkBytes(_) | RespType::BulkString(Some(_))));
+        assert_eq!(
+            Restore.execute(&[bulk("key"), bulk("0"), payload.clone()], &mut state).unwrap_err(),
+            "BUSYKEY Target key name already exists."
+        );
+
+        Restore
+            .execute(&[bulk("copy"), bulk("5000"), payload.clone()], &mut state)
+            .unwrap();
+        assert_eq!(state.get("copy"), Some(&Value::String(b"value".to_vec())));
+        assert!(state.expires_at("copy").is_some());
+
+        Restore
+            .execute(&[bulk("copy"), bulk("0"), payload, bulk("REPLACE")], &mut state)
+            .unwrap();
+        assert_eq!(state.expires_at("copy"), None);
+    }
+
+    #[test]
+    fn test_missing_key_and_bad_payload() {
+        let mut state = DefaultServerState::default();
+
+        assert_eq!(Dump.execute(&[bulk("nope")], &mut state).unwrap(), RespType::BulkString(None));
+        assert_eq!(
+            Restore.execute(&[bulk("key"), bulk("0"), bulk("00zz")], &mut state).unwrap_err(),
+            "DUMP payload version or checksum are wrong"
+        );
+    }
+
+    #[test]
+    fn test_del() {
+        let mut state = DefaultServerState::default();
+        Set.execute(&[bulk("a"), bulk("1")], &mut state).unwrap();
+        Set.execute(&[bulk("b"), bulk("2")], &mut state).unwrap();
+
+        let result = Del.execute(&[bulk("a"), bulk("b"), bulk("c")], &mut state).unwrap();
+
+        assert_eq!(result, RespType::Integer(2));
+        assert_eq!(state.get("a"), None);
+    }
+}
+
+#[cfg(test)]
+mod test_type {
+    use codecrafters_redis::resp::commands::{Command, Get, Set, Type};
+    use codecrafters_redis::resp::protocol::RespType;
+    use codecrafters_redis::resp::state::default_server_state::DefaultServerState;
+    use codecrafters_redis::resp::state::server_state::ServerState;
+    use codecrafters_redis::resp::state::value::Value;
+
+    fn bulk(s: &str) -> RespType {
+        RespType::BulkString(Some(s.to_string()))
+    }
+
+    #[test]
+    fn test_type_names() {
+        let mut state = DefaultServerState::default();
+        Set.execute(&[bulk("string"), bulk("1")], &mut state).unwrap();
+        state.set("list".to_string(), Value::List(vec!["a".to_string()].into()), None).unwrap();
+        state.set("hash".to_string(), Value::Hash(Default::default()), None).unwrap();
+        state.set("set".to_string(), Value::Set(Default::default()), None).unwrap();
+
+        for (key, name) in [("string", "string"), ("list", "list"), ("hash", "hash"), ("set", "set"), ("nope", "none")] {
+            assert_eq!(
+                Type.execute(&[bulk(key)], &mut state).unwrap(),
+                RespType::SimpleString(name.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_get_wrong_type() {
+        let mut state = DefaultServerState::default();
+        state.set("list".to_string(), Value::List(vec!["a".to_string()].into()), None).unwrap();
+
+        assert_eq!(
+            Get.execute(&[bulk("list")], &mut state).unwrap_err(),
+            "WRONGTYPE Operation against a key holding the wrong kind of value"
+        );
+    }
+}
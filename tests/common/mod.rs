@@ -0,0 +1,56 @@
+//! Helpers shared by the integration tests that run commands through the dispatcher.
+#![allow(dead_code)]
+
+use codecrafters_redis::resp::client::ClientContext;
+use codecrafters_redis::resp::command_dispatcher::CommandDispatcher;
+use codecrafters_redis::resp::protocol::RespType;
+use codecrafters_redis::resp::state::default_server_state::DefaultServerState;
+
+pub fn bulk(s: &str) -> RespType {
+    RespType::BulkString(Some(s.to_string()))
+}
+
+pub fn ints(values: &[i64]) -> RespType {
+    RespType::Array(values.iter().map(|value| RespType::Integer(*value)).collect())
+}
+
+pub fn ok() -> RespType {
+    RespType::SimpleString("OK".to_string())
+}
+
+pub fn run(state: &mut DefaultServerState, args: &[&str]) -> Result<RespType, String> {
+    let mut client = ClientContext::default();
+    CommandDispatcher::new().dispatch(args[0], args[1..].iter().map(|arg| bulk(arg)).collect(), state, &mut client)
+}
+
+/// Flattens a reply to its strings and integers.
+pub fn strings(reply: RespType) -> Vec<String> {
+    match reply {
+        RespType::Array(items) => items.into_iter().flat_map(strings).collect(),
+        RespType::BulkString(Some(value)) => vec![value],
+        RespType::Integer(value) => vec![value.to_string()],
+        RespType::BulkString(None) | RespType::NullArray => vec!["(nil)".to_string()],
+        other => panic!("unexpected reply {:?}", other),
+    }
+}
+
+/// Binary payload of `DUMP key`.
+pub fn dump(state: &mut DefaultServerState, key: &str) -> Vec<u8> {
+    match run(state, &["DUMP", key]).unwrap() {
+        RespType::BulkString(Some(payload)) => payload.into_bytes(),
+        RespType::BulkBytes(payload) => payload,
+        other => panic!("DUMP must return a payload, got {:?}", other),
+    }
+}
+
+/// `RESTORE key 0 payload`, recreating the key without TTL.
+pub fn restore(state: &mut DefaultServerState, key: &str, payload: &[u8]) -> Result<RespType, String> {
+    let args = vec![bulk(key), bulk("0"), RespType::bulk_bytes(payload.to_vec())];
+    CommandDispatcher::new().dispatch("RESTORE", args, state, &mut ClientContext::default())
+}
+
+/// Copies `key` to `copy` through `DUMP` and `RESTORE`.
+pub fn dump_and_restore(state: &mut DefaultServerState, key: &str, copy: &str) {
+    let payload = dump(state, key);
+    restore(state, copy, &payload).unwrap();
+}
@@ -7,7 +7,7 @@ use std::time::Duration;
 
 fn start_server() -> Child {
     Command::new("cargo")
-        .args(&["run"])
+        .args(["run"])
         .spawn()
         .expect("failed to start server")
 }
@@ -28,7 +28,7 @@ static START: Once = Once::new();
 
 fn start_server_once() {
     START.call_once(|| {
-        let mut child = start_server();
+        let child = start_server();
         wait_for_server_ready("127.0.0.1:6379");
         // Leak the child so it lives for the duration of the tests
         std::mem::forget(child);
@@ -114,3 +114,95 @@ fn test_get_set() {
 
     assert!(stream.shutdown(std::net::Shutdown::Both).is_ok());
 }
+
+fn encode(parts: &[&str]) -> Vec<u8> {
+    let mut out = format!("*{}\r\n", parts.len()).into_bytes();
+    for part in parts {
+        out.extend_from_slice(format!("${}\r\n{}\r\n", part.len(), part).as_bytes());
+    }
+    out
+}
+
+fn read_reply(stream: &mut TcpStream) -> Vec<u8> {
+    let mut buf = [0; 128];
+    let n = stream.read(&mut buf).unwrap();
+    buf[..n].to_vec()
+}
+
+#[test]
+fn test_blocking_pop_serves_longest_waiting_client_first() {
+    start_server_once();
+    let mut first = TcpStream::connect("127.0.0.1:6379").unwrap();
+    let mut second = TcpStream::connect("127.0.0.1:6379").unwrap();
+    let mut pusher = TcpStream::connect("127.0.0.1:6379").unwrap();
+
+    first.write_all(&encode(&["BLPOP", "e2e:queue:other", "e2e:queue", "0"])).unwrap();
+    sleep(Duration::from_millis(100));
+    second.write_all(&encode(&["BLPOP", "e2e:queue", "5"])).unwrap();
+    sleep(Duration::from_millis(100));
+
+    let response = send_and_receive(&mut pusher, &encode(&["RPUSH", "e2e:queue", "a"]));
+    assert_eq!(response, b":1\r\n");
+    assert_eq!(read_reply(&mut first), b"*2\r\n$9\r\ne2e:queue\r\n$1\r\na\r\n");
+
+    // The second client is still blocked and gets the next element.
+    second.set_read_timeout(Some(Duration::from_millis(100))).unwrap();
+    assert!(second.read(&mut [0; 16]).is_err());
+    second.set_read_timeout(None).unwrap();
+    send_and_receive(&mut pusher, &encode(&["LPUSH", "e2e:queue", "b"]));
+    assert_eq!(read_reply(&mut second), b"*2\r\n$9\r\ne2e:queue\r\n$1\r\nb\r\n");
+
+    let response = send_and_receive(&mut pusher, &encode(&["LLEN", "e2e:queue"]));
+    assert_eq!(response, b":0\r\n");
+}
+
+#[test]
+fn test_blocking_pop_timeout() {
+    start_server_once();
+    let mut stream = TcpStream::connect("127.0.0.1:6379").unwrap();
+
+    let start = std::time::Instant::now();
+    let response = send_and_receive(&mut stream, &encode(&["BRPOP", "e2e:empty", "0.2"]));
+    assert_eq!(response, b"*-1\r\n");
+    assert!(start.elapsed() >= Duration::from_millis(200));
+
+    let response = send_and_receive(&mut stream, &encode(&["BLMOVE", "e2e:empty", "e2e:dst", "LEFT", "LEFT", "0.1"]));
+    assert_eq!(response, b"$-1\r\n");
+
+    let response = send_and_receive(&mut stream, &encode(&["BLPOP", "e2e:empty", "-1"]));
+    assert_eq!(response, b"-timeout is negative\r\n");
+}
+
+#[test]
+fn test_blocking_move_and_mpop() {
+    start_server_once();
+    let mut mover = TcpStream::connect("127.0.0.1:6379").unwrap();
+    let mut popper = TcpStream::connect("127.0.0.1:6379").unwrap();
+    let mut pusher = TcpStream::connect("127.0.0.1:6379").unwrap();
+
+    mover.write_all(&encode(&["BLMOVE", "e2e:jobs", "e2e:working", "RIGHT", "LEFT", "0"])).unwrap();
+    popper.write_all(&encode(&["BLMPOP", "0", "1", "e2e:working", "LEFT", "COUNT", "5"])).unwrap();
+    sleep(Duration::from_millis(100));
+
+    // The moved element wakes up the client waiting on the destination.
+    send_and_receive(&mut pusher, &encode(&["RPUSH", "e2e:jobs", "job"]));
+    assert_eq!(read_reply(&mut mover), b"$3\r\njob\r\n");
+    assert_eq!(read_reply(&mut popper), b"*2\r\n$11\r\ne2e:working\r\n*1\r\n$3\r\njob\r\n");
+}
+
+#[test]
+fn test_disconnected_blocked_client_is_not_served() {
+    start_server_once();
+    let mut blocked = TcpStream::connect("127.0.0.1:6379").unwrap();
+    let mut pusher = TcpStream::connect("127.0.0.1:6379").unwrap();
+
+    blocked.write_all(&encode(&["BLPOP", "e2e:abandoned", "0"])).unwrap();
+    sleep(Duration::from_millis(100));
+    blocked.shutdown(std::net::Shutdown::Both).unwrap();
+    drop(blocked);
+    sleep(Duration::from_millis(100));
+
+    send_and_receive(&mut pusher, &encode(&["RPUSH", "e2e:abandoned", "kept"]));
+    let response = send_and_receive(&mut pusher, &encode(&["LPOP", "e2e:abandoned"]));
+    assert_eq!(response, b"$4\r\nkept\r\n");
+}
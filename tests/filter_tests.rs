@@ -0,0 +1,203 @@
+/// Integration tests for Bloom and Cuckoo filter commands
+#[cfg(test)]
+mod test_filters {
+    use codecrafters_redis::resp::client::ClientContext;
+    use codecrafters_redis::resp::command_dispatcher::CommandDispatcher;
+    use codecrafters_redis::resp::protocol::RespType;
+    use codecrafters_redis::resp::state::default_server_state::DefaultServerState;
+
+    fn bulk(s: &str) -> RespType {
+        RespType::BulkString(Some(s.to_string()))
+    }
+
+    fn run(state: &mut DefaultServerState, args: &[&str]) -> Result<RespType, String> {
+        let mut client = ClientContext::default();
+        CommandDispatcher::new().dispatch(args[0], args[1..].iter().map(|arg| bulk(arg)).collect(), state, &mut client)
+    }
+
+    fn int(state: &mut DefaultServerState, args: &[&str]) -> i64 {
+        match run(state, args).unwrap() {
+            RespType::Integer(value) => value,
+            other => panic!("expected an integer, got {:?}", other),
+        }
+    }
+
+    fn ints(values: &[i64]) -> RespType {
+        RespType::Array(values.iter().map(|value| RespType::Integer(*value)).collect())
+    }
+
+    fn ok() -> RespType {
+        RespType::SimpleString("OK".to_string())
+    }
+
+    /// Copies `key` to `copy` through `DUMP` and `RESTORE`.
+    fn dump_and_restore(state: &mut DefaultServerState, key: &str, copy: &str) {
+        let payload = run(state, &["DUMP", key]).unwrap();
+        let args = vec![bulk(copy), bulk("0"), payload];
+        CommandDispatcher::new().dispatch("RESTORE", args, state, &mut ClientContext::default()).unwrap();
+    }
+
+    #[test]
+    fn test_bloom_add_and_exists() {
+        let mut state = DefaultServerState::default();
+
+        // Adding creates a filter with the default settings.
+        assert_eq!(int(&mut state, &["BF.ADD", "seen", "a"]), 1);
+        assert_eq!(int(&mut state, &["BF.ADD", "seen", "a"]), 0);
+        assert_eq!(run(&mut state, &["BF.MADD", "seen", "a", "b", "c"]).unwrap(), ints(&[0, 1, 1]));
+        assert_eq!(int(&mut state, &["BF.EXISTS", "seen", "b"]), 1);
+        assert_eq!(int(&mut state, &["BF.EXISTS", "seen", "z"]), 0);
+        assert_eq!(run(&mut state, &["BF.MEXISTS", "seen", "c", "z"]).unwrap(), ints(&[1, 0]));
+        assert_eq!(int(&mut state, &["BF.EXISTS", "missing", "a"]), 0);
+        assert_eq!(run(&mut state, &["BF.MEXISTS", "missing", "a"]).unwrap(), ints(&[0]));
+        assert_eq!(run(&mut state, &["TYPE", "seen"]).unwrap(), RespType::SimpleString("MBbloom--".to_string()));
+
+        run(&mut state, &["SET", "text", "a"]).unwrap();
+        assert!(run(&mut state, &["BF.ADD", "text", "a"]).unwrap_err().starts_with("WRONGTYPE"));
+        assert!(run(&mut state, &["BF.EXISTS", "text", "a"]).unwrap_err().starts_with("WRONGTYPE"));
+    }
+
+    #[test]
+    fn test_bloom_scaling_and_error_rate() {
+        let mut state = DefaultServerState::default();
+        assert_eq!(run(&mut state, &["BF.RESERVE", "small", "0.01", "10", "EXPANSION", "3"]).unwrap(), ok());
+
+        // Items keep being found as the filter grows past its capacity.
+        let items = (0..1000).map(|index| format!("item:{}", index)).collect::<Vec<_>>();
+        for item in &items {
+            run(&mut state, &["BF.ADD", "small", item]).unwrap();
+        }
+        assert!(items.iter().all(|item| int(&mut state, &["BF.EXISTS", "small", item]) == 1));
+        let false_positives =
+            (0..1000).filter(|index| int(&mut state, &["BF.EXISTS", "small", &format!("other:{}", index)]) == 1);
+        assert!(false_positives.count() < 30);
+
+        // Non-scaling filters refuse items past their capacity.
+        assert_eq!(run(&mut state, &["BF.RESERVE", "fixed", "0.01", "2", "NONSCALING"]).unwrap(), ok());
+        assert_eq!(
+            run(&mut state, &["BF.MADD", "fixed", "a", "b", "c"]).unwrap(),
+            RespType::Array(vec![
+                RespType::Integer(1),
+                RespType::Integer(1),
+                RespType::Error("non scaling filter is full".to_string())
+            ])
+        );
+        assert_eq!(run(&mut state, &["BF.ADD", "fixed", "d"]).unwrap_err(), "non scaling filter is full");
+        assert_eq!(int(&mut state, &["BF.ADD", "fixed", "a"]), 0);
+    }
+
+    #[test]
+    fn test_bloom_reserve_errors() {
+        let mut state = DefaultServerState::default();
+        let error = |state: &mut DefaultServerState, args: &[&str]| {
+            let mut command = vec!["BF.RESERVE", "bf"];
+            command.extend(args);
+            run(state, &command).unwrap_err()
+        };
+
+        assert_eq!(error(&mut state, &["0.01"]), "wrong number of arguments for 'bf.reserve' command");
+        assert_eq!(error(&mut state, &["high", "10"]), "bad error rate");
+        assert_eq!(error(&mut state, &["1", "10"]), "(0 < error rate range < 1)");
+        assert_eq!(error(&mut state, &["0.01", "0"]), "(capacity should be larger than 0)");
+        assert_eq!(error(&mut state, &["0.01", "10", "EXPANSION", "0"]), "expansion should be greater or equal to 1");
+        assert_eq!(error(&mut state, &["0.01", "10", "EXPANSION", "x"]), "bad expansion");
+        assert_eq!(
+            error(&mut state, &["0.01", "10", "NONSCALING", "EXPANSION", "2"]),
+            "Nonscaling filters cannot expand"
+        );
+        assert_eq!(error(&mut state, &["0.01", "10", "FAST"]), "syntax error");
+        assert_eq!(error(&mut state, &["0.0000001", "100000000000"]), "Insufficient memory to create filter");
+
+        run(&mut state, &["BF.ADD", "bf", "a"]).unwrap();
+        assert_eq!(error(&mut state, &["0.01", "10"]), "item exists");
+    }
+
+    #[test]
+    fn test_cuckoo_add_delete_and_exists() {
+        let mut state = DefaultServerState::default();
+
+        assert_eq!(int(&mut state, &["CF.ADD", "cf", "a"]), 1);
+        assert_eq!(int(&mut state, &["CF.ADD", "cf", "a"]), 1);
+        assert_eq!(int(&mut state, &["CF.EXISTS", "cf", "a"]), 1);
+        assert_eq!(int(&mut state, &["CF.EXISTS", "cf", "b"]), 0);
+        assert_eq!(run(&mut state, &["TYPE", "cf"]).unwrap(), RespType::SimpleString("MBbloomCF".to_string()));
+
+        // Each addition needs its own deletion.
+        assert_eq!(int(&mut state, &["CF.DEL", "cf", "a"]), 1);
+        assert_eq!(int(&mut state, &["CF.EXISTS", "cf", "a"]), 1);
+        assert_eq!(int(&mut state, &["CF.DEL", "cf", "a"]), 1);
+        assert_eq!(int(&mut state, &["CF.EXISTS", "cf", "a"]), 0);
+        assert_eq!(int(&mut state, &["CF.DEL", "cf", "a"]), 0);
+
+        assert_eq!(int(&mut state, &["CF.EXISTS", "missing", "a"]), 0);
+        assert_eq!(run(&mut state, &["CF.DEL", "missing", "a"]).unwrap_err(), "Not found");
+        run(&mut state, &["BF.ADD", "bf", "a"]).unwrap();
+        assert!(run(&mut state, &["CF.ADD", "bf", "a"]).unwrap_err().starts_with("WRONGTYPE"));
+    }
+
+    #[test]
+    fn test_cuckoo_growth() {
+        let mut state = DefaultServerState::default();
+        assert_eq!(run(&mut state, &["CF.RESERVE", "grows", "8", "BUCKETSIZE", "2", "EXPANSION", "2"]).unwrap(), ok());
+
+        // Full filters get larger ones chained after them.
+        let items = (0..200).map(|index| format!("item:{}", index)).collect::<Vec<_>>();
+        for item in &items {
+            assert_eq!(int(&mut state, &["CF.ADD", "grows", item]), 1);
+        }
+        assert!(items.iter().all(|item| int(&mut state, &["CF.EXISTS", "grows", item]) == 1));
+        for item in &items[..100] {
+            assert_eq!(int(&mut state, &["CF.DEL", "grows", item]), 1);
+        }
+        assert!(items[100..].iter().all(|item| int(&mut state, &["CF.EXISTS", "grows", item]) == 1));
+
+        // Without expansion, additions fail once evictions cannot make room.
+        let reserve = ["CF.RESERVE", "fixed", "4", "BUCKETSIZE", "2", "MAXITERATIONS", "5", "EXPANSION", "0"];
+        run(&mut state, &reserve).unwrap();
+        let added = (0..50).take_while(|index| run(&mut state, &["CF.ADD", "fixed", &index.to_string()]).is_ok());
+        assert!(added.count() <= 4);
+        assert_eq!(run(&mut state, &["CF.ADD", "fixed", "last"]).unwrap_err(), "Filter is full");
+        assert!((0..3).all(|index| int(&mut state, &["CF.EXISTS", "fixed", &index.to_string()]) == 1));
+    }
+
+    #[test]
+    fn test_cuckoo_reserve_errors() {
+        let mut state = DefaultServerState::default();
+        let error = |state: &mut DefaultServerState, args: &[&str]| {
+            let mut command = vec!["CF.RESERVE", "cf"];
+            command.extend(args);
+            run(state, &command).unwrap_err()
+        };
+
+        assert_eq!(error(&mut state, &["0"]), "Bad capacity");
+        assert_eq!(error(&mut state, &["100", "BUCKETSIZE", "256"]), "Bad bucket size");
+        assert_eq!(error(&mut state, &["100", "MAXITERATIONS", "0"]), "Bad maxIterations");
+        assert_eq!(error(&mut state, &["100", "EXPANSION", "-1"]), "Bad expansion");
+        assert_eq!(error(&mut state, &["100", "BUCKETSIZE"]), "wrong number of arguments for 'cf.reserve' command");
+        assert_eq!(error(&mut state, &["100", "SIZE", "2"]), "syntax error");
+        assert_eq!(error(&mut state, &["7", "BUCKETSIZE", "4"]), "Capacity must be at least (BucketSize * 2)");
+
+        run(&mut state, &["CF.ADD", "cf", "a"]).unwrap();
+        assert_eq!(error(&mut state, &["100"]), "item exists");
+    }
+
+    #[test]
+    fn test_filters_survive_dump() {
+        let mut state = DefaultServerState::default();
+        run(&mut state, &["BF.RESERVE", "bf", "0.001", "5", "EXPANSION", "4"]).unwrap();
+        run(&mut state, &["CF.RESERVE", "cf", "4", "EXPANSION", "2"]).unwrap();
+        let items = (0..20).map(|index| index.to_string()).collect::<Vec<_>>();
+        for item in &items {
+            run(&mut state, &["BF.ADD", "bf", item]).unwrap();
+            run(&mut state, &["CF.ADD", "cf", item]).unwrap();
+        }
+
+        dump_and_restore(&mut state, "bf", "bf copy");
+        dump_and_restore(&mut state, "cf", "cf copy");
+        assert!(items.iter().all(|item| int(&mut state, &["BF.EXISTS", "bf copy", item]) == 1));
+        assert!(items.iter().all(|item| int(&mut state, &["CF.EXISTS", "cf copy", item]) == 1));
+        assert_eq!(int(&mut state, &["BF.ADD", "bf copy", "0"]), 0);
+        assert_eq!(int(&mut state, &["CF.DEL", "cf copy", "0"]), 1);
+        assert_eq!(int(&mut state, &["CF.EXISTS", "cf", "0"]), 1);
+    }
+}
@@ -0,0 +1,214 @@
+/// Integration tests for geospatial commands
+#[cfg(test)]
+mod test_geo {
+    use codecrafters_redis::resp::client::ClientContext;
+    use codecrafters_redis::resp::command_dispatcher::CommandDispatcher;
+    use codecrafters_redis::resp::protocol::RespType;
+    use codecrafters_redis::resp::state::default_server_state::DefaultServerState;
+
+    fn bulk(s: &str) -> RespType {
+        RespType::BulkString(Some(s.to_string()))
+    }
+
+    fn run(state: &mut DefaultServerState, args: &[&str]) -> Result<RespType, String> {
+        let mut client = ClientContext::default();
+        CommandDispatcher::new().dispatch(args[0], args[1..].iter().map(|arg| bulk(arg)).collect(), state, &mut client)
+    }
+
+    /// Flattens a reply to its strings and integers.
+    fn strings(reply: RespType) -> Vec<String> {
+        match reply {
+            RespType::Array(items) => items.into_iter().flat_map(strings).collect(),
+            RespType::BulkString(Some(value)) => vec![value],
+            RespType::Integer(value) => vec![value.to_string()],
+            RespType::BulkString(None) | RespType::NullArray => vec!["(nil)".to_string()],
+            other => panic!("unexpected reply {:?}", other),
+        }
+    }
+
+    /// Members found by `GEOSEARCH Sicily` with the given arguments, flattened.
+    fn search(state: &mut DefaultServerState, args: &[&str]) -> Vec<String> {
+        let mut command = vec!["GEOSEARCH", "Sicily"];
+        command.extend(args);
+        strings(run(state, &command).unwrap())
+    }
+
+    fn sicily() -> DefaultServerState {
+        let mut state = DefaultServerState::default();
+        let args = ["GEOADD", "Sicily", "13.361389", "38.115556", "Palermo", "15.087269", "37.502669", "Catania"];
+        assert_eq!(run(&mut state, &args).unwrap(), RespType::Integer(2));
+        state
+    }
+
+    #[test]
+    fn test_add_and_read_positions() {
+        let mut state = sicily();
+
+        // Members are scored by their 52-bit geohash.
+        assert_eq!(
+            strings(run(&mut state, &["ZRANGE", "Sicily", "0", "-1", "WITHSCORES"]).unwrap()),
+            vec!["Palermo", "3479099956230698", "Catania", "3479447370796909"]
+        );
+        assert_eq!(
+            strings(run(&mut state, &["GEOHASH", "Sicily", "Palermo", "Catania", "Rome"]).unwrap()),
+            vec!["sqc8b49rny0", "sqdtr74hyu0", "(nil)"]
+        );
+
+        let positions = strings(run(&mut state, &["GEOPOS", "Sicily", "Palermo", "Rome"]).unwrap());
+        assert_eq!(positions.len(), 3);
+        assert!((positions[0].parse::<f64>().unwrap() - 13.361389).abs() < 1e-5);
+        assert!((positions[1].parse::<f64>().unwrap() - 38.115556).abs() < 1e-5);
+        assert_eq!(positions[2], "(nil)");
+        assert_eq!(strings(run(&mut state, &["GEOPOS", "missing", "a"]).unwrap()), vec!["(nil)"]);
+
+        // NX, XX and CH, as for sorted sets.
+        assert_eq!(run(&mut state, &["GEOADD", "Sicily", "NX", "13", "38", "Palermo"]).unwrap(), RespType::Integer(0));
+        assert_eq!(run(&mut state, &["GEOADD", "Sicily", "XX", "13", "38", "Rome"]).unwrap(), RespType::Integer(0));
+        assert_eq!(
+            run(&mut state, &["GEOADD", "Sicily", "XX", "CH", "13", "38", "Palermo"]).unwrap(),
+            RespType::Integer(1)
+        );
+        assert_eq!(run(&mut state, &["ZCARD", "Sicily"]).unwrap(), RespType::Integer(2));
+        assert_eq!(run(&mut state, &["GEOADD", "empty", "XX", "13", "38", "a"]).unwrap(), RespType::Integer(0));
+        assert_eq!(run(&mut state, &["TYPE", "empty"]).unwrap(), RespType::SimpleString("none".to_string()));
+
+        assert_eq!(
+            run(&mut state, &["GEOADD", "Sicily", "13", "86", "North"]).unwrap_err(),
+            "invalid longitude,latitude pair 13.000000,86.000000"
+        );
+        assert_eq!(run(&mut state, &["GEOADD", "Sicily", "13", "38"]).unwrap_err(), "syntax error");
+        assert_eq!(run(&mut state, &["GEOADD", "Sicily", "NX", "XX", "13", "38", "a"]).unwrap_err(), "syntax error");
+        assert_eq!(
+            run(&mut state, &["GEOADD", "Sicily", "east", "38", "a"]).unwrap_err(),
+            "value is not a valid float"
+        );
+    }
+
+    #[test]
+    fn test_distance() {
+        let mut state = sicily();
+
+        assert_eq!(run(&mut state, &["GEODIST", "Sicily", "Palermo", "Catania"]).unwrap(), bulk("166274.1516"));
+        assert_eq!(run(&mut state, &["GEODIST", "Sicily", "Palermo", "Catania", "km"]).unwrap(), bulk("166.2742"));
+        assert_eq!(run(&mut state, &["GEODIST", "Sicily", "Palermo", "Catania", "MI"]).unwrap(), bulk("103.3182"));
+        assert_eq!(run(&mut state, &["GEODIST", "Sicily", "Palermo", "Rome"]).unwrap(), RespType::BulkString(None));
+        assert_eq!(run(&mut state, &["GEODIST", "missing", "a", "b"]).unwrap(), RespType::BulkString(None));
+        assert_eq!(
+            run(&mut state, &["GEODIST", "Sicily", "Palermo", "Catania", "yd"]).unwrap_err(),
+            "unsupported unit provided. please use M, KM, FT, MI"
+        );
+    }
+
+    #[test]
+    fn test_search() {
+        let mut state = sicily();
+        let edges = ["GEOADD", "Sicily", "12.758489", "38.788135", "edge1", "17.241510", "38.788135", "edge2"];
+        run(&mut state, &edges).unwrap();
+
+        assert_eq!(
+            search(&mut state, &["FROMLONLAT", "15", "37", "BYRADIUS", "200", "km", "ASC"]),
+            vec!["Catania", "Palermo"]
+        );
+        assert_eq!(
+            search(&mut state, &["FROMLONLAT", "15", "37", "BYBOX", "400", "400", "km", "DESC", "WITHDIST"]),
+            vec!["edge1", "279.7405", "edge2", "279.7403", "Palermo", "190.4424", "Catania", "56.4413"]
+        );
+
+        // COUNT sorts by distance unless ANY is given.
+        assert_eq!(
+            search(&mut state, &["FROMMEMBER", "Palermo", "BYRADIUS", "500", "km", "COUNT", "2"]),
+            vec!["Palermo", "edge1"]
+        );
+        let any = search(&mut state, &["FROMMEMBER", "Palermo", "BYRADIUS", "500", "km", "COUNT", "1", "ANY"]);
+        assert_eq!(any.len(), 1);
+
+        let detailed =
+            search(&mut state, &["FROMMEMBER", "Catania", "BYRADIUS", "1", "m", "WITHCOORD", "WITHHASH", "WITHDIST"]);
+        assert_eq!(&detailed[..3], ["Catania", "0.0000", "3479447370796909"]);
+        assert!((detailed[3].parse::<f64>().unwrap() - 15.087269).abs() < 1e-5);
+        assert_eq!(detailed.len(), 5);
+
+        // The box is measured along the meridian and the parallel of each member.
+        assert_eq!(search(&mut state, &["FROMLONLAT", "15", "37", "BYBOX", "400", "200", "km"]), vec!["Catania"]);
+        assert_eq!(
+            run(&mut state, &["GEOSEARCH", "missing", "FROMMEMBER", "a", "BYRADIUS", "1", "m"]).unwrap(),
+            RespType::Array(Vec::new())
+        );
+    }
+
+    #[test]
+    fn test_search_errors() {
+        let mut state = sicily();
+        let error = |state: &mut DefaultServerState, args: &[&str]| {
+            let mut command = vec!["GEOSEARCH", "Sicily"];
+            command.extend(args);
+            run(state, &command).unwrap_err()
+        };
+
+        assert_eq!(
+            error(&mut state, &["BYRADIUS", "1", "km"]),
+            "exactly one of FROMMEMBER or FROMLONLAT can be specified for GEOSEARCH"
+        );
+        assert_eq!(
+            error(&mut state, &["FROMMEMBER", "Palermo", "FROMLONLAT", "15", "37", "BYRADIUS", "1", "km"]),
+            "exactly one of FROMMEMBER or FROMLONLAT can be specified for GEOSEARCH"
+        );
+        assert_eq!(
+            error(&mut state, &["FROMMEMBER", "Palermo"]),
+            "exactly one of BYRADIUS and BYBOX can be specified for GEOSEARCH"
+        );
+        assert_eq!(
+            error(&mut state, &["FROMMEMBER", "Rome", "BYRADIUS", "1", "km"]),
+            "could not decode requested zset member"
+        );
+        assert_eq!(error(&mut state, &["FROMMEMBER", "Palermo", "BYRADIUS", "-1", "km"]), "radius cannot be negative");
+        assert_eq!(
+            error(&mut state, &["FROMMEMBER", "Palermo", "BYBOX", "1", "-1", "km"]),
+            "height or width cannot be negative"
+        );
+        assert_eq!(
+            error(&mut state, &["FROMMEMBER", "Palermo", "BYRADIUS", "1", "km", "COUNT", "0"]),
+            "COUNT must be > 0"
+        );
+        assert_eq!(
+            error(&mut state, &["FROMMEMBER", "Palermo", "BYRADIUS", "1", "km", "ANY"]),
+            "the ANY argument requires COUNT argument"
+        );
+        assert_eq!(error(&mut state, &["FROMMEMBER", "Palermo", "BYRADIUS", "1", "km", "STOREDIST"]), "syntax error");
+        run(&mut state, &["SET", "text", "a"]).unwrap();
+        assert!(run(&mut state, &["GEOSEARCH", "text", "FROMLONLAT", "15", "37", "BYRADIUS", "1", "m"])
+            .unwrap_err()
+            .starts_with("WRONGTYPE"));
+    }
+
+    #[test]
+    fn test_search_store() {
+        let mut state = sicily();
+        let store = |state: &mut DefaultServerState, destination: &str, args: &[&str]| {
+            let mut command = vec!["GEOSEARCHSTORE", destination, "Sicily", "FROMLONLAT"];
+            command.extend(args);
+            run(state, &command)
+        };
+
+        assert_eq!(store(&mut state, "near", &["15", "37", "BYRADIUS", "200", "km"]).unwrap(), RespType::Integer(2));
+        assert_eq!(
+            strings(run(&mut state, &["ZRANGE", "near", "0", "-1", "WITHSCORES"]).unwrap()),
+            vec!["Palermo", "3479099956230698", "Catania", "3479447370796909"]
+        );
+        assert_eq!(run(&mut state, &["GEODIST", "near", "Palermo", "Catania", "km"]).unwrap(), bulk("166.2742"));
+
+        let store_dist = ["15", "37", "BYRADIUS", "200", "km", "STOREDIST"];
+        assert_eq!(store(&mut state, "dist", &store_dist).unwrap(), RespType::Integer(2));
+        let scores = strings(run(&mut state, &["ZRANGE", "dist", "0", "-1", "WITHSCORES"]).unwrap());
+        assert_eq!(scores[0], "Catania");
+        assert!((scores[1].parse::<f64>().unwrap() - 56.4413).abs() < 1e-3);
+
+        // An empty result deletes the destination.
+        assert_eq!(store(&mut state, "near", &["0", "0", "BYRADIUS", "1", "km"]).unwrap(), RespType::Integer(0));
+        assert_eq!(run(&mut state, &["TYPE", "near"]).unwrap(), RespType::SimpleString("none".to_string()));
+        assert_eq!(
+            store(&mut state, "near", &["15", "37", "BYRADIUS", "1", "km", "WITHDIST"]).unwrap_err(),
+            "GEOSEARCHSTORE is not compatible with WITHDIST, WITHHASH and WITHCOORD options"
+        );
+    }
+}
@@ -0,0 +1,328 @@
+/// Integration tests for hash commands
+#[cfg(test)]
+mod test_hash {
+    use codecrafters_redis::resp::client::ClientContext;
+    use codecrafters_redis::resp::command_dispatcher::CommandDispatcher;
+    use codecrafters_redis::resp::commands::scan::glob_match;
+    use codecrafters_redis::resp::protocol::RespType;
+    use codecrafters_redis::resp::state::default_server_state::DefaultServerState;
+    use codecrafters_redis::resp::state::server_state::ServerState;
+    use codecrafters_redis::resp::state::value::Value;
+
+    fn bulk(s: &str) -> RespType {
+        RespType::BulkString(Some(s.to_string()))
+    }
+
+    fn array(items: &[&str]) -> RespType {
+        RespType::Array(items.iter().map(|item| bulk(item)).collect())
+    }
+
+    fn run(state: &mut DefaultServerState, args: &[&str]) -> Result<RespType, String> {
+        let mut client = ClientContext::default();
+        CommandDispatcher::new().dispatch(args[0], args[1..].iter().map(|arg| bulk(arg)).collect(), state, &mut client)
+    }
+
+    /// Bulk strings of an array reply, sorted since hashes have no order.
+    fn sorted(reply: RespType) -> Vec<String> {
+        let RespType::Array(items) = reply else { panic!("expected an array, got {:?}", reply) };
+        let mut items = items
+            .into_iter()
+            .map(|item| match item {
+                RespType::BulkString(Some(s)) => s,
+                other => panic!("expected a bulk string, got {:?}", other),
+            })
+            .collect::<Vec<_>>();
+        items.sort();
+        items
+    }
+
+    #[test]
+    fn test_set_get_and_delete() {
+        let mut state = DefaultServerState::default();
+
+        assert_eq!(run(&mut state, &["HSET", "h", "a", "1", "b", "2"]).unwrap(), RespType::Integer(2));
+        assert_eq!(run(&mut state, &["HSET", "h", "b", "3", "c", "4"]).unwrap(), RespType::Integer(1));
+        assert_eq!(run(&mut state, &["HGET", "h", "b"]).unwrap(), bulk("3"));
+        assert_eq!(run(&mut state, &["HGET", "h", "z"]).unwrap(), RespType::BulkString(None));
+        assert_eq!(
+            run(&mut state, &["HMGET", "h", "a", "z", "c"]).unwrap(),
+            RespType::Array(vec![bulk("1"), RespType::BulkString(None), bulk("4")])
+        );
+        assert_eq!(run(&mut state, &["HLEN", "h"]).unwrap(), RespType::Integer(3));
+        assert_eq!(run(&mut state, &["HEXISTS", "h", "a"]).unwrap(), RespType::Integer(1));
+        assert_eq!(run(&mut state, &["HSTRLEN", "h", "c"]).unwrap(), RespType::Integer(1));
+        assert_eq!(sorted(run(&mut state, &["HKEYS", "h"]).unwrap()), ["a", "b", "c"]);
+        assert_eq!(sorted(run(&mut state, &["HVALS", "h"]).unwrap()), ["1", "3", "4"]);
+        assert_eq!(sorted(run(&mut state, &["HGETALL", "h"]).unwrap()), ["1", "3", "4", "a", "b", "c"]);
+        assert_eq!(run(&mut state, &["TYPE", "h"]).unwrap(), RespType::SimpleString("hash".to_string()));
+
+        assert_eq!(run(&mut state, &["HSETNX", "h", "a", "9"]).unwrap(), RespType::Integer(0));
+        assert_eq!(run(&mut state, &["HSETNX", "h", "d", "9"]).unwrap(), RespType::Integer(1));
+        assert_eq!(run(&mut state, &["HDEL", "h", "a", "b", "z"]).unwrap(), RespType::Integer(2));
+        assert_eq!(run(&mut state, &["HDEL", "h", "c", "d"]).unwrap(), RespType::Integer(2));
+        assert!(!state.exists("h"));
+        assert_eq!(run(&mut state, &["HGETALL", "h"]).unwrap(), array(&[]));
+
+        assert!(run(&mut state, &["HSET", "h", "a"]).is_err());
+        run(&mut state, &["SET", "s", "v"]).unwrap();
+        assert!(run(&mut state, &["HGET", "s", "a"]).unwrap_err().starts_with("WRONGTYPE"));
+    }
+
+    #[test]
+    fn test_increments() {
+        let mut state = DefaultServerState::default();
+
+        assert_eq!(run(&mut state, &["HINCRBY", "h", "n", "5"]).unwrap(), RespType::Integer(5));
+        assert_eq!(run(&mut state, &["HINCRBY", "h", "n", "-7"]).unwrap(), RespType::Integer(-2));
+        assert_eq!(run(&mut state, &["HINCRBYFLOAT", "h", "f", "10.5"]).unwrap(), bulk("10.5"));
+        assert_eq!(run(&mut state, &["HINCRBYFLOAT", "h", "f", "0.1"]).unwrap(), bulk("10.6"));
+        assert_eq!(run(&mut state, &["HINCRBYFLOAT", "h", "n", "2"]).unwrap(), bulk("0"));
+
+        run(&mut state, &["HSET", "h", "s", "abc", "max", &i64::MAX.to_string()]).unwrap();
+        assert_eq!(run(&mut state, &["HINCRBY", "h", "s", "1"]).unwrap_err(), "hash value is not an integer");
+        assert_eq!(
+            run(&mut state, &["HINCRBY", "h", "max", "1"]).unwrap_err(),
+            "increment or decrement would overflow"
+        );
+        assert_eq!(run(&mut state, &["HINCRBYFLOAT", "h", "s", "1"]).unwrap_err(), "hash value is not a float");
+    }
+
+    #[test]
+    fn test_random_fields() {
+        let mut state = DefaultServerState::default();
+        run(&mut state, &["HSET", "h", "a", "1", "b", "2", "c", "3"]).unwrap();
+
+        let RespType::BulkString(Some(field)) = run(&mut state, &["HRANDFIELD", "h"]).unwrap() else {
+            panic!("expected a field");
+        };
+        assert!(["a", "b", "c"].contains(&field.as_str()));
+        assert_eq!(sorted(run(&mut state, &["HRANDFIELD", "h", "10"]).unwrap()), ["a", "b", "c"]);
+        assert_eq!(sorted(run(&mut state, &["HRANDFIELD", "h", "2"]).unwrap()).len(), 2);
+        assert_eq!(sorted(run(&mut state, &["HRANDFIELD", "h", "-7"]).unwrap()).len(), 7);
+        assert_eq!(
+            run(&mut state, &["HRANDFIELD", "h", &i64::MIN.to_string()]).unwrap_err(),
+            "value is out of range"
+        );
+        assert_eq!(
+            sorted(run(&mut state, &["HRANDFIELD", "h", "3", "WITHVALUES"]).unwrap()),
+            ["1", "2", "3", "a", "b", "c"]
+        );
+        assert_eq!(run(&mut state, &["HRANDFIELD", "nope"]).unwrap(), RespType::BulkString(None));
+        assert_eq!(run(&mut state, &["HRANDFIELD", "nope", "3"]).unwrap(), array(&[]));
+    }
+
+    #[test]
+    fn test_scan() {
+        let mut state = DefaultServerState::default();
+        for i in 0..25 {
+            run(&mut state, &["HSET", "h", &format!("field:{}", i), &i.to_string()]).unwrap();
+        }
+
+        // Every field is returned exactly once over a full scan.
+        let mut cursor = "0".to_string();
+        let mut fields = Vec::new();
+        loop {
+            let reply = run(&mut state, &["HSCAN", "h", &cursor, "COUNT", "7", "NOVALUES"]).unwrap();
+            let RespType::Array(mut parts) = reply else { panic!("expected an array") };
+            fields.extend(sorted(parts.pop().unwrap()));
+            let Some(RespType::BulkString(Some(next))) = parts.pop() else { panic!("expected a cursor") };
+            cursor = next;
+            if cursor == "0" {
+                break;
+            }
+        }
+        fields.sort();
+        let mut expected = (0..25).map(|i| format!("field:{}", i)).collect::<Vec<_>>();
+        expected.sort();
+        assert_eq!(fields, expected);
+
+        let reply = run(&mut state, &["HSCAN", "h", "0", "MATCH", "field:1?", "COUNT", "100"]).unwrap();
+        let RespType::Array(mut parts) = reply else { panic!("expected an array") };
+        let matched = sorted(parts.pop().unwrap());
+        assert_eq!(parts, vec![bulk("0")]);
+        assert_eq!(matched.len(), 20);
+        assert!(matched.contains(&"field:17".to_string()));
+        assert!(matched.contains(&"17".to_string()));
+        assert_eq!(run(&mut state, &["HSCAN", "h", "x"]).unwrap_err(), "invalid cursor");
+        assert_eq!(run(&mut state, &["HSCAN", "nope", "0"]).unwrap(), RespType::Array(vec![bulk("0"), array(&[])]));
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*", "anything"));
+        assert!(glob_match("h?llo", "hello"));
+        assert!(!glob_match("h?llo", "hllo"));
+        assert!(glob_match("h*llo", "heeeello"));
+        assert!(glob_match("h[ae]llo", "hallo"));
+        assert!(!glob_match("h[^e]llo", "hello"));
+        assert!(glob_match("h[a-b]llo", "hbllo"));
+        assert!(glob_match("h\\*llo", "h*llo"));
+        assert!(!glob_match("h\\*llo", "hello"));
+    }
+    fn integers(values: &[i64]) -> RespType {
+        RespType::Array(values.iter().map(|value| RespType::Integer(*value)).collect())
+    }
+
+    fn now_ms() -> i64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as i64
+    }
+
+    #[test]
+    fn test_field_expiration() {
+        let mut state = DefaultServerState::default();
+        run(&mut state, &["HSET", "h", "a", "1", "b", "2", "c", "3"]).unwrap();
+
+        assert_eq!(
+            run(&mut state, &["HEXPIRE", "h", "100", "FIELDS", "2", "a", "z"]).unwrap(),
+            integers(&[1, -2])
+        );
+        assert_eq!(run(&mut state, &["HTTL", "h", "FIELDS", "3", "a", "b", "z"]).unwrap(), integers(&[100, -1, -2]));
+        let RespType::Array(pttl) = run(&mut state, &["HPTTL", "h", "FIELDS", "1", "a"]).unwrap() else {
+            panic!("expected an array");
+        };
+        assert!(matches!(pttl[0], RespType::Integer(ms) if ms > 99_000 && ms <= 100_000));
+
+        let at = now_ms() / 1000 + 500;
+        assert_eq!(
+            run(&mut state, &["HEXPIREAT", "h", &at.to_string(), "FIELDS", "1", "b"]).unwrap(),
+            integers(&[1])
+        );
+        assert_eq!(run(&mut state, &["HEXPIRETIME", "h", "FIELDS", "1", "b"]).unwrap(), integers(&[at]));
+        assert_eq!(
+            run(&mut state, &["HPEXPIRETIME", "h", "FIELDS", "1", "b"]).unwrap(),
+            integers(&[at * 1000])
+        );
+
+        assert_eq!(run(&mut state, &["HPERSIST", "h", "FIELDS", "3", "a", "c", "z"]).unwrap(), integers(&[1, -1, -2]));
+        assert_eq!(run(&mut state, &["HTTL", "h", "FIELDS", "1", "a"]).unwrap(), integers(&[-1]));
+
+        // Setting a field clears its expiration time, incrementing it does not.
+        run(&mut state, &["HPEXPIRE", "h", "100000", "FIELDS", "2", "a", "c"]).unwrap();
+        run(&mut state, &["HSET", "h", "a", "10"]).unwrap();
+        run(&mut state, &["HINCRBY", "h", "c", "1"]).unwrap();
+        assert_eq!(run(&mut state, &["HTTL", "h", "FIELDS", "2", "a", "c"]).unwrap(), integers(&[-1, 100]));
+
+        // A time in the past deletes the field right away.
+        assert_eq!(run(&mut state, &["HEXPIRE", "h", "0", "FIELDS", "1", "a"]).unwrap(), integers(&[2]));
+        assert_eq!(run(&mut state, &["HEXISTS", "h", "a"]).unwrap(), RespType::Integer(0));
+
+        assert_eq!(run(&mut state, &["HTTL", "nope", "FIELDS", "1", "a"]).unwrap(), integers(&[-2]));
+        assert_eq!(
+            run(&mut state, &["HEXPIRE", "h", "10", "FIELDS", "2", "a"]).unwrap_err(),
+            "The `numfields` parameter must match the number of arguments"
+        );
+        assert_eq!(
+            run(&mut state, &["HEXPIRE", "h", "10", "a"]).unwrap_err(),
+            "Mandatory argument FIELDS is missing or not at the right position"
+        );
+        assert_eq!(
+            run(&mut state, &["HEXPIRE", "h", "-1", "FIELDS", "1", "a"]).unwrap_err(),
+            "invalid expire time in 'hexpire' command"
+        );
+    }
+
+    #[test]
+    fn test_field_expiration_conditions() {
+        let mut state = DefaultServerState::default();
+        run(&mut state, &["HSET", "h", "a", "1", "b", "2"]).unwrap();
+        run(&mut state, &["HEXPIRE", "h", "100", "FIELDS", "1", "a"]).unwrap();
+
+        assert_eq!(run(&mut state, &["HEXPIRE", "h", "200", "NX", "FIELDS", "2", "a", "b"]).unwrap(), integers(&[0, 1]));
+        assert_eq!(run(&mut state, &["HTTL", "h", "FIELDS", "2", "a", "b"]).unwrap(), integers(&[100, 200]));
+        run(&mut state, &["HPERSIST", "h", "FIELDS", "1", "b"]).unwrap();
+
+        assert_eq!(run(&mut state, &["HEXPIRE", "h", "300", "XX", "FIELDS", "2", "a", "b"]).unwrap(), integers(&[1, 0]));
+        assert_eq!(run(&mut state, &["HEXPIRE", "h", "50", "GT", "FIELDS", "2", "a", "b"]).unwrap(), integers(&[0, 0]));
+        assert_eq!(run(&mut state, &["HEXPIRE", "h", "50", "LT", "FIELDS", "2", "a", "b"]).unwrap(), integers(&[1, 1]));
+        assert_eq!(run(&mut state, &["HTTL", "h", "FIELDS", "2", "a", "b"]).unwrap(), integers(&[50, 50]));
+    }
+
+    #[test]
+    fn test_expired_fields_are_removed() {
+        let mut state = DefaultServerState::default();
+        run(&mut state, &["HSET", "h", "a", "1", "b", "2"]).unwrap();
+        run(&mut state, &["HPEXPIRE", "h", "20", "FIELDS", "1", "a"]).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(30));
+
+        // Lazily on access.
+        assert_eq!(run(&mut state, &["HGETALL", "h"]).unwrap(), array(&["b", "2"]));
+        assert_eq!(run(&mut state, &["HLEN", "h"]).unwrap(), RespType::Integer(1));
+
+        // The hash goes away with its last field, even if nobody reads it.
+        run(&mut state, &["HPEXPIRE", "h", "20", "FIELDS", "1", "b"]).unwrap();
+        run(&mut state, &["HSET", "other", "a", "1"]).unwrap();
+        run(&mut state, &["HPEXPIRE", "other", "20", "FIELDS", "1", "a"]).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(30));
+        state.active_expire();
+        assert!(state.keys().is_empty());
+        assert_eq!(run(&mut state, &["HSET", "h", "b", "3"]).unwrap(), RespType::Integer(1));
+        assert_eq!(run(&mut state, &["HTTL", "h", "FIELDS", "1", "b"]).unwrap(), integers(&[-1]));
+    }
+
+    #[test]
+    fn test_active_expiration_samples_again_while_keys_expire() {
+        let mut state = DefaultServerState::default();
+        for i in 0..1000 {
+            let hash = format!("h{}", i);
+            run(&mut state, &["SET", &i.to_string(), "a", "PX", "20"]).unwrap();
+            run(&mut state, &["HSET", &hash, "a", "1", "b", "2"]).unwrap();
+            run(&mut state, &["HPEXPIRE", &hash, "20", "FIELDS", "1", "a"]).unwrap();
+        }
+        run(&mut state, &["SET", "kept", "a"]).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(30));
+
+        // Every sample is fully expired, so a single cycle gets through all of them.
+        state.active_expire();
+        assert_eq!(state.keys().len(), 1001);
+        let values = state.get_all().into_iter().map(|(_, value)| value);
+        assert_eq!(values.filter(|value| matches!(value, Value::Hash(fields) if fields.len() == 1)).count(), 1000);
+    }
+
+    #[test]
+    fn test_field_expiration_survives_snapshots() {
+        let mut state = DefaultServerState::default();
+        run(&mut state, &["HSET", "h", "a", "1", "b", "2"]).unwrap();
+        run(&mut state, &["HEXPIRE", "h", "100", "FIELDS", "1", "a"]).unwrap();
+        let expires_at = run(&mut state, &["HPEXPIRETIME", "h", "FIELDS", "1", "a"]).unwrap();
+
+        let mut restored = DefaultServerState::default();
+        restored.load_rdb(&state.dump_rdb()).unwrap();
+        assert_eq!(run(&mut restored, &["HPEXPIRETIME", "h", "FIELDS", "1", "a"]).unwrap(), expires_at);
+        assert_eq!(run(&mut restored, &["HTTL", "h", "FIELDS", "1", "b"]).unwrap(), integers(&[-1]));
+    }
+
+    #[test]
+    fn test_field_expiration_survives_dump() {
+        let mut state = DefaultServerState::default();
+        run(&mut state, &["HSET", "h", "a", "1", "b", "2"]).unwrap();
+        run(&mut state, &["HEXPIRE", "h", "100", "FIELDS", "1", "a"]).unwrap();
+        let expires_at = run(&mut state, &["HPEXPIRETIME", "h", "FIELDS", "1", "a"]).unwrap();
+
+        let payload = run(&mut state, &["DUMP", "h"]).unwrap();
+        run(&mut state, &["DEL", "h"]).unwrap();
+        let args = vec![bulk("h"), bulk("0"), payload];
+        CommandDispatcher::new().dispatch("RESTORE", args, &mut state, &mut ClientContext::default()).unwrap();
+        assert_eq!(run(&mut state, &["HPEXPIRETIME", "h", "FIELDS", "1", "a"]).unwrap(), expires_at);
+        assert_eq!(run(&mut state, &["HTTL", "h", "FIELDS", "1", "b"]).unwrap(), integers(&[-1]));
+        assert_eq!(run(&mut state, &["HGET", "h", "b"]).unwrap(), bulk("2"));
+    }
+
+    #[test]
+    fn test_binary_fields_are_rejected() {
+        let mut state = DefaultServerState::default();
+        let dispatcher = CommandDispatcher::new();
+        for args in [
+            vec![bulk("hash"), RespType::BulkBytes(vec![0xff]), bulk("1")],
+            vec![bulk("hash"), bulk("field"), RespType::BulkBytes(vec![0xff])],
+        ] {
+            assert_eq!(
+                dispatcher.dispatch("HSET", args, &mut state, &mut ClientContext::default()).unwrap_err(),
+                "HSET arguments must be strings"
+            );
+        }
+        assert!(state.keys().is_empty());
+    }
+}
@@ -0,0 +1,138 @@
+/// Integration tests for HyperLogLog commands
+#[cfg(test)]
+mod test_hyperloglog {
+    use codecrafters_redis::resp::client::ClientContext;
+    use codecrafters_redis::resp::command_dispatcher::CommandDispatcher;
+    use codecrafters_redis::resp::protocol::RespType;
+    use codecrafters_redis::resp::state::default_server_state::DefaultServerState;
+
+    fn bulk(s: &str) -> RespType {
+        RespType::BulkString(Some(s.to_string()))
+    }
+
+    fn run(state: &mut DefaultServerState, args: &[&str]) -> Result<RespType, String> {
+        let mut client = ClientContext::default();
+        CommandDispatcher::new().dispatch(args[0], args[1..].iter().map(|arg| bulk(arg)).collect(), state, &mut client)
+    }
+
+    /// Adds `element:<i>` for each `i` of the range to the HyperLogLog at `key`.
+    fn add_range(state: &mut DefaultServerState, key: &str, range: std::ops::Range<usize>) {
+        let elements = range.map(|i| format!("element:{}", i)).collect::<Vec<_>>();
+        for chunk in elements.chunks(1000) {
+            let mut args = vec!["PFADD", key];
+            args.extend(chunk.iter().map(String::as_str));
+            run(state, &args).unwrap();
+        }
+    }
+
+    fn count(state: &mut DefaultServerState, keys: &[&str]) -> i64 {
+        let mut args = vec!["PFCOUNT"];
+        args.extend(keys);
+        match run(state, &args).unwrap() {
+            RespType::Integer(count) => count,
+            other => panic!("expected an integer, got {:?}", other),
+        }
+    }
+
+    fn get(state: &mut DefaultServerState, key: &str) -> Vec<u8> {
+        match run(state, &["GET", key]).unwrap() {
+            RespType::BulkString(Some(value)) => value.into_bytes(),
+            RespType::BulkBytes(value) => value,
+            other => panic!("expected a bulk string, got {:?}", other),
+        }
+    }
+
+    fn set(state: &mut DefaultServerState, key: &str, value: &[u8]) {
+        let args = vec![bulk(key), RespType::bulk_bytes(value.to_vec())];
+        CommandDispatcher::new().dispatch("SET", args, state, &mut ClientContext::default()).unwrap();
+    }
+
+    fn assert_close(estimate: i64, exact: usize) {
+        let error = (estimate - exact as i64).abs() as f64 / exact as f64;
+        assert!(error < 0.02, "estimated {} distinct elements instead of {}", estimate, exact);
+    }
+
+    #[test]
+    fn test_add_and_count() {
+        let mut state = DefaultServerState::default();
+
+        assert_eq!(
+            run(&mut state, &["PFADD", "hll", "a", "b", "c", "d", "e", "f", "g"]).unwrap(),
+            RespType::Integer(1)
+        );
+        assert_eq!(count(&mut state, &["hll"]), 7);
+        assert_eq!(run(&mut state, &["PFADD", "hll", "a", "b"]).unwrap(), RespType::Integer(0));
+        assert_eq!(count(&mut state, &["hll"]), 7);
+
+        assert_eq!(run(&mut state, &["PFADD", "empty"]).unwrap(), RespType::Integer(1));
+        assert_eq!(run(&mut state, &["PFADD", "empty"]).unwrap(), RespType::Integer(0));
+        assert_eq!(count(&mut state, &["empty"]), 0);
+        assert_eq!(count(&mut state, &["missing"]), 0);
+        assert_eq!(run(&mut state, &["TYPE", "hll"]).unwrap(), RespType::SimpleString("string".to_string()));
+
+        run(&mut state, &["SET", "text", "hello"]).unwrap();
+        assert_eq!(
+            run(&mut state, &["PFADD", "text", "a"]).unwrap_err(),
+            "WRONGTYPE Key is not a valid HyperLogLog string value."
+        );
+        // A dense header without registers.
+        let truncated = format!("HYLL{}", "\u{0}".repeat(12));
+        run(&mut state, &["SET", "truncated", &truncated]).unwrap();
+        assert_eq!(count(&mut state, &["hll"]), 7);
+        assert_eq!(run(&mut state, &["PFCOUNT", "truncated"]).unwrap_err(), "INVALIDOBJ Corrupted HLL object detected");
+        run(&mut state, &["RPUSH", "list", "a"]).unwrap();
+        assert!(run(&mut state, &["PFCOUNT", "list"]).unwrap_err().starts_with("WRONGTYPE"));
+    }
+
+    #[test]
+    fn test_accuracy() {
+        let mut state = DefaultServerState::default();
+        let mut added = 0;
+        for total in [100, 1_000, 10_000, 100_000, 250_000] {
+            add_range(&mut state, "hll", added..total);
+            added = total;
+            assert_close(count(&mut state, &["hll"]), total);
+        }
+    }
+
+    #[test]
+    fn test_sparse_promotion_and_copy() {
+        let mut state = DefaultServerState::default();
+        add_range(&mut state, "hll", 0..100);
+        let sparse = get(&mut state, "hll");
+        assert!(sparse.starts_with(b"HYLL\x01"));
+        assert!(sparse.len() < 1000);
+
+        add_range(&mut state, "hll", 100..5_000);
+        let dense = get(&mut state, "hll");
+        assert!(dense.starts_with(b"HYLL\x00"));
+        assert_eq!(dense.len(), 16 + 16384 * 6 / 8);
+
+        // HyperLogLogs are plain strings that can be copied around.
+        set(&mut state, "copy", &dense);
+        assert_eq!(count(&mut state, &["copy"]), count(&mut state, &["hll"]));
+        assert_eq!(run(&mut state, &["PFADD", "copy", "element:1"]).unwrap(), RespType::Integer(0));
+        assert_eq!(run(&mut state, &["PFADD", "copy", "new"]).unwrap(), RespType::Integer(1));
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut state = DefaultServerState::default();
+        add_range(&mut state, "a", 0..6_000);
+        add_range(&mut state, "b", 4_000..10_000);
+
+        assert_close(count(&mut state, &["a", "b", "missing"]), 10_000);
+        assert_eq!(run(&mut state, &["PFMERGE", "union", "a", "b"]).unwrap(), RespType::SimpleString("OK".to_string()));
+        assert_eq!(count(&mut state, &["union"]), count(&mut state, &["a", "b"]));
+
+        // The destination is part of the union.
+        add_range(&mut state, "c", 10_000..12_000);
+        run(&mut state, &["PFMERGE", "c", "union"]).unwrap();
+        assert_close(count(&mut state, &["c"]), 12_000);
+
+        run(&mut state, &["PFMERGE", "new"]).unwrap();
+        assert_eq!(count(&mut state, &["new"]), 0);
+        run(&mut state, &["SET", "text", "hello"]).unwrap();
+        assert!(run(&mut state, &["PFMERGE", "union", "text"]).is_err());
+    }
+}
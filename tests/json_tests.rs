@@ -0,0 +1,294 @@
+/// Integration tests for JSON document commands
+#[cfg(test)]
+mod test_json {
+    use codecrafters_redis::resp::client::ClientContext;
+    use codecrafters_redis::resp::command_dispatcher::CommandDispatcher;
+    use codecrafters_redis::resp::protocol::RespType;
+    use codecrafters_redis::resp::state::default_server_state::DefaultServerState;
+
+    fn bulk(s: &str) -> RespType {
+        RespType::BulkString(Some(s.to_string()))
+    }
+
+    fn run(state: &mut DefaultServerState, args: &[&str]) -> Result<RespType, String> {
+        let mut client = ClientContext::default();
+        CommandDispatcher::new().dispatch(args[0], args[1..].iter().map(|arg| bulk(arg)).collect(), state, &mut client)
+    }
+
+    /// Flattens a reply to its strings and integers.
+    fn strings(reply: RespType) -> Vec<String> {
+        match reply {
+            RespType::Array(items) => items.into_iter().flat_map(strings).collect(),
+            RespType::BulkString(Some(value)) => vec![value],
+            RespType::Integer(value) => vec![value.to_string()],
+            RespType::BulkString(None) | RespType::NullArray => vec!["(nil)".to_string()],
+            other => panic!("unexpected reply {:?}", other),
+        }
+    }
+
+    fn ok() -> RespType {
+        RespType::SimpleString("OK".to_string())
+    }
+
+    /// Copies `key` to `copy` through `DUMP` and `RESTORE`.
+    fn dump_and_restore(state: &mut DefaultServerState, key: &str, copy: &str) {
+        let payload = run(state, &["DUMP", key]).unwrap();
+        let args = vec![bulk(copy), bulk("0"), payload];
+        CommandDispatcher::new().dispatch("RESTORE", args, state, &mut ClientContext::default()).unwrap();
+    }
+
+    /// State holding `doc` as a small store document.
+    fn store() -> DefaultServerState {
+        let mut state = DefaultServerState::default();
+        let document = r#"{
+            "name": "shop",
+            "open": true,
+            "items": [{"id": 1, "price": 9.5, "tags": ["a", "b"]}, {"id": 2, "price": 20, "tags": []}],
+            "owner": {"name": "ann", "age": 40}
+        }"#;
+        assert_eq!(run(&mut state, &["JSON.SET", "doc", "$", document]).unwrap(), ok());
+        state
+    }
+
+    #[test]
+    fn test_set_and_get() {
+        let mut state = store();
+
+        assert_eq!(run(&mut state, &["JSON.GET", "doc", "$.owner.name"]).unwrap(), bulk(r#"["ann"]"#));
+        assert_eq!(run(&mut state, &["JSON.GET", "doc", ".owner.name"]).unwrap(), bulk(r#""ann""#));
+        assert_eq!(run(&mut state, &["JSON.GET", "doc", "owner.age"]).unwrap(), bulk("40"));
+        assert_eq!(run(&mut state, &["JSON.GET", "doc", "$..name"]).unwrap(), bulk(r#"["shop","ann"]"#));
+        assert_eq!(run(&mut state, &["JSON.GET", "doc", "$.items[*].id"]).unwrap(), bulk("[1,2]"));
+        assert_eq!(run(&mut state, &["JSON.GET", "doc", "$.items[-1].price"]).unwrap(), bulk("[20]"));
+        assert_eq!(run(&mut state, &["JSON.GET", "doc", "$.missing"]).unwrap(), bulk("[]"));
+        assert_eq!(run(&mut state, &["JSON.GET", "doc", ".missing"]).unwrap_err(), "Path '.missing' does not exist");
+        assert_eq!(run(&mut state, &["JSON.GET", "nothing"]).unwrap(), RespType::BulkString(None));
+
+        // Several paths reply with an object keyed by path.
+        assert_eq!(
+            run(&mut state, &["JSON.GET", "doc", ".name", ".owner.age"]).unwrap(),
+            bulk(r#"{".name":"shop",".owner.age":40}"#)
+        );
+        assert_eq!(
+            run(&mut state, &["JSON.GET", "doc", "$.name", ".open"]).unwrap(),
+            bulk(r#"{"$.name":["shop"],".open":[true]}"#)
+        );
+
+        // Existing values are replaced and missing members added to their parent object.
+        assert_eq!(run(&mut state, &["JSON.SET", "doc", "$.owner.age", "41"]).unwrap(), ok());
+        assert_eq!(run(&mut state, &["JSON.SET", "doc", "$.owner.email", r#""a@b.c""#]).unwrap(), ok());
+        assert_eq!(
+            run(&mut state, &["JSON.GET", "doc", "$.owner"]).unwrap(),
+            bulk(r#"[{"name":"ann","age":41,"email":"a@b.c"}]"#)
+        );
+        assert_eq!(run(&mut state, &["JSON.SET", "doc", "$.items[*].id", "0"]).unwrap(), ok());
+        assert_eq!(run(&mut state, &["JSON.GET", "doc", "$.items[*].id"]).unwrap(), bulk("[0,0]"));
+        assert_eq!(run(&mut state, &["JSON.SET", "doc", "$.a.b", "1"]).unwrap(), RespType::BulkString(None));
+
+        // NX and XX
+        assert_eq!(run(&mut state, &["JSON.SET", "doc", "$.name", "1", "NX"]).unwrap(), RespType::BulkString(None));
+        assert_eq!(run(&mut state, &["JSON.SET", "doc", "$.city", "1", "XX"]).unwrap(), RespType::BulkString(None));
+        assert_eq!(run(&mut state, &["JSON.SET", "doc", "$.city", r#""Oslo""#, "NX"]).unwrap(), ok());
+        assert_eq!(run(&mut state, &["JSON.GET", "doc", "city"]).unwrap(), bulk(r#""Oslo""#));
+
+        assert_eq!(run(&mut state, &["TYPE", "doc"]).unwrap(), RespType::SimpleString("ReJSON-RL".to_string()));
+    }
+
+    #[test]
+    fn test_get_formatting() {
+        let mut state = DefaultServerState::default();
+        run(&mut state, &["JSON.SET", "doc", ".", r#"{"a":[1,2],"b":{}}"#]).unwrap();
+
+        assert_eq!(
+            run(&mut state, &["JSON.GET", "doc", "INDENT", "  ", "NEWLINE", "\n", "SPACE", " "]).unwrap(),
+            bulk("{\n  \"a\": [\n    1,\n    2\n  ],\n  \"b\": {}\n}")
+        );
+        assert_eq!(run(&mut state, &["JSON.GET", "doc", "SPACE", " ", "$.a"]).unwrap(), bulk("[[1,2]]"));
+        assert_eq!(run(&mut state, &["JSON.GET", "doc", "INDENT"]).unwrap_err(), "syntax error");
+    }
+
+    #[test]
+    fn test_filters() {
+        let mut state = store();
+
+        assert_eq!(run(&mut state, &["JSON.GET", "doc", "$.items[?(@.price > 10)].id"]).unwrap(), bulk("[2]"));
+        assert_eq!(
+            run(&mut state, &["JSON.GET", "doc", "$.items[?(@.price < 10 || @.id == 2)].id"]).unwrap(),
+            bulk("[1,2]")
+        );
+        assert_eq!(
+            run(&mut state, &["JSON.GET", "doc", r#"$.items[?(@.id >= 1 && @.price != 20)].tags[0]"#]).unwrap(),
+            bulk(r#"["a"]"#)
+        );
+        assert_eq!(run(&mut state, &["JSON.GET", "doc", "$.items[0:1].id"]).unwrap(), bulk("[1]"));
+        assert_eq!(run(&mut state, &["JSON.GET", "doc", "$['name','open']"]).unwrap(), bulk(r#"["shop",true]"#));
+        assert_eq!(run(&mut state, &["JSON.GET", "doc", "$.items["]).unwrap_err(), "Invalid JSONPath '$.items['");
+    }
+
+    #[test]
+    fn test_deeply_nested_filters() {
+        let mut state = store();
+
+        let nested = |depth: usize, open: &str, close: &str| {
+            format!("$.items[?({}@.id == 2{})].id", open.repeat(depth), close.repeat(depth))
+        };
+        assert_eq!(run(&mut state, &["JSON.GET", "doc", &nested(100, "(", ")")]).unwrap(), bulk("[2]"));
+
+        // Nesting that would overflow the stack is an invalid path, not a crash.
+        for path in [nested(100_000, "(", ")"), nested(100_000, "@[?(", ")]")] {
+            assert!(run(&mut state, &["JSON.GET", "doc", &path]).unwrap_err().starts_with("Invalid JSONPath"));
+        }
+    }
+
+    #[test]
+    fn test_delete_and_type() {
+        let mut state = store();
+
+        assert_eq!(
+            strings(run(&mut state, &["JSON.TYPE", "doc", "$..name"]).unwrap()),
+            vec!["string", "string"]
+        );
+        assert_eq!(run(&mut state, &["JSON.TYPE", "doc"]).unwrap(), bulk("object"));
+        assert_eq!(run(&mut state, &["JSON.TYPE", "doc", ".items[0].price"]).unwrap(), bulk("number"));
+        assert_eq!(run(&mut state, &["JSON.TYPE", "doc", ".owner.age"]).unwrap(), bulk("integer"));
+        assert_eq!(run(&mut state, &["JSON.TYPE", "nothing"]).unwrap(), RespType::BulkString(None));
+
+        assert_eq!(run(&mut state, &["JSON.DEL", "doc", "$..tags"]).unwrap(), RespType::Integer(2));
+        // Values inside deleted ones are not counted.
+        assert_eq!(run(&mut state, &["JSON.DEL", "doc", "$.items..*"]).unwrap(), RespType::Integer(2));
+        assert_eq!(run(&mut state, &["JSON.GET", "doc", "$.items"]).unwrap(), bulk("[[]]"));
+        assert_eq!(run(&mut state, &["JSON.DEL", "doc", "$.missing"]).unwrap(), RespType::Integer(0));
+        assert_eq!(run(&mut state, &["JSON.DEL", "doc", "$.owner"]).unwrap(), RespType::Integer(1));
+        assert_eq!(strings(run(&mut state, &["JSON.OBJKEYS", "doc"]).unwrap()), vec!["name", "open", "items"]);
+
+        assert_eq!(run(&mut state, &["JSON.DEL", "doc"]).unwrap(), RespType::Integer(1));
+        assert_eq!(run(&mut state, &["TYPE", "doc"]).unwrap(), RespType::SimpleString("none".to_string()));
+        assert_eq!(run(&mut state, &["JSON.DEL", "doc"]).unwrap(), RespType::Integer(0));
+    }
+
+    #[test]
+    fn test_numbers_and_strings() {
+        let mut state = store();
+
+        assert_eq!(run(&mut state, &["JSON.NUMINCRBY", "doc", "$..price", "1"]).unwrap(), bulk("[10.5,21]"));
+        assert_eq!(run(&mut state, &["JSON.NUMINCRBY", "doc", ".owner.age", "0.5"]).unwrap(), bulk("40.5"));
+        assert_eq!(run(&mut state, &["JSON.NUMINCRBY", "doc", "$.name", "1"]).unwrap(), bulk("[null]"));
+        assert_eq!(
+            run(&mut state, &["JSON.NUMINCRBY", "doc", ".name", "1"]).unwrap_err(),
+            "WRONGTYPE wrong type of path value - expected number but found string"
+        );
+        assert_eq!(
+            run(&mut state, &["JSON.NUMINCRBY", "doc", ".owner.age", "1e308"]).unwrap(),
+            bulk("1e308")
+        );
+        assert_eq!(
+            run(&mut state, &["JSON.NUMINCRBY", "doc", ".owner.age", "1e308"]).unwrap_err(),
+            "result is too large"
+        );
+        assert_eq!(
+            run(&mut state, &["JSON.NUMINCRBY", "doc", ".name", r#""1""#]).unwrap_err(),
+            "expected a number as increment"
+        );
+
+        assert_eq!(
+            strings(run(&mut state, &["JSON.STRAPPEND", "doc", "$..name", r#""!""#]).unwrap()),
+            vec!["5", "4"]
+        );
+        assert_eq!(run(&mut state, &["JSON.STRAPPEND", "doc", ".name", r#""s""#]).unwrap(), RespType::Integer(6));
+        assert_eq!(run(&mut state, &["JSON.GET", "doc", "name"]).unwrap(), bulk(r#""shop!s""#));
+        assert_eq!(
+            run(&mut state, &["JSON.STRAPPEND", "doc", ".name", "1"]).unwrap_err(),
+            "expected a JSON string to append"
+        );
+        assert_eq!(
+            run(&mut state, &["JSON.STRAPPEND", "nothing", r#""a""#]).unwrap_err(),
+            "could not perform this operation on a key that doesn't exist"
+        );
+    }
+
+    #[test]
+    fn test_arrays() {
+        let mut state = store();
+
+        assert_eq!(
+            strings(run(&mut state, &["JSON.ARRAPPEND", "doc", "$..tags", r#""c""#, "1"]).unwrap()),
+            vec!["4", "2"]
+        );
+        assert_eq!(run(&mut state, &["JSON.GET", "doc", "$.items[1].tags"]).unwrap(), bulk(r#"[["c",1]]"#));
+        assert_eq!(
+            strings(run(&mut state, &["JSON.ARRLEN", "doc", "$.items[*].tags"]).unwrap()),
+            vec!["4", "2"]
+        );
+        assert_eq!(run(&mut state, &["JSON.ARRLEN", "doc", ".items"]).unwrap(), RespType::Integer(2));
+        assert_eq!(strings(run(&mut state, &["JSON.ARRLEN", "doc", "$.name"]).unwrap()), vec!["(nil)"]);
+        assert_eq!(run(&mut state, &["JSON.ARRLEN", "nothing"]).unwrap(), RespType::BulkString(None));
+
+        assert_eq!(
+            run(&mut state, &["JSON.ARRINSERT", "doc", ".items[0].tags", "-1", "true", "null"]).unwrap(),
+            RespType::Integer(6)
+        );
+        assert_eq!(
+            run(&mut state, &["JSON.GET", "doc", ".items[0].tags"]).unwrap(),
+            bulk(r#"["a","b","c",true,null,1]"#)
+        );
+        assert_eq!(
+            run(&mut state, &["JSON.ARRINSERT", "doc", "$..tags", "3", "0"]).unwrap_err(),
+            "index out of bounds"
+        );
+        assert_eq!(run(&mut state, &["JSON.ARRLEN", "doc", ".items[1].tags"]).unwrap(), RespType::Integer(2));
+
+        assert_eq!(run(&mut state, &["JSON.ARRPOP", "doc", ".items[0].tags"]).unwrap(), bulk("1"));
+        assert_eq!(run(&mut state, &["JSON.ARRPOP", "doc", ".items[0].tags", "0"]).unwrap(), bulk(r#""a""#));
+        assert_eq!(run(&mut state, &["JSON.ARRPOP", "doc", ".items[0].tags", "99"]).unwrap(), bulk("null"));
+        assert_eq!(
+            strings(run(&mut state, &["JSON.ARRPOP", "doc", "$.items[*].id"]).unwrap()),
+            vec!["(nil)", "(nil)"]
+        );
+        run(&mut state, &["JSON.SET", "doc", "$.empty", "[]"]).unwrap();
+        assert_eq!(run(&mut state, &["JSON.ARRPOP", "doc", ".empty"]).unwrap(), RespType::BulkString(None));
+        assert_eq!(
+            run(&mut state, &["JSON.ARRAPPEND", "doc", ".name", "1"]).unwrap_err(),
+            "WRONGTYPE wrong type of path value - expected array but found string"
+        );
+
+        assert_eq!(
+            strings(run(&mut state, &["JSON.OBJKEYS", "doc", "$..owner"]).unwrap()),
+            vec!["name", "age"]
+        );
+        assert_eq!(strings(run(&mut state, &["JSON.OBJKEYS", "doc", "$.name"]).unwrap()), vec!["(nil)"]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut state = DefaultServerState::default();
+
+        assert_eq!(
+            run(&mut state, &["JSON.SET", "doc", "$.a", "1"]).unwrap_err(),
+            "new objects must be created at the root"
+        );
+        assert_eq!(
+            run(&mut state, &["JSON.SET", "doc", "$", "{\"a\":"]).unwrap_err(),
+            "EOF while parsing a value at line 1 column 6"
+        );
+        assert_eq!(run(&mut state, &["JSON.SET", "doc", "$", "1", "XX"]).unwrap(), RespType::BulkString(None));
+        assert_eq!(run(&mut state, &["JSON.SET", "doc", "$", "1", "YY"]).unwrap_err(), "syntax error");
+        assert_eq!(
+            run(&mut state, &["JSON.ARRAPPEND", "doc", "$", "1"]).unwrap_err(),
+            "could not perform this operation on a key that doesn't exist"
+        );
+
+        run(&mut state, &["SET", "text", "a"]).unwrap();
+        assert!(run(&mut state, &["JSON.GET", "text"]).unwrap_err().starts_with("WRONGTYPE"));
+        assert!(run(&mut state, &["JSON.SET", "text", "$", "1"]).unwrap_err().starts_with("WRONGTYPE"));
+    }
+
+    #[test]
+    fn test_dump_and_restore() {
+        let mut state = store();
+
+        dump_and_restore(&mut state, "doc", "copy");
+
+        assert_eq!(run(&mut state, &["JSON.GET", "copy"]).unwrap(), run(&mut state, &["JSON.GET", "doc"]).unwrap());
+        assert_eq!(run(&mut state, &["JSON.TYPE", "copy", ".items[0].price"]).unwrap(), bulk("number"));
+    }
+}
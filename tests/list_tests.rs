@@ -0,0 +1,251 @@
+/// Integration tests for list commands
+#[cfg(test)]
+mod test_list {
+    use codecrafters_redis::resp::client::ClientContext;
+    use codecrafters_redis::resp::command_dispatcher::CommandDispatcher;
+    use codecrafters_redis::resp::protocol::RespType;
+    use codecrafters_redis::resp::state::default_server_state::DefaultServerState;
+    use codecrafters_redis::resp::state::server_state::ServerState;
+
+    fn bulk(s: &str) -> RespType {
+        RespType::BulkString(Some(s.to_string()))
+    }
+
+    fn array(items: &[&str]) -> RespType {
+        RespType::Array(items.iter().map(|item| bulk(item)).collect())
+    }
+
+    fn run(state: &mut DefaultServerState, args: &[&str]) -> Result<RespType, String> {
+        let mut client = ClientContext::default();
+        CommandDispatcher::new().dispatch(args[0], args[1..].iter().map(|arg| bulk(arg)).collect(), state, &mut client)
+    }
+
+    #[test]
+    fn test_push_and_range() {
+        let mut state = DefaultServerState::default();
+
+        assert_eq!(run(&mut state, &["RPUSH", "l", "a", "b", "c"]).unwrap(), RespType::Integer(3));
+        assert_eq!(run(&mut state, &["LPUSH", "l", "x", "y"]).unwrap(), RespType::Integer(5));
+        assert_eq!(run(&mut state, &["LRANGE", "l", "0", "-1"]).unwrap(), array(&["y", "x", "a", "b", "c"]));
+        assert_eq!(run(&mut state, &["LRANGE", "l", "-2", "100"]).unwrap(), array(&["b", "c"]));
+        assert_eq!(run(&mut state, &["LRANGE", "l", "3", "1"]).unwrap(), array(&[]));
+        assert_eq!(run(&mut state, &["LRANGE", "nope", "0", "-1"]).unwrap(), array(&[]));
+        assert_eq!(run(&mut state, &["LLEN", "l"]).unwrap(), RespType::Integer(5));
+        assert_eq!(run(&mut state, &["LINDEX", "l", "-1"]).unwrap(), bulk("c"));
+        assert_eq!(run(&mut state, &["LINDEX", "l", "5"]).unwrap(), RespType::BulkString(None));
+
+        assert_eq!(run(&mut state, &["LPUSHX", "nope", "a"]).unwrap(), RespType::Integer(0));
+        assert_eq!(run(&mut state, &["RPUSHX", "l", "z"]).unwrap(), RespType::Integer(6));
+        assert!(!state.exists("nope"));
+    }
+
+    #[test]
+    fn test_pop_deletes_empty_lists() {
+        let mut state = DefaultServerState::default();
+        run(&mut state, &["RPUSH", "l", "a", "b", "c"]).unwrap();
+
+        assert_eq!(run(&mut state, &["LPOP", "l"]).unwrap(), bulk("a"));
+        assert_eq!(run(&mut state, &["RPOP", "l", "5"]).unwrap(), array(&["c", "b"]));
+        assert!(!state.exists("l"));
+        assert_eq!(run(&mut state, &["LPOP", "l"]).unwrap(), RespType::BulkString(None));
+        assert_eq!(run(&mut state, &["LPOP", "l", "2"]).unwrap(), RespType::NullArray);
+        assert_eq!(
+            run(&mut state, &["LPOP", "l", "-1"]).unwrap_err(),
+            "value is out of range, must be positive"
+        );
+    }
+
+    #[test]
+    fn test_wrong_type() {
+        let mut state = DefaultServerState::default();
+        run(&mut state, &["SET", "s", "value"]).unwrap();
+        run(&mut state, &["RPUSH", "l", "a"]).unwrap();
+
+        for command in [
+            vec!["LPUSH", "s", "a"],
+            vec!["LPOP", "s"],
+            vec!["LLEN", "s"],
+            vec!["LRANGE", "s", "0", "-1"],
+            vec!["LMOVE", "l", "s", "LEFT", "LEFT"],
+        ] {
+            assert_eq!(
+                run(&mut state, &command).unwrap_err(),
+                "WRONGTYPE Operation against a key holding the wrong kind of value"
+            );
+        }
+        assert_eq!(run(&mut state, &["GET", "l"]).unwrap_err(), "WRONGTYPE Operation against a key holding the wrong kind of value");
+        // The failed LMOVE did not pop anything.
+        assert_eq!(run(&mut state, &["LLEN", "l"]).unwrap(), RespType::Integer(1));
+        assert_eq!(run(&mut state, &["TYPE", "l"]).unwrap(), RespType::SimpleString("list".to_string()));
+    }
+
+    #[test]
+    fn test_set_insert_and_remove() {
+        let mut state = DefaultServerState::default();
+        run(&mut state, &["RPUSH", "l", "a", "b", "a", "c", "a"]).unwrap();
+
+        assert_eq!(run(&mut state, &["LSET", "l", "-1", "z"]).unwrap(), RespType::SimpleString("OK".to_string()));
+        assert_eq!(run(&mut state, &["LSET", "l", "9", "z"]).unwrap_err(), "index out of range");
+        assert_eq!(run(&mut state, &["LSET", "nope", "0", "z"]).unwrap_err(), "no such key");
+
+        assert_eq!(run(&mut state, &["LINSERT", "l", "BEFORE", "c", "x"]).unwrap(), RespType::Integer(6));
+        assert_eq!(run(&mut state, &["LINSERT", "l", "AFTER", "c", "y"]).unwrap(), RespType::Integer(7));
+        assert_eq!(run(&mut state, &["LINSERT", "l", "AFTER", "missing", "y"]).unwrap(), RespType::Integer(-1));
+        assert_eq!(run(&mut state, &["LINSERT", "nope", "AFTER", "c", "y"]).unwrap(), RespType::Integer(0));
+        assert_eq!(
+            run(&mut state, &["LRANGE", "l", "0", "-1"]).unwrap(),
+            array(&["a", "b", "a", "x", "c", "y", "z"])
+        );
+
+        assert_eq!(run(&mut state, &["LREM", "l", "-1", "a"]).unwrap(), RespType::Integer(1));
+        assert_eq!(run(&mut state, &["LRANGE", "l", "0", "-1"]).unwrap(), array(&["a", "b", "x", "c", "y", "z"]));
+        assert_eq!(run(&mut state, &["LREM", "l", "0", "a"]).unwrap(), RespType::Integer(1));
+
+        assert_eq!(run(&mut state, &["LTRIM", "l", "1", "-2"]).unwrap(), RespType::SimpleString("OK".to_string()));
+        assert_eq!(run(&mut state, &["LRANGE", "l", "0", "-1"]).unwrap(), array(&["x", "c", "y"]));
+        run(&mut state, &["LTRIM", "l", "5", "10"]).unwrap();
+        assert!(!state.exists("l"));
+    }
+
+    #[test]
+    fn test_lpos() {
+        let mut state = DefaultServerState::default();
+        run(&mut state, &["RPUSH", "l", "a", "b", "c", "1", "2", "3", "c", "c"]).unwrap();
+
+        assert_eq!(run(&mut state, &["LPOS", "l", "c"]).unwrap(), RespType::Integer(2));
+        assert_eq!(run(&mut state, &["LPOS", "l", "c", "RANK", "2"]).unwrap(), RespType::Integer(6));
+        assert_eq!(run(&mut state, &["LPOS", "l", "c", "RANK", "-1"]).unwrap(), RespType::Integer(7));
+        assert_eq!(
+            run(&mut state, &["LPOS", "l", "c", "COUNT", "0"]).unwrap(),
+            RespType::Array(vec![RespType::Integer(2), RespType::Integer(6), RespType::Integer(7)])
+        );
+        assert_eq!(
+            run(&mut state, &["LPOS", "l", "c", "RANK", "-1", "COUNT", "2"]).unwrap(),
+            RespType::Array(vec![RespType::Integer(7), RespType::Integer(6)])
+        );
+        assert_eq!(
+            run(&mut state, &["LPOS", "l", "c", "COUNT", "0", "MAXLEN", "3"]).unwrap(),
+            RespType::Array(vec![RespType::Integer(2)])
+        );
+        assert_eq!(run(&mut state, &["LPOS", "l", "x"]).unwrap(), RespType::BulkString(None));
+        assert!(run(&mut state, &["LPOS", "l", "c", "RANK", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_move() {
+        let mut state = DefaultServerState::default();
+        run(&mut state, &["RPUSH", "src", "a", "b", "c"]).unwrap();
+
+        assert_eq!(run(&mut state, &["LMOVE", "src", "dst", "LEFT", "RIGHT"]).unwrap(), bulk("a"));
+        assert_eq!(run(&mut state, &["RPOPLPUSH", "src", "dst"]).unwrap(), bulk("c"));
+        assert_eq!(run(&mut state, &["LRANGE", "dst", "0", "-1"]).unwrap(), array(&["c", "a"]));
+
+        // Rotation of a single list.
+        assert_eq!(run(&mut state, &["LMOVE", "dst", "dst", "LEFT", "RIGHT"]).unwrap(), bulk("c"));
+        assert_eq!(run(&mut state, &["LRANGE", "dst", "0", "-1"]).unwrap(), array(&["a", "c"]));
+
+        run(&mut state, &["LMOVE", "src", "dst", "LEFT", "LEFT"]).unwrap();
+        assert!(!state.exists("src"));
+        assert_eq!(run(&mut state, &["LMOVE", "src", "dst", "LEFT", "LEFT"]).unwrap(), RespType::BulkString(None));
+        assert_eq!(run(&mut state, &["LMOVE", "dst", "src", "UP", "LEFT"]).unwrap_err(), "syntax error");
+    }
+
+    #[test]
+    fn test_binary_elements_are_rejected() {
+        let mut state = DefaultServerState::default();
+        let args = vec![bulk("list"), RespType::BulkBytes(vec![0xff])];
+        assert_eq!(
+            CommandDispatcher::new().dispatch("RPUSH", args, &mut state, &mut ClientContext::default()).unwrap_err(),
+            "RPUSH arguments must be strings"
+        );
+        assert!(state.keys().is_empty());
+    }
+}
+
+#[cfg(test)]
+mod test_blocking {
+    use codecrafters_redis::resp::blocking::{KeyOperation, KeyWait};
+    use codecrafters_redis::resp::client::{BlockedOn, ClientContext};
+    use codecrafters_redis::resp::command_dispatcher::CommandDispatcher;
+    use codecrafters_redis::resp::commands::list::End;
+    use codecrafters_redis::resp::protocol::RespType;
+    use codecrafters_redis::resp::state::default_server_state::DefaultServerState;
+
+    fn bulk(s: &str) -> RespType {
+        RespType::BulkString(Some(s.to_string()))
+    }
+
+    fn run(state: &mut DefaultServerState, client: &mut ClientContext, args: &[&str]) -> Result<RespType, String> {
+        CommandDispatcher::new().dispatch(args[0], args[1..].iter().map(|arg| bulk(arg)).collect(), state, client)
+    }
+
+    #[test]
+    fn test_served_immediately() {
+        let mut state = DefaultServerState::default();
+        let mut client = ClientContext::default();
+        run(&mut state, &mut client, &["RPUSH", "b", "1", "2", "3"]).unwrap();
+
+        assert_eq!(
+            run(&mut state, &mut client, &["BLPOP", "a", "b", "0"]).unwrap(),
+            RespType::Array(vec![bulk("b"), bulk("1")])
+        );
+        assert_eq!(
+            run(&mut state, &mut client, &["BLMPOP", "0", "2", "a", "b", "RIGHT", "COUNT", "5"]).unwrap(),
+            RespType::Array(vec![bulk("b"), RespType::Array(vec![bulk("3"), bulk("2")])])
+        );
+        assert_eq!(client.blocked, None);
+        assert_eq!(run(&mut state, &mut client, &["LMPOP", "1", "b", "LEFT"]).unwrap(), RespType::NullArray);
+    }
+
+    #[test]
+    fn test_blocks_on_empty_keys() {
+        let mut state = DefaultServerState::default();
+        let mut client = ClientContext::default();
+
+        run(&mut state, &mut client, &["BRPOP", "a", "b", "0"]).unwrap();
+        assert_eq!(
+            client.blocked.take(),
+            Some(BlockedOn::Keys(KeyWait {
+                keys: vec!["a".to_string(), "b".to_string()],
+                operation: KeyOperation::Pop(End::Right),
+                deadline: None,
+            }))
+        );
+
+        run(&mut state, &mut client, &["BLMOVE", "a", "b", "LEFT", "RIGHT", "1.5"]).unwrap();
+        let Some(BlockedOn::Keys(wait)) = client.blocked.take() else {
+            panic!("BLMOVE must block");
+        };
+        assert!(wait.deadline.is_some());
+        assert_eq!(
+            wait.operation,
+            KeyOperation::Move {
+                destination: "b".to_string(),
+                from: End::Left,
+                to: End::Right,
+            }
+        );
+    }
+
+    #[test]
+    fn test_invalid_arguments() {
+        let mut state = DefaultServerState::default();
+        let mut client = ClientContext::default();
+
+        assert_eq!(
+            run(&mut state, &mut client, &["BLPOP", "a", "soon"]).unwrap_err(),
+            "timeout is not a float or out of range"
+        );
+        assert_eq!(run(&mut state, &mut client, &["BLPOP", "a", "-1"]).unwrap_err(), "timeout is negative");
+        assert_eq!(
+            run(&mut state, &mut client, &["BLMPOP", "0", "0", "a", "LEFT"]).unwrap_err(),
+            "numkeys should be greater than 0"
+        );
+        assert_eq!(
+            run(&mut state, &mut client, &["LMPOP", "1", "a", "LEFT", "COUNT", "0"]).unwrap_err(),
+            "count should be greater than 0"
+        );
+        assert_eq!(run(&mut state, &mut client, &["LMPOP", "1", "a", "UP"]).unwrap_err(), "syntax error");
+        assert_eq!(client.blocked, None);
+    }
+}
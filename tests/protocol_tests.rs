@@ -136,7 +136,7 @@ mod resp_parser {
 
 #[cfg(test)]
 mod resp_serializer {
-    use codecrafters_redis::resp::protocol::{serialize, RespType};
+    use codecrafters_redis::resp::protocol::{deserialize, serialize, RespType};
 
     #[test]
     fn serialize_simple_string() {
@@ -186,6 +186,16 @@ mod resp_serializer {
         assert_eq!(serialized_input, b"$-1\r\n");
     }
 
+    #[test]
+    fn binary_bulk_string_round_trip() {
+        let input = b"$3\r\n\xff\x00a\r\n";
+        let (parsed, len) = deserialize(input).unwrap();
+        assert_eq!(parsed, RespType::BulkBytes(vec![0xff, 0, b'a']));
+        assert_eq!(len, input.len());
+        assert_eq!(serialize(&parsed), input);
+        assert_eq!(RespType::bulk_bytes(b"text".to_vec()), RespType::BulkString(Some("text".to_string())));
+    }
+
     #[test]
     fn serialize_incomplete_bulk_string() {
         let input = RespType::BulkString(Some("hel".to_string()));
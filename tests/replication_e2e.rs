@@ -0,0 +1,291 @@
+use std::io::{Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::process::{Child, Command};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, sleep};
+use std::time::Duration;
+
+/// Server process that is killed when the test finishes.
+struct Server(Child);
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+fn start_server(args: &[&str]) -> Server {
+    let child = Command::new(env!("CARGO_BIN_EXE_codecrafters-redis"))
+        .args(args)
+        .spawn()
+        .expect("failed to start server");
+    Server(child)
+}
+
+fn wait_for_server_ready(addr: &str) {
+    let start = std::time::Instant::now();
+    while start.elapsed() < Duration::from_secs(30) {
+        if TcpStream::connect(addr).is_ok() {
+            return;
+        }
+        sleep(Duration::from_millis(100));
+    }
+    panic!("Server did not start in time");
+}
+
+fn encode(parts: &[&str]) -> Vec<u8> {
+    let mut out = format!("*{}\r\n", parts.len()).into_bytes();
+    for part in parts {
+        out.extend(format!("${}\r\n{}\r\n", part.len(), part).into_bytes());
+    }
+    out
+}
+
+fn send_and_receive(stream: &mut TcpStream, parts: &[&str]) -> String {
+    stream.write_all(&encode(parts)).unwrap();
+    let mut buf = [0; 4096];
+    let n = stream.read(&mut buf).unwrap();
+    String::from_utf8_lossy(&buf[..n]).to_string()
+}
+
+/// Polls `GET key` until it returns the expected reply or the timeout expires.
+fn wait_for_value(addr: &str, key: &str, expected: &str) -> bool {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    for _ in 0..50 {
+        if send_and_receive(&mut stream, &["GET", key]) == expected {
+            return true;
+        }
+        sleep(Duration::from_millis(100));
+    }
+    false
+}
+
+fn info_field(addr: &str, section: &str, field: &str) -> String {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    let info = send_and_receive(&mut stream, &["INFO", section]);
+    info.lines()
+        .find_map(|line| line.strip_prefix(&format!("{}:", field)))
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// TCP proxy between a replica and its primary whose connections can be cut
+/// to simulate a network blip.
+struct Proxy {
+    connections: Arc<Mutex<Vec<TcpStream>>>,
+}
+
+impl Proxy {
+    fn start(listen: &str, target: &'static str) -> Proxy {
+        let listener = TcpListener::bind(listen).unwrap();
+        let connections = Arc::new(Mutex::new(Vec::new()));
+        let registry = Arc::clone(&connections);
+
+        thread::spawn(move || {
+            for client in listener.incoming() {
+                let Ok(client) = client else { continue };
+                let Ok(upstream) = TcpStream::connect(target) else { continue };
+                registry.lock().unwrap().push(client.try_clone().unwrap());
+                registry.lock().unwrap().push(upstream.try_clone().unwrap());
+                pipe(client.try_clone().unwrap(), upstream.try_clone().unwrap());
+                pipe(upstream, client);
+            }
+        });
+
+        Proxy { connections }
+    }
+
+    fn cut(&self) {
+        for stream in self.connections.lock().unwrap().drain(..) {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+fn pipe(mut from: TcpStream, mut to: TcpStream) {
+    thread::spawn(move || {
+        let mut buf = [0; 4096];
+        while let Ok(n) = from.read(&mut buf) {
+            if n == 0 || to.write_all(&buf[..n]).is_err() {
+                break;
+            }
+        }
+        let _ = to.shutdown(Shutdown::Both);
+    });
+}
+
+#[test]
+fn test_replica_receives_writes() {
+    let _master = start_server(&["--port", "6400"]);
+    wait_for_server_ready("127.0.0.1:6400");
+
+    let mut master = TcpStream::connect("127.0.0.1:6400").unwrap();
+    assert_eq!(send_and_receive(&mut master, &["SET", "before", "sync"]), "+OK\r\n");
+
+    let _replica = start_server(&["--port", "6401", "--replicaof", "127.0.0.1 6400"]);
+    wait_for_server_ready("127.0.0.1:6401");
+
+    // Data written before the replica connected arrives with the RDB snapshot.
+    assert!(wait_for_value("127.0.0.1:6401", "before", "$4\r\nsync\r\n"));
+
+    assert_eq!(send_and_receive(&mut master, &["SET", "foo", "bar"]), "+OK\r\n");
+    assert!(wait_for_value("127.0.0.1:6401", "foo", "$3\r\nbar\r\n"));
+
+    assert_eq!(info_field("127.0.0.1:6400", "replication", "connected_slaves"), "1");
+    assert_eq!(info_field("127.0.0.1:6401", "replication", "role"), "slave");
+    assert_eq!(info_field("127.0.0.1:6401", "replication", "master_link_status"), "up");
+
+    let mut replica = TcpStream::connect("127.0.0.1:6401").unwrap();
+    assert_eq!(
+        send_and_receive(&mut replica, &["SET", "foo", "baz"]),
+        "-READONLY You can't write against a read only replica.\r\n"
+    );
+}
+
+#[test]
+fn test_partial_resync_after_network_blip() {
+    let _master = start_server(&["--port", "6410"]);
+    wait_for_server_ready("127.0.0.1:6410");
+    let proxy = Proxy::start("127.0.0.1:6411", "127.0.0.1:6410");
+
+    let _replica = start_server(&["--port", "6412", "--replicaof", "127.0.0.1 6411"]);
+    wait_for_server_ready("127.0.0.1:6412");
+
+    let mut master = TcpStream::connect("127.0.0.1:6410").unwrap();
+    assert_eq!(send_and_receive(&mut master, &["SET", "a", "1"]), "+OK\r\n");
+    assert!(wait_for_value("127.0.0.1:6412", "a", "$1\r\n1\r\n"));
+
+    proxy.cut();
+    // Written while the link is down: must be served from the backlog.
+    assert_eq!(send_and_receive(&mut master, &["SET", "b", "2"]), "+OK\r\n");
+
+    assert!(wait_for_value("127.0.0.1:6412", "b", "$1\r\n2\r\n"));
+    assert_eq!(info_field("127.0.0.1:6410", "stats", "sync_full"), "1");
+    assert_eq!(info_field("127.0.0.1:6410", "stats", "sync_partial_ok"), "1");
+    assert_eq!(
+        info_field("127.0.0.1:6410", "replication", "master_repl_offset"),
+        info_field("127.0.0.1:6412", "replication", "master_repl_offset")
+    );
+}
+
+#[test]
+fn test_wait_for_replicas() {
+    let _master = start_server(&["--port", "6420"]);
+    wait_for_server_ready("127.0.0.1:6420");
+    let _replica1 = start_server(&["--port", "6421", "--replicaof", "127.0.0.1 6420"]);
+    let _replica2 = start_server(&["--port", "6422", "--replicaof", "127.0.0.1 6420"]);
+    wait_for_server_ready("127.0.0.1:6421");
+    wait_for_server_ready("127.0.0.1:6422");
+
+    let mut master = TcpStream::connect("127.0.0.1:6420").unwrap();
+    for _ in 0..50 {
+        if info_field("127.0.0.1:6420", "replication", "connected_slaves") == "2" {
+            break;
+        }
+        sleep(Duration::from_millis(100));
+    }
+
+    assert_eq!(send_and_receive(&mut master, &["SET", "foo", "1"]), "+OK\r\n");
+    assert_eq!(send_and_receive(&mut master, &["WAIT", "2", "2000"]), ":2\r\n");
+
+    // Asking for more replicas than there are waits for the whole timeout.
+    let started = std::time::Instant::now();
+    assert_eq!(send_and_receive(&mut master, &["SET", "foo", "2"]), "+OK\r\n");
+    assert_eq!(send_and_receive(&mut master, &["WAIT", "3", "500"]), ":2\r\n");
+    assert!(started.elapsed() >= Duration::from_millis(500));
+}
+
+fn sentinel_master_addr(addr: &str) -> String {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    send_and_receive(&mut stream, &["SENTINEL", "get-master-addr-by-name", "mymaster"])
+}
+
+#[test]
+fn test_sentinel_failover() {
+    let mut master = start_server(&["--port", "6430"]);
+    wait_for_server_ready("127.0.0.1:6430");
+    let _replica1 = start_server(&["--port", "6431", "--replicaof", "127.0.0.1 6430"]);
+    let _replica2 = start_server(&["--port", "6432", "--replicaof", "127.0.0.1 6430"]);
+    wait_for_server_ready("127.0.0.1:6431");
+    wait_for_server_ready("127.0.0.1:6432");
+
+    let sentinel_ports = ["26430", "26431", "26432"];
+    let _sentinels = sentinel_ports
+        .iter()
+        .map(|port| {
+            let mut args = vec![
+                "--sentinel".to_string(),
+                "--port".to_string(),
+                port.to_string(),
+                "--sentinel-monitor".to_string(),
+                "mymaster 127.0.0.1 6430 2".to_string(),
+                "--sentinel-down-after-milliseconds".to_string(),
+                "1000".to_string(),
+                "--sentinel-failover-timeout".to_string(),
+                "5000".to_string(),
+            ];
+            for peer in sentinel_ports.iter().filter(|peer| *peer != port) {
+                args.push("--sentinel-peer".to_string());
+                args.push(format!("127.0.0.1 {}", peer));
+            }
+            start_server(&args.iter().map(String::as_str).collect::<Vec<_>>())
+        })
+        .collect::<Vec<_>>();
+    for port in sentinel_ports {
+        wait_for_server_ready(&format!("127.0.0.1:{}", port));
+    }
+
+    let original = "*2\r\n$9\r\n127.0.0.1\r\n$4\r\n6430\r\n";
+    assert_eq!(sentinel_master_addr("127.0.0.1:26430"), original);
+
+    let mut client = TcpStream::connect("127.0.0.1:6430").unwrap();
+    assert_eq!(send_and_receive(&mut client, &["SET", "foo", "bar"]), "+OK\r\n");
+    assert!(wait_for_value("127.0.0.1:6431", "foo", "$3\r\nbar\r\n"));
+    assert!(wait_for_value("127.0.0.1:6432", "foo", "$3\r\nbar\r\n"));
+    // Let the sentinels discover the replicas.
+    sleep(Duration::from_secs(2));
+
+    let _ = master.0.kill();
+    let _ = master.0.wait();
+
+    let mut promoted = String::new();
+    for _ in 0..150 {
+        let reply = sentinel_master_addr("127.0.0.1:26430");
+        if reply != original {
+            promoted = reply;
+            break;
+        }
+        sleep(Duration::from_millis(100));
+    }
+    let (new_master, other) = if promoted.ends_with("6431\r\n") {
+        ("127.0.0.1:6431", "127.0.0.1:6432")
+    } else if promoted.ends_with("6432\r\n") {
+        ("127.0.0.1:6432", "127.0.0.1:6431")
+    } else {
+        panic!("no failover happened: {:?}", promoted);
+    };
+
+    assert_eq!(info_field(new_master, "replication", "role"), "master");
+    let new_port = &new_master[new_master.len() - 4..];
+    assert_eq!(info_field(other, "replication", "master_port"), new_port);
+
+    // Every sentinel agrees on the new primary.
+    for port in ["26431", "26432"] {
+        let addr = format!("127.0.0.1:{}", port);
+        let mut agreed = false;
+        for _ in 0..30 {
+            if sentinel_master_addr(&addr) == promoted {
+                agreed = true;
+                break;
+            }
+            sleep(Duration::from_millis(100));
+        }
+        assert!(agreed);
+    }
+
+    // The remaining replica follows the new primary.
+    let mut client = TcpStream::connect(new_master).unwrap();
+    assert_eq!(send_and_receive(&mut client, &["SET", "foo", "baz"]), "+OK\r\n");
+    assert!(wait_for_value(other, "foo", "$3\r\nbaz\r\n"));
+}
@@ -0,0 +1,686 @@
+/// Integration tests for replication building blocks
+#[cfg(test)]
+mod test_backlog {
+    use codecrafters_redis::resp::replication::backlog::ReplicationBacklog;
+
+    #[test]
+    fn test_read_from_start() {
+        let mut backlog = ReplicationBacklog::new(16);
+        backlog.feed(b"hello");
+        backlog.feed(b"world");
+
+        assert_eq!(backlog.end_offset(), 10);
+        assert_eq!(backlog.first_byte_offset(), 1);
+        assert_eq!(backlog.read_from(1).unwrap(), b"helloworld".to_vec());
+        assert_eq!(backlog.read_from(6).unwrap(), b"world".to_vec());
+    }
+
+    #[test]
+    fn test_read_at_end_is_empty() {
+        let mut backlog = ReplicationBacklog::new(16);
+        backlog.feed(b"hello");
+
+        assert_eq!(backlog.read_from(6).unwrap(), Vec::<u8>::new());
+        assert!(backlog.read_from(7).is_none());
+    }
+
+    #[test]
+    fn test_wraps_around() {
+        let mut backlog = ReplicationBacklog::new(8);
+        backlog.feed(b"abcdef");
+        backlog.feed(b"ghijk");
+
+        assert_eq!(backlog.histlen(), 8);
+        assert_eq!(backlog.first_byte_offset(), 4);
+        assert_eq!(backlog.read_from(4).unwrap(), b"defghijk".to_vec());
+        assert_eq!(backlog.read_from(9).unwrap(), b"ijk".to_vec());
+        assert!(backlog.read_from(3).is_none());
+    }
+
+    #[test]
+    fn test_oversized_write_keeps_tail() {
+        let mut backlog = ReplicationBacklog::new(4);
+        backlog.feed(b"abcdefghij");
+
+        assert_eq!(backlog.end_offset(), 10);
+        assert_eq!(backlog.read_from(7).unwrap(), b"ghij".to_vec());
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut backlog = ReplicationBacklog::new(8);
+        backlog.feed(b"abc");
+        backlog.reset(100);
+
+        assert_eq!(backlog.end_offset(), 100);
+        assert!(backlog.read_from(3).is_none());
+        backlog.feed(b"xyz");
+        assert_eq!(backlog.read_from(101).unwrap(), b"xyz".to_vec());
+    }
+}
+
+#[cfg(test)]
+mod test_psync {
+    use codecrafters_redis::resp::client::ClientContext;
+    use codecrafters_redis::resp::commands::{Command, Psync};
+    use codecrafters_redis::resp::protocol::{serialize, RespType};
+    use codecrafters_redis::resp::state::default_server_state::DefaultServerState;
+    use codecrafters_redis::resp::state::server_state::ServerState;
+
+    fn bulk(s: &str) -> RespType {
+        RespType::BulkString(Some(s.to_string()))
+    }
+
+    #[test]
+    fn test_full_resync() {
+        let mut state = DefaultServerState::default();
+        let mut client = ClientContext::default();
+
+        let result = Psync
+            .execute_with_client(&[bulk("?"), bulk("-1")], &mut state, &mut client)
+            .unwrap();
+
+        let expected = format!("FULLRESYNC {} 0", state.replication().replid());
+        assert_eq!(result, RespType::SimpleString(expected));
+        assert!(client.replica_id.is_some());
+
+        let payload = client.replication_stream.as_mut().unwrap().try_recv().unwrap();
+        assert!(payload.starts_with(b"$"));
+        assert_eq!(state.replication().stats.full, 1);
+    }
+
+    #[test]
+    fn test_partial_resync_within_backlog() {
+        let mut state = DefaultServerState::default();
+        state
+            .replication()
+            .propagate(&RespType::Array(vec![bulk("SET"), bulk("a"), bulk("1")]));
+        let replid = state.replication().replid().to_string();
+
+        let mut client = ClientContext {
+            capabilities: vec!["psync2".to_string()],
+            ..Default::default()
+        };
+        let result = Psync
+            .execute_with_client(&[bulk(&replid), bulk("1")], &mut state, &mut client)
+            .unwrap();
+
+        assert_eq!(result, RespType::SimpleString(format!("CONTINUE {}", replid)));
+        let missing = client.replication_stream.as_mut().unwrap().try_recv().unwrap();
+        assert_eq!(missing, b"*3\r\n$3\r\nSET\r\n$1\r\na\r\n$1\r\n1\r\n".to_vec());
+        assert_eq!(state.replication().stats.partial_ok, 1);
+    }
+
+    #[test]
+    fn test_partial_resync_unknown_replid() {
+        let mut state = DefaultServerState::default();
+        let mut client = ClientContext::default();
+
+        let result = Psync
+            .execute_with_client(&[bulk("0123456789"), bulk("1")], &mut state, &mut client)
+            .unwrap();
+
+        assert!(matches!(result, RespType::SimpleString(ref s) if s.starts_with("FULLRESYNC")));
+        assert_eq!(state.replication().stats.partial_err, 1);
+    }
+
+    #[test]
+    fn test_registered_replica_receives_writes() {
+        let mut state = DefaultServerState::default();
+        let mut client = ClientContext::default();
+        Psync
+            .execute_with_client(&[bulk("?"), bulk("-1")], &mut state, &mut client)
+            .unwrap();
+        let stream = client.replication_stream.as_mut().unwrap();
+        stream.try_recv().unwrap();
+
+        let dispatcher = codecrafters_redis::resp::command_dispatcher::CommandDispatcher::new();
+        dispatcher
+            .dispatch("SET", vec![bulk("key"), bulk("value")], &mut state, &mut ClientContext::default())
+            .unwrap();
+
+        let propagated = stream.try_recv().unwrap();
+        assert_eq!(propagated, b"*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$5\r\nvalue\r\n".to_vec());
+        assert_eq!(state.replication().offset(), propagated.len() as u64);
+
+        // Reads are not propagated.
+        dispatcher
+            .dispatch("GET", vec![bulk("key")], &mut state, &mut ClientContext::default())
+            .unwrap();
+        assert!(stream.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_incrbyfloat_propagates_its_result() {
+        let mut state = DefaultServerState::default();
+        let mut client = ClientContext::default();
+        Psync
+            .execute_with_client(&[bulk("?"), bulk("-1")], &mut state, &mut client)
+            .unwrap();
+        let stream = client.replication_stream.as_mut().unwrap();
+        stream.try_recv().unwrap();
+
+        let dispatcher = codecrafters_redis::resp::command_dispatcher::CommandDispatcher::new();
+        for increment in ["0.1", "0.2"] {
+            dispatcher
+                .dispatch("INCRBYFLOAT", vec![bulk("key"), bulk(increment)], &mut state, &mut ClientContext::default())
+                .unwrap();
+        }
+
+        // Replicas store the value computed here instead of adding the increment themselves.
+        let set = |value: &str| {
+            serialize(&RespType::Array(vec![bulk("SET"), bulk("key"), bulk(value), bulk("KEEPTTL")]))
+        };
+        assert_eq!(stream.try_recv().unwrap(), set("0.1"));
+        assert_eq!(stream.try_recv().unwrap(), set("0.30000000000000004"));
+        assert!(stream.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_psync_without_connection() {
+        let mut state = DefaultServerState::default();
+
+        let result = Psync.execute(&[bulk("?"), bulk("-1")], &mut state);
+
+        assert!(result.is_err());
+    }
+}
+
+#[cfg(test)]
+mod test_replconf {
+    use codecrafters_redis::resp::client::ClientContext;
+    use codecrafters_redis::resp::commands::{Command, Replconf};
+    use codecrafters_redis::resp::protocol::RespType;
+    use codecrafters_redis::resp::state::default_server_state::DefaultServerState;
+    use codecrafters_redis::resp::state::server_state::ServerState;
+
+    fn bulk(s: &str) -> RespType {
+        RespType::BulkString(Some(s.to_string()))
+    }
+
+    #[test]
+    fn test_listening_port_and_capa() {
+        let mut state = DefaultServerState::default();
+        let mut client = ClientContext::default();
+
+        let result = Replconf
+            .execute_with_client(
+                &[bulk("listening-port"), bulk("6380"), bulk("capa"), bulk("psync2")],
+                &mut state,
+                &mut client,
+            )
+            .unwrap();
+
+        assert_eq!(result, RespType::SimpleString("OK".to_string()));
+        assert_eq!(client.listening_port, Some(6380));
+        assert_eq!(client.capabilities, vec!["psync2".to_string()]);
+    }
+
+    #[test]
+    fn test_ack_updates_replica_offset() {
+        let mut state = DefaultServerState::default();
+        let (id, _stream) = state
+            .replication()
+            .register_replica("127.0.0.1".to_string(), 6380, 0, Vec::new());
+        let mut client = ClientContext {
+            replica_id: Some(id),
+            ..Default::default()
+        };
+
+        Replconf
+            .execute_with_client(&[bulk("ACK"), bulk("42")], &mut state, &mut client)
+            .unwrap();
+
+        assert_eq!(state.replication().replicas()[0].ack_offset, 42);
+        assert!(state.replication().info().contains("slave0:ip=127.0.0.1,port=6380,state=online,offset=42"));
+    }
+
+    #[test]
+    fn test_getack() {
+        let mut state = DefaultServerState::default();
+        state.replication().feed(b"*1\r\n$4\r\nPING\r\n");
+
+        let result = Replconf.execute(&[bulk("GETACK"), bulk("*")], &mut state).unwrap();
+
+        assert_eq!(
+            result,
+            RespType::Array(vec![bulk("REPLCONF"), bulk("ACK"), bulk("14")])
+        );
+    }
+
+    #[test]
+    fn test_odd_arguments() {
+        let mut state = DefaultServerState::default();
+
+        let result = Replconf.execute(&[bulk("listening-port")], &mut state);
+
+        assert!(result.is_err());
+    }
+}
+
+#[cfg(test)]
+mod test_rdb {
+    use std::collections::HashMap;
+
+    use codecrafters_redis::resp::rdb::{decode, dump_value, encode, restore_value, RdbEntry};
+    use codecrafters_redis::resp::state::stream::{ConsumerGroup, Stream, StreamId, Trim};
+    use codecrafters_redis::resp::state::value::Value;
+
+    #[test]
+    fn test_round_trip() {
+        let entries = vec![
+            RdbEntry {
+                key: "foo".to_string(),
+                value: Value::String(b"bar".to_vec()),
+                expires_at: None,
+                field_expires: HashMap::new(),
+            },
+            RdbEntry {
+                key: "temp".to_string(),
+                value: Value::String(b"x".repeat(100)),
+                expires_at: Some(1_700_000_000_000),
+                field_expires: HashMap::new(),
+            },
+        ];
+
+        assert_eq!(decode(&encode(&entries)).unwrap(), entries);
+    }
+
+    #[test]
+    fn test_round_trip_collections() {
+        let entries = vec![
+            RdbEntry {
+                key: "list".to_string(),
+                value: Value::List(["a", "b", "a"].iter().map(|s| s.to_string()).collect()),
+                expires_at: None,
+                field_expires: HashMap::new(),
+            },
+            RdbEntry {
+                key: "set".to_string(),
+                value: Value::Set(["x", "y"].iter().map(|s| s.to_string()).collect()),
+                expires_at: Some(1_700_000_000_000),
+                field_expires: HashMap::new(),
+            },
+            RdbEntry {
+                key: "hash".to_string(),
+                value: Value::Hash([("f".to_string(), "v".to_string())].into_iter().collect()),
+                expires_at: None,
+                field_expires: HashMap::new(),
+            },
+            RdbEntry {
+                key: "zset".to_string(),
+                value: Value::SortedSet(
+                    [("m", 1.5), ("n", f64::NEG_INFINITY)].iter().map(|(m, s)| (m.to_string(), *s)).collect(),
+                ),
+                expires_at: None,
+                field_expires: HashMap::new(),
+            },
+        ];
+
+        assert_eq!(decode(&encode(&entries)).unwrap(), entries);
+        for entry in &entries {
+            let restored = restore_value(&dump_value(&entry.value, &entry.field_expires)).unwrap();
+            assert_eq!(restored, (entry.value.clone(), entry.field_expires.clone()));
+        }
+    }
+
+    #[test]
+    fn test_round_trip_stream() {
+        let mut stream = Stream::default();
+        for i in 0..250u64 {
+            let fields = if i % 7 == 0 {
+                vec![("other".to_string(), "x".repeat(i as usize * 20))]
+            } else {
+                let values = [i.to_string(), (-5000 - i as i64).to_string(), (i * 123_456_789_012).to_string()];
+                ["a", "b", "c"].iter().map(|f| f.to_string()).zip(values).collect()
+            };
+            stream.add(StreamId::new(1_700_000_000_000 + i / 3, i % 3), fields);
+        }
+        stream.remove(StreamId::new(1_700_000_000_010, 1));
+        stream.trim(Trim::MaxLen(200), None);
+        let mut group = ConsumerGroup::new(StreamId::new(1_700_000_000_060, 0), Some(180));
+        group.deliver(StreamId::new(1_700_000_000_030, 2), "alice", 1_700_000_100_000, 3);
+        group.deliver(StreamId::new(1_700_000_000_031, 0), "bob", 1_700_000_200_000, 1);
+        group.touch_consumer("idle", 1_700_000_300_000);
+        stream.groups.insert("workers".to_string(), group);
+        stream.groups.insert("new".to_string(), ConsumerGroup::new(StreamId::MIN, None));
+
+        let entries = vec![RdbEntry {
+            key: "stream".to_string(),
+            value: Value::Stream(stream),
+            expires_at: None,
+            field_expires: HashMap::new(),
+        }];
+        assert_eq!(decode(&encode(&entries)).unwrap(), entries);
+        assert_eq!(restore_value(&dump_value(&entries[0].value, &HashMap::new())).unwrap().0, entries[0].value);
+        assert_eq!(
+            restore_value(&dump_value(&Value::Stream(Stream::default()), &HashMap::new())).unwrap().0,
+            Value::Stream(Stream::default())
+        );
+    }
+
+    #[test]
+    fn test_round_trip_hash_field_expiration() {
+        let fields = [("a", "1"), ("b", "2"), ("c", "3")];
+        let entries = vec![RdbEntry {
+            key: "hash".to_string(),
+            value: Value::Hash(fields.iter().map(|(f, v)| (f.to_string(), v.to_string())).collect()),
+            expires_at: Some(1_800_000_000_000),
+            field_expires: [("a".to_string(), 1_700_000_000_000), ("c".to_string(), 1_700_000_005_000)]
+                .into_iter()
+                .collect(),
+        }];
+
+        assert_eq!(decode(&encode(&entries)).unwrap(), entries);
+        let restored = restore_value(&dump_value(&entries[0].value, &entries[0].field_expires)).unwrap();
+        assert_eq!(restored, (entries[0].value.clone(), entries[0].field_expires.clone()));
+    }
+
+    #[test]
+    fn test_decode_integer_encoded_strings() {
+        let mut rdb = b"REDIS0011".to_vec();
+        rdb.extend_from_slice(&[0xFA, 0x0A]);
+        rdb.extend_from_slice(b"redis-bits");
+        rdb.extend_from_slice(&[0xC0, 0x40]);
+        rdb.extend_from_slice(&[0xFE, 0x00, 0xFB, 0x01, 0x00, 0x00, 0x01]);
+        rdb.extend_from_slice(b"n");
+        rdb.extend_from_slice(&[0xC1, 0x39, 0x30]);
+        rdb.push(0xFF);
+        rdb.extend_from_slice(&[0; 8]);
+
+        let entries = decode(&rdb).unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].key, "n");
+        assert_eq!(entries[0].value, Value::String(b"12345".to_vec()));
+    }
+
+    #[test]
+    fn test_invalid_header() {
+        assert!(decode(b"NOTANRDB").is_err());
+    }
+}
+
+#[cfg(test)]
+mod test_config {
+    use codecrafters_redis::resp::config::ServerConfig;
+    use codecrafters_redis::resp::replication::MasterAddr;
+
+    fn args(s: &[&str]) -> Vec<String> {
+        s.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_defaults() {
+        let config = ServerConfig::from_args(Vec::new()).unwrap();
+
+        assert_eq!(config, ServerConfig::default());
+        assert_eq!(config.port, 6379);
+    }
+
+    #[test]
+    fn test_replica_options() {
+        let config = ServerConfig::from_args(args(&[
+            "--port",
+            "6380",
+            "--replicaof",
+            "localhost 6379",
+            "--repl-backlog-size",
+            "2mb",
+            "--replica-serve-stale-data",
+            "no",
+        ]))
+        .unwrap();
+
+        assert_eq!(config.port, 6380);
+        assert_eq!(
+            config.replicaof,
+            Some(MasterAddr {
+                host: "localhost".to_string(),
+                port: 6379
+            })
+        );
+        assert_eq!(config.repl_backlog_size, 2 * 1024 * 1024);
+        assert!(config.replica_read_only);
+        assert!(!config.replica_serve_stale_data);
+    }
+
+    #[test]
+    fn test_unknown_option() {
+        assert!(ServerConfig::from_args(args(&["--nope", "1"])).is_err());
+    }
+}
+
+#[cfg(test)]
+mod test_wait {
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    use codecrafters_redis::resp::client::{BlockedOn, ClientContext};
+    use codecrafters_redis::resp::commands::{Command, Wait};
+    use codecrafters_redis::resp::protocol::RespType;
+    use codecrafters_redis::resp::replication::master::wait_for_acks;
+    use codecrafters_redis::resp::replication::{MasterAddr, ReplicationState};
+    use codecrafters_redis::resp::state::default_server_state::DefaultServerState;
+    use codecrafters_redis::resp::state::server_state::ServerState;
+    use tokio::sync::Mutex;
+
+    fn bulk(s: &str) -> RespType {
+        RespType::BulkString(Some(s.to_string()))
+    }
+
+    #[test]
+    fn test_wait_without_replicas() {
+        let mut state = DefaultServerState::default();
+        let mut client = ClientContext::default();
+
+        let result = Wait
+            .execute_with_client(&[bulk("0"), bulk("100")], &mut state, &mut client)
+            .unwrap();
+
+        assert_eq!(result, RespType::Integer(0));
+        assert!(client.blocked.is_none());
+    }
+
+    #[test]
+    fn test_wait_already_acknowledged() {
+        let mut state = DefaultServerState::default();
+        state
+            .replication()
+            .register_replica("127.0.0.1".to_string(), 6380, 0, Vec::new());
+        let mut client = ClientContext::default();
+
+        let result = Wait
+            .execute_with_client(&[bulk("1"), bulk("100")], &mut state, &mut client)
+            .unwrap();
+
+        assert_eq!(result, RespType::Integer(1));
+        assert!(client.blocked.is_none());
+    }
+
+    #[test]
+    fn test_wait_blocks_and_requests_acks() {
+        let mut state = DefaultServerState::default();
+        let (_, mut stream) = state
+            .replication()
+            .register_replica("127.0.0.1".to_string(), 6380, 0, Vec::new());
+        state.replication().feed(b"*1\r\n$4\r\nPING\r\n");
+        stream.try_recv().unwrap();
+        let mut client = ClientContext::default();
+
+        Wait.execute_with_client(&[bulk("1"), bulk("0")], &mut state, &mut client)
+            .unwrap();
+
+        assert_eq!(
+            client.blocked,
+            Some(BlockedOn::Replicas {
+                numreplicas: 1,
+                offset: 14,
+                deadline: None
+            })
+        );
+        assert_eq!(
+            stream.try_recv().unwrap(),
+            b"*3\r\n$8\r\nREPLCONF\r\n$6\r\nGETACK\r\n$1\r\n*\r\n".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_wait_on_replica() {
+        let master = MasterAddr {
+            host: "127.0.0.1".to_string(),
+            port: 6379,
+        };
+        let mut state = DefaultServerState::with_replication(ReplicationState::new(1024, Some(master)));
+
+        let result = Wait.execute(&[bulk("1"), bulk("0")], &mut state);
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_acks_wakes_on_ack() {
+        let state = Arc::new(Mutex::new(DefaultServerState::default()));
+        let id = {
+            let mut guard = state.lock().await;
+            let (id, _) = guard
+                .replication()
+                .register_replica("127.0.0.1".to_string(), 6380, 0, Vec::new());
+            guard.replication().feed(b"data");
+            id
+        };
+
+        let acker = Arc::clone(&state);
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            acker.lock().await.replication().record_ack(id, 4);
+        });
+
+        let deadline = Some(Instant::now() + Duration::from_secs(5));
+        assert_eq!(wait_for_acks(&state, 1, 4, deadline).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_acks_times_out() {
+        let state = Mutex::new(DefaultServerState::default());
+        state
+            .lock()
+            .await
+            .replication()
+            .register_replica("127.0.0.1".to_string(), 6380, 0, Vec::new());
+        state.lock().await.replication().feed(b"data");
+
+        let started = Instant::now();
+        let deadline = Some(started + Duration::from_millis(100));
+
+        assert_eq!(wait_for_acks(&state, 1, 4, deadline).await, 0);
+        assert!(started.elapsed() >= Duration::from_millis(100));
+    }
+}
+
+#[cfg(test)]
+mod test_read_only_replica {
+    use codecrafters_redis::resp::client::ClientContext;
+    use codecrafters_redis::resp::command_dispatcher::CommandDispatcher;
+    use codecrafters_redis::resp::protocol::RespType;
+    use codecrafters_redis::resp::replication::{MasterAddr, ReplicationState};
+    use codecrafters_redis::resp::state::default_server_state::DefaultServerState;
+    use codecrafters_redis::resp::state::server_state::ServerState;
+
+    fn bulk(s: &str) -> RespType {
+        RespType::BulkString(Some(s.to_string()))
+    }
+
+    fn replica_state() -> DefaultServerState {
+        let master = MasterAddr {
+            host: "127.0.0.1".to_string(),
+            port: 6379,
+        };
+        DefaultServerState::with_replication(ReplicationState::new(1024, Some(master)))
+    }
+
+    #[test]
+    fn test_replica_rejects_client_writes() {
+        let mut state = replica_state();
+        let dispatcher = CommandDispatcher::new();
+
+        let result = dispatcher.dispatch(
+            "SET",
+            vec![bulk("key"), bulk("value")],
+            &mut state,
+            &mut ClientContext::default(),
+        );
+
+        assert_eq!(
+            result.unwrap_err(),
+            "READONLY You can't write against a read only replica.".to_string()
+        );
+    }
+
+    #[test]
+    fn test_replica_accepts_writes_from_primary() {
+        let mut state = replica_state();
+        let dispatcher = CommandDispatcher::new();
+
+        dispatcher
+            .dispatch(
+                "SET",
+                vec![bulk("key"), bulk("value")],
+                &mut state,
+                &mut ClientContext::master_link(),
+            )
+            .unwrap();
+
+        let result = dispatcher
+            .dispatch("GET", vec![bulk("key")], &mut state, &mut ClientContext::default())
+            .unwrap();
+        assert_eq!(result, bulk("value"));
+    }
+
+    #[test]
+    fn test_writable_replica() {
+        let mut state = replica_state();
+        state.replication().read_only = false;
+        let dispatcher = CommandDispatcher::new();
+
+        let result = dispatcher.dispatch(
+            "SET",
+            vec![bulk("key"), bulk("value")],
+            &mut state,
+            &mut ClientContext::default(),
+        );
+
+        assert!(result.is_ok());
+        // Local writes on a replica are never propagated.
+        assert_eq!(state.replication().offset(), 0);
+    }
+
+    #[test]
+    fn test_stale_reads_refused_when_disabled() {
+        let mut state = replica_state();
+        state.replication().serve_stale_data = false;
+        let dispatcher = CommandDispatcher::new();
+        let mut client = ClientContext::default();
+
+        let result = dispatcher.dispatch("GET", vec![bulk("key")], &mut state, &mut client);
+        assert!(result.unwrap_err().starts_with("MASTERDOWN"));
+
+        // Introspection keeps working while the link is down.
+        assert!(dispatcher.dispatch("PING", vec![], &mut state, &mut client).is_ok());
+        assert!(dispatcher.dispatch("INFO", vec![], &mut state, &mut client).is_ok());
+
+        state.replication().set_link_up(true);
+        let result = dispatcher.dispatch("GET", vec![bulk("key")], &mut state, &mut client);
+        assert_eq!(result.unwrap(), RespType::BulkString(None));
+    }
+
+    #[test]
+    fn test_stale_reads_served_by_default() {
+        let mut state = replica_state();
+        let dispatcher = CommandDispatcher::new();
+
+        let result = dispatcher.dispatch("GET", vec![bulk("key")], &mut state, &mut ClientContext::default());
+
+        assert_eq!(result.unwrap(), RespType::BulkString(None));
+    }
+}
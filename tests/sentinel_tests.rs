@@ -0,0 +1,211 @@
+/// Integration tests for REPLICAOF and sentinel mode
+#[cfg(test)]
+mod test_replicaof {
+    use codecrafters_redis::resp::commands::{Command, Replicaof};
+    use codecrafters_redis::resp::protocol::RespType;
+    use codecrafters_redis::resp::replication::{MasterAddr, ReplicationState};
+    use codecrafters_redis::resp::state::default_server_state::DefaultServerState;
+    use codecrafters_redis::resp::state::server_state::ServerState;
+
+    fn bulk(s: &str) -> RespType {
+        RespType::BulkString(Some(s.to_string()))
+    }
+
+    fn addr(port: u16) -> MasterAddr {
+        MasterAddr {
+            host: "127.0.0.1".to_string(),
+            port,
+        }
+    }
+
+    #[test]
+    fn test_promote_keeps_old_history() {
+        let mut state = DefaultServerState::with_replication(ReplicationState::new(1024, Some(addr(6379))));
+        state.replication().feed(b"0123456789");
+        let old_replid = state.replication().replid().to_string();
+
+        let result = Replicaof.execute(&[bulk("NO"), bulk("ONE")], &mut state).unwrap();
+
+        assert_eq!(result, RespType::SimpleString("OK".to_string()));
+        let replication = state.replication();
+        assert!(replication.is_master());
+        assert_ne!(replication.replid(), old_replid);
+        // Replicas of the old primary can continue with the previous id.
+        assert_eq!(replication.partial_resync(&old_replid, 11), Some(Vec::new()));
+        assert_eq!(replication.partial_resync(&old_replid, 6), Some(b"56789".to_vec()));
+        assert_eq!(replication.partial_resync(&old_replid, 12), None);
+    }
+
+    #[test]
+    fn test_follow_new_master() {
+        let mut state = DefaultServerState::default();
+
+        let result = Replicaof.execute(&[bulk("127.0.0.1"), bulk("6380")], &mut state).unwrap();
+        assert_eq!(result, RespType::SimpleString("OK".to_string()));
+        assert_eq!(state.replication().master().map(|link| &link.addr), Some(&addr(6380)));
+
+        let result = Replicaof.execute(&[bulk("127.0.0.1"), bulk("6380")], &mut state).unwrap();
+        assert_eq!(
+            result,
+            RespType::SimpleString("OK Already connected to specified master".to_string())
+        );
+    }
+
+    #[test]
+    fn test_invalid_port() {
+        let mut state = DefaultServerState::default();
+        assert!(Replicaof.execute(&[bulk("127.0.0.1"), bulk("nope")], &mut state).is_err());
+    }
+}
+
+#[cfg(test)]
+mod test_sentinel {
+    use std::time::Duration;
+
+    use codecrafters_redis::resp::command_dispatcher::CommandDispatcher;
+    use codecrafters_redis::resp::client::ClientContext;
+    use codecrafters_redis::resp::config::ServerConfig;
+    use codecrafters_redis::resp::protocol::RespType;
+    use codecrafters_redis::resp::replication::MasterAddr;
+    use codecrafters_redis::resp::sentinel::monitor::parse_info;
+    use codecrafters_redis::resp::sentinel::{MonitorConfig, SentinelConfig, SentinelState};
+    use codecrafters_redis::resp::state::default_server_state::DefaultServerState;
+    use codecrafters_redis::resp::state::server_state::ServerState;
+
+    fn bulk(s: &str) -> RespType {
+        RespType::BulkString(Some(s.to_string()))
+    }
+
+    fn addr(port: u16) -> MasterAddr {
+        MasterAddr {
+            host: "127.0.0.1".to_string(),
+            port,
+        }
+    }
+
+    fn sentinel_state() -> DefaultServerState {
+        let config = SentinelConfig {
+            monitors: vec![MonitorConfig {
+                name: "mymaster".to_string(),
+                addr: addr(6379),
+                quorum: 2,
+            }],
+            peers: vec![addr(26380)],
+            ..Default::default()
+        };
+        DefaultServerState::with_sentinel(SentinelState::new(&config, addr(26379)))
+    }
+
+    fn sentinel(state: &mut DefaultServerState, args: &[&str]) -> Result<RespType, String> {
+        CommandDispatcher::sentinel().dispatch(
+            "SENTINEL",
+            args.iter().map(|arg| bulk(arg)).collect(),
+            state,
+            &mut ClientContext::default(),
+        )
+    }
+
+    #[test]
+    fn test_get_master_addr_by_name() {
+        let mut state = sentinel_state();
+
+        assert_eq!(
+            sentinel(&mut state, &["get-master-addr-by-name", "mymaster"]).unwrap(),
+            RespType::Array(vec![bulk("127.0.0.1"), bulk("6379")])
+        );
+        assert_eq!(
+            sentinel(&mut state, &["get-master-addr-by-name", "other"]).unwrap(),
+            RespType::BulkString(None)
+        );
+        assert!(sentinel(&mut state, &["master", "other"]).is_err());
+    }
+
+    #[test]
+    fn test_vote_once_per_epoch() {
+        let mut state = sentinel_state();
+        let question = ["is-master-down-by-addr", "127.0.0.1", "6379", "1", "runid-a"];
+
+        // No vote is given while we think the primary is reachable.
+        assert_eq!(
+            sentinel(&mut state, &question).unwrap(),
+            RespType::Array(vec![RespType::Integer(0), bulk("*"), RespType::Integer(0)])
+        );
+
+        state.sentinel().unwrap().masters[0].subjectively_down = true;
+        assert_eq!(
+            sentinel(&mut state, &question).unwrap(),
+            RespType::Array(vec![RespType::Integer(1), bulk("runid-a"), RespType::Integer(1)])
+        );
+        // Another candidate in the same epoch gets our previous choice.
+        assert_eq!(
+            sentinel(&mut state, &["is-master-down-by-addr", "127.0.0.1", "6379", "1", "runid-b"]).unwrap(),
+            RespType::Array(vec![RespType::Integer(1), bulk("runid-a"), RespType::Integer(1)])
+        );
+        assert_eq!(
+            sentinel(&mut state, &["is-master-down-by-addr", "127.0.0.1", "6379", "2", "runid-b"]).unwrap(),
+            RespType::Array(vec![RespType::Integer(1), bulk("runid-b"), RespType::Integer(2)])
+        );
+        assert_eq!(state.sentinel().unwrap().current_epoch, 2);
+    }
+
+    #[test]
+    fn test_hello_applies_newer_config() {
+        let mut state = sentinel_state();
+        let hello = |port: &'static str, config_epoch: &'static str| {
+            ["hello", "mymaster", "127.0.0.1", port, config_epoch, "peer", "127.0.0.1", "26380", "3"]
+        };
+
+        sentinel(&mut state, &hello("6380", "1")).unwrap();
+        let sentinel_state = state.sentinel().unwrap();
+        assert_eq!(sentinel_state.masters[0].addr, addr(6380));
+        assert_eq!(sentinel_state.masters[0].replicas[0].addr, addr(6379));
+        assert_eq!(sentinel_state.current_epoch, 3);
+        assert_eq!(sentinel_state.peers[0].runid.as_deref(), Some("peer"));
+
+        // Older configurations are ignored.
+        sentinel(&mut state, &hello("6381", "1")).unwrap();
+        assert_eq!(state.sentinel().unwrap().masters[0].addr, addr(6380));
+    }
+
+    #[test]
+    fn test_parse_info() {
+        let info = parse_info(
+            "# Replication\r\nrole:master\r\nconnected_slaves:2\r\n\
+             slave0:ip=127.0.0.1,port=6380,state=online,offset=10,lag=0\r\n\
+             slave1:ip=127.0.0.1,port=6381,state=online,offset=8,lag=0\r\n\
+             master_repl_offset:10\r\n",
+        );
+
+        assert!(info.is_master);
+        assert_eq!(info.offset, 10);
+        assert_eq!(info.replicas, vec![addr(6380), addr(6381)]);
+
+        let info = parse_info("role:slave\r\nmaster_host:127.0.0.1\r\nmaster_port:6379\r\nslave_repl_offset:42\r\n");
+        assert!(!info.is_master);
+        assert_eq!(info.master, Some(addr(6379)));
+        assert_eq!(info.offset, 42);
+    }
+
+    #[test]
+    fn test_config() {
+        let args = [
+            "--sentinel",
+            "--sentinel-monitor",
+            "mymaster 127.0.0.1 6379 2",
+            "--sentinel-peer",
+            "127.0.0.1 26380",
+            "--sentinel-down-after-milliseconds",
+            "1000",
+        ];
+        let config = ServerConfig::from_args(args.iter().map(|arg| arg.to_string())).unwrap();
+
+        assert_eq!(config.port, 26379);
+        let sentinel = config.sentinel.unwrap();
+        assert_eq!(sentinel.monitors[0].quorum, 2);
+        assert_eq!(sentinel.peers, vec![addr(26380)]);
+        assert_eq!(sentinel.down_after, Duration::from_millis(1000));
+
+        let invalid = ["--sentinel", "--sentinel-monitor", "mymaster 127.0.0.1 6379"];
+        assert!(ServerConfig::from_args(invalid.iter().map(|arg| arg.to_string())).is_err());
+    }
+}
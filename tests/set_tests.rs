@@ -0,0 +1,208 @@
+/// Integration tests for set commands
+#[cfg(test)]
+mod test_set {
+    use codecrafters_redis::resp::client::ClientContext;
+    use codecrafters_redis::resp::command_dispatcher::CommandDispatcher;
+    use codecrafters_redis::resp::protocol::RespType;
+    use codecrafters_redis::resp::state::default_server_state::DefaultServerState;
+    use codecrafters_redis::resp::state::member_set::{MemberSet, MAX_INTSET_ENTRIES};
+    use codecrafters_redis::resp::state::server_state::ServerState;
+    use codecrafters_redis::resp::state::value::Value;
+
+    fn bulk(s: &str) -> RespType {
+        RespType::BulkString(Some(s.to_string()))
+    }
+
+    fn run(state: &mut DefaultServerState, args: &[&str]) -> Result<RespType, String> {
+        let mut client = ClientContext::default();
+        CommandDispatcher::new().dispatch(args[0], args[1..].iter().map(|arg| bulk(arg)).collect(), state, &mut client)
+    }
+
+    /// Bulk strings of an array reply, sorted since sets have no order.
+    fn sorted(reply: RespType) -> Vec<String> {
+        let RespType::Array(items) = reply else { panic!("expected an array, got {:?}", reply) };
+        let mut items = items
+            .into_iter()
+            .map(|item| match item {
+                RespType::BulkString(Some(s)) => s,
+                other => panic!("expected a bulk string, got {:?}", other),
+            })
+            .collect::<Vec<_>>();
+        items.sort();
+        items
+    }
+
+    fn is_intset(state: &mut DefaultServerState, key: &str) -> bool {
+        match state.get(key) {
+            Some(Value::Set(set)) => set.is_intset(),
+            other => panic!("expected a set, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_add_remove_and_members() {
+        let mut state = DefaultServerState::default();
+
+        assert_eq!(run(&mut state, &["SADD", "s", "a", "b", "a"]).unwrap(), RespType::Integer(2));
+        assert_eq!(run(&mut state, &["SADD", "s", "b", "c"]).unwrap(), RespType::Integer(1));
+        assert_eq!(sorted(run(&mut state, &["SMEMBERS", "s"]).unwrap()), ["a", "b", "c"]);
+        assert_eq!(run(&mut state, &["SCARD", "s"]).unwrap(), RespType::Integer(3));
+        assert_eq!(run(&mut state, &["SISMEMBER", "s", "a"]).unwrap(), RespType::Integer(1));
+        assert_eq!(
+            run(&mut state, &["SMISMEMBER", "s", "a", "z"]).unwrap(),
+            RespType::Array(vec![RespType::Integer(1), RespType::Integer(0)])
+        );
+        assert_eq!(run(&mut state, &["TYPE", "s"]).unwrap(), RespType::SimpleString("set".to_string()));
+
+        assert_eq!(run(&mut state, &["SREM", "s", "a", "z"]).unwrap(), RespType::Integer(1));
+        assert_eq!(run(&mut state, &["SREM", "s", "b", "c"]).unwrap(), RespType::Integer(2));
+        assert!(!state.exists("s"));
+
+        run(&mut state, &["SET", "str", "v"]).unwrap();
+        assert!(run(&mut state, &["SADD", "str", "a"]).unwrap_err().starts_with("WRONGTYPE"));
+        assert!(run(&mut state, &["SUNION", "nope", "str"]).unwrap_err().starts_with("WRONGTYPE"));
+    }
+
+    #[test]
+    fn test_intset_encoding() {
+        let mut state = DefaultServerState::default();
+
+        run(&mut state, &["SADD", "s", "3", "-1", "2"]).unwrap();
+        assert!(is_intset(&mut state, "s"));
+        assert_eq!(run(&mut state, &["SISMEMBER", "s", "-1"]).unwrap(), RespType::Integer(1));
+        // "02" is not the way 2 is printed, so it is a different member.
+        assert_eq!(run(&mut state, &["SISMEMBER", "s", "02"]).unwrap(), RespType::Integer(0));
+        assert_eq!(run(&mut state, &["SADD", "s", "02"]).unwrap(), RespType::Integer(1));
+        assert!(!is_intset(&mut state, "s"));
+        assert_eq!(sorted(run(&mut state, &["SMEMBERS", "s"]).unwrap()), ["-1", "02", "2", "3"]);
+
+        let mut set = (0..MAX_INTSET_ENTRIES).map(|i| i.to_string()).collect::<MemberSet>();
+        assert!(set.is_intset());
+        assert!(set.insert(MAX_INTSET_ENTRIES.to_string()));
+        assert!(!set.is_intset());
+        assert_eq!(set.len(), MAX_INTSET_ENTRIES + 1);
+        assert_eq!(set, (0..=MAX_INTSET_ENTRIES).map(|i| i.to_string()).collect::<MemberSet>());
+    }
+
+    #[test]
+    fn test_pop_and_random_members() {
+        let mut state = DefaultServerState::default();
+        run(&mut state, &["SADD", "s", "a", "b", "c"]).unwrap();
+
+        assert_eq!(sorted(run(&mut state, &["SRANDMEMBER", "s", "10"]).unwrap()), ["a", "b", "c"]);
+        assert_eq!(sorted(run(&mut state, &["SRANDMEMBER", "s", "-5"]).unwrap()).len(), 5);
+        assert_eq!(
+            run(&mut state, &["SRANDMEMBER", "s", &i64::MIN.to_string()]).unwrap_err(),
+            "value is out of range"
+        );
+        assert_eq!(run(&mut state, &["SCARD", "s"]).unwrap(), RespType::Integer(3));
+
+        let RespType::BulkString(Some(popped)) = run(&mut state, &["SPOP", "s"]).unwrap() else {
+            panic!("expected a member");
+        };
+        assert_eq!(run(&mut state, &["SISMEMBER", "s", &popped]).unwrap(), RespType::Integer(0));
+        assert_eq!(sorted(run(&mut state, &["SPOP", "s", "5"]).unwrap()).len(), 2);
+        assert!(!state.exists("s"));
+        assert_eq!(run(&mut state, &["SPOP", "s"]).unwrap(), RespType::BulkString(None));
+        assert_eq!(run(&mut state, &["SPOP", "s", "2"]).unwrap(), RespType::Array(vec![]));
+        assert_eq!(run(&mut state, &["SRANDMEMBER", "s"]).unwrap(), RespType::BulkString(None));
+    }
+
+    #[test]
+    fn test_move() {
+        let mut state = DefaultServerState::default();
+        run(&mut state, &["SADD", "src", "a", "b"]).unwrap();
+
+        assert_eq!(run(&mut state, &["SMOVE", "src", "dst", "a"]).unwrap(), RespType::Integer(1));
+        assert_eq!(run(&mut state, &["SMOVE", "src", "dst", "z"]).unwrap(), RespType::Integer(0));
+        assert_eq!(sorted(run(&mut state, &["SMEMBERS", "dst"]).unwrap()), ["a"]);
+
+        run(&mut state, &["SET", "str", "v"]).unwrap();
+        assert!(run(&mut state, &["SMOVE", "src", "str", "b"]).unwrap_err().starts_with("WRONGTYPE"));
+        assert_eq!(run(&mut state, &["SCARD", "src"]).unwrap(), RespType::Integer(1));
+
+        assert_eq!(run(&mut state, &["SMOVE", "src", "dst", "b"]).unwrap(), RespType::Integer(1));
+        assert!(!state.exists("src"));
+    }
+
+    #[test]
+    fn test_algebra() {
+        let mut state = DefaultServerState::default();
+        run(&mut state, &["SADD", "a", "1", "2", "3", "x"]).unwrap();
+        run(&mut state, &["SADD", "b", "2", "3", "4"]).unwrap();
+        run(&mut state, &["SADD", "c", "3", "x"]).unwrap();
+
+        assert_eq!(sorted(run(&mut state, &["SINTER", "a", "b"]).unwrap()), ["2", "3"]);
+        assert_eq!(sorted(run(&mut state, &["SINTER", "a", "b", "c"]).unwrap()), ["3"]);
+        assert_eq!(sorted(run(&mut state, &["SINTER", "a", "nope"]).unwrap()), Vec::<String>::new());
+        assert_eq!(sorted(run(&mut state, &["SUNION", "b", "c", "nope"]).unwrap()), ["2", "3", "4", "x"]);
+        assert_eq!(sorted(run(&mut state, &["SDIFF", "a", "b", "nope"]).unwrap()), ["1", "x"]);
+        assert_eq!(sorted(run(&mut state, &["SDIFF", "nope", "a"]).unwrap()), Vec::<String>::new());
+
+        assert_eq!(run(&mut state, &["SINTERCARD", "2", "a", "b"]).unwrap(), RespType::Integer(2));
+        assert_eq!(run(&mut state, &["SINTERCARD", "2", "a", "b", "LIMIT", "1"]).unwrap(), RespType::Integer(1));
+        assert_eq!(
+            run(&mut state, &["SINTERCARD", "3", "a", "b"]).unwrap_err(),
+            "Number of keys can't be greater than number of args"
+        );
+        assert_eq!(
+            run(&mut state, &["SINTERCARD", "2", "a", "b", "LIMIT", "-1"]).unwrap_err(),
+            "LIMIT can't be negative"
+        );
+
+        assert_eq!(run(&mut state, &["SUNIONSTORE", "dst", "a", "b"]).unwrap(), RespType::Integer(5));
+        assert_eq!(sorted(run(&mut state, &["SMEMBERS", "dst"]).unwrap()), ["1", "2", "3", "4", "x"]);
+        assert_eq!(run(&mut state, &["SINTERSTORE", "dst", "a", "b"]).unwrap(), RespType::Integer(2));
+        assert!(is_intset(&mut state, "dst"));
+        assert_eq!(run(&mut state, &["SDIFFSTORE", "dst", "c", "a"]).unwrap(), RespType::Integer(0));
+        assert!(!state.exists("dst"));
+
+        // The destination may be one of the sources and is replaced whatever its type.
+        run(&mut state, &["SET", "str", "v"]).unwrap();
+        assert_eq!(run(&mut state, &["SINTERSTORE", "str", "a", "c"]).unwrap(), RespType::Integer(2));
+        assert_eq!(run(&mut state, &["SDIFFSTORE", "a", "a", "c"]).unwrap(), RespType::Integer(2));
+        assert_eq!(sorted(run(&mut state, &["SMEMBERS", "a"]).unwrap()), ["1", "2"]);
+    }
+
+    #[test]
+    fn test_scan() {
+        let mut state = DefaultServerState::default();
+        for i in 0..30 {
+            run(&mut state, &["SADD", "ints", &i.to_string()]).unwrap();
+            run(&mut state, &["SADD", "strs", &format!("m{}", i)]).unwrap();
+        }
+
+        for key in ["ints", "strs"] {
+            let mut cursor = "0".to_string();
+            let mut members = Vec::new();
+            loop {
+                let reply = run(&mut state, &["SSCAN", key, &cursor, "COUNT", "4"]).unwrap();
+                let RespType::Array(mut parts) = reply else { panic!("expected an array") };
+                members.extend(sorted(parts.pop().unwrap()));
+                let Some(RespType::BulkString(Some(next))) = parts.pop() else { panic!("expected a cursor") };
+                cursor = next;
+                if cursor == "0" {
+                    break;
+                }
+            }
+            members.sort();
+            assert_eq!(members, sorted(run(&mut state, &["SMEMBERS", key]).unwrap()));
+        }
+
+        let reply = run(&mut state, &["SSCAN", "ints", "0", "MATCH", "2?", "COUNT", "100"]).unwrap();
+        let RespType::Array(mut parts) = reply else { panic!("expected an array") };
+        assert_eq!(sorted(parts.pop().unwrap()).len(), 10);
+        assert_eq!(run(&mut state, &["SSCAN", "ints", "0", "NOVALUES"]).unwrap_err(), "syntax error");
+    }
+
+    #[test]
+    fn test_binary_members_are_rejected() {
+        let mut state = DefaultServerState::default();
+        let args = vec![bulk("set"), RespType::BulkBytes(vec![0xff])];
+        assert_eq!(
+            CommandDispatcher::new().dispatch("SADD", args, &mut state, &mut ClientContext::default()).unwrap_err(),
+            "SADD arguments must be strings"
+        );
+        assert!(state.keys().is_empty());
+    }
+}
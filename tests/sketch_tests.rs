@@ -0,0 +1,195 @@
+mod common;
+
+/// Integration tests for Count-Min Sketch and Top-K commands
+#[cfg(test)]
+mod test_sketches {
+    use codecrafters_redis::resp::protocol::RespType;
+    use codecrafters_redis::resp::state::default_server_state::DefaultServerState;
+
+    use crate::common::{dump_and_restore, ints, ok, run, strings};
+
+    #[test]
+    fn test_cms_count_and_query() {
+        let mut state = DefaultServerState::default();
+        assert_eq!(run(&mut state, &["CMS.INITBYDIM", "views", "2000", "5"]).unwrap(), ok());
+        assert_eq!(run(&mut state, &["TYPE", "views"]).unwrap(), RespType::SimpleString("CMSk-TYPE".to_string()));
+
+        assert_eq!(run(&mut state, &["CMS.INCRBY", "views", "home", "3", "about", "1"]).unwrap(), ints(&[3, 1]));
+        assert_eq!(run(&mut state, &["CMS.INCRBY", "views", "home", "2", "home", "1"]).unwrap(), ints(&[5, 6]));
+        assert_eq!(run(&mut state, &["CMS.QUERY", "views", "home", "about", "blog"]).unwrap(), ints(&[6, 1, 0]));
+
+        // A counter that would overflow fails the whole command.
+        assert_eq!(
+            run(&mut state, &["CMS.INCRBY", "views", "about", "1", "home", "4294967290"]).unwrap_err(),
+            "CMS: INCRBY overflow"
+        );
+        assert_eq!(run(&mut state, &["CMS.QUERY", "views", "about"]).unwrap(), ints(&[1]));
+
+        // Counts only overestimate, however crowded the sketch.
+        run(&mut state, &["CMS.INITBYPROB", "crowded", "0.1", "0.01"]).unwrap();
+        for index in 0..200 {
+            run(&mut state, &["CMS.INCRBY", "crowded", &format!("item:{}", index), "2"]).unwrap();
+        }
+        let counts = strings(run(&mut state, &["CMS.QUERY", "crowded", "item:0", "item:199"]).unwrap());
+        assert!(counts.iter().all(|count| count.parse::<i64>().unwrap() >= 2));
+    }
+
+    #[test]
+    fn test_cms_merge() {
+        let mut state = DefaultServerState::default();
+        for key in ["a", "b", "total"] {
+            run(&mut state, &["CMS.INITBYDIM", key, "1000", "4"]).unwrap();
+        }
+        run(&mut state, &["CMS.INCRBY", "a", "x", "5", "y", "1"]).unwrap();
+        run(&mut state, &["CMS.INCRBY", "b", "x", "2"]).unwrap();
+
+        assert_eq!(run(&mut state, &["CMS.MERGE", "total", "2", "a", "b"]).unwrap(), ok());
+        assert_eq!(run(&mut state, &["CMS.QUERY", "total", "x", "y"]).unwrap(), ints(&[7, 1]));
+        assert_eq!(run(&mut state, &["CMS.MERGE", "total", "2", "a", "b", "WEIGHTS", "2", "-1"]).unwrap(), ok());
+        assert_eq!(run(&mut state, &["CMS.QUERY", "total", "x", "y"]).unwrap(), ints(&[8, 2]));
+        // The destination may be one of the sources.
+        assert_eq!(run(&mut state, &["CMS.MERGE", "a", "2", "a", "a"]).unwrap(), ok());
+        assert_eq!(run(&mut state, &["CMS.QUERY", "a", "x"]).unwrap(), ints(&[10]));
+
+        assert_eq!(
+            run(&mut state, &["CMS.MERGE", "total", "2", "a", "b", "WEIGHTS", "-2", "1"]).unwrap_err(),
+            "CMS: MERGE overflow"
+        );
+        assert_eq!(
+            run(&mut state, &["CMS.MERGE", "total", "2", "a", "b", "WEIGHTS", "1"]).unwrap_err(),
+            "CMS: wrong number of keys/weights"
+        );
+        assert_eq!(run(&mut state, &["CMS.MERGE", "total", "3", "a", "b"]).unwrap_err(), "CMS: invalid numkeys");
+        assert_eq!(run(&mut state, &["CMS.MERGE", "total", "1", "missing"]).unwrap_err(), "CMS: key does not exist");
+        assert_eq!(run(&mut state, &["CMS.MERGE", "missing", "1", "a"]).unwrap_err(), "CMS: key does not exist");
+        run(&mut state, &["CMS.INITBYDIM", "narrow", "10", "4"]).unwrap();
+        assert_eq!(
+            run(&mut state, &["CMS.MERGE", "total", "2", "a", "narrow"]).unwrap_err(),
+            "CMS: width/depth is not equal"
+        );
+    }
+
+    #[test]
+    fn test_cms_errors() {
+        let mut state = DefaultServerState::default();
+
+        assert_eq!(run(&mut state, &["CMS.INITBYDIM", "cms", "0", "5"]).unwrap_err(), "CMS: invalid width");
+        assert_eq!(run(&mut state, &["CMS.INITBYDIM", "cms", "10", "x"]).unwrap_err(), "CMS: invalid depth");
+        assert_eq!(
+            run(&mut state, &["CMS.INITBYPROB", "cms", "1", "0.01"]).unwrap_err(),
+            "CMS: invalid overestimation value"
+        );
+        assert_eq!(run(&mut state, &["CMS.INITBYPROB", "cms", "0.1", "0"]).unwrap_err(), "CMS: invalid prob value");
+        assert_eq!(
+            run(&mut state, &["CMS.INITBYDIM", "cms", "1000000000", "1000"]).unwrap_err(),
+            "CMS: Insufficient memory to create sketch"
+        );
+        assert_eq!(run(&mut state, &["CMS.QUERY", "cms", "a"]).unwrap_err(), "CMS: key does not exist");
+        assert_eq!(run(&mut state, &["CMS.INCRBY", "cms", "a", "1"]).unwrap_err(), "CMS: key does not exist");
+
+        run(&mut state, &["CMS.INITBYDIM", "cms", "10", "2"]).unwrap();
+        assert_eq!(run(&mut state, &["CMS.INITBYDIM", "cms", "10", "2"]).unwrap_err(), "CMS: key already exists");
+        assert_eq!(run(&mut state, &["CMS.INCRBY", "cms", "a", "-1"]).unwrap_err(), "CMS: Cannot parse number");
+        assert_eq!(
+            run(&mut state, &["CMS.INCRBY", "cms", "a"]).unwrap_err(),
+            "wrong number of arguments for 'cms.incrby' command"
+        );
+        run(&mut state, &["SET", "text", "a"]).unwrap();
+        assert!(run(&mut state, &["CMS.QUERY", "text", "a"]).unwrap_err().starts_with("WRONGTYPE"));
+    }
+
+    #[test]
+    fn test_topk_tracks_heavy_hitters() {
+        let mut state = DefaultServerState::default();
+        assert_eq!(run(&mut state, &["TOPK.RESERVE", "trending", "3", "50", "5", "0.9"]).unwrap(), ok());
+        assert_eq!(run(&mut state, &["TYPE", "trending"]).unwrap(), RespType::SimpleString("TopK-TYPE".to_string()));
+
+        assert_eq!(
+            strings(run(&mut state, &["TOPK.ADD", "trending", "a", "b", "c"]).unwrap()),
+            vec!["(nil)", "(nil)", "(nil)"]
+        );
+        assert_eq!(
+            strings(run(&mut state, &["TOPK.INCRBY", "trending", "a", "10", "b", "5", "c", "3"]).unwrap()),
+            vec!["(nil)", "(nil)", "(nil)"]
+        );
+        // A fourth item only enters the list by beating the least frequent one, which it pushes out.
+        assert_eq!(strings(run(&mut state, &["TOPK.INCRBY", "trending", "d", "2"]).unwrap()), vec!["(nil)"]);
+        assert_eq!(strings(run(&mut state, &["TOPK.INCRBY", "trending", "d", "5"]).unwrap()), vec!["c"]);
+
+        assert_eq!(
+            strings(run(&mut state, &["TOPK.LIST", "trending", "WITHCOUNT"]).unwrap()),
+            vec!["a", "11", "d", "7", "b", "6"]
+        );
+        assert_eq!(strings(run(&mut state, &["TOPK.LIST", "trending"]).unwrap()), vec!["a", "d", "b"]);
+        assert_eq!(run(&mut state, &["TOPK.QUERY", "trending", "a", "c", "z"]).unwrap(), ints(&[1, 0, 0]));
+    }
+
+    #[test]
+    fn test_topk_with_default_dimensions() {
+        let mut state = DefaultServerState::default();
+        run(&mut state, &["TOPK.RESERVE", "top", "2"]).unwrap();
+
+        // Heavy hitters keep their place among many rare items sharing their buckets.
+        for round in 0..50 {
+            run(&mut state, &["TOPK.ADD", "top", "heavy", "heavy", "medium", &format!("rare:{}", round)]).unwrap();
+        }
+        assert_eq!(strings(run(&mut state, &["TOPK.LIST", "top"]).unwrap()), vec!["heavy", "medium"]);
+
+        dump_and_restore(&mut state, "top", "copy");
+        assert_eq!(
+            run(&mut state, &["TOPK.LIST", "copy", "WITHCOUNT"]).unwrap(),
+            run(&mut state, &["TOPK.LIST", "top", "WITHCOUNT"]).unwrap()
+        );
+        // The restored sketch decays buckets the same way as the original.
+        for args in [["TOPK.ADD", "top", "rare:x"], ["TOPK.ADD", "copy", "rare:x"]] {
+            run(&mut state, &args).unwrap();
+        }
+        assert_eq!(
+            run(&mut state, &["TOPK.LIST", "copy", "WITHCOUNT"]).unwrap(),
+            run(&mut state, &["TOPK.LIST", "top", "WITHCOUNT"]).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_topk_errors() {
+        let mut state = DefaultServerState::default();
+
+        assert_eq!(run(&mut state, &["TOPK.RESERVE", "top", "0"]).unwrap_err(), "TopK: invalid k");
+        assert_eq!(run(&mut state, &["TOPK.RESERVE", "top", "3", "0", "5", "0.9"]).unwrap_err(), "TopK: invalid width");
+        assert_eq!(
+            run(&mut state, &["TOPK.RESERVE", "top", "3", "8", "-1", "0.9"]).unwrap_err(),
+            "TopK: invalid depth"
+        );
+        assert_eq!(
+            run(&mut state, &["TOPK.RESERVE", "top", "3", "8", "5", "1.5"]).unwrap_err(),
+            "TopK: invalid decay value. must be '<= 1' & '> 0'"
+        );
+        assert_eq!(
+            run(&mut state, &["TOPK.RESERVE", "top", "3", "8"]).unwrap_err(),
+            "wrong number of arguments for 'topk.reserve' command"
+        );
+        assert_eq!(run(&mut state, &["TOPK.ADD", "top", "a"]).unwrap_err(), "TopK: key does not exist");
+        assert_eq!(run(&mut state, &["TOPK.LIST", "top"]).unwrap_err(), "TopK: key does not exist");
+
+        run(&mut state, &["TOPK.RESERVE", "top", "3"]).unwrap();
+        assert_eq!(run(&mut state, &["TOPK.RESERVE", "top", "3"]).unwrap_err(), "TopK: key already exists");
+        assert_eq!(
+            run(&mut state, &["TOPK.INCRBY", "top", "a", "100001"]).unwrap_err(),
+            "TopK: increment must be an integer greater or equal to 1 and less than or equal to 100,000"
+        );
+        assert_eq!(run(&mut state, &["TOPK.LIST", "top", "COUNTS"]).unwrap_err(), "syntax error");
+        run(&mut state, &["CMS.INITBYDIM", "cms", "10", "2"]).unwrap();
+        assert!(run(&mut state, &["TOPK.ADD", "cms", "a"]).unwrap_err().starts_with("WRONGTYPE"));
+    }
+
+    #[test]
+    fn test_cms_survives_dump() {
+        let mut state = DefaultServerState::default();
+        run(&mut state, &["CMS.INITBYDIM", "cms", "100", "3"]).unwrap();
+        run(&mut state, &["CMS.INCRBY", "cms", "a", "7", "b", "300"]).unwrap();
+
+        dump_and_restore(&mut state, "cms", "copy");
+        assert_eq!(run(&mut state, &["CMS.QUERY", "copy", "a", "b"]).unwrap(), ints(&[7, 300]));
+        assert_eq!(run(&mut state, &["CMS.MERGE", "cms", "1", "copy"]).unwrap(), ok());
+    }
+}
@@ -0,0 +1,341 @@
+/// Integration tests for sorted set commands
+#[cfg(test)]
+mod test_sorted_set {
+    use codecrafters_redis::resp::blocking::{serve, KeyOperation, KeyWait};
+    use codecrafters_redis::resp::client::{BlockedOn, ClientContext};
+    use codecrafters_redis::resp::command_dispatcher::CommandDispatcher;
+    use codecrafters_redis::resp::protocol::RespType;
+    use codecrafters_redis::resp::random::random_below;
+    use codecrafters_redis::resp::state::default_server_state::DefaultServerState;
+    use codecrafters_redis::resp::state::server_state::ServerState;
+    use codecrafters_redis::resp::state::sorted_set::SortedSet;
+
+    fn bulk(s: &str) -> RespType {
+        RespType::BulkString(Some(s.to_string()))
+    }
+
+    fn run(state: &mut DefaultServerState, args: &[&str]) -> Result<RespType, String> {
+        let mut client = ClientContext::default();
+        CommandDispatcher::new().dispatch(args[0], args[1..].iter().map(|arg| bulk(arg)).collect(), state, &mut client)
+    }
+
+    fn strings(reply: RespType) -> Vec<String> {
+        let RespType::Array(items) = reply else { panic!("expected an array, got {:?}", reply) };
+        items
+            .into_iter()
+            .map(|item| match item {
+                RespType::BulkString(Some(s)) => s,
+                other => panic!("expected a bulk string, got {:?}", other),
+            })
+            .collect()
+    }
+
+    fn setup(state: &mut DefaultServerState) {
+        run(state, &["ZADD", "z", "1", "a", "2", "b", "2", "c", "3", "d", "5", "e"]).unwrap();
+    }
+
+    #[test]
+    fn test_add_options() {
+        let mut state = DefaultServerState::default();
+
+        assert_eq!(run(&mut state, &["ZADD", "z", "1", "a", "2", "b"]).unwrap(), RespType::Integer(2));
+        assert_eq!(run(&mut state, &["ZADD", "z", "CH", "5", "a", "2", "b", "3", "c"]).unwrap(), RespType::Integer(2));
+        assert_eq!(run(&mut state, &["ZADD", "z", "NX", "9", "a", "4", "d"]).unwrap(), RespType::Integer(1));
+        assert_eq!(run(&mut state, &["ZADD", "z", "XX", "CH", "9", "a", "4", "x"]).unwrap(), RespType::Integer(1));
+        assert_eq!(run(&mut state, &["ZSCORE", "z", "a"]).unwrap(), bulk("9"));
+        assert_eq!(run(&mut state, &["ZSCORE", "z", "x"]).unwrap(), RespType::BulkString(None));
+
+        assert_eq!(run(&mut state, &["ZADD", "z", "GT", "CH", "1", "a", "10", "b"]).unwrap(), RespType::Integer(1));
+        assert_eq!(run(&mut state, &["ZADD", "z", "LT", "CH", "1", "a", "20", "b"]).unwrap(), RespType::Integer(1));
+        assert_eq!(
+            run(&mut state, &["ZMSCORE", "z", "a", "b", "x"]).unwrap(),
+            RespType::Array(vec![bulk("1"), bulk("10"), RespType::BulkString(None)])
+        );
+
+        assert_eq!(run(&mut state, &["ZADD", "z", "INCR", "2.5", "a"]).unwrap(), bulk("3.5"));
+        assert_eq!(run(&mut state, &["ZADD", "z", "NX", "INCR", "1", "a"]).unwrap(), RespType::BulkString(None));
+        assert_eq!(run(&mut state, &["ZINCRBY", "z", "-0.5", "a"]).unwrap(), bulk("3"));
+        assert_eq!(run(&mut state, &["ZINCRBY", "z", "+inf", "n"]).unwrap(), bulk("inf"));
+        assert_eq!(
+            run(&mut state, &["ZINCRBY", "z", "-inf", "n"]).unwrap_err(),
+            "resulting score is not a number (NaN)"
+        );
+        assert_eq!(run(&mut state, &["ZADD", "nope", "XX", "1", "a"]).unwrap(), RespType::Integer(0));
+        assert!(!state.exists("nope"));
+
+        assert_eq!(
+            run(&mut state, &["ZADD", "z", "NX", "XX", "1", "a"]).unwrap_err(),
+            "XX and NX options at the same time are not compatible"
+        );
+        assert_eq!(
+            run(&mut state, &["ZADD", "z", "NX", "GT", "1", "a"]).unwrap_err(),
+            "GT, LT, and/or NX options at the same time are not compatible"
+        );
+        assert_eq!(
+            run(&mut state, &["ZADD", "z", "INCR", "1", "a", "2", "b"]).unwrap_err(),
+            "INCR option supports a single increment-element pair"
+        );
+        assert_eq!(run(&mut state, &["ZADD", "z", "nan", "a"]).unwrap_err(), "value is not a valid float");
+        assert_eq!(run(&mut state, &["ZADD", "z", "1", "a", "2"]).unwrap_err(), "syntax error");
+
+        run(&mut state, &["SET", "str", "v"]).unwrap();
+        assert!(run(&mut state, &["ZADD", "str", "1", "a"]).unwrap_err().starts_with("WRONGTYPE"));
+        assert_eq!(run(&mut state, &["TYPE", "z"]).unwrap(), RespType::SimpleString("zset".to_string()));
+    }
+
+    #[test]
+    fn test_score_formatting() {
+        let mut state = DefaultServerState::default();
+        for (score, expected) in [
+            ("1e20", "1e+20"),
+            ("0.0000001", "9.9999999999999995e-08"),
+            ("0.1", "0.10000000000000001"),
+            ("0.0001", "0.0001"),
+            ("12345678901234567", "12345678901234568"),
+            ("100000000000000000", "1e+17"),
+            ("-2.5", "-2.5"),
+            ("-inf", "-inf"),
+        ] {
+            run(&mut state, &["ZADD", "z", score, "m"]).unwrap();
+            assert_eq!(run(&mut state, &["ZSCORE", "z", "m"]).unwrap(), bulk(expected), "{}", score);
+        }
+        assert_eq!(
+            run(&mut state, &["ZRANGE", "z", "0", "-1", "WITHSCORES"]).unwrap(),
+            RespType::Array(vec![bulk("m"), bulk("-inf")])
+        );
+    }
+
+    #[test]
+    fn test_rank_count_and_remove() {
+        let mut state = DefaultServerState::default();
+        setup(&mut state);
+
+        assert_eq!(run(&mut state, &["ZCARD", "z"]).unwrap(), RespType::Integer(5));
+        assert_eq!(run(&mut state, &["ZRANK", "z", "c"]).unwrap(), RespType::Integer(2));
+        assert_eq!(run(&mut state, &["ZREVRANK", "z", "c"]).unwrap(), RespType::Integer(2));
+        assert_eq!(run(&mut state, &["ZREVRANK", "z", "e"]).unwrap(), RespType::Integer(0));
+        assert_eq!(
+            run(&mut state, &["ZRANK", "z", "d", "WITHSCORE"]).unwrap(),
+            RespType::Array(vec![RespType::Integer(3), bulk("3")])
+        );
+        assert_eq!(run(&mut state, &["ZRANK", "z", "x"]).unwrap(), RespType::BulkString(None));
+
+        assert_eq!(run(&mut state, &["ZCOUNT", "z", "2", "3"]).unwrap(), RespType::Integer(3));
+        assert_eq!(run(&mut state, &["ZCOUNT", "z", "(2", "+inf"]).unwrap(), RespType::Integer(2));
+        assert_eq!(run(&mut state, &["ZCOUNT", "z", "4", "1"]).unwrap(), RespType::Integer(0));
+        assert_eq!(run(&mut state, &["ZCOUNT", "z", "x", "1"]).unwrap_err(), "min or max is not a float");
+
+        assert_eq!(run(&mut state, &["ZREM", "z", "a", "x"]).unwrap(), RespType::Integer(1));
+        assert_eq!(run(&mut state, &["ZREMRANGEBYSCORE", "z", "(2", "3"]).unwrap(), RespType::Integer(1));
+        assert_eq!(run(&mut state, &["ZREMRANGEBYRANK", "z", "-1", "-1"]).unwrap(), RespType::Integer(1));
+        assert_eq!(strings(run(&mut state, &["ZRANGE", "z", "0", "-1"]).unwrap()), ["b", "c"]);
+        assert_eq!(run(&mut state, &["ZREMRANGEBYLEX", "z", "-", "+"]).unwrap(), RespType::Integer(2));
+        assert!(!state.exists("z"));
+    }
+
+    #[test]
+    fn test_range() {
+        let mut state = DefaultServerState::default();
+        setup(&mut state);
+
+        assert_eq!(strings(run(&mut state, &["ZRANGE", "z", "1", "2"]).unwrap()), ["b", "c"]);
+        assert_eq!(strings(run(&mut state, &["ZRANGE", "z", "-2", "100"]).unwrap()), ["d", "e"]);
+        assert_eq!(strings(run(&mut state, &["ZRANGE", "z", "0", "1", "REV"]).unwrap()), ["e", "d"]);
+        assert_eq!(strings(run(&mut state, &["ZRANGE", "z", "3", "1"]).unwrap()), Vec::<String>::new());
+        assert_eq!(
+            strings(run(&mut state, &["ZRANGE", "z", "0", "1", "WITHSCORES"]).unwrap()),
+            ["a", "1", "b", "2"]
+        );
+
+        assert_eq!(strings(run(&mut state, &["ZRANGE", "z", "(1", "3", "BYSCORE"]).unwrap()), ["b", "c", "d"]);
+        assert_eq!(
+            strings(run(&mut state, &["ZRANGE", "z", "+inf", "2", "BYSCORE", "REV", "LIMIT", "1", "2"]).unwrap()),
+            ["d", "c"]
+        );
+        assert_eq!(
+            strings(run(&mut state, &["ZRANGE", "z", "-inf", "+inf", "BYSCORE", "LIMIT", "3", "-1"]).unwrap()),
+            ["d", "e"]
+        );
+
+        run(&mut state, &["ZADD", "lex", "0", "a", "0", "b", "0", "c", "0", "d"]).unwrap();
+        assert_eq!(strings(run(&mut state, &["ZRANGE", "lex", "[b", "(d", "BYLEX"]).unwrap()), ["b", "c"]);
+        assert_eq!(strings(run(&mut state, &["ZRANGE", "lex", "+", "(b", "BYLEX", "REV"]).unwrap()), ["d", "c"]);
+        assert_eq!(
+            run(&mut state, &["ZRANGE", "lex", "b", "d", "BYLEX"]).unwrap_err(),
+            "min or max not valid string range item"
+        );
+        assert_eq!(
+            run(&mut state, &["ZRANGE", "z", "0", "1", "LIMIT", "0", "1"]).unwrap_err(),
+            "syntax error, LIMIT is only supported in combination with either BYSCORE or BYLEX"
+        );
+        assert_eq!(
+            run(&mut state, &["ZRANGE", "lex", "-", "+", "BYLEX", "WITHSCORES"]).unwrap_err(),
+            "syntax error, WITHSCORES not supported in combination with BYLEX"
+        );
+
+        assert_eq!(
+            run(&mut state, &["ZRANGESTORE", "dst", "z", "2", "5", "BYSCORE", "LIMIT", "0", "2"]).unwrap(),
+            RespType::Integer(2)
+        );
+        assert_eq!(
+            strings(run(&mut state, &["ZRANGE", "dst", "0", "-1", "WITHSCORES"]).unwrap()),
+            ["b", "2", "c", "2"]
+        );
+        assert_eq!(run(&mut state, &["ZRANGESTORE", "dst", "nope", "0", "-1"]).unwrap(), RespType::Integer(0));
+        assert!(!state.exists("dst"));
+    }
+
+    #[test]
+    fn test_pop_and_scan() {
+        let mut state = DefaultServerState::default();
+        setup(&mut state);
+
+        assert_eq!(strings(run(&mut state, &["ZPOPMIN", "z"]).unwrap()), ["a", "1"]);
+        assert_eq!(strings(run(&mut state, &["ZPOPMAX", "z", "2"]).unwrap()), ["e", "5", "d", "3"]);
+        assert_eq!(
+            run(&mut state, &["ZPOPMIN", "z", "-1"]).unwrap_err(),
+            "value is out of range, must be positive"
+        );
+
+        let reply = run(&mut state, &["ZSCAN", "z", "0"]).unwrap();
+        let RespType::Array(mut parts) = reply else { panic!("expected an array") };
+        let mut entries = strings(parts.pop().unwrap());
+        entries.sort();
+        assert_eq!(entries, ["2", "2", "b", "c"]);
+        assert_eq!(parts.pop(), Some(bulk("0")));
+
+        assert_eq!(strings(run(&mut state, &["ZPOPMAX", "z", "10"]).unwrap()), ["c", "2", "b", "2"]);
+        assert!(!state.exists("z"));
+        assert_eq!(strings(run(&mut state, &["ZPOPMIN", "z"]).unwrap()), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_algebra() {
+        let mut state = DefaultServerState::default();
+        run(&mut state, &["ZADD", "a", "1", "x", "2", "y", "3", "z"]).unwrap();
+        run(&mut state, &["ZADD", "b", "10", "y", "20", "z", "30", "w"]).unwrap();
+        run(&mut state, &["SADD", "s", "z", "v"]).unwrap();
+
+        assert_eq!(
+            strings(run(&mut state, &["ZUNION", "2", "a", "b", "WITHSCORES"]).unwrap()),
+            ["x", "1", "y", "12", "z", "23", "w", "30"]
+        );
+        assert_eq!(
+            strings(run(&mut state, &["ZINTER", "2", "a", "b", "WEIGHTS", "2", "0.5", "WITHSCORES"]).unwrap()),
+            ["y", "9", "z", "16"]
+        );
+        assert_eq!(
+            strings(run(&mut state, &["ZINTER", "3", "a", "b", "s", "AGGREGATE", "MAX", "WITHSCORES"]).unwrap()),
+            ["z", "20"]
+        );
+        assert_eq!(
+            strings(run(&mut state, &["ZUNION", "2", "a", "s", "AGGREGATE", "MIN", "WITHSCORES"]).unwrap()),
+            ["v", "1", "x", "1", "z", "1", "y", "2"]
+        );
+        assert_eq!(strings(run(&mut state, &["ZDIFF", "3", "a", "s", "nope"]).unwrap()), ["x", "y"]);
+        assert_eq!(strings(run(&mut state, &["ZINTER", "2", "a", "nope"]).unwrap()), Vec::<String>::new());
+
+        assert_eq!(run(&mut state, &["ZUNIONSTORE", "dst", "2", "a", "b"]).unwrap(), RespType::Integer(4));
+        assert_eq!(run(&mut state, &["ZSCORE", "dst", "z"]).unwrap(), bulk("23"));
+        assert_eq!(run(&mut state, &["ZINTERSTORE", "a", "2", "a", "b"]).unwrap(), RespType::Integer(2));
+        assert_eq!(strings(run(&mut state, &["ZRANGE", "a", "0", "-1"]).unwrap()), ["y", "z"]);
+        assert_eq!(run(&mut state, &["ZDIFFSTORE", "dst", "2", "a", "b"]).unwrap(), RespType::Integer(0));
+        assert!(!state.exists("dst"));
+
+        assert_eq!(run(&mut state, &["ZINTERCARD", "2", "a", "b"]).unwrap(), RespType::Integer(2));
+        assert_eq!(run(&mut state, &["ZINTERCARD", "2", "a", "b", "LIMIT", "1"]).unwrap(), RespType::Integer(1));
+        assert_eq!(
+            run(&mut state, &["ZUNION", "0", "a"]).unwrap_err(),
+            "at least 1 input key is needed for 'zunion' command"
+        );
+        assert_eq!(run(&mut state, &["ZUNION", "3", "a", "b"]).unwrap_err(), "syntax error");
+        assert_eq!(run(&mut state, &["ZDIFF", "2", "a", "b", "WEIGHTS", "1", "2"]).unwrap_err(), "syntax error");
+        assert_eq!(
+            run(&mut state, &["ZINTER", "2", "a", "b", "WEIGHTS", "1", "x"]).unwrap_err(),
+            "weight value is not a float"
+        );
+        assert_eq!(run(&mut state, &["ZUNIONSTORE", "d", "1", "a", "WITHSCORES"]).unwrap_err(), "syntax error");
+
+        run(&mut state, &["SET", "str", "v"]).unwrap();
+        assert!(run(&mut state, &["ZUNION", "2", "nope", "str"]).unwrap_err().starts_with("WRONGTYPE"));
+    }
+
+    #[test]
+    fn test_blocking_pops() {
+        let mut state = DefaultServerState::default();
+        run(&mut state, &["ZADD", "z", "1", "a", "2", "b", "3", "c"]).unwrap();
+
+        assert_eq!(
+            run(&mut state, &["BZPOPMAX", "nope", "z", "0"]).unwrap(),
+            RespType::Array(vec![bulk("z"), bulk("c"), bulk("3")])
+        );
+        assert_eq!(
+            run(&mut state, &["ZMPOP", "2", "nope", "z", "MIN", "COUNT", "5"]).unwrap(),
+            RespType::Array(vec![
+                bulk("z"),
+                RespType::Array(vec![
+                    RespType::Array(vec![bulk("a"), bulk("1")]),
+                    RespType::Array(vec![bulk("b"), bulk("2")]),
+                ]),
+            ])
+        );
+        assert_eq!(run(&mut state, &["ZMPOP", "1", "z", "MAX"]).unwrap(), RespType::NullArray);
+        assert_eq!(run(&mut state, &["ZMPOP", "1", "z", "UP"]).unwrap_err(), "syntax error");
+
+        assert_eq!(
+            run(&mut state, &["BZMPOP", "0", "0", "z", "MAX"]).unwrap_err(),
+            "numkeys should be greater than 0"
+        );
+        let mut client = ClientContext::default();
+        CommandDispatcher::new()
+            .dispatch("BZPOPMIN", vec![bulk("y"), bulk("z"), bulk("0")], &mut state, &mut client)
+            .unwrap();
+        let Some(BlockedOn::Keys(wait)) = client.blocked.take() else {
+            panic!("BZPOPMIN must block");
+        };
+        assert_eq!(
+            wait,
+            KeyWait {
+                keys: vec!["y".to_string(), "z".to_string()],
+                operation: KeyOperation::ZPop { max: false },
+                deadline: None,
+            }
+        );
+        assert_eq!(serve(&mut state, &wait).unwrap(), None);
+        run(&mut state, &["ZADD", "z", "5", "m", "4", "n"]).unwrap();
+        assert_eq!(
+            serve(&mut state, &wait).unwrap(),
+            Some(RespType::Array(vec![bulk("z"), bulk("n"), bulk("4")]))
+        );
+    }
+
+    #[test]
+    fn test_skiplist_matches_sorted_vector() {
+        let mut set = SortedSet::default();
+        let mut expected: Vec<(String, f64)> = Vec::new();
+        for _ in 0..2000 {
+            let member = format!("m{}", random_below(300));
+            let score = random_below(50) as f64;
+            if random_below(3) == 0 {
+                assert_eq!(set.remove(&member), expected.iter().any(|(m, _)| *m == member));
+                expected.retain(|(m, _)| *m != member);
+            } else {
+                let is_new = !expected.iter().any(|(m, _)| *m == member);
+                assert_eq!(set.insert(member.clone(), score), is_new);
+                expected.retain(|(m, _)| *m != member);
+                expected.push((member, score));
+            }
+        }
+        expected.sort_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+
+        assert_eq!(set.len(), expected.len());
+        assert_eq!(set.range(0, usize::MAX, false), expected);
+        for (rank, (member, _)) in expected.iter().enumerate() {
+            assert_eq!(set.rank(member), Some(rank));
+            assert_eq!(set.range(rank, rank + 1, false)[0].0, *member);
+        }
+        let mut reversed = expected.clone();
+        reversed.reverse();
+        assert_eq!(set.range(3, 10, true), reversed[3..10].to_vec());
+    }
+}
@@ -0,0 +1,466 @@
+/// Integration tests for stream commands
+#[cfg(test)]
+mod test_stream {
+    use codecrafters_redis::resp::blocking::{serve, KeyOperation, KeyWait};
+    use codecrafters_redis::resp::client::{BlockedOn, ClientContext};
+    use codecrafters_redis::resp::command_dispatcher::CommandDispatcher;
+    use codecrafters_redis::resp::protocol::{deserialize, RespType};
+    use codecrafters_redis::resp::state::default_server_state::DefaultServerState;
+    use codecrafters_redis::resp::state::server_state::ServerState;
+    use codecrafters_redis::resp::state::stream::StreamId;
+    use codecrafters_redis::resp::state::value::Value;
+
+    fn bulk(s: &str) -> RespType {
+        RespType::BulkString(Some(s.to_string()))
+    }
+
+    fn run(state: &mut DefaultServerState, args: &[&str]) -> Result<RespType, String> {
+        let mut client = ClientContext::default();
+        CommandDispatcher::new().dispatch(args[0], args[1..].iter().map(|arg| bulk(arg)).collect(), state, &mut client)
+    }
+
+    fn entry(id: &str, fields: &[&str]) -> RespType {
+        RespType::Array(vec![bulk(id), RespType::Array(fields.iter().map(|field| bulk(field)).collect())])
+    }
+
+    /// IDs of the entries of an `XRANGE` reply.
+    fn ids(reply: RespType) -> Vec<String> {
+        let RespType::Array(entries) = reply else { panic!("expected an array, got {:?}", reply) };
+        entries
+            .into_iter()
+            .map(|entry| match entry {
+                RespType::Array(mut parts) => match parts.remove(0) {
+                    RespType::BulkString(Some(id)) => id,
+                    other => panic!("expected an ID, got {:?}", other),
+                },
+                other => panic!("expected an entry, got {:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_add_ids() {
+        let mut state = DefaultServerState::default();
+
+        assert_eq!(run(&mut state, &["XADD", "s", "1-1", "f", "v"]).unwrap(), bulk("1-1"));
+        assert_eq!(run(&mut state, &["XADD", "s", "1-*", "f", "v"]).unwrap(), bulk("1-2"));
+        assert_eq!(run(&mut state, &["XADD", "s", "5", "f", "v"]).unwrap(), bulk("5-0"));
+        assert_eq!(
+            run(&mut state, &["XADD", "s", "5-0", "f", "v"]).unwrap_err(),
+            "The ID specified in XADD is equal or smaller than the target stream top item"
+        );
+        assert_eq!(
+            run(&mut state, &["XADD", "s", "4-*", "f", "v"]).unwrap_err(),
+            "The ID specified in XADD is equal or smaller than the target stream top item"
+        );
+        assert_eq!(
+            run(&mut state, &["XADD", "new", "0-0", "f", "v"]).unwrap_err(),
+            "The ID specified in XADD must be greater than 0-0"
+        );
+        assert_eq!(run(&mut state, &["XADD", "new", "0-*", "f", "v"]).unwrap(), bulk("0-1"));
+        assert_eq!(
+            run(&mut state, &["XADD", "s", "x-1", "f", "v"]).unwrap_err(),
+            "Invalid stream ID specified as stream command argument"
+        );
+        assert_eq!(
+            run(&mut state, &["XADD", "s", "*", "f"]).unwrap_err(),
+            "wrong number of arguments for 'xadd' command"
+        );
+
+        let RespType::BulkString(Some(id)) = run(&mut state, &["XADD", "s", "*", "f", "v"]).unwrap() else {
+            panic!("expected an ID");
+        };
+        assert!(id.split('-').next().unwrap().parse::<u64>().unwrap() > 5);
+        assert_eq!(run(&mut state, &["XLEN", "s"]).unwrap(), RespType::Integer(4));
+
+        let reply = run(&mut state, &["XADD", "none", "NOMKSTREAM", "*", "f", "v"]).unwrap();
+        assert_eq!(reply, RespType::BulkString(None));
+        assert!(!state.exists("none"));
+        assert_eq!(run(&mut state, &["TYPE", "s"]).unwrap(), RespType::SimpleString("stream".to_string()));
+        run(&mut state, &["SET", "str", "v"]).unwrap();
+        assert!(run(&mut state, &["XADD", "str", "*", "f", "v"]).unwrap_err().starts_with("WRONGTYPE"));
+    }
+
+    #[test]
+    fn test_range() {
+        let mut state = DefaultServerState::default();
+        for id in ["1-0", "1-1", "2-0", "3-5"] {
+            run(&mut state, &["XADD", "s", id, "id", id]).unwrap();
+        }
+
+        assert_eq!(
+            run(&mut state, &["XRANGE", "s", "-", "1"]).unwrap(),
+            RespType::Array(vec![entry("1-0", &["id", "1-0"]), entry("1-1", &["id", "1-1"])])
+        );
+        assert_eq!(ids(run(&mut state, &["XRANGE", "s", "(1-0", "+"]).unwrap()), ["1-1", "2-0", "3-5"]);
+        assert_eq!(ids(run(&mut state, &["XRANGE", "s", "-", "+", "COUNT", "2"]).unwrap()), ["1-0", "1-1"]);
+        assert_eq!(ids(run(&mut state, &["XREVRANGE", "s", "+", "(1-1", "COUNT", "2"]).unwrap()), ["3-5", "2-0"]);
+        assert_eq!(ids(run(&mut state, &["XRANGE", "s", "3", "2"]).unwrap()), Vec::<String>::new());
+        assert_eq!(ids(run(&mut state, &["XRANGE", "nope", "-", "+"]).unwrap()), Vec::<String>::new());
+        assert_eq!(run(&mut state, &["XRANGE", "s", "(+", "+"]).unwrap_err(), "invalid start ID for the interval");
+        assert_eq!(run(&mut state, &["XRANGE", "s", "-", "+", "LIMIT", "1"]).unwrap_err(), "syntax error");
+    }
+
+    #[test]
+    fn test_delete_and_trim() {
+        let mut state = DefaultServerState::default();
+        for seq in 1..=10 {
+            run(&mut state, &["XADD", "s", &format!("1-{}", seq), "f", "v"]).unwrap();
+        }
+
+        assert_eq!(run(&mut state, &["XDEL", "s", "1-1", "1-1", "9-9"]).unwrap(), RespType::Integer(1));
+        assert_eq!(run(&mut state, &["XTRIM", "s", "MAXLEN", "=", "7"]).unwrap(), RespType::Integer(2));
+        assert_eq!(run(&mut state, &["XTRIM", "s", "MINID", "~", "1-9", "LIMIT", "2"]).unwrap(), RespType::Integer(2));
+        assert_eq!(ids(run(&mut state, &["XRANGE", "s", "-", "+"]).unwrap()), ["1-6", "1-7", "1-8", "1-9", "1-10"]);
+        assert_eq!(
+            run(&mut state, &["XTRIM", "s", "MAXLEN", "1", "LIMIT", "2"]).unwrap_err(),
+            "syntax error, LIMIT cannot be used without the special ~ option"
+        );
+        assert_eq!(run(&mut state, &["XTRIM", "s", "MAXLEN", "-1"]).unwrap_err(), "The MAXLEN argument must be >= 0.");
+
+        assert_eq!(
+            run(&mut state, &["XADD", "s", "MAXLEN", "2", "2-0", "f", "v"]).unwrap(),
+            bulk("2-0")
+        );
+        assert_eq!(ids(run(&mut state, &["XRANGE", "s", "-", "+"]).unwrap()), ["1-10", "2-0"]);
+
+        // Emptied streams are kept, and so is their last ID.
+        assert_eq!(run(&mut state, &["XTRIM", "s", "MAXLEN", "0"]).unwrap(), RespType::Integer(2));
+        assert_eq!(run(&mut state, &["XLEN", "s"]).unwrap(), RespType::Integer(0));
+        assert!(run(&mut state, &["XADD", "s", "1-20", "f", "v"]).is_err());
+    }
+
+    #[test]
+    fn test_read() {
+        let mut state = DefaultServerState::default();
+        run(&mut state, &["XADD", "a", "1-0", "f", "1"]).unwrap();
+        run(&mut state, &["XADD", "a", "2-0", "f", "2"]).unwrap();
+        run(&mut state, &["XADD", "b", "1-0", "g", "1"]).unwrap();
+
+        assert_eq!(
+            run(&mut state, &["XREAD", "COUNT", "1", "STREAMS", "a", "b", "0", "0-0"]).unwrap(),
+            RespType::Array(vec![
+                RespType::Array(vec![bulk("a"), RespType::Array(vec![entry("1-0", &["f", "1"])])]),
+                RespType::Array(vec![bulk("b"), RespType::Array(vec![entry("1-0", &["g", "1"])])]),
+            ])
+        );
+        assert_eq!(
+            run(&mut state, &["XREAD", "STREAMS", "a", "b", "1-0", "1-0"]).unwrap(),
+            RespType::Array(vec![RespType::Array(vec![bulk("a"), RespType::Array(vec![entry("2-0", &["f", "2"])])])])
+        );
+        assert_eq!(run(&mut state, &["XREAD", "STREAMS", "a", "$"]).unwrap(), RespType::NullArray);
+        assert_eq!(
+            run(&mut state, &["XREAD", "STREAMS", "a", "b", "0"]).unwrap_err(),
+            "Unbalanced 'xread' list of streams: for each stream key an ID or '$' must be specified."
+        );
+        assert_eq!(run(&mut state, &["XREAD", "BLOCK", "-1", "STREAMS", "a", "0"]).unwrap_err(), "timeout is negative");
+    }
+
+    #[test]
+    fn test_blocking_read() {
+        let mut state = DefaultServerState::default();
+        let mut client = ClientContext::default();
+        run(&mut state, &["XADD", "a", "1-0", "f", "1"]).unwrap();
+
+        let args = ["BLOCK", "0", "STREAMS", "a", "missing", "$", "$"];
+        let reply = CommandDispatcher::new()
+            .dispatch("XREAD", args.iter().map(|arg| bulk(arg)).collect(), &mut state, &mut client)
+            .unwrap();
+        assert_eq!(reply, RespType::NullArray);
+        let Some(BlockedOn::Keys(wait)) = client.blocked.take() else {
+            panic!("XREAD BLOCK must block");
+        };
+        assert_eq!(
+            wait,
+            KeyWait {
+                keys: vec!["a".to_string(), "missing".to_string()],
+                operation: KeyOperation::XRead {
+                    ids: vec![StreamId::new(1, 0), StreamId::MIN],
+                    count: None,
+                },
+                deadline: None,
+            }
+        );
+
+        assert_eq!(serve(&mut state, &wait).unwrap(), None);
+        run(&mut state, &["XADD", "missing", "5-0", "g", "2"]).unwrap();
+        assert_eq!(
+            serve(&mut state, &wait).unwrap(),
+            Some(RespType::Array(vec![RespType::Array(vec![
+                bulk("missing"),
+                RespType::Array(vec![entry("5-0", &["g", "2"])]),
+            ])]))
+        );
+    }
+
+    /// Runs a command and returns its reply as nested vectors of strings, for replies
+    /// holding integers and bulk strings only.
+    fn strings(state: &mut DefaultServerState, args: &[&str]) -> Vec<String> {
+        fn flatten(reply: RespType, out: &mut Vec<String>) {
+            match reply {
+                RespType::Array(items) => items.into_iter().for_each(|item| flatten(item, out)),
+                RespType::BulkString(Some(value)) => out.push(value),
+                RespType::Integer(value) => out.push(value.to_string()),
+                RespType::BulkString(None) | RespType::NullArray => out.push("(nil)".to_string()),
+                other => panic!("unexpected reply {:?}", other),
+            }
+        }
+        let mut out = Vec::new();
+        flatten(run(state, args).unwrap(), &mut out);
+        out
+    }
+
+    #[test]
+    fn test_group_management() {
+        let mut state = DefaultServerState::default();
+        let ok = RespType::SimpleString("OK".to_string());
+
+        assert_eq!(
+            run(&mut state, &["XGROUP", "CREATE", "s", "g", "$"]).unwrap_err(),
+            "The XGROUP subcommand requires the key to exist. Note that for CREATE you may want to use the \
+             MKSTREAM option to create an empty stream automatically."
+        );
+        assert_eq!(run(&mut state, &["XGROUP", "CREATE", "s", "g", "$", "MKSTREAM"]).unwrap(), ok);
+        assert_eq!(run(&mut state, &["XLEN", "s"]).unwrap(), RespType::Integer(0));
+        assert_eq!(
+            run(&mut state, &["XGROUP", "CREATE", "s", "g", "0"]).unwrap_err(),
+            "BUSYGROUP Consumer Group name already exists"
+        );
+        assert_eq!(run(&mut state, &["XGROUP", "CREATECONSUMER", "s", "g", "c"]).unwrap(), RespType::Integer(1));
+        assert_eq!(run(&mut state, &["XGROUP", "CREATECONSUMER", "s", "g", "c"]).unwrap(), RespType::Integer(0));
+        assert_eq!(
+            run(&mut state, &["XGROUP", "CREATECONSUMER", "s", "nope", "c"]).unwrap_err(),
+            "NOGROUP No such consumer group 'nope' for key name 's'"
+        );
+        assert_eq!(run(&mut state, &["XGROUP", "SETID", "s", "g", "5-0", "ENTRIESREAD", "3"]).unwrap(), ok);
+        assert_eq!(
+            run(&mut state, &["XGROUP", "SETID", "s", "g", "0", "ENTRIESREAD", "-2"]).unwrap_err(),
+            "value for ENTRIESREAD must be positive or -1"
+        );
+        assert_eq!(
+            strings(&mut state, &["XINFO", "GROUPS", "s"]),
+            ["name", "g", "consumers", "1", "pending", "0", "last-delivered-id", "5-0", "entries-read", "3", "lag", "0"]
+        );
+        assert_eq!(run(&mut state, &["XGROUP", "DELCONSUMER", "s", "g", "c"]).unwrap(), RespType::Integer(0));
+        assert_eq!(run(&mut state, &["XGROUP", "DESTROY", "s", "g"]).unwrap(), RespType::Integer(1));
+        assert_eq!(run(&mut state, &["XGROUP", "DESTROY", "s", "g"]).unwrap(), RespType::Integer(0));
+        assert_eq!(run(&mut state, &["XINFO", "GROUPS", "s"]).unwrap(), RespType::Array(vec![]));
+    }
+
+    #[test]
+    fn test_read_group() {
+        let mut state = DefaultServerState::default();
+        for id in ["1-0", "2-0", "3-0"] {
+            run(&mut state, &["XADD", "s", id, "f", id]).unwrap();
+        }
+        run(&mut state, &["XGROUP", "CREATE", "s", "g", "0"]).unwrap();
+
+        assert_eq!(
+            run(&mut state, &["XREADGROUP", "GROUP", "g", "alice", "COUNT", "2", "STREAMS", "s", ">"]).unwrap(),
+            RespType::Array(vec![RespType::Array(vec![
+                bulk("s"),
+                RespType::Array(vec![entry("1-0", &["f", "1-0"]), entry("2-0", &["f", "2-0"])]),
+            ])])
+        );
+        assert_eq!(
+            strings(&mut state, &["XREADGROUP", "GROUP", "g", "bob", "STREAMS", "s", ">"]),
+            ["s", "3-0", "f", "3-0"]
+        );
+        assert_eq!(
+            run(&mut state, &["XREADGROUP", "GROUP", "g", "bob", "STREAMS", "s", ">"]).unwrap(),
+            RespType::NullArray
+        );
+
+        // History reads return the consumer's own pending entries after the ID.
+        assert_eq!(
+            strings(&mut state, &["XREADGROUP", "GROUP", "g", "alice", "STREAMS", "s", "1"]),
+            ["s", "2-0", "f", "2-0"]
+        );
+        assert_eq!(strings(&mut state, &["XPENDING", "s", "g"]), ["3", "1-0", "3-0", "alice", "2", "bob", "1"]);
+        let pending = strings(&mut state, &["XPENDING", "s", "g", "-", "+", "10", "alice"]);
+        // Idle times are left out.
+        assert_eq!([&pending[..2], &pending[3..6], &pending[7..]].concat(), ["1-0", "alice", "1", "2-0", "alice", "2"]);
+
+        assert_eq!(run(&mut state, &["XACK", "s", "g", "1-0", "3-0", "9-0"]).unwrap(), RespType::Integer(2));
+        assert_eq!(run(&mut state, &["XACK", "s", "nope", "2-0"]).unwrap(), RespType::Integer(0));
+        assert_eq!(strings(&mut state, &["XPENDING", "s", "g"]), ["1", "2-0", "2-0", "alice", "1"]);
+
+        // Entries deleted while pending are returned without their fields.
+        run(&mut state, &["XDEL", "s", "2-0"]).unwrap();
+        assert_eq!(
+            strings(&mut state, &["XREADGROUP", "GROUP", "g", "alice", "STREAMS", "s", "0"]),
+            ["s", "2-0", "(nil)"]
+        );
+
+        run(&mut state, &["XADD", "s", "4-0", "f", "4-0"]).unwrap();
+        assert_eq!(
+            strings(&mut state, &["XREADGROUP", "GROUP", "g", "bob", "NOACK", "STREAMS", "s", ">"]),
+            ["s", "4-0", "f", "4-0"]
+        );
+        assert_eq!(strings(&mut state, &["XPENDING", "s", "g"])[0], "1");
+
+        assert_eq!(
+            run(&mut state, &["XREADGROUP", "GROUP", "nope", "c", "STREAMS", "s", ">"]).unwrap_err(),
+            "NOGROUP No such key 's' or consumer group 'nope' in XREADGROUP with GROUP option"
+        );
+        assert!(run(&mut state, &["XREADGROUP", "GROUP", "g", "c", "STREAMS", "s", "$"]).is_err());
+        assert_eq!(
+            run(&mut state, &["XPENDING", "s", "nope"]).unwrap_err(),
+            "NOGROUP No such key 's' or consumer group 'nope'"
+        );
+    }
+
+    #[test]
+    fn test_claim() {
+        let mut state = DefaultServerState::default();
+        for id in ["1-0", "2-0", "3-0"] {
+            run(&mut state, &["XADD", "s", id, "f", id]).unwrap();
+        }
+        run(&mut state, &["XGROUP", "CREATE", "s", "g", "0"]).unwrap();
+        run(&mut state, &["XREADGROUP", "GROUP", "g", "alice", "STREAMS", "s", ">"]).unwrap();
+
+        // Entries are not idle for long enough yet.
+        assert_eq!(run(&mut state, &["XCLAIM", "s", "g", "bob", "60000", "1-0"]).unwrap(), RespType::Array(vec![]));
+        assert_eq!(
+            run(&mut state, &["XCLAIM", "s", "g", "bob", "0", "1-0", "RETRYCOUNT", "5"]).unwrap(),
+            RespType::Array(vec![entry("1-0", &["f", "1-0"])])
+        );
+        let pending = strings(&mut state, &["XPENDING", "s", "g", "-", "1", "1"]);
+        assert_eq!((pending[1].as_str(), pending[3].as_str()), ("bob", "5"));
+        assert_eq!(
+            strings(&mut state, &["XCLAIM", "s", "g", "bob", "0", "2-0", "3-0", "IDLE", "5000", "JUSTID"]),
+            ["2-0", "3-0"]
+        );
+        assert!(strings(&mut state, &["XPENDING", "s", "g", "IDLE", "4000", "-", "+", "10"]).len() == 8);
+        assert_eq!(strings(&mut state, &["XPENDING", "s", "g"]), ["3", "1-0", "3-0", "bob", "3"]);
+
+        assert_eq!(
+            run(&mut state, &["XCLAIM", "s", "g", "bob", "0", "1-0", "BOGUS"]).unwrap_err(),
+            "Unrecognized XCLAIM option 'BOGUS'"
+        );
+
+        run(&mut state, &["XDEL", "s", "2-0"]).unwrap();
+        assert_eq!(
+            strings(&mut state, &["XAUTOCLAIM", "s", "g", "carol", "0", "-", "COUNT", "1"]),
+            ["2-0", "1-0", "f", "1-0"]
+        );
+        assert_eq!(
+            strings(&mut state, &["XAUTOCLAIM", "s", "g", "carol", "0", "2-0", "JUSTID"]),
+            ["0-0", "3-0", "2-0"]
+        );
+        assert_eq!(strings(&mut state, &["XPENDING", "s", "g"]), ["2", "1-0", "3-0", "carol", "2"]);
+        assert_eq!(
+            run(&mut state, &["XAUTOCLAIM", "s", "g", "carol", "0", "-", "COUNT", "0"]).unwrap_err(),
+            "COUNT must be > 0"
+        );
+
+        // FORCE creates pending entries, for entries still in the stream only.
+        run(&mut state, &["XACK", "s", "g", "1-0"]).unwrap();
+        assert_eq!(strings(&mut state, &["XCLAIM", "s", "g", "dave", "0", "1-0", "2-0", "FORCE", "JUSTID"]), ["1-0"]);
+        assert_eq!(run(&mut state, &["XGROUP", "DELCONSUMER", "s", "g", "carol"]).unwrap(), RespType::Integer(1));
+        assert_eq!(strings(&mut state, &["XPENDING", "s", "g"]), ["1", "1-0", "1-0", "dave", "1"]);
+    }
+
+    #[test]
+    fn test_info() {
+        let mut state = DefaultServerState::default();
+        for id in ["1-0", "2-0", "3-0"] {
+            run(&mut state, &["XADD", "s", id, "f", id]).unwrap();
+        }
+        run(&mut state, &["XDEL", "s", "2-0"]).unwrap();
+        run(&mut state, &["XGROUP", "CREATE", "s", "g", "0"]).unwrap();
+
+        assert_eq!(
+            strings(&mut state, &["XINFO", "STREAM", "s"]),
+            [
+                "length", "2", "last-generated-id", "3-0", "max-deleted-entry-id", "2-0", "entries-added", "3",
+                "recorded-first-entry-id", "1-0", "groups", "1", "first-entry", "1-0", "f", "1-0", "last-entry", "3-0",
+                "f", "3-0",
+            ]
+        );
+        // The lag is unknown while the group has deleted entries ahead.
+        assert_eq!(strings(&mut state, &["XINFO", "GROUPS", "s"])[8..], ["entries-read", "(nil)", "lag", "(nil)"]);
+        run(&mut state, &["XREADGROUP", "GROUP", "g", "c", "COUNT", "1", "STREAMS", "s", ">"]).unwrap();
+        assert_eq!(strings(&mut state, &["XINFO", "GROUPS", "s"])[8..], ["entries-read", "(nil)", "lag", "(nil)"]);
+        run(&mut state, &["XREADGROUP", "GROUP", "g", "c", "STREAMS", "s", ">"]).unwrap();
+        assert_eq!(strings(&mut state, &["XINFO", "GROUPS", "s"])[8..], ["entries-read", "3", "lag", "0"]);
+
+        let consumers = strings(&mut state, &["XINFO", "CONSUMERS", "s", "g"]);
+        assert_eq!([&consumers[..4], &consumers[6..7]].concat(), ["name", "c", "pending", "2", "inactive"]);
+        assert_eq!(run(&mut state, &["XINFO", "STREAM", "nope"]).unwrap_err(), "no such key");
+        assert_eq!(
+            run(&mut state, &["XINFO", "CONSUMERS", "s", "nope"]).unwrap_err(),
+            "NOGROUP No such key 's' or consumer group 'nope'"
+        );
+    }
+
+    #[test]
+    fn test_blocking_read_group() {
+        let mut state = DefaultServerState::default();
+        let mut client = ClientContext::default();
+        run(&mut state, &["XGROUP", "CREATE", "s", "g", "$", "MKSTREAM"]).unwrap();
+
+        let args = ["GROUP", "g", "c", "BLOCK", "0", "STREAMS", "s", ">"];
+        let reply = CommandDispatcher::new()
+            .dispatch("XREADGROUP", args.iter().map(|arg| bulk(arg)).collect(), &mut state, &mut client)
+            .unwrap();
+        assert_eq!(reply, RespType::NullArray);
+        let Some(BlockedOn::Keys(wait)) = client.blocked.take() else {
+            panic!("XREADGROUP BLOCK must block");
+        };
+
+        run(&mut state, &["XADD", "s", "1-0", "f", "v"]).unwrap();
+        assert_eq!(
+            serve(&mut state, &wait).unwrap(),
+            Some(RespType::Array(vec![RespType::Array(vec![
+                bulk("s"),
+                RespType::Array(vec![entry("1-0", &["f", "v"])]),
+            ])]))
+        );
+        assert_eq!(strings(&mut state, &["XPENDING", "s", "g"]), ["1", "1-0", "1-0", "c", "1"]);
+    }
+
+    #[test]
+    fn test_groups_replicate_deterministically() {
+        let mut master = DefaultServerState::default();
+        let (_, mut replication) = master
+            .replication()
+            .register_replica("127.0.0.1".to_string(), 6380, 0, Vec::new());
+        let commands: [&[&str]; 8] = [
+            &["XADD", "s", "1-1", "f", "1"],
+            &["XADD", "s", "1-*", "f", "2"],
+            &["XGROUP", "CREATE", "s", "g", "0"],
+            &["XREADGROUP", "GROUP", "g", "alice", "STREAMS", "s", ">"],
+            &["XREADGROUP", "GROUP", "g", "alice", "STREAMS", "s", "0"],
+            &["XCLAIM", "s", "g", "bob", "0", "1-1", "IDLE", "100", "LASTID", "9-9"],
+            &["XAUTOCLAIM", "s", "g", "carol", "0", "-", "COUNT", "1"],
+            &["XACK", "s", "g", "1-2"],
+        ];
+        for args in commands {
+            run(&mut master, args).unwrap();
+        }
+
+        let mut replica = DefaultServerState::default();
+        while let Ok(bytes) = replication.try_recv() {
+            let mut bytes = &bytes[..];
+            while !bytes.is_empty() {
+                let (RespType::Array(parts), len) = deserialize(bytes).unwrap() else {
+                    panic!("expected a command");
+                };
+                let RespType::BulkString(Some(name)) = &parts[0] else {
+                    panic!("expected a command name");
+                };
+                CommandDispatcher::new()
+                    .dispatch(name, parts[1..].to_vec(), &mut replica, &mut ClientContext::default())
+                    .unwrap();
+                bytes = &bytes[len..];
+            }
+        }
+        let Some(Value::Stream(stream)) = master.get("s") else {
+            panic!("expected a stream");
+        };
+        let group = &stream.groups["g"];
+        assert_eq!((group.last_id, group.pending.len(), group.consumers.len()), (StreamId::new(9, 9), 1, 3));
+        assert_eq!(master.get("s").cloned(), replica.get("s").cloned());
+    }
+}
@@ -0,0 +1,263 @@
+mod common;
+
+/// Integration tests for string commands
+#[cfg(test)]
+mod test_strings {
+    use std::thread::sleep;
+    use std::time::Duration;
+
+    use codecrafters_redis::resp::protocol::RespType;
+    use codecrafters_redis::resp::state::default_server_state::DefaultServerState;
+
+    use crate::common::{bulk, ok, run};
+
+    fn nil() -> RespType {
+        RespType::BulkString(None)
+    }
+
+    #[test]
+    fn test_counters() {
+        let mut state = DefaultServerState::default();
+        assert_eq!(run(&mut state, &["INCR", "hits"]).unwrap(), RespType::Integer(1));
+        assert_eq!(run(&mut state, &["INCRBY", "hits", "41"]).unwrap(), RespType::Integer(42));
+        assert_eq!(run(&mut state, &["DECR", "hits"]).unwrap(), RespType::Integer(41));
+        assert_eq!(run(&mut state, &["DECRBY", "hits", "50"]).unwrap(), RespType::Integer(-9));
+        assert_eq!(run(&mut state, &["GET", "hits"]).unwrap(), bulk("-9"));
+
+        // Counters are plain strings, whatever set them.
+        run(&mut state, &["SET", "stock", "100"]).unwrap();
+        assert_eq!(run(&mut state, &["INCRBY", "stock", "-30"]).unwrap(), RespType::Integer(70));
+
+        for value in ["abc", "1.5", "+1", "007", " 1", ""] {
+            run(&mut state, &["SET", "bad", value]).unwrap();
+            assert_eq!(
+                run(&mut state, &["INCR", "bad"]).unwrap_err(),
+                "value is not an integer or out of range",
+                "{}",
+                value
+            );
+        }
+        assert_eq!(
+            run(&mut state, &["INCRBY", "stock", "ten"]).unwrap_err(),
+            "value is not an integer or out of range"
+        );
+
+        run(&mut state, &["SET", "big", &i64::MAX.to_string()]).unwrap();
+        assert_eq!(run(&mut state, &["INCR", "big"]).unwrap_err(), "increment or decrement would overflow");
+        assert_eq!(run(&mut state, &["GET", "big"]).unwrap(), bulk(&i64::MAX.to_string()));
+        assert_eq!(
+            run(&mut state, &["DECRBY", "stock", &i64::MIN.to_string()]).unwrap_err(),
+            "decrement would overflow"
+        );
+        assert_eq!(
+            run(&mut state, &["INCR", "hits", "1"]).unwrap_err(),
+            "wrong number of arguments for 'incr' command"
+        );
+
+        run(&mut state, &["RPUSH", "list", "a"]).unwrap();
+        assert!(run(&mut state, &["INCR", "list"]).unwrap_err().starts_with("WRONGTYPE"));
+    }
+
+    #[test]
+    fn test_counters_keep_ttl() {
+        let mut state = DefaultServerState::default();
+        run(&mut state, &["PSETEX", "counter", "100", "1"]).unwrap();
+        run(&mut state, &["PSETEX", "float", "100", "1"]).unwrap();
+        assert_eq!(run(&mut state, &["INCR", "counter"]).unwrap(), RespType::Integer(2));
+        assert_eq!(run(&mut state, &["INCRBYFLOAT", "float", "0.5"]).unwrap(), bulk("1.5"));
+
+        sleep(Duration::from_millis(150));
+        assert_eq!(run(&mut state, &["GET", "counter"]).unwrap(), nil());
+        assert_eq!(run(&mut state, &["GET", "float"]).unwrap(), nil());
+    }
+
+    #[test]
+    fn test_incrbyfloat() {
+        let mut state = DefaultServerState::default();
+        run(&mut state, &["SET", "price", "10.50"]).unwrap();
+        assert_eq!(run(&mut state, &["INCRBYFLOAT", "price", "0.1"]).unwrap(), bulk("10.6"));
+        assert_eq!(run(&mut state, &["INCRBYFLOAT", "price", "-5"]).unwrap(), bulk("5.6"));
+        assert_eq!(run(&mut state, &["GET", "price"]).unwrap(), bulk("5.6"));
+        assert_eq!(run(&mut state, &["INCRBYFLOAT", "new", "5.0e3"]).unwrap(), bulk("5000"));
+        assert_eq!(run(&mut state, &["INCR", "new"]).unwrap(), RespType::Integer(5001));
+
+        assert_eq!(run(&mut state, &["INCRBYFLOAT", "price", "abc"]).unwrap_err(), "value is not a valid float");
+        assert_eq!(run(&mut state, &["INCRBYFLOAT", "price", "inf"]).unwrap_err(), "value is not a valid float");
+        run(&mut state, &["SET", "text", "abc"]).unwrap();
+        assert_eq!(run(&mut state, &["INCRBYFLOAT", "text", "1"]).unwrap_err(), "value is not a valid float");
+        run(&mut state, &["SET", "huge", "1.7e308"]).unwrap();
+        assert_eq!(
+            run(&mut state, &["INCRBYFLOAT", "huge", "1.7e308"]).unwrap_err(),
+            "increment would produce NaN or Infinity"
+        );
+    }
+
+    #[test]
+    fn test_ranges() {
+        let mut state = DefaultServerState::default();
+        assert_eq!(run(&mut state, &["APPEND", "greeting", "Hello"]).unwrap(), RespType::Integer(5));
+        assert_eq!(run(&mut state, &["APPEND", "greeting", " World"]).unwrap(), RespType::Integer(11));
+        assert_eq!(run(&mut state, &["STRLEN", "greeting"]).unwrap(), RespType::Integer(11));
+        assert_eq!(run(&mut state, &["STRLEN", "missing"]).unwrap(), RespType::Integer(0));
+
+        for (start, end, expected) in [
+            ("0", "4", "Hello"),
+            ("-5", "-1", "World"),
+            ("0", "-1", "Hello World"),
+            ("6", "100", "World"),
+            ("-100", "2", "Hel"),
+            ("5", "3", ""),
+            ("-1", "-5", ""),
+            ("11", "20", ""),
+        ] {
+            assert_eq!(
+                run(&mut state, &["GETRANGE", "greeting", start, end]).unwrap(),
+                bulk(expected),
+                "{} {}",
+                start,
+                end
+            );
+        }
+        assert_eq!(run(&mut state, &["GETRANGE", "missing", "0", "-1"]).unwrap(), bulk(""));
+
+        assert_eq!(run(&mut state, &["SETRANGE", "greeting", "6", "Redis"]).unwrap(), RespType::Integer(11));
+        assert_eq!(run(&mut state, &["GET", "greeting"]).unwrap(), bulk("Hello Redis"));
+        assert_eq!(run(&mut state, &["SETRANGE", "greeting", "11", "!"]).unwrap(), RespType::Integer(12));
+        assert_eq!(run(&mut state, &["SETRANGE", "padded", "3", "x"]).unwrap(), RespType::Integer(4));
+        assert_eq!(run(&mut state, &["GET", "padded"]).unwrap(), bulk("\0\0\0x"));
+        // Empty values neither create nor grow strings.
+        assert_eq!(run(&mut state, &["SETRANGE", "empty", "10", ""]).unwrap(), RespType::Integer(0));
+        assert_eq!(run(&mut state, &["TYPE", "empty"]).unwrap(), RespType::SimpleString("none".to_string()));
+        assert_eq!(run(&mut state, &["SETRANGE", "padded", "10", ""]).unwrap(), RespType::Integer(4));
+
+        assert_eq!(run(&mut state, &["SETRANGE", "padded", "-1", "x"]).unwrap_err(), "offset is out of range");
+        assert_eq!(
+            run(&mut state, &["SETRANGE", "padded", "536870911", "xy"]).unwrap_err(),
+            "string exceeds maximum allowed size (proto-max-bulk-len)"
+        );
+        assert_eq!(
+            run(&mut state, &["GETRANGE", "padded", "a", "1"]).unwrap_err(),
+            "value is not an integer or out of range"
+        );
+    }
+
+    #[test]
+    fn test_get_variants() {
+        let mut state = DefaultServerState::default();
+        assert_eq!(run(&mut state, &["GETSET", "key", "one"]).unwrap(), nil());
+        assert_eq!(run(&mut state, &["GETSET", "key", "two"]).unwrap(), bulk("one"));
+        assert_eq!(run(&mut state, &["GETDEL", "key"]).unwrap(), bulk("two"));
+        assert_eq!(run(&mut state, &["GETDEL", "key"]).unwrap(), nil());
+        assert_eq!(run(&mut state, &["GET", "key"]).unwrap(), nil());
+
+        assert_eq!(run(&mut state, &["SETNX", "lock", "a"]).unwrap(), RespType::Integer(1));
+        assert_eq!(run(&mut state, &["SETNX", "lock", "b"]).unwrap(), RespType::Integer(0));
+        assert_eq!(run(&mut state, &["GET", "lock"]).unwrap(), bulk("a"));
+
+        // GETSET drops the TTL, GETEX sets or removes it.
+        run(&mut state, &["PSETEX", "replaced", "50", "a"]).unwrap();
+        run(&mut state, &["GETSET", "replaced", "b"]).unwrap();
+        run(&mut state, &["SETEX", "persisted", "1", "a"]).unwrap();
+        assert_eq!(run(&mut state, &["GETEX", "persisted", "PERSIST"]).unwrap(), bulk("a"));
+        run(&mut state, &["SET", "expiring", "a"]).unwrap();
+        assert_eq!(run(&mut state, &["GETEX", "expiring", "PX", "50"]).unwrap(), bulk("a"));
+        assert_eq!(run(&mut state, &["GETEX", "expiring"]).unwrap(), bulk("a"));
+        sleep(Duration::from_millis(100));
+        assert_eq!(run(&mut state, &["GET", "replaced"]).unwrap(), bulk("b"));
+        assert_eq!(run(&mut state, &["GET", "expiring"]).unwrap(), nil());
+        sleep(Duration::from_millis(1000));
+        assert_eq!(run(&mut state, &["GET", "persisted"]).unwrap(), bulk("a"));
+
+        // A time in the past deletes the key.
+        assert_eq!(run(&mut state, &["GETEX", "persisted", "EXAT", "1"]).unwrap(), bulk("a"));
+        assert_eq!(run(&mut state, &["GET", "persisted"]).unwrap(), nil());
+        assert_eq!(run(&mut state, &["GETEX", "missing", "EX", "10"]).unwrap(), nil());
+
+        assert_eq!(
+            run(&mut state, &["GETEX", "lock", "EX", "0"]).unwrap_err(),
+            "invalid expire time in 'getex' command"
+        );
+        assert_eq!(run(&mut state, &["GETEX", "lock", "KEEPTTL"]).unwrap_err(), "syntax error");
+        assert_eq!(run(&mut state, &["GETEX", "lock", "EX", "1", "PX", "1"]).unwrap_err(), "syntax error");
+        assert_eq!(
+            run(&mut state, &["SETEX", "lock", "-1", "a"]).unwrap_err(),
+            "invalid expire time in 'setex' command"
+        );
+        run(&mut state, &["RPUSH", "list", "a"]).unwrap();
+        assert!(run(&mut state, &["GETDEL", "list"]).unwrap_err().starts_with("WRONGTYPE"));
+        assert!(run(&mut state, &["GETSET", "list", "a"]).unwrap_err().starts_with("WRONGTYPE"));
+    }
+
+    #[test]
+    fn test_set_options() {
+        let mut state = DefaultServerState::default();
+        assert_eq!(run(&mut state, &["SET", "key", "a", "XX"]).unwrap(), nil());
+        assert_eq!(run(&mut state, &["GET", "key"]).unwrap(), nil());
+        assert_eq!(run(&mut state, &["SET", "key", "a", "nx"]).unwrap(), ok());
+        assert_eq!(run(&mut state, &["SET", "key", "b", "NX"]).unwrap(), nil());
+        assert_eq!(run(&mut state, &["SET", "key", "b", "NX", "GET"]).unwrap(), bulk("a"));
+        assert_eq!(run(&mut state, &["SET", "key", "b", "XX", "GET"]).unwrap(), bulk("a"));
+        assert_eq!(run(&mut state, &["SET", "key", "c", "GET"]).unwrap(), bulk("b"));
+        assert_eq!(run(&mut state, &["SET", "other", "c", "GET"]).unwrap(), nil());
+
+        // KEEPTTL carries the TTL over, any other SET drops it.
+        run(&mut state, &["SET", "kept", "a", "PX", "50"]).unwrap();
+        run(&mut state, &["SET", "kept", "b", "KEEPTTL"]).unwrap();
+        run(&mut state, &["SET", "dropped", "a", "EX", "1", "GET"]).unwrap();
+        run(&mut state, &["SET", "dropped", "b"]).unwrap();
+        run(&mut state, &["SET", "expiring", "a", "PXAT", "99999999999999"]).unwrap();
+        sleep(Duration::from_millis(100));
+        assert_eq!(run(&mut state, &["GET", "kept"]).unwrap(), nil());
+        assert_eq!(run(&mut state, &["GET", "dropped"]).unwrap(), bulk("b"));
+        assert_eq!(run(&mut state, &["GET", "expiring"]).unwrap(), bulk("a"));
+
+        // A time in the past deletes the key.
+        assert_eq!(run(&mut state, &["SET", "expiring", "b", "EXAT", "1"]).unwrap(), ok());
+        assert_eq!(run(&mut state, &["GET", "expiring"]).unwrap(), nil());
+
+        for options in [
+            &["NX", "XX"][..],
+            &["EX", "1", "PX", "1"],
+            &["EX", "1", "KEEPTTL"],
+            &["KEEPTTL", "EX", "1"],
+            &["EX"],
+            &["60"],
+            &["FOREVER"],
+        ] {
+            let args = [&["SET", "key", "a"][..], options].concat();
+            assert_eq!(run(&mut state, &args).unwrap_err(), "syntax error", "{:?}", options);
+        }
+        assert_eq!(
+            run(&mut state, &["SET", "key", "a", "EX", "0"]).unwrap_err(),
+            "invalid expire time in 'set' command"
+        );
+        run(&mut state, &["RPUSH", "list", "a"]).unwrap();
+        assert!(run(&mut state, &["SET", "list", "a", "GET"]).unwrap_err().starts_with("WRONGTYPE"));
+        assert_eq!(run(&mut state, &["SET", "list", "a", "NX"]).unwrap(), nil());
+        assert_eq!(run(&mut state, &["SET", "list", "a"]).unwrap(), ok());
+    }
+
+    #[test]
+    fn test_multiple_keys() {
+        let mut state = DefaultServerState::default();
+        assert_eq!(run(&mut state, &["MSET", "a", "1", "b", "2"]).unwrap(), ok());
+        run(&mut state, &["RPUSH", "list", "x"]).unwrap();
+        assert_eq!(
+            run(&mut state, &["MGET", "a", "b", "c", "list"]).unwrap(),
+            RespType::Array(vec![bulk("1"), bulk("2"), nil(), nil()])
+        );
+
+        // Nothing is set if any key exists.
+        assert_eq!(run(&mut state, &["MSETNX", "c", "3", "a", "9"]).unwrap(), RespType::Integer(0));
+        assert_eq!(run(&mut state, &["MGET", "a", "c"]).unwrap(), RespType::Array(vec![bulk("1"), nil()]));
+        assert_eq!(run(&mut state, &["MSETNX", "c", "3", "d", "4"]).unwrap(), RespType::Integer(1));
+        assert_eq!(run(&mut state, &["MGET", "c", "d"]).unwrap(), RespType::Array(vec![bulk("3"), bulk("4")]));
+
+        // MSET replaces values of any type.
+        assert_eq!(run(&mut state, &["MSET", "list", "y"]).unwrap(), ok());
+        assert_eq!(run(&mut state, &["GET", "list"]).unwrap(), bulk("y"));
+        assert_eq!(run(&mut state, &["MSET", "a"]).unwrap_err(), "wrong number of arguments for 'mset' command");
+        assert_eq!(run(&mut state, &["MSETNX"]).unwrap_err(), "wrong number of arguments for 'msetnx' command");
+        assert_eq!(run(&mut state, &["MGET"]).unwrap_err(), "wrong number of arguments for 'mget' command");
+    }
+}
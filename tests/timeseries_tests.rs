@@ -0,0 +1,404 @@
+mod common;
+
+/// Integration tests for time series commands
+#[cfg(test)]
+mod test_timeseries {
+    use codecrafters_redis::resp::protocol::RespType;
+    use codecrafters_redis::resp::state::default_server_state::DefaultServerState;
+
+    use crate::common::{bulk, dump, ok, restore, run};
+
+    fn sample(timestamp: i64, value: &str) -> RespType {
+        RespType::Array(vec![RespType::Integer(timestamp), bulk(value)])
+    }
+
+    fn samples(samples: &[(i64, &str)]) -> RespType {
+        RespType::Array(samples.iter().map(|(timestamp, value)| sample(*timestamp, value)).collect())
+    }
+
+    fn labels(labels: &[(&str, Option<&str>)]) -> RespType {
+        RespType::Array(
+            labels
+                .iter()
+                .map(|(name, value)| {
+                    RespType::Array(vec![bulk(name), RespType::BulkString(value.map(str::to_string))])
+                })
+                .collect(),
+        )
+    }
+
+    /// Adds `(timestamp, value)` samples to `key`, creating it when missing.
+    fn add_all(state: &mut DefaultServerState, key: &str, samples: &[(u64, f64)]) {
+        for (timestamp, value) in samples {
+            run(state, &["TS.ADD", key, &timestamp.to_string(), &value.to_string()]).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_add_and_get() {
+        let mut state = DefaultServerState::default();
+        assert_eq!(run(&mut state, &["TS.CREATE", "temp", "RETENTION", "0", "LABELS", "sensor", "1"]).unwrap(), ok());
+        assert_eq!(run(&mut state, &["TS.CREATE", "temp"]).unwrap_err(), "TSDB: key already exists");
+        assert_eq!(run(&mut state, &["TYPE", "temp"]).unwrap(), RespType::SimpleString("TSDB-TYPE".to_string()));
+        assert_eq!(run(&mut state, &["TS.GET", "temp"]).unwrap(), RespType::Array(vec![]));
+
+        assert_eq!(run(&mut state, &["TS.ADD", "temp", "1000", "20.5"]).unwrap(), RespType::Integer(1000));
+        assert_eq!(
+            run(&mut state, &["TS.ADD", "temp", "1000", "21"]).unwrap_err(),
+            "TSDB: Error at upsert, update is not supported when DUPLICATE_POLICY is set to BLOCK mode"
+        );
+        assert_eq!(
+            run(&mut state, &["TS.ADD", "temp", "1000", "21", "ON_DUPLICATE", "SUM"]).unwrap(),
+            RespType::Integer(1000)
+        );
+        run(&mut state, &["TS.ADD", "temp", "2000", "19"]).unwrap();
+        assert_eq!(run(&mut state, &["TS.GET", "temp"]).unwrap(), sample(2000, "19"));
+        assert_eq!(run(&mut state, &["TS.RANGE", "temp", "-", "+"]).unwrap(), samples(&[(1000, "41.5"), (2000, "19")]));
+
+        // Adding to a missing key creates it with the options given.
+        run(&mut state, &["TS.ADD", "peak", "5", "1", "DUPLICATE_POLICY", "max", "LABELS", "kind", "peak"]).unwrap();
+        run(&mut state, &["TS.ADD", "peak", "5", "0"]).unwrap();
+        run(&mut state, &["TS.ADD", "peak", "5", "3"]).unwrap();
+        assert_eq!(run(&mut state, &["TS.RANGE", "peak", "-", "+"]).unwrap(), samples(&[(5, "3")]));
+
+        let RespType::Integer(now) = run(&mut state, &["TS.ADD", "clock", "*", "1"]).unwrap() else {
+            panic!("TS.ADD must reply with the timestamp");
+        };
+        assert!(now > 1_600_000_000_000);
+    }
+
+    #[test]
+    fn test_add_errors() {
+        let mut state = DefaultServerState::default();
+
+        assert_eq!(run(&mut state, &["TS.ADD", "temp", "abc", "1"]).unwrap_err(), "TSDB: invalid timestamp");
+        assert_eq!(run(&mut state, &["TS.ADD", "temp", "-1", "1"]).unwrap_err(), "TSDB: invalid timestamp");
+        assert_eq!(run(&mut state, &["TS.ADD", "temp", "1", "abc"]).unwrap_err(), "TSDB: invalid value");
+        assert_eq!(run(&mut state, &["TS.ADD", "temp", "1", "nan"]).unwrap_err(), "TSDB: invalid value");
+        assert_eq!(
+            run(&mut state, &["TS.CREATE", "temp", "DUPLICATE_POLICY", "newest"]).unwrap_err(),
+            "TSDB: Unknown DUPLICATE_POLICY"
+        );
+        assert_eq!(
+            run(&mut state, &["TS.CREATE", "temp", "RETENTION", "-5"]).unwrap_err(),
+            "TSDB: Couldn't parse RETENTION"
+        );
+        assert_eq!(
+            run(&mut state, &["TS.CREATE", "temp", "CHUNK_SIZE", "10"]).unwrap_err(),
+            "TSDB: CHUNK_SIZE value must be a multiple of 8 in the range [48 .. 1048576]"
+        );
+        assert_eq!(
+            run(&mut state, &["TS.CREATE", "temp", "LABELS", "room"]).unwrap_err(),
+            "wrong number of arguments for 'ts.create' command"
+        );
+        assert_eq!(run(&mut state, &["TS.CREATE", "temp", "ON_DUPLICATE", "last"]).unwrap_err(), "syntax error");
+        // Nothing is created by a failed command.
+        assert_eq!(run(&mut state, &["TS.GET", "temp"]).unwrap_err(), "TSDB: the key does not exist");
+        assert_eq!(run(&mut state, &["TS.RANGE", "temp", "-", "+"]).unwrap_err(), "TSDB: the key does not exist");
+
+        run(&mut state, &["SET", "text", "a"]).unwrap();
+        assert!(run(&mut state, &["TS.ADD", "text", "1", "1"]).unwrap_err().starts_with("WRONGTYPE"));
+        assert!(run(&mut state, &["TS.CREATE", "text"]).unwrap_err().starts_with("TSDB: key already exists"));
+    }
+
+    #[test]
+    fn test_retention() {
+        let mut state = DefaultServerState::default();
+        run(&mut state, &["TS.CREATE", "recent", "RETENTION", "100"]).unwrap();
+        add_all(&mut state, "recent", &[(1000, 1.0), (1050, 2.0), (1120, 3.0)]);
+        assert_eq!(run(&mut state, &["TS.RANGE", "recent", "-", "+"]).unwrap(), samples(&[(1050, "2"), (1120, "3")]));
+
+        assert_eq!(
+            run(&mut state, &["TS.ADD", "recent", "1010", "4"]).unwrap_err(),
+            "TSDB: Timestamp is older than retention"
+        );
+        run(&mut state, &["TS.ADD", "recent", "1030", "4"]).unwrap();
+        assert_eq!(
+            run(&mut state, &["TS.RANGE", "recent", "-", "+"]).unwrap(),
+            samples(&[(1030, "4"), (1050, "2"), (1120, "3")])
+        );
+    }
+
+    #[test]
+    fn test_range_aggregation() {
+        let mut state = DefaultServerState::default();
+        add_all(&mut state, "metric", &[(1, 1.0), (2, 2.0), (3, 3.0), (11, 10.0), (14, 20.0), (25, 5.0)]);
+
+        for (aggregation, expected) in [
+            ("avg", [(0, "2"), (10, "15"), (20, "5")]),
+            ("sum", [(0, "6"), (10, "30"), (20, "5")]),
+            ("min", [(0, "1"), (10, "10"), (20, "5")]),
+            ("max", [(0, "3"), (10, "20"), (20, "5")]),
+            ("count", [(0, "3"), (10, "2"), (20, "1")]),
+            ("first", [(0, "1"), (10, "10"), (20, "5")]),
+            ("LAST", [(0, "3"), (10, "20"), (20, "5")]),
+        ] {
+            assert_eq!(
+                run(&mut state, &["TS.RANGE", "metric", "-", "+", "AGGREGATION", aggregation, "10"]).unwrap(),
+                samples(&expected),
+                "{}",
+                aggregation
+            );
+        }
+
+        assert_eq!(
+            run(&mut state, &["TS.RANGE", "metric", "1", "+", "ALIGN", "start", "AGGREGATION", "sum", "10"]).unwrap(),
+            samples(&[(1, "6"), (11, "30"), (21, "5")])
+        );
+        assert_eq!(
+            run(&mut state, &["TS.RANGE", "metric", "-", "+", "COUNT", "2", "AGGREGATION", "sum", "10"]).unwrap(),
+            samples(&[(0, "6"), (10, "30")])
+        );
+        assert_eq!(
+            run(&mut state, &["TS.REVRANGE", "metric", "-", "+", "AGGREGATION", "max", "10", "COUNT", "2"]).unwrap(),
+            samples(&[(20, "5"), (10, "20")])
+        );
+        assert_eq!(
+            run(&mut state, &["TS.RANGE", "metric", "2", "11"]).unwrap(),
+            samples(&[(2, "2"), (3, "3"), (11, "10")])
+        );
+        assert_eq!(run(&mut state, &["TS.RANGE", "metric", "11", "2"]).unwrap(), samples(&[]));
+        assert_eq!(
+            run(&mut state, &["TS.REVRANGE", "metric", "-", "+", "FILTER_BY_VALUE", "2", "10"]).unwrap(),
+            samples(&[(25, "5"), (11, "10"), (3, "3"), (2, "2")])
+        );
+        assert_eq!(
+            run(&mut state, &["TS.RANGE", "metric", "-", "+", "FILTER_BY_TS", "1", "14", "99", "COUNT", "5"]).unwrap(),
+            samples(&[(1, "1"), (14, "20")])
+        );
+
+        assert_eq!(
+            run(&mut state, &["TS.RANGE", "metric", "-", "+", "ALIGN", "0"]).unwrap_err(),
+            "TSDB: ALIGN parameter can only be used with AGGREGATION"
+        );
+        assert_eq!(
+            run(&mut state, &["TS.RANGE", "metric", "-", "+", "AGGREGATION", "median", "10"]).unwrap_err(),
+            "TSDB: Unknown aggregation type"
+        );
+        assert_eq!(
+            run(&mut state, &["TS.RANGE", "metric", "-", "+", "AGGREGATION", "avg", "0"]).unwrap_err(),
+            "TSDB: bucketDuration must be greater than zero"
+        );
+        assert_eq!(run(&mut state, &["TS.RANGE", "metric", "x", "+"]).unwrap_err(), "TSDB: wrong fromTimestamp");
+        assert_eq!(run(&mut state, &["TS.RANGE", "metric", "-", "+", "WITHLABELS"]).unwrap_err(), "syntax error");
+    }
+
+    #[test]
+    fn test_madd() {
+        let mut state = DefaultServerState::default();
+        run(&mut state, &["TS.CREATE", "a"]).unwrap();
+        run(&mut state, &["TS.CREATE", "b"]).unwrap();
+
+        assert_eq!(
+            run(&mut state, &["TS.MADD", "a", "1", "1", "b", "1", "2", "missing", "1", "3", "a", "1", "4"]).unwrap(),
+            RespType::Array(vec![
+                RespType::Integer(1),
+                RespType::Integer(1),
+                RespType::Error("TSDB: the key does not exist".to_string()),
+                RespType::Error(
+                    "TSDB: Error at upsert, update is not supported when DUPLICATE_POLICY is set to BLOCK mode"
+                        .to_string()
+                ),
+            ])
+        );
+        assert_eq!(run(&mut state, &["TS.RANGE", "b", "-", "+"]).unwrap(), samples(&[(1, "2")]));
+        assert_eq!(run(&mut state, &["TS.MADD", "a", "2", "x"]).unwrap_err(), "TSDB: invalid value");
+        assert_eq!(
+            run(&mut state, &["TS.MADD", "a", "2"]).unwrap_err(),
+            "wrong number of arguments for 'ts.madd' command"
+        );
+        assert_eq!(run(&mut state, &["TS.RANGE", "a", "-", "+"]).unwrap(), samples(&[(1, "1")]));
+    }
+
+    #[test]
+    fn test_mrange() {
+        let mut state = DefaultServerState::default();
+        run(&mut state, &["TS.CREATE", "cpu:1", "LABELS", "type", "cpu", "host", "a"]).unwrap();
+        run(&mut state, &["TS.CREATE", "cpu:2", "LABELS", "type", "cpu", "host", "b"]).unwrap();
+        run(&mut state, &["TS.CREATE", "mem:1", "LABELS", "type", "mem", "host", "a"]).unwrap();
+        add_all(&mut state, "cpu:1", &[(10, 1.0), (20, 2.0)]);
+        add_all(&mut state, "cpu:2", &[(10, 3.0)]);
+        add_all(&mut state, "mem:1", &[(15, 4.0)]);
+        run(&mut state, &["SET", "type=cpu", "not a series"]).unwrap();
+
+        let series = |key: &str, labels: RespType, samples: RespType| RespType::Array(vec![bulk(key), labels, samples]);
+        assert_eq!(
+            run(&mut state, &["TS.MRANGE", "-", "+", "FILTER", "type=cpu"]).unwrap(),
+            RespType::Array(vec![
+                series("cpu:1", RespType::Array(vec![]), samples(&[(10, "1"), (20, "2")])),
+                series("cpu:2", RespType::Array(vec![]), samples(&[(10, "3")])),
+            ])
+        );
+        assert_eq!(
+            run(&mut state, &["TS.MRANGE", "-", "+", "WITHLABELS", "FILTER", "host=a"]).unwrap(),
+            RespType::Array(vec![
+                series(
+                    "cpu:1",
+                    labels(&[("type", Some("cpu")), ("host", Some("a"))]),
+                    samples(&[(10, "1"), (20, "2")])
+                ),
+                series("mem:1", labels(&[("type", Some("mem")), ("host", Some("a"))]), samples(&[(15, "4")])),
+            ])
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &[
+                    "TS.MREVRANGE",
+                    "-",
+                    "+",
+                    "AGGREGATION",
+                    "sum",
+                    "100",
+                    "SELECTED_LABELS",
+                    "host",
+                    "zone",
+                    "FILTER",
+                    "type=(cpu,mem)",
+                    "host!=b"
+                ]
+            )
+            .unwrap(),
+            RespType::Array(vec![
+                series("cpu:1", labels(&[("host", Some("a")), ("zone", None)]), samples(&[(0, "3")])),
+                series("mem:1", labels(&[("host", Some("a")), ("zone", None)]), samples(&[(0, "4")])),
+            ])
+        );
+
+        // Empty values match series without the label.
+        let keys = |reply: RespType| match reply {
+            RespType::Array(series) => series
+                .into_iter()
+                .map(|series| match series {
+                    RespType::Array(parts) => parts[0].clone(),
+                    other => panic!("unexpected series {:?}", other),
+                })
+                .collect::<Vec<_>>(),
+            other => panic!("unexpected reply {:?}", other),
+        };
+        assert_eq!(
+            keys(run(&mut state, &["TS.MRANGE", "-", "+", "FILTER", "type=cpu", "zone="]).unwrap()),
+            vec![bulk("cpu:1"), bulk("cpu:2")]
+        );
+        assert_eq!(
+            keys(run(&mut state, &["TS.MRANGE", "-", "+", "FILTER", "host=a", "type!="]).unwrap()),
+            vec![bulk("cpu:1"), bulk("mem:1")]
+        );
+        assert_eq!(
+            keys(run(&mut state, &["TS.MRANGE", "-", "+", "FILTER", "host=a", "type!=(cpu,disk)"]).unwrap()),
+            vec![bulk("mem:1")]
+        );
+
+        assert_eq!(
+            run(&mut state, &["TS.MRANGE", "-", "+", "FILTER", "zone="]).unwrap_err(),
+            "TSDB: please provide at least one matcher of the form label=value"
+        );
+        assert_eq!(
+            run(&mut state, &["TS.MRANGE", "-", "+"]).unwrap_err(),
+            "TSDB: please provide at least one matcher of the form label=value"
+        );
+        assert_eq!(
+            run(&mut state, &["TS.MRANGE", "-", "+", "FILTER", "type"]).unwrap_err(),
+            "TSDB: failed parsing labels"
+        );
+    }
+
+    #[test]
+    fn test_compaction_rules() {
+        let mut state = DefaultServerState::default();
+        run(&mut state, &["TS.CREATE", "raw"]).unwrap();
+        run(&mut state, &["TS.CREATE", "avg"]).unwrap();
+        assert_eq!(run(&mut state, &["TS.CREATERULE", "raw", "avg", "AGGREGATION", "avg", "10"]).unwrap(), ok());
+
+        add_all(&mut state, "raw", &[(1, 10.0), (5, 20.0)]);
+        assert_eq!(run(&mut state, &["TS.RANGE", "avg", "-", "+"]).unwrap(), samples(&[]));
+        // A sample in a later bucket closes the current one.
+        add_all(&mut state, "raw", &[(12, 30.0)]);
+        assert_eq!(run(&mut state, &["TS.RANGE", "avg", "-", "+"]).unwrap(), samples(&[(0, "15")]));
+        // A late sample updates the bucket it belongs to.
+        add_all(&mut state, "raw", &[(3, 30.0)]);
+        assert_eq!(run(&mut state, &["TS.RANGE", "avg", "-", "+"]).unwrap(), samples(&[(0, "20")]));
+        add_all(&mut state, "raw", &[(25, 1.0)]);
+        assert_eq!(run(&mut state, &["TS.RANGE", "avg", "-", "+"]).unwrap(), samples(&[(0, "20"), (10, "30")]));
+
+        // Compactions are not chained.
+        run(&mut state, &["TS.CREATE", "max"]).unwrap();
+        assert_eq!(
+            run(&mut state, &["TS.CREATERULE", "avg", "max", "AGGREGATION", "max", "100"]).unwrap_err(),
+            "TSDB: the source key already has a source rule"
+        );
+        assert_eq!(
+            run(&mut state, &["TS.CREATERULE", "max", "raw", "AGGREGATION", "max", "100"]).unwrap_err(),
+            "TSDB: the destination key already has a dst rule"
+        );
+        run(&mut state, &["TS.CREATERULE", "raw", "max", "AGGREGATION", "max", "100", "50"]).unwrap();
+        add_all(&mut state, "raw", &[(38, 1.0), (60, 7.0), (200, 0.0), (300, 0.0)]);
+        assert_eq!(
+            run(&mut state, &["TS.RANGE", "avg", "-", "+"]).unwrap(),
+            samples(&[(0, "20"), (10, "30"), (20, "1"), (30, "1"), (60, "7"), (200, "0")])
+        );
+        assert_eq!(
+            run(&mut state, &["TS.RANGE", "max", "-", "+"]).unwrap(),
+            samples(&[(0, "30"), (50, "7"), (150, "0")])
+        );
+
+        run(&mut state, &["TS.CREATE", "other"]).unwrap();
+        assert_eq!(
+            run(&mut state, &["TS.CREATERULE", "other", "max", "AGGREGATION", "sum", "10"]).unwrap_err(),
+            "TSDB: the destination key already has a src rule"
+        );
+        assert_eq!(
+            run(&mut state, &["TS.CREATERULE", "raw", "raw", "AGGREGATION", "sum", "10"]).unwrap_err(),
+            "TSDB: the source key and destination key should be different"
+        );
+        assert_eq!(
+            run(&mut state, &["TS.CREATERULE", "raw", "missing", "AGGREGATION", "sum", "10"]).unwrap_err(),
+            "TSDB: the key does not exist"
+        );
+        assert_eq!(
+            run(&mut state, &["TS.CREATERULE", "raw", "other", "AGGREGATION", "median", "10"]).unwrap_err(),
+            "TSDB: Unknown aggregation type"
+        );
+
+        assert_eq!(run(&mut state, &["TS.DELETERULE", "raw", "max"]).unwrap(), ok());
+        assert_eq!(
+            run(&mut state, &["TS.DELETERULE", "raw", "max"]).unwrap_err(),
+            "TSDB: compaction rule does not exist"
+        );
+        assert_eq!(run(&mut state, &["TS.CREATERULE", "other", "max", "AGGREGATION", "sum", "10"]).unwrap(), ok());
+
+        // Samples are still added once the destination is deleted.
+        run(&mut state, &["DEL", "avg"]).unwrap();
+        assert_eq!(run(&mut state, &["TS.ADD", "raw", "500", "1"]).unwrap(), RespType::Integer(500));
+    }
+
+    #[test]
+    fn test_survives_dump() {
+        let mut state = DefaultServerState::default();
+        run(&mut state, &["TS.CREATE", "raw", "RETENTION", "10", "DUPLICATE_POLICY", "sum", "LABELS", "a", "b"])
+            .unwrap();
+        run(&mut state, &["TS.CREATE", "sum"]).unwrap();
+        run(&mut state, &["TS.CREATERULE", "raw", "sum", "AGGREGATION", "sum", "10", "5"]).unwrap();
+        add_all(&mut state, "raw", &[(5, 1.5), (7, 2.0), (7, 2.0)]);
+
+        let payload = dump(&mut state, "raw");
+        run(&mut state, &["DEL", "raw"]).unwrap();
+        restore(&mut state, "raw", &payload).unwrap();
+        assert_eq!(run(&mut state, &["TS.RANGE", "raw", "-", "+"]).unwrap(), samples(&[(5, "1.5"), (7, "4")]));
+
+        // The duplicate policy, labels, retention and rule with its open bucket were restored, and
+        // the bucket is aggregated before its samples fall out of the retention.
+        add_all(&mut state, "raw", &[(7, 1.0), (16, 1.0)]);
+        assert_eq!(run(&mut state, &["TS.RANGE", "sum", "-", "+"]).unwrap(), samples(&[(5, "6.5")]));
+        assert_eq!(run(&mut state, &["TS.RANGE", "raw", "-", "+"]).unwrap(), samples(&[(7, "5"), (16, "1")]));
+        assert_eq!(
+            run(&mut state, &["TS.ADD", "raw", "2", "1"]).unwrap_err(),
+            "TSDB: Timestamp is older than retention"
+        );
+        let reply = run(&mut state, &["TS.MRANGE", "-", "+", "WITHLABELS", "FILTER", "a=b"]).unwrap();
+        let RespType::Array(series) = reply else {
+            panic!("TS.MRANGE must reply with an array");
+        };
+        assert_eq!(series.len(), 1);
+    }
+}